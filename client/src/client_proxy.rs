@@ -17,9 +17,10 @@ use libra_types::{
     },
     account_state_blob::{AccountStateBlob, AccountStateWithProof},
     contract_event::{ContractEvent, EventWithProof},
+    event::EventKey,
     transaction::{
-        parse_as_transaction_argument, RawTransaction, Script, SignedTransaction,
-        TransactionArgument, TransactionPayload, Version,
+        parse_as_transaction_argument, parse_as_vector_with, RawTransaction, Script,
+        SignedTransaction, TransactionArgument, TransactionPayload, Version,
     },
     transaction_helpers::{create_signed_txn, create_unsigned_txn, TransactionSigner},
 };
@@ -716,27 +717,53 @@ impl ClientProxy {
             ),
         };
         let access_path = AccessPath::new(account, path);
-        let start_seq_number = space_delim_strings[3].parse::<u64>().map_err(|error| {
+        let (start_seq_number, ascending, limit) =
+            Self::parse_event_range(&space_delim_strings[3..])?;
+        self.client
+            .get_events_by_access_path(access_path, start_seq_number, ascending, limit)
+    }
+
+    /// Get events by event key with start sequence number and limit. This works for any event
+    /// handle, including the ones published outside of the LibraAccount resource.
+    pub fn get_events_by_event_key(
+        &mut self,
+        space_delim_strings: &[&str],
+    ) -> Result<Vec<EventWithProof>> {
+        ensure!(
+            space_delim_strings.len() == 5,
+            "Invalid number of arguments to get events by event key"
+        );
+        let event_key = hex::decode(space_delim_strings[1])
+            .map_err(|error| format_err!("Invalid event key {}: {}", space_delim_strings[1], error))
+            .and_then(|bytes| EventKey::try_from(bytes.as_slice()))?;
+        let (start_seq_number, ascending, limit) =
+            Self::parse_event_range(&space_delim_strings[2..])?;
+        self.client
+            .get_events_by_event_key(event_key, start_seq_number, ascending, limit)
+    }
+
+    /// Parse `<start_sequence_number> <ascending> <limit>` of an event query.
+    fn parse_event_range(space_delim_strings: &[&str]) -> Result<(u64, bool, u64)> {
+        let start_seq_number = space_delim_strings[0].parse::<u64>().map_err(|error| {
             format_parse_data_error(
                 "start_seq_number",
                 InputType::UnsignedInt,
-                space_delim_strings[3],
+                space_delim_strings[0],
                 error,
             )
         })?;
-        let ascending = parse_bool(space_delim_strings[4]).map_err(|error| {
-            format_parse_data_error("ascending", InputType::Bool, space_delim_strings[4], error)
+        let ascending = parse_bool(space_delim_strings[1]).map_err(|error| {
+            format_parse_data_error("ascending", InputType::Bool, space_delim_strings[1], error)
         })?;
-        let limit = space_delim_strings[5].parse::<u64>().map_err(|error| {
+        let limit = space_delim_strings[2].parse::<u64>().map_err(|error| {
             format_parse_data_error(
-                "start_seq_number",
+                "limit",
                 InputType::UnsignedInt,
-                space_delim_strings[3],
+                space_delim_strings[2],
                 error,
             )
         })?;
-        self.client
-            .get_events_by_access_path(access_path, start_seq_number, ascending, limit)
+        Ok((start_seq_number, ascending, limit))
     }
 
     /// Write mnemonic recover to the file specified.
@@ -1058,6 +1085,9 @@ fn parse_as_transaction_argument_for_client(s: &str) -> Result<TransactionArgume
         let account_address = ClientProxy::address_from_strings(s)?;
         return Ok(TransactionArgument::Address(account_address));
     }
+    if s.starts_with('[') {
+        return parse_as_vector_with(s, parse_as_transaction_argument_for_client);
+    }
     parse_as_transaction_argument(s)
 }
 
@@ -1092,9 +1122,12 @@ impl fmt::Display for AccountEntry {
 
 #[cfg(test)]
 mod tests {
-    use crate::client_proxy::{parse_bool, AddressAndIndex, ClientProxy};
+    use crate::client_proxy::{
+        parse_as_transaction_argument_for_client, parse_bool, AddressAndIndex, ClientProxy,
+    };
     use config::{config::PersistableConfig, trusted_peers::ConfigHelpers};
     use libra_tools::tempdir::TempPath;
+    use libra_types::transaction::TransactionArgument;
     use libra_wallet::io_utils;
     use proptest::prelude::*;
 
@@ -1146,6 +1179,20 @@ mod tests {
         assert!(parse_bool("ad1f").is_err());
     }
 
+    #[test]
+    fn test_parse_vector_argument() {
+        let (first, second) = ("a".repeat(64), "b".repeat(64));
+        let arg = parse_as_transaction_argument_for_client(&format!("[{},{}]", first, second));
+        assert_eq!(
+            arg.unwrap(),
+            TransactionArgument::Vector(vec![
+                TransactionArgument::Address(ClientProxy::address_from_strings(&first).unwrap()),
+                TransactionArgument::Address(ClientProxy::address_from_strings(&second).unwrap()),
+            ])
+        );
+        assert!(parse_as_transaction_argument_for_client(&format!("[{},1]", first)).is_err());
+    }
+
     #[test]
     fn test_micro_libra_conversion() {
         assert!(ClientProxy::convert_to_micro_libras("").is_err());
@@ -10,6 +10,7 @@ use admission_control_proto::{
     AdmissionControlStatus, SubmitTransactionResponse,
 };
 use crypto::ed25519::*;
+use event_decoder::ModuleFetcher;
 use failure::prelude::*;
 use futures::Future;
 use grpcio::{CallOption, ChannelBuilder, EnvBuilder};
@@ -21,14 +22,18 @@ use libra_types::{
     account_state_blob::{AccountStateBlob, AccountStateWithProof},
     contract_event::{ContractEvent, EventWithProof},
     crypto_proxies::ValidatorVerifier,
+    event::EventKey,
     get_with_proof::{
         RequestItem, ResponseItem, UpdateToLatestLedgerRequest, UpdateToLatestLedgerResponse,
     },
+    language_storage::ModuleId,
     transaction::{SignedTransaction, Version},
-    vm_error::StatusCode,
+    vm_error::{StatusCode, VMStatus},
 };
+use std::collections::BTreeMap;
 use std::convert::TryFrom;
 use std::sync::Arc;
+use vm::file_format::CompiledModule;
 
 const MAX_GRPC_RETRY_COUNT: u64 = 1;
 
@@ -85,12 +90,15 @@ impl GRPCClient {
                     sender_account.sequence_number =
                         self.get_sequence_number(sender_account.address)?;
                     bail!(
-                        "Transaction failed with vm status: {:?}, please retry your transaction.",
-                        vm_error
+                        "Transaction failed with vm status: {}, please retry your transaction.",
+                        self.describe_vm_status(&vm_error)
                     );
                 }
             }
-            bail!("Transaction failed with vm status: {:?}", vm_error);
+            bail!(
+                "Transaction failed with vm status: {}",
+                self.describe_vm_status(&vm_error)
+            );
         } else if let Some(mempool_error) = completed_resp.mempool_error {
             bail!(
                 "Transaction failed with mempool status: {:?}",
@@ -293,9 +301,75 @@ impl GRPCClient {
         }
     }
 
+    /// Get events emitted under `event_key` from validator. Unlike `get_events_by_access_path`,
+    /// this works for event handles outside of the LibraAccount resource, but cannot prove that
+    /// no more events exist beyond the returned ones.
+    pub fn get_events_by_event_key(
+        &self,
+        event_key: EventKey,
+        start_event_seq_num: u64,
+        ascending: bool,
+        limit: u64,
+    ) -> Result<Vec<EventWithProof>> {
+        let req_item = RequestItem::GetEventsByEventKey {
+            event_key,
+            start_event_seq_num,
+            ascending,
+            limit,
+        };
+
+        let mut response = self.get_with_proof_sync(vec![req_item])?;
+        response
+            .response_items
+            .remove(0)
+            .into_get_events_by_event_key_response()
+    }
+
     fn get_default_grpc_call_option() -> CallOption {
         CallOption::default()
             .wait_for_ready(true)
             .timeout(std::time::Duration::from_millis(5000))
     }
 }
+
+impl GRPCClient {
+    /// Describes `vm_status`. If the transaction aborted in a module that declared the abort code,
+    /// the name and description of the code, published along with the module, are included.
+    fn describe_vm_status(&self, vm_status: &VMStatus) -> String {
+        let description = format!("{:?}", vm_status);
+        if vm_status.major_status != StatusCode::ABORTED {
+            return description;
+        }
+        let (code, location) = match (vm_status.sub_status, &vm_status.abort_location) {
+            (Some(code), Some(location)) => (code, location),
+            _ => return description,
+        };
+        // The description is best effort: the module may have been unpublished or be unreachable.
+        match self.get_module(&location.module) {
+            Ok(module) => match module.abort_code(code) {
+                Some(abort_code) => format!(
+                    "{} ({}: {})",
+                    description, abort_code.name, abort_code.description
+                ),
+                None => description,
+            },
+            Err(_) => description,
+        }
+    }
+}
+
+impl ModuleFetcher for GRPCClient {
+    /// Get the latest version of a module published on chain.
+    fn get_module(&self, module_id: &ModuleId) -> Result<CompiledModule> {
+        let blob = self
+            .get_account_blob(*module_id.address())?
+            .0
+            .ok_or_else(|| format_err!("No account found at {}", module_id.address()))?;
+        let account_state = BTreeMap::<Vec<u8>, Vec<u8>>::try_from(&blob)?;
+        let code = account_state
+            .get(&AccessPath::code_access_path(module_id).path)
+            .ok_or_else(|| format_err!("Module {:?} is not published", module_id))?;
+        CompiledModule::deserialize(code)
+            .map_err(|e| format_err!("Unable to deserialize module {:?}: {:?}", module_id, e))
+    }
+}
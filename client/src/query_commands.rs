@@ -2,7 +2,11 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use crate::{client_proxy::ClientProxy, commands::*};
-use libra_types::account_config::get_account_resource_or_default;
+use event_decoder::{EventDecoder, ModuleFetcher};
+use libra_types::{
+    account_config::get_account_resource_or_default,
+    contract_event::{ContractEvent, EventWithProof},
+};
 use transaction_builder::get_transaction_name;
 
 /// Major command for query operations.
@@ -138,8 +142,10 @@ impl Command for QueryCommandGetTxnByAccountSeq {
                         );
                         if let Some(events_inner) = &events {
                             println!("Events: ");
+                            let decoder = EventDecoder::new(&client.client);
                             for event in events_inner {
                                 println!("{}", event);
+                                print_decoded_event(&decoder, event);
                             }
                         }
                     }
@@ -175,6 +181,7 @@ impl Command for QueryCommandGetTxnByRange {
                 // Note that this should never panic because we shouldn't return items
                 // if the version wasn't able to be parsed in the first place
                 let mut cur_version = params[1].parse::<u64>().expect("Unable to parse version");
+                let decoder = EventDecoder::new(&client.client);
                 for (txn, opt_events) in comm_txns_and_events {
                     println!(
                         "Transaction at version {}: {}",
@@ -187,6 +194,7 @@ impl Command for QueryCommandGetTxnByRange {
                         } else {
                             for event in events {
                                 println!("{}", event);
+                                print_decoded_event(&decoder, &event);
                             }
                         }
                     }
@@ -206,25 +214,48 @@ impl Command for QueryCommandGetEvent {
         vec!["event", "ev"]
     }
     fn get_params_help(&self) -> &'static str {
-        "<account_ref_id>|<account_address> <sent|received> <start_sequence_number> <ascending=true|false> <limit>"
+        "(<account_ref_id>|<account_address> <sent|received>)|<event_key> <start_sequence_number> <ascending=true|false> <limit>"
     }
     fn get_description(&self) -> &'static str {
-        "Get events by account and event type (sent|received)."
+        "Get events by account and event type (sent|received), or by event key for any other event handle."
     }
     fn execute(&self, client: &mut ClientProxy, params: &[&str]) {
+        if params.len() == 5 {
+            println!(">> Getting events by event key.");
+            match client.get_events_by_event_key(&params) {
+                Ok(events) => print_events(client, events),
+                Err(e) => report_error("Error getting events by event key", e),
+            }
+            return;
+        }
         println!(">> Getting events by account and event type.");
         match client.get_events_by_account_and_type(&params) {
             Ok((events, last_event_state)) => {
-                if events.is_empty() {
-                    println!("No events returned");
-                } else {
-                    for event in events {
-                        println!("{}", event);
-                    }
-                }
+                print_events(client, events);
                 println!("Last event state: {:#?}", last_event_state);
             }
             Err(e) => report_error("Error getting events by access path", e),
         }
     }
 }
+
+/// Print `events` along with their decoded data.
+fn print_events(client: &ClientProxy, events: Vec<EventWithProof>) {
+    if events.is_empty() {
+        println!("No events returned");
+    } else {
+        let decoder = EventDecoder::new(&client.client);
+        for event in events {
+            println!("{}", event);
+            print_decoded_event(&decoder, &event.event);
+        }
+    }
+}
+
+/// Print the data of `event` decoded according to the layout of its type.
+fn print_decoded_event(decoder: &EventDecoder<impl ModuleFetcher>, event: &ContractEvent) {
+    match decoder.decode_event(event) {
+        Ok(data) => println!("Decoded event data: {}", data),
+        Err(e) => println!("Unable to decode event data: {}", e),
+    }
+}
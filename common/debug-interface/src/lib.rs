@@ -1,7 +1,11 @@
 // Copyright (c) The Libra Core Contributors
 // SPDX-License-Identifier: Apache-2.0
 
-use crate::proto::{GetNodeDetailsRequest, NodeDebugInterfaceClient};
+use crate::proto::{
+    ClearPeerBanRequest, EquivocationEvidence, GetEquivocationEvidenceRequest,
+    GetNodeDetailsRequest, GetPeerBansRequest, GetPeerMetricsRequest, NodeDebugInterfaceClient,
+    PeerBan, PeerMetrics,
+};
 use failure::prelude::*;
 use grpcio::{ChannelBuilder, EnvBuilder};
 use std::{collections::HashMap, sync::Arc};
@@ -57,4 +61,51 @@ impl NodeDebugClient {
             })
             .collect()
     }
+
+    pub fn get_peer_bans(&self) -> Result<Vec<PeerBan>> {
+        let response = self
+            .client
+            .get_peer_bans(&GetPeerBansRequest::default())
+            .context("Unable to query peer bans")?;
+        Ok(response.bans)
+    }
+
+    /// Lifts the ban of a peer on the network the node has the given peer id on. Returns whether
+    /// the peer was banned.
+    pub fn clear_peer_ban<S: Into<String>>(&self, network: S, peer_id: S) -> Result<bool> {
+        let mut request = ClearPeerBanRequest::default();
+        request.network = network.into();
+        request.peer_id = peer_id.into();
+        let response = self
+            .client
+            .clear_peer_ban(&request)
+            .context("Unable to clear peer ban")?;
+        Ok(response.cleared)
+    }
+
+    /// Returns the metrics of the connected peers, only of the given network and peer if they are
+    /// not empty.
+    pub fn get_peer_metrics<S: Into<String>>(
+        &self,
+        network: S,
+        peer_id: S,
+    ) -> Result<Vec<PeerMetrics>> {
+        let mut request = GetPeerMetricsRequest::default();
+        request.network = network.into();
+        request.peer_id = peer_id.into();
+        let response = self
+            .client
+            .get_peer_metrics(&request)
+            .context("Unable to query peer metrics")?;
+        Ok(response.peers)
+    }
+
+    /// Returns the equivocation evidence stored by the consensus of the node.
+    pub fn get_equivocation_evidence(&self) -> Result<Vec<EquivocationEvidence>> {
+        let response = self
+            .client
+            .get_equivocation_evidence(&GetEquivocationEvidenceRequest::default())
+            .context("Unable to query equivocation evidence")?;
+        Ok(response.evidence)
+    }
 }
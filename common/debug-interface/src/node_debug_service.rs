@@ -6,21 +6,151 @@
 use crate::{
     json_log,
     proto::{
-        Event, GetEventsRequest, GetEventsResponse, GetNodeDetailsRequest, GetNodeDetailsResponse,
-        NodeDebugInterface,
+        ClearPeerBanRequest, ClearPeerBanResponse, EquivocationEvidence, Event,
+        GetEquivocationEvidenceRequest, GetEquivocationEvidenceResponse, GetEventsRequest,
+        GetEventsResponse, GetNodeDetailsRequest, GetNodeDetailsResponse, GetPeerBansRequest,
+        GetPeerBansResponse, GetPeerMetricsRequest, GetPeerMetricsResponse, LatencyHistogram,
+        NodeDebugInterface, PeerBan, PeerMetrics, ProtocolMetrics,
     },
 };
+use failure::prelude::*;
 use futures::Future;
+use grpc_helpers::provide_grpc_response;
 use libra_logger::prelude::*;
+use libra_types::PeerId;
 use metrics::counters::COUNTER_ADMISSION_CONTROL_CANNOT_SEND_REPLY;
+use network::{PeerReputation, PeerStats, LATENCY_BUCKETS_MS};
+use std::{
+    collections::HashMap,
+    str::FromStr,
+    sync::{Arc, RwLock},
+    time::UNIX_EPOCH,
+};
+
+/// Lists the equivocation evidence persisted by consensus.
+pub trait EquivocationEvidenceSource: Send + Sync {
+    fn equivocation_evidence(&self) -> Result<Vec<EquivocationEvidence>>;
+}
+
+/// The source of the equivocation evidence, set once consensus is created: the debug interface
+/// is started before consensus.
+pub type EquivocationEvidenceSlot = Arc<RwLock<Option<Arc<dyn EquivocationEvidenceSource>>>>;
 
 #[derive(Clone, Default)]
-pub struct NodeDebugService {}
+pub struct NodeDebugService {
+    /// Reputation of the peers of each network of the node, keyed by the peer id of the node on
+    /// the network.
+    peer_reputations: HashMap<PeerId, Arc<RwLock<PeerReputation>>>,
+    /// Metrics of the connected peers of each network of the node, keyed by the peer id of the
+    /// node on the network.
+    peer_metrics: HashMap<PeerId, network::PeerMetrics>,
+    /// Equivocation evidence stored by consensus, unset on a node which does not run consensus.
+    equivocation_evidence: EquivocationEvidenceSlot,
+}
 
 impl NodeDebugService {
     pub fn new() -> Self {
         Default::default()
     }
+
+    /// Creates a service which also exposes the bans of the peers of the given networks.
+    pub fn with_peer_reputations(
+        peer_reputations: HashMap<PeerId, Arc<RwLock<PeerReputation>>>,
+    ) -> Self {
+        Self {
+            peer_reputations,
+            ..Self::default()
+        }
+    }
+
+    /// Also exposes the metrics of the connected peers of the given networks.
+    pub fn with_peer_metrics(
+        mut self,
+        peer_metrics: HashMap<PeerId, network::PeerMetrics>,
+    ) -> Self {
+        self.peer_metrics = peer_metrics;
+        self
+    }
+
+    /// Also exposes the equivocation evidence stored by consensus.
+    pub fn with_equivocation_evidence(
+        mut self,
+        equivocation_evidence: EquivocationEvidenceSlot,
+    ) -> Self {
+        self.equivocation_evidence = equivocation_evidence;
+        self
+    }
+
+    fn get_peer_bans_inner(&self) -> GetPeerBansResponse {
+        let mut response = GetPeerBansResponse::default();
+        for (network, reputation) in &self.peer_reputations {
+            for (peer_id, expiry) in reputation.read().unwrap().bans() {
+                let mut ban = PeerBan::default();
+                ban.network = format!("{:x}", network);
+                ban.peer_id = format!("{:x}", peer_id);
+                ban.expiry_secs = expiry.map_or(0, |expiry| {
+                    expiry
+                        .duration_since(UNIX_EPOCH)
+                        .expect("Ban expiry before the UNIX epoch")
+                        .as_secs()
+                });
+                response.bans.push(ban);
+            }
+        }
+        response
+    }
+
+    fn clear_peer_ban_inner(&self, req: ClearPeerBanRequest) -> Result<ClearPeerBanResponse> {
+        let network = parse_peer_id(&req.network)?;
+        let peer_id = parse_peer_id(&req.peer_id)?;
+        let reputation = self
+            .peer_reputations
+            .get(&network)
+            .ok_or_else(|| format_err!("Unknown network {}", req.network))?;
+        let mut response = ClearPeerBanResponse::default();
+        response.cleared = reputation.write().unwrap().clear_ban(&peer_id);
+        Ok(response)
+    }
+
+    fn get_peer_metrics_inner(&self, req: GetPeerMetricsRequest) -> Result<GetPeerMetricsResponse> {
+        let network_filter = if req.network.is_empty() {
+            None
+        } else {
+            Some(parse_peer_id(&req.network)?)
+        };
+        let peer_filter = if req.peer_id.is_empty() {
+            None
+        } else {
+            Some(parse_peer_id(&req.peer_id)?)
+        };
+        let mut response = GetPeerMetricsResponse::default();
+        for (network, peer_metrics) in &self.peer_metrics {
+            if network_filter.map_or(false, |filter| filter != *network) {
+                continue;
+            }
+            for (peer_id, stats) in peer_metrics.peers() {
+                if peer_filter.map_or(false, |filter| filter != peer_id) {
+                    continue;
+                }
+                response
+                    .peers
+                    .push(to_peer_metrics_proto(network, &peer_id, stats));
+            }
+        }
+        Ok(response)
+    }
+
+    fn get_equivocation_evidence_inner(&self) -> Result<GetEquivocationEvidenceResponse> {
+        let source = self
+            .equivocation_evidence
+            .read()
+            .unwrap()
+            .clone()
+            .ok_or_else(|| format_err!("Consensus is not running on this node"))?;
+        let mut response = GetEquivocationEvidenceResponse::default();
+        response.evidence = source.equivocation_evidence()?;
+        Ok(response)
+    }
 }
 
 impl NodeDebugInterface for NodeDebugService {
@@ -54,6 +184,87 @@ impl NodeDebugInterface for NodeDebugService {
         }
         ctx.spawn(sink.success(response).map_err(default_reply_error_logger))
     }
+
+    fn get_peer_bans(
+        &mut self,
+        ctx: ::grpcio::RpcContext<'_>,
+        _req: GetPeerBansRequest,
+        sink: ::grpcio::UnarySink<GetPeerBansResponse>,
+    ) {
+        info!("[GRPC] get_peer_bans");
+        let response = self.get_peer_bans_inner();
+        ctx.spawn(sink.success(response).map_err(default_reply_error_logger))
+    }
+
+    fn clear_peer_ban(
+        &mut self,
+        ctx: ::grpcio::RpcContext<'_>,
+        req: ClearPeerBanRequest,
+        sink: ::grpcio::UnarySink<ClearPeerBanResponse>,
+    ) {
+        info!("[GRPC] clear_peer_ban");
+        let response = self.clear_peer_ban_inner(req);
+        provide_grpc_response(response, ctx, sink);
+    }
+
+    fn get_peer_metrics(
+        &mut self,
+        ctx: ::grpcio::RpcContext<'_>,
+        req: GetPeerMetricsRequest,
+        sink: ::grpcio::UnarySink<GetPeerMetricsResponse>,
+    ) {
+        info!("[GRPC] get_peer_metrics");
+        let response = self.get_peer_metrics_inner(req);
+        provide_grpc_response(response, ctx, sink);
+    }
+
+    fn get_equivocation_evidence(
+        &mut self,
+        ctx: ::grpcio::RpcContext<'_>,
+        _req: GetEquivocationEvidenceRequest,
+        sink: ::grpcio::UnarySink<GetEquivocationEvidenceResponse>,
+    ) {
+        info!("[GRPC] get_equivocation_evidence");
+        let response = self.get_equivocation_evidence_inner();
+        provide_grpc_response(response, ctx, sink);
+    }
+}
+
+fn to_peer_metrics_proto(network: &PeerId, peer_id: &PeerId, stats: PeerStats) -> PeerMetrics {
+    let mut peer_metrics = PeerMetrics::default();
+    peer_metrics.network = format!("{:x}", network);
+    peer_metrics.peer_id = format!("{:x}", peer_id);
+    peer_metrics.open_substreams = stats.open_substreams;
+    peer_metrics.ping_rtt = Some(to_latency_histogram_proto(&stats.ping_rtt));
+    for (protocol, protocol_stats) in stats.protocols {
+        let mut protocol_metrics = ProtocolMetrics::default();
+        protocol_metrics.protocol = String::from_utf8_lossy(&protocol).into_owned();
+        protocol_metrics.bytes_sent = protocol_stats.bytes_sent;
+        protocol_metrics.messages_sent = protocol_stats.messages_sent;
+        protocol_metrics.bytes_received = protocol_stats.bytes_received;
+        protocol_metrics.messages_received = protocol_stats.messages_received;
+        protocol_metrics.rpc_errors = protocol_stats.rpc_errors;
+        protocol_metrics.rpc_latency =
+            Some(to_latency_histogram_proto(&protocol_stats.rpc_latency));
+        peer_metrics.protocols.push(protocol_metrics);
+    }
+    peer_metrics
+}
+
+fn to_latency_histogram_proto(histogram: &network::LatencyHistogram) -> LatencyHistogram {
+    let mut proto = LatencyHistogram::default();
+    proto.bucket_bounds_ms = LATENCY_BUCKETS_MS.to_vec();
+    proto.bucket_counts = histogram.bucket_counts.clone();
+    proto.count = histogram.count;
+    proto.sum_micros = histogram.sum.as_micros() as u64;
+    proto
+}
+
+// Parses a hex encoded peer id, with or without the "0x" prefix.
+fn parse_peer_id(peer_id: &str) -> Result<PeerId> {
+    let peer_id = peer_id.trim_start_matches("0x");
+    ensure!(!peer_id.is_empty(), "Empty peer id");
+    PeerId::from_str(peer_id)
 }
 
 fn default_reply_error_logger<T: ::std::fmt::Debug>(e: T) {
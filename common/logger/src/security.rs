@@ -39,6 +39,12 @@ pub enum SecurityEvent {
     /// Consensus received an invalid sync info message
     InvalidSyncInfoMsg,
 
+    /// Consensus detected a validator signing two conflicting messages in the same round
+    ConsensusEquivocation,
+
+    /// Consensus received an invalid equivocation evidence
+    InvalidEquivocationEvidence,
+
     /// A block being committed or executed is invalid
     InvalidBlock,
 
@@ -48,6 +54,9 @@ pub enum SecurityEvent {
     /// Network discovery received an invalid DiscoveryMsg
     InvalidDiscoveryMsg,
 
+    /// Network banned a peer for misbehaving
+    PeerBanned,
+
     /// Error for testing
     #[cfg(test)]
     TestError,
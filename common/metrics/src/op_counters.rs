@@ -39,7 +39,9 @@ pub struct OpMetrics {
     counters: IntCounterVec,
     gauges: IntGaugeVec,
     peer_gauges: IntGaugeVec,
+    peer_counters: IntCounterVec,
     duration_histograms: HistogramVec,
+    peer_duration_histograms: HistogramVec,
 }
 
 impl OpMetrics {
@@ -68,6 +70,14 @@ impl OpMetrics {
                 &["op", "remote_peer_id"],
             )
             .unwrap(),
+            peer_counters: IntCounterVec::new(
+                Opts::new(
+                    format!("{}_peer", name_str.clone()),
+                    format!("Counters of each remote peer for {}", name_str),
+                ),
+                &["op", "remote_peer_id"],
+            )
+            .unwrap(),
             duration_histograms: HistogramVec::new(
                 HistogramOpts::new(
                     format!("{}_duration", name_str.clone()),
@@ -76,6 +86,14 @@ impl OpMetrics {
                 &["op"],
             )
             .unwrap(),
+            peer_duration_histograms: HistogramVec::new(
+                HistogramOpts::new(
+                    format!("{}_peer_duration", name_str.clone()),
+                    format!("Histogram values of each remote peer for {}", name_str),
+                ),
+                &["op", "remote_peer_id"],
+            )
+            .unwrap(),
         }
     }
 
@@ -101,11 +119,47 @@ impl OpMetrics {
         self.counters.with_label_values(&[name])
     }
 
+    #[inline]
+    pub fn peer_counter(&self, name: &str, remote_peer_id: &str) -> IntCounter {
+        self.peer_counters
+            .with_label_values(&[name, remote_peer_id])
+    }
+
     #[inline]
     pub fn histogram(&self, name: &str) -> Histogram {
         self.duration_histograms.with_label_values(&[name])
     }
 
+    pub fn peer_duration_histogram(&self, name: &str, remote_peer_id: &str) -> DurationHistogram {
+        DurationHistogram::new(
+            self.peer_duration_histograms
+                .with_label_values(&[name, remote_peer_id]),
+        )
+    }
+
+    /// Removes the series of a remote peer from the peer gauges, e.g. once the peer is
+    /// disconnected, so that the churn of the peers does not grow the exported metrics forever.
+    pub fn remove_peer_gauge(&self, name: &str, remote_peer_id: &str) {
+        // The series does not exist if it was never updated.
+        let _ = self
+            .peer_gauges
+            .remove_label_values(&[name, remote_peer_id]);
+    }
+
+    /// Removes the series of a remote peer from the peer counters.
+    pub fn remove_peer_counter(&self, name: &str, remote_peer_id: &str) {
+        let _ = self
+            .peer_counters
+            .remove_label_values(&[name, remote_peer_id]);
+    }
+
+    /// Removes the series of a remote peer from the peer duration histograms.
+    pub fn remove_peer_duration_histogram(&self, name: &str, remote_peer_id: &str) {
+        let _ = self
+            .peer_duration_histograms
+            .remove_label_values(&[name, remote_peer_id]);
+    }
+
     pub fn duration_histogram(&self, name: &str) -> DurationHistogram {
         DurationHistogram::new(self.duration_histograms.with_label_values(&[name]))
     }
@@ -159,20 +213,24 @@ impl OpMetrics {
 
 impl Collector for OpMetrics {
     fn desc(&self) -> Vec<&Desc> {
-        let mut ms = Vec::with_capacity(4);
+        let mut ms = Vec::with_capacity(6);
         ms.extend(self.counters.desc());
         ms.extend(self.gauges.desc());
         ms.extend(self.peer_gauges.desc());
+        ms.extend(self.peer_counters.desc());
         ms.extend(self.duration_histograms.desc());
+        ms.extend(self.peer_duration_histograms.desc());
         ms
     }
 
     fn collect(&self) -> Vec<MetricFamily> {
-        let mut ms = Vec::with_capacity(4);
+        let mut ms = Vec::with_capacity(6);
         ms.extend(self.counters.collect());
         ms.extend(self.gauges.collect());
         ms.extend(self.peer_gauges.collect());
+        ms.extend(self.peer_counters.collect());
         ms.extend(self.duration_histograms.collect());
+        ms.extend(self.peer_duration_histograms.collect());
         ms
     }
 }
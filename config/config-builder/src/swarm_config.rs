@@ -83,15 +83,21 @@ impl SwarmConfig {
             network_keypairs_file: upstream_network_keys_file_name.into(),
             network_peers_file: template_network.network_peers_file.clone(),
             seed_peers_file: template_network.seed_peers_file.clone(),
-            listen_address: upstream_full_node_address.clone(),
-            advertised_address: upstream_full_node_address.clone(),
+            listen_addresses: vec![upstream_full_node_address.clone()],
+            advertised_addresses: vec![upstream_full_node_address.clone()],
             discovery_interval_ms: template_network.discovery_interval_ms,
             connectivity_check_interval_ms: template_network.connectivity_check_interval_ms,
             enable_encryption_and_authentication: template_network
                 .enable_encryption_and_authentication,
             is_permissioned,
+            target_connections: template_network.target_connections,
+            max_inbound_connections_per_ip: template_network.max_inbound_connections_per_ip,
+            max_inbound_connections_per_subnet: template_network.max_inbound_connections_per_subnet,
+            dns_seed_addr: template_network.dns_seed_addr.clone(),
+            ban_list_file: template_network.ban_list_file.clone(),
             // Dummy values - will be loaded from corresponding files.
             network_keypairs: NetworkKeyPairs::default(),
+            config_path: None,
             network_peers: template_network.network_peers.clone(),
             seed_peers: template_network.seed_peers.clone(),
         };
@@ -325,15 +331,21 @@ impl SwarmConfig {
             network_keypairs_file: network_keys_file_name.into(),
             network_peers_file: network_peers_file_name.into(),
             seed_peers_file: seed_peers_file_name.into(),
-            listen_address: addrs[0].clone(),
-            advertised_address: addrs[0].clone(),
+            listen_addresses: vec![addrs[0].clone()],
+            advertised_addresses: vec![addrs[0].clone()],
             discovery_interval_ms: template_network.discovery_interval_ms,
             connectivity_check_interval_ms: template_network.connectivity_check_interval_ms,
             enable_encryption_and_authentication: template_network
                 .enable_encryption_and_authentication,
             is_permissioned: template_network.is_permissioned,
+            target_connections: template_network.target_connections,
+            max_inbound_connections_per_ip: template_network.max_inbound_connections_per_ip,
+            max_inbound_connections_per_subnet: template_network.max_inbound_connections_per_subnet,
+            dns_seed_addr: template_network.dns_seed_addr.clone(),
+            ban_list_file: template_network.ban_list_file.clone(),
             // Dummy values - will be loaded from corresponding files.
             network_keypairs: NetworkKeyPairs::default(),
+            config_path: None,
             network_peers: template_network.network_peers.clone(),
             seed_peers: template_network.seed_peers.clone(),
         };
@@ -9,7 +9,10 @@ use crate::{
         ConfigHelpers, ConsensusPeersConfig, ConsensusPrivateKey, NetworkPeersConfig,
         NetworkPrivateKeys, UpstreamPeersConfig,
     },
-    utils::{deserialize_whitelist, get_available_port, get_local_ip, serialize_whitelist},
+    utils::{
+        deserialize_addresses, deserialize_whitelist, get_available_port, get_local_ip,
+        serialize_whitelist,
+    },
 };
 use crypto::ValidKey;
 use failure::prelude::*;
@@ -296,11 +299,18 @@ impl Default for StorageConfig {
 #[serde(default)]
 pub struct NetworkConfig {
     pub peer_id: String,
-    // TODO: Add support for multiple listen/advertised addresses in config.
-    // The address that this node is listening on for new connections.
-    pub listen_address: Multiaddr,
-    // The address that this node advertises to other nodes for the discovery protocol.
-    pub advertised_address: Multiaddr,
+    // The addresses that this node is listening on for new connections, e.g. both an IPv4 and an
+    // IPv6 address. The single `listen_address` of the older configs is accepted as well.
+    #[serde(alias = "listen_address", deserialize_with = "deserialize_addresses")]
+    pub listen_addresses: Vec<Multiaddr>,
+    // The addresses that this node advertises to other nodes for the discovery protocol, by
+    // decreasing priority. For instance, a node behind a NAT advertises both its public and its
+    // private addresses. The single `advertised_address` of the older configs is accepted as well.
+    #[serde(
+        alias = "advertised_address",
+        deserialize_with = "deserialize_addresses"
+    )]
+    pub advertised_addresses: Vec<Multiaddr>,
     pub discovery_interval_ms: u64,
     pub connectivity_check_interval_ms: u64,
     // Flag to toggle if Noise is used for encryption and authentication.
@@ -308,7 +318,25 @@ pub struct NetworkConfig {
     // If the network is permissioned, only trusted peers are allowed to connect. Otherwise, any
     // node can connect. If this flag is set to true, the `enable_encryption_and_authentication`
     // must also be set to true.
+    // In a permissionless network (e.g. the public full node network), the peers authenticate
+    // with self-certified keys: the peer_id must be derived from the network identity key. A
+    // validator joins such a network with a dedicated identity, i.e. a separate network config
+    // with the "full_node" role and its own keypairs.
     pub is_permissioned: bool,
+    // In a permissionless network, the peers found by the discovery protocol are dialed until
+    // this number of peers is connected.
+    pub target_connections: usize,
+    // In a permissionless network, the max number of inbound connections accepted from the same
+    // IP address, and from the same subnet (/24 for IPv4, /48 for IPv6).
+    pub max_inbound_connections_per_ip: usize,
+    pub max_inbound_connections_per_subnet: usize,
+    // The seed record listing the public full nodes, which this node publishes through the
+    // discovery protocol, as comma-separated `<peer id>@<host>:<port>` entries whose hosts are
+    // resolved through DNS by the full nodes. Left empty if the node does not publish any.
+    pub dns_seed_addr: String,
+    // The file the permanent bans of misbehaving peers are persisted in, one peer id per line. If
+    // not set, the bans are kept in memory and lifted upon restart.
+    pub ban_list_file: Option<PathBuf>,
     // The role of the node in the network. One of: {"validator", "full_node"}.
     pub role: String,
     // network_keypairs contains the node's network keypairs.
@@ -316,6 +344,10 @@ pub struct NetworkConfig {
     #[serde(skip)]
     pub network_keypairs: NetworkKeyPairs,
     pub network_keypairs_file: PathBuf,
+    // The path of the node config this network config was loaded from, relatively to which the
+    // keypairs and peers files are reloaded when the keys are rotated while the node runs.
+    #[serde(skip)]
+    pub config_path: Option<PathBuf>,
     // network peers are the nodes allowed to connect when the network is started in permissioned
     // mode.
     #[serde(skip)]
@@ -332,14 +364,20 @@ impl Default for NetworkConfig {
         NetworkConfig {
             peer_id: "".to_string(),
             role: "validator".to_string(),
-            listen_address: "/ip4/0.0.0.0/tcp/6180".parse::<Multiaddr>().unwrap(),
-            advertised_address: "/ip4/127.0.0.1/tcp/6180".parse::<Multiaddr>().unwrap(),
+            listen_addresses: vec!["/ip4/0.0.0.0/tcp/6180".parse::<Multiaddr>().unwrap()],
+            advertised_addresses: vec!["/ip4/127.0.0.1/tcp/6180".parse::<Multiaddr>().unwrap()],
             discovery_interval_ms: 1000,
             connectivity_check_interval_ms: 5000,
             enable_encryption_and_authentication: true,
             is_permissioned: true,
+            target_connections: 8,
+            max_inbound_connections_per_ip: 4,
+            max_inbound_connections_per_subnet: 16,
+            dns_seed_addr: "".to_string(),
+            ban_list_file: None,
             network_keypairs_file: PathBuf::from("network_keypairs.config.toml"),
             network_keypairs: NetworkKeyPairs::default(),
+            config_path: None,
             network_peers_file: PathBuf::from("network_peers.config.toml"),
             network_peers: NetworkPeersConfig::default(),
             seed_peers_file: PathBuf::from("seed_peers.config.toml"),
@@ -350,6 +388,7 @@ impl Default for NetworkConfig {
 
 impl NetworkConfig {
     pub fn load<P: AsRef<Path>>(&mut self, path: P) -> Result<()> {
+        self.config_path = Some(path.as_ref().to_path_buf());
         if !self.network_peers_file.as_os_str().is_empty() {
             self.network_peers = NetworkPeersConfig::load_config(
                 path.as_ref().with_file_name(&self.network_peers_file),
@@ -364,13 +403,13 @@ impl NetworkConfig {
             self.seed_peers =
                 SeedPeersConfig::load_config(path.as_ref().with_file_name(&self.seed_peers_file));
         }
-        if self.advertised_address.to_string().is_empty() {
-            self.advertised_address =
-                get_local_ip().ok_or_else(|| ::failure::err_msg("No local IP"))?;
+        if self.advertised_addresses.is_empty() {
+            self.advertised_addresses =
+                vec![get_local_ip().ok_or_else(|| ::failure::err_msg("No local IP"))?];
         }
-        if self.listen_address.to_string().is_empty() {
-            self.listen_address =
-                get_local_ip().ok_or_else(|| ::failure::err_msg("No local IP"))?;
+        if self.listen_addresses.is_empty() {
+            self.listen_addresses =
+                vec![get_local_ip().ok_or_else(|| ::failure::err_msg("No local IP"))?];
         }
         // If PeerId is not set, it is derived from NetworkIdentityKey.
         if self.peer_id == "" {
@@ -395,6 +434,8 @@ pub struct ConsensusConfig {
     pub contiguous_rounds: u32,
     pub max_pruned_blocks_in_mem: Option<u64>,
     pub pacemaker_initial_timeout_ms: Option<u64>,
+    // Strategy of the pacemaker round durations: "exponential" or "adaptive".
+    pub pacemaker_time_interval: String,
     // consensus_keypair contains the node's consensus keypair.
     // it is filled later on from consensus_keypair_file.
     #[serde(skip)]
@@ -403,6 +444,8 @@ pub struct ConsensusConfig {
     #[serde(skip)]
     pub consensus_peers: ConsensusPeersConfig,
     pub consensus_peers_file: PathBuf,
+    pub safety_rules: SafetyRulesConfig,
+    pub batching: BatchingConfig,
 }
 
 impl Default for ConsensusConfig {
@@ -413,10 +456,88 @@ impl Default for ConsensusConfig {
             contiguous_rounds: 2,
             max_pruned_blocks_in_mem: None,
             pacemaker_initial_timeout_ms: None,
+            pacemaker_time_interval: "exponential".to_string(),
             consensus_keypair: ConsensusKeyPair::default(),
             consensus_keypair_file: PathBuf::from("consensus_keypair.config.toml"),
             consensus_peers: ConsensusPeersConfig::default(),
             consensus_peers_file: PathBuf::from("consensus_peers.config.toml"),
+            safety_rules: SafetyRulesConfig::default(),
+            batching: BatchingConfig::default(),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SafetyRulesBackend {
+    // SafetyRules runs within the consensus process.
+    InProcess,
+    // SafetyRules runs as a separate process (see `safety-rules` binary) listening on
+    // `address:safety_rules_port`, which must be a loopback address.
+    Remote,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(default)]
+pub struct SafetyRulesConfig {
+    pub backend: SafetyRulesBackend,
+    pub address: String,
+    pub safety_rules_port: u16,
+    // The file SafetyRules keeps its signed state in. If not set, the state is kept in memory and
+    // recovered from ConsensusDB upon restart (in-process backend only).
+    pub storage_file: Option<PathBuf>,
+    // Timeout of the requests of consensus to a remote SafetyRules process.
+    pub request_timeout_ms: u64,
+    // The file holding the secret consensus authenticates its requests to a remote SafetyRules
+    // process with (remote backend only). Both processes read it: it should not be readable by
+    // anyone else.
+    pub secret_file: Option<PathBuf>,
+}
+
+impl Default for SafetyRulesConfig {
+    fn default() -> SafetyRulesConfig {
+        SafetyRulesConfig {
+            backend: SafetyRulesBackend::InProcess,
+            address: "localhost".to_string(),
+            safety_rules_port: 6186,
+            storage_file: None,
+            request_timeout_ms: 1_000,
+            secret_file: None,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(default)]
+pub struct BatchingConfig {
+    // If enabled, the validators disseminate the transactions in batches ahead of the proposals,
+    // which only carry the certified batch digests.
+    pub enabled: bool,
+    // Max number of transactions in a batch.
+    pub max_batch_size: u64,
+    // Interval of pulling a new batch from mempool.
+    pub batch_interval_ms: u64,
+    // Timeout of fetching a missing batch from one of the validators storing it.
+    pub fetch_timeout_ms: u64,
+    // Max number of uncommitted batches stored per author: the batches beyond the quota are
+    // neither stored nor signed.
+    pub max_batches_per_author: usize,
+    // Max total size in bytes of the uncommitted batches stored locally.
+    pub max_store_bytes: usize,
+    // Number of commits after which an uncommitted batch (and its proof of store) is dropped.
+    pub batch_expiration_commits: u64,
+}
+
+impl Default for BatchingConfig {
+    fn default() -> BatchingConfig {
+        BatchingConfig {
+            enabled: false,
+            max_batch_size: 100,
+            batch_interval_ms: 50,
+            fetch_timeout_ms: 1000,
+            max_batches_per_author: 200,
+            max_store_bytes: 256 * 1024 * 1024,
+            batch_expiration_commits: 1000,
         }
     }
 }
@@ -431,6 +552,14 @@ pub enum ConsensusProposerType {
     MultipleOrderedProposers,
 }
 
+#[derive(Debug, Eq, PartialEq, Clone, Copy)]
+pub enum PacemakerTimeIntervalType {
+    // Round durations grow exponentially from the initial timeout in the rounds without commit
+    Exponential,
+    // The base round duration is tuned from the measured QC formation times
+    Adaptive,
+}
+
 impl ConsensusConfig {
     pub fn load<P: AsRef<Path>>(&mut self, path: P) -> Result<()> {
         if !self.consensus_keypair_file.as_os_str().is_empty() {
@@ -470,6 +599,25 @@ impl ConsensusConfig {
     pub fn pacemaker_initial_timeout_ms(&self) -> &Option<u64> {
         &self.pacemaker_initial_timeout_ms
     }
+
+    pub fn get_pacemaker_time_interval_type(&self) -> PacemakerTimeIntervalType {
+        match self.pacemaker_time_interval.as_str() {
+            "exponential" => PacemakerTimeIntervalType::Exponential,
+            "adaptive" => PacemakerTimeIntervalType::Adaptive,
+            &_ => panic!(
+                "Invalid pacemaker time interval: {:?}, expected \"exponential\" or \"adaptive\"",
+                self.pacemaker_time_interval
+            ),
+        }
+    }
+
+    pub fn safety_rules(&self) -> &SafetyRulesConfig {
+        &self.safety_rules
+    }
+
+    pub fn batching(&self) -> &BatchingConfig {
+        &self.batching
+    }
 }
 
 #[derive(Clone, Debug, Deserialize, Serialize)]
@@ -704,14 +852,14 @@ impl NodeConfigHelpers {
         network.network_keypairs =
             NetworkKeyPairs::load(network_signing_private_key, network_identity_private_key);
         let seed_peers_config = SeedPeersConfigHelpers::get_test_config(&test_network_peers, None);
-        network.listen_address = seed_peers_config
+        network.listen_addresses = vec![seed_peers_config
             .seed_peers
             .get(&peer_id.to_string())
             .unwrap()
             .get(0)
             .unwrap()
-            .clone();
-        network.advertised_address = network.listen_address.clone();
+            .clone()];
+        network.advertised_addresses = network.listen_addresses.clone();
         network.seed_peers = seed_peers_config;
         network.network_peers = test_network_peers;
         config
@@ -726,30 +874,37 @@ impl NodeConfigHelpers {
         config.execution.port = get_available_port();
         config.mempool.mempool_service_port = get_available_port();
         config.secret_service.secret_service_port = get_available_port();
+        config.consensus.safety_rules.safety_rules_port = get_available_port();
         config.storage.port = get_available_port();
     }
 }
 
-/// Holds the VM configuration, currently this is only the publishing options for scripts and
-/// modules, but in the future this may need to be expanded to hold more information.
+/// Holds the VM configuration: the publishing options for scripts and modules, and how the
+/// transactions of a block are executed.
 #[derive(Clone, Debug, Deserialize, Serialize)]
 #[serde(default)]
 pub struct VMConfig {
+    /// Number of threads used to speculatively execute the transactions of a block in parallel.
+    /// Blocks are executed sequentially if this is 1 or less.
+    /// (Declared before the publishing options, as TOML values must precede the tables.)
+    pub execution_concurrency_level: usize,
     pub publishing_options: VMPublishingOption,
 }
 
 impl Default for VMConfig {
     fn default() -> VMConfig {
         VMConfig {
+            execution_concurrency_level: 1,
             publishing_options: VMPublishingOption::Open,
         }
     }
 }
 
-/// Defines and holds the publishing policies for the VM. There are three possible configurations:
+/// Defines and holds the publishing policies for the VM. There are four possible configurations:
 /// 1. No module publishing, only whitelisted scripts are allowed.
 /// 2. No module publishing, custom scripts are allowed.
 /// 3. Both module publishing and custom scripts are allowed.
+/// 4. Module publishing, custom scripts and upgrades of published modules are allowed.
 /// We represent these as an enum instead of a struct since whitelisting and module/script
 /// publishing are mutually exclusive options.
 #[derive(Clone, Debug, Deserialize, Serialize)]
@@ -763,12 +918,22 @@ pub enum VMPublishingOption {
     CustomScripts,
     /// Allow both custom scripts and custom module publishing
     Open,
+    /// Allow custom scripts, custom module publishing and republishing a module under the same
+    /// name, as long as the new version is compatible with the published one
+    Upgradable,
 }
 
 impl VMPublishingOption {
     pub fn is_open(&self) -> bool {
         match self {
-            VMPublishingOption::Open => true,
+            VMPublishingOption::Open | VMPublishingOption::Upgradable => true,
+            _ => false,
+        }
+    }
+
+    pub fn allows_module_upgrades(&self) -> bool {
+        match self {
+            VMPublishingOption::Upgradable => true,
             _ => false,
         }
     }
@@ -788,6 +953,7 @@ impl VMConfig {
     #[cfg(any(test, feature = "testing"))]
     pub fn empty_whitelist_FOR_TESTING() -> Self {
         VMConfig {
+            execution_concurrency_level: 1,
             publishing_options: VMPublishingOption::Locked(HashSet::new()),
         }
     }
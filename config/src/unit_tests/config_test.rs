@@ -26,3 +26,61 @@ fn verify_all_configs() {
         }
     }
 }
+
+#[test]
+fn verify_config_serialization() {
+    // The generated configs (e.g. by the genesis generation and the swarm) are serializable
+    let config = NodeConfigHelpers::get_single_node_test_config(false);
+    let serialized = toml::to_string(&config).expect("NodeConfig");
+    NodeConfig::parse(&serialized).expect("NodeConfig");
+    let serialized = toml::to_string(&VMConfig::empty_whitelist_FOR_TESTING()).expect("VMConfig");
+    VMConfig::parse(&serialized).expect("VMConfig");
+}
+
+#[test]
+fn verify_single_address_config() {
+    // The configs written before the support of multiple addresses are still valid
+    let config = NetworkConfig::parse(
+        r#"
+        listen_address = "/ip4/0.0.0.0/tcp/6180"
+        advertised_address = "/ip4/127.0.0.1/tcp/6180"
+        "#,
+    )
+    .expect("NetworkConfig");
+    assert_eq!(
+        config.listen_addresses,
+        vec!["/ip4/0.0.0.0/tcp/6180".parse::<Multiaddr>().unwrap()]
+    );
+    assert_eq!(
+        config.advertised_addresses,
+        vec!["/ip4/127.0.0.1/tcp/6180".parse::<Multiaddr>().unwrap()]
+    );
+
+    let config = NetworkConfig::parse(
+        r#"
+        listen_addresses = ["/ip4/0.0.0.0/tcp/6180", "/ip6/::/tcp/6180"]
+        "#,
+    )
+    .expect("NetworkConfig");
+    assert_eq!(config.listen_addresses.len(), 2);
+}
+
+#[test]
+#[should_panic(expected = "Invalid pacemaker time interval: \"linear\", expected")]
+fn invalid_pacemaker_time_interval() {
+    let config = ConsensusConfig {
+        pacemaker_time_interval: "linear".to_string(),
+        ..ConsensusConfig::default()
+    };
+    config.get_pacemaker_time_interval_type();
+}
+
+#[test]
+#[should_panic(expected = "Invalid proposer type: random_proposer")]
+fn invalid_proposer_type() {
+    let config = ConsensusConfig {
+        proposer_type: "random_proposer".to_string(),
+        ..ConsensusConfig::default()
+    };
+    config.get_proposer_type();
+}
@@ -61,6 +61,26 @@ pub fn get_local_ip() -> Option<Multiaddr> {
     })
 }
 
+/// Deserializes a list of addresses, also accepting a single address as written by the configs
+/// predating the support of multiple addresses (e.g. `listen_address = "/ip4/0.0.0.0/tcp/6180"`).
+pub fn deserialize_addresses<'de, D>(
+    deserializer: D,
+) -> ::std::result::Result<Vec<Multiaddr>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Addresses {
+        One(Multiaddr),
+        Many(Vec<Multiaddr>),
+    }
+    Ok(match Addresses::deserialize(deserializer)? {
+        Addresses::One(addr) => vec![addr],
+        Addresses::Many(addrs) => addrs,
+    })
+}
+
 pub fn deserialize_whitelist<'de, D>(
     deserializer: D,
 ) -> ::std::result::Result<HashSet<[u8; SCRIPT_HASH_LENGTH]>, D::Error>
@@ -0,0 +1,351 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::common::Author;
+use crypto::{
+    hash::{BatchHasher, BatchInfoHasher, CryptoHash, CryptoHasher},
+    HashValue,
+};
+use failure::prelude::*;
+use libra_types::{
+    account_address::AccountAddress,
+    crypto_proxies::{Signature, ValidatorSigner, ValidatorVerifier},
+};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::BTreeMap,
+    convert::{TryFrom, TryInto},
+    fmt::{Display, Formatter},
+};
+
+#[cfg(test)]
+#[path = "batch_test.rs"]
+mod batch_test;
+
+/// Batch is a set of transactions disseminated by a validator ahead of the proposals. Consensus
+/// treats the transactions as opaque bytes (the serialized payload) and refers to the batch by
+/// the digest of these bytes.
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq, Eq)]
+pub struct Batch {
+    author: Author,
+    epoch: u64,
+    num_txns: u64,
+    data: Vec<u8>,
+}
+
+impl Display for Batch {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        write!(
+            f,
+            "Batch: [author: {}, epoch: {}, num_txns: {}, bytes: {}]",
+            self.author.short_str(),
+            self.epoch,
+            self.num_txns,
+            self.data.len()
+        )
+    }
+}
+
+impl Batch {
+    pub fn new(author: Author, epoch: u64, num_txns: u64, data: Vec<u8>) -> Self {
+        Self {
+            author,
+            epoch,
+            num_txns,
+            data,
+        }
+    }
+
+    pub fn author(&self) -> Author {
+        self.author
+    }
+
+    pub fn epoch(&self) -> u64 {
+        self.epoch
+    }
+
+    pub fn num_txns(&self) -> u64 {
+        self.num_txns
+    }
+
+    pub fn data(&self) -> &[u8] {
+        &self.data
+    }
+
+    /// The digest of the serialized transactions.
+    pub fn digest(&self) -> HashValue {
+        let mut state = BatchHasher::default();
+        state.write(&self.data);
+        state.finish()
+    }
+
+    /// The information identifying the batch, which is what the validators sign.
+    pub fn info(&self) -> BatchInfo {
+        BatchInfo::new(self.author, self.epoch, self.digest(), self.num_txns)
+    }
+}
+
+/// BatchInfo identifies a batch: proposals only carry the batch infos (certified by a
+/// ProofOfStore) instead of the transactions.
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct BatchInfo {
+    author: Author,
+    epoch: u64,
+    digest: HashValue,
+    num_txns: u64,
+}
+
+impl Display for BatchInfo {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        write!(
+            f,
+            "BatchInfo: [author: {}, epoch: {}, digest: {}, num_txns: {}]",
+            self.author.short_str(),
+            self.epoch,
+            self.digest,
+            self.num_txns
+        )
+    }
+}
+
+impl BatchInfo {
+    pub fn new(author: Author, epoch: u64, digest: HashValue, num_txns: u64) -> Self {
+        Self {
+            author,
+            epoch,
+            digest,
+            num_txns,
+        }
+    }
+
+    pub fn author(&self) -> Author {
+        self.author
+    }
+
+    pub fn epoch(&self) -> u64 {
+        self.epoch
+    }
+
+    pub fn digest(&self) -> HashValue {
+        self.digest
+    }
+
+    pub fn num_txns(&self) -> u64 {
+        self.num_txns
+    }
+}
+
+impl CryptoHash for BatchInfo {
+    type Hasher = BatchInfoHasher;
+
+    fn hash(&self) -> HashValue {
+        let mut state = Self::Hasher::default();
+        state.write(lcs::to_bytes(self).expect("Should serialize.").as_ref());
+        state.finish()
+    }
+}
+
+/// SignedBatchInfo is the statement of a validator that it stores the given batch and is able
+/// to serve it to the other validators.
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq, Eq)]
+pub struct SignedBatchInfo {
+    info: BatchInfo,
+    signer: Author,
+    signature: Signature,
+}
+
+impl SignedBatchInfo {
+    pub fn new(info: BatchInfo, validator_signer: &ValidatorSigner) -> Self {
+        let signature = validator_signer
+            .sign_message(info.hash())
+            .expect("Failed to sign BatchInfo");
+        Self {
+            info,
+            signer: validator_signer.author(),
+            signature: signature.into(),
+        }
+    }
+
+    pub fn info(&self) -> &BatchInfo {
+        &self.info
+    }
+
+    pub fn signer(&self) -> Author {
+        self.signer
+    }
+
+    pub fn signature(&self) -> &Signature {
+        &self.signature
+    }
+
+    pub fn verify(&self, validator: &ValidatorVerifier) -> failure::Result<()> {
+        self.signature
+            .verify(validator, self.signer, self.info.hash())
+            .with_context(|e| format!("Fail to verify SignedBatchInfo: {:?}", e))?;
+        Ok(())
+    }
+}
+
+/// ProofOfStore is an availability certificate: a quorum of validators has signed the batch
+/// info, hence at least one honest validator stores the batch and can serve it. The payload of
+/// the proposals is a list of proofs of store.
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq, Eq)]
+pub struct ProofOfStore {
+    info: BatchInfo,
+    signatures: BTreeMap<Author, Signature>,
+}
+
+impl Display for ProofOfStore {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        write!(
+            f,
+            "ProofOfStore: [{}, signers: {}]",
+            self.info,
+            self.signatures.len()
+        )
+    }
+}
+
+impl ProofOfStore {
+    pub fn new(info: BatchInfo, signatures: BTreeMap<Author, Signature>) -> Self {
+        Self { info, signatures }
+    }
+
+    pub fn info(&self) -> &BatchInfo {
+        &self.info
+    }
+
+    pub fn digest(&self) -> HashValue {
+        self.info.digest
+    }
+
+    /// The validators that signed the batch info: the batch can be fetched from any of them.
+    pub fn signers(&self) -> impl Iterator<Item = &Author> {
+        self.signatures.keys()
+    }
+
+    /// Verifies that the signers form a quorum and that all the signatures are valid.
+    pub fn verify(&self, validator: &ValidatorVerifier) -> failure::Result<()> {
+        validator.check_voting_power(self.signatures.keys())?;
+        let info_hash = self.info.hash();
+        for (author, signature) in &self.signatures {
+            signature
+                .verify(validator, *author, info_hash)
+                .with_context(|e| format!("Fail to verify ProofOfStore: {:?}", e))?;
+        }
+        Ok(())
+    }
+}
+
+impl TryFrom<network::proto::Batch> for Batch {
+    type Error = failure::Error;
+
+    fn try_from(proto: network::proto::Batch) -> failure::Result<Self> {
+        Ok(Batch::new(
+            Author::try_from(proto.author)?,
+            proto.epoch,
+            proto.num_txns,
+            proto.data,
+        ))
+    }
+}
+
+impl From<Batch> for network::proto::Batch {
+    fn from(batch: Batch) -> Self {
+        Self {
+            author: batch.author.into(),
+            num_txns: batch.num_txns,
+            data: batch.data,
+            epoch: batch.epoch,
+        }
+    }
+}
+
+impl TryFrom<network::proto::BatchInfo> for BatchInfo {
+    type Error = failure::Error;
+
+    fn try_from(proto: network::proto::BatchInfo) -> failure::Result<Self> {
+        Ok(BatchInfo::new(
+            Author::try_from(proto.author)?,
+            proto.epoch,
+            HashValue::from_slice(&proto.digest)?,
+            proto.num_txns,
+        ))
+    }
+}
+
+impl From<BatchInfo> for network::proto::BatchInfo {
+    fn from(info: BatchInfo) -> Self {
+        Self {
+            author: info.author.into(),
+            digest: info.digest.to_vec(),
+            num_txns: info.num_txns,
+            epoch: info.epoch,
+        }
+    }
+}
+
+impl TryFrom<network::proto::SignedBatchInfo> for SignedBatchInfo {
+    type Error = failure::Error;
+
+    fn try_from(proto: network::proto::SignedBatchInfo) -> failure::Result<Self> {
+        Ok(SignedBatchInfo {
+            info: proto
+                .info
+                .ok_or_else(|| format_err!("Missing batch info"))?
+                .try_into()?,
+            signer: Author::try_from(proto.signer)?,
+            signature: Signature::try_from(&proto.signature)?,
+        })
+    }
+}
+
+impl From<SignedBatchInfo> for network::proto::SignedBatchInfo {
+    fn from(signed_info: SignedBatchInfo) -> Self {
+        Self {
+            info: Some(signed_info.info.into()),
+            signer: signed_info.signer.into(),
+            signature: signed_info.signature.to_bytes().to_vec(),
+        }
+    }
+}
+
+impl TryFrom<network::proto::ProofOfStore> for ProofOfStore {
+    type Error = failure::Error;
+
+    fn try_from(proto: network::proto::ProofOfStore) -> failure::Result<Self> {
+        let info = proto
+            .info
+            .ok_or_else(|| format_err!("Missing batch info"))?
+            .try_into()?;
+        let signatures = proto
+            .signatures
+            .into_iter()
+            .map(|proto| {
+                let author = AccountAddress::try_from(proto.validator_id)?;
+                let signature = Signature::try_from(&proto.signature)?;
+                Ok((author, signature))
+            })
+            .collect::<Result<BTreeMap<_, _>>>()?;
+        Ok(ProofOfStore::new(info, signatures))
+    }
+}
+
+impl From<ProofOfStore> for network::proto::ProofOfStore {
+    fn from(proof: ProofOfStore) -> Self {
+        let signatures = proof
+            .signatures
+            .into_iter()
+            .map(
+                |(validator_id, signature)| libra_types::proto::types::ValidatorSignature {
+                    validator_id: validator_id.to_vec(),
+                    signature: signature.to_bytes().to_vec(),
+                },
+            )
+            .collect();
+        Self {
+            info: Some(proof.info.into()),
+            signatures,
+        }
+    }
+}
@@ -0,0 +1,95 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::batch::{Batch, ProofOfStore, SignedBatchInfo};
+use libra_types::crypto_proxies::random_validator_verifier;
+use std::{collections::BTreeMap, convert::TryFrom};
+
+#[test]
+fn test_batch_digest() {
+    let (signers, _) = random_validator_verifier(2, None, false);
+    let batch = Batch::new(signers[0].author(), 1, 2, vec![1, 2, 3]);
+    // The digest only covers the transactions.
+    assert_eq!(
+        batch.digest(),
+        Batch::new(signers[1].author(), 1, 2, vec![1, 2, 3]).digest()
+    );
+    assert_ne!(
+        batch.digest(),
+        Batch::new(signers[0].author(), 1, 2, vec![1, 2, 4]).digest()
+    );
+    assert_eq!(batch.info().digest(), batch.digest());
+    assert_eq!(batch.info().author(), signers[0].author());
+    assert_eq!(batch.info().epoch(), 1);
+}
+
+#[test]
+fn test_proof_of_store_verification() {
+    let (signers, validators) = random_validator_verifier(4, None, false);
+    let info = Batch::new(signers[0].author(), 1, 1, vec![0; 16]).info();
+
+    let signed_infos: Vec<_> = signers
+        .iter()
+        .map(|signer| SignedBatchInfo::new(info.clone(), signer))
+        .collect();
+    for signed_info in &signed_infos {
+        assert!(signed_info.verify(&validators).is_ok());
+    }
+
+    let mut signatures = BTreeMap::new();
+    for signed_info in &signed_infos[..2] {
+        signatures.insert(signed_info.signer(), signed_info.signature().clone());
+    }
+    // Two signatures out of four validators are not a quorum.
+    assert!(ProofOfStore::new(info.clone(), signatures.clone())
+        .verify(&validators)
+        .is_err());
+
+    signatures.insert(
+        signed_infos[2].signer(),
+        signed_infos[2].signature().clone(),
+    );
+    let proof = ProofOfStore::new(info.clone(), signatures.clone());
+    assert!(proof.verify(&validators).is_ok());
+    assert_eq!(proof.signers().count(), 3);
+
+    // A signature of a different batch, or of the same batch in another epoch, is rejected.
+    for other_batch in vec![
+        Batch::new(signers[0].author(), 1, 1, vec![1; 16]),
+        Batch::new(signers[0].author(), 2, 1, vec![0; 16]),
+    ] {
+        let mut signatures = signatures.clone();
+        signatures.insert(
+            signers[3].author(),
+            SignedBatchInfo::new(other_batch.info(), &signers[3])
+                .signature()
+                .clone(),
+        );
+        assert!(ProofOfStore::new(info.clone(), signatures)
+            .verify(&validators)
+            .is_err());
+    }
+}
+
+#[test]
+fn test_proof_of_store_proto_conversion() {
+    let (signers, validators) = random_validator_verifier(4, None, false);
+    let batch = Batch::new(signers[1].author(), 1, 3, vec![7; 32]);
+    assert_eq!(
+        Batch::try_from(network::proto::Batch::from(batch.clone())).unwrap(),
+        batch
+    );
+
+    let signatures = signers
+        .iter()
+        .map(|signer| {
+            let signed_info = SignedBatchInfo::new(batch.info(), signer);
+            (signed_info.signer(), signed_info.signature().clone())
+        })
+        .collect();
+    let proof = ProofOfStore::new(batch.info(), signatures);
+    let converted =
+        ProofOfStore::try_from(network::proto::ProofOfStore::from(proof.clone())).unwrap();
+    assert_eq!(converted, proof);
+    assert!(converted.verify(&validators).is_ok());
+}
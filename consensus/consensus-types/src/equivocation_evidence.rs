@@ -0,0 +1,250 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{
+    block::Block,
+    common::{Author, Payload, Round},
+    vote_msg::VoteMsg,
+};
+use crypto::{
+    hash::{CryptoHash, CryptoHasher, EquivocationEvidenceHasher},
+    HashValue,
+};
+use failure::prelude::*;
+use libra_types::crypto_proxies::ValidatorVerifier;
+use network::proto::{DoubleProposal, DoubleVote, EquivocationEvidence_oneof};
+use serde::{Deserialize, Serialize};
+use std::{
+    convert::{TryFrom, TryInto},
+    fmt::{Display, Formatter},
+};
+
+#[cfg(test)]
+#[path = "equivocation_evidence_test.rs"]
+mod equivocation_evidence_test;
+
+/// EquivocationEvidence is a self-contained proof that a validator has signed two conflicting
+/// messages in the same round of the same epoch. It carries both signed messages, so that anyone
+/// holding the validator set of that epoch (e.g., a transaction script on chain) can verify it
+/// without trusting the reporter.
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq, Eq)]
+pub enum EquivocationEvidence<T> {
+    /// Two different proposals signed by the same author for the same round.
+    DoubleProposal { first: Block<T>, second: Block<T> },
+    /// Two votes for different LedgerInfos signed by the same author for the same round.
+    DoubleVote { first: VoteMsg, second: VoteMsg },
+}
+
+impl<T: PartialEq> Display for EquivocationEvidence<T> {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        match self {
+            EquivocationEvidence::DoubleProposal { first, second } => write!(
+                f,
+                "DoubleProposal: [author: {}, first: {}, second: {}]",
+                first.author().map_or("None".to_string(), |a| a.short_str()),
+                first,
+                second
+            ),
+            EquivocationEvidence::DoubleVote { first, second } => write!(
+                f,
+                "DoubleVote: [author: {}, round: {}, first block id: {}, second block id: {}]",
+                first.author().short_str(),
+                first.vote_data().proposed().round(),
+                first.vote_data().proposed().id(),
+                second.vote_data().proposed().id()
+            ),
+        }
+    }
+}
+
+impl<T: Payload> EquivocationEvidence<T> {
+    /// Returns the author that signed both conflicting messages.
+    /// Proposals carried by a well-formed evidence always have an author; NIL blocks are not
+    /// signed and cannot be used as evidence.
+    pub fn author(&self) -> Option<Author> {
+        match self {
+            EquivocationEvidence::DoubleProposal { first, .. } => first.author(),
+            EquivocationEvidence::DoubleVote { first, .. } => Some(first.author()),
+        }
+    }
+
+    /// Returns the round in which the equivocation happened.
+    pub fn round(&self) -> Round {
+        match self {
+            EquivocationEvidence::DoubleProposal { first, .. } => first.round(),
+            EquivocationEvidence::DoubleVote { first, .. } => first.vote_data().proposed().round(),
+        }
+    }
+
+    /// Returns the epoch in which the equivocation happened.
+    pub fn epoch(&self) -> u64 {
+        match self {
+            EquivocationEvidence::DoubleProposal { first, .. } => first.epoch(),
+            EquivocationEvidence::DoubleVote { first, .. } => first.vote_data().proposed().epoch(),
+        }
+    }
+
+    /// A short name of the kind of equivocation (used for logging and metrics).
+    pub fn kind(&self) -> &'static str {
+        match self {
+            EquivocationEvidence::DoubleProposal { .. } => "double_proposal",
+            EquivocationEvidence::DoubleVote { .. } => "double_vote",
+        }
+    }
+
+    /// Verifies that both messages are authored by the same validator for the same round and
+    /// epoch, that they indeed conflict, and that both signatures are valid.
+    pub fn verify(&self, validator: &ValidatorVerifier) -> failure::Result<()> {
+        match self {
+            EquivocationEvidence::DoubleProposal { first, second } => {
+                let author = first
+                    .author()
+                    .ok_or_else(|| format_err!("Evidence proposal has no author"))?;
+                ensure!(
+                    second.author() == Some(author),
+                    "Evidence proposals are authored by different validators"
+                );
+                ensure!(
+                    first.epoch() == second.epoch() && first.round() == second.round(),
+                    "Evidence proposals are from different rounds"
+                );
+                ensure!(
+                    first.id() != second.id(),
+                    "Evidence proposals are identical"
+                );
+                first
+                    .validate_signatures(validator)
+                    .and_then(|_| second.validate_signatures(validator))
+                    .with_context(|e| format!("Fail to verify DoubleProposal: {:?}", e))?;
+            }
+            EquivocationEvidence::DoubleVote { first, second } => {
+                ensure!(
+                    first.author() == second.author(),
+                    "Evidence votes are authored by different validators"
+                );
+                let (first_block, second_block) =
+                    (first.vote_data().proposed(), second.vote_data().proposed());
+                ensure!(
+                    first_block.epoch() == second_block.epoch()
+                        && first_block.round() == second_block.round(),
+                    "Evidence votes are from different rounds"
+                );
+                ensure!(
+                    first.ledger_info().hash() != second.ledger_info().hash(),
+                    "Evidence votes are for the same LedgerInfo"
+                );
+                first
+                    .verify(validator)
+                    .and_then(|_| second.verify(validator))
+                    .with_context(|e| format!("Fail to verify DoubleVote: {:?}", e))?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<T> CryptoHash for EquivocationEvidence<T> {
+    type Hasher = EquivocationEvidenceHasher;
+
+    /// The hash identifies the equivocation rather than the exact messages: two reports of the same
+    /// conflicting pair (received in a different order or with different sync info) hash the same.
+    fn hash(&self) -> HashValue {
+        let evidence_internal = match self {
+            EquivocationEvidence::DoubleProposal { first, second } => EvidenceSerializer {
+                is_double_vote: false,
+                author: first.author(),
+                epoch: first.epoch(),
+                round: first.round(),
+                digests: ordered(first.id(), second.id()),
+            },
+            EquivocationEvidence::DoubleVote { first, second } => EvidenceSerializer {
+                is_double_vote: true,
+                author: Some(first.author()),
+                epoch: first.vote_data().proposed().epoch(),
+                round: first.vote_data().proposed().round(),
+                digests: ordered(first.ledger_info().hash(), second.ledger_info().hash()),
+            },
+        };
+        let mut state = Self::Hasher::default();
+        state.write(
+            lcs::to_bytes(&evidence_internal)
+                .expect("Should serialize.")
+                .as_ref(),
+        );
+        state.finish()
+    }
+}
+
+// Internal use only. Contains all the fields that identify an equivocation.
+#[derive(Serialize)]
+struct EvidenceSerializer {
+    is_double_vote: bool,
+    author: Option<Author>,
+    epoch: u64,
+    round: Round,
+    digests: (HashValue, HashValue),
+}
+
+fn ordered(a: HashValue, b: HashValue) -> (HashValue, HashValue) {
+    if a <= b {
+        (a, b)
+    } else {
+        (b, a)
+    }
+}
+
+impl<T: Payload> TryFrom<network::proto::EquivocationEvidence> for EquivocationEvidence<T> {
+    type Error = failure::Error;
+
+    fn try_from(proto: network::proto::EquivocationEvidence) -> failure::Result<Self> {
+        Ok(match proto.evidence {
+            Some(EquivocationEvidence_oneof::DoubleProposal(proposals)) => {
+                EquivocationEvidence::DoubleProposal {
+                    first: proposals
+                        .first
+                        .ok_or_else(|| format_err!("Missing first proposal"))?
+                        .try_into()?,
+                    second: proposals
+                        .second
+                        .ok_or_else(|| format_err!("Missing second proposal"))?
+                        .try_into()?,
+                }
+            }
+            Some(EquivocationEvidence_oneof::DoubleVote(votes)) => {
+                EquivocationEvidence::DoubleVote {
+                    first: votes
+                        .first
+                        .ok_or_else(|| format_err!("Missing first vote"))?
+                        .try_into()?,
+                    second: votes
+                        .second
+                        .ok_or_else(|| format_err!("Missing second vote"))?
+                        .try_into()?,
+                }
+            }
+            None => bail!("Missing evidence"),
+        })
+    }
+}
+
+impl<T: Payload> From<EquivocationEvidence<T>> for network::proto::EquivocationEvidence {
+    fn from(evidence: EquivocationEvidence<T>) -> Self {
+        let evidence = match evidence {
+            EquivocationEvidence::DoubleProposal { first, second } => {
+                EquivocationEvidence_oneof::DoubleProposal(DoubleProposal {
+                    first: Some(first.into()),
+                    second: Some(second.into()),
+                })
+            }
+            EquivocationEvidence::DoubleVote { first, second } => {
+                EquivocationEvidence_oneof::DoubleVote(DoubleVote {
+                    first: Some(first.into()),
+                    second: Some(second.into()),
+                })
+            }
+        };
+        Self {
+            evidence: Some(evidence),
+        }
+    }
+}
@@ -0,0 +1,142 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{
+    block::Block, block_info::BlockInfo, equivocation_evidence::EquivocationEvidence,
+    quorum_cert::QuorumCert, sync_info::SyncInfo, vote_data::VoteData, vote_msg::VoteMsg,
+};
+use crypto::hash::{CryptoHash, HashValue};
+use libra_types::{
+    crypto_proxies::{random_validator_verifier, ValidatorSigner},
+    ledger_info::LedgerInfo,
+};
+use std::convert::TryFrom;
+
+fn make_proposal(payload: i64, round: u64, signer: &ValidatorSigner) -> Block<i64> {
+    Block::new_internal(
+        payload,
+        0,
+        round,
+        1,
+        QuorumCert::certificate_for_genesis(),
+        signer,
+    )
+}
+
+fn make_vote(round: u64, signer: &ValidatorSigner) -> VoteMsg {
+    let genesis_qc = QuorumCert::certificate_for_genesis();
+    VoteMsg::new(
+        VoteData::new(BlockInfo::random(round), BlockInfo::random(round - 1)),
+        signer.author(),
+        LedgerInfo::new(
+            0,
+            HashValue::zero(),
+            HashValue::zero(),
+            HashValue::zero(),
+            0,
+            0,
+            None,
+        ),
+        signer,
+        SyncInfo::new(genesis_qc.clone(), genesis_qc, None),
+    )
+}
+
+#[test]
+fn test_double_proposal_verification() {
+    let (signers, validators) = random_validator_verifier(2, None, false);
+    let first = make_proposal(1, 1, &signers[0]);
+    let second = make_proposal(2, 1, &signers[0]);
+
+    let evidence = EquivocationEvidence::DoubleProposal {
+        first: first.clone(),
+        second: second.clone(),
+    };
+    assert!(evidence.verify(&validators).is_ok());
+    assert_eq!(evidence.author(), Some(signers[0].author()));
+    assert_eq!(evidence.round(), 1);
+
+    // The same proposal twice is not an equivocation.
+    let same = EquivocationEvidence::DoubleProposal {
+        first: first.clone(),
+        second: first.clone(),
+    };
+    assert!(same.verify(&validators).is_err());
+
+    // Proposals of different rounds are not an equivocation.
+    let different_rounds = EquivocationEvidence::DoubleProposal {
+        first: first.clone(),
+        second: make_proposal(2, 2, &signers[0]),
+    };
+    assert!(different_rounds.verify(&validators).is_err());
+
+    // Proposals of different authors are not an equivocation.
+    let different_authors = EquivocationEvidence::DoubleProposal {
+        first,
+        second: make_proposal(2, 1, &signers[1]),
+    };
+    assert!(different_authors.verify(&validators).is_err());
+}
+
+#[test]
+fn test_double_vote_verification() {
+    let (signers, validators) = random_validator_verifier(2, None, false);
+    let first = make_vote(1, &signers[0]);
+    let second = make_vote(1, &signers[0]);
+
+    let evidence = EquivocationEvidence::<i64>::DoubleVote {
+        first: first.clone(),
+        second: second.clone(),
+    };
+    assert!(evidence.verify(&validators).is_ok());
+    assert_eq!(evidence.author(), Some(signers[0].author()));
+
+    // Adding a round signature does not change the voted LedgerInfo.
+    let mut timeout_vote = first.clone();
+    timeout_vote.add_round_signature(&signers[0]);
+    let timeout = EquivocationEvidence::<i64>::DoubleVote {
+        first: first.clone(),
+        second: timeout_vote,
+    };
+    assert!(timeout.verify(&validators).is_err());
+
+    let different_authors = EquivocationEvidence::<i64>::DoubleVote {
+        first,
+        second: make_vote(1, &signers[1]),
+    };
+    assert!(different_authors.verify(&validators).is_err());
+}
+
+#[test]
+fn test_evidence_hash_ignores_order() {
+    let (signers, _) = random_validator_verifier(1, None, false);
+    let first = make_proposal(1, 1, &signers[0]);
+    let second = make_proposal(2, 1, &signers[0]);
+    let evidence = EquivocationEvidence::DoubleProposal {
+        first: first.clone(),
+        second: second.clone(),
+    };
+    let swapped = EquivocationEvidence::DoubleProposal {
+        first: second,
+        second: first,
+    };
+    assert_eq!(evidence.hash(), swapped.hash());
+}
+
+#[test]
+fn test_proto_conversion() {
+    let (signers, _) = random_validator_verifier(1, None, false);
+    let evidence = EquivocationEvidence::DoubleProposal {
+        first: make_proposal(1, 1, &signers[0]),
+        second: make_proposal(2, 1, &signers[0]),
+    };
+    let proto: network::proto::EquivocationEvidence = evidence.clone().into();
+    assert_eq!(EquivocationEvidence::try_from(proto).unwrap(), evidence);
+
+    let evidence = EquivocationEvidence::<i64>::DoubleVote {
+        first: make_vote(1, &signers[0]),
+        second: make_vote(1, &signers[0]),
+    };
+    let proto: network::proto::EquivocationEvidence = evidence.clone().into();
+    assert_eq!(EquivocationEvidence::try_from(proto).unwrap(), evidence);
+}
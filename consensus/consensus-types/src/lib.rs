@@ -1,9 +1,11 @@
 // Copyright (c) The Libra Core Contributors
 // SPDX-License-Identifier: Apache-2.0
 
+pub mod batch;
 pub mod block;
 pub mod block_info;
 pub mod common;
+pub mod equivocation_evidence;
 pub mod proposal_msg;
 pub mod quorum_cert;
 pub mod sync_info;
@@ -89,6 +89,11 @@ impl VoteMsg {
         );
     }
 
+    /// Sets the round signature that has been generated externally (e.g., by SafetyRules).
+    pub fn set_round_signature(&mut self, round_signature: Signature) {
+        self.round_signature.replace(round_signature);
+    }
+
     pub fn vote_data(&self) -> &VoteData {
         &self.vote_data
     }
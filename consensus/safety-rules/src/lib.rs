@@ -1,15 +1,39 @@
 // Copyright (c) The Libra Core Contributors
 // SPDX-License-Identifier: Apache-2.0
 
+use crate::persistent_storage::PersistentStorage;
 use consensus_types::{
+    batch::{BatchInfo, SignedBatchInfo},
     block::Block,
-    common::{Payload, Round},
+    block_info::BlockInfo,
+    common::{self, Payload, Round},
     quorum_cert::QuorumCert,
+    sync_info::SyncInfo,
+    vote_data::VoteData,
+    vote_msg::VoteMsg,
+};
+use crypto::{
+    hash::{ConsensusStateHasher, CryptoHash, CryptoHasher},
+    HashValue,
+};
+use failure::{ensure, Fail};
+use libra_types::{
+    crypto_proxies::{Signature, ValidatorSigner, ValidatorVerifier},
+    ledger_info::LedgerInfo,
 };
-use crypto::HashValue;
-use failure::Fail;
 use serde::{Deserialize, Serialize};
-use std::fmt::{Display, Formatter};
+use std::{
+    fmt::{Display, Formatter},
+    sync::Arc,
+};
+
+pub mod persistent_storage;
+pub mod proto;
+pub mod remote_service;
+pub mod safety_rules_node;
+mod t_safety_rules;
+
+pub use t_safety_rules::{BlockProposal, TSafetyRules, VoteProposal};
 
 #[cfg(test)]
 #[path = "safety_rules_test.rs"]
@@ -88,6 +112,9 @@ pub enum ProposalReject {
 #[derive(Serialize, Default, Deserialize, Debug, Eq, PartialEq, Clone)]
 pub struct ConsensusState {
     last_vote_round: Round,
+    // The round of the last block signed by this validator as a proposer: a validator never
+    // signs two proposals for the same round.
+    last_proposed_round: Round,
 
     // A "preferred block" is the two-chain head with the highest block round.
     // We're using the `head` / `tail` terminology for describing the chains of QCs for describing
@@ -107,9 +134,10 @@ impl Display for ConsensusState {
             f,
             "ConsensusState: [\n\
              \tlast_vote_round = {},\n\
+             \tlast_proposed_round = {},\n\
              \tpreferred_block_round = {}\n\
              ]",
-            self.last_vote_round, self.preferred_block_round
+            self.last_vote_round, self.last_proposed_round, self.preferred_block_round
         )
     }
 }
@@ -118,6 +146,7 @@ impl ConsensusState {
     pub fn new(last_vote_round: Round, preferred_block_round: Round) -> Self {
         Self {
             last_vote_round,
+            last_proposed_round: 0,
             preferred_block_round,
         }
     }
@@ -127,6 +156,11 @@ impl ConsensusState {
         self.last_vote_round
     }
 
+    /// Returns the round of the last proposal signed by this validator
+    pub fn last_proposed_round(&self) -> Round {
+        self.last_proposed_round
+    }
+
     /// Returns the preferred block round
     pub fn preferred_block_round(&self) -> Round {
         self.preferred_block_round
@@ -143,26 +177,63 @@ impl ConsensusState {
         }
     }
 
+    /// Set the round of the last signed proposal
+    fn set_last_proposed_round(&mut self, last_proposed_round: Round) {
+        self.last_proposed_round = last_proposed_round;
+    }
+
     /// Set the preferred block round
     fn set_preferred_block_round(&mut self, preferred_block_round: Round) {
         self.preferred_block_round = preferred_block_round;
     }
 }
 
+impl CryptoHash for ConsensusState {
+    type Hasher = ConsensusStateHasher;
+
+    fn hash(&self) -> HashValue {
+        let mut state = Self::Hasher::default();
+        state.write(lcs::to_bytes(self).expect("Should serialize.").as_ref());
+        state.finish()
+    }
+}
+
 /// SafetyRules is responsible for two things that are critical for the safety of the consensus:
 /// 1) voting rules,
 /// 2) commit rules.
 /// SafetyRules is NOT THREAD SAFE (should be protected outside via e.g., RwLock).
 /// The commit decisions are returned to the caller as result of learning about a new QuorumCert.
+/// SafetyRules owns the consensus signing key: proposals, votes, timeouts and batch infos are
+/// all signed here, votes and timeouts only after the updated state has been persisted in its own
+/// storage. The state is never updated based on a QC whose signatures were not verified.
 pub struct SafetyRules {
     // Keeps the state.
     state: ConsensusState,
+    // Every update of the state is written through to the persistent storage.
+    persistent_storage: Box<dyn PersistentStorage>,
+    validator_signer: Arc<ValidatorSigner>,
+    // The epoch of the validator set: only the batches of this epoch are signed.
+    epoch: u64,
+    // Verifies the signatures of the QCs and proposals SafetyRules learns about.
+    validator_verifier: Arc<ValidatorVerifier>,
 }
 
 impl SafetyRules {
-    /// Constructs a new instance of SafetyRules given the BlockTree and ConsensusState.
-    pub fn new(state: ConsensusState) -> Self {
-        Self { state }
+    /// Constructs a new instance of SafetyRules with the ConsensusState read from the given
+    /// storage, for the validators of the given epoch.
+    pub fn new(
+        persistent_storage: Box<dyn PersistentStorage>,
+        validator_signer: Arc<ValidatorSigner>,
+        epoch: u64,
+        validator_verifier: Arc<ValidatorVerifier>,
+    ) -> Self {
+        Self {
+            state: persistent_storage.consensus_state(),
+            persistent_storage,
+            validator_signer,
+            epoch,
+            validator_verifier,
+        }
     }
 
     /// Learn about a new quorum certificate. Several things can happen as a result of that:
@@ -172,12 +243,21 @@ impl SafetyRules {
     /// Requires that all the ancestors of the block are available for at least up to the last
     /// committed block, might panic otherwise.
     /// The update function is invoked whenever a system learns about a potentially high QC.
-    pub fn update(&mut self, qc: &QuorumCert) {
+    /// The QC is rejected unless it is signed by a quorum of the validators.
+    pub fn update(&mut self, qc: &QuorumCert) -> failure::Result<()> {
+        qc.verify(&self.validator_verifier)?;
+        self.update_preferred_block_round(qc)
+    }
+
+    fn update_preferred_block_round(&mut self, qc: &QuorumCert) -> failure::Result<()> {
         // Preferred block rule: choose the highest 2-chain head.
         if qc.parent_block().round() > self.state.preferred_block_round() {
             self.state
                 .set_preferred_block_round(qc.parent_block().round());
+            self.persistent_storage
+                .set_consensus_state(self.state.clone())?;
         }
+        Ok(())
     }
 
     /// Check if a one-chain at round r+2 causes a commit at round r and return the committed
@@ -250,4 +330,145 @@ impl SafetyRules {
             })
         }
     }
+
+    /// Follows the voting rules for the executed proposal and, if it can be voted for, persists
+    /// the updated state and returns the signed vote.
+    /// The proposal and its QC must be correctly signed: the QC is then taken into account for
+    /// the preferred block round before applying the voting rules.
+    /// The LedgerInfo to be committed in case the vote gathers QC is built from the BlockInfo
+    /// certified by the proposal's QC, hence no access to the block tree is required.
+    pub fn construct_and_sign_vote<T: Payload>(
+        &mut self,
+        vote_proposal: &VoteProposal<T>,
+        sync_info: SyncInfo,
+    ) -> failure::Result<VoteMsg> {
+        let proposed_block = vote_proposal.block();
+        proposed_block.validate_signatures(&self.validator_verifier)?;
+        proposed_block.verify_well_formed()?;
+        self.update_preferred_block_round(proposed_block.quorum_cert())?;
+        let vote_info = self.voting_rule(proposed_block)?;
+        self.persistent_storage
+            .set_consensus_state(vote_info.consensus_state().clone())?;
+
+        let ledger_info_placeholder = match vote_info.potential_commit_id() {
+            Some(_) => {
+                let committed_block = proposed_block.quorum_cert().parent_block();
+                LedgerInfo::new(
+                    committed_block.version(),
+                    committed_block.executed_state_id(),
+                    HashValue::zero(),
+                    committed_block.id(),
+                    committed_block.epoch(),
+                    committed_block.timestamp_usecs(),
+                    None,
+                )
+            }
+            // Used in case we're using a ledger info just as a placeholder for signing the votes
+            // and there is no real block committed.
+            None => LedgerInfo::new(
+                0,
+                HashValue::zero(),
+                HashValue::zero(),
+                HashValue::zero(),
+                0,
+                0,
+                None,
+            ),
+        };
+
+        Ok(VoteMsg::new(
+            VoteData::new(
+                BlockInfo::from_block(
+                    proposed_block,
+                    vote_proposal.executed_state_id(),
+                    vote_proposal.version(),
+                ),
+                proposed_block.quorum_cert().certified_block().clone(),
+            ),
+            self.validator_signer.author(),
+            ledger_info_placeholder,
+            self.validator_signer.as_ref(),
+            sync_info,
+        ))
+    }
+
+    /// Signs the given round, which allows the signature to be aggregated into a timeout
+    /// certificate. Only the round of the last vote can be timed out: the validator must have
+    /// voted (either for a proposal or a NIL block) in the round it gives up on.
+    pub fn sign_timeout(&self, round: Round) -> failure::Result<Signature> {
+        ensure!(
+            round == self.state.last_vote_round(),
+            "Timeout round {} does not match the last vote round {}",
+            round,
+            self.state.last_vote_round()
+        );
+        Ok(self
+            .validator_signer
+            .sign_message(common::round_hash(round))?
+            .into())
+    }
+
+    /// Signs a proposal for the given round. The proposal must extend a correctly signed QC and
+    /// its round must be newer than both the certified block and the last vote: a validator that
+    /// has already voted in a round cannot propose in it anymore.
+    /// At most one proposal is signed per round: the round is persisted before the signed block
+    /// is returned.
+    pub fn sign_proposal<T: Payload>(
+        &mut self,
+        proposal: &BlockProposal<T>,
+    ) -> failure::Result<Block<T>> {
+        let quorum_cert = proposal.quorum_cert();
+        quorum_cert.verify(&self.validator_verifier)?;
+        ensure!(
+            proposal.round() > quorum_cert.certified_block().round(),
+            "Proposal round {} is not higher than the round of its certified parent {}",
+            proposal.round(),
+            quorum_cert.certified_block().round()
+        );
+        ensure!(
+            proposal.round() > self.state.last_vote_round(),
+            "Proposal round {} is not newer than the last vote round {}",
+            proposal.round(),
+            self.state.last_vote_round()
+        );
+        ensure!(
+            proposal.round() > self.state.last_proposed_round(),
+            "Proposal round {} is not newer than the last proposed round {}",
+            proposal.round(),
+            self.state.last_proposed_round()
+        );
+        let mut state = self.state.clone();
+        state.set_last_proposed_round(proposal.round());
+        self.persistent_storage.set_consensus_state(state.clone())?;
+        self.state = state;
+        Ok(Block::new_internal(
+            proposal.payload().clone(),
+            proposal.epoch(),
+            proposal.round(),
+            proposal.timestamp_usecs(),
+            quorum_cert.clone(),
+            self.validator_signer.as_ref(),
+        ))
+    }
+
+    /// Signs the info of a batch stored by this validator, attesting that it can serve the batch
+    /// to the others. Only the batches of the current epoch authored by one of its validators
+    /// are signed, so that the signatures cannot be gathered into a proof of store for another
+    /// epoch or for a batch no validator disseminated.
+    pub fn sign_batch_info(&self, info: BatchInfo) -> failure::Result<SignedBatchInfo> {
+        ensure!(
+            info.epoch() == self.epoch,
+            "{} is not in the current epoch {}",
+            info,
+            self.epoch
+        );
+        ensure!(
+            self.validator_verifier
+                .get_public_key(&info.author())
+                .is_some(),
+            "{} is not authored by a validator",
+            info
+        );
+        Ok(SignedBatchInfo::new(info, self.validator_signer.as_ref()))
+    }
 }
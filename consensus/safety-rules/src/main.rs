@@ -0,0 +1,30 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use executable_helpers::helpers::setup_executable;
+use safety_rules::safety_rules_node::SafetyRulesNode;
+use std::path::PathBuf;
+use structopt::StructOpt;
+
+#[derive(Debug, StructOpt)]
+#[structopt(about = "Libra SafetyRules")]
+struct Args {
+    #[structopt(short = "f", long, parse(from_os_str))]
+    /// Path to NodeConfig
+    config: Option<PathBuf>,
+    #[structopt(short = "d", long)]
+    /// Disable logging
+    no_logging: bool,
+}
+
+/// Run SafetyRules in its own process.
+fn main() {
+    let args = Args::from_args();
+
+    let (config, _logger) =
+        setup_executable(args.config.as_ref().map(PathBuf::as_path), args.no_logging);
+
+    SafetyRulesNode::new(config)
+        .run()
+        .expect("Unable to run SafetyRules");
+}
@@ -0,0 +1,144 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::ConsensusState;
+use crypto::hash::CryptoHash;
+use failure::prelude::*;
+use libra_types::crypto_proxies::{Signature, ValidatorSigner, ValidatorVerifier};
+use serde::{Deserialize, Serialize};
+use std::{
+    fs::{self, File},
+    io::Write,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+#[cfg(test)]
+#[path = "persistent_storage_test.rs"]
+mod persistent_storage_test;
+
+/// SafetyRules needs an abstract storage interface to act as a common utility for storing
+/// persistent data to local disk, cloud, secrets managers, or even memory (for tests).
+/// Any set function is expected to sync to the remote system before returning.
+pub trait PersistentStorage: Send + Sync {
+    fn consensus_state(&self) -> ConsensusState;
+    fn set_consensus_state(&mut self, consensus_state: ConsensusState) -> Result<()>;
+}
+
+/// InMemoryStorage keeps the state in memory only: it is used in tests and in the validators
+/// that keep persisting the ConsensusState through ConsensusDB.
+#[derive(Default)]
+pub struct InMemoryStorage {
+    consensus_state: ConsensusState,
+}
+
+impl InMemoryStorage {
+    pub fn new(consensus_state: ConsensusState) -> Self {
+        Self { consensus_state }
+    }
+
+    pub fn default_storage() -> Box<dyn PersistentStorage> {
+        Box::new(Self::default())
+    }
+}
+
+impl PersistentStorage for InMemoryStorage {
+    fn consensus_state(&self) -> ConsensusState {
+        self.consensus_state.clone()
+    }
+
+    fn set_consensus_state(&mut self, consensus_state: ConsensusState) -> Result<()> {
+        self.consensus_state = consensus_state;
+        Ok(())
+    }
+}
+
+// The record written to disk: the state is signed by the consensus key, hence a process that
+// does not hold the key cannot modify it without being detected.
+#[derive(Deserialize, Serialize)]
+struct SignedConsensusState {
+    consensus_state: ConsensusState,
+    signature: Signature,
+}
+
+/// OnDiskStorage keeps the state in a local file, signed by the consensus key of SafetyRules.
+/// The signature is verified upon loading the state, so any modification of the file done
+/// without the key makes SafetyRules refuse to start instead of signing conflicting messages.
+/// Note that the signature cannot protect against replacing the file by an older copy of itself.
+pub struct OnDiskStorage {
+    file_path: PathBuf,
+    consensus_state: ConsensusState,
+    validator_signer: Arc<ValidatorSigner>,
+}
+
+impl OnDiskStorage {
+    /// Loads and verifies the state stored in the given file. In case the file does not exist,
+    /// it is created with the given initial state.
+    pub fn new(
+        file_path: PathBuf,
+        validator_signer: Arc<ValidatorSigner>,
+        initial_state: ConsensusState,
+    ) -> Result<Self> {
+        let consensus_state = if file_path.exists() {
+            Self::load(&file_path, &validator_signer)?
+        } else {
+            initial_state
+        };
+        let mut storage = Self {
+            file_path,
+            consensus_state: consensus_state.clone(),
+            validator_signer,
+        };
+        storage.set_consensus_state(consensus_state)?;
+        Ok(storage)
+    }
+
+    fn load(file_path: &Path, validator_signer: &ValidatorSigner) -> Result<ConsensusState> {
+        let bytes = fs::read(file_path)?;
+        let signed_state: SignedConsensusState = lcs::from_bytes(&bytes)
+            .with_context(|e| format!("Failed to decode SafetyRules state: {}", e))?;
+        let validator =
+            ValidatorVerifier::new_single(validator_signer.author(), validator_signer.public_key());
+        signed_state
+            .signature
+            .verify(
+                &validator,
+                validator_signer.author(),
+                signed_state.consensus_state.hash(),
+            )
+            .with_context(|e| format!("SafetyRules state has been tampered with: {:?}", e))?;
+        Ok(signed_state.consensus_state)
+    }
+}
+
+impl PersistentStorage for OnDiskStorage {
+    fn consensus_state(&self) -> ConsensusState {
+        self.consensus_state.clone()
+    }
+
+    fn set_consensus_state(&mut self, consensus_state: ConsensusState) -> Result<()> {
+        let signature = self
+            .validator_signer
+            .sign_message(consensus_state.hash())?
+            .into();
+        let bytes = lcs::to_bytes(&SignedConsensusState {
+            consensus_state: consensus_state.clone(),
+            signature,
+        })?;
+        // Write to a temporary file first, so that a crash never leaves a partially written
+        // state behind. Both the file and the rename are synced before returning: otherwise a
+        // crash could roll back the last vote round and let the validator vote twice.
+        let tmp_path = self.file_path.with_extension("tmp");
+        let mut file = File::create(&tmp_path)?;
+        file.write_all(&bytes)?;
+        file.sync_all()?;
+        fs::rename(&tmp_path, &self.file_path)?;
+        let parent = match self.file_path.parent() {
+            Some(parent) if !parent.as_os_str().is_empty() => parent,
+            _ => Path::new("."),
+        };
+        File::open(parent)?.sync_all()?;
+        self.consensus_state = consensus_state;
+        Ok(())
+    }
+}
@@ -0,0 +1,66 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{
+    persistent_storage::{OnDiskStorage, PersistentStorage},
+    ConsensusState,
+};
+use libra_tools::tempdir::TempPath;
+use libra_types::crypto_proxies::ValidatorSigner;
+use std::{fs, sync::Arc};
+
+#[test]
+fn test_on_disk_storage_recovery() {
+    let signer = Arc::new(ValidatorSigner::from_int(0));
+    let file = TempPath::new();
+
+    let mut storage = OnDiskStorage::new(
+        file.path().to_path_buf(),
+        signer.clone(),
+        ConsensusState::default(),
+    )
+    .unwrap();
+    assert_eq!(storage.consensus_state(), ConsensusState::default());
+    let state = ConsensusState::new(5, 3);
+    storage.set_consensus_state(state.clone()).unwrap();
+
+    // The initial state is ignored once the file exists.
+    let storage =
+        OnDiskStorage::new(file.path().to_path_buf(), signer, ConsensusState::default()).unwrap();
+    assert_eq!(storage.consensus_state(), state);
+}
+
+#[test]
+fn test_on_disk_storage_tampering() {
+    let signer = Arc::new(ValidatorSigner::from_int(0));
+    let file = TempPath::new();
+    OnDiskStorage::new(
+        file.path().to_path_buf(),
+        signer.clone(),
+        ConsensusState::new(5, 3),
+    )
+    .unwrap();
+
+    // Overwrite the state with a lower last vote round, keeping the signature.
+    let mut bytes = fs::read(file.path()).unwrap();
+    bytes[0] = 1;
+    fs::write(file.path(), bytes).unwrap();
+    assert!(
+        OnDiskStorage::new(file.path().to_path_buf(), signer, ConsensusState::default()).is_err()
+    );
+
+    // A state signed by a different key is rejected as well.
+    let other_file = TempPath::new();
+    OnDiskStorage::new(
+        other_file.path().to_path_buf(),
+        Arc::new(ValidatorSigner::from_int(1)),
+        ConsensusState::default(),
+    )
+    .unwrap();
+    assert!(OnDiskStorage::new(
+        other_file.path().to_path_buf(),
+        Arc::new(ValidatorSigner::from_int(0)),
+        ConsensusState::default(),
+    )
+    .is_err());
+}
@@ -0,0 +1,6 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+#![allow(bare_trait_objects)]
+
+include!(concat!(env!("OUT_DIR"), "/safety_rules.rs"));
@@ -0,0 +1,377 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! SafetyRules can run in a separate process holding the consensus signing key: the consensus
+//! process then talks to it over a local grpc connection using `RemoteSafetyRules`, while
+//! `SafetyRulesServer` serves the requests on top of a local `SafetyRules` instance.
+//! The consensus data structures are passed as LCS-serialized bytes.
+//!
+//! The connection never leaves the host: SafetyRules only listens on a loopback address and
+//! serves the requests carrying the secret shared with consensus (see
+//! `SafetyRulesConfig::secret_file`), so that no other local process gets its messages signed.
+
+use crate::{
+    proto::{
+        ConsensusStateRequest, ConsensusStateResponse, ConstructAndSignVoteRequest,
+        ConstructAndSignVoteResponse, SafetyRulesService, SafetyRulesServiceClient,
+        SignBatchInfoRequest, SignBatchInfoResponse, SignProposalRequest, SignProposalResponse,
+        SignTimeoutRequest, SignTimeoutResponse, UpdateRequest, UpdateResponse,
+    },
+    BlockProposal, ConsensusState, SafetyRules, TSafetyRules, VoteProposal,
+};
+use config::config::SafetyRulesConfig;
+use consensus_types::{
+    batch::{BatchInfo, SignedBatchInfo},
+    block::Block,
+    common::{Payload, Round},
+    quorum_cert::QuorumCert,
+    sync_info::SyncInfo,
+    vote_msg::VoteMsg,
+};
+use failure::prelude::*;
+use futures::{
+    compat::Future01CompatExt,
+    future::{BoxFuture, FutureExt},
+};
+use grpc_helpers::provide_grpc_response;
+use grpcio::{
+    CallOption, ChannelBuilder, ClientUnaryReceiver, EnvBuilder, MetadataBuilder, RpcContext,
+};
+use libra_types::crypto_proxies::Signature;
+use std::{
+    fs,
+    marker::PhantomData,
+    net::ToSocketAddrs,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+#[cfg(test)]
+#[path = "remote_service_test.rs"]
+mod remote_service_test;
+
+/// The request header carrying the secret shared by consensus and SafetyRules.
+const SECRET_HEADER: &str = "safety-rules-secret";
+
+/// The minimum length of the shared secret.
+const MIN_SECRET_LEN: usize = 32;
+
+/// Reads the secret shared by consensus and SafetyRules from the configured secret file.
+pub fn read_secret(config: &SafetyRulesConfig) -> Result<String> {
+    let secret_file = config
+        .secret_file
+        .as_ref()
+        .ok_or_else(|| format_err!("A remote SafetyRules requires a secret file"))?;
+    let secret = fs::read_to_string(secret_file)
+        .map_err(|e| format_err!("Unable to read {}: {}", secret_file.display(), e))?;
+    let secret = secret.trim();
+    ensure!(
+        secret.len() >= MIN_SECRET_LEN && secret.bytes().all(|b| b.is_ascii_graphic()),
+        "The SafetyRules secret must be made of at least {} printable ASCII characters",
+        MIN_SECRET_LEN
+    );
+    Ok(secret.to_string())
+}
+
+/// Ensures that the configured address of SafetyRules only resolves to loopback addresses.
+pub fn ensure_loopback_address(config: &SafetyRulesConfig) -> Result<()> {
+    let addresses: Vec<_> = (config.address.as_str(), config.safety_rules_port)
+        .to_socket_addrs()?
+        .collect();
+    ensure!(
+        !addresses.is_empty() && addresses.iter().all(|addr| addr.ip().is_loopback()),
+        "SafetyRules must run on a loopback address, not {}",
+        config.address
+    );
+    Ok(())
+}
+
+/// Compares the secrets in constant time, not to leak how much of the expected one was guessed.
+fn secrets_match(received: &[u8], expected: &[u8]) -> bool {
+    received.len() == expected.len()
+        && received
+            .iter()
+            .zip(expected)
+            .fold(0, |diff, (a, b)| diff | (a ^ b))
+            == 0
+}
+
+/// SafetyRulesServer serves the requests of a remote consensus process.
+pub struct SafetyRulesServer<T> {
+    safety_rules: Arc<Mutex<SafetyRules>>,
+    secret: Arc<String>,
+    phantom: PhantomData<T>,
+}
+
+impl<T> Clone for SafetyRulesServer<T> {
+    fn clone(&self) -> Self {
+        Self {
+            safety_rules: Arc::clone(&self.safety_rules),
+            secret: Arc::clone(&self.secret),
+            phantom: PhantomData,
+        }
+    }
+}
+
+impl<T: Payload> SafetyRulesServer<T> {
+    /// Serves the requests authenticated with the given secret.
+    pub fn new(safety_rules: SafetyRules, secret: String) -> Self {
+        Self {
+            safety_rules: Arc::new(Mutex::new(safety_rules)),
+            secret: Arc::new(secret),
+            phantom: PhantomData,
+        }
+    }
+
+    fn authenticate(&self, ctx: &RpcContext) -> Result<()> {
+        let authenticated = ctx.request_headers().iter().any(|(key, value)| {
+            key == SECRET_HEADER && secrets_match(value, self.secret.as_bytes())
+        });
+        ensure!(authenticated, "The request is not authenticated");
+        Ok(())
+    }
+
+    fn consensus_state_inner(&self) -> Result<ConsensusStateResponse> {
+        let safety_rules = self.safety_rules.lock().unwrap();
+        Ok(ConsensusStateResponse {
+            consensus_state: lcs::to_bytes(&safety_rules.consensus_state())?,
+        })
+    }
+
+    fn update_inner(&self, req: UpdateRequest) -> Result<UpdateResponse> {
+        let qc: QuorumCert = lcs::from_bytes(&req.quorum_cert)?;
+        self.safety_rules.lock().unwrap().update(&qc)?;
+        Ok(UpdateResponse::default())
+    }
+
+    fn construct_and_sign_vote_inner(
+        &self,
+        req: ConstructAndSignVoteRequest,
+    ) -> Result<ConstructAndSignVoteResponse> {
+        let vote_proposal: VoteProposal<T> = lcs::from_bytes(&req.vote_proposal)?;
+        let sync_info: SyncInfo = lcs::from_bytes(&req.sync_info)?;
+        let vote = self
+            .safety_rules
+            .lock()
+            .unwrap()
+            .construct_and_sign_vote(&vote_proposal, sync_info)?;
+        Ok(ConstructAndSignVoteResponse {
+            vote: lcs::to_bytes(&vote)?,
+        })
+    }
+
+    fn sign_timeout_inner(&self, req: SignTimeoutRequest) -> Result<SignTimeoutResponse> {
+        let signature = self.safety_rules.lock().unwrap().sign_timeout(req.round)?;
+        Ok(SignTimeoutResponse {
+            signature: signature.to_bytes(),
+        })
+    }
+
+    fn sign_proposal_inner(&self, req: SignProposalRequest) -> Result<SignProposalResponse> {
+        let proposal: BlockProposal<T> = lcs::from_bytes(&req.block_proposal)?;
+        let block = self.safety_rules.lock().unwrap().sign_proposal(&proposal)?;
+        Ok(SignProposalResponse {
+            block: lcs::to_bytes(&block)?,
+        })
+    }
+
+    fn sign_batch_info_inner(&self, req: SignBatchInfoRequest) -> Result<SignBatchInfoResponse> {
+        let info: BatchInfo = lcs::from_bytes(&req.batch_info)?;
+        let signed_info = self.safety_rules.lock().unwrap().sign_batch_info(info)?;
+        Ok(SignBatchInfoResponse {
+            signed_batch_info: lcs::to_bytes(&signed_info)?,
+        })
+    }
+}
+
+impl<T: Payload> SafetyRulesService for SafetyRulesServer<T> {
+    fn consensus_state(
+        &mut self,
+        ctx: ::grpcio::RpcContext,
+        _req: ConsensusStateRequest,
+        sink: ::grpcio::UnarySink<ConsensusStateResponse>,
+    ) {
+        let response = self
+            .authenticate(&ctx)
+            .and_then(|()| self.consensus_state_inner());
+        provide_grpc_response(response, ctx, sink);
+    }
+
+    fn update(
+        &mut self,
+        ctx: ::grpcio::RpcContext,
+        req: UpdateRequest,
+        sink: ::grpcio::UnarySink<UpdateResponse>,
+    ) {
+        let response = self
+            .authenticate(&ctx)
+            .and_then(|()| self.update_inner(req));
+        provide_grpc_response(response, ctx, sink);
+    }
+
+    fn construct_and_sign_vote(
+        &mut self,
+        ctx: ::grpcio::RpcContext,
+        req: ConstructAndSignVoteRequest,
+        sink: ::grpcio::UnarySink<ConstructAndSignVoteResponse>,
+    ) {
+        let response = self
+            .authenticate(&ctx)
+            .and_then(|()| self.construct_and_sign_vote_inner(req));
+        provide_grpc_response(response, ctx, sink);
+    }
+
+    fn sign_timeout(
+        &mut self,
+        ctx: ::grpcio::RpcContext,
+        req: SignTimeoutRequest,
+        sink: ::grpcio::UnarySink<SignTimeoutResponse>,
+    ) {
+        let response = self
+            .authenticate(&ctx)
+            .and_then(|()| self.sign_timeout_inner(req));
+        provide_grpc_response(response, ctx, sink);
+    }
+
+    fn sign_proposal(
+        &mut self,
+        ctx: ::grpcio::RpcContext,
+        req: SignProposalRequest,
+        sink: ::grpcio::UnarySink<SignProposalResponse>,
+    ) {
+        let response = self
+            .authenticate(&ctx)
+            .and_then(|()| self.sign_proposal_inner(req));
+        provide_grpc_response(response, ctx, sink);
+    }
+
+    fn sign_batch_info(
+        &mut self,
+        ctx: ::grpcio::RpcContext,
+        req: SignBatchInfoRequest,
+        sink: ::grpcio::UnarySink<SignBatchInfoResponse>,
+    ) {
+        let response = self
+            .authenticate(&ctx)
+            .and_then(|()| self.sign_batch_info_inner(req));
+        provide_grpc_response(response, ctx, sink);
+    }
+}
+
+/// RemoteSafetyRules forwards all the requests to a SafetyRules process running on the same
+/// host. The requests are authenticated with the shared secret and time out after
+/// `request_timeout_ms`.
+pub struct RemoteSafetyRules<T> {
+    client: SafetyRulesServiceClient,
+    secret: String,
+    timeout: Duration,
+    phantom: PhantomData<T>,
+}
+
+impl<T: Payload> RemoteSafetyRules<T> {
+    pub fn new(config: &SafetyRulesConfig) -> Result<Self> {
+        ensure_loopback_address(config)?;
+        let secret = read_secret(config)?;
+        let env = Arc::new(EnvBuilder::new().name_prefix("grpc-safety-rules-").build());
+        let channel = ChannelBuilder::new(env)
+            .connect(&format!("{}:{}", config.address, config.safety_rules_port));
+        Ok(Self {
+            client: SafetyRulesServiceClient::new(channel),
+            secret,
+            timeout: Duration::from_millis(config.request_timeout_ms),
+            phantom: PhantomData,
+        })
+    }
+
+    /// Sends the request with the shared secret and returns the future of its response.
+    fn call<Req, Resp: Send + 'static>(
+        &self,
+        req: Result<Req>,
+        send: fn(
+            &SafetyRulesServiceClient,
+            &Req,
+            CallOption,
+        ) -> grpcio::Result<ClientUnaryReceiver<Resp>>,
+    ) -> BoxFuture<'static, Result<Resp>> {
+        let receiver = req.and_then(|req| {
+            let mut headers = MetadataBuilder::new();
+            headers.add_str(SECRET_HEADER, &self.secret)?;
+            let option = CallOption::default()
+                .timeout(self.timeout)
+                .headers(headers.build());
+            Ok(send(&self.client, &req, option)?)
+        });
+        async move { Ok(receiver?.compat().await?) }.boxed()
+    }
+}
+
+impl<T: Payload> TSafetyRules<T> for RemoteSafetyRules<T> {
+    fn consensus_state(&self) -> BoxFuture<'static, Result<ConsensusState>> {
+        let response = self.call(
+            Ok(ConsensusStateRequest::default()),
+            SafetyRulesServiceClient::consensus_state_async_opt,
+        );
+        async move { Ok(lcs::from_bytes(&response.await?.consensus_state)?) }.boxed()
+    }
+
+    fn update(&mut self, qc: &QuorumCert) -> BoxFuture<'static, Result<()>> {
+        let req = lcs::to_bytes(qc).map(|quorum_cert| UpdateRequest { quorum_cert });
+        let response = self.call(
+            req.map_err(Error::from),
+            SafetyRulesServiceClient::update_async_opt,
+        );
+        async move {
+            response.await?;
+            Ok(())
+        }
+        .boxed()
+    }
+
+    fn construct_and_sign_vote(
+        &mut self,
+        vote_proposal: &VoteProposal<T>,
+        sync_info: SyncInfo,
+    ) -> BoxFuture<'static, Result<VoteMsg>> {
+        let req = lcs::to_bytes(vote_proposal).and_then(|vote_proposal| {
+            Ok(ConstructAndSignVoteRequest {
+                vote_proposal,
+                sync_info: lcs::to_bytes(&sync_info)?,
+            })
+        });
+        let response = self.call(
+            req.map_err(Error::from),
+            SafetyRulesServiceClient::construct_and_sign_vote_async_opt,
+        );
+        async move { Ok(lcs::from_bytes(&response.await?.vote)?) }.boxed()
+    }
+
+    fn sign_timeout(&mut self, round: Round) -> BoxFuture<'static, Result<Signature>> {
+        let response = self.call(
+            Ok(SignTimeoutRequest { round }),
+            SafetyRulesServiceClient::sign_timeout_async_opt,
+        );
+        async move { Ok(Signature::try_from(&response.await?.signature)?) }.boxed()
+    }
+
+    fn sign_proposal(
+        &mut self,
+        proposal: &BlockProposal<T>,
+    ) -> BoxFuture<'static, Result<Block<T>>> {
+        let req =
+            lcs::to_bytes(proposal).map(|block_proposal| SignProposalRequest { block_proposal });
+        let response = self.call(
+            req.map_err(Error::from),
+            SafetyRulesServiceClient::sign_proposal_async_opt,
+        );
+        async move { Ok(lcs::from_bytes(&response.await?.block)?) }.boxed()
+    }
+
+    fn sign_batch_info(&self, info: BatchInfo) -> BoxFuture<'static, Result<SignedBatchInfo>> {
+        let req = lcs::to_bytes(&info).map(|batch_info| SignBatchInfoRequest { batch_info });
+        let response = self.call(
+            req.map_err(Error::from),
+            SafetyRulesServiceClient::sign_batch_info_async_opt,
+        );
+        async move { Ok(lcs::from_bytes(&response.await?.signed_batch_info)?) }.boxed()
+    }
+}
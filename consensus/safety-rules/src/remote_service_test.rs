@@ -0,0 +1,123 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{
+    persistent_storage::InMemoryStorage,
+    proto::create_safety_rules_service,
+    remote_service::{RemoteSafetyRules, SafetyRulesServer},
+    BlockProposal, SafetyRules, TSafetyRules, VoteProposal,
+};
+use config::{config::SafetyRulesConfig, utils::get_available_port};
+use consensus_types::{
+    batch::BatchInfo, block::Block, quorum_cert::QuorumCert, sync_info::SyncInfo,
+};
+use crypto::HashValue;
+use futures::executor::block_on;
+use grpc_helpers::spawn_service_thread;
+use libra_tools::tempdir::TempPath;
+use libra_types::crypto_proxies::{ValidatorSigner, ValidatorVerifier};
+use std::{fs, sync::Arc};
+
+const SECRET: &str = "0123456789abcdef0123456789abcdef";
+
+fn config_with_secret(port: u16, secret: &str, secret_file: &TempPath) -> SafetyRulesConfig {
+    fs::write(secret_file.path(), secret).unwrap();
+    let mut config = SafetyRulesConfig::default();
+    config.address = "localhost".to_string();
+    config.safety_rules_port = port;
+    config.secret_file = Some(secret_file.path().to_path_buf());
+    config
+}
+
+#[test]
+fn test_remote_safety_rules() {
+    let signer = ValidatorSigner::from_int(0);
+    let validator = ValidatorVerifier::new_single(signer.author(), signer.public_key());
+    let safety_rules = SafetyRules::new(
+        InMemoryStorage::default_storage(),
+        Arc::new(signer.clone()),
+        1,
+        Arc::new(validator.clone()),
+    );
+    let port = get_available_port();
+    let _handle = spawn_service_thread(
+        create_safety_rules_service(SafetyRulesServer::<u64>::new(
+            safety_rules,
+            SECRET.to_string(),
+        )),
+        "localhost".to_string(),
+        port,
+        "test_safety_rules",
+    );
+    let secret_file = TempPath::new();
+    let mut remote =
+        RemoteSafetyRules::<u64>::new(&config_with_secret(port, SECRET, &secret_file)).unwrap();
+
+    let genesis_qc = QuorumCert::certificate_for_genesis();
+    let block = block_on(remote.sign_proposal(&BlockProposal::new(1, 0, 1, 1, genesis_qc.clone())))
+        .unwrap();
+    assert_eq!(
+        block,
+        Block::new_internal(1, 0, 1, 1, genesis_qc.clone(), &signer)
+    );
+    block_on(remote.update(block.quorum_cert())).unwrap();
+    let vote = block_on(remote.construct_and_sign_vote(
+        &VoteProposal::new(block.clone(), HashValue::zero(), 0),
+        SyncInfo::new(genesis_qc.clone(), genesis_qc, None),
+    ))
+    .unwrap();
+    assert_eq!(vote.vote_data().proposed().id(), block.id());
+    assert!(vote.verify(&validator).is_ok());
+    assert_eq!(
+        block_on(remote.consensus_state())
+            .unwrap()
+            .last_vote_round(),
+        1
+    );
+
+    // The errors of SafetyRules are propagated to the client.
+    assert!(block_on(remote.sign_timeout(2)).is_err());
+    assert!(block_on(remote.sign_timeout(1)).is_ok());
+
+    let info = BatchInfo::new(signer.author(), 1, HashValue::zero(), 1);
+    let signed_info = block_on(remote.sign_batch_info(info.clone())).unwrap();
+    assert_eq!(signed_info.info(), &info);
+    assert!(signed_info.verify(&validator).is_ok());
+    assert!(block_on(remote.sign_batch_info(BatchInfo::new(
+        signer.author(),
+        2,
+        HashValue::zero(),
+        1
+    )))
+    .is_err());
+
+    // The requests authenticated with another secret are rejected.
+    let other_secret_file = TempPath::new();
+    let mut impostor = RemoteSafetyRules::<u64>::new(&config_with_secret(
+        port,
+        "fedcba9876543210fedcba9876543210",
+        &other_secret_file,
+    ))
+    .unwrap();
+    assert!(block_on(impostor.sign_timeout(1)).is_err());
+    assert!(block_on(impostor.consensus_state()).is_err());
+}
+
+#[test]
+fn test_remote_safety_rules_config() {
+    let secret_file = TempPath::new();
+    let port = get_available_port();
+    assert!(RemoteSafetyRules::<u64>::new(&config_with_secret(port, SECRET, &secret_file)).is_ok());
+
+    // The secret is required and must not be trivially guessable.
+    let mut config = config_with_secret(port, SECRET, &secret_file);
+    config.secret_file = None;
+    assert!(RemoteSafetyRules::<u64>::new(&config).is_err());
+    let config = config_with_secret(port, "secret", &secret_file);
+    assert!(RemoteSafetyRules::<u64>::new(&config).is_err());
+
+    // SafetyRules cannot be reached through the network.
+    let mut config = config_with_secret(port, SECRET, &secret_file);
+    config.address = "8.8.8.8".to_string();
+    assert!(RemoteSafetyRules::<u64>::new(&config).is_err());
+}
@@ -0,0 +1,113 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! A SafetyRules node runs SafetyRules in its own process, so that the consensus signing key and
+//! the safety state are not accessible to the consensus process.
+//! It accepts connections on node_config.consensus.safety_rules.address and
+//! node_config.consensus.safety_rules.safety_rules_port, which must be a loopback address, and
+//! only serves the requests authenticated with the secret of
+//! node_config.consensus.safety_rules.secret_file.
+//! For an example on how to run the node see main.rs.
+
+use crate::{
+    persistent_storage::OnDiskStorage,
+    proto::create_safety_rules_service,
+    remote_service::{self, SafetyRulesServer},
+    ConsensusState, SafetyRules,
+};
+use config::config::NodeConfig;
+use consensus_types::batch::ProofOfStore;
+use failure::prelude::*;
+use grpc_helpers::spawn_service_thread;
+use libra_logger::prelude::*;
+use libra_types::{
+    account_address::AccountAddress, crypto_proxies::ValidatorSigner,
+    transaction::SignedTransaction,
+};
+use std::{convert::TryFrom, sync::Arc, thread};
+
+/// The default name of the file the state is kept in (within the data directory).
+const DEFAULT_STORAGE_FILE: &str = "safety_rules.state";
+
+/// SafetyRules node holds the consensus signing key and serves the requests of consensus.
+pub struct SafetyRulesNode {
+    node_config: NodeConfig,
+}
+
+impl SafetyRulesNode {
+    /// Instantiates the node with a config file.
+    pub fn new(node_config: NodeConfig) -> Self {
+        Self { node_config }
+    }
+
+    /// Starts the SafetyRules service.
+    pub fn run(mut self) -> Result<()> {
+        info!("Starting SafetyRules node");
+
+        let peer_id = self
+            .node_config
+            .get_validator_network_config()
+            .ok_or_else(|| format_err!("SafetyRules requires a validator network config"))?
+            .peer_id
+            .clone();
+        let author = AccountAddress::try_from(peer_id)?;
+        let private_key = self
+            .node_config
+            .consensus
+            .consensus_keypair
+            .take_consensus_private()
+            .ok_or_else(|| format_err!("Consensus private key absent"))?;
+        let signer = Arc::new(ValidatorSigner::new(author, private_key));
+
+        let config = self.node_config.consensus.safety_rules().clone();
+        remote_service::ensure_loopback_address(&config)?;
+        let secret = remote_service::read_secret(&config)?;
+        let storage_file = config.storage_file.unwrap_or_else(|| {
+            self.node_config
+                .base
+                .data_dir_path
+                .join(DEFAULT_STORAGE_FILE)
+        });
+        let storage =
+            OnDiskStorage::new(storage_file, Arc::clone(&signer), ConsensusState::default())?;
+        let validators = Arc::new(
+            self.node_config
+                .consensus
+                .consensus_peers
+                .get_validator_verifier(),
+        );
+        // No reconfiguration yet: like consensus, SafetyRules starts in epoch 0 with the validators
+        // of the config.
+        let safety_rules = SafetyRules::new(Box::new(storage), signer, 0, validators);
+
+        // The payload of the blocks must match the one consensus is configured with: the proofs
+        // of store of the disseminated batches when batching is enabled, the list of signed
+        // transactions otherwise.
+        let service = if self.node_config.consensus.batching().enabled {
+            create_safety_rules_service(SafetyRulesServer::<Vec<ProofOfStore>>::new(
+                safety_rules,
+                secret,
+            ))
+        } else {
+            create_safety_rules_service(SafetyRulesServer::<Vec<SignedTransaction>>::new(
+                safety_rules,
+                secret,
+            ))
+        };
+        let _service_handle = spawn_service_thread(
+            service,
+            config.address,
+            config.safety_rules_port,
+            "safety_rules",
+        );
+
+        info!(
+            "Started SafetyRules node on port {}",
+            config.safety_rules_port
+        );
+
+        loop {
+            thread::park();
+        }
+    }
+}
@@ -1,18 +1,23 @@
 // Copyright (c) The Libra Core Contributors
 // SPDX-License-Identifier: Apache-2.0
 
-use crate::{ConsensusState, ProposalReject, SafetyRules};
+use crate::{
+    persistent_storage::{InMemoryStorage, OnDiskStorage},
+    BlockProposal, ConsensusState, ProposalReject, SafetyRules, VoteProposal,
+};
 use consensus_types::{
-    block::Block, block_info::BlockInfo, common::Round, quorum_cert::QuorumCert,
+    batch::BatchInfo, block::Block, block_info::BlockInfo, common::Round, quorum_cert::QuorumCert,
     sync_info::SyncInfo, vote_data::VoteData, vote_msg::VoteMsg,
 };
 use crypto::hash::{CryptoHash, HashValue};
+use libra_tools::tempdir::TempPath;
 use libra_types::{
-    crypto_proxies::ValidatorSigner,
+    crypto_proxies::{ValidatorSigner, ValidatorVerifier},
     ledger_info::{LedgerInfo, LedgerInfoWithSignatures},
 };
 use std::{
     collections::BTreeMap,
+    sync::Arc,
     time::{SystemTime, UNIX_EPOCH},
 };
 
@@ -94,12 +99,24 @@ fn make_block_with_parent(
     make_block_with_qc(round, qc, validator_signer)
 }
 
+fn make_safety_rules(validator_signer: &ValidatorSigner) -> SafetyRules {
+    SafetyRules::new(
+        InMemoryStorage::default_storage(),
+        Arc::new(validator_signer.clone()),
+        1,
+        Arc::new(ValidatorVerifier::new_single(
+            validator_signer.author(),
+            validator_signer.public_key(),
+        )),
+    )
+}
+
 #[test]
 fn test_initial_state() {
     // Start from scratch, verify the state
     let block = Block::<u64>::make_genesis_block();
 
-    let safety_rules = SafetyRules::new(ConsensusState::default());
+    let safety_rules = make_safety_rules(&ValidatorSigner::from_int(0));
     let state = safety_rules.consensus_state();
     assert_eq!(state.last_vote_round(), block.round());
     assert_eq!(state.preferred_block_round(), block.round());
@@ -109,7 +126,7 @@ fn test_initial_state() {
 fn test_preferred_block_rule() {
     // Preferred block is the highest 2-chain head.
     let validator_signer = ValidatorSigner::from_int(0);
-    let mut safety_rules = SafetyRules::new(ConsensusState::default());
+    let mut safety_rules = make_safety_rules(&validator_signer);
 
     // build a tree of the following form:
     //             _____    _____
@@ -130,43 +147,43 @@ fn test_preferred_block_rule() {
     let a3 = make_block_with_parent(round + 6, &a2, None, &validator_signer, &b3.quorum_cert());
     let a4 = make_block_with_parent(round + 7, &a3, None, &validator_signer, &a3.quorum_cert());
 
-    safety_rules.update(a1.quorum_cert());
+    safety_rules.update(a1.quorum_cert()).unwrap();
     assert_eq!(
         safety_rules.consensus_state().preferred_block_round(),
         genesis_block.round()
     );
 
-    safety_rules.update(b1.quorum_cert());
+    safety_rules.update(b1.quorum_cert()).unwrap();
     assert_eq!(
         safety_rules.consensus_state().preferred_block_round(),
         genesis_block.round()
     );
 
-    safety_rules.update(a2.quorum_cert());
+    safety_rules.update(a2.quorum_cert()).unwrap();
     assert_eq!(
         safety_rules.consensus_state().preferred_block_round(),
         genesis_block.round()
     );
 
-    safety_rules.update(b2.quorum_cert());
+    safety_rules.update(b2.quorum_cert()).unwrap();
     assert_eq!(
         safety_rules.consensus_state().preferred_block_round(),
         genesis_block.round()
     );
 
-    safety_rules.update(a3.quorum_cert());
+    safety_rules.update(a3.quorum_cert()).unwrap();
     assert_eq!(
         safety_rules.consensus_state().preferred_block_round(),
         b1.round()
     );
 
-    safety_rules.update(b3.quorum_cert());
+    safety_rules.update(b3.quorum_cert()).unwrap();
     assert_eq!(
         safety_rules.consensus_state().preferred_block_round(),
         b1.round()
     );
 
-    safety_rules.update(a4.quorum_cert());
+    safety_rules.update(a4.quorum_cert()).unwrap();
     assert_eq!(
         safety_rules.consensus_state().preferred_block_round(),
         a2.round()
@@ -177,7 +194,7 @@ fn test_preferred_block_rule() {
 /// Test the potential ledger info that we're going to use in case of voting
 fn test_voting_potential_commit_id() {
     let validator_signer = ValidatorSigner::from_int(0);
-    let mut safety_rules = SafetyRules::new(ConsensusState::default());
+    let mut safety_rules = make_safety_rules(&validator_signer);
 
     // build a tree of the following form:
     //            _____
@@ -211,18 +228,18 @@ fn test_voting_potential_commit_id() {
     );
 
     for b in &[&a1, &b1, &a2, &a3] {
-        safety_rules.update(b.quorum_cert());
+        safety_rules.update(b.quorum_cert()).unwrap();
         let voting_info = safety_rules.voting_rule(b).unwrap();
         assert_eq!(voting_info.potential_commit_id, None);
     }
 
-    safety_rules.update(a4.quorum_cert());
+    safety_rules.update(a4.quorum_cert()).unwrap();
     assert_eq!(
         safety_rules.voting_rule(&a4).unwrap().potential_commit_id,
         Some(a2.id())
     );
 
-    safety_rules.update(a5.quorum_cert());
+    safety_rules.update(a5.quorum_cert()).unwrap();
     assert_eq!(
         safety_rules.voting_rule(&a5).unwrap().potential_commit_id,
         Some(a3.id())
@@ -232,7 +249,7 @@ fn test_voting_potential_commit_id() {
 #[test]
 fn test_voting() {
     let validator_signer = ValidatorSigner::from_int(0);
-    let mut safety_rules = SafetyRules::new(ConsensusState::default());
+    let mut safety_rules = make_safety_rules(&validator_signer);
 
     // build a tree of the following form:
     //             _____    __________
@@ -265,19 +282,19 @@ fn test_voting() {
     let a4 = make_block_with_parent(round + 7, &a3, None, &validator_signer, &b3.quorum_cert());
     let b4 = make_block_with_parent(round + 8, &b2, None, &validator_signer, &a4.quorum_cert());
 
-    safety_rules.update(a1.quorum_cert());
+    safety_rules.update(a1.quorum_cert()).unwrap();
     let mut voting_info = safety_rules.voting_rule(&a1).unwrap();
     assert_eq!(voting_info.potential_commit_id, None);
 
-    safety_rules.update(b1.quorum_cert());
+    safety_rules.update(b1.quorum_cert()).unwrap();
     voting_info = safety_rules.voting_rule(&b1).unwrap();
     assert_eq!(voting_info.potential_commit_id, None);
 
-    safety_rules.update(a2.quorum_cert());
+    safety_rules.update(a2.quorum_cert()).unwrap();
     voting_info = safety_rules.voting_rule(&a2).unwrap();
     assert_eq!(voting_info.potential_commit_id, None);
 
-    safety_rules.update(b2.quorum_cert());
+    safety_rules.update(b2.quorum_cert()).unwrap();
     assert_eq!(
         safety_rules.voting_rule(&b2),
         Err(ProposalReject::OldProposal {
@@ -286,19 +303,19 @@ fn test_voting() {
         })
     );
 
-    safety_rules.update(a3.quorum_cert());
+    safety_rules.update(a3.quorum_cert()).unwrap();
     voting_info = safety_rules.voting_rule(&a3).unwrap();
     assert_eq!(voting_info.potential_commit_id, None);
 
-    safety_rules.update(b3.quorum_cert());
+    safety_rules.update(b3.quorum_cert()).unwrap();
     voting_info = safety_rules.voting_rule(&b3).unwrap();
     assert_eq!(voting_info.potential_commit_id, None);
 
-    safety_rules.update(a4.quorum_cert());
+    safety_rules.update(a4.quorum_cert()).unwrap();
     voting_info = safety_rules.voting_rule(&a4).unwrap();
     assert_eq!(voting_info.potential_commit_id, None);
 
-    safety_rules.update(a4.quorum_cert());
+    safety_rules.update(a4.quorum_cert()).unwrap();
     assert_eq!(
         safety_rules.voting_rule(&a4),
         Err(ProposalReject::OldProposal {
@@ -306,7 +323,7 @@ fn test_voting() {
             proposal_round: 7,
         })
     );
-    safety_rules.update(b4.quorum_cert());
+    safety_rules.update(b4.quorum_cert()).unwrap();
     assert_eq!(
         safety_rules.voting_rule(&b4),
         Err(ProposalReject::ProposalRoundLowerThenPreferredBlock {
@@ -318,7 +335,7 @@ fn test_voting() {
 #[test]
 fn test_commit_rule_consecutive_rounds() {
     let validator_signer = ValidatorSigner::from_int(0);
-    let safety_rules = SafetyRules::new(ConsensusState::default());
+    let safety_rules = make_safety_rules(&ValidatorSigner::from_int(0));
 
     // build a tree of the following form:
     //             ___________
@@ -364,3 +381,161 @@ fn test_commit_rule_consecutive_rounds() {
         Some(a2.id())
     );
 }
+
+#[test]
+fn test_construct_and_sign_vote() {
+    let validator_signer = ValidatorSigner::from_int(0);
+    let mut safety_rules = make_safety_rules(&validator_signer);
+    let validator =
+        ValidatorVerifier::new_single(validator_signer.author(), validator_signer.public_key());
+
+    let genesis_qc = QuorumCert::certificate_for_genesis();
+    let sync_info = SyncInfo::new(genesis_qc.clone(), genesis_qc.clone(), None);
+    let a1 = make_block_with_qc(1, genesis_qc, &validator_signer);
+    let vote_proposal = VoteProposal::new(a1.clone(), HashValue::zero(), 0);
+
+    // A round cannot be timed out before voting in it.
+    assert!(safety_rules.sign_timeout(a1.round()).is_err());
+
+    let vote = safety_rules
+        .construct_and_sign_vote(&vote_proposal, sync_info.clone())
+        .unwrap();
+    assert_eq!(vote.author(), validator_signer.author());
+    assert_eq!(vote.vote_data().proposed().id(), a1.id());
+    assert!(vote.verify(&validator).is_ok());
+    assert_eq!(safety_rules.consensus_state().last_vote_round(), a1.round());
+
+    // Voting again in the same round is rejected.
+    assert!(safety_rules
+        .construct_and_sign_vote(&vote_proposal, sync_info)
+        .is_err());
+
+    assert!(safety_rules.sign_timeout(a1.round()).is_ok());
+    assert!(safety_rules.sign_timeout(a1.round() + 1).is_err());
+}
+
+#[test]
+fn test_reject_unverified_quorum_cert() {
+    let validator_signer = ValidatorSigner::from_int(0);
+    let other_signer = ValidatorSigner::from_int(1);
+    let mut safety_rules = make_safety_rules(&validator_signer);
+
+    // The QCs are signed by a validator that is not part of the validator set.
+    let genesis_qc = QuorumCert::certificate_for_genesis();
+    let a1 = make_block_with_qc(1, genesis_qc.clone(), &other_signer);
+    let a2 = make_block_with_parent(2, &a1, None, &other_signer, &genesis_qc);
+    let a3 = make_block_with_parent(3, &a2, None, &other_signer, a2.quorum_cert());
+    assert!(safety_rules.update(a3.quorum_cert()).is_err());
+    assert_eq!(safety_rules.consensus_state().preferred_block_round(), 0);
+
+    // A proposal carrying such a QC is not voted for.
+    let sync_info = SyncInfo::new(genesis_qc.clone(), genesis_qc, None);
+    let vote_proposal = VoteProposal::new(a3, HashValue::zero(), 0);
+    assert!(safety_rules
+        .construct_and_sign_vote(&vote_proposal, sync_info)
+        .is_err());
+    assert_eq!(safety_rules.consensus_state(), ConsensusState::default());
+}
+
+#[test]
+fn test_sign_proposal() {
+    let validator_signer = ValidatorSigner::from_int(0);
+    let mut safety_rules = make_safety_rules(&validator_signer);
+    let genesis_qc = QuorumCert::certificate_for_genesis();
+
+    let proposal = BlockProposal::new(1, 1, 1, 1, genesis_qc.clone());
+    let block = safety_rules.sign_proposal(&proposal).unwrap();
+    assert_eq!(block.author(), Some(validator_signer.author()));
+    assert_eq!(block.round(), 1);
+    assert_eq!(block.payload(), Some(&1));
+
+    // The round must be higher than the one of the certified parent.
+    assert!(safety_rules
+        .sign_proposal(&BlockProposal::new(0, 1, 0, 1, genesis_qc.clone()))
+        .is_err());
+
+    // A validator doesn't propose in a round it has already voted in.
+    let sync_info = SyncInfo::new(genesis_qc.clone(), genesis_qc.clone(), None);
+    safety_rules
+        .construct_and_sign_vote(&VoteProposal::new(block, HashValue::zero(), 0), sync_info)
+        .unwrap();
+    assert!(safety_rules.sign_proposal(&proposal).is_err());
+    assert!(safety_rules
+        .sign_proposal(&BlockProposal::new(2, 1, 2, 2, genesis_qc))
+        .is_ok());
+}
+
+#[test]
+fn test_sign_proposal_equivocation() {
+    let validator_signer = Arc::new(ValidatorSigner::from_int(0));
+    let validator_verifier = Arc::new(ValidatorVerifier::new_single(
+        validator_signer.author(),
+        validator_signer.public_key(),
+    ));
+    let file = TempPath::new();
+    let make_safety_rules = || {
+        let storage = OnDiskStorage::new(
+            file.path().to_path_buf(),
+            validator_signer.clone(),
+            ConsensusState::default(),
+        )
+        .unwrap();
+        SafetyRules::new(
+            Box::new(storage),
+            validator_signer.clone(),
+            1,
+            validator_verifier.clone(),
+        )
+    };
+    let genesis_qc = QuorumCert::certificate_for_genesis();
+
+    let mut safety_rules = make_safety_rules();
+    safety_rules
+        .sign_proposal(&BlockProposal::new(1, 1, 1, 1, genesis_qc.clone()))
+        .unwrap();
+    assert_eq!(safety_rules.consensus_state().last_proposed_round(), 1);
+    // A second, different proposal for the same round is not signed.
+    assert!(safety_rules
+        .sign_proposal(&BlockProposal::new(2, 1, 1, 1, genesis_qc.clone()))
+        .is_err());
+
+    // The proposed round survives a restart.
+    let mut safety_rules = make_safety_rules();
+    assert_eq!(safety_rules.consensus_state().last_proposed_round(), 1);
+    assert!(safety_rules
+        .sign_proposal(&BlockProposal::new(2, 1, 1, 1, genesis_qc.clone()))
+        .is_err());
+    assert!(safety_rules
+        .sign_proposal(&BlockProposal::new(2, 1, 2, 2, genesis_qc))
+        .is_ok());
+}
+
+#[test]
+fn test_sign_batch_info() {
+    let validator_signer = ValidatorSigner::from_int(0);
+    let safety_rules = make_safety_rules(&validator_signer);
+
+    let info = BatchInfo::new(validator_signer.author(), 1, HashValue::zero(), 1);
+    let signed_info = safety_rules.sign_batch_info(info.clone()).unwrap();
+    assert_eq!(signed_info.info(), &info);
+    assert_eq!(signed_info.signer(), validator_signer.author());
+
+    // The batches of another epoch are not signed.
+    assert!(safety_rules
+        .sign_batch_info(BatchInfo::new(
+            validator_signer.author(),
+            2,
+            HashValue::zero(),
+            1
+        ))
+        .is_err());
+    // Nor are the batches authored by a node outside of the validator set.
+    assert!(safety_rules
+        .sign_batch_info(BatchInfo::new(
+            ValidatorSigner::from_int(1).author(),
+            1,
+            HashValue::zero(),
+            1
+        ))
+        .is_err());
+}
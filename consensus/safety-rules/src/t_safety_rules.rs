@@ -0,0 +1,212 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{ConsensusState, SafetyRules};
+use consensus_types::{
+    batch::{BatchInfo, SignedBatchInfo},
+    block::Block,
+    common::{Payload, Round},
+    quorum_cert::QuorumCert,
+    sync_info::SyncInfo,
+    vote_msg::VoteMsg,
+};
+use crypto::HashValue;
+use failure::Result;
+use futures::future::{self, BoxFuture, FutureExt};
+use libra_types::{crypto_proxies::Signature, transaction::Version};
+use serde::{Deserialize, Serialize};
+use std::sync::{Arc, Mutex};
+
+/// VoteProposal carries an executed proposal: the block together with the state it has been
+/// executed to. This is all SafetyRules needs to follow the voting rules and construct a vote.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct VoteProposal<T> {
+    block: Block<T>,
+    /// The accumulator root hash after executing the block.
+    executed_state_id: HashValue,
+    /// The version of the latest transaction after executing the block.
+    version: Version,
+}
+
+impl<T> VoteProposal<T> {
+    pub fn new(block: Block<T>, executed_state_id: HashValue, version: Version) -> Self {
+        Self {
+            block,
+            executed_state_id,
+            version,
+        }
+    }
+
+    pub fn block(&self) -> &Block<T> {
+        &self.block
+    }
+
+    pub fn executed_state_id(&self) -> HashValue {
+        self.executed_state_id
+    }
+
+    pub fn version(&self) -> Version {
+        self.version
+    }
+}
+
+/// BlockProposal carries the content of a new proposal generated by this validator: SafetyRules
+/// signs it into a block if the proposal does not violate the rules.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct BlockProposal<T> {
+    payload: T,
+    /// The epoch of the parent block.
+    epoch: u64,
+    round: Round,
+    timestamp_usecs: u64,
+    /// The QC of the parent block.
+    quorum_cert: QuorumCert,
+}
+
+impl<T> BlockProposal<T> {
+    pub fn new(
+        payload: T,
+        epoch: u64,
+        round: Round,
+        timestamp_usecs: u64,
+        quorum_cert: QuorumCert,
+    ) -> Self {
+        Self {
+            payload,
+            epoch,
+            round,
+            timestamp_usecs,
+            quorum_cert,
+        }
+    }
+
+    pub fn payload(&self) -> &T {
+        &self.payload
+    }
+
+    pub fn epoch(&self) -> u64 {
+        self.epoch
+    }
+
+    pub fn round(&self) -> Round {
+        self.round
+    }
+
+    pub fn timestamp_usecs(&self) -> u64 {
+        self.timestamp_usecs
+    }
+
+    pub fn quorum_cert(&self) -> &QuorumCert {
+        &self.quorum_cert
+    }
+}
+
+/// Interface for SafetyRules: it can either run in the consensus process (`SafetyRules`) or in a
+/// separate process holding the consensus signing key (`RemoteSafetyRules`).
+/// All the signatures of a validator are produced through this interface. The requests return
+/// futures, so that waiting for a remote SafetyRules process does not block the consensus
+/// runtime: the local instances complete them right away.
+pub trait TSafetyRules<T>: Send + Sync {
+    /// Returns the up-to-date state of consensus (for monitoring / debugging purposes).
+    fn consensus_state(&self) -> BoxFuture<'static, Result<ConsensusState>>;
+
+    /// Learns about a new quorum certificate, potentially updating the preferred block round.
+    fn update(&mut self, qc: &QuorumCert) -> BoxFuture<'static, Result<()>>;
+
+    /// Follows the voting rules and returns a signed vote for the executed proposal.
+    fn construct_and_sign_vote(
+        &mut self,
+        vote_proposal: &VoteProposal<T>,
+        sync_info: SyncInfo,
+    ) -> BoxFuture<'static, Result<VoteMsg>>;
+
+    /// Returns a signature of the round that can be aggregated into a timeout certificate.
+    fn sign_timeout(&mut self, round: Round) -> BoxFuture<'static, Result<Signature>>;
+
+    /// Returns the block signed for the given proposal.
+    fn sign_proposal(
+        &mut self,
+        proposal: &BlockProposal<T>,
+    ) -> BoxFuture<'static, Result<Block<T>>>;
+
+    /// Returns the signed info of a batch stored by this validator.
+    fn sign_batch_info(&self, info: BatchInfo) -> BoxFuture<'static, Result<SignedBatchInfo>>;
+}
+
+impl<T: Payload> TSafetyRules<T> for SafetyRules {
+    fn consensus_state(&self) -> BoxFuture<'static, Result<ConsensusState>> {
+        future::ok(SafetyRules::consensus_state(self)).boxed()
+    }
+
+    fn update(&mut self, qc: &QuorumCert) -> BoxFuture<'static, Result<()>> {
+        future::ready(SafetyRules::update(self, qc)).boxed()
+    }
+
+    fn construct_and_sign_vote(
+        &mut self,
+        vote_proposal: &VoteProposal<T>,
+        sync_info: SyncInfo,
+    ) -> BoxFuture<'static, Result<VoteMsg>> {
+        future::ready(SafetyRules::construct_and_sign_vote(
+            self,
+            vote_proposal,
+            sync_info,
+        ))
+        .boxed()
+    }
+
+    fn sign_timeout(&mut self, round: Round) -> BoxFuture<'static, Result<Signature>> {
+        future::ready(SafetyRules::sign_timeout(self, round)).boxed()
+    }
+
+    fn sign_proposal(
+        &mut self,
+        proposal: &BlockProposal<T>,
+    ) -> BoxFuture<'static, Result<Block<T>>> {
+        future::ready(SafetyRules::sign_proposal(self, proposal)).boxed()
+    }
+
+    fn sign_batch_info(&self, info: BatchInfo) -> BoxFuture<'static, Result<SignedBatchInfo>> {
+        future::ready(SafetyRules::sign_batch_info(self, info)).boxed()
+    }
+}
+
+/// A SafetyRules instance shared by the components of the consensus process, e.g. by the event
+/// processor and the batch coordinator.
+impl<T: Payload> TSafetyRules<T> for Arc<Mutex<SafetyRules>> {
+    fn consensus_state(&self) -> BoxFuture<'static, Result<ConsensusState>> {
+        future::ok(self.lock().unwrap().consensus_state()).boxed()
+    }
+
+    fn update(&mut self, qc: &QuorumCert) -> BoxFuture<'static, Result<()>> {
+        future::ready(self.lock().unwrap().update(qc)).boxed()
+    }
+
+    fn construct_and_sign_vote(
+        &mut self,
+        vote_proposal: &VoteProposal<T>,
+        sync_info: SyncInfo,
+    ) -> BoxFuture<'static, Result<VoteMsg>> {
+        future::ready(
+            self.lock()
+                .unwrap()
+                .construct_and_sign_vote(vote_proposal, sync_info),
+        )
+        .boxed()
+    }
+
+    fn sign_timeout(&mut self, round: Round) -> BoxFuture<'static, Result<Signature>> {
+        future::ready(self.lock().unwrap().sign_timeout(round)).boxed()
+    }
+
+    fn sign_proposal(
+        &mut self,
+        proposal: &BlockProposal<T>,
+    ) -> BoxFuture<'static, Result<Block<T>>> {
+        future::ready(self.lock().unwrap().sign_proposal(proposal)).boxed()
+    }
+
+    fn sign_batch_info(&self, info: BatchInfo) -> BoxFuture<'static, Result<SignedBatchInfo>> {
+        future::ready(self.lock().unwrap().sign_batch_info(info)).boxed()
+    }
+}
@@ -0,0 +1,297 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{
+    chained_bft::{
+        batching::{encode_batch, BatchFetchRequest, BatchStore},
+        network::{BatchNetworkReceivers, ConsensusNetworkImpl},
+    },
+    counters,
+    state_replication::TxnManager,
+};
+use channel;
+use config::config::BatchingConfig;
+use consensus_types::{
+    batch::{Batch, BatchInfo, ProofOfStore, SignedBatchInfo},
+    common::Author,
+};
+use crypto::HashValue;
+use failure::prelude::*;
+use futures::{select, StreamExt};
+use libra_logger::prelude::*;
+use libra_types::{
+    crypto_proxies::{Signature, ValidatorVerifier},
+    transaction::SignedTransaction,
+};
+use safety_rules::TSafetyRules;
+use std::{
+    collections::{BTreeMap, HashMap},
+    sync::Arc,
+    time::{Duration, Instant},
+};
+use tokio::{runtime::TaskExecutor, timer::Interval};
+
+/// Max number of own batches waiting for their commit: no new batch is pulled from mempool
+/// beyond this limit, which provides back pressure in case consensus is slower than the
+/// dissemination.
+const MAX_PENDING_BATCHES: usize = 100;
+
+/// A batch that does not gather a quorum of signatures within this time is dropped, so that its
+/// transactions can be pulled into a new batch.
+const CERTIFICATION_TIMEOUT: Duration = Duration::from_secs(10);
+
+// Own batch waiting for its commit.
+struct PendingBatch {
+    txns: Vec<SignedTransaction>,
+    info: BatchInfo,
+    signatures: BTreeMap<Author, Signature>,
+    certified: bool,
+    created: Instant,
+}
+
+/// BatchCoordinator runs the dissemination of the batches: it pulls the batches of this
+/// validator from mempool and aggregates their signatures into proofs of store, it stores and
+/// signs the batches of the other validators, and serves the batch retrieval requests.
+pub struct BatchCoordinator {
+    txn_manager: Arc<dyn TxnManager<Payload = Vec<SignedTransaction>>>,
+    batch_store: Arc<BatchStore>,
+    fetch_requests: channel::Receiver<BatchFetchRequest>,
+    max_batch_size: u64,
+    batch_interval: Duration,
+    fetch_timeout: Duration,
+}
+
+impl BatchCoordinator {
+    pub fn new(
+        txn_manager: Arc<dyn TxnManager<Payload = Vec<SignedTransaction>>>,
+        batch_store: Arc<BatchStore>,
+        fetch_requests: channel::Receiver<BatchFetchRequest>,
+        config: &BatchingConfig,
+    ) -> Self {
+        Self {
+            txn_manager,
+            batch_store,
+            fetch_requests,
+            max_batch_size: config.max_batch_size,
+            batch_interval: Duration::from_millis(config.batch_interval_ms),
+            fetch_timeout: Duration::from_millis(config.fetch_timeout_ms),
+        }
+    }
+
+    /// Spawns the dissemination task processing the batch messages of the given receivers.
+    pub fn start(
+        self,
+        executor: TaskExecutor,
+        network: ConsensusNetworkImpl,
+        author: Author,
+        epoch: u64,
+        safety_rules: Box<dyn TSafetyRules<Vec<ProofOfStore>>>,
+        validators: Arc<ValidatorVerifier>,
+        receivers: BatchNetworkReceivers,
+    ) {
+        let task = BatchCoordinatorTask {
+            txn_manager: self.txn_manager,
+            batch_store: self.batch_store,
+            max_batch_size: self.max_batch_size,
+            fetch_timeout: self.fetch_timeout,
+            executor: executor.clone(),
+            network,
+            author,
+            epoch,
+            safety_rules,
+            validators,
+            pending: HashMap::new(),
+        };
+        executor.spawn(task.run(self.fetch_requests, receivers, self.batch_interval));
+    }
+}
+
+struct BatchCoordinatorTask {
+    txn_manager: Arc<dyn TxnManager<Payload = Vec<SignedTransaction>>>,
+    batch_store: Arc<BatchStore>,
+    max_batch_size: u64,
+    fetch_timeout: Duration,
+    executor: TaskExecutor,
+    network: ConsensusNetworkImpl,
+    author: Author,
+    // The epoch of the own batches: SafetyRules only signs the batches of its epoch.
+    epoch: u64,
+    // The batch infos are signed by SafetyRules, which holds the signing key.
+    safety_rules: Box<dyn TSafetyRules<Vec<ProofOfStore>>>,
+    validators: Arc<ValidatorVerifier>,
+    pending: HashMap<HashValue, PendingBatch>,
+}
+
+impl BatchCoordinatorTask {
+    async fn run(
+        mut self,
+        mut fetch_requests: channel::Receiver<BatchFetchRequest>,
+        mut receivers: BatchNetworkReceivers,
+        batch_interval: Duration,
+    ) {
+        let mut interval = Interval::new_interval(batch_interval).fuse();
+        loop {
+            select! {
+                _ = interval.select_next_some() => {
+                    self.create_batch().await;
+                }
+                batch = receivers.batches.select_next_some() => {
+                    self.process_batch(batch).await;
+                }
+                signed_info = receivers.signed_batch_infos.select_next_some() => {
+                    self.process_signed_batch_info(signed_info).await;
+                }
+                proof = receivers.proofs_of_store.select_next_some() => {
+                    self.batch_store.insert_proof(proof);
+                }
+                request = receivers.batch_retrieval.select_next_some() => {
+                    if request
+                        .response_sender
+                        .send(self.batch_store.get_batch(&request.digest))
+                        .is_err()
+                    {
+                        error!("Failed to return the requested batch");
+                    }
+                }
+                request = fetch_requests.select_next_some() => {
+                    self.fetch_batch(request);
+                }
+                complete => {
+                    break;
+                }
+            }
+        }
+    }
+
+    /// Pulls a new batch from mempool and broadcasts it to the validators.
+    async fn create_batch(&mut self) {
+        let batch_store = Arc::clone(&self.batch_store);
+        self.pending.retain(|digest, pending| {
+            !batch_store.is_committed(digest)
+                && (pending.certified || pending.created.elapsed() < CERTIFICATION_TIMEOUT)
+        });
+        if self.pending.len() >= MAX_PENDING_BATCHES {
+            return;
+        }
+        let exclude_txns = self.pending.values().map(|pending| &pending.txns).collect();
+        let txns = match self
+            .txn_manager
+            .pull_txns(self.max_batch_size, exclude_txns)
+            .await
+        {
+            Ok(txns) => txns,
+            Err(e) => {
+                error!("Failed to pull a batch from mempool: {:?}", e);
+                return;
+            }
+        };
+        if txns.is_empty() {
+            return;
+        }
+        let batch = encode_batch(self.author, self.epoch, &txns);
+        debug!("Created {}", batch);
+        counters::BATCHES_CREATED_COUNT.inc();
+        self.pending.insert(
+            batch.digest(),
+            PendingBatch {
+                txns,
+                info: batch.info(),
+                signatures: BTreeMap::new(),
+                certified: false,
+                created: Instant::now(),
+            },
+        );
+        // The batch is delivered to self as well, hence it is stored and signed the same way as
+        // the batches of the other validators.
+        self.network.broadcast_batch(batch).await;
+    }
+
+    /// Stores the batch and sends the signature of its info back to its author. The batches
+    /// of another epoch and the ones rejected by the store (over the quota of their author or
+    /// the store capacity) are not signed: a signature is a promise to serve the batch.
+    async fn process_batch(&mut self, batch: Batch) {
+        let author = batch.author();
+        let info = batch.info();
+        if info.epoch() != self.epoch {
+            warn!("Not signing {}, the current epoch is {}", info, self.epoch);
+            return;
+        }
+        if let Err(e) = self.batch_store.insert_batch(batch) {
+            warn!("Not signing the batch: {:?}", e);
+            return;
+        }
+        let signed_info = match self.safety_rules.sign_batch_info(info.clone()).await {
+            Ok(signed_info) => signed_info,
+            Err(e) => {
+                error!("Failed to sign {}: {:?}", info, e);
+                return;
+            }
+        };
+        self.network
+            .send_signed_batch_info(signed_info, author)
+            .await;
+    }
+
+    /// Aggregates the signatures of an own batch: the proof of store is broadcast as soon as
+    /// they form a quorum.
+    async fn process_signed_batch_info(&mut self, signed_info: SignedBatchInfo) {
+        let pending = match self.pending.get_mut(&signed_info.info().digest()) {
+            Some(pending) if pending.info == *signed_info.info() && !pending.certified => pending,
+            _ => return,
+        };
+        pending
+            .signatures
+            .insert(signed_info.signer(), signed_info.signature().clone());
+        if self
+            .validators
+            .check_voting_power(pending.signatures.keys())
+            .is_err()
+        {
+            return;
+        }
+        pending.certified = true;
+        let proof = ProofOfStore::new(pending.info.clone(), pending.signatures.clone());
+        debug!("Certified {}", proof);
+        counters::BATCHES_CERTIFIED_COUNT.inc();
+        self.network.broadcast_proof_of_store(proof).await;
+    }
+
+    /// Spawns the retrieval of a missing batch from the validators that signed its proof.
+    fn fetch_batch(&self, request: BatchFetchRequest) {
+        let mut network = self.network.clone();
+        let batch_store = Arc::clone(&self.batch_store);
+        let author = self.author;
+        let timeout = self.fetch_timeout;
+        self.executor.spawn(async move {
+            let BatchFetchRequest {
+                proof,
+                response_sender,
+            } = request;
+            let pre_fetch_instant = Instant::now();
+            let digest = proof.digest();
+            for peer in proof.signers().filter(|peer| **peer != author) {
+                match network.request_batch(digest, *peer, timeout).await {
+                    Ok(batch) => {
+                        counters::BATCHES_FETCHED_COUNT.inc();
+                        counters::BATCH_FETCH_DURATION_S
+                            .observe_duration(pre_fetch_instant.elapsed());
+                        // The fetched batch is certified, hence it is returned for execution
+                        // even if the store does not keep it.
+                        if let Err(e) = batch_store.insert_batch(batch.clone()) {
+                            debug!("Fetched batch is not stored: {:?}", e);
+                        }
+                        if response_sender.send(Ok(batch)).is_err() {
+                            error!("Failed to return the fetched batch {}", digest);
+                        }
+                        return;
+                    }
+                    Err(e) => warn!("Failed to fetch batch {} from {}: {:?}", digest, peer, e),
+                }
+            }
+            let _ = response_sender.send(Err(format_err!(
+                "Failed to fetch batch {} from any of its signers",
+                digest
+            )));
+        });
+    }
+}
@@ -0,0 +1,118 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{
+    chained_bft::batching::{resolve_txns, BatchStore},
+    state_computer::ExecutionProxy,
+    state_replication::StateComputer,
+};
+use consensus_types::{batch::ProofOfStore, block::Block, quorum_cert::QuorumCert};
+use executor::{ExecutedTrees, ProcessedVMOutput, StateComputeResult};
+use failure::prelude::*;
+use futures::{future, Future, FutureExt};
+use libra_types::crypto_proxies::{LedgerInfoWithSignatures, ValidatorVerifier};
+use std::{pin::Pin, sync::Arc};
+
+/// BatchStateComputer resolves the certified batches of a block into their transactions before
+/// executing them with the ExecutionProxy.
+pub struct BatchStateComputer {
+    execution_proxy: Arc<ExecutionProxy>,
+    batch_store: Arc<BatchStore>,
+    validators: Arc<ValidatorVerifier>,
+    max_block_size: u64,
+}
+
+impl BatchStateComputer {
+    pub fn new(
+        execution_proxy: Arc<ExecutionProxy>,
+        batch_store: Arc<BatchStore>,
+        validators: Arc<ValidatorVerifier>,
+        max_block_size: u64,
+    ) -> Self {
+        Self {
+            execution_proxy,
+            batch_store,
+            validators,
+            max_block_size,
+        }
+    }
+}
+
+impl StateComputer for BatchStateComputer {
+    type Payload = Vec<ProofOfStore>;
+
+    /// The proofs of store are verified before the batches are resolved: a block carrying an
+    /// invalid proof, or more transactions than the maximum block size, fails to execute and is
+    /// therefore never voted for.
+    fn compute(
+        &self,
+        // The block to be executed.
+        block: &Block<Self::Payload>,
+        // The executed trees after executing the parent block.
+        parent_executed_trees: ExecutedTrees,
+    ) -> Pin<Box<dyn Future<Output = Result<(ProcessedVMOutput, StateComputeResult)>> + Send>> {
+        let proofs = block.payload().cloned().unwrap_or_default();
+        let num_txns: u64 = proofs.iter().map(|proof| proof.info().num_txns()).sum();
+        if proofs.len() as u64 > self.max_block_size || num_txns > self.max_block_size {
+            return future::err(format_err!(
+                "Block {} has {} proofs of store with {} transactions, above the block size {}",
+                block.id(),
+                proofs.len(),
+                num_txns,
+                self.max_block_size
+            ))
+            .boxed();
+        }
+        for proof in &proofs {
+            if let Err(e) = proof.verify(&self.validators) {
+                return future::err(e).boxed();
+            }
+        }
+        let txns_future = resolve_txns(&self.batch_store, &proofs);
+        let execution_proxy = Arc::clone(&self.execution_proxy);
+        let (parent_id, block_id) = (block.parent_id(), block.id());
+        async move {
+            let txns = txns_future.await?;
+            execution_proxy
+                .execute_transactions(txns, parent_executed_trees, parent_id, block_id)
+                .await
+        }
+        .boxed()
+    }
+
+    /// Send a successful commit. A future is fulfilled when the state is finalized.
+    /// The batches that are no longer stored locally are fetched again before committing.
+    fn commit(
+        &self,
+        payload_and_output_list: Vec<(Self::Payload, Arc<ProcessedVMOutput>)>,
+        finality_proof: LedgerInfoWithSignatures,
+    ) -> Pin<Box<dyn Future<Output = Result<()>> + Send>> {
+        let txns_futures: Vec<_> = payload_and_output_list
+            .iter()
+            .map(|(proofs, _)| resolve_txns(&self.batch_store, proofs))
+            .collect();
+        let execution_proxy = Arc::clone(&self.execution_proxy);
+        async move {
+            let mut txns_and_output_list = vec![];
+            for ((_, output), txns) in payload_and_output_list
+                .into_iter()
+                .zip(future::join_all(txns_futures).await)
+            {
+                txns_and_output_list.push((txns?, output));
+            }
+            execution_proxy
+                .commit_transactions(txns_and_output_list, finality_proof)
+                .await
+        }
+        .boxed()
+    }
+
+    /// Synchronize to a commit that not present locally.
+    fn sync_to(&self, commit: QuorumCert) -> Pin<Box<dyn Future<Output = Result<bool>> + Send>> {
+        self.execution_proxy.sync_to(commit)
+    }
+
+    fn committed_trees(&self) -> ExecutedTrees {
+        self.execution_proxy.committed_trees()
+    }
+}
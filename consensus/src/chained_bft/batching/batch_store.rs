@@ -0,0 +1,269 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{chained_bft::batching::decode_batch, counters};
+use channel;
+use config::config::BatchingConfig;
+use consensus_types::{
+    batch::{Batch, ProofOfStore},
+    common::Author,
+};
+use crypto::{hash::CryptoHash, HashValue};
+use failure::prelude::*;
+use futures::{channel::oneshot, future, Future, FutureExt, SinkExt};
+use std::{
+    collections::{HashMap, HashSet, VecDeque},
+    pin::Pin,
+    sync::Mutex,
+};
+
+#[cfg(test)]
+#[path = "batch_store_test.rs"]
+mod batch_store_test;
+
+/// The number of committed batch digests remembered in order to ignore the late messages about
+/// these batches.
+const MAX_COMMITTED_DIGESTS: usize = 10_000;
+
+/// BatchFetchRequest asks the BatchCoordinator to retrieve a missing batch from one of the
+/// validators that signed its proof of store.
+pub struct BatchFetchRequest {
+    pub proof: ProofOfStore,
+    pub response_sender: oneshot::Sender<Result<Batch>>,
+}
+
+/// BatchStore keeps the batches received from the validators until they are committed, as well
+/// as the queue of the certified batches that can be included in the next proposals.
+/// It is shared by the BatchCoordinator (filling the store) with the BatchTxnManager and the
+/// BatchStateComputer (resolving the proposal payloads).
+/// The store is bounded: every author has a quota of uncommitted batches, the total size of the
+/// stored batches is capped, and the batches (as well as the proofs) that are not committed
+/// within the configured number of commits are dropped.
+pub struct BatchStore {
+    inner: Mutex<BatchStoreInner>,
+    fetch_sender: channel::Sender<BatchFetchRequest>,
+    max_batches_per_author: usize,
+    max_store_bytes: usize,
+    expiration_commits: u64,
+}
+
+/// An entry of the store, with the number of commits observed when it was inserted.
+struct Stored<T> {
+    item: T,
+    inserted_at: u64,
+}
+
+#[derive(Default)]
+struct BatchStoreInner {
+    batches: HashMap<HashValue, Stored<Batch>>,
+    batches_per_author: HashMap<Author, usize>,
+    total_bytes: usize,
+    // Certified batches that are not committed yet, in the order of certification.
+    proofs: VecDeque<Stored<ProofOfStore>>,
+    committed: HashSet<HashValue>,
+    committed_order: VecDeque<HashValue>,
+    // Number of commits observed so far, used for expiring the stale batches.
+    num_commits: u64,
+}
+
+impl BatchStoreInner {
+    fn remove_batch(&mut self, digest: &HashValue) {
+        if let Some(stored) = self.batches.remove(digest) {
+            self.total_bytes -= stored.item.data().len();
+            let author = stored.item.author();
+            if let Some(count) = self.batches_per_author.get_mut(&author) {
+                *count -= 1;
+                if *count == 0 {
+                    self.batches_per_author.remove(&author);
+                }
+            }
+        }
+    }
+
+    fn update_counters(&self) {
+        counters::NUM_BATCHES_IN_STORE.set(self.batches.len() as i64);
+        counters::NUM_PENDING_PROOFS_OF_STORE.set(self.proofs.len() as i64);
+    }
+}
+
+impl BatchStore {
+    pub fn new(fetch_sender: channel::Sender<BatchFetchRequest>, config: &BatchingConfig) -> Self {
+        Self {
+            inner: Mutex::new(BatchStoreInner::default()),
+            fetch_sender,
+            max_batches_per_author: config.max_batches_per_author,
+            max_store_bytes: config.max_store_bytes,
+            expiration_commits: config.batch_expiration_commits,
+        }
+    }
+
+    /// Stores the given batch. Fails if the batch has already been committed, if its author has
+    /// exhausted the quota or if the store is full: the batches that are not stored must not be
+    /// signed.
+    pub fn insert_batch(&self, batch: Batch) -> Result<()> {
+        let mut inner = self.inner.lock().unwrap();
+        let digest = batch.digest();
+        ensure!(
+            !inner.committed.contains(&digest),
+            "{} is already committed",
+            batch
+        );
+        if inner.batches.contains_key(&digest) {
+            return Ok(());
+        }
+        let author = batch.author();
+        let num_author_batches = inner.batches_per_author.get(&author).cloned().unwrap_or(0);
+        ensure!(
+            num_author_batches < self.max_batches_per_author,
+            "{} exceeds the quota of {} uncommitted batches per author",
+            batch,
+            self.max_batches_per_author
+        );
+        ensure!(
+            inner.total_bytes + batch.data().len() <= self.max_store_bytes,
+            "{} exceeds the store capacity of {} bytes",
+            batch,
+            self.max_store_bytes
+        );
+        inner.total_bytes += batch.data().len();
+        *inner.batches_per_author.entry(author).or_insert(0) += 1;
+        let inserted_at = inner.num_commits;
+        inner.batches.insert(
+            digest,
+            Stored {
+                item: batch,
+                inserted_at,
+            },
+        );
+        inner.update_counters();
+        Ok(())
+    }
+
+    pub fn get_batch(&self, digest: &HashValue) -> Option<Batch> {
+        self.inner
+            .lock()
+            .unwrap()
+            .batches
+            .get(digest)
+            .map(|stored| stored.item.clone())
+    }
+
+    /// Adds a certified batch to the queue of the batches to propose.
+    pub fn insert_proof(&self, proof: ProofOfStore) {
+        let mut inner = self.inner.lock().unwrap();
+        let digest = proof.digest();
+        if inner.committed.contains(&digest)
+            || inner.proofs.iter().any(|p| p.item.digest() == digest)
+        {
+            return;
+        }
+        let inserted_at = inner.num_commits;
+        inner.proofs.push_back(Stored {
+            item: proof,
+            inserted_at,
+        });
+        inner.update_counters();
+    }
+
+    /// Returns the oldest certified batches that are not excluded, up to the given total number
+    /// of transactions.
+    /// The same transaction may be carried by the batches of several validators: a stored batch
+    /// whose transactions are all carried by the batches pulled before is skipped.
+    pub fn pull_proofs(&self, max_txns: u64, exclude: &HashSet<HashValue>) -> Vec<ProofOfStore> {
+        let inner = self.inner.lock().unwrap();
+        let mut num_txns = 0;
+        let mut proofs = vec![];
+        let mut txn_hashes = HashSet::new();
+        for proof in inner.proofs.iter().map(|stored| &stored.item) {
+            if exclude.contains(&proof.digest()) {
+                continue;
+            }
+            if num_txns + proof.info().num_txns() > max_txns {
+                break;
+            }
+            let batch_txn_hashes: Vec<_> = inner
+                .batches
+                .get(&proof.digest())
+                .and_then(|stored| decode_batch(proof, &stored.item).ok())
+                .map(|txns| txns.iter().map(CryptoHash::hash).collect())
+                .unwrap_or_default();
+            if !batch_txn_hashes.is_empty()
+                && batch_txn_hashes
+                    .iter()
+                    .all(|hash| txn_hashes.contains(hash))
+            {
+                continue;
+            }
+            txn_hashes.extend(batch_txn_hashes);
+            num_txns += proof.info().num_txns();
+            proofs.push(proof.clone());
+        }
+        proofs
+    }
+
+    /// Returns the batch certified by the given proof: in case it is not stored locally, it is
+    /// fetched from the validators that signed the proof.
+    pub fn get_or_fetch(
+        &self,
+        proof: &ProofOfStore,
+    ) -> Pin<Box<dyn Future<Output = Result<Batch>> + Send>> {
+        if let Some(batch) = self.get_batch(&proof.digest()) {
+            return future::ok(batch).boxed();
+        }
+        let (response_sender, response_receiver) = oneshot::channel();
+        let request = BatchFetchRequest {
+            proof: proof.clone(),
+            response_sender,
+        };
+        let mut fetch_sender = self.fetch_sender.clone();
+        async move {
+            fetch_sender
+                .send(request)
+                .await
+                .map_err(|e| format_err!("Failed to request a batch fetch: {:?}", e))?;
+            response_receiver.await?
+        }
+        .boxed()
+    }
+
+    /// Removes the committed batches: their transactions are persisted by the executor.
+    /// The uncommitted batches and proofs that outlived the expiration are garbage collected.
+    pub fn commit(&self, digests: &[HashValue]) {
+        let mut inner = self.inner.lock().unwrap();
+        inner.num_commits += 1;
+        for digest in digests {
+            inner.remove_batch(digest);
+            if inner.committed.insert(*digest) {
+                inner.committed_order.push_back(*digest);
+            }
+        }
+        while inner.committed_order.len() > MAX_COMMITTED_DIGESTS {
+            if let Some(digest) = inner.committed_order.pop_front() {
+                inner.committed.remove(&digest);
+            }
+        }
+        let num_commits = inner.num_commits;
+        let expiration_commits = self.expiration_commits;
+        let is_expired = |inserted_at: u64| num_commits - inserted_at > expiration_commits;
+        let expired: Vec<_> = inner
+            .batches
+            .iter()
+            .filter(|(_, stored)| is_expired(stored.inserted_at))
+            .map(|(digest, _)| *digest)
+            .collect();
+        for digest in &expired {
+            inner.remove_batch(digest);
+        }
+        let BatchStoreInner {
+            proofs, committed, ..
+        } = &mut *inner;
+        proofs.retain(|stored| {
+            !committed.contains(&stored.item.digest()) && !is_expired(stored.inserted_at)
+        });
+        inner.update_counters();
+    }
+
+    pub fn is_committed(&self, digest: &HashValue) -> bool {
+        self.inner.lock().unwrap().committed.contains(digest)
+    }
+}
@@ -0,0 +1,261 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::chained_bft::batching::{encode_batch, resolve_txns, BatchFetchRequest, BatchStore};
+use channel;
+use config::config::BatchingConfig;
+use consensus_types::batch::{Batch, ProofOfStore, SignedBatchInfo};
+use crypto::ed25519::compat::generate_keypair;
+use futures::{executor::block_on, future::join, StreamExt};
+use libra_types::{
+    crypto_proxies::{random_validator_verifier, ValidatorSigner},
+    test_helpers::transaction_test_helpers::get_test_signed_txn,
+    transaction::SignedTransaction,
+};
+use std::collections::HashSet;
+
+fn make_batch(signer: &ValidatorSigner, num_txns: u64, seed: u8) -> Batch {
+    Batch::new(signer.author(), 1, num_txns, vec![seed; 8])
+}
+
+fn make_txns(signer: &ValidatorSigner, first_sequence_number: u64) -> Vec<SignedTransaction> {
+    let (private_key, public_key) = generate_keypair(None);
+    (first_sequence_number..first_sequence_number + 2)
+        .map(|sequence_number| {
+            get_test_signed_txn(
+                signer.author(),
+                sequence_number,
+                private_key.clone(),
+                public_key.clone(),
+                None,
+            )
+        })
+        .collect()
+}
+
+fn make_proof(batch: &Batch, signers: &[ValidatorSigner]) -> ProofOfStore {
+    let signatures = signers
+        .iter()
+        .map(|signer| {
+            let signed_info = SignedBatchInfo::new(batch.info(), signer);
+            (signed_info.signer(), signed_info.signature().clone())
+        })
+        .collect();
+    ProofOfStore::new(batch.info(), signatures)
+}
+
+#[test]
+fn test_pull_proofs() {
+    let (signers, _) = random_validator_verifier(4, None, false);
+    let (fetch_sender, _fetch_receiver) = channel::new_test(8);
+    let store = BatchStore::new(fetch_sender, &BatchingConfig::default());
+    let batches: Vec<_> = (0..3)
+        .map(|i| make_batch(&signers[0], 10, i as u8))
+        .collect();
+    let proofs: Vec<_> = batches
+        .iter()
+        .map(|batch| make_proof(batch, &signers[..3]))
+        .collect();
+    for proof in &proofs {
+        store.insert_proof(proof.clone());
+    }
+    // Duplicate proofs are ignored.
+    store.insert_proof(proofs[0].clone());
+
+    assert_eq!(store.pull_proofs(100, &HashSet::new()), proofs);
+    assert_eq!(store.pull_proofs(25, &HashSet::new()), proofs[..2].to_vec());
+    let exclude = vec![proofs[0].digest()].into_iter().collect();
+    assert_eq!(store.pull_proofs(100, &exclude), proofs[1..].to_vec());
+
+    // The committed batches are never proposed again.
+    store.commit(&[proofs[1].digest()]);
+    store.insert_proof(proofs[1].clone());
+    assert_eq!(
+        store.pull_proofs(100, &HashSet::new()),
+        vec![proofs[0].clone(), proofs[2].clone()]
+    );
+}
+
+#[test]
+fn test_commit_removes_batches() {
+    let (signers, _) = random_validator_verifier(4, None, false);
+    let (fetch_sender, _fetch_receiver) = channel::new_test(8);
+    let store = BatchStore::new(fetch_sender, &BatchingConfig::default());
+    let batch = make_batch(&signers[1], 1, 0);
+    store.insert_batch(batch.clone()).unwrap();
+    assert_eq!(store.get_batch(&batch.digest()), Some(batch.clone()));
+
+    store.commit(&[batch.digest()]);
+    assert!(store.is_committed(&batch.digest()));
+    assert_eq!(store.get_batch(&batch.digest()), None);
+    // A late copy of a committed batch is not stored.
+    assert!(store.insert_batch(batch.clone()).is_err());
+    assert_eq!(store.get_batch(&batch.digest()), None);
+}
+
+#[test]
+fn test_get_or_fetch() {
+    let (signers, _) = random_validator_verifier(4, None, false);
+    let (fetch_sender, mut fetch_receiver) = channel::new_test::<BatchFetchRequest>(8);
+    let store = BatchStore::new(fetch_sender, &BatchingConfig::default());
+    let stored = make_batch(&signers[0], 1, 0);
+    let missing = make_batch(&signers[1], 1, 1);
+    store.insert_batch(stored.clone()).unwrap();
+
+    // A stored batch is returned right away.
+    let stored_proof = make_proof(&stored, &signers[..3]);
+    assert_eq!(block_on(store.get_or_fetch(&stored_proof)).unwrap(), stored);
+
+    // A missing batch is requested from the coordinator.
+    let missing_proof = make_proof(&missing, &signers[..3]);
+    let missing_clone = missing.clone();
+    let serve_fetch = async move {
+        let request = fetch_receiver.next().await.unwrap();
+        assert_eq!(request.proof.digest(), missing_clone.digest());
+        request.response_sender.send(Ok(missing_clone)).unwrap();
+    };
+    let (fetched, _) = block_on(join(store.get_or_fetch(&missing_proof), serve_fetch));
+    assert_eq!(fetched.unwrap(), missing);
+}
+
+#[test]
+fn test_insert_batch_limits() {
+    let (signers, _) = random_validator_verifier(4, None, false);
+    let (fetch_sender, _fetch_receiver) = channel::new_test(8);
+    let config = BatchingConfig {
+        max_batches_per_author: 2,
+        max_store_bytes: 32,
+        ..BatchingConfig::default()
+    };
+    let store = BatchStore::new(fetch_sender, &config);
+    let first = make_batch(&signers[0], 1, 0);
+    store.insert_batch(first.clone()).unwrap();
+    store.insert_batch(make_batch(&signers[0], 1, 1)).unwrap();
+    // Inserting a stored batch again does not count against the quota.
+    store.insert_batch(first.clone()).unwrap();
+
+    // The quota is per author.
+    let over_quota = make_batch(&signers[0], 1, 2);
+    assert!(store.insert_batch(over_quota.clone()).is_err());
+    assert_eq!(store.get_batch(&over_quota.digest()), None);
+    store.insert_batch(make_batch(&signers[1], 1, 3)).unwrap();
+
+    // The total size is capped: 4 batches of 8 bytes fill the store.
+    store.insert_batch(make_batch(&signers[2], 1, 4)).unwrap();
+    assert!(store.insert_batch(make_batch(&signers[3], 1, 5)).is_err());
+
+    // Committing a batch releases both the quota of its author and the space.
+    store.commit(&[first.digest()]);
+    store.insert_batch(over_quota).unwrap();
+}
+
+#[test]
+fn test_commit_expires_stale_batches() {
+    let (signers, _) = random_validator_verifier(4, None, false);
+    let (fetch_sender, _fetch_receiver) = channel::new_test(8);
+    let config = BatchingConfig {
+        batch_expiration_commits: 2,
+        ..BatchingConfig::default()
+    };
+    let store = BatchStore::new(fetch_sender, &config);
+    let stale = make_batch(&signers[0], 1, 0);
+    store.insert_batch(stale.clone()).unwrap();
+    store.insert_proof(make_proof(&stale, &signers[..3]));
+    store.commit(&[]);
+    let fresh = make_batch(&signers[1], 1, 1);
+    let fresh_proof = make_proof(&fresh, &signers[..3]);
+    store.insert_batch(fresh.clone()).unwrap();
+    store.insert_proof(fresh_proof.clone());
+    store.commit(&[]);
+    assert_eq!(store.get_batch(&stale.digest()), Some(stale.clone()));
+
+    // The stale batch and its proof are dropped after 2 commits, the fresh ones are kept.
+    store.commit(&[]);
+    assert_eq!(store.get_batch(&stale.digest()), None);
+    assert_eq!(store.get_batch(&fresh.digest()), Some(fresh));
+    assert_eq!(store.pull_proofs(100, &HashSet::new()), vec![fresh_proof]);
+}
+
+#[test]
+fn test_resolve_rejected_batches() {
+    let (signers, _) = random_validator_verifier(4, None, false);
+    let (fetch_sender, mut fetch_receiver) = channel::new_test::<BatchFetchRequest>(8);
+    let config = BatchingConfig {
+        max_batches_per_author: 1,
+        batch_expiration_commits: 1,
+        ..BatchingConfig::default()
+    };
+    let store = BatchStore::new(fetch_sender, &config);
+    let stored_txns = make_txns(&signers[0], 0);
+    let stored = encode_batch(signers[0].author(), 1, &stored_txns);
+    let rejected_txns = make_txns(&signers[0], 2);
+    let rejected = encode_batch(signers[0].author(), 1, &rejected_txns);
+    store.insert_batch(stored.clone()).unwrap();
+    // The second batch of the author exceeds its quota, yet it can be certified by the others.
+    assert!(store.insert_batch(rejected.clone()).is_err());
+    let proofs = vec![
+        make_proof(&stored, &signers[..3]),
+        make_proof(&rejected, &signers[1..]),
+    ];
+
+    // The rejected batch is fetched when resolving the payload (e.g., upon commit).
+    let rejected_clone = rejected.clone();
+    let serve_fetch = async move {
+        let request = fetch_receiver.next().await.unwrap();
+        assert_eq!(request.proof.digest(), rejected_clone.digest());
+        request.response_sender.send(Ok(rejected_clone)).unwrap();
+        fetch_receiver
+    };
+    let (txns, mut fetch_receiver) = block_on(join(resolve_txns(&store, &proofs), serve_fetch));
+    let mut expected_txns = stored_txns.clone();
+    expected_txns.extend(rejected_txns);
+    assert_eq!(txns.unwrap(), expected_txns);
+
+    // An expired batch is fetched again as well.
+    store.commit(&[]);
+    store.commit(&[]);
+    assert_eq!(store.get_batch(&stored.digest()), None);
+    let serve_fetch = async move {
+        let request = fetch_receiver.next().await.unwrap();
+        assert_eq!(request.proof.digest(), stored.digest());
+        request.response_sender.send(Ok(stored)).unwrap();
+    };
+    let (txns, _) = block_on(join(resolve_txns(&store, &proofs[..1]), serve_fetch));
+    assert_eq!(txns.unwrap(), stored_txns);
+}
+
+#[test]
+fn test_duplicate_txns_across_batches() {
+    let (signers, _) = random_validator_verifier(4, None, false);
+    let (fetch_sender, _fetch_receiver) = channel::new_test(8);
+    let store = BatchStore::new(fetch_sender, &BatchingConfig::default());
+    // The mempools of the first two validators share the same transactions.
+    let txns = make_txns(&signers[0], 0);
+    let mut other_txns = txns[1..].to_vec();
+    other_txns.extend(make_txns(&signers[0], 2));
+    let batches = vec![
+        encode_batch(signers[0].author(), 1, &txns),
+        encode_batch(signers[1].author(), 1, &txns),
+        encode_batch(signers[2].author(), 1, &other_txns),
+    ];
+    let proofs: Vec<_> = batches
+        .iter()
+        .map(|batch| make_proof(batch, &signers[..3]))
+        .collect();
+    for (batch, proof) in batches.iter().zip(&proofs) {
+        store.insert_batch(batch.clone()).unwrap();
+        store.insert_proof(proof.clone());
+    }
+
+    // The batch carrying only transactions of the previous batches is not proposed.
+    assert_eq!(
+        store.pull_proofs(100, &HashSet::new()),
+        vec![proofs[0].clone(), proofs[2].clone()]
+    );
+
+    // A proposal carrying the same transaction twice executes it once.
+    let resolved = block_on(resolve_txns(&store, &proofs)).unwrap();
+    let mut expected_txns = txns;
+    expected_txns.extend(other_txns[1..].to_vec());
+    assert_eq!(resolved, expected_txns);
+}
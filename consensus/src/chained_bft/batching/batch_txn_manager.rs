@@ -0,0 +1,74 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{
+    chained_bft::batching::{resolve_txns, BatchStore},
+    state_replication::TxnManager,
+};
+use consensus_types::batch::ProofOfStore;
+use executor::StateComputeResult;
+use failure::prelude::*;
+use futures::{future, Future, FutureExt};
+use libra_types::transaction::SignedTransaction;
+use std::{collections::HashSet, pin::Pin, sync::Arc};
+
+/// BatchTxnManager proposes the certified batches instead of the transactions; the committed
+/// transactions are reported to the underlying TxnManager (i.e., mempool).
+pub struct BatchTxnManager {
+    txn_manager: Arc<dyn TxnManager<Payload = Vec<SignedTransaction>>>,
+    batch_store: Arc<BatchStore>,
+}
+
+impl BatchTxnManager {
+    pub fn new(
+        txn_manager: Arc<dyn TxnManager<Payload = Vec<SignedTransaction>>>,
+        batch_store: Arc<BatchStore>,
+    ) -> Self {
+        Self {
+            txn_manager,
+            batch_store,
+        }
+    }
+}
+
+impl TxnManager for BatchTxnManager {
+    type Payload = Vec<ProofOfStore>;
+
+    /// The returned future is fulfilled with the proofs of the oldest certified batches that are
+    /// not pending in the branch, up to `max_size` transactions.
+    fn pull_txns(
+        &self,
+        max_size: u64,
+        exclude_payloads: Vec<&Self::Payload>,
+    ) -> Pin<Box<dyn Future<Output = Result<Self::Payload>> + Send>> {
+        let exclude_digests: HashSet<_> = exclude_payloads
+            .into_iter()
+            .flatten()
+            .map(ProofOfStore::digest)
+            .collect();
+        future::ok(self.batch_store.pull_proofs(max_size, &exclude_digests)).boxed()
+    }
+
+    fn commit_txns<'a>(
+        &'a self,
+        proofs: &Self::Payload,
+        compute_result: &StateComputeResult,
+        // Monotonic timestamp_usecs of committed blocks is used to GC expired transactions.
+        timestamp_usecs: u64,
+    ) -> Pin<Box<dyn Future<Output = Result<()>> + Send + 'a>> {
+        // The batches of a committed block have been resolved upon its execution, but the store
+        // might have dropped them since: they are fetched again in that case.
+        let txns_future = resolve_txns(&self.batch_store, proofs);
+        let compute_result = compute_result.clone();
+        let digests: Vec<_> = proofs.iter().map(ProofOfStore::digest).collect();
+        async move {
+            let txns = txns_future.await?;
+            let commit_future =
+                self.txn_manager
+                    .commit_txns(&txns, &compute_result, timestamp_usecs);
+            self.batch_store.commit(&digests);
+            commit_future.await
+        }
+        .boxed()
+    }
+}
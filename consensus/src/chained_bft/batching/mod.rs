@@ -0,0 +1,86 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Batching decouples the dissemination of the transactions from the consensus protocol (enabled
+//! by `ConsensusConfig::batching`).
+//!
+//! Every validator periodically pulls a batch of transactions from its mempool and broadcasts it
+//! to all the validators, which store it and reply with their signature of the batch info.
+//! A quorum of signatures forms a `ProofOfStore`, which is broadcast as well. The proposals then
+//! carry the proofs of store instead of the transactions: the upload bandwidth of the leader no
+//! longer limits the throughput. Before executing a block, the batches are resolved from the
+//! local `BatchStore`, fetching the missing ones from the validators that signed their proofs.
+
+mod batch_coordinator;
+mod batch_state_computer;
+mod batch_store;
+mod batch_txn_manager;
+
+pub use batch_coordinator::BatchCoordinator;
+pub use batch_state_computer::BatchStateComputer;
+pub use batch_store::{BatchFetchRequest, BatchStore};
+pub use batch_txn_manager::BatchTxnManager;
+
+use consensus_types::batch::{Batch, ProofOfStore};
+use crypto::hash::CryptoHash;
+use failure::prelude::*;
+use futures::{future, Future};
+use libra_types::transaction::SignedTransaction;
+use std::collections::HashSet;
+
+/// Creates a batch of the given transactions.
+fn encode_batch(
+    author: consensus_types::common::Author,
+    epoch: u64,
+    txns: &[SignedTransaction],
+) -> Batch {
+    let data = lcs::to_bytes(&txns).expect("Failed to serialize transactions");
+    Batch::new(author, epoch, txns.len() as u64, data)
+}
+
+/// Returns the transactions of the given batch, checking they match its proof of store.
+fn decode_batch(proof: &ProofOfStore, batch: &Batch) -> Result<Vec<SignedTransaction>> {
+    ensure!(
+        batch.info() == *proof.info(),
+        "Batch {} does not match {}",
+        batch,
+        proof
+    );
+    let txns: Vec<SignedTransaction> = lcs::from_bytes(batch.data())?;
+    ensure!(
+        txns.len() as u64 == batch.num_txns(),
+        "Batch {} has {} transactions",
+        batch,
+        txns.len()
+    );
+    Ok(txns)
+}
+
+/// Returns the transactions of the batches certified by the given proofs. The batches that are
+/// not stored locally (e.g., rejected by the quota of their author or expired) are fetched from
+/// the validators that signed their proofs, so that a certified block can always be executed
+/// and committed.
+/// The mempools share their transactions, so the batches of different validators may carry the
+/// same transaction: only its first occurrence is kept.
+fn resolve_txns(
+    batch_store: &BatchStore,
+    proofs: &[ProofOfStore],
+) -> impl Future<Output = Result<Vec<SignedTransaction>>> + Send + 'static {
+    let proofs = proofs.to_vec();
+    let batch_futures: Vec<_> = proofs
+        .iter()
+        .map(|proof| batch_store.get_or_fetch(proof))
+        .collect();
+    async move {
+        let mut txns = vec![];
+        let mut txn_hashes = HashSet::new();
+        for (proof, batch) in proofs.iter().zip(future::join_all(batch_futures).await) {
+            for txn in decode_batch(proof, &batch?)? {
+                if txn_hashes.insert(txn.hash()) {
+                    txns.push(txn);
+                }
+            }
+        }
+        Ok(txns)
+    }
+}
@@ -10,7 +10,7 @@ use crate::{
 };
 use consensus_types::{
     block::{Block, ExecutedBlock},
-    common::{Payload, Round},
+    common::Payload,
     quorum_cert::QuorumCert,
     timeout_certificate::TimeoutCertificate,
     vote_msg::VoteMsg,
@@ -20,12 +20,9 @@ use executor::{ProcessedVMOutput, StateComputeResult};
 use failure::ResultExt;
 use libra_logger::prelude::*;
 
+use libra_types::crypto_proxies::ValidatorVerifier;
 #[cfg(any(test, feature = "fuzzing"))]
 use libra_types::validator_set::ValidatorSet;
-use libra_types::{
-    crypto_proxies::{ValidatorSigner, ValidatorVerifier},
-    ledger_info::LedgerInfo,
-};
 use mirai_annotations::checked_precondition;
 use std::{
     collections::{vec_deque::VecDeque, HashMap},
@@ -63,7 +60,6 @@ pub enum NeedFetchResult {
 ///             ╰--------------> D3
 pub struct BlockStore<T> {
     inner: Arc<RwLock<BlockTree<T>>>,
-    validator_signer: ValidatorSigner,
     state_computer: Arc<dyn StateComputer<Payload = T>>,
     enforce_increasing_timestamps: bool,
     /// The persistent storage backing up the in-memory data structure, every write should go
@@ -75,7 +71,6 @@ impl<T: Payload> BlockStore<T> {
     pub async fn new(
         storage: Arc<dyn PersistentStorage<T>>,
         initial_data: RecoveryData<T>,
-        validator_signer: ValidatorSigner,
         state_computer: Arc<dyn StateComputer<Payload = T>>,
         enforce_increasing_timestamps: bool,
         max_pruned_blocks_in_mem: usize,
@@ -95,7 +90,6 @@ impl<T: Payload> BlockStore<T> {
         ));
         BlockStore {
             inner,
-            validator_signer,
             state_computer,
             enforce_increasing_timestamps,
             storage,
@@ -183,10 +177,6 @@ impl<T: Payload> BlockStore<T> {
         *self.inner.write().unwrap() = tree;
     }
 
-    pub fn signer(&self) -> &ValidatorSigner {
-        &self.validator_signer
-    }
-
     /// Execute and insert a block if it passes all validation tests.
     /// Returns the Arc to the block kept in the block store after persisting it to storage
     ///
@@ -383,54 +373,6 @@ impl<T: Payload> BlockStore<T> {
         id_to_remove
     }
 
-    /// If block id information is found, returns the ledger info placeholder, otherwise, return
-    /// a placeholder with info of the genesis block.
-    pub fn ledger_info_placeholder(&self, id: Option<HashValue>) -> LedgerInfo {
-        let block_id = match id {
-            None => return Self::zero_ledger_info_placeholder(),
-            Some(id) => id,
-        };
-        let block = match self.get_block(block_id) {
-            Some(b) => b,
-            None => {
-                return Self::zero_ledger_info_placeholder();
-            }
-        };
-        let (state_id, version) = match self.get_compute_result(block_id) {
-            Some(compute_state) => (
-                compute_state.executed_state.state_id,
-                compute_state.executed_state.version,
-            ),
-            None => {
-                return Self::zero_ledger_info_placeholder();
-            }
-        };
-        LedgerInfo::new(
-            version,
-            state_id,
-            HashValue::zero(),
-            block_id,
-            0, // TODO [Reconfiguration] use the real epoch number.
-            block.timestamp_usecs(),
-            None,
-        )
-    }
-
-    /// Used in case we're using a ledger info just as a placeholder for signing the votes / QCs
-    /// and there is no real block committed.
-    /// It's all pretty much zeroes.
-    fn zero_ledger_info_placeholder() -> LedgerInfo {
-        LedgerInfo::new(
-            0,
-            HashValue::zero(),
-            HashValue::zero(),
-            HashValue::zero(),
-            0,
-            0,
-            None,
-        )
-    }
-
     fn verify_and_get_parent(&self, block: &Block<T>) -> failure::Result<Arc<ExecutedBlock<T>>> {
         ensure!(
             self.inner.read().unwrap().root().round() < block.round(),
@@ -481,31 +423,6 @@ impl<T: Payload> BlockReader for BlockStore<T> {
         self.inner.read().unwrap().path_from_root(block_id)
     }
 
-    fn create_block(
-        &self,
-        parent: &Block<Self::Payload>,
-        payload: Self::Payload,
-        round: Round,
-        timestamp_usecs: u64,
-    ) -> Block<Self::Payload> {
-        if self.enforce_increasing_timestamps {
-            checked_precondition!(parent.timestamp_usecs() < timestamp_usecs);
-        }
-        let quorum_cert = self
-            .get_quorum_cert_for_block(parent.id())
-            .expect("Parent for the newly created block is not certified!")
-            .as_ref()
-            .clone();
-        Block::make_block(
-            parent,
-            payload,
-            round,
-            timestamp_usecs,
-            quorum_cert,
-            &self.validator_signer,
-        )
-    }
-
     fn highest_certified_block(&self) -> Arc<ExecutedBlock<Self::Payload>> {
         self.inner.read().unwrap().highest_certified_block()
     }
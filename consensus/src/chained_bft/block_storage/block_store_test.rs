@@ -1,7 +1,7 @@
 // Copyright (c) The Libra Core Contributors
 // SPDX-License-Identifier: Apache-2.0
 
-use crate::chained_bft::test_utils::{build_empty_tree_with_custom_signing, build_simple_tree};
+use crate::chained_bft::test_utils::build_simple_tree;
 use crate::chained_bft::{
     block_storage::{BlockReader, NeedFetchResult, VoteReceptionResult},
     test_utils::{self, build_empty_tree, TreeInserter},
@@ -28,7 +28,9 @@ use std::{cmp::min, collections::HashSet};
 fn test_block_store_create_block() {
     let block_store = build_empty_tree();
     let genesis = block_store.root();
-    let a1 = block_store.create_block(genesis.block(), vec![1], 1, 1);
+    let inserter = TreeInserter::new(block_store.clone());
+    let a1 =
+        inserter.create_block_with_qc(QuorumCert::certificate_for_genesis(), &genesis, 1, vec![1]);
     assert_eq!(a1.parent_id(), genesis.id());
     assert_eq!(a1.round(), 1);
     assert_eq!(a1.quorum_cert().certified_block().id(), genesis.id());
@@ -49,18 +51,17 @@ fn test_block_store_create_block() {
             ),
             a1_ref.quorum_cert().certified_block().clone(),
         ),
-        block_store.signer().author(),
+        inserter.signer().author(),
         placeholder_ledger_info(),
-        block_store.signer(),
+        inserter.signer(),
         test_utils::placeholder_sync_info(),
     );
-    let validator_verifier = ValidatorVerifier::new_single(
-        block_store.signer().author(),
-        block_store.signer().public_key(),
-    );
+    let validator_verifier =
+        ValidatorVerifier::new_single(inserter.signer().author(), inserter.signer().public_key());
     block_store.insert_vote_and_qc(vote_msg, &validator_verifier);
 
-    let b1 = block_store.create_block(a1_ref.block(), vec![2], 2, 2);
+    let a1_qc = block_store.get_quorum_cert_for_block(a1_ref.id()).unwrap();
+    let b1 = inserter.create_block_with_qc(a1_qc.as_ref().clone(), &a1_ref, 2, vec![2]);
     assert_eq!(b1.parent_id(), a1_ref.id());
     assert_eq!(b1.round(), 2);
     assert_eq!(b1.quorum_cert().certified_block().id(), a1_ref.id());
@@ -146,16 +147,14 @@ proptest! {
 
     #[test]
     fn test_block_store_insert(
-        (mut private_keys, blocks) in block_test_utils::block_forest_and_its_keys(
+        (private_keys, blocks) in block_test_utils::block_forest_and_its_keys(
             // quorum size
             10,
             // recursion depth
             50)
     ){
         let authors: HashSet<Author> = private_keys.iter().map(|private_key| AccountAddress::from_public_key(&private_key.public_key())).collect();
-        let priv_key = private_keys.pop().expect("several keypairs generated");
-        let signer = ValidatorSigner::new(None, priv_key);
-        let block_store = build_empty_tree_with_custom_signing(signer);
+        let block_store = build_empty_tree();
         for block in blocks {
             if block.round() > 0 && authors.contains(&block.author().unwrap()) {
                 let known_parent = block_store.block_exists(block.parent_id());
@@ -301,9 +300,9 @@ fn test_insert_vote() {
     // Set up enough different authors to support different votes for the same block.
     let (signers, validator_verifier) = random_validator_verifier(11, Some(10), false);
     let my_signer = signers[10].clone();
-    let block_store = build_empty_tree_with_custom_signing(my_signer);
+    let block_store = build_empty_tree();
     let genesis = block_store.root();
-    let mut inserter = TreeInserter::new(block_store.clone());
+    let mut inserter = TreeInserter::new_with_signer(my_signer, block_store.clone());
     let block = inserter.insert_block_with_qc(QuorumCert::certificate_for_genesis(), &genesis, 1);
 
     assert!(block_store.get_quorum_cert_for_block(block.id()).is_none());
@@ -384,7 +383,7 @@ fn test_illegal_timestamp() {
         // This timestamp is illegal, it is the same as genesis
         genesis.timestamp_usecs(),
         QuorumCert::certificate_for_genesis(),
-        block_store.signer(),
+        &ValidatorSigner::random(None),
     );
     let result = block_on(block_store.execute_and_insert_block(block_with_illegal_timestamp));
     assert!(result.is_err());
@@ -419,7 +418,7 @@ fn test_need_fetch_for_qc() {
     let a3 = inserter.insert_block(&a2, 3, None);
     block_tree.prune_tree(a2.id());
     let need_fetch_qc = placeholder_certificate_for_block(
-        vec![block_tree.signer()],
+        vec![inserter.signer()],
         HashValue::zero(),
         a3.round() + 1,
         HashValue::zero(),
@@ -428,7 +427,7 @@ fn test_need_fetch_for_qc() {
     );
     let too_old_qc = QuorumCert::certificate_for_genesis();
     let can_insert_qc = placeholder_certificate_for_block(
-        vec![block_tree.signer()],
+        vec![inserter.signer()],
         a3.id(),
         a3.round(),
         a2.id(),
@@ -467,7 +466,7 @@ fn test_need_sync_for_qc() {
     let a3 = inserter.insert_block(&a2, 3, None);
     block_tree.prune_tree(a3.id());
     let qc = placeholder_certificate_for_block(
-        vec![block_tree.signer()],
+        vec![inserter.signer()],
         HashValue::zero(),
         a3.round() + 3,
         HashValue::zero(),
@@ -479,7 +478,7 @@ fn test_need_sync_for_qc() {
         true
     );
     let qc = placeholder_certificate_for_block(
-        vec![block_tree.signer()],
+        vec![inserter.signer()],
         HashValue::zero(),
         a3.round() + 2,
         HashValue::zero(),
@@ -2,10 +2,8 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use consensus_types::{
-    block::{Block, ExecutedBlock},
-    common::Round,
-    quorum_cert::QuorumCert,
-    timeout_certificate::TimeoutCertificate,
+    block::ExecutedBlock, quorum_cert::QuorumCert, timeout_certificate::TimeoutCertificate,
+    vote_msg::VoteMsg,
 };
 use crypto::HashValue;
 use std::sync::Arc;
@@ -27,7 +25,8 @@ pub enum VoteReceptionResult {
     /// The very same vote message has been processed in past.
     DuplicateVote,
     /// The very same author has already voted for another proposal in this round (equivocation).
-    EquivocateVote,
+    /// Carries the previously received vote and the new conflicting one.
+    EquivocateVote(Box<(VoteMsg, VoteMsg)>),
     /// This block has been already certified.
     OldQuorumCertificate(Arc<QuorumCert>),
     /// This block has just been certified after adding the vote.
@@ -66,22 +65,6 @@ pub trait BlockReader: Send + Sync {
     fn path_from_root(&self, block_id: HashValue)
         -> Option<Vec<Arc<ExecutedBlock<Self::Payload>>>>;
 
-    /// Generates and returns a block with the given parent and payload.
-    /// Note that it does not add the block to the tree, just generates it.
-    /// The main reason we want this function in the BlockStore is the fact that the signer required
-    /// for signing the newly created block is held by the block store.
-    /// The function panics in the following cases:
-    /// * If the parent or its quorum certificate are not present in the tree,
-    /// * If the given round (which is typically calculated by Pacemaker) is not greater than that
-    ///   of a parent.
-    fn create_block(
-        &self,
-        parent: &Block<Self::Payload>,
-        payload: Self::Payload,
-        round: Round,
-        timestamp_usecs: u64,
-    ) -> Block<Self::Payload>;
-
     /// Return the certified block with the highest round.
     fn highest_certified_block(&self) -> Arc<ExecutedBlock<Self::Payload>>;
 
@@ -25,6 +25,8 @@ struct LastVoteInfo {
     li_digest: HashValue,
     round: Round,
     is_timeout: bool, // true if a vote includes a round signature that can be aggregated to TC
+    // The vote itself is kept as a proof in case the author equivocates in the same round.
+    vote_msg: VoteMsg,
 }
 
 /// Last pending votes of the authors. Should be cleared upon reconfiguration.
@@ -135,7 +137,8 @@ impl PendingVotes {
 
     /// If this is the first vote from Author, add it to map. If Author has
     /// already voted on same block then return DuplicateVote error. If Author has already voted
-    /// on some other result, prune the last vote and insert new one in map.
+    /// on some other result, prune the last vote and insert new one in map. If the other result
+    /// was voted in the same round, return EquivocateVote error carrying both votes.
     fn replace_prev_vote(&mut self, vote_msg: &VoteMsg) -> Result<(), VoteReceptionResult> {
         let author = vote_msg.author();
         let round = vote_msg.vote_data().proposed().round();
@@ -145,6 +148,7 @@ impl PendingVotes {
             li_digest,
             round,
             is_timeout,
+            vote_msg: vote_msg.clone(),
         };
         let last_voted_info = match self.author_to_last_voted_info.insert(author, vote_info) {
             None => {
@@ -186,7 +190,10 @@ impl PendingVotes {
                 author.short_str(),
                 round
             );
-            return Err(VoteReceptionResult::EquivocateVote);
+            return Err(VoteReceptionResult::EquivocateVote(Box::new((
+                last_voted_info.vote_msg,
+                vote_msg.clone(),
+            ))));
         }
         if let Some(pending_tc) = self.round_to_tc.get_mut(&last_voted_info.round) {
             // Removing signature from last tc
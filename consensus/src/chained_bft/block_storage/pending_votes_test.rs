@@ -68,7 +68,10 @@ fn test_qc_aggregation() {
     );
     assert_eq!(
         pending_votes.insert_vote(&vote_data_2_author_0, &validator),
-        VoteReceptionResult::EquivocateVote
+        VoteReceptionResult::EquivocateVote(Box::new((
+            vote_data_1_author_0.clone(),
+            vote_data_2_author_0.clone()
+        )))
     );
     // A different author voting for a different result in the same round but without a round
     // signature: VoteAdded
@@ -4,6 +4,7 @@
 use crate::state_replication::{StateComputer, TxnManager};
 use crate::{
     chained_bft::{
+        batching::{BatchCoordinator, BatchStateComputer, BatchStore, BatchTxnManager},
         chained_bft_smr::{ChainedBftSMR, ChainedBftSMRConfig},
         persistent_storage::{PersistentStorage, StorageWriteProxy},
     },
@@ -13,8 +14,14 @@ use crate::{
     state_replication::StateMachineReplication,
     txn_manager::MempoolProxy,
 };
-use config::config::NodeConfig;
-use consensus_types::common::Author;
+use channel;
+use config::config::{NodeConfig, SafetyRulesBackend};
+use consensus_types::{
+    batch::ProofOfStore,
+    common::{Author, Payload},
+};
+use crypto::hash::CryptoHash;
+use debug_interface::{node_debug_service::EquivocationEvidenceSource, proto};
 use executor::Executor;
 use failure::prelude::*;
 use libra_logger::prelude::*;
@@ -30,24 +37,32 @@ use std::{convert::TryFrom, sync::Arc};
 use tokio::runtime;
 use vm_runtime::MoveVM;
 
+#[cfg(test)]
+#[path = "chained_bft_consensus_provider_test.rs"]
+mod chained_bft_consensus_provider_test;
+
 ///  The state necessary to begin state machine replication including ValidatorSet, networking etc.
 pub struct InitialSetup {
     pub author: Author,
     pub epoch: u64,
-    pub signer: ValidatorSigner,
+    /// The consensus signing key, only read from the config when SafetyRules runs in the
+    /// consensus process: a remote SafetyRules process reads it on its own.
+    pub signer: Option<ValidatorSigner>,
     pub validator: ValidatorVerifier,
     pub network_sender: ConsensusNetworkSender,
     pub network_events: ConsensusNetworkEvents,
 }
 
 /// Supports the implementation of ConsensusProvider using LibraBFT.
-pub struct ChainedBftProvider {
-    smr: ChainedBftSMR<Vec<SignedTransaction>>,
-    txn_manager: Arc<dyn TxnManager<Payload = Vec<SignedTransaction>>>,
-    state_computer: Arc<dyn StateComputer<Payload = Vec<SignedTransaction>>>,
+/// The payload of the blocks is either the transactions or the proofs of store of the batches
+/// of transactions disseminated ahead of the proposals (see `batching`).
+pub struct ChainedBftProvider<T> {
+    smr: ChainedBftSMR<T>,
+    txn_manager: Arc<dyn TxnManager<Payload = T>>,
+    state_computer: Arc<dyn StateComputer<Payload = T>>,
 }
 
-impl ChainedBftProvider {
+impl ChainedBftProvider<Vec<SignedTransaction>> {
     pub fn new(
         node_config: &mut NodeConfig,
         network_sender: ConsensusNetworkSender,
@@ -56,6 +71,71 @@ impl ChainedBftProvider {
         executor: Arc<Executor<MoveVM>>,
         synchronizer_client: Arc<StateSyncClient>,
     ) -> Self {
+        let smr = Self::create_smr(node_config, network_sender, network_events);
+        let txn_manager = Arc::new(MempoolProxy::new(mempool_client.clone()));
+        let state_computer = Arc::new(ExecutionProxy::new(executor, synchronizer_client.clone()));
+        Self {
+            smr,
+            txn_manager,
+            state_computer,
+        }
+    }
+}
+
+impl ChainedBftProvider<Vec<ProofOfStore>> {
+    /// Creates a provider proposing the batches disseminated by the BatchCoordinator.
+    pub fn new_with_batching(
+        node_config: &mut NodeConfig,
+        network_sender: ConsensusNetworkSender,
+        network_events: ConsensusNetworkEvents,
+        mempool_client: Arc<MempoolClient>,
+        executor: Arc<Executor<MoveVM>>,
+        synchronizer_client: Arc<StateSyncClient>,
+    ) -> Self {
+        let mut smr = Self::create_smr(node_config, network_sender, network_events);
+        let validators = Arc::new(
+            node_config
+                .consensus
+                .consensus_peers
+                .get_validator_verifier(),
+        );
+        let (fetch_sender, fetch_receiver) = channel::new(1_024, &counters::PENDING_BATCH_FETCHES);
+        let batch_store = Arc::new(BatchStore::new(
+            fetch_sender,
+            node_config.consensus.batching(),
+        ));
+        let mempool_proxy = Arc::new(MempoolProxy::new(mempool_client.clone()));
+        smr.set_batch_coordinator(BatchCoordinator::new(
+            mempool_proxy.clone(),
+            Arc::clone(&batch_store),
+            fetch_receiver,
+            node_config.consensus.batching(),
+        ));
+        let txn_manager = Arc::new(BatchTxnManager::new(
+            mempool_proxy,
+            Arc::clone(&batch_store),
+        ));
+        let execution_proxy = Arc::new(ExecutionProxy::new(executor, synchronizer_client.clone()));
+        let state_computer = Arc::new(BatchStateComputer::new(
+            execution_proxy,
+            batch_store,
+            validators,
+            node_config.consensus.max_block_size(),
+        ));
+        Self {
+            smr,
+            txn_manager,
+            state_computer,
+        }
+    }
+}
+
+impl<T: Payload> ChainedBftProvider<T> {
+    fn create_smr(
+        node_config: &mut NodeConfig,
+        network_sender: ConsensusNetworkSender,
+        network_events: ConsensusNetworkEvents,
+    ) -> ChainedBftSMR<T> {
         let runtime = runtime::Builder::new()
             .name_prefix("consensus-")
             .build()
@@ -71,14 +151,7 @@ impl ChainedBftProvider {
             initial_data.last_vote().map_or("None".to_string(), |v| v.to_string()),
             initial_data.highest_timeout_certificate().map_or("None".to_string(), |v| v.to_string()),
         );
-        let txn_manager = Arc::new(MempoolProxy::new(mempool_client.clone()));
-        let state_computer = Arc::new(ExecutionProxy::new(executor, synchronizer_client.clone()));
-        let smr = ChainedBftSMR::new(initial_setup, runtime, config, storage, initial_data);
-        Self {
-            smr,
-            txn_manager,
-            state_computer,
-        }
+        ChainedBftSMR::new(initial_setup, runtime, config, storage, initial_data)
     }
 
     /// Retrieve the initial "state" for consensus. This function is synchronous and returns after
@@ -95,14 +168,19 @@ impl ChainedBftProvider {
             .clone();
         let author =
             AccountAddress::try_from(peer_id_str).expect("Failed to parse peer id of a validator");
-        let private_key = node_config
-            .consensus
-            .consensus_keypair
-            .take_consensus_private()
-            .expect(
-            "Failed to move a Consensus private key from a NodeConfig, key absent or already read",
-        );
-        let signer = ValidatorSigner::new(author, private_key);
+        let signer = match node_config.consensus.safety_rules().backend {
+            SafetyRulesBackend::InProcess => {
+                let private_key = node_config
+                    .consensus
+                    .consensus_keypair
+                    .take_consensus_private()
+                    .expect(
+                    "Failed to move a Consensus private key from a NodeConfig, key absent or already read",
+                );
+                Some(ValidatorSigner::new(author, private_key))
+            }
+            SafetyRulesBackend::Remote => None,
+        };
         // Keeping the initial set of validators in a node config is embarrassing and we should
         // all feel bad about it.
         let validator = node_config
@@ -128,7 +206,31 @@ impl ChainedBftProvider {
     }
 }
 
-impl ConsensusProvider for ChainedBftProvider {
+/// Lists the equivocation evidence persisted in the ConsensusDB for the debug interface.
+struct StoredEquivocationEvidence<T> {
+    storage: Arc<dyn PersistentStorage<T>>,
+}
+
+impl<T: Payload> EquivocationEvidenceSource for StoredEquivocationEvidence<T> {
+    fn equivocation_evidence(&self) -> Result<Vec<proto::EquivocationEvidence>> {
+        let mut evidence_list = vec![];
+        for evidence in self.storage.retrieve_equivocation_evidence()? {
+            let mut proto_evidence = proto::EquivocationEvidence::default();
+            proto_evidence.id = format!("{:x}", evidence.hash());
+            proto_evidence.author = evidence
+                .author()
+                .map_or_else(String::new, |author| format!("{:x}", author));
+            proto_evidence.epoch = evidence.epoch();
+            proto_evidence.round = evidence.round();
+            proto_evidence.kind = evidence.kind().to_string();
+            proto_evidence.description = evidence.to_string();
+            evidence_list.push(proto_evidence);
+        }
+        Ok(evidence_list)
+    }
+}
+
+impl<T: Payload> ConsensusProvider for ChainedBftProvider<T> {
     fn start(&mut self) -> Result<()> {
         debug!("Starting consensus provider.");
         self.smr.start(
@@ -141,4 +243,10 @@ impl ConsensusProvider for ChainedBftProvider {
         self.smr.stop();
         debug!("Consensus provider stopped.");
     }
+
+    fn equivocation_evidence_source(&self) -> Arc<dyn EquivocationEvidenceSource> {
+        Arc::new(StoredEquivocationEvidence {
+            storage: self.smr.storage(),
+        })
+    }
 }
@@ -0,0 +1,45 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::chained_bft::{
+    chained_bft_consensus_provider::StoredEquivocationEvidence,
+    persistent_storage::PersistentStorage,
+    test_utils::{MockStorage, TestPayload},
+};
+use consensus_types::{
+    block::Block, equivocation_evidence::EquivocationEvidence, quorum_cert::QuorumCert,
+};
+use debug_interface::node_debug_service::EquivocationEvidenceSource;
+use libra_types::crypto_proxies::ValidatorSigner;
+
+#[test]
+fn test_list_stored_equivocation_evidence() {
+    let (storage, _) = MockStorage::<TestPayload>::start_for_testing();
+    let source = StoredEquivocationEvidence {
+        storage: storage.clone(),
+    };
+    assert!(source.equivocation_evidence().unwrap().is_empty());
+
+    let signer = ValidatorSigner::random(None);
+    let make_proposal = |payload| {
+        Block::new_internal(
+            vec![payload],
+            0,
+            3,
+            1,
+            QuorumCert::certificate_for_genesis(),
+            &signer,
+        )
+    };
+    let evidence = EquivocationEvidence::DoubleProposal {
+        first: make_proposal(1),
+        second: make_proposal(2),
+    };
+    storage.save_equivocation_evidence(evidence).unwrap();
+
+    let listed = source.equivocation_evidence().unwrap();
+    assert_eq!(listed.len(), 1);
+    assert_eq!(listed[0].author, format!("{:x}", signer.author()));
+    assert_eq!(listed[0].round, 3);
+    assert_eq!(listed[0].kind, "double_proposal");
+}
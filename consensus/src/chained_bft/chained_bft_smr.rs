@@ -5,16 +5,19 @@ use crate::chained_bft::chained_bft_consensus_provider::InitialSetup;
 use crate::chained_bft::epoch_manager::EpochManager;
 use crate::{
     chained_bft::{
+        batching::BatchCoordinator,
         block_storage::BlockStore,
         event_processor::EventProcessor,
         liveness::{
             multi_proposer_election::MultiProposer,
-            pacemaker::{ExponentialTimeInterval, Pacemaker},
+            pacemaker::{
+                AdaptiveTimeInterval, ExponentialTimeInterval, Pacemaker, PacemakerTimeInterval,
+            },
             proposal_generator::ProposalGenerator,
             proposer_election::ProposerElection,
             rotating_proposer_election::{choose_leader, RotatingProposer},
         },
-        network::ConsensusNetworkImpl,
+        network::{ConsensusNetworkImpl, NetworkReceivers},
         persistent_storage::{PersistentStorage, RecoveryData},
     },
     counters,
@@ -22,15 +25,25 @@ use crate::{
     util::time_service::{ClockTimeService, TimeService},
 };
 use channel;
-use config::config::{ConsensusConfig, ConsensusProposerType};
-use consensus_types::common::{Payload, Round};
+use config::config::{
+    ConsensusConfig, ConsensusProposerType, PacemakerTimeIntervalType, SafetyRulesBackend,
+    SafetyRulesConfig,
+};
+use consensus_types::common::{Author, Payload, Round};
 use failure::prelude::*;
 use futures::{executor::block_on, select, stream::StreamExt};
 use libra_logger::prelude::*;
 use libra_types::crypto_proxies::{ValidatorSigner, ValidatorVerifier};
 use network::validator_network::{ConsensusNetworkEvents, ConsensusNetworkSender};
-use safety_rules::SafetyRules;
-use std::{sync::Arc, time::Duration};
+use safety_rules::{
+    persistent_storage::{InMemoryStorage, OnDiskStorage, PersistentStorage as SafetyStorage},
+    remote_service::RemoteSafetyRules,
+    ConsensusState, SafetyRules, TSafetyRules,
+};
+use std::{
+    sync::{Arc, Mutex},
+    time::Duration,
+};
 use tokio::runtime::{Runtime, TaskExecutor};
 
 /// Consensus configuration derived from ConsensusConfig
@@ -39,12 +52,16 @@ pub struct ChainedBftSMRConfig {
     pub max_pruned_blocks_in_mem: usize,
     /// Initial timeout for pacemaker
     pub pacemaker_initial_timeout: Duration,
+    /// Strategy of the pacemaker round durations
+    pub pacemaker_time_interval: PacemakerTimeIntervalType,
     /// Consensus proposer type
     pub proposer_type: ConsensusProposerType,
     /// Contiguous rounds for proposer
     pub contiguous_rounds: u32,
     /// Max block size (number of transactions) that consensus pulls from mempool
     pub max_block_size: u64,
+    /// Where SafetyRules runs and keeps its state
+    pub safety_rules: SafetyRulesConfig,
 }
 
 impl ChainedBftSMRConfig {
@@ -53,9 +70,11 @@ impl ChainedBftSMRConfig {
         ChainedBftSMRConfig {
             max_pruned_blocks_in_mem: cfg.max_pruned_blocks_in_mem().unwrap_or(10000) as usize,
             pacemaker_initial_timeout: Duration::from_millis(pacemaker_initial_timeout_ms),
+            pacemaker_time_interval: cfg.get_pacemaker_time_interval_type(),
             proposer_type: cfg.get_proposer_type(),
             contiguous_rounds: cfg.contiguous_rounds(),
             max_block_size: cfg.max_block_size(),
+            safety_rules: cfg.safety_rules().clone(),
         }
     }
 }
@@ -70,6 +89,7 @@ pub struct ChainedBftSMR<T> {
     config: ChainedBftSMRConfig,
     storage: Arc<dyn PersistentStorage<T>>,
     initial_data: Option<RecoveryData<T>>,
+    batch_coordinator: Option<BatchCoordinator>,
 }
 
 impl<T: Payload> ChainedBftSMR<T> {
@@ -87,9 +107,19 @@ impl<T: Payload> ChainedBftSMR<T> {
             config,
             storage,
             initial_data: Some(initial_data),
+            batch_coordinator: None,
         }
     }
 
+    pub fn storage(&self) -> Arc<dyn PersistentStorage<T>> {
+        Arc::clone(&self.storage)
+    }
+
+    /// Sets the coordinator of the batch dissemination, which is started along with the network.
+    pub fn set_batch_coordinator(&mut self, batch_coordinator: BatchCoordinator) {
+        self.batch_coordinator = Some(batch_coordinator);
+    }
+
     #[cfg(test)]
     pub fn block_store(&self) -> Option<Arc<BlockStore<T>>> {
         self.block_store.clone()
@@ -102,14 +132,70 @@ impl<T: Payload> ChainedBftSMR<T> {
     ) -> Pacemaker {
         // 1.5^6 ~= 11
         // Timeout goes from initial_timeout to initial_timeout*11 in 6 steps
-        let time_interval = Box::new(ExponentialTimeInterval::new(
-            self.config.pacemaker_initial_timeout,
-            1.5,
-            6,
-        ));
+        let initial_timeout = self.config.pacemaker_initial_timeout;
+        let time_interval: Box<dyn PacemakerTimeInterval> =
+            match self.config.pacemaker_time_interval {
+                PacemakerTimeIntervalType::Exponential => {
+                    Box::new(ExponentialTimeInterval::new(initial_timeout, 1.5, 6))
+                }
+                // The base timeout follows the measured latencies within an order of magnitude
+                // of the initial timeout.
+                PacemakerTimeIntervalType::Adaptive => Box::new(AdaptiveTimeInterval::new(
+                    initial_timeout,
+                    initial_timeout / 10,
+                    initial_timeout * 10,
+                    1.5,
+                    6,
+                )),
+            };
         Pacemaker::new(time_interval, time_service, timeout_sender)
     }
 
+    /// Create the SafetyRules instance shared by the event processor and the batch coordinator:
+    /// the signing key is only present if SafetyRules runs in the consensus process.
+    fn create_local_safety_rules(
+        &self,
+        signer: Option<ValidatorSigner>,
+        epoch: u64,
+        validators: Arc<ValidatorVerifier>,
+        consensus_state: ConsensusState,
+    ) -> Option<Arc<Mutex<SafetyRules>>> {
+        let config = &self.config.safety_rules;
+        match config.backend {
+            SafetyRulesBackend::InProcess => {
+                let signer = Arc::new(
+                    signer.expect("SafetyRules running in process requires the signing key"),
+                );
+                let storage: Box<dyn SafetyStorage> = match &config.storage_file {
+                    Some(file) => Box::new(
+                        OnDiskStorage::new(file.clone(), Arc::clone(&signer), consensus_state)
+                            .expect("Unable to initialize SafetyRules storage"),
+                    ),
+                    None => Box::new(InMemoryStorage::new(consensus_state)),
+                };
+                Some(Arc::new(Mutex::new(SafetyRules::new(
+                    storage, signer, epoch, validators,
+                ))))
+            }
+            SafetyRulesBackend::Remote => None,
+        }
+    }
+
+    /// Create a SafetyRules client: either a handle to the local instance or a client of a
+    /// remote SafetyRules process.
+    fn create_safety_rules<P: Payload>(
+        &self,
+        local: &Option<Arc<Mutex<SafetyRules>>>,
+    ) -> Box<dyn TSafetyRules<P>> {
+        match local {
+            Some(safety_rules) => Box::new(Arc::clone(safety_rules)),
+            None => Box::new(
+                RemoteSafetyRules::new(&self.config.safety_rules)
+                    .expect("Unable to set up the SafetyRules client"),
+            ),
+        }
+    }
+
     /// Create a proposer election handler based on proposers
     fn create_proposer_election(
         &self,
@@ -139,9 +225,8 @@ impl<T: Payload> ChainedBftSMR<T> {
         executor: TaskExecutor,
         mut event_processor: EventProcessor<T>,
         mut pacemaker_timeout_sender_rx: channel::Receiver<Round>,
-        mut network: ConsensusNetworkImpl,
+        mut network_receivers: NetworkReceivers<T>,
     ) {
-        let mut network_receivers = network.start(&executor);
         let fut = async move {
             event_processor.start().await;
             loop {
@@ -161,6 +246,9 @@ impl<T: Payload> ChainedBftSMR<T> {
                     sync_info_msg = network_receivers.sync_info_msgs.select_next_some() => {
                         event_processor.process_sync_info_msg(sync_info_msg.0, sync_info_msg.1).await;
                     }
+                    evidence = network_receivers.equivocation_evidence.select_next_some() => {
+                        event_processor.process_equivocation_evidence_msg(evidence).await;
+                    }
                     complete => {
                         break;
                     }
@@ -175,7 +263,8 @@ impl<T: Payload> ChainedBftSMR<T> {
         epoch_mgr: Arc<EpochManager>,
         network_sender: ConsensusNetworkSender,
         network_events: ConsensusNetworkEvents,
-        signer: ValidatorSigner,
+        author: Author,
+        signer: Option<ValidatorSigner>,
         initial_data: RecoveryData<T>,
         txn_manager: Arc<dyn TxnManager<Payload = T>>,
         state_computer: Arc<dyn StateComputer<Payload = T>>,
@@ -186,20 +275,42 @@ impl<T: Payload> ChainedBftSMR<T> {
             .expect("Consensus start: No valid runtime found!")
             .executor();
         let time_service = Arc::new(ClockTimeService::new(executor.clone()));
-        let network = ConsensusNetworkImpl::new(
-            signer.author(),
+        let mut network = ConsensusNetworkImpl::new(
+            author,
             network_sender.clone(),
             network_events,
             Arc::clone(&epoch_mgr),
         );
+        let network_receivers = network.start(&executor);
 
         let last_vote = initial_data.last_vote();
-        let safety_rules = SafetyRules::new(initial_data.state());
+        let local_safety_rules = self.create_local_safety_rules(
+            signer,
+            epoch_mgr.epoch(),
+            epoch_mgr.validators(),
+            initial_data.state(),
+        );
+        // The batch coordinator is started before the recovery of the block store, which might
+        // need to fetch the batches of the recovered blocks.
+        if let Some(batch_coordinator) = self.batch_coordinator.take() {
+            let batch_receivers = network
+                .take_batch_receivers()
+                .expect("[consensus] Batch receivers are already taken");
+            batch_coordinator.start(
+                executor.clone(),
+                network.clone(),
+                author,
+                epoch_mgr.epoch(),
+                self.create_safety_rules(&local_safety_rules),
+                epoch_mgr.validators(),
+                batch_receivers,
+            );
+        }
+        let safety_rules = self.create_safety_rules(&local_safety_rules);
 
         let block_store = Arc::new(block_on(BlockStore::new(
             Arc::clone(&self.storage),
             initial_data,
-            signer,
             Arc::clone(&state_computer),
             true,
             self.config.max_pruned_blocks_in_mem,
@@ -223,6 +334,7 @@ impl<T: Payload> ChainedBftSMR<T> {
 
         let proposer_election = self.create_proposer_election(epoch_mgr.validators().as_ref());
         let event_processor = EventProcessor::new(
+            author,
             Arc::clone(&block_store),
             last_vote,
             pacemaker,
@@ -238,7 +350,12 @@ impl<T: Payload> ChainedBftSMR<T> {
             epoch_mgr.validators(),
         );
 
-        Self::start_event_processing(executor, event_processor, timeout_receiver, network);
+        Self::start_event_processing(
+            executor,
+            event_processor,
+            timeout_receiver,
+            network_receivers,
+        );
     }
 }
 
@@ -280,6 +397,7 @@ impl<T: Payload> StateMachineReplication for ChainedBftSMR<T> {
             epoch_mgr,
             initial_setup.network_sender,
             initial_setup.network_events,
+            initial_setup.author,
             initial_setup.signer,
             initial_data,
             txn_manager,
@@ -28,8 +28,9 @@ use crate::chained_bft::{
     persistent_storage::RecoveryData,
     test_utils::{consensus_runtime, with_smr_id},
 };
-use config::config::ConsensusProposerType::{
-    self, FixedProposer, MultipleOrderedProposers, RotatingProposer,
+use config::config::{
+    ConsensusProposerType::{self, FixedProposer, MultipleOrderedProposers, RotatingProposer},
+    PacemakerTimeIntervalType, SafetyRulesConfig,
 };
 use libra_types::crypto_proxies::{
     random_validator_verifier, LedgerInfoWithSignatures, ValidatorSigner,
@@ -76,13 +77,15 @@ impl SMRNode {
         let config = ChainedBftSMRConfig {
             max_pruned_blocks_in_mem: 10000,
             pacemaker_initial_timeout: Duration::from_secs(3),
+            pacemaker_time_interval: PacemakerTimeIntervalType::Exponential,
             proposer_type,
             contiguous_rounds: 2,
             max_block_size: 50,
+            safety_rules: SafetyRulesConfig::default(),
         };
         let initial_setup = InitialSetup {
             author,
-            signer: signer.clone(),
+            signer: Some(signer.clone()),
             epoch: 0,
             validator: epoch_mgr.validators().as_ref().clone(),
             network_sender,
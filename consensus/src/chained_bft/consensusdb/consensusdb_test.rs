@@ -56,3 +56,22 @@ fn test_delete_block_and_qc() {
     assert_eq!(db.get_blocks::<i64>().unwrap().len(), 0);
     assert_eq!(db.get_quorum_certificates().unwrap().len(), 0);
 }
+
+#[test]
+fn test_put_get_equivocation_evidence() {
+    let tmp_dir = TempPath::new();
+    let db = ConsensusDB::new(&tmp_dir);
+
+    assert_eq!(db.get_equivocation_evidence().unwrap().len(), 0);
+
+    let evidence_id = HashValue::random();
+    db.save_equivocation_evidence(evidence_id, vec![0x01, 0x02, 0x03])
+        .unwrap();
+    // Saving the same evidence twice is idempotent.
+    db.save_equivocation_evidence(evidence_id, vec![0x01, 0x02, 0x03])
+        .unwrap();
+
+    let evidence = db.get_equivocation_evidence().unwrap();
+    assert_eq!(evidence.len(), 1);
+    assert_eq!(evidence.get(&evidence_id), Some(&vec![0x01, 0x02, 0x03]));
+}
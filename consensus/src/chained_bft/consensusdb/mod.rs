@@ -7,6 +7,7 @@ mod schema;
 
 use crate::chained_bft::consensusdb::schema::{
     block::{BlockSchema, SchemaBlock},
+    equivocation_evidence::EquivocationEvidenceSchema,
     quorum_certificate::QCSchema,
     single_entry::{SingleEntryKey, SingleEntrySchema},
 };
@@ -14,7 +15,7 @@ use consensus_types::{block::Block, common::Payload, quorum_cert::QuorumCert};
 use crypto::HashValue;
 use failure::prelude::*;
 use libra_logger::prelude::*;
-use schema::{BLOCK_CF_NAME, QC_CF_NAME, SINGLE_ENTRY_CF_NAME};
+use schema::{BLOCK_CF_NAME, EQUIVOCATION_EVIDENCE_CF_NAME, QC_CF_NAME, SINGLE_ENTRY_CF_NAME};
 use schemadb::{
     ColumnFamilyOptions, ColumnFamilyOptionsMap, ReadOptions, SchemaBatch, DB, DEFAULT_CF_NAME,
 };
@@ -23,6 +24,7 @@ use std::{collections::HashMap, iter::Iterator, path::Path, time::Instant};
 type HighestTimeoutCertificate = Vec<u8>;
 type ConsensusStateData = Vec<u8>;
 type VoteMsgData = Vec<u8>;
+type EquivocationEvidenceData = Vec<u8>;
 
 pub struct ConsensusDB {
     db: DB,
@@ -36,6 +38,10 @@ impl ConsensusDB {
                 ColumnFamilyOptions::default(),
             ),
             (BLOCK_CF_NAME, ColumnFamilyOptions::default()),
+            (
+                EQUIVOCATION_EVIDENCE_CF_NAME,
+                ColumnFamilyOptions::default(),
+            ),
             (QC_CF_NAME, ColumnFamilyOptions::default()),
             (SINGLE_ENTRY_CF_NAME, ColumnFamilyOptions::default()),
         ]
@@ -150,6 +156,29 @@ impl ConsensusDB {
         self.commit(batch)
     }
 
+    /// Persist the evidence of an equivocating validator. The evidence is never pruned: it has to
+    /// survive restarts until it can be submitted on chain.
+    pub fn save_equivocation_evidence(
+        &self,
+        evidence_id: HashValue,
+        evidence: EquivocationEvidenceData,
+    ) -> Result<()> {
+        let mut batch = SchemaBatch::new();
+        batch.put::<EquivocationEvidenceSchema>(&evidence_id, &evidence)?;
+        self.commit(batch)
+    }
+
+    /// Get all the persisted equivocation evidence.
+    pub fn get_equivocation_evidence(
+        &self,
+    ) -> Result<HashMap<HashValue, EquivocationEvidenceData>> {
+        let mut iter = self
+            .db
+            .iter::<EquivocationEvidenceSchema>(ReadOptions::default())?;
+        iter.seek_to_first();
+        iter.collect::<Result<HashMap<HashValue, EquivocationEvidenceData>>>()
+    }
+
     /// Write the whole schema batch including all data necessary to mutate the ledger
     /// state of some transaction by leveraging rocksdb atomicity support.
     fn commit(&self, batch: SchemaBatch) -> Result<()> {
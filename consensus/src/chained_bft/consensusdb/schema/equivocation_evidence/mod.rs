@@ -0,0 +1,48 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! This module defines physical storage schema for the evidence of equivocating validators.
+//!
+//! Serialized evidence bytes identified by the evidence hash.
+//! ```text
+//! |<----key---->|<----value---->|
+//! | evidence_id |   evidence    |
+//! ```
+
+use super::EQUIVOCATION_EVIDENCE_CF_NAME;
+use crypto::HashValue;
+use failure::prelude::*;
+use schemadb::{
+    define_schema,
+    schema::{KeyCodec, ValueCodec},
+};
+
+define_schema!(
+    EquivocationEvidenceSchema,
+    HashValue,
+    Vec<u8>,
+    EQUIVOCATION_EVIDENCE_CF_NAME
+);
+
+impl KeyCodec<EquivocationEvidenceSchema> for HashValue {
+    fn encode_key(&self) -> Result<Vec<u8>> {
+        Ok(self.to_vec())
+    }
+
+    fn decode_key(data: &[u8]) -> Result<Self> {
+        Ok(HashValue::from_slice(data)?)
+    }
+}
+
+impl ValueCodec<EquivocationEvidenceSchema> for Vec<u8> {
+    fn encode_value(&self) -> Result<Vec<u8>> {
+        Ok(self.clone())
+    }
+
+    fn decode_value(data: &[u8]) -> Result<Self> {
+        Ok(data.to_vec())
+    }
+}
+
+#[cfg(test)]
+mod test;
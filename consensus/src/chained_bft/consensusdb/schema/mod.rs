@@ -2,6 +2,7 @@
 // SPDX-License-Identifier: Apache-2.0
 
 pub(crate) mod block;
+pub(crate) mod equivocation_evidence;
 pub(crate) mod quorum_certificate;
 pub(crate) mod single_entry;
 
@@ -9,6 +10,7 @@ use failure::prelude::*;
 use schemadb::ColumnFamilyName;
 
 pub(super) const BLOCK_CF_NAME: ColumnFamilyName = "block";
+pub(super) const EQUIVOCATION_EVIDENCE_CF_NAME: ColumnFamilyName = "equivocation_evidence";
 pub(super) const QC_CF_NAME: ColumnFamilyName = "quorum_certificate";
 pub(super) const SINGLE_ENTRY_CF_NAME: ColumnFamilyName = "single_entry";
 
@@ -7,7 +7,6 @@ use std::sync::{Arc, RwLock};
 /// Manages the current epoch and validator set to provide quorum size/voting power and signature
 /// verification.
 pub struct EpochManager {
-    #[allow(dead_code)]
     epoch: u64,
     validators: RwLock<Arc<ValidatorVerifier>>,
 }
@@ -20,6 +19,10 @@ impl EpochManager {
         }
     }
 
+    pub fn epoch(&self) -> u64 {
+        self.epoch
+    }
+
     pub fn validators(&self) -> Arc<ValidatorVerifier> {
         Arc::clone(&self.validators.read().unwrap())
     }
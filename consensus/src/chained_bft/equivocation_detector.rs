@@ -0,0 +1,74 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use consensus_types::{
+    block::Block,
+    common::{Author, Payload, Round},
+    equivocation_evidence::EquivocationEvidence,
+};
+use crypto::{hash::CryptoHash, HashValue};
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+#[cfg(test)]
+#[path = "equivocation_detector_test.rs"]
+mod equivocation_detector_test;
+
+/// The number of rounds around the current round, for which the detector keeps the proposals.
+/// Proposals outside of this window are ignored (the ones from older rounds are not processed by
+/// the event processor anyway, and the ones from future rounds cannot be bounded in memory).
+pub const PROPOSAL_HISTORY_ROUNDS: Round = 10;
+
+/// EquivocationDetector keeps the first signed proposal of every author in the recent rounds and
+/// generates an EquivocationEvidence once a conflicting proposal arrives.
+/// Conflicting votes are detected by PendingVotes: the detector is only responsible for
+/// deduplicating the evidence (locally detected, restored from storage or received from peers).
+pub struct EquivocationDetector<T> {
+    // Round -> (Author -> first proposal seen from this author in this round)
+    round_to_proposals: BTreeMap<Round, HashMap<Author, Block<T>>>,
+    // The ids of all the evidence that has been already recorded.
+    known_evidence: HashSet<HashValue>,
+}
+
+impl<T: Payload> EquivocationDetector<T> {
+    pub fn new(known_evidence: Vec<EquivocationEvidence<T>>) -> Self {
+        Self {
+            round_to_proposals: BTreeMap::new(),
+            known_evidence: known_evidence.iter().map(CryptoHash::hash).collect(),
+        }
+    }
+
+    /// Remembers the proposal and returns an evidence in case its author has already proposed a
+    /// different block in the same round. The proposal signature is assumed to be verified.
+    pub fn process_proposal(
+        &mut self,
+        proposal: &Block<T>,
+        current_round: Round,
+    ) -> Option<EquivocationEvidence<T>> {
+        let author = proposal.author()?;
+        let lowest_round = current_round.saturating_sub(PROPOSAL_HISTORY_ROUNDS);
+        self.round_to_proposals = self.round_to_proposals.split_off(&lowest_round);
+        if proposal.round() < lowest_round
+            || proposal.round() > current_round.saturating_add(PROPOSAL_HISTORY_ROUNDS)
+        {
+            return None;
+        }
+        let first = self
+            .round_to_proposals
+            .entry(proposal.round())
+            .or_insert_with(HashMap::new)
+            .entry(author)
+            .or_insert_with(|| proposal.clone());
+        if first.id() == proposal.id() {
+            return None;
+        }
+        Some(EquivocationEvidence::DoubleProposal {
+            first: first.clone(),
+            second: proposal.clone(),
+        })
+    }
+
+    /// Returns true if the given evidence has not been recorded before (and records it).
+    pub fn add_evidence(&mut self, evidence: &EquivocationEvidence<T>) -> bool {
+        self.known_evidence.insert(evidence.hash())
+    }
+}
@@ -0,0 +1,92 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::chained_bft::{
+    equivocation_detector::{EquivocationDetector, PROPOSAL_HISTORY_ROUNDS},
+    test_utils::TestPayload,
+};
+use consensus_types::{
+    block::Block, equivocation_evidence::EquivocationEvidence, quorum_cert::QuorumCert,
+};
+use libra_types::crypto_proxies::ValidatorSigner;
+
+fn make_proposal(payload: usize, round: u64, signer: &ValidatorSigner) -> Block<TestPayload> {
+    Block::new_internal(
+        vec![payload],
+        0,
+        round,
+        1,
+        QuorumCert::certificate_for_genesis(),
+        signer,
+    )
+}
+
+#[test]
+fn test_detect_double_proposal() {
+    let signer = ValidatorSigner::random(None);
+    let mut detector = EquivocationDetector::new(vec![]);
+    let first = make_proposal(1, 1, &signer);
+    let second = make_proposal(2, 1, &signer);
+
+    assert!(detector.process_proposal(&first, 1).is_none());
+    // Receiving the same proposal again is not an equivocation.
+    assert!(detector.process_proposal(&first, 1).is_none());
+    assert_eq!(
+        detector.process_proposal(&second, 1),
+        Some(EquivocationEvidence::DoubleProposal {
+            first,
+            second: second.clone(),
+        })
+    );
+    // Proposals of other authors or other rounds are unrelated.
+    let other_signer = ValidatorSigner::random([1u8; 32]);
+    assert!(detector
+        .process_proposal(&make_proposal(3, 1, &other_signer), 1)
+        .is_none());
+    assert!(detector
+        .process_proposal(&make_proposal(3, 2, &signer), 1)
+        .is_none());
+}
+
+#[test]
+fn test_proposals_outside_of_window() {
+    let signer = ValidatorSigner::random(None);
+    let mut detector = EquivocationDetector::new(vec![]);
+    let current_round = PROPOSAL_HISTORY_ROUNDS + 5;
+
+    // Too far in the future: not remembered.
+    let future_round = current_round + PROPOSAL_HISTORY_ROUNDS + 1;
+    assert!(detector
+        .process_proposal(&make_proposal(1, future_round, &signer), current_round)
+        .is_none());
+    assert!(detector
+        .process_proposal(&make_proposal(2, future_round, &signer), current_round)
+        .is_none());
+
+    // Remembered proposals are pruned once the current round moves forward.
+    assert!(detector
+        .process_proposal(&make_proposal(1, current_round, &signer), current_round)
+        .is_none());
+    let new_round = current_round + PROPOSAL_HISTORY_ROUNDS + 1;
+    assert!(detector
+        .process_proposal(&make_proposal(2, current_round, &signer), new_round)
+        .is_none());
+}
+
+#[test]
+fn test_evidence_dedup() {
+    let signer = ValidatorSigner::random(None);
+    let evidence = EquivocationEvidence::DoubleProposal {
+        first: make_proposal(1, 1, &signer),
+        second: make_proposal(2, 1, &signer),
+    };
+    let mut detector = EquivocationDetector::new(vec![evidence.clone()]);
+    assert!(!detector.add_evidence(&evidence));
+
+    let new_evidence = EquivocationEvidence::DoubleProposal {
+        first: make_proposal(1, 2, &signer),
+        second: make_proposal(2, 2, &signer),
+    };
+    assert!(detector.add_evidence(&new_evidence));
+    assert!(!detector.add_evidence(&new_evidence));
+}
@@ -4,6 +4,7 @@
 use crate::{
     chained_bft::{
         block_storage::{BlockReader, BlockStore, NeedFetchResult, VoteReceptionResult},
+        equivocation_detector::EquivocationDetector,
         liveness::{
             pacemaker::{NewRoundEvent, NewRoundReason, Pacemaker},
             proposal_generator::ProposalGenerator,
@@ -21,16 +22,15 @@ use crate::{
 };
 use consensus_types::{
     block::Block,
-    block_info::BlockInfo,
     common::{Author, Payload, Round},
+    equivocation_evidence::EquivocationEvidence,
     proposal_msg::ProposalMsg,
     quorum_cert::QuorumCert,
     sync_info::SyncInfo,
     timeout_certificate::TimeoutCertificate,
-    vote_data::VoteData,
     vote_msg::VoteMsg,
 };
-use crypto::HashValue;
+use crypto::{hash::CryptoHash, HashValue};
 use failure::ResultExt;
 use libra_logger::prelude::*;
 use libra_types::crypto_proxies::{LedgerInfoWithSignatures, ValidatorVerifier};
@@ -41,7 +41,7 @@ use mirai_annotations::{
 use network::proto::BlockRetrievalStatus;
 #[cfg(test)]
 use safety_rules::ConsensusState;
-use safety_rules::SafetyRules;
+use safety_rules::{TSafetyRules, VoteProposal};
 use std::time::Instant;
 use std::{sync::Arc, time::Duration};
 use termion::color::*;
@@ -65,7 +65,7 @@ pub struct EventProcessor<T> {
     pacemaker: Pacemaker,
     proposer_election: Box<dyn ProposerElection<T> + Send + Sync>,
     proposal_generator: ProposalGenerator<T>,
-    safety_rules: SafetyRules,
+    safety_rules: Box<dyn TSafetyRules<T>>,
     state_computer: Arc<dyn StateComputer<Payload = T>>,
     txn_manager: Arc<dyn TxnManager<Payload = T>>,
     network: ConsensusNetworkImpl,
@@ -76,16 +76,18 @@ pub struct EventProcessor<T> {
     // Cache of the last sent vote message.
     last_vote_sent: Option<(VoteMsg, Round)>,
     validators: Arc<ValidatorVerifier>,
+    equivocation_detector: EquivocationDetector<T>,
 }
 
 impl<T: Payload> EventProcessor<T> {
     pub fn new(
+        author: Author,
         block_store: Arc<BlockStore<T>>,
         last_vote: Option<VoteMsg>,
         pacemaker: Pacemaker,
         proposer_election: Box<dyn ProposerElection<T> + Send + Sync>,
         proposal_generator: ProposalGenerator<T>,
-        safety_rules: SafetyRules,
+        safety_rules: Box<dyn TSafetyRules<T>>,
         state_computer: Arc<dyn StateComputer<Payload = T>>,
         txn_manager: Arc<dyn TxnManager<Payload = T>>,
         network: ConsensusNetworkImpl,
@@ -100,11 +102,17 @@ impl<T: Payload> EventProcessor<T> {
             network.clone(),
             Arc::clone(&state_computer),
         );
-        let author = block_store.signer().author();
         let last_vote_sent = last_vote.map(|v| {
             let round = v.vote_data().proposed().round();
             (v, round)
         });
+        let known_evidence = storage
+            .retrieve_equivocation_evidence()
+            .unwrap_or_else(|e| {
+                error!("Failed to retrieve the equivocation evidence: {:?}", e);
+                vec![]
+            });
+        let equivocation_detector = EquivocationDetector::new(known_evidence);
         Self {
             author,
             block_store,
@@ -121,6 +129,7 @@ impl<T: Payload> EventProcessor<T> {
             enforce_increasing_timestamps,
             last_vote_sent,
             validators,
+            equivocation_detector,
         }
     }
 
@@ -136,7 +145,7 @@ impl<T: Payload> EventProcessor<T> {
     /// Replica:
     ///
     /// Do nothing
-    async fn process_new_round_event(&self, new_round_event: NewRoundEvent) {
+    async fn process_new_round_event(&mut self, new_round_event: NewRoundEvent) {
         debug!("Processing {}", new_round_event);
         counters::CURRENT_ROUND.set(new_round_event.round as i64);
         counters::ROUND_TIMEOUT_MS.set(new_round_event.timeout.as_millis() as i64);
@@ -168,7 +177,7 @@ impl<T: Payload> EventProcessor<T> {
     }
 
     async fn generate_proposal(
-        &self,
+        &mut self,
         new_round_event: NewRoundEvent,
     ) -> failure::Result<ProposalMsg<T>> {
         // Proposal generator will ensure that at most one proposal is generated per round
@@ -179,6 +188,7 @@ impl<T: Payload> EventProcessor<T> {
                 self.pacemaker.current_round_deadline(),
             )
             .await?;
+        let proposal = self.safety_rules.sign_proposal(&proposal).await?;
         debug!("Propose {}", proposal);
         // return proposal
         Ok(ProposalMsg::new(proposal, self.gen_sync_info()))
@@ -203,6 +213,13 @@ impl<T: Payload> EventProcessor<T> {
         // but it's known that the pacemaker's round is not going to decrease so we can already
         // filter out the proposals from old rounds.
         let current_round = self.pacemaker.current_round();
+        if let Some(evidence) = self
+            .equivocation_detector
+            .process_proposal(proposal_msg.proposal(), current_round)
+        {
+            self.record_equivocation(evidence).await;
+            return None;
+        }
         if proposal_msg.round() < current_round {
             return None;
         }
@@ -354,7 +371,13 @@ impl<T: Payload> EventProcessor<T> {
             // The timeout event is late: the node has already moved to another round.
             return;
         }
-        let last_vote_round = self.safety_rules.consensus_state().last_vote_round();
+        let last_vote_round = match self.safety_rules.consensus_state().await {
+            Ok(consensus_state) => consensus_state.last_vote_round(),
+            Err(e) => {
+                error!("Failed to retrieve the consensus state: {}", e);
+                return;
+            }
+        };
         warn!(
             "Round {} timed out: {}, expected round proposer was {:?}, broadcasting the vote to all replicas",
             round,
@@ -378,7 +401,13 @@ impl<T: Payload> EventProcessor<T> {
         };
 
         if !timeout_vote_msg.is_timeout() {
-            timeout_vote_msg.add_round_signature(self.block_store.signer());
+            match self.safety_rules.sign_timeout(round).await {
+                Ok(round_signature) => timeout_vote_msg.set_round_signature(round_signature),
+                Err(e) => {
+                    error!("Failed to sign the timeout of round {}: {}", round, e);
+                    return;
+                }
+            }
         }
         self.network.broadcast_vote(timeout_vote_msg).await
     }
@@ -411,8 +440,8 @@ impl<T: Payload> EventProcessor<T> {
         qc: &QuorumCert,
         tc: Option<&TimeoutCertificate>,
     ) -> failure::Result<()> {
-        self.safety_rules.update(qc);
-        let consensus_state = self.safety_rules.consensus_state();
+        self.safety_rules.update(qc).await?;
+        let consensus_state = self.safety_rules.consensus_state().await?;
         counters::PREFERRED_BLOCK_ROUND.set(consensus_state.preferred_block_round() as i64);
 
         let mut highest_committed_proposal_round = None;
@@ -490,18 +519,21 @@ impl<T: Payload> EventProcessor<T> {
         // Safety invariant: The last voted round is updated to be the same as the proposed block's
         // round. At this point, the replica has decided to vote for the proposed block.
         debug_checked_verify_eq!(
-            self.safety_rules.consensus_state().last_vote_round(),
-            proposal_round
+            self.safety_rules
+                .consensus_state()
+                .await
+                .map(|state| state.last_vote_round())
+                .ok(),
+            Some(proposal_round)
         );
         // Safety invariant: qc_parent <-- qc
         // the preferred block round must be at least as large as qc_parent's round.
-        debug_checked_verify!(
-            (*self)
-                .safety_rules
-                .consensus_state()
-                .preferred_block_round()
-                >= certified_parent_block_round
-        );
+        debug_checked_verify!((*self)
+            .safety_rules
+            .consensus_state()
+            .await
+            .map(|state| state.preferred_block_round() >= certified_parent_block_round)
+            .unwrap_or(false));
 
         let recipients = self
             .proposer_election
@@ -616,37 +648,27 @@ impl<T: Payload> EventProcessor<T> {
         self.wait_before_vote_if_needed(block.timestamp_usecs())
             .await?;
 
-        let vote_info = self
-            .safety_rules
-            .voting_rule(block)
-            .with_context(|e| format!("{}Rejected{} {}: {:?}", Fg(Red), Fg(Reset), block, e))?;
-        let consensus_state = self.safety_rules.consensus_state();
-        counters::LAST_VOTE_ROUND.set(consensus_state.last_vote_round() as i64);
-
-        let proposal_id = vote_info.proposal_id();
         let executed_state = &self
             .block_store
-            .get_compute_result(proposal_id)
+            .get_compute_result(block.id())
             .expect("Block proposed_block: no execution state found for inserted block.")
             .executed_state;
-
-        let ledger_info_placeholder = self
-            .block_store
-            .ledger_info_placeholder(vote_info.potential_commit_id());
-
-        let vote_msg = VoteMsg::new(
-            VoteData::new(
-                BlockInfo::from_block(block, executed_state.state_id, executed_state.version),
-                block.quorum_cert().certified_block().clone(),
-            ),
-            self.author,
-            ledger_info_placeholder,
-            self.block_store.signer(),
-            self.gen_sync_info(),
+        let vote_proposal = VoteProposal::new(
+            block.clone(),
+            executed_state.state_id,
+            executed_state.version,
         );
 
+        let vote_msg = self
+            .safety_rules
+            .construct_and_sign_vote(&vote_proposal, self.gen_sync_info())
+            .await
+            .with_context(|e| format!("{}Rejected{} {}: {:?}", Fg(Red), Fg(Reset), block, e))?;
+        let consensus_state = self.safety_rules.consensus_state().await?;
+        counters::LAST_VOTE_ROUND.set(consensus_state.last_vote_round() as i64);
+
         self.storage
-            .save_consensus_state(vote_info.consensus_state().clone(), vote_msg.clone())
+            .save_consensus_state(consensus_state, vote_msg.clone())
             .with_context(|e| format!("Fail to persist consensus state: {:?}", e))?;
         self.last_vote_sent
             .replace((vote_msg.clone(), block.round()));
@@ -707,10 +729,53 @@ impl<T: Payload> EventProcessor<T> {
                 self.new_qc_aggregated(qc, vote_author).await
             }
             VoteReceptionResult::NewTimeoutCertificate(tc) => self.new_tc_aggregated(tc).await,
+            VoteReceptionResult::EquivocateVote(votes) => {
+                let (first, second) = *votes;
+                self.record_equivocation(EquivocationEvidence::DoubleVote { first, second })
+                    .await;
+                Ok(())
+            }
             _ => Ok(()),
         }
     }
 
+    /// Records the evidence of an equivocation detected locally: the evidence is persisted and
+    /// broadcast to the other validators, so that they can persist it as well.
+    async fn record_equivocation(&mut self, evidence: EquivocationEvidence<T>) {
+        if !self.equivocation_detector.add_evidence(&evidence) {
+            return;
+        }
+        counters::EQUIVOCATIONS_DETECTED_COUNT.inc();
+        security_log(SecurityEvent::ConsensusEquivocation)
+            .data(&evidence)
+            .log();
+        self.persist_equivocation(evidence.clone());
+        self.network.broadcast_equivocation_evidence(evidence).await;
+    }
+
+    /// Process the evidence of an equivocation received from a peer (its signatures are already
+    /// verified by the network task). The evidence is persisted, but not re-broadcast.
+    pub async fn process_equivocation_evidence_msg(&mut self, evidence: EquivocationEvidence<T>) {
+        if !self.equivocation_detector.add_evidence(&evidence) {
+            return;
+        }
+        counters::EQUIVOCATION_EVIDENCE_RECEIVED_COUNT.inc();
+        self.persist_equivocation(evidence);
+    }
+
+    fn persist_equivocation(&self, evidence: EquivocationEvidence<T>) {
+        warn!("{}Equivocation{} {}", Fg(Red), Fg(Reset), evidence);
+        event!("equivocation",
+            "author": evidence.author().map_or("None".to_string(), |a| a.short_str()),
+            "round": evidence.round(),
+            "kind": evidence.kind(),
+            "evidence_id": evidence.hash().short_str(),
+        );
+        if let Err(e) = self.storage.save_equivocation_evidence(evidence) {
+            error!("Failed to persist the equivocation evidence: {:?}", e);
+        }
+    }
+
     async fn new_qc_aggregated(
         &mut self,
         qc: Arc<QuorumCert>,
@@ -892,7 +957,7 @@ impl<T: Payload> EventProcessor<T> {
 
     /// Inspect the current consensus state.
     #[cfg(test)]
-    pub fn consensus_state(&self) -> ConsensusState {
-        self.safety_rules.consensus_state()
+    pub async fn consensus_state(&self) -> ConsensusState {
+        self.safety_rules.consensus_state().await.unwrap()
     }
 }
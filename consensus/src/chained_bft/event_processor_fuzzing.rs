@@ -24,14 +24,14 @@ use network::{
 };
 use prost::Message as _;
 use prost_ext::MessageExt;
-use safety_rules::SafetyRules;
+use safety_rules::{persistent_storage::InMemoryStorage, SafetyRules};
 use std::convert::TryFrom;
 use std::sync::Arc;
 use tokio::runtime::Runtime;
 
 // This generates a proposal for round 1
 pub fn generate_corpus_proposal() -> Vec<u8> {
-    let event_processor = create_node_for_fuzzing();
+    let mut event_processor = create_node_for_fuzzing();
     block_on(async {
         let proposal = event_processor
             .generate_proposal(NewRoundEvent {
@@ -54,7 +54,6 @@ lazy_static! {
 
 // helpers
 fn build_empty_store(
-    signer: ValidatorSigner,
     storage: Arc<dyn PersistentStorage<TestPayload>>,
     initial_data: RecoveryData<TestPayload>,
 ) -> Arc<BlockStore<TestPayload>> {
@@ -63,7 +62,6 @@ fn build_empty_store(
     Arc::new(block_on(BlockStore::new(
         storage,
         initial_data,
-        signer,
         Arc::new(EmptyStateComputer),
         true,
         10, // max pruned blocks in mem
@@ -95,7 +93,12 @@ fn create_node_for_fuzzing() -> EventProcessor<TestPayload> {
     let consensus_state = initial_data.state();
 
     // TODO: remove
-    let safety_rules = SafetyRules::new(consensus_state);
+    let safety_rules = Box::new(SafetyRules::new(
+        Box::new(InMemoryStorage::new(consensus_state)),
+        Arc::new(signer.clone()),
+        epoch_mgr.epoch(),
+        epoch_mgr.validators(),
+    ));
 
     // TODO: mock channels
     let (network_reqs_tx, _network_reqs_rx) = channel::new_test(8);
@@ -110,7 +113,7 @@ fn create_node_for_fuzzing() -> EventProcessor<TestPayload> {
     );
 
     // TODO: mock
-    let block_store = build_empty_store(signer.clone(), storage.clone(), initial_data);
+    let block_store = build_empty_store(storage.clone(), initial_data);
 
     // TODO: remove
     let time_service = Arc::new(SimulatedTimeService::new());
@@ -138,6 +141,7 @@ fn create_node_for_fuzzing() -> EventProcessor<TestPayload> {
 
     // event processor
     EventProcessor::new(
+        signer.author(),
         Arc::clone(&block_store),
         None,
         pacemaker,
@@ -50,7 +50,7 @@ use network::{
     proto::{BlockRetrievalStatus, ConsensusMsg_oneof},
     validator_network::{ConsensusNetworkEvents, ConsensusNetworkSender},
 };
-use safety_rules::{ConsensusState, SafetyRules};
+use safety_rules::{persistent_storage::InMemoryStorage, ConsensusState, SafetyRules};
 use std::convert::TryFrom;
 use std::{collections::HashMap, sync::Arc, time::Duration};
 use tokio::runtime::TaskExecutor;
@@ -68,7 +68,6 @@ pub struct NodeSetup {
 
 impl NodeSetup {
     fn build_empty_store(
-        signer: ValidatorSigner,
         storage: Arc<dyn PersistentStorage<TestPayload>>,
         state_computer: Arc<dyn StateComputer<Payload = TestPayload>>,
         initial_data: RecoveryData<TestPayload>,
@@ -76,7 +75,6 @@ impl NodeSetup {
         Arc::new(block_on(BlockStore::new(
             storage,
             initial_data,
-            signer,
             state_computer,
             true,
             10, // max pruned blocks in mem
@@ -151,12 +149,8 @@ impl NodeSetup {
             Arc::clone(&storage),
         ));
 
-        let block_store = Self::build_empty_store(
-            signer.clone(),
-            storage.clone(),
-            state_computer.clone(),
-            initial_data,
-        );
+        let block_store =
+            Self::build_empty_store(storage.clone(), state_computer.clone(), initial_data);
         let time_service = Arc::new(ClockTimeService::new(executor.clone()));
         let proposal_generator = ProposalGenerator::new(
             block_store.clone(),
@@ -165,12 +159,18 @@ impl NodeSetup {
             1,
             true,
         );
-        let safety_rules = SafetyRules::new(consensus_state);
+        let safety_rules = Box::new(SafetyRules::new(
+            Box::new(InMemoryStorage::new(consensus_state)),
+            Arc::new(signer.clone()),
+            epoch_mgr.epoch(),
+            epoch_mgr.validators(),
+        ));
 
         let pacemaker = Self::create_pacemaker(time_service.clone());
 
         let proposer_election = Self::create_proposer_election(proposer_author);
         let mut event_processor = EventProcessor::new(
+            author,
             Arc::clone(&block_store),
             last_vote_sent,
             pacemaker,
@@ -218,10 +218,10 @@ impl NodeSetup {
 fn basic_new_rank_event_test() {
     let runtime = consensus_runtime();
     let mut playground = NetworkPlayground::new(runtime.executor());
-    let nodes = NodeSetup::create_nodes(&mut playground, runtime.executor(), 2);
-    let node = &nodes[0];
+    let mut nodes = NodeSetup::create_nodes(&mut playground, runtime.executor(), 2);
+    let node = &mut nodes[0];
     let genesis = node.block_store.root();
-    let mut inserter = TreeInserter::new(node.block_store.clone());
+    let mut inserter = TreeInserter::new_with_signer(node.signer.clone(), node.block_store.clone());
     let a1 = inserter.insert_block_with_qc(QuorumCert::certificate_for_genesis(), &genesis, 1);
     block_on(async move {
         let new_round = 1;
@@ -271,14 +271,14 @@ fn basic_new_rank_event_test() {
                 BlockInfo::from_block(a1.block(), executed_state.state_id, executed_state.version),
                 a1.quorum_cert().certified_block().clone(),
             ),
-            node.block_store.signer().author(),
+            node.signer.author(),
             placeholder_ledger_info(),
-            node.block_store.signer(),
+            &node.signer,
             test_utils::placeholder_sync_info(),
         );
         let validator_verifier = Arc::new(ValidatorVerifier::new_single(
-            node.block_store.signer().author(),
-            node.block_store.signer().public_key(),
+            node.signer.author(),
+            node.signer.public_key(),
         ));
         node.block_store
             .insert_vote_and_qc(vote_msg, validator_verifier.as_ref());
@@ -336,7 +336,7 @@ fn process_successful_proposal_test() {
             1,
             1,
             genesis_qc.clone(),
-            node.block_store.signer(),
+            &node.signer,
         );
         let proposal_id = proposal.id();
         node.event_processor.process_proposed_block(proposal).await;
@@ -387,7 +387,7 @@ fn process_old_proposal_test() {
         1,
         1,
         genesis_qc.clone(),
-        node.block_store.signer(),
+        &node.signer,
     );
     let new_block_id = new_block.id();
     let old_block = Block::make_block(
@@ -396,7 +396,7 @@ fn process_old_proposal_test() {
         1,
         2,
         genesis_qc.clone(),
-        node.block_store.signer(),
+        &node.signer,
     );
     let old_block_id = old_block.id();
     block_on(async move {
@@ -446,7 +446,7 @@ fn process_round_mismatch_test() {
         1,
         1,
         genesis_qc.clone(),
-        node.block_store.signer(),
+        &node.signer,
     );
     let block_skip_round = Block::make_block(
         genesis.block(),
@@ -454,7 +454,7 @@ fn process_round_mismatch_test() {
         2,
         2,
         genesis_qc.clone(),
-        node.block_store.signer(),
+        &node.signer,
     );
     block_on(async move {
         let bad_proposal = ProposalMsg::<TestPayload>::new(
@@ -491,9 +491,14 @@ fn process_vote_timeout_msg_test() {
     let mut static_proposer = nodes.pop().unwrap();
 
     let genesis = non_proposer.block_store.root();
-    let block_0 = non_proposer
-        .block_store
-        .create_block(genesis.block(), vec![1], 1, 1);
+    let block_0 = Block::make_block(
+        genesis.block(),
+        vec![1],
+        1,
+        1,
+        QuorumCert::certificate_for_genesis(),
+        &non_proposer.signer,
+    );
     let block_0_id = block_0.id();
     block_on(
         non_proposer
@@ -589,7 +594,7 @@ fn process_proposer_mismatch_test() {
         1,
         1,
         genesis_qc.clone(),
-        node.block_store.signer(),
+        &node.signer,
     );
     let block_incorrect_proposer = Block::make_block(
         genesis.block(),
@@ -597,7 +602,7 @@ fn process_proposer_mismatch_test() {
         1,
         1,
         genesis_qc.clone(),
-        incorrect_proposer.block_store.signer(),
+        &incorrect_proposer.signer,
     );
     block_on(async move {
         let bad_proposal = ProposalMsg::<TestPayload>::new(
@@ -642,7 +647,7 @@ fn process_timeout_certificate_test() {
         1,
         1,
         genesis_qc.clone(),
-        node.block_store.signer(),
+        &node.signer,
     );
     let block_skip_round = Block::make_block(
         genesis.block(),
@@ -650,7 +655,7 @@ fn process_timeout_certificate_test() {
         2,
         2,
         genesis_qc.clone(),
-        node.block_store.signer(),
+        &node.signer,
     );
     let tc = TimeoutCertificate::new(1, HashMap::new());
 
@@ -688,7 +693,7 @@ fn process_votes_basic_test() {
         .pop()
         .unwrap();
     let genesis = node.block_store.root();
-    let mut inserter = TreeInserter::new(node.block_store.clone());
+    let mut inserter = TreeInserter::new_with_signer(node.signer.clone(), node.block_store.clone());
     let a1 = inserter.insert_block_with_qc(QuorumCert::certificate_for_genesis(), &genesis, 1);
     let executed_state = &node
         .block_store
@@ -710,9 +715,9 @@ fn process_votes_basic_test() {
 
     let vote_msg = VoteMsg::new(
         vote_data,
-        node.block_store.signer().author(),
+        node.signer.author(),
         placeholder_ledger_info(),
-        node.block_store.signer(),
+        &node.signer,
         test_utils::placeholder_sync_info(),
     );
 
@@ -747,7 +752,7 @@ fn process_block_retrieval() {
         1,
         1,
         genesis_qc.clone(),
-        node.block_store.signer(),
+        &node.signer,
     );
     let block_id = block.id();
 
@@ -823,7 +828,7 @@ fn basic_restart_test() {
     let mut node = NodeSetup::create_nodes(&mut playground, runtime.executor(), 1)
         .pop()
         .unwrap();
-    let mut inserter = TreeInserter::new(node.block_store.clone());
+    let mut inserter = TreeInserter::new_with_signer(node.signer.clone(), node.block_store.clone());
     let node_mut = &mut node;
 
     let genesis = node_mut.block_store.root();
@@ -852,10 +857,9 @@ fn basic_restart_test() {
     }
     // verify after restart we recover the data
     node = node.restart(&mut playground, runtime.executor());
-    assert_eq!(
-        node.event_processor.consensus_state(),
-        ConsensusState::new(num_proposals, num_proposals - 2),
-    );
+    let consensus_state = block_on(node.event_processor.consensus_state());
+    assert_eq!(consensus_state.last_vote_round(), num_proposals);
+    assert_eq!(consensus_state.preferred_block_round(), num_proposals - 2);
     for block in proposals {
         assert_eq!(node.block_store.block_exists(block.id()), true);
     }
@@ -9,6 +9,7 @@ use channel;
 use consensus_types::common::Round;
 use libra_logger::prelude::*;
 use std::{
+    collections::VecDeque,
     fmt,
     sync::Arc,
     time::{Duration, Instant},
@@ -64,6 +65,36 @@ pub trait PacemakerTimeInterval: Send + Sync + 'static {
     /// to calculate the round duration of round 6 and the highest committed round is 3 (meaning
     /// the highest round to commit a block is round 5, then the round index is 0.
     fn get_round_duration(&self, round_index_after_committed_qc: usize) -> Duration;
+
+    /// Notifies the time interval about the time it took to form a quorum certificate for a
+    /// round: strategies that adapt to the observed network latency can use it to tune the
+    /// round durations. Ignored by default.
+    fn record_qc_latency(&mut self, _latency: Duration) {}
+}
+
+/// Calculates the duration of a round given its base duration: base * exponent_base^power,
+/// where power = min(round_index_after_committed_qc, max_exponent).
+fn exponential_duration(
+    base_ms: u64,
+    exponent_base: f64,
+    max_exponent: usize,
+    round_index_after_committed_qc: usize,
+) -> Duration {
+    let pow = round_index_after_committed_qc.min(max_exponent) as u32;
+    let base_multiplier = exponent_base.powf(f64::from(pow));
+    let duration_ms = ((base_ms as f64) * base_multiplier).ceil() as u64;
+    Duration::from_millis(duration_ms)
+}
+
+fn check_exponent(exponent_base: f64, max_exponent: usize) {
+    assert!(
+        max_exponent < 32,
+        "max_exponent for PacemakerTimeInterval should be <32"
+    );
+    assert!(
+        exponent_base.powf(max_exponent as f64).ceil() < f64::from(std::u32::MAX),
+        "Maximum interval multiplier should be less then u32::Max"
+    );
 }
 
 /// Round durations increase exponentially
@@ -91,14 +122,7 @@ impl ExponentialTimeInterval {
     }
 
     pub fn new(base: Duration, exponent_base: f64, max_exponent: usize) -> Self {
-        assert!(
-            max_exponent < 32,
-            "max_exponent for PacemakerTimeInterval should be <32"
-        );
-        assert!(
-            exponent_base.powf(max_exponent as f64).ceil() < f64::from(std::u32::MAX),
-            "Maximum interval multiplier should be less then u32::Max"
-        );
+        check_exponent(exponent_base, max_exponent);
         ExponentialTimeInterval {
             base_ms: base.as_millis() as u64, // any reasonable ms timeout fits u64 perfectly
             exponent_base,
@@ -109,10 +133,98 @@ impl ExponentialTimeInterval {
 
 impl PacemakerTimeInterval for ExponentialTimeInterval {
     fn get_round_duration(&self, round_index_after_committed_qc: usize) -> Duration {
-        let pow = round_index_after_committed_qc.min(self.max_exponent) as u32;
-        let base_multiplier = self.exponent_base.powf(f64::from(pow));
-        let duration_ms = ((self.base_ms as f64) * base_multiplier).ceil() as u64;
-        Duration::from_millis(duration_ms)
+        exponential_duration(
+            self.base_ms,
+            self.exponent_base,
+            self.max_exponent,
+            round_index_after_committed_qc,
+        )
+    }
+}
+
+/// Number of the most recent QC latencies the adaptive base duration is derived from.
+const ADAPTIVE_WINDOW_SIZE: usize = 100;
+/// The percentile of the recent QC latencies a round is expected to complete in.
+const ADAPTIVE_PERCENTILE: f64 = 0.99;
+/// Safety margin applied on top of the percentile latency.
+const ADAPTIVE_LATENCY_MULTIPLIER: f64 = 2.0;
+
+/// Round durations adapt to the observed network latency: the base duration is derived from the
+/// p99 of the time it took to form the QCs of the recent rounds (with a safety margin), clamped
+/// to [min_base, max_base]. On top of the base duration, rounds without commit are backed off
+/// exponentially similar to `ExponentialTimeInterval`: the backoff resets as soon as there is
+/// progress (a new commit), while the base keeps following the measured latencies.
+/// Until the first QC latency is recorded the initial base duration is used.
+pub struct AdaptiveTimeInterval {
+    // Base duration used before any QC latency has been recorded.
+    initial_base_ms: u64,
+    // Bounds of the adaptive base duration.
+    min_base_ms: u64,
+    max_base_ms: u64,
+    // By how much we increase interval every round without commit
+    exponent_base: f64,
+    // Maximum time interval won't exceed base * mul^max_pow.
+    max_exponent: usize,
+    // The most recent QC latencies in ms, the oldest first.
+    qc_latencies_ms: VecDeque<u64>,
+    // The base duration derived from the recent QC latencies.
+    base_ms: u64,
+}
+
+impl AdaptiveTimeInterval {
+    pub fn new(
+        initial_base: Duration,
+        min_base: Duration,
+        max_base: Duration,
+        exponent_base: f64,
+        max_exponent: usize,
+    ) -> Self {
+        check_exponent(exponent_base, max_exponent);
+        assert!(
+            min_base <= max_base,
+            "min_base of AdaptiveTimeInterval should not exceed max_base"
+        );
+        let initial_base_ms = initial_base.as_millis() as u64;
+        Self {
+            initial_base_ms,
+            min_base_ms: min_base.as_millis() as u64,
+            max_base_ms: max_base.as_millis() as u64,
+            exponent_base,
+            max_exponent,
+            qc_latencies_ms: VecDeque::with_capacity(ADAPTIVE_WINDOW_SIZE),
+            base_ms: initial_base_ms,
+        }
+    }
+
+    fn compute_base_ms(&self) -> u64 {
+        if self.qc_latencies_ms.is_empty() {
+            return self.initial_base_ms;
+        }
+        let mut sorted: Vec<u64> = self.qc_latencies_ms.iter().cloned().collect();
+        sorted.sort_unstable();
+        let index = ((sorted.len() as f64) * ADAPTIVE_PERCENTILE).ceil() as usize;
+        let percentile_ms = sorted[index.max(1) - 1];
+        let base_ms = ((percentile_ms as f64) * ADAPTIVE_LATENCY_MULTIPLIER).ceil() as u64;
+        base_ms.max(self.min_base_ms).min(self.max_base_ms)
+    }
+}
+
+impl PacemakerTimeInterval for AdaptiveTimeInterval {
+    fn get_round_duration(&self, round_index_after_committed_qc: usize) -> Duration {
+        exponential_duration(
+            self.base_ms,
+            self.exponent_base,
+            self.max_exponent,
+            round_index_after_committed_qc,
+        )
+    }
+
+    fn record_qc_latency(&mut self, latency: Duration) {
+        if self.qc_latencies_ms.len() == ADAPTIVE_WINDOW_SIZE {
+            self.qc_latencies_ms.pop_front();
+        }
+        self.qc_latencies_ms.push_back(latency.as_millis() as u64);
+        self.base_ms = self.compute_base_ms();
     }
 }
 
@@ -143,6 +255,9 @@ pub struct Pacemaker {
     // The deadline for the next local timeout event. It is reset every time a new round start, or
     // a previous deadline expires.
     current_round_deadline: Instant,
+    // The time (as reported by the time service) the current round has started at: used for
+    // measuring the time it takes to form a QC.
+    current_round_start: Duration,
     // Service for timer
     time_service: Arc<dyn TimeService>,
     // To send local timeout events to the subscriber (e.g., SMR)
@@ -167,6 +282,7 @@ impl Pacemaker {
             highest_committed_round: 0,
             current_round: 0,
             current_round_deadline: Instant::now(),
+            current_round_start: Duration::from_secs(0),
             time_service,
             timeout_sender,
         }
@@ -211,8 +327,18 @@ impl Pacemaker {
         }
         let new_round = std::cmp::max(qc_round, tc_round) + 1;
         if new_round > self.current_round {
+            let now = self.time_service.get_current_timestamp();
+            // Only a QC for the current round tells how long it takes to form a QC: a QC of a
+            // higher round might have been learnt while catching up.
+            if qc_round >= tc_round && qc_round == self.current_round && self.current_round > 0 {
+                let latency = now
+                    .checked_sub(self.current_round_start)
+                    .unwrap_or_else(|| Duration::from_secs(0));
+                self.time_interval.record_qc_latency(latency);
+            }
             // Start a new round.
             self.current_round = new_round;
+            self.current_round_start = now;
             let timeout = self.setup_timeout();
             // The new round reason is QCReady in case both QC and TC are equal
             let new_round_reason = if qc_round >= tc_round {
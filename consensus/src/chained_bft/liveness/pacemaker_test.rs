@@ -3,7 +3,8 @@
 
 use crate::{
     chained_bft::liveness::pacemaker::{
-        ExponentialTimeInterval, NewRoundEvent, NewRoundReason, Pacemaker, PacemakerTimeInterval,
+        AdaptiveTimeInterval, ExponentialTimeInterval, NewRoundEvent, NewRoundReason, Pacemaker,
+        PacemakerTimeInterval,
     },
     util::mock_time_service::SimulatedTimeService,
 };
@@ -26,6 +27,44 @@ fn test_pacemaker_time_interval() {
     assert_eq!(6750, interval.get_round_duration(1000).as_millis());
 }
 
+#[test]
+fn test_adaptive_time_interval() {
+    let mut interval = AdaptiveTimeInterval::new(
+        Duration::from_millis(1000),
+        Duration::from_millis(100),
+        Duration::from_millis(5000),
+        1.5,
+        2,
+    );
+    // The initial base is used until the first QC latency is known
+    assert_eq!(1000, interval.get_round_duration(0).as_millis());
+    assert_eq!(1500, interval.get_round_duration(1).as_millis());
+
+    // The base is twice the p99 of the recent QC latencies
+    for _ in 0..99 {
+        interval.record_qc_latency(Duration::from_millis(200));
+    }
+    interval.record_qc_latency(Duration::from_millis(300));
+    assert_eq!(400, interval.get_round_duration(0).as_millis());
+    assert_eq!(900, interval.get_round_duration(2).as_millis());
+    assert_eq!(900, interval.get_round_duration(1000).as_millis());
+
+    // A single outlier is ignored, but not two of them
+    interval.record_qc_latency(Duration::from_millis(600));
+    assert_eq!(600, interval.get_round_duration(0).as_millis());
+    interval.record_qc_latency(Duration::from_millis(600));
+    assert_eq!(1200, interval.get_round_duration(0).as_millis());
+
+    // The base is bounded
+    interval.record_qc_latency(Duration::from_millis(10_000));
+    interval.record_qc_latency(Duration::from_millis(10_000));
+    assert_eq!(5000, interval.get_round_duration(0).as_millis());
+    for _ in 0..100 {
+        interval.record_qc_latency(Duration::from_millis(10));
+    }
+    assert_eq!(100, interval.get_round_duration(0).as_millis());
+}
+
 #[test]
 /// Verify that Pacemaker properly outputs local timeout events upon timeout
 fn test_basic_timeout() {
@@ -57,6 +96,89 @@ fn test_round_event_generation() {
     expect_qc(5, pm.process_certificates(Some(4), Some(4), None));
 }
 
+#[test]
+/// Simulates the rounds under a network latency that changes over time: the adaptive time
+/// interval should waste fewer rounds on timeouts than the exponential one with the same
+/// initial timeout.
+fn test_adaptive_time_interval_simulation() {
+    let latencies = simulated_qc_latencies();
+    let exponential = simulate_rounds(
+        Box::new(ExponentialTimeInterval::new(
+            Duration::from_millis(1000),
+            1.5,
+            6,
+        )),
+        &latencies,
+    );
+    let adaptive = simulate_rounds(
+        Box::new(AdaptiveTimeInterval::new(
+            Duration::from_millis(1000),
+            Duration::from_millis(100),
+            Duration::from_millis(10_000),
+            1.5,
+            6,
+        )),
+        &latencies,
+    );
+    assert!(
+        adaptive * 2 < exponential,
+        "adaptive wasted {} rounds, exponential wasted {} rounds",
+        adaptive,
+        exponential
+    );
+}
+
+/// Deterministic pseudo-random QC latencies (in ms): a fast network, followed by a period of
+/// degraded network with latencies varying around the initial timeout, and back.
+fn simulated_qc_latencies() -> Vec<u64> {
+    let mut seed: u64 = 42;
+    let mut next = move |low: u64, high: u64| {
+        seed = seed
+            .wrapping_mul(6_364_136_223_846_793_005)
+            .wrapping_add(1_442_695_040_888_963_407);
+        low + (seed >> 33) % (high - low)
+    };
+    let mut latencies = vec![];
+    latencies.extend((0..200).map(|_| next(100, 300)));
+    latencies.extend((0..400).map(|_| next(500, 1500)));
+    latencies.extend((0..200).map(|_| next(100, 300)));
+    latencies
+}
+
+/// Drives a Pacemaker through the rounds, each round forming a QC after the given latency unless
+/// the round times out first (in which case a TC is formed). Returns the number of rounds
+/// wasted on timeouts.
+fn simulate_rounds(time_interval: Box<dyn PacemakerTimeInterval>, latencies: &[u64]) -> usize {
+    let time_service = SimulatedTimeService::new();
+    let (timeout_tx, _timeout_rx) = channel::new_test(1_024);
+    let mut pm = Pacemaker::new(time_interval, Arc::new(time_service.clone()), timeout_tx);
+    let mut event = pm.process_certificates(Some(0), None, None).unwrap();
+    let mut consecutive_qcs = 0;
+    let mut wasted_rounds = 0;
+    for latency in latencies {
+        let round = event.round;
+        let latency = Duration::from_millis(*latency);
+        event = if latency <= event.timeout {
+            block_on(time_service.sleep(latency));
+            consecutive_qcs += 1;
+            // 3-chain commit rule
+            let committed_round = if consecutive_qcs >= 3 {
+                Some(round - 2)
+            } else {
+                None
+            };
+            pm.process_certificates(Some(round), None, committed_round)
+        } else {
+            block_on(time_service.sleep(event.timeout));
+            consecutive_qcs = 0;
+            wasted_rounds += 1;
+            pm.process_certificates(None, Some(round), None)
+        }
+        .unwrap();
+    }
+    wasted_rounds
+}
+
 fn make_pacemaker() -> (Pacemaker, channel::Receiver<Round>) {
     let time_interval = Box::new(ExponentialTimeInterval::fixed(Duration::from_millis(2)));
     let simulated_time = SimulatedTimeService::auto_advance_until(Duration::from_millis(4));
@@ -13,6 +13,7 @@ use consensus_types::{
 };
 use failure::ResultExt;
 use libra_logger::prelude::*;
+use safety_rules::BlockProposal;
 use std::{
     sync::{Arc, Mutex},
     time::{Duration, Instant},
@@ -32,8 +33,7 @@ mod proposal_generator_test;
 /// TxnManager should be aware of the pending transactions in the branch that it is extending,
 /// such that it will filter them out to avoid transaction duplication.
 pub struct ProposalGenerator<T> {
-    // Block store is queried both for finding the branch to extend and for generating the
-    // proposed block.
+    // Block store is queried for finding the branch to extend and the QC of its head.
     block_store: Arc<dyn BlockReader<Payload = T> + Send + Sync>,
     // Transaction manager is delivering the transactions.
     txn_manager: Arc<dyn TxnManager<Payload = T>>,
@@ -99,7 +99,7 @@ impl<T: Payload> ProposalGenerator<T> {
         &self,
         round: Round,
         round_deadline: Instant,
-    ) -> failure::Result<Block<T>> {
+    ) -> failure::Result<BlockProposal<T>> {
         {
             let mut last_round_generated = self.last_round_generated.lock().unwrap();
             if *last_round_generated < round {
@@ -210,11 +210,16 @@ impl<T: Payload> ProposalGenerator<T> {
                 .with_context(|e| format!("Fail to retrieve txn: {}", e))?
         };
 
-        Ok(self.block_store.create_block(
-            hqc_block.block(),
+        let quorum_cert = self
+            .block_store
+            .get_quorum_cert_for_block(hqc_block.id())
+            .ok_or_else(|| format_err!("Quorum Cert for HQC block not found"))?;
+        Ok(BlockProposal::new(
             txns,
+            hqc_block.block().epoch(),
             round,
             block_timestamp.as_micros() as u64,
+            quorum_cert.as_ref().clone(),
         ))
     }
 }
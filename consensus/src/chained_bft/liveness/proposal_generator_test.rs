@@ -34,8 +34,8 @@ fn test_proposal_generation_empty_tree() {
 
     // Generate proposals for an empty tree.
     let proposal = block_on(proposal_generator.generate_proposal(1, minute_from_now())).unwrap();
-    assert_eq!(proposal.parent_id(), genesis.id());
     assert_eq!(proposal.round(), 1);
+    assert_eq!(proposal.epoch(), genesis.block().epoch());
     assert_eq!(proposal.quorum_cert().certified_block().id(), genesis.id());
 
     // Duplicate proposals on the same round are not allowed
@@ -63,7 +63,9 @@ fn test_proposal_generation_parent() {
     assert_eq!(
         block_on(proposal_generator.generate_proposal(10, minute_from_now()))
             .unwrap()
-            .parent_id(),
+            .quorum_cert()
+            .certified_block()
+            .id(),
         genesis.id()
     );
 
@@ -71,7 +73,6 @@ fn test_proposal_generation_parent() {
     inserter.insert_qc_for_block(a1.as_ref(), None);
     let a1_child_res =
         block_on(proposal_generator.generate_proposal(11, minute_from_now())).unwrap();
-    assert_eq!(a1_child_res.parent_id(), a1.id());
     assert_eq!(a1_child_res.round(), 11);
     assert_eq!(a1_child_res.quorum_cert().certified_block().id(), a1.id());
 
@@ -79,7 +80,6 @@ fn test_proposal_generation_parent() {
     inserter.insert_qc_for_block(b1.as_ref(), None);
     let b1_child_res =
         block_on(proposal_generator.generate_proposal(12, minute_from_now())).unwrap();
-    assert_eq!(b1_child_res.parent_id(), b1.id());
     assert_eq!(b1_child_res.round(), 12);
     assert_eq!(b1_child_res.quorum_cert().certified_block().id(), b1.id());
 }
@@ -119,13 +119,13 @@ fn test_empty_proposal_after_reconfiguration() {
     // Normal proposal is not empty
     let normal_proposal =
         block_on(proposal_generator.generate_proposal(42, minute_from_now())).unwrap();
-    assert!(!normal_proposal.payload().unwrap().is_empty());
+    assert!(!normal_proposal.payload().is_empty());
     let a2 = inserter.insert_reconfiguration_block(&a1, 2);
     inserter.insert_qc_for_block(a2.as_ref(), None);
     // The direct child is empty
     let empty_proposal_1 =
         block_on(proposal_generator.generate_proposal(43, minute_from_now())).unwrap();
-    assert!(empty_proposal_1.payload().unwrap().is_empty());
+    assert!(empty_proposal_1.payload().is_empty());
     // insert one more block after reconfiguration
     let a3 = inserter.create_block_with_qc(
         inserter.create_qc_for_block(a2.as_ref(), None),
@@ -138,5 +138,5 @@ fn test_empty_proposal_after_reconfiguration() {
     // Indirect child is empty too
     let empty_proposal_2 =
         block_on(proposal_generator.generate_proposal(44, minute_from_now())).unwrap();
-    assert!(empty_proposal_2.payload().unwrap().is_empty());
+    assert!(empty_proposal_2.payload().is_empty());
 }
@@ -3,9 +3,11 @@
 
 mod consensusdb;
 
+mod batching;
 mod block_storage;
 pub mod chained_bft_consensus_provider;
 mod chained_bft_smr;
+mod equivocation_detector;
 mod network;
 
 pub mod epoch_manager;
@@ -18,6 +20,8 @@ mod chained_bft_smr_test;
 mod network_tests;
 #[cfg(test)]
 mod proto_test;
+#[cfg(test)]
+mod simulator;
 
 #[cfg(any(test, feature = "fuzzing"))]
 mod test_utils;
@@ -5,23 +5,32 @@ use crate::{chained_bft::epoch_manager::EpochManager, counters};
 use bytes::Bytes;
 use channel;
 use consensus_types::{
+    batch::{Batch, ProofOfStore, SignedBatchInfo},
     block::Block,
     common::{Author, Payload},
+    equivocation_evidence::EquivocationEvidence,
     proposal_msg::{ProposalMsg, ProposalUncheckedSignatures},
     sync_info::SyncInfo,
     vote_msg::VoteMsg,
 };
 use crypto::HashValue;
 use failure::{self, ResultExt};
-use futures::{channel::oneshot, stream::select, SinkExt, Stream, StreamExt, TryStreamExt};
+use futures::{
+    channel::{mpsc, oneshot},
+    stream::select,
+    SinkExt, Stream, StreamExt, TryStreamExt,
+};
 use libra_logger::prelude::*;
 use libra_types::account_address::AccountAddress;
 use network::{
     proto::{
-        BlockRetrievalStatus, ConsensusMsg, ConsensusMsg_oneof, Proposal, RequestBlock,
-        RespondBlock, SyncInfo as SyncInfoProto, Vote,
+        Batch as BatchProto, Block as BlockProto, BlockRetrievalStatus, ConsensusMsg,
+        ConsensusMsg_oneof, EquivocationEvidence as EquivocationEvidenceProto,
+        ProofOfStore as ProofOfStoreProto, Proposal, RequestBatch, RequestBlock, RespondBatch,
+        RespondBlock, SignedBatchInfo as SignedBatchInfoProto, SyncInfo as SyncInfoProto, Vote,
     },
     validator_network::{ConsensusNetworkEvents, ConsensusNetworkSender, Event, RpcError},
+    Misbehavior,
 };
 use prost_ext::MessageExt;
 use std::{
@@ -31,6 +40,9 @@ use std::{
 };
 use tokio::runtime::TaskExecutor;
 
+/// Maximum number of blocks sent in each chunk of a block retrieval response.
+const BLOCKS_PER_CHUNK: usize = 10;
+
 /// The response sent back from EventProcessor for the BlockRetrievalRequest.
 #[derive(Debug)]
 pub struct BlockRetrievalResponse<T> {
@@ -71,6 +83,14 @@ pub struct BlockRetrievalRequest<T> {
     pub response_sender: oneshot::Sender<BlockRetrievalResponse<T>>,
 }
 
+/// BatchRetrievalRequest carries the digest of the requested batch as well as the oneshot sender
+/// to deliver the batch (if found).
+#[derive(Debug)]
+pub struct BatchRetrievalRequest {
+    pub digest: HashValue,
+    pub response_sender: oneshot::Sender<Option<Batch>>,
+}
+
 /// Just a convenience struct to keep all the network proxy receiving queues in one place.
 /// Will be returned by the networking trait upon startup.
 pub struct NetworkReceivers<T> {
@@ -78,6 +98,27 @@ pub struct NetworkReceivers<T> {
     pub votes: channel::Receiver<VoteMsg>,
     pub block_retrieval: channel::Receiver<BlockRetrievalRequest<T>>,
     pub sync_info_msgs: channel::Receiver<(SyncInfo, AccountAddress)>,
+    pub equivocation_evidence: channel::Receiver<EquivocationEvidence<T>>,
+}
+
+/// Error of a message whose signatures failed to verify: its sender gets reported for an invalid
+/// signature rather than for a malformed message.
+#[derive(Debug, Fail)]
+#[fail(display = "{}", _0)]
+struct InvalidSignatures(failure::Error);
+
+/// Error of a message which failed to deserialize: its sender gets reported for a malformed
+/// message. The other processing failures (e.g. a stale message) are not reported.
+#[derive(Debug, Fail)]
+#[fail(display = "{}", _0)]
+struct DecodingError(failure::Error);
+
+/// The receiving queues of the batch dissemination messages (consumed by the BatchCoordinator).
+pub struct BatchNetworkReceivers {
+    pub batches: channel::Receiver<Batch>,
+    pub signed_batch_infos: channel::Receiver<SignedBatchInfo>,
+    pub proofs_of_store: channel::Receiver<ProofOfStore>,
+    pub batch_retrieval: channel::Receiver<BatchRetrievalRequest>,
 }
 
 /// Implements the actual networking support for all consensus messaging.
@@ -90,6 +131,9 @@ pub struct ConsensusNetworkImpl {
     // Note that we do not support self rpc requests as it might cause infinite recursive calls.
     self_sender: channel::Sender<failure::Result<Event<ConsensusMsg>>>,
     self_receiver: Option<channel::Receiver<failure::Result<Event<ConsensusMsg>>>>,
+    // The receivers of the batch dissemination messages are created upon start and taken by the
+    // BatchCoordinator (if any).
+    batch_receivers: Option<BatchNetworkReceivers>,
     epoch_mgr: Arc<EpochManager>,
 }
 
@@ -101,6 +145,7 @@ impl Clone for ConsensusNetworkImpl {
             network_events: None,
             self_sender: self.self_sender.clone(),
             self_receiver: None,
+            batch_receivers: None,
             epoch_mgr: Arc::clone(&self.epoch_mgr),
         }
     }
@@ -120,6 +165,7 @@ impl ConsensusNetworkImpl {
             network_events: Some(network_events),
             self_sender,
             self_receiver: Some(self_receiver),
+            batch_receivers: None,
             epoch_mgr,
         }
     }
@@ -131,6 +177,15 @@ impl ConsensusNetworkImpl {
         let (block_request_tx, block_request_rx) =
             channel::new(1_024, &counters::PENDING_BLOCK_REQUESTS);
         let (sync_info_tx, sync_info_rx) = channel::new(1_024, &counters::PENDING_SYNC_INFO_MSGS);
+        let (equivocation_evidence_tx, equivocation_evidence_rx) =
+            channel::new(1_024, &counters::PENDING_EQUIVOCATION_EVIDENCE);
+        let (batch_tx, batch_rx) = channel::new(1_024, &counters::PENDING_BATCHES);
+        let (signed_batch_info_tx, signed_batch_info_rx) =
+            channel::new(1_024, &counters::PENDING_SIGNED_BATCH_INFOS);
+        let (proof_of_store_tx, proof_of_store_rx) =
+            channel::new(1_024, &counters::PENDING_PROOFS_OF_STORE);
+        let (batch_request_tx, batch_request_rx) =
+            channel::new(1_024, &counters::PENDING_BATCH_REQUESTS);
         let network_events = self
             .network_events
             .take()
@@ -141,14 +196,28 @@ impl ConsensusNetworkImpl {
             .take()
             .expect("[consensus]: self receiver is already taken");
         let all_events = select(network_events, own_msgs);
+        self.batch_receivers = Some(BatchNetworkReceivers {
+            batches: batch_rx,
+            signed_batch_infos: signed_batch_info_rx,
+            proofs_of_store: proof_of_store_rx,
+            batch_retrieval: batch_request_rx,
+        });
         executor.spawn(
             NetworkTask {
                 proposal_tx,
                 vote_tx,
                 block_request_tx,
                 sync_info_tx,
+                equivocation_evidence_tx,
+                batch_tx,
+                signed_batch_info_tx,
+                proof_of_store_tx,
+                batch_request_tx,
                 all_events,
+                author: self.author,
+                network_sender: self.network_sender.clone(),
                 epoch_mgr: Arc::clone(&self.epoch_mgr),
+                executor: executor.clone(),
             }
             .run(),
         );
@@ -157,9 +226,26 @@ impl ConsensusNetworkImpl {
             votes: vote_rx,
             block_retrieval: block_request_rx,
             sync_info_msgs: sync_info_rx,
+            equivocation_evidence: equivocation_evidence_rx,
         }
     }
 
+    /// Takes the receivers of the batch dissemination messages (available once started).
+    pub fn take_batch_receivers(&mut self) -> Option<BatchNetworkReceivers> {
+        self.batch_receivers.take()
+    }
+
+    /// Takes the receiver of the messages sent to self without starting the network task: the
+    /// caller is then responsible for delivering them (used by the consensus simulator).
+    #[cfg(test)]
+    pub fn take_self_receiver(
+        &mut self,
+    ) -> channel::Receiver<failure::Result<Event<ConsensusMsg>>> {
+        self.self_receiver
+            .take()
+            .expect("[consensus]: self receiver is already taken")
+    }
+
     /// Tries to retrieve num of blocks backwards starting from id from the given peer: the function
     /// returns a future that is either fulfilled with BlockRetrievalResponse, or with a
     /// BlockRetrievalFailure.
@@ -177,10 +263,19 @@ impl ConsensusNetworkImpl {
         counters::BLOCK_RETRIEVAL_COUNT.inc_by(num_blocks as i64);
         let pre_retrieval_instant = Instant::now();
 
-        let res_block = self
+        let res_block = match self
             .network_sender
             .request_block(from, req_msg, timeout)
-            .await?;
+            .await
+        {
+            Ok(res_block) => res_block,
+            Err(e) => {
+                if let RpcError::TimedOut = e {
+                    report_peer(&mut self.network_sender, from, Misbehavior::RpcTimeout).await;
+                }
+                return Err(e.into());
+            }
+        };
         let mut blocks = vec![];
         let status = res_block.status();
         for block in res_block.blocks.into_iter() {
@@ -201,6 +296,34 @@ impl ConsensusNetworkImpl {
         Ok(response)
     }
 
+    /// Tries to retrieve the batch with the given digest from the given peer.
+    pub async fn request_batch(
+        &mut self,
+        digest: HashValue,
+        from: Author,
+        timeout: Duration,
+    ) -> failure::Result<Batch> {
+        ensure!(from != self.author, "Retrieve batch from self");
+        let mut req_msg = RequestBatch::default();
+        req_msg.digest = digest.to_vec();
+        let response = self
+            .network_sender
+            .request_batch(from, req_msg, timeout)
+            .await?;
+        let batch = Batch::try_from(
+            response
+                .batch
+                .ok_or_else(|| format_err!("Batch {} not found", digest))?,
+        )?;
+        ensure!(
+            batch.digest() == digest,
+            "Batch digest mismatch: expect {}, get {}",
+            digest,
+            batch.digest()
+        );
+        Ok(batch)
+    }
+
     /// Tries to send the given proposal (block and proposer metadata) to all the participants.
     /// A validator on the receiving end is going to be notified about a new proposal in the
     /// proposal queue.
@@ -271,6 +394,55 @@ impl ConsensusNetworkImpl {
         self.broadcast(msg).await
     }
 
+    /// Broadcasts the evidence of an equivocating validator to all validators.
+    pub async fn broadcast_equivocation_evidence<T: Payload>(
+        &mut self,
+        evidence: EquivocationEvidence<T>,
+    ) {
+        let msg = ConsensusMsg {
+            message: Some(ConsensusMsg_oneof::EquivocationEvidence(evidence.into())),
+        };
+        self.broadcast(msg).await
+    }
+
+    /// Broadcasts a new batch to all validators (including itself), which are expected to reply
+    /// with their signatures of the batch info.
+    pub async fn broadcast_batch(&mut self, batch: Batch) {
+        let msg = ConsensusMsg {
+            message: Some(ConsensusMsg_oneof::Batch(batch.into())),
+        };
+        self.broadcast(msg).await
+    }
+
+    /// Sends the signature of the batch info to the author of the batch.
+    pub async fn send_signed_batch_info(&self, signed_info: SignedBatchInfo, recipient: Author) {
+        let msg = ConsensusMsg {
+            message: Some(ConsensusMsg_oneof::SignedBatchInfo(signed_info.into())),
+        };
+        if recipient == self.author {
+            let self_msg = Event::Message((self.author, msg));
+            if let Err(err) = self.self_sender.clone().send(Ok(self_msg)).await {
+                error!("Error delivering a self signed batch info: {:?}", err);
+            }
+            return;
+        }
+        if let Err(e) = self.network_sender.clone().send_to(recipient, msg).await {
+            warn!(
+                "Failed to send a signed batch info to peer {:?}: {:?}",
+                recipient, e
+            );
+        }
+    }
+
+    /// Broadcasts the proof of store of a batch to all validators (including itself), so that
+    /// any proposer can include it in its proposals.
+    pub async fn broadcast_proof_of_store(&mut self, proof: ProofOfStore) {
+        let msg = ConsensusMsg {
+            message: Some(ConsensusMsg_oneof::ProofOfStore(proof.into())),
+        };
+        self.broadcast(msg).await
+    }
+
     /// Sends the given sync info to the given author.
     /// The future is fulfilled as soon as the message is added to the internal network channel
     /// (does not indicate whether the message is delivered or sent out).
@@ -297,8 +469,18 @@ struct NetworkTask<T, S> {
     vote_tx: channel::Sender<VoteMsg>,
     block_request_tx: channel::Sender<BlockRetrievalRequest<T>>,
     sync_info_tx: channel::Sender<(SyncInfo, AccountAddress)>,
+    equivocation_evidence_tx: channel::Sender<EquivocationEvidence<T>>,
+    batch_tx: channel::Sender<Batch>,
+    signed_batch_info_tx: channel::Sender<SignedBatchInfo>,
+    proof_of_store_tx: channel::Sender<ProofOfStore>,
+    batch_request_tx: channel::Sender<BatchRetrievalRequest>,
     all_events: S,
+    author: Author,
+    // Used to report the peers sending invalid messages.
+    network_sender: ConsensusNetworkSender,
     epoch_mgr: Arc<EpochManager>,
+    // Used to stream the responses of the streaming rpcs without holding up the other messages.
+    executor: TaskExecutor,
 }
 
 impl<T, S> NetworkTask<T, S>
@@ -329,19 +511,40 @@ where
                         }),
                         Vote(vote) => self.process_vote(vote).await,
                         SyncInfo(sync_info) => self.process_sync_info(sync_info, peer_id).await,
+                        EquivocationEvidence(evidence) => {
+                            self.process_equivocation_evidence(evidence).await
+                        }
+                        Batch(batch) => self.process_batch(batch, peer_id).await,
+                        SignedBatchInfo(signed_info) => {
+                            self.process_signed_batch_info(signed_info).await
+                        }
+                        ProofOfStore(proof) => self.process_proof_of_store(proof).await,
                         _ => {
                             warn!("Unexpected msg from {}: {:?}", peer_id, msg);
                             continue;
                         }
                     };
                     if let Err(e) = r {
-                        warn!("Failed to process msg {}", e)
+                        warn!("Failed to process msg {}", e);
+                        let misbehavior = if e.downcast_ref::<InvalidSignatures>().is_some() {
+                            Some(Misbehavior::InvalidSignature)
+                        } else if e.downcast_ref::<DecodingError>().is_some() {
+                            Some(Misbehavior::MalformedMessage)
+                        } else {
+                            None
+                        };
+                        match misbehavior {
+                            Some(misbehavior) if peer_id != self.author => {
+                                report_peer(&mut self.network_sender, peer_id, misbehavior).await;
+                            }
+                            _ => (),
+                        }
                     }
                 }
                 Event::RpcRequest((peer_id, msg, callback)) => {
                     let r = match msg.message {
-                        Some(RequestBlock(request)) => {
-                            self.process_request_block(request, callback).await
+                        Some(RequestBatch(request)) => {
+                            self.process_request_batch(request, callback).await
                         }
                         _ => {
                             warn!("Unexpected RPC from {}: {:?}", peer_id, msg);
@@ -352,6 +555,20 @@ where
                         warn!("Failed to process RPC {:?}", e)
                     }
                 }
+                Event::StreamingRpcRequest((peer_id, msg, res_tx)) => {
+                    let r = match msg.message {
+                        Some(RequestBlock(request)) => {
+                            self.process_request_block(request, res_tx).await
+                        }
+                        _ => {
+                            warn!("Unexpected streaming RPC from {}: {:?}", peer_id, msg);
+                            continue;
+                        }
+                    };
+                    if let Err(e) = r {
+                        warn!("Failed to process streaming RPC {:?}", e)
+                    }
+                }
                 Event::NewPeer(peer_id) => {
                     debug!("Peer {} connected", peer_id);
                 }
@@ -363,9 +580,11 @@ where
     }
 
     async fn process_proposal(&mut self, proposal: Proposal) -> failure::Result<()> {
-        let proposal = ProposalUncheckedSignatures::<T>::try_from(proposal)?;
+        let proposal =
+            ProposalUncheckedSignatures::<T>::try_from(proposal).map_err(DecodingError)?;
         let proposal = proposal
-            .validate_signatures(self.epoch_mgr.validators().as_ref())?
+            .validate_signatures(self.epoch_mgr.validators().as_ref())
+            .map_err(InvalidSignatures)?
             .verify_well_formed()?;
         debug!("Received proposal {}", proposal);
         if self.proposal_tx.try_send(proposal).is_err() {
@@ -375,7 +594,7 @@ where
     }
 
     async fn process_vote(&mut self, vote: Vote) -> failure::Result<()> {
-        let vote = VoteMsg::try_from(vote)?;
+        let vote = VoteMsg::try_from(vote).map_err(DecodingError)?;
         debug!("Received {}", vote);
         vote.verify(self.epoch_mgr.validators().as_ref())
             .map_err(|e| {
@@ -383,7 +602,7 @@ where
                     .error(&e)
                     .data(&vote)
                     .log();
-                e
+                InvalidSignatures(e)
             })?;
         if self.vote_tx.try_send(vote).is_err() {
             counters::DROP_NETWORK_TO_CONSENSUS.inc();
@@ -396,7 +615,7 @@ where
         sync_info: SyncInfoProto,
         peer: AccountAddress,
     ) -> failure::Result<()> {
-        let sync_info = SyncInfo::try_from(sync_info)?;
+        let sync_info = SyncInfo::try_from(sync_info).map_err(DecodingError)?;
         sync_info
             .verify(self.epoch_mgr.validators().as_ref())
             .map_err(|e| {
@@ -404,16 +623,90 @@ where
                     .error(&e)
                     .data(&sync_info)
                     .log();
-                e
+                InvalidSignatures(e)
+            })?;
+        if self.sync_info_tx.try_send((sync_info, peer)).is_err() {
+            counters::DROP_NETWORK_TO_CONSENSUS.inc();
+        }
+        Ok(())
+    }
+
+    async fn process_equivocation_evidence(
+        &mut self,
+        evidence: EquivocationEvidenceProto,
+    ) -> failure::Result<()> {
+        let evidence = EquivocationEvidence::<T>::try_from(evidence).map_err(DecodingError)?;
+        evidence
+            .verify(self.epoch_mgr.validators().as_ref())
+            .map_err(|e| {
+                security_log(SecurityEvent::InvalidEquivocationEvidence)
+                    .error(&e)
+                    .data(&evidence)
+                    .log();
+                InvalidSignatures(e)
             })?;
-        self.sync_info_tx.try_send((sync_info, peer))?;
+        if self.equivocation_evidence_tx.try_send(evidence).is_err() {
+            counters::DROP_NETWORK_TO_CONSENSUS.inc();
+        }
+        Ok(())
+    }
+
+    async fn process_batch(
+        &mut self,
+        batch: BatchProto,
+        peer: AccountAddress,
+    ) -> failure::Result<()> {
+        let batch = Batch::try_from(batch).map_err(DecodingError)?;
+        ensure!(
+            batch.author() == peer,
+            "Batch author {} differs from the sender {}",
+            batch.author(),
+            peer
+        );
+        ensure!(
+            self.epoch_mgr
+                .validators()
+                .get_public_key(&batch.author())
+                .is_some(),
+            "Batch author {} is not a validator",
+            batch.author()
+        );
+        debug!("Received {}", batch);
+        if self.batch_tx.try_send(batch).is_err() {
+            counters::DROP_NETWORK_TO_CONSENSUS.inc();
+        }
+        Ok(())
+    }
+
+    async fn process_signed_batch_info(
+        &mut self,
+        signed_info: SignedBatchInfoProto,
+    ) -> failure::Result<()> {
+        let signed_info = SignedBatchInfo::try_from(signed_info).map_err(DecodingError)?;
+        signed_info
+            .verify(self.epoch_mgr.validators().as_ref())
+            .map_err(InvalidSignatures)?;
+        if self.signed_batch_info_tx.try_send(signed_info).is_err() {
+            counters::DROP_NETWORK_TO_CONSENSUS.inc();
+        }
+        Ok(())
+    }
+
+    async fn process_proof_of_store(&mut self, proof: ProofOfStoreProto) -> failure::Result<()> {
+        let proof = ProofOfStore::try_from(proof).map_err(DecodingError)?;
+        proof
+            .verify(self.epoch_mgr.validators().as_ref())
+            .map_err(InvalidSignatures)?;
+        if self.proof_of_store_tx.try_send(proof).is_err() {
+            counters::DROP_NETWORK_TO_CONSENSUS.inc();
+        }
         Ok(())
     }
 
     async fn process_request_block(
         &mut self,
         request: RequestBlock,
-        callback: oneshot::Sender<Result<Bytes, RpcError>>,
+        mut res_tx: mpsc::Sender<Result<Bytes, RpcError>>,
     ) -> failure::Result<()> {
         let block_id = HashValue::from_slice(&request.block_id[..])?;
         let num_blocks = request.num_blocks;
@@ -431,11 +724,53 @@ where
             counters::DROP_NETWORK_TO_CONSENSUS.inc();
         }
         let BlockRetrievalResponse { status, blocks } = rx.await?;
-        let mut response = RespondBlock::default();
-        response.set_status(status);
-        response.blocks = blocks.into_iter().map(Into::into).collect();
+        // Every chunk carries the status, so a response without blocks is still one chunk.
+        let mut blocks = blocks.into_iter().map(BlockProto::from).peekable();
+        let mut chunks = vec![];
+        loop {
+            let mut response = RespondBlock::default();
+            response.set_status(status);
+            response.blocks = blocks.by_ref().take(BLOCKS_PER_CHUNK).collect();
+            let response_msg = ConsensusMsg {
+                message: Some(ConsensusMsg_oneof::RespondBlock(response)),
+            };
+            chunks.push(response_msg.to_bytes()?);
+            if blocks.peek().is_none() {
+                break;
+            }
+        }
+        // The chunks are sent as fast as the requester reads them, on a separate task.
+        self.executor.spawn(async move {
+            for chunk in chunks {
+                if res_tx.send(Ok(chunk)).await.is_err() {
+                    warn!("Block retrieval streaming RPC was canceled");
+                    return;
+                }
+            }
+        });
+        Ok(())
+    }
+
+    async fn process_request_batch(
+        &mut self,
+        request: RequestBatch,
+        callback: oneshot::Sender<Result<Bytes, RpcError>>,
+    ) -> failure::Result<()> {
+        let digest = HashValue::from_slice(&request.digest[..])?;
+        debug!("Received request_batch RPC for {}", digest);
+        let (tx, rx) = oneshot::channel();
+        let request = BatchRetrievalRequest {
+            digest,
+            response_sender: tx,
+        };
+        if self.batch_request_tx.try_send(request).is_err() {
+            counters::DROP_NETWORK_TO_CONSENSUS.inc();
+        }
+        let response = RespondBatch {
+            batch: rx.await?.map(Into::into),
+        };
         let response_msg = ConsensusMsg {
-            message: Some(ConsensusMsg_oneof::RespondBlock(response)),
+            message: Some(ConsensusMsg_oneof::RespondBatch(response)),
         };
         let response_data = response_msg.to_bytes()?;
         callback
@@ -443,3 +778,15 @@ where
             .map_err(|_| format_err!("handling inbound rpc call timed out"))
     }
 }
+
+// Reports a misbehavior of the given peer to the network, which bans it once it misbehaved too
+// much.
+async fn report_peer(
+    network_sender: &mut ConsensusNetworkSender,
+    peer_id: Author,
+    misbehavior: Misbehavior,
+) {
+    if let Err(e) = network_sender.report_peer(peer_id, misbehavior).await {
+        warn!("Failed to report peer {}: {:?}", peer_id, e);
+    }
+}
@@ -8,14 +8,15 @@ use crate::chained_bft::{
 };
 use channel;
 use consensus_types::{
-    block::Block, block_info::BlockInfo, common::Author, proposal_msg::ProposalMsg,
+    batch::Batch, block::Block, block_info::BlockInfo, common::Author, proposal_msg::ProposalMsg,
     quorum_cert::QuorumCert, sync_info::SyncInfo, vote_data::VoteData, vote_msg::VoteMsg,
 };
+use crypto::HashValue;
 use futures::{channel::mpsc, executor::block_on, SinkExt, StreamExt};
 use network::{
     interface::{NetworkNotification, NetworkRequest},
     proto::{BlockRetrievalStatus, ConsensusMsg, ConsensusMsg_oneof},
-    protocols::rpc::InboundRpcRequest,
+    protocols::rpc::{streaming::InboundStreamingRpcRequest, InboundRpcRequest},
     validator_network::{ConsensusNetworkEvents, ConsensusNetworkSender},
 };
 use prost::Message;
@@ -116,6 +117,30 @@ impl NetworkPlayground {
                         .await
                         .unwrap();
                 }
+                NetworkRequest::SendStreamingRpc(dst, outbound_req) => {
+                    if drop_rpc {
+                        continue;
+                    }
+                    let mut node_consensus_tx = node_consensus_txs
+                        .lock()
+                        .unwrap()
+                        .get(&dst)
+                        .unwrap()
+                        .clone();
+
+                    let inbound_req = InboundStreamingRpcRequest {
+                        protocol: outbound_req.protocol,
+                        data: outbound_req.data,
+                        res_tx: outbound_req.res_tx,
+                    };
+
+                    node_consensus_tx
+                        .send(NetworkNotification::RecvStreamingRpc(src, inbound_req))
+                        .await
+                        .unwrap();
+                }
+                // The playground does not keep track of the peers' reputation.
+                NetworkRequest::ReportPeer(_, _) => (),
                 // Other NetworkRequest get buffered for `deliver_messages` to
                 // synchronously drain.
                 net_req => {
@@ -300,6 +325,7 @@ impl DropConfig {
         match net_req {
             NetworkRequest::SendMessage(dst, _) => self.0.get(src).unwrap().contains(&dst),
             NetworkRequest::SendRpc(dst, _) => self.0.get(src).unwrap().contains(&dst),
+            NetworkRequest::SendStreamingRpc(dst, _) => self.0.get(src).unwrap().contains(&dst),
             _ => true,
         }
     }
@@ -434,3 +460,58 @@ fn test_rpc() {
         assert_eq!(response.blocks[0], *genesis);
     });
 }
+
+#[test]
+fn test_batch_rpc() {
+    let runtime = consensus_runtime();
+    let num_nodes = 2;
+    let mut playground = NetworkPlayground::new(runtime.executor());
+    let mut nodes = Vec::new();
+    let (signers, validator_verifier) = random_validator_verifier(num_nodes, None, false);
+    let peers: Vec<_> = signers.iter().map(|signer| signer.author()).collect();
+    let epoch_mgr = Arc::new(EpochManager::new(0, validator_verifier));
+    for peer in peers.iter() {
+        let (network_reqs_tx, network_reqs_rx) = channel::new_test(8);
+        let (consensus_tx, consensus_rx) = channel::new_test(8);
+        let network_sender = ConsensusNetworkSender::new(network_reqs_tx);
+        let network_events = ConsensusNetworkEvents::new(consensus_rx);
+
+        playground.add_node(*peer, consensus_tx, network_reqs_rx);
+        let mut node = ConsensusNetworkImpl::new(
+            *peer,
+            network_sender,
+            network_events,
+            Arc::clone(&epoch_mgr),
+        );
+        node.start::<u64>(&runtime.executor());
+        nodes.push(node);
+    }
+    let batch = Batch::new(peers[1], 1, 1, vec![1, 2, 3]);
+    let batch_clone = batch.clone();
+
+    // verify request batch rpc: only the known batch is returned
+    let mut batch_retrieval = nodes[1].take_batch_receivers().unwrap().batch_retrieval;
+    let on_request_batch = async move {
+        while let Some(request) = batch_retrieval.next().await {
+            let response = if request.digest == batch_clone.digest() {
+                Some(batch_clone.clone())
+            } else {
+                None
+            };
+            request.response_sender.send(response).unwrap();
+        }
+    };
+    runtime.executor().spawn(on_request_batch);
+    let peer = peers[1];
+    block_on(async move {
+        let response = nodes[0]
+            .request_batch(batch.digest(), peer, Duration::from_secs(5))
+            .await
+            .unwrap();
+        assert_eq!(response, batch);
+        assert!(nodes[0]
+            .request_batch(HashValue::zero(), peer, Duration::from_secs(5))
+            .await
+            .is_err());
+    });
+}
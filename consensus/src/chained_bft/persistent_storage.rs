@@ -6,10 +6,10 @@ use crate::{
 };
 use config::config::NodeConfig;
 use consensus_types::{
-    block::Block, common::Payload, quorum_cert::QuorumCert,
-    timeout_certificate::TimeoutCertificate, vote_msg::VoteMsg,
+    block::Block, common::Payload, equivocation_evidence::EquivocationEvidence,
+    quorum_cert::QuorumCert, timeout_certificate::TimeoutCertificate, vote_msg::VoteMsg,
 };
-use crypto::HashValue;
+use crypto::{hash::CryptoHash, HashValue};
 use failure::{Result, ResultExt};
 use libra_logger::prelude::*;
 use libra_types::ledger_info::LedgerInfo;
@@ -51,6 +51,13 @@ pub trait PersistentStorage<T>: PersistentLivenessStorage + Send + Sync {
     /// Persist the consensus state.
     fn save_consensus_state(&self, state: ConsensusState, vote_msg: VoteMsg) -> Result<()>;
 
+    /// Persist the evidence of an equivocating validator. The evidence is kept until it is
+    /// explicitly consumed (e.g., submitted on chain), it is not affected by tree pruning.
+    fn save_equivocation_evidence(&self, evidence: EquivocationEvidence<T>) -> Result<()>;
+
+    /// Retrieve all the persisted equivocation evidence.
+    fn retrieve_equivocation_evidence(&self) -> Result<Vec<EquivocationEvidence<T>>>;
+
     /// When the node restart, construct the instance and returned the data read from db.
     /// This could guarantee we only read once during start, and we would panic if the
     /// read fails.
@@ -331,6 +338,19 @@ impl<T: Payload> PersistentStorage<T> for StorageWriteProxy {
             .save_state(to_vec_named(&state)?, to_vec_named(&vote_msg)?)
     }
 
+    fn save_equivocation_evidence(&self, evidence: EquivocationEvidence<T>) -> Result<()> {
+        self.db
+            .save_equivocation_evidence(evidence.hash(), to_vec_named(&evidence)?)
+    }
+
+    fn retrieve_equivocation_evidence(&self) -> Result<Vec<EquivocationEvidence<T>>> {
+        self.db
+            .get_equivocation_evidence()?
+            .into_iter()
+            .map(|(_id, evidence)| Ok(from_slice(&evidence[..])?))
+            .collect()
+    }
+
     fn start(config: &NodeConfig) -> (Arc<Self>, RecoveryData<T>) {
         info!("Start consensus recovery.");
         let read_client = create_storage_read_client(config);
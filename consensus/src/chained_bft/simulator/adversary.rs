@@ -0,0 +1,232 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::chained_bft::test_utils::TestPayload;
+use consensus_types::{
+    block::Block,
+    common::{Author, Round},
+    proposal_msg::{ProposalMsg, ProposalUncheckedSignatures},
+};
+use libra_types::crypto_proxies::ValidatorSigner;
+use network::proto::{ConsensusMsg, ConsensusMsg_oneof};
+use rand::{rngs::StdRng, Rng};
+use std::{collections::HashSet, convert::TryFrom, time::Duration};
+
+/// A message in flight between two different nodes.
+#[derive(Clone, Debug)]
+pub struct Envelope {
+    pub src: Author,
+    pub dst: Author,
+    pub msg: ConsensusMsg,
+}
+
+impl Envelope {
+    pub fn is_proposal(&self) -> bool {
+        match self.msg.message {
+            Some(ConsensusMsg_oneof::Proposal(_)) => true,
+            _ => false,
+        }
+    }
+
+    pub fn is_vote(&self) -> bool {
+        match self.msg.message {
+            Some(ConsensusMsg_oneof::Vote(_)) => true,
+            _ => false,
+        }
+    }
+}
+
+/// What happens to a message in flight.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Fate {
+    /// Delivered after the network latency.
+    Deliver,
+    /// Delivered after the network latency plus the given delay.
+    Delay(Duration),
+    /// Never delivered.
+    Drop,
+}
+
+/// An adversary controls the network and the local clocks of the simulated nodes: it can drop,
+/// delay or tamper with the messages in flight and delay the local timeouts.
+/// All the randomness must come from the given rng so that a run can be replayed from its seed.
+///
+/// Messages sent by a node to itself and block retrieval RPCs are not subject to the adversaries.
+pub trait Adversary {
+    /// Decides the fate of a message sent over the network, possibly modifying it.
+    fn on_message(&mut self, _rng: &mut StdRng, _now: Duration, _envelope: &mut Envelope) -> Fate {
+        Fate::Deliver
+    }
+
+    /// Returns the delay of a local timeout of the given node.
+    fn on_local_timeout(
+        &mut self,
+        _rng: &mut StdRng,
+        _now: Duration,
+        _node: Author,
+        _round: Round,
+    ) -> Duration {
+        Duration::from_secs(0)
+    }
+}
+
+/// Splits the nodes into groups that cannot communicate with each other during [start, end).
+pub struct Partition {
+    groups: Vec<HashSet<Author>>,
+    start: Duration,
+    end: Duration,
+}
+
+impl Partition {
+    pub fn new(groups: Vec<Vec<Author>>, start: Duration, end: Duration) -> Self {
+        Self {
+            groups: groups
+                .into_iter()
+                .map(|group| group.into_iter().collect())
+                .collect(),
+            start,
+            end,
+        }
+    }
+}
+
+impl Adversary for Partition {
+    fn on_message(&mut self, _rng: &mut StdRng, now: Duration, envelope: &mut Envelope) -> Fate {
+        if now < self.start || now >= self.end {
+            return Fate::Deliver;
+        }
+        let connected = self
+            .groups
+            .iter()
+            .any(|group| group.contains(&envelope.src) && group.contains(&envelope.dst));
+        if connected {
+            Fate::Deliver
+        } else {
+            Fate::Drop
+        }
+    }
+}
+
+/// Drops each vote with the given probability.
+pub struct DropVotes {
+    probability: f64,
+}
+
+impl DropVotes {
+    pub fn new(probability: f64) -> Self {
+        Self { probability }
+    }
+}
+
+impl Adversary for DropVotes {
+    fn on_message(&mut self, rng: &mut StdRng, _now: Duration, envelope: &mut Envelope) -> Fate {
+        if envelope.is_vote() && rng.gen_bool(self.probability) {
+            Fate::Drop
+        } else {
+            Fate::Deliver
+        }
+    }
+}
+
+/// Delays each message by up to the given duration, which reorders the messages.
+pub struct DelayMessages {
+    max_delay: Duration,
+}
+
+impl DelayMessages {
+    pub fn new(max_delay: Duration) -> Self {
+        Self { max_delay }
+    }
+}
+
+impl Adversary for DelayMessages {
+    fn on_message(&mut self, rng: &mut StdRng, _now: Duration, _envelope: &mut Envelope) -> Fate {
+        let max_delay_ms = self.max_delay.as_millis() as u64;
+        Fate::Delay(Duration::from_millis(rng.gen_range(0, max_delay_ms + 1)))
+    }
+}
+
+/// Delays the local timeouts of the given nodes.
+pub struct DelayTimeouts {
+    nodes: HashSet<Author>,
+    delay: Duration,
+}
+
+impl DelayTimeouts {
+    pub fn new(nodes: Vec<Author>, delay: Duration) -> Self {
+        Self {
+            nodes: nodes.into_iter().collect(),
+            delay,
+        }
+    }
+}
+
+impl Adversary for DelayTimeouts {
+    fn on_local_timeout(
+        &mut self,
+        _rng: &mut StdRng,
+        _now: Duration,
+        node: Author,
+        _round: Round,
+    ) -> Duration {
+        if self.nodes.contains(&node) {
+            self.delay
+        } else {
+            Duration::from_secs(0)
+        }
+    }
+}
+
+/// A Byzantine proposer: the proposals of the given node are replaced by a conflicting block
+/// (same round and parent, different payload) for the recipients with an odd position in the
+/// list of the validators, hence the honest nodes see different proposals for the same round.
+pub struct EquivocatingProposer {
+    signer: ValidatorSigner,
+    odd_recipients: HashSet<Author>,
+}
+
+impl EquivocatingProposer {
+    pub fn new(signer: ValidatorSigner, validators: &[Author]) -> Self {
+        Self {
+            signer,
+            odd_recipients: validators.iter().skip(1).step_by(2).cloned().collect(),
+        }
+    }
+
+    fn conflicting_proposal(&self, msg: &ConsensusMsg) -> Option<ConsensusMsg> {
+        let proposal = match &msg.message {
+            Some(ConsensusMsg_oneof::Proposal(proposal)) => proposal.clone(),
+            _ => return None,
+        };
+        let proposal: ProposalMsg<TestPayload> =
+            ProposalUncheckedSignatures::try_from(proposal).ok()?.into();
+        let block = proposal.proposal();
+        let conflicting_block = Block::new_internal(
+            vec![],
+            block.epoch(),
+            block.round(),
+            block.timestamp_usecs(),
+            block.quorum_cert().clone(),
+            &self.signer,
+        );
+        let conflicting_proposal =
+            ProposalMsg::new(conflicting_block, proposal.sync_info().clone());
+        Some(ConsensusMsg {
+            message: Some(ConsensusMsg_oneof::Proposal(conflicting_proposal.into())),
+        })
+    }
+}
+
+impl Adversary for EquivocatingProposer {
+    fn on_message(&mut self, _rng: &mut StdRng, _now: Duration, envelope: &mut Envelope) -> Fate {
+        if envelope.src == self.signer.author()
+            && envelope.is_proposal()
+            && self.odd_recipients.contains(&envelope.dst)
+        {
+            if let Some(msg) = self.conflicting_proposal(&envelope.msg) {
+                envelope.msg = msg;
+            }
+        }
+        Fate::Deliver
+    }
+}
@@ -0,0 +1,95 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::util::time_service::{ScheduledTask, TimeService};
+use futures::{future, Future, FutureExt};
+use std::{
+    pin::Pin,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+/// A task scheduled by a node, to be run by the simulator at the deadline.
+pub struct Timer {
+    pub deadline: Duration,
+    pub node: usize,
+    pub task: Box<dyn ScheduledTask>,
+}
+
+struct ClockState {
+    now: Duration,
+    scheduled: Vec<Timer>,
+}
+
+/// The simulated time shared by all the nodes of a simulation. Time only moves forward when the
+/// simulator processes the next event (or when a node sleeps), and the tasks scheduled by the
+/// nodes are handed over to the simulator instead of being run by an executor.
+#[derive(Clone)]
+pub struct SimulatedClock {
+    state: Arc<Mutex<ClockState>>,
+}
+
+impl SimulatedClock {
+    pub fn new() -> Self {
+        Self {
+            state: Arc::new(Mutex::new(ClockState {
+                now: Duration::from_secs(0),
+                scheduled: vec![],
+            })),
+        }
+    }
+
+    /// Returns the TimeService of the given node.
+    pub fn time_service(&self, node: usize) -> Arc<dyn TimeService> {
+        Arc::new(NodeTimeService {
+            node,
+            clock: self.clone(),
+        })
+    }
+
+    pub fn now(&self) -> Duration {
+        self.state.lock().unwrap().now
+    }
+
+    /// Moves the time forward to the given time (the time never goes backwards).
+    pub fn advance_to(&self, time: Duration) {
+        let mut state = self.state.lock().unwrap();
+        if time > state.now {
+            state.now = time;
+        }
+    }
+
+    /// Takes all the timers scheduled since the previous call.
+    pub fn take_timers(&self) -> Vec<Timer> {
+        std::mem::replace(&mut self.state.lock().unwrap().scheduled, vec![])
+    }
+}
+
+struct NodeTimeService {
+    node: usize,
+    clock: SimulatedClock,
+}
+
+impl TimeService for NodeTimeService {
+    fn run_after(&self, timeout: Duration, task: Box<dyn ScheduledTask>) {
+        let mut state = self.clock.state.lock().unwrap();
+        let deadline = state.now + timeout;
+        state.scheduled.push(Timer {
+            deadline,
+            node: self.node,
+            task,
+        });
+    }
+
+    fn get_current_timestamp(&self) -> Duration {
+        self.clock.now()
+    }
+
+    fn sleep(&self, t: Duration) -> Pin<Box<dyn Future<Output = ()> + Send>> {
+        // Nodes only sleep for a few ms in order to produce increasing block timestamps:
+        // the simulated time simply moves forward.
+        let now = self.clock.now();
+        self.clock.advance_to(now + t);
+        future::ready(()).boxed()
+    }
+}
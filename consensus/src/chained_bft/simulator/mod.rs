@@ -0,0 +1,702 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! A deterministic simulator of a whole network of consensus nodes.
+//!
+//! Unlike the `NetworkPlayground` based tests, nothing is running on an executor: the simulator
+//! drives the `EventProcessor` of every node itself, one event at a time, in the order of a
+//! simulated clock. (The playground delivers the messages to the network tasks of the nodes
+//! running on a tokio runtime, so the interleaving of the nodes depends on the thread scheduling
+//! and a run can't be replayed; hence the simulator keeps its own queue of events.) The events are the messages in flight (with a latency drawn from a seeded
+//! rng) and the local timeouts of the nodes. Adversaries can drop, delay, reorder or tamper with
+//! the messages and delay the local timeouts (see `adversary`).
+//!
+//! The safety (no two conflicting blocks are ever committed) and liveness (there is progress
+//! within the configured bound after the global stabilization time) invariants are checked after
+//! every step: a violation panics with the seed, which replays the exact same run.
+
+mod adversary;
+mod clock;
+#[cfg(test)]
+mod simulator_test;
+
+pub use adversary::{
+    Adversary, DelayMessages, DelayTimeouts, DropVotes, Envelope, EquivocatingProposer, Fate,
+    Partition,
+};
+
+use crate::chained_bft::{
+    block_storage::BlockStore,
+    epoch_manager::EpochManager,
+    event_processor::EventProcessor,
+    liveness::{
+        pacemaker::{ExponentialTimeInterval, Pacemaker},
+        proposal_generator::ProposalGenerator,
+        rotating_proposer_election::RotatingProposer,
+    },
+    network::{BlockRetrievalRequest, BlockRetrievalResponse, ConsensusNetworkImpl},
+    test_utils::{MockStateComputer, MockStorage, MockTransactionManager, TestPayload},
+};
+use channel;
+use clock::{SimulatedClock, Timer};
+use consensus_types::{
+    block::Block,
+    common::{Author, Round},
+    equivocation_evidence::EquivocationEvidence,
+    proposal_msg::{ProposalMsg, ProposalUncheckedSignatures},
+    sync_info::SyncInfo,
+    vote_msg::VoteMsg,
+};
+use crypto::HashValue;
+use failure::prelude::*;
+use futures::{
+    channel::{mpsc, oneshot},
+    executor::block_on,
+    future,
+    task::{noop_waker, Context, Poll},
+    SinkExt, Stream, StreamExt,
+};
+use libra_logger::prelude::*;
+use libra_types::crypto_proxies::{
+    random_validator_verifier, LedgerInfoWithSignatures, ValidatorSigner, ValidatorVerifier,
+};
+use network::{
+    interface::NetworkRequest,
+    proto::{ConsensusMsg, ConsensusMsg_oneof, RespondBlock},
+    protocols::rpc::streaming::OutboundStreamingRpcRequest,
+    validator_network::{ConsensusNetworkEvents, ConsensusNetworkSender, Event},
+};
+use prost::Message;
+use prost_ext::MessageExt;
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use safety_rules::{persistent_storage::InMemoryStorage, SafetyRules};
+use std::{
+    collections::{BTreeMap, HashMap},
+    convert::TryFrom,
+    sync::Arc,
+    time::Duration,
+};
+
+/// Parameters of a simulation.
+#[derive(Clone, Debug)]
+pub struct SimulatorConfig {
+    pub num_nodes: usize,
+    /// The seed of all the random decisions of a run.
+    pub seed: u64,
+    /// Bounds of the network latency of every message.
+    pub min_latency: Duration,
+    pub max_latency: Duration,
+    /// The initial round timeout of the pacemakers.
+    pub round_timeout: Duration,
+    /// The liveness invariant is only checked after the global stabilization time.
+    pub gst: Duration,
+    /// The maximum time without any new commit after the global stabilization time.
+    pub liveness_bound: Option<Duration>,
+}
+
+impl SimulatorConfig {
+    pub fn new(num_nodes: usize, seed: u64) -> Self {
+        Self {
+            num_nodes,
+            seed,
+            min_latency: Duration::from_millis(10),
+            max_latency: Duration::from_millis(100),
+            round_timeout: Duration::from_secs(1),
+            gst: Duration::from_secs(0),
+            liveness_bound: Some(Duration::from_secs(30)),
+        }
+    }
+}
+
+/// An input of an EventProcessor, verified the same way the network task does.
+enum NodeInput {
+    Proposal(ProposalMsg<TestPayload>),
+    Vote(VoteMsg),
+    SyncInfo(SyncInfo, Author),
+    EquivocationEvidence(EquivocationEvidence<TestPayload>),
+    LocalTimeout(Round),
+}
+
+enum SimEvent {
+    Message(Envelope),
+    Timer(Timer),
+    LocalTimeout { node: usize, round: Round },
+}
+
+struct SimNode {
+    author: Author,
+    signer: ValidatorSigner,
+    event_processor: EventProcessor<TestPayload>,
+    network_reqs_rx: channel::Receiver<NetworkRequest>,
+    self_rx: channel::Receiver<failure::Result<Event<ConsensusMsg>>>,
+    timeout_rx: channel::Receiver<Round>,
+    commit_rx: mpsc::UnboundedReceiver<LedgerInfoWithSignatures>,
+    committed_round: Round,
+}
+
+impl SimNode {
+    fn new(
+        index: usize,
+        signer: ValidatorSigner,
+        proposers: Vec<Author>,
+        epoch_mgr: Arc<EpochManager>,
+        clock: &SimulatedClock,
+        round_timeout: Duration,
+    ) -> Self {
+        let author = signer.author();
+        let (network_reqs_tx, network_reqs_rx) = channel::new_test(1_024);
+        // The inbound network events are never used: the messages are delivered by the simulator.
+        let (_consensus_tx, consensus_rx) = channel::new_test(1);
+        let mut network = ConsensusNetworkImpl::new(
+            author,
+            ConsensusNetworkSender::new(network_reqs_tx),
+            ConsensusNetworkEvents::new(consensus_rx),
+            Arc::clone(&epoch_mgr),
+        );
+        let self_rx = network.take_self_receiver();
+
+        let (storage, initial_data) = MockStorage::<TestPayload>::start_for_testing();
+        let consensus_state = initial_data.state();
+        let last_vote = initial_data.last_vote();
+        let (commit_cb_sender, commit_rx) = mpsc::unbounded::<LedgerInfoWithSignatures>();
+        let state_computer = Arc::new(MockStateComputer::new(
+            commit_cb_sender,
+            Arc::clone(&storage),
+        ));
+        let block_store = Arc::new(block_on(BlockStore::new(
+            storage.clone(),
+            initial_data,
+            state_computer.clone(),
+            true,
+            10, // max pruned blocks in mem
+        )));
+
+        let time_service = clock.time_service(index);
+        let proposal_generator = ProposalGenerator::new(
+            block_store.clone(),
+            Arc::new(MockTransactionManager::new()),
+            time_service.clone(),
+            1,
+            true,
+        );
+        let safety_rules = Box::new(SafetyRules::new(
+            Box::new(InMemoryStorage::new(consensus_state)),
+            Arc::new(signer.clone()),
+            epoch_mgr.epoch(),
+            epoch_mgr.validators(),
+        ));
+        let (timeout_tx, timeout_rx) = channel::new_test(1_024);
+        let pacemaker = Pacemaker::new(
+            Box::new(ExponentialTimeInterval::new(round_timeout, 1.5, 6)),
+            time_service.clone(),
+            timeout_tx,
+        );
+        let event_processor = EventProcessor::new(
+            author,
+            block_store,
+            last_vote,
+            pacemaker,
+            Box::new(RotatingProposer::new(proposers, 1)),
+            proposal_generator,
+            safety_rules,
+            state_computer,
+            Arc::new(MockTransactionManager::new()),
+            network,
+            storage,
+            time_service,
+            true,
+            epoch_mgr.validators(),
+        );
+        Self {
+            author,
+            signer,
+            event_processor,
+            network_reqs_rx,
+            self_rx,
+            timeout_rx,
+            commit_rx,
+            committed_round: 0,
+        }
+    }
+}
+
+/// Drives a network of consensus nodes deterministically, see the module documentation.
+pub struct Simulator {
+    config: SimulatorConfig,
+    rng: StdRng,
+    clock: SimulatedClock,
+    nodes: Vec<SimNode>,
+    validators: Arc<ValidatorVerifier>,
+    adversaries: Vec<Box<dyn Adversary>>,
+    // Pending events ordered by their time and then by their scheduling order.
+    events: BTreeMap<(Duration, u64), SimEvent>,
+    next_event_id: u64,
+    steps: u64,
+    // All the blocks seen on the network, proposed or retrieved: block id -> (round, parent id).
+    blocks: HashMap<HashValue, (Round, HashValue)>,
+    genesis_id: HashValue,
+    // The committed chain: round -> block id.
+    committed: BTreeMap<Round, HashValue>,
+    // The last time any node committed a new block.
+    last_progress: Duration,
+    trace: Vec<String>,
+}
+
+impl Simulator {
+    pub fn new(config: SimulatorConfig) -> Self {
+        let (signers, validator_verifier) =
+            random_validator_verifier(config.num_nodes, None, false);
+        let epoch_mgr = Arc::new(EpochManager::new(0, validator_verifier));
+        let proposers = epoch_mgr.validators().get_ordered_account_addresses();
+        let clock = SimulatedClock::new();
+        let nodes = signers
+            .into_iter()
+            .enumerate()
+            .map(|(index, signer)| {
+                SimNode::new(
+                    index,
+                    signer,
+                    proposers.clone(),
+                    Arc::clone(&epoch_mgr),
+                    &clock,
+                    config.round_timeout,
+                )
+            })
+            .collect();
+        let mut simulator = Self {
+            rng: StdRng::seed_from_u64(config.seed),
+            config,
+            clock,
+            nodes,
+            validators: epoch_mgr.validators(),
+            adversaries: vec![],
+            events: BTreeMap::new(),
+            next_event_id: 0,
+            steps: 0,
+            blocks: HashMap::new(),
+            genesis_id: Block::<TestPayload>::make_genesis_block().id(),
+            committed: BTreeMap::new(),
+            last_progress: Duration::from_secs(0),
+            trace: vec![],
+        };
+        for node in simulator.nodes.iter_mut() {
+            block_on(node.event_processor.start());
+        }
+        simulator.collect_outputs(vec![]);
+        simulator
+    }
+
+    pub fn add_adversary(&mut self, adversary: Box<dyn Adversary>) {
+        self.adversaries.push(adversary);
+    }
+
+    /// The authors of the nodes, in the order of the validators.
+    pub fn authors(&self) -> Vec<Author> {
+        self.nodes.iter().map(|node| node.author).collect()
+    }
+
+    pub fn signer(&self, author: Author) -> ValidatorSigner {
+        self.nodes[self.node_index(author)].signer.clone()
+    }
+
+    pub fn now(&self) -> Duration {
+        self.clock.now()
+    }
+
+    /// The highest committed round of every node.
+    pub fn committed_rounds(&self) -> Vec<Round> {
+        self.nodes.iter().map(|node| node.committed_round).collect()
+    }
+
+    /// The commits of all the nodes in the order they happened: two runs with the same seed
+    /// have the same trace.
+    pub fn trace(&self) -> &[String] {
+        &self.trace
+    }
+
+    /// Processes all the events up to the given time.
+    pub fn run_until(&mut self, time: Duration) {
+        while let Some((event_time, _)) = self.events.keys().next() {
+            if *event_time > time {
+                break;
+            }
+            self.step();
+        }
+        self.clock.advance_to(time);
+        self.check_liveness();
+    }
+
+    /// Processes the next event and checks the invariants. Returns false if there are no more
+    /// events.
+    pub fn step(&mut self) -> bool {
+        let key = match self.events.keys().next() {
+            Some(key) => *key,
+            None => return false,
+        };
+        let event = self.events.remove(&key).expect("The event must exist");
+        self.clock.advance_to(key.0);
+        self.steps += 1;
+        let outbox = match event {
+            SimEvent::Message(envelope) => self.deliver(envelope),
+            SimEvent::Timer(mut timer) => {
+                block_on(timer.task.run());
+                self.fire_local_timeouts(timer.node)
+            }
+            SimEvent::LocalTimeout { node, round } => {
+                self.process(node, NodeInput::LocalTimeout(round))
+            }
+        };
+        self.collect_outputs(outbox);
+        self.check_safety();
+        self.check_liveness();
+        true
+    }
+
+    fn node_index(&self, author: Author) -> usize {
+        self.nodes
+            .iter()
+            .position(|node| node.author == author)
+            .unwrap_or_else(|| panic!("Unknown node {}", author))
+    }
+
+    fn schedule(&mut self, time: Duration, event: SimEvent) {
+        self.events.insert((time, self.next_event_id), event);
+        self.next_event_id += 1;
+    }
+
+    /// Sends a message over the network, subject to the latency and the adversaries.
+    fn send(&mut self, mut envelope: Envelope) {
+        let now = self.now();
+        if envelope.src == envelope.dst {
+            self.schedule(now, SimEvent::Message(envelope));
+            return;
+        }
+        let min_latency_ms = self.config.min_latency.as_millis() as u64;
+        let max_latency_ms = self.config.max_latency.as_millis() as u64;
+        let mut latency =
+            Duration::from_millis(self.rng.gen_range(min_latency_ms, max_latency_ms + 1));
+        for adversary in self.adversaries.iter_mut() {
+            match adversary.on_message(&mut self.rng, now, &mut envelope) {
+                Fate::Deliver => (),
+                Fate::Delay(delay) => latency += delay,
+                Fate::Drop => {
+                    debug!("[simulator] Dropped {:?}", envelope);
+                    return;
+                }
+            }
+        }
+        self.schedule(now + latency, SimEvent::Message(envelope));
+    }
+
+    /// Collects the messages sent and the timers scheduled by the nodes.
+    fn collect_outputs(&mut self, mut outbox: Vec<Envelope>) {
+        for node in self.nodes.iter_mut() {
+            while let Some(request) = try_recv(&mut node.network_reqs_rx) {
+                match request {
+                    NetworkRequest::SendMessage(dst, message) => {
+                        match ConsensusMsg::decode(message.mdata.as_ref()) {
+                            Ok(msg) => outbox.push(Envelope {
+                                src: node.author,
+                                dst,
+                                msg,
+                            }),
+                            Err(e) => error!("[simulator] Failed to decode a message: {:?}", e),
+                        }
+                    }
+                    // The simulator does not keep track of the peers' reputation.
+                    NetworkRequest::ReportPeer(_, _) => (),
+                    // The requester is no longer waiting: drop the request.
+                    request => warn!("[simulator] Unexpected request {:?}", request),
+                }
+            }
+            while let Some(event) = try_recv(&mut node.self_rx) {
+                if let Ok(Event::Message((_, msg))) = event {
+                    outbox.push(Envelope {
+                        src: node.author,
+                        dst: node.author,
+                        msg,
+                    });
+                }
+            }
+        }
+        for envelope in outbox {
+            self.send(envelope);
+        }
+        for timer in self.clock.take_timers() {
+            self.schedule(timer.deadline, SimEvent::Timer(timer));
+        }
+    }
+
+    fn fire_local_timeouts(&mut self, index: usize) -> Vec<Envelope> {
+        let mut outbox = vec![];
+        let now = self.now();
+        while let Some(round) = try_recv(&mut self.nodes[index].timeout_rx) {
+            let author = self.nodes[index].author;
+            let mut delay = Duration::from_secs(0);
+            for adversary in self.adversaries.iter_mut() {
+                delay += adversary.on_local_timeout(&mut self.rng, now, author, round);
+            }
+            if delay > Duration::from_secs(0) {
+                let event = SimEvent::LocalTimeout { node: index, round };
+                self.schedule(now + delay, event);
+            } else {
+                outbox.extend(self.process(index, NodeInput::LocalTimeout(round)));
+            }
+        }
+        outbox
+    }
+
+    fn deliver(&mut self, envelope: Envelope) -> Vec<Envelope> {
+        let index = self.node_index(envelope.dst);
+        match self.verify(envelope) {
+            Ok(input) => self.process(index, input),
+            Err(e) => {
+                warn!("[simulator] Invalid message: {:?}", e);
+                vec![]
+            }
+        }
+    }
+
+    /// Verifies a message the same way the network task does.
+    fn verify(&mut self, envelope: Envelope) -> failure::Result<NodeInput> {
+        let validators = self.validators.as_ref();
+        match envelope.msg.message {
+            Some(ConsensusMsg_oneof::Proposal(proposal)) => {
+                let proposal = ProposalUncheckedSignatures::<TestPayload>::try_from(proposal)?
+                    .validate_signatures(validators)?
+                    .verify_well_formed()?;
+                let block = proposal.proposal();
+                self.blocks
+                    .insert(block.id(), (block.round(), block.parent_id()));
+                Ok(NodeInput::Proposal(proposal))
+            }
+            Some(ConsensusMsg_oneof::Vote(vote)) => {
+                let vote = VoteMsg::try_from(vote)?;
+                vote.verify(validators)?;
+                Ok(NodeInput::Vote(vote))
+            }
+            Some(ConsensusMsg_oneof::SyncInfo(sync_info)) => {
+                let sync_info = SyncInfo::try_from(sync_info)?;
+                sync_info.verify(validators)?;
+                Ok(NodeInput::SyncInfo(sync_info, envelope.src))
+            }
+            Some(ConsensusMsg_oneof::EquivocationEvidence(evidence)) => {
+                let evidence = EquivocationEvidence::<TestPayload>::try_from(evidence)?;
+                evidence.verify(validators)?;
+                Ok(NodeInput::EquivocationEvidence(evidence))
+            }
+            msg => bail!("Unexpected message {:?}", msg),
+        }
+    }
+
+    /// Runs the given input on the node. The block retrieval RPCs the node sends meanwhile are
+    /// served right away by the peers; all the other messages are returned.
+    fn process(&mut self, index: usize, input: NodeInput) -> Vec<Envelope> {
+        let (before, rest) = self.nodes.split_at_mut(index);
+        let (node, after) = rest.split_first_mut().expect("Node index out of bounds");
+        let peers: Vec<&SimNode> = before.iter().chain(after.iter()).collect();
+        let mut outbox = vec![];
+        let handler = Box::pin(handle_input(&mut node.event_processor, input));
+        let server = Box::pin(serve_requests(
+            node.author,
+            &mut node.network_reqs_rx,
+            &peers,
+            &mut outbox,
+            &mut self.blocks,
+        ));
+        block_on(future::select(handler, server));
+        outbox
+    }
+
+    /// Safety: all the blocks committed by all the nodes form a single chain.
+    fn check_safety(&mut self) {
+        let mut commits = vec![];
+        for (index, node) in self.nodes.iter_mut().enumerate() {
+            while let Some(ledger_info) = try_recv(&mut node.commit_rx) {
+                commits.push((index, ledger_info.ledger_info().consensus_block_id()));
+            }
+        }
+        for (index, block_id) in commits {
+            self.check_commit(index, block_id);
+        }
+    }
+
+    fn check_commit(&mut self, index: usize, block_id: HashValue) {
+        let author = self.nodes[index].author;
+        // The committed block and its ancestors, the highest round first.
+        let mut chain = vec![];
+        let mut id = block_id;
+        while let Some((round, parent_id)) = self.blocks.get(&id) {
+            chain.push((*round, id));
+            id = *parent_id;
+        }
+        // Every block a node executes has been proposed or retrieved: the chain must go back to
+        // genesis.
+        if id != self.genesis_id {
+            self.violation(format!(
+                "{} committed block {}, which descends from the unknown block {}",
+                author.short_str(),
+                block_id,
+                id,
+            ));
+        }
+        let (round, lowest_round) = match (chain.first(), chain.last()) {
+            (Some((round, _)), Some((lowest_round, _))) => (*round, *lowest_round),
+            // Genesis
+            _ => return,
+        };
+        let ancestors: HashMap<Round, HashValue> = chain.iter().cloned().collect();
+        let highest_committed_round = self.committed.keys().next_back().cloned().unwrap_or(0);
+        // The committed chain must extend the block and its ancestors, or be extended by them.
+        let consistent =
+            self.committed
+                .range(lowest_round..=round)
+                .all(|(committed_round, committed_id)| {
+                    ancestors.get(committed_round) == Some(committed_id)
+                })
+                && (round >= highest_committed_round
+                    || self.committed.get(&round) == Some(&block_id));
+        if !consistent {
+            self.violation(format!(
+                "{} committed block {} of round {}, which conflicts with the committed chain",
+                author.short_str(),
+                block_id,
+                round,
+            ));
+        }
+        self.committed.extend(chain);
+        let now = self.now();
+        if round > self.nodes[index].committed_round {
+            self.nodes[index].committed_round = round;
+            self.last_progress = now;
+        }
+        let record = format!(
+            "{:?} {} committed {} of round {}",
+            now,
+            author.short_str(),
+            block_id,
+            round
+        );
+        debug!("[simulator] {}", record);
+        self.trace.push(record);
+    }
+
+    /// Liveness: after the global stabilization time, some node commits a new block at least
+    /// once within the liveness bound.
+    fn check_liveness(&self) {
+        let bound = match self.config.liveness_bound {
+            Some(bound) => bound,
+            None => return,
+        };
+        let since = self.last_progress.max(self.config.gst);
+        if self.now() > since + bound {
+            self.violation(format!("No progress since {:?}", since));
+        }
+    }
+
+    fn violation(&self, error: String) {
+        panic!(
+            "[simulator] Invariant violated at step {} ({:?}) of the run with seed {}: {}",
+            self.steps,
+            self.now(),
+            self.config.seed,
+            error
+        );
+    }
+}
+
+async fn handle_input(event_processor: &mut EventProcessor<TestPayload>, input: NodeInput) {
+    match input {
+        NodeInput::Proposal(proposal) => event_processor.process_proposal_msg(proposal).await,
+        NodeInput::Vote(vote) => event_processor.process_vote(vote).await,
+        NodeInput::SyncInfo(sync_info, peer) => {
+            event_processor.process_sync_info_msg(sync_info, peer).await
+        }
+        NodeInput::EquivocationEvidence(evidence) => {
+            event_processor
+                .process_equivocation_evidence_msg(evidence)
+                .await
+        }
+        NodeInput::LocalTimeout(round) => event_processor.process_local_timeout(round).await,
+    }
+}
+
+/// Serves the block retrieval RPCs sent by a node, recording the retrieved blocks, and buffers
+/// its other messages. Never completes: it is dropped once the node is done processing its input.
+async fn serve_requests(
+    author: Author,
+    network_reqs_rx: &mut channel::Receiver<NetworkRequest>,
+    peers: &[&SimNode],
+    outbox: &mut Vec<Envelope>,
+    blocks: &mut HashMap<HashValue, (Round, HashValue)>,
+) {
+    while let Some(request) = network_reqs_rx.next().await {
+        match request {
+            NetworkRequest::SendMessage(dst, message) => {
+                match ConsensusMsg::decode(message.mdata.as_ref()) {
+                    Ok(msg) => outbox.push(Envelope {
+                        src: author,
+                        dst,
+                        msg,
+                    }),
+                    Err(e) => error!("[simulator] Failed to decode a message: {:?}", e),
+                }
+            }
+            NetworkRequest::SendStreamingRpc(dst, request) => {
+                if let Some(peer) = peers.iter().find(|peer| peer.author == dst) {
+                    if let Err(e) = serve_block_retrieval(peer, request, blocks).await {
+                        warn!("[simulator] Failed to serve an rpc: {:?}", e);
+                    }
+                }
+            }
+            _ => (),
+        }
+    }
+    future::pending::<()>().await
+}
+
+async fn serve_block_retrieval(
+    peer: &SimNode,
+    mut request: OutboundStreamingRpcRequest,
+    blocks: &mut HashMap<HashValue, (Round, HashValue)>,
+) -> failure::Result<()> {
+    let request_block = match ConsensusMsg::decode(request.data.as_ref())?.message {
+        Some(ConsensusMsg_oneof::RequestBlock(request_block)) => request_block,
+        msg => bail!("Unexpected rpc {:?}", msg),
+    };
+    let (response_sender, response_receiver) = oneshot::channel();
+    peer.event_processor
+        .process_block_retrieval(BlockRetrievalRequest {
+            block_id: HashValue::from_slice(&request_block.block_id[..])?,
+            num_blocks: request_block.num_blocks,
+            response_sender,
+        })
+        .await;
+    let response = response_receiver.await?;
+    for block in &response.blocks {
+        blocks.insert(block.id(), (block.round(), block.parent_id()));
+    }
+    let BlockRetrievalResponse { status, blocks } = response;
+    let mut response = RespondBlock::default();
+    response.set_status(status);
+    response.blocks = blocks.into_iter().map(Into::into).collect();
+    let response_msg = ConsensusMsg {
+        message: Some(ConsensusMsg_oneof::RespondBlock(response)),
+    };
+    // The whole response is sent as a single chunk.
+    request
+        .res_tx
+        .send(Ok(response_msg.to_bytes()?))
+        .await
+        .map_err(|_| format_err!("The requester is gone"))
+}
+
+/// Returns the next item of the stream if it is ready.
+fn try_recv<S: Stream + Unpin>(stream: &mut S) -> Option<S::Item> {
+    let waker = noop_waker();
+    let mut context = Context::from_waker(&waker);
+    match stream.poll_next_unpin(&mut context) {
+        Poll::Ready(item) => item,
+        Poll::Pending => None,
+    }
+}
@@ -0,0 +1,89 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::chained_bft::simulator::{
+    DelayMessages, DelayTimeouts, DropVotes, EquivocatingProposer, Partition, Simulator,
+    SimulatorConfig,
+};
+use crypto::HashValue;
+use std::time::Duration;
+
+#[test]
+fn test_happy_path() {
+    let mut simulator = Simulator::new(SimulatorConfig::new(4, 0));
+    simulator.run_until(Duration::from_secs(20));
+    for round in simulator.committed_rounds() {
+        assert!(
+            round > 10,
+            "committed rounds: {:?}",
+            simulator.committed_rounds()
+        );
+    }
+}
+
+#[test]
+fn test_replay_from_seed() {
+    let run = |seed| {
+        let mut simulator = Simulator::new(SimulatorConfig::new(4, seed));
+        simulator.add_adversary(Box::new(DelayMessages::new(Duration::from_millis(500))));
+        simulator.add_adversary(Box::new(DropVotes::new(0.2)));
+        simulator.run_until(Duration::from_secs(20));
+        simulator.trace().to_vec()
+    };
+    let trace = run(42);
+    assert!(!trace.is_empty());
+    assert_eq!(trace, run(42));
+}
+
+#[test]
+fn test_partition() {
+    let partition_end = Duration::from_secs(15);
+    let mut config = SimulatorConfig::new(4, 1);
+    config.gst = partition_end;
+    let mut simulator = Simulator::new(config);
+    let authors = simulator.authors();
+    // Neither half of the network has a quorum during the partition
+    simulator.add_adversary(Box::new(Partition::new(
+        vec![authors[..2].to_vec(), authors[2..].to_vec()],
+        Duration::from_secs(5),
+        partition_end,
+    )));
+
+    simulator.run_until(partition_end);
+    let committed_rounds = simulator.committed_rounds();
+    simulator.run_until(Duration::from_secs(60));
+    for (before, after) in committed_rounds
+        .iter()
+        .zip(simulator.committed_rounds().iter())
+    {
+        assert!(after > before);
+    }
+}
+
+#[test]
+fn test_byzantine_proposer() {
+    let mut simulator = Simulator::new(SimulatorConfig::new(4, 2));
+    let authors = simulator.authors();
+    let byzantine_signer = simulator.signer(authors[0]);
+    simulator.add_adversary(Box::new(EquivocatingProposer::new(
+        byzantine_signer,
+        &authors,
+    )));
+    simulator.add_adversary(Box::new(DropVotes::new(0.1)));
+    simulator.add_adversary(Box::new(DelayTimeouts::new(
+        vec![authors[3]],
+        Duration::from_millis(500),
+    )));
+    // The invariants are checked at every step
+    simulator.run_until(Duration::from_secs(60));
+    for round in simulator.committed_rounds() {
+        assert!(round > 0);
+    }
+}
+
+#[test]
+#[should_panic(expected = "which descends from the unknown block")]
+fn test_commit_of_unknown_block() {
+    let mut simulator = Simulator::new(SimulatorConfig::new(4, 3));
+    simulator.check_commit(0, HashValue::random());
+}
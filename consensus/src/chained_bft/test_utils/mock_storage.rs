@@ -7,10 +7,10 @@ use crate::chained_bft::persistent_storage::{
 
 use config::config::{NodeConfig, NodeConfigHelpers};
 use consensus_types::{
-    block::Block, common::Payload, quorum_cert::QuorumCert,
-    timeout_certificate::TimeoutCertificate, vote_msg::VoteMsg,
+    block::Block, common::Payload, equivocation_evidence::EquivocationEvidence,
+    quorum_cert::QuorumCert, timeout_certificate::TimeoutCertificate, vote_msg::VoteMsg,
 };
-use crypto::HashValue;
+use crypto::{hash::CryptoHash, HashValue};
 use failure::Result;
 use libra_types::ledger_info::LedgerInfo;
 use safety_rules::ConsensusState;
@@ -25,6 +25,7 @@ pub struct MockSharedStorage<T> {
     pub qc: Mutex<HashMap<HashValue, QuorumCert>>,
     pub state: Mutex<ConsensusState>,
     pub last_vote: Mutex<Option<VoteMsg>>,
+    pub equivocation_evidence: Mutex<HashMap<HashValue, EquivocationEvidence<T>>>,
 
     // Liveness state
     pub highest_timeout_certificate: Mutex<Option<TimeoutCertificate>>,
@@ -163,12 +164,33 @@ impl<T: Payload> PersistentStorage<T> for MockStorage<T> {
         Ok(())
     }
 
+    fn save_equivocation_evidence(&self, evidence: EquivocationEvidence<T>) -> Result<()> {
+        self.shared_storage
+            .equivocation_evidence
+            .lock()
+            .unwrap()
+            .insert(evidence.hash(), evidence);
+        Ok(())
+    }
+
+    fn retrieve_equivocation_evidence(&self) -> Result<Vec<EquivocationEvidence<T>>> {
+        Ok(self
+            .shared_storage
+            .equivocation_evidence
+            .lock()
+            .unwrap()
+            .values()
+            .cloned()
+            .collect())
+    }
+
     fn start(_config: &NodeConfig) -> (Arc<Self>, RecoveryData<T>) {
         let shared_storage = Arc::new(MockSharedStorage {
             block: Mutex::new(HashMap::new()),
             qc: Mutex::new(HashMap::new()),
             state: Mutex::new(ConsensusState::default()),
             last_vote: Mutex::new(None),
+            equivocation_evidence: Mutex::new(HashMap::new()),
             highest_timeout_certificate: Mutex::new(None),
         });
         let storage = MockStorage::new(Arc::clone(&shared_storage));
@@ -219,6 +241,14 @@ impl<T: Payload> PersistentStorage<T> for EmptyStorage {
         Ok(())
     }
 
+    fn save_equivocation_evidence(&self, _: EquivocationEvidence<T>) -> Result<()> {
+        Ok(())
+    }
+
+    fn retrieve_equivocation_evidence(&self) -> Result<Vec<EquivocationEvidence<T>>> {
+        Ok(vec![])
+    }
+
     fn start(_: &NodeConfig) -> (Arc<Self>, RecoveryData<T>) {
         let genesis = Block::make_genesis_block();
         let genesis_qc = QuorumCert::certificate_for_genesis();
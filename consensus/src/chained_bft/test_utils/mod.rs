@@ -72,18 +72,10 @@ pub fn build_chain() -> Vec<Arc<ExecutedBlock<TestPayload>>> {
 }
 
 pub fn build_empty_tree() -> Arc<BlockStore<TestPayload>> {
-    let signer = ValidatorSigner::random(None);
-    build_empty_tree_with_custom_signing(signer)
-}
-
-pub fn build_empty_tree_with_custom_signing(
-    my_signer: ValidatorSigner,
-) -> Arc<BlockStore<TestPayload>> {
     let (storage, initial_data) = EmptyStorage::start_for_testing();
     Arc::new(block_on(BlockStore::new(
         storage,
         initial_data,
-        my_signer,
         Arc::new(EmptyStateComputer),
         true,
         10, // max pruned blocks in mem
@@ -92,19 +84,32 @@ pub fn build_empty_tree_with_custom_signing(
 
 pub struct TreeInserter {
     payload_val: usize,
+    signer: ValidatorSigner,
     block_store: Arc<BlockStore<TestPayload>>,
 }
 
 impl TreeInserter {
     pub fn new(block_store: Arc<BlockStore<TestPayload>>) -> Self {
+        Self::new_with_signer(ValidatorSigner::random(None), block_store)
+    }
+
+    pub fn new_with_signer(
+        signer: ValidatorSigner,
+        block_store: Arc<BlockStore<TestPayload>>,
+    ) -> Self {
         Self {
             payload_val: 0,
+            signer,
             block_store,
         }
     }
 
+    pub fn signer(&self) -> &ValidatorSigner {
+        &self.signer
+    }
+
     /// This function is generating a placeholder QC for a block's parent that is signed by a single
-    /// signer kept by the block store. If more sophisticated QC required, please use
+    /// signer kept by the inserter. If more sophisticated QC required, please use
     /// `insert_block_with_qc`.
     pub fn insert_block(
         &mut self,
@@ -142,7 +147,7 @@ impl TreeInserter {
         consensus_block_id: Option<HashValue>,
     ) -> QuorumCert {
         placeholder_certificate_for_block(
-            vec![self.block_store.signer()],
+            vec![&self.signer],
             block.id(),
             block.round(),
             block.quorum_cert().certified_block().id(),
@@ -174,7 +179,7 @@ impl TreeInserter {
             round,
             parent.timestamp_usecs() + 1,
             parent_qc,
-            self.block_store.signer(),
+            &self.signer,
         )
     }
 
@@ -6,6 +6,7 @@ use failure::prelude::*;
 use network::validator_network::{ConsensusNetworkEvents, ConsensusNetworkSender};
 
 use crate::chained_bft::chained_bft_consensus_provider::ChainedBftProvider;
+use debug_interface::node_debug_service::EquivocationEvidenceSource;
 use executor::Executor;
 use grpcio::{ChannelBuilder, EnvBuilder};
 use libra_mempool::proto::mempool::MempoolClient;
@@ -25,6 +26,9 @@ pub trait ConsensusProvider {
 
     /// Stop the consensus operations. The function returns after graceful shutdown.
     fn stop(&mut self);
+
+    /// Returns the equivocation evidence persisted by consensus, exposed by the debug interface.
+    fn equivocation_evidence_source(&self) -> Arc<dyn EquivocationEvidenceSource>;
 }
 
 /// Helper function to create a ConsensusProvider based on configuration
@@ -35,14 +39,26 @@ pub fn make_consensus_provider(
     executor: Arc<Executor<MoveVM>>,
     state_sync_client: Arc<StateSyncClient>,
 ) -> Box<dyn ConsensusProvider> {
-    Box::new(ChainedBftProvider::new(
-        node_config,
-        network_sender,
-        network_receiver,
-        create_mempool_client(node_config),
-        executor,
-        state_sync_client,
-    ))
+    let mempool_client = create_mempool_client(node_config);
+    if node_config.consensus.batching().enabled {
+        Box::new(ChainedBftProvider::new_with_batching(
+            node_config,
+            network_sender,
+            network_receiver,
+            mempool_client,
+            executor,
+            state_sync_client,
+        ))
+    } else {
+        Box::new(ChainedBftProvider::new(
+            node_config,
+            network_sender,
+            network_receiver,
+            mempool_client,
+            executor,
+            state_sync_client,
+        ))
+    }
 }
 /// Create a mempool client assuming the mempool is running on localhost
 fn create_mempool_client(config: &NodeConfig) -> Arc<MempoolClient> {
@@ -44,6 +44,37 @@ pub static ref FAILED_TXNS_COUNT: IntCounter = OP_COUNTERS.counter("failed_txns_
 /// Count of how many messages dropped between network task and main consensus task
 pub static ref DROP_NETWORK_TO_CONSENSUS: IntCounter = OP_COUNTERS.counter("drop_network_to_consensus");
 
+//////////////////////
+// EQUIVOCATION COUNTERS
+//////////////////////
+/// Count of the equivocations (double proposals or double votes) detected by this validator since
+/// last restart.
+pub static ref EQUIVOCATIONS_DETECTED_COUNT: IntCounter = OP_COUNTERS.counter("equivocations_detected_count");
+
+/// Count of the new equivocation evidence received from other validators since last restart.
+pub static ref EQUIVOCATION_EVIDENCE_RECEIVED_COUNT: IntCounter = OP_COUNTERS.counter("equivocation_evidence_received_count");
+
+//////////////////////
+// BATCH COUNTERS
+//////////////////////
+/// Count of the batches created by this validator since last restart.
+pub static ref BATCHES_CREATED_COUNT: IntCounter = OP_COUNTERS.counter("batches_created_count");
+
+/// Count of the batches of this validator certified by a proof of store since last restart.
+pub static ref BATCHES_CERTIFIED_COUNT: IntCounter = OP_COUNTERS.counter("batches_certified_count");
+
+/// Count of the batches fetched from the other validators upon execution since last restart.
+pub static ref BATCHES_FETCHED_COUNT: IntCounter = OP_COUNTERS.counter("batches_fetched_count");
+
+/// Number of batches stored locally.
+pub static ref NUM_BATCHES_IN_STORE: IntGauge = OP_COUNTERS.gauge("num_batches_in_store");
+
+/// Number of certified batches that are not proposed yet.
+pub static ref NUM_PENDING_PROOFS_OF_STORE: IntGauge = OP_COUNTERS.gauge("num_pending_proofs_of_store");
+
+/// Histogram of batch fetch duration.
+pub static ref BATCH_FETCH_DURATION_S: DurationHistogram = OP_COUNTERS.duration_histogram("batch_fetch_duration_s");
+
 //////////////////////
 // PROPOSAL ELECTION
 //////////////////////
@@ -212,4 +243,22 @@ pub static ref PENDING_SYNC_INFO_MSGS: IntGauge = OP_COUNTERS.gauge("pending_syn
 
 /// Count of the pending winning proposals.
 pub static ref PENDING_WINNING_PROPOSALS: IntGauge = OP_COUNTERS.gauge("pending_winning_proposals");
+
+/// Count of the pending inbound equivocation evidence messages.
+pub static ref PENDING_EQUIVOCATION_EVIDENCE: IntGauge = OP_COUNTERS.gauge("pending_equivocation_evidence");
+
+/// Count of the pending inbound batches.
+pub static ref PENDING_BATCHES: IntGauge = OP_COUNTERS.gauge("pending_batches");
+
+/// Count of the pending inbound signed batch infos.
+pub static ref PENDING_SIGNED_BATCH_INFOS: IntGauge = OP_COUNTERS.gauge("pending_signed_batch_infos");
+
+/// Count of the pending inbound proofs of store.
+pub static ref PENDING_PROOFS_OF_STORE: IntGauge = OP_COUNTERS.gauge("pending_proofs_of_store");
+
+/// Count of the pending inbound batch requests
+pub static ref PENDING_BATCH_REQUESTS: IntGauge = OP_COUNTERS.gauge("pending_batch_requests");
+
+/// Count of the pending batch fetches requested by the execution
+pub static ref PENDING_BATCH_FETCHES: IntGauge = OP_COUNTERS.gauge("pending_batch_fetches");
 }
@@ -4,6 +4,7 @@
 use crate::{counters, state_replication::StateComputer};
 use consensus_types::block::Block;
 use consensus_types::quorum_cert::QuorumCert;
+use crypto::HashValue;
 use executor::{CommittableBlock, ExecutedTrees, Executor, ProcessedVMOutput, StateComputeResult};
 use failure::Result;
 use futures::{Future, FutureExt};
@@ -35,29 +36,21 @@ impl ExecutionProxy {
             synchronizer,
         }
     }
-}
 
-impl StateComputer for ExecutionProxy {
-    type Payload = Vec<SignedTransaction>;
-
-    fn compute(
+    /// Executes the transactions of the given block on top of the executed trees of its parent.
+    pub fn execute_transactions(
         &self,
-        // The block to be executed.
-        block: &Block<Self::Payload>,
-        // The executed trees after executing the parent block.
+        txns: Vec<SignedTransaction>,
         parent_executed_trees: ExecutedTrees,
+        parent_id: HashValue,
+        block_id: HashValue,
     ) -> Pin<Box<dyn Future<Output = Result<(ProcessedVMOutput, StateComputeResult)>> + Send>> {
         let pre_execution_instant = Instant::now();
         let execute_future = self.executor.execute_block(
-            block
-                .payload()
-                .unwrap_or(&Self::Payload::default())
-                .iter()
-                .map(|txn| Transaction::UserTransaction(txn.clone()))
-                .collect(),
+            txns.into_iter().map(Transaction::UserTransaction).collect(),
             parent_executed_trees,
-            block.parent_id(),
-            block.id(),
+            parent_id,
+            block_id,
         );
         async move {
             match execute_future.await {
@@ -85,13 +78,13 @@ impl StateComputer for ExecutionProxy {
                 Err(e) => Err(e.into()),
             }
         }
-            .boxed()
+        .boxed()
     }
 
-    /// Send a successful commit. A future is fulfilled when the state is finalized.
-    fn commit(
+    /// Commits the transactions of the given blocks along with their execution output.
+    pub fn commit_transactions(
         &self,
-        payload_and_output_list: Vec<(Self::Payload, Arc<ProcessedVMOutput>)>,
+        txns_and_output_list: Vec<(Vec<SignedTransaction>, Arc<ProcessedVMOutput>)>,
         finality_proof: LedgerInfoWithSignatures,
     ) -> Pin<Box<dyn Future<Output = Result<()>> + Send>> {
         let version = finality_proof.ledger_info().version();
@@ -100,16 +93,16 @@ impl StateComputer for ExecutionProxy {
         let pre_commit_instant = Instant::now();
         let synchronizer = Arc::clone(&self.synchronizer);
 
-        let committable_blocks = payload_and_output_list
+        let committable_blocks = txns_and_output_list
             .into_iter()
-            .map(|payload_and_output| {
+            .map(|txns_and_output| {
                 CommittableBlock::new(
-                    payload_and_output
+                    txns_and_output
                         .0
                         .into_iter()
                         .map(Transaction::UserTransaction)
                         .collect(),
-                    payload_and_output.1,
+                    txns_and_output.1,
                 )
             })
             .collect();
@@ -131,7 +124,35 @@ impl StateComputer for ExecutionProxy {
                 Err(e) => Err(e.into()),
             }
         }
-            .boxed()
+        .boxed()
+    }
+}
+
+impl StateComputer for ExecutionProxy {
+    type Payload = Vec<SignedTransaction>;
+
+    fn compute(
+        &self,
+        // The block to be executed.
+        block: &Block<Self::Payload>,
+        // The executed trees after executing the parent block.
+        parent_executed_trees: ExecutedTrees,
+    ) -> Pin<Box<dyn Future<Output = Result<(ProcessedVMOutput, StateComputeResult)>> + Send>> {
+        self.execute_transactions(
+            block.payload().cloned().unwrap_or_default(),
+            parent_executed_trees,
+            block.parent_id(),
+            block.id(),
+        )
+    }
+
+    /// Send a successful commit. A future is fulfilled when the state is finalized.
+    fn commit(
+        &self,
+        payload_and_output_list: Vec<(Self::Payload, Arc<ProcessedVMOutput>)>,
+        finality_proof: LedgerInfoWithSignatures,
+    ) -> Pin<Box<dyn Future<Output = Result<()>> + Send>> {
+        self.commit_transactions(payload_and_output_list, finality_proof)
     }
 
     /// Synchronize to a commit that not present locally.
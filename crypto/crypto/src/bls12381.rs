@@ -27,6 +27,10 @@
 //! **Note**: The above example generates a private key using a private function intended only for
 //! testing purposes. Production code should generate the key according to the spec [draft-irtf-cfrg-bls-signature-00](https://tools.ietf.org/id/draft-irtf-cfrg-bls-signature-00.html#keygen).
 //!
+//! Signatures of the same message can be aggregated into a single signature, verified against
+//! the aggregate of the signers' public keys with one pairing check; see
+//! [`BLS12381Signature::verify_aggregate`].
+//!
 //! This module is not currently used, but could be included in the future for improved
 //! performance in consensus.
 
@@ -35,8 +39,8 @@ use core::convert::TryFrom;
 use crypto_derive::{Deref, SilentDebug, SilentDisplay};
 use failure::prelude::*;
 use pairing::{
-    bls12_381::{Fr, FrRepr},
-    PrimeField,
+    bls12_381::{Fr, FrRepr, G1Compressed, G2Compressed, G1, G2},
+    CurveAffine, CurveProjective, EncodedPoint, PrimeField,
 };
 use rand::Rng;
 use serde::{Deserialize, Serialize};
@@ -76,6 +80,47 @@ impl BLS12381Signature {
     pub fn to_bytes(&self) -> [u8; BLS12381_SIGNATURE_LENGTH] {
         self.0.to_bytes()
     }
+
+    /// Checks that `signatures`, all signing the same `message`, are valid under `public_keys`.
+    ///
+    /// The signatures are added up in G2 and the public keys in G1, and the aggregate signature
+    /// is verified against the aggregate public key with a single pairing check, so the cost is
+    /// one verification plus one point addition per signer.
+    ///
+    /// **Note**: the aggregate is only secure if every public key comes with a proof of possession
+    /// of its private key. Otherwise a signer can pick a rogue key computed from the others' keys
+    /// and forge an aggregate signature that all of them appear to have signed.
+    pub fn verify_aggregate(
+        signatures: &[BLS12381Signature],
+        message: &[u8],
+        public_keys: &[BLS12381PublicKey],
+    ) -> Result<()> {
+        ensure!(
+            !signatures.is_empty() && signatures.len() == public_keys.len(),
+            "Expected one signature per public key, got {} signatures and {} public keys",
+            signatures.len(),
+            public_keys.len()
+        );
+        let mut aggregate_signature = G2::zero();
+        for signature in signatures {
+            let mut compressed = G2Compressed::empty();
+            compressed.as_mut().copy_from_slice(&signature.to_bytes());
+            aggregate_signature.add_assign_mixed(&compressed.into_affine()?);
+        }
+        let mut aggregate_key = G1::zero();
+        for public_key in public_keys {
+            let mut compressed = G1Compressed::empty();
+            compressed.as_mut().copy_from_slice(&public_key.to_bytes());
+            aggregate_key.add_assign_mixed(&compressed.into_affine()?);
+        }
+
+        let signature = BLS12381Signature::try_from(
+            aggregate_signature.into_affine().into_compressed().as_ref(),
+        )?;
+        let public_key =
+            BLS12381PublicKey::try_from(aggregate_key.into_affine().into_compressed().as_ref())?;
+        signature.verify_arbitrary_msg(message, &public_key)
+    }
 }
 
 ///////////////////////
@@ -582,6 +582,30 @@ define_hasher! {
     (VoteDataHasher, VOTE_DATA_HASHER, b"VoteData")
 }
 
+define_hasher! {
+    /// The hasher used to compute the hash of an EquivocationEvidence object.
+    (
+        EquivocationEvidenceHasher,
+        EQUIVOCATION_EVIDENCE_HASHER,
+        b"EquivocationEvidence"
+    )
+}
+
+define_hasher! {
+    /// The hasher used to compute the digest of the transactions of a Batch object.
+    (BatchHasher, BATCH_HASHER, b"Batch")
+}
+
+define_hasher! {
+    /// The hasher used to compute the hash of a BatchInfo object.
+    (BatchInfoHasher, BATCH_INFO_HASHER, b"BatchInfo")
+}
+
+define_hasher! {
+    /// The hasher used to compute the hash of a ConsensusState object.
+    (ConsensusStateHasher, CONSENSUS_STATE_HASHER, b"ConsensusState")
+}
+
 define_hasher! {
     /// The hasher used to compute the hash of a ContractEvent object.
     (ContractEventHasher, CONTRACT_EVENT_HASHER, b"ContractEvent")
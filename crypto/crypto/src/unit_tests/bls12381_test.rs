@@ -3,7 +3,7 @@
 
 use crate::{
     bls12381::{
-        BLS12381PrivateKey, BLS12381PublicKey, BLS12381_PRIVATE_KEY_LENGTH,
+        BLS12381PrivateKey, BLS12381PublicKey, BLS12381Signature, BLS12381_PRIVATE_KEY_LENGTH,
         BLS12381_PUBLIC_KEY_LENGTH, BLS12381_SIGNATURE_LENGTH,
     },
     hash::HashValue,
@@ -11,6 +11,7 @@ use crate::{
     unit_tests::uniform_keypair_strategy,
 };
 use proptest::prelude::*;
+use rand::{rngs::StdRng, SeedableRng};
 use std::convert::TryFrom;
 
 proptest! {
@@ -89,3 +90,50 @@ proptest! {
         prop_assert!(keypair.public_key.verify_signature(&hash, &deserialized).is_ok());
     }
 }
+
+#[test]
+fn test_verify_aggregate() {
+    let mut rng: StdRng = SeedableRng::from_seed([0; 32]);
+    let private_keys: Vec<_> = (0..3)
+        .map(|_| BLS12381PrivateKey::generate_for_testing(&mut rng))
+        .collect();
+    let public_keys: Vec<BLS12381PublicKey> = private_keys.iter().map(Into::into).collect();
+    let message = HashValue::random();
+    let signatures: Vec<_> = private_keys
+        .iter()
+        .map(|private_key| private_key.sign_message(&message))
+        .collect();
+
+    assert!(
+        BLS12381Signature::verify_aggregate(&signatures, message.as_ref(), &public_keys).is_ok()
+    );
+    assert!(BLS12381Signature::verify_aggregate(
+        &signatures,
+        HashValue::random().as_ref(),
+        &public_keys
+    )
+    .is_err());
+    // Every signer must be accounted for.
+    assert!(BLS12381Signature::verify_aggregate(
+        &signatures[1..],
+        message.as_ref(),
+        &public_keys[1..]
+    )
+    .is_ok());
+    assert!(
+        BLS12381Signature::verify_aggregate(&signatures[1..], message.as_ref(), &public_keys)
+            .is_err()
+    );
+    let other_signature = private_keys[0].sign_message(&HashValue::random());
+    assert!(BLS12381Signature::verify_aggregate(
+        &[
+            other_signature,
+            signatures[1].clone(),
+            signatures[2].clone()
+        ],
+        message.as_ref(),
+        &public_keys
+    )
+    .is_err());
+    assert!(BLS12381Signature::verify_aggregate(&[], message.as_ref(), &[]).is_err());
+}
@@ -2,8 +2,9 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use bytecode_verifier::DuplicationChecker;
+use libra_types::{identifier::Identifier, vm_error::StatusCode};
 use proptest::prelude::*;
-use vm::file_format::CompiledModule;
+use vm::file_format::{empty_module, AbortCodeDefinition, CompiledModule};
 
 proptest! {
     #[test]
@@ -12,3 +13,29 @@ proptest! {
         prop_assert!(!duplication_checker.verify().is_empty());
     }
 }
+
+#[test]
+fn duplicate_abort_codes() {
+    let abort_code = |code: u64, name: &str| AbortCodeDefinition {
+        code,
+        name: Identifier::new(name).unwrap(),
+        description: String::new(),
+    };
+    let cases = vec![
+        (vec![abort_code(1, "EONE"), abort_code(2, "ETWO")], true),
+        (vec![abort_code(1, "EONE"), abort_code(1, "ETWO")], false),
+        (vec![abort_code(1, "EONE"), abort_code(2, "EONE")], false),
+    ];
+    for (abort_codes, is_valid) in cases {
+        let mut module = empty_module();
+        module.abort_codes = abort_codes;
+        let module = module.freeze().unwrap();
+        let errors = DuplicationChecker::new(&module).verify();
+        if is_valid {
+            assert!(errors.is_empty());
+        } else {
+            assert_eq!(errors.len(), 1);
+            assert_eq!(errors[0].major_status, StatusCode::DUPLICATE_ELEMENT);
+        }
+    }
+}
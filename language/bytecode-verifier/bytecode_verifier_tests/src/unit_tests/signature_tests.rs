@@ -161,6 +161,7 @@ fn no_verify_locals_good() {
                 },
             },
         ],
+        abort_codes: vec![],
     };
     assert!(VerifiedModule::new(compiled_module_good.freeze().unwrap()).is_ok());
 }
@@ -208,6 +209,7 @@ fn no_verify_locals_bad1() {
                 code: vec![Ret],
             },
         }],
+        abort_codes: vec![],
     };
     assert!(VerifiedModule::new(compiled_module_bad1.freeze().unwrap()).is_err());
 }
@@ -254,6 +256,7 @@ fn no_verify_locals_bad2() {
                 code: vec![Ret],
             },
         }],
+        abort_codes: vec![],
     };
     assert!(VerifiedModule::new(compiled_module_bad2.freeze().unwrap()).is_err());
 }
@@ -301,6 +304,7 @@ fn no_verify_locals_bad3() {
                 code: vec![Ret],
             },
         }],
+        abort_codes: vec![],
     };
     assert!(VerifiedModule::new(compiled_module_bad1.freeze().unwrap()).is_err());
 }
@@ -348,6 +352,7 @@ fn no_verify_locals_bad4() {
                 code: vec![Ret],
             },
         }],
+        abort_codes: vec![],
     };
     assert!(VerifiedModule::new(compiled_module_bad1.freeze().unwrap()).is_err());
 }
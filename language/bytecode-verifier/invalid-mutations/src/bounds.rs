@@ -62,6 +62,7 @@ impl PointerKind {
             UserString => &[],
             ByteArrayPool => &[],
             AddressPool => &[],
+            AbortCode => &[],
             // LocalPool and CodeDefinition are function-local, and this only works for
             // module-scoped indexes.
             // XXX maybe don't treat LocalPool and CodeDefinition the same way as the others?
@@ -8,6 +8,7 @@
 //! - struct and field definitions are consistent
 //! - the handles in struct and function definitions point to IMPLEMENTED_MODULE_INDEX
 //! - all struct and function handles pointing to IMPLEMENTED_MODULE_INDEX have a definition
+//! - no two abort codes share a code or a name
 use libra_types::vm_error::{StatusCode, VMStatus};
 use std::{collections::HashSet, hash::Hash};
 use vm::{
@@ -142,6 +143,16 @@ impl<'a> DuplicationChecker<'a> {
                 StatusCode::DUPLICATE_ELEMENT,
             ))
         }
+        let abort_codes = &self.module.as_inner().abort_codes;
+        if let Some(idx) = Self::first_duplicate_element(abort_codes.iter().map(|x| x.code))
+            .or_else(|| Self::first_duplicate_element(abort_codes.iter().map(|x| &x.name)))
+        {
+            errors.push(verification_error(
+                IndexKind::AbortCode,
+                idx,
+                StatusCode::DUPLICATE_ELEMENT,
+            ))
+        }
 
         // Check that:
         // (1) the order of struct definitions matches the order of field definitions,
@@ -0,0 +1,161 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! This module provides a checker for verifying that a module can replace a published version of
+//! itself. Modules linked against the published version and resources stored under its types
+//! must stay valid, so every struct must keep its kind and its layout, and every public function
+//! must stay public with the same signature. New structs and functions can be added, and the
+//! code of existing functions can change.
+use libra_types::vm_error::{StatusCode, VMStatus};
+use vm::{
+    access::ModuleAccess,
+    file_format::{CompiledModule, SignatureToken},
+    views::{
+        FunctionDefinitionView, ModuleView, StructDefinitionView, StructHandleView, ViewInternals,
+    },
+};
+
+pub struct CompatibilityChecker<'a> {
+    old_module: &'a CompiledModule,
+    new_module: &'a CompiledModule,
+}
+
+impl<'a> CompatibilityChecker<'a> {
+    pub fn new(old_module: &'a CompiledModule, new_module: &'a CompiledModule) -> Self {
+        Self {
+            old_module,
+            new_module,
+        }
+    }
+
+    pub fn verify(self) -> Vec<VMStatus> {
+        let old_view = ModuleView::new(self.old_module);
+        let new_view = ModuleView::new(self.new_module);
+        let mut errors = vec![];
+
+        for old_struct in old_view.structs() {
+            let name = old_struct.name();
+            let message = match new_view.struct_definition(name) {
+                None => Some("was removed"),
+                Some(new_struct) => self.struct_mismatch(&old_struct, new_struct),
+            };
+            if let Some(message) = message {
+                errors.push(
+                    VMStatus::new(StatusCode::INCOMPATIBLE_MODULE_UPGRADE)
+                        .with_message(format!("struct {} {}", name, message)),
+                );
+            }
+        }
+
+        for old_function in old_view.functions().filter(|function| function.is_public()) {
+            let name = old_function.name();
+            let message = match new_view.function_definition(name) {
+                Some(new_function) if new_function.is_public() => {
+                    self.function_mismatch(&old_function, new_function)
+                }
+                _ => Some("is no longer public"),
+            };
+            if let Some(message) = message {
+                errors.push(
+                    VMStatus::new(StatusCode::INCOMPATIBLE_MODULE_UPGRADE)
+                        .with_message(format!("function {} {}", name, message)),
+                );
+            }
+        }
+
+        errors
+    }
+
+    fn struct_mismatch(
+        &self,
+        old_struct: &StructDefinitionView<'a, CompiledModule>,
+        new_struct: &StructDefinitionView<'a, CompiledModule>,
+    ) -> Option<&'static str> {
+        if old_struct.is_nominal_resource() != new_struct.is_nominal_resource()
+            || old_struct.type_formals() != new_struct.type_formals()
+        {
+            return Some("changed kind");
+        }
+        let layout_matches = match (old_struct.fields(), new_struct.fields()) {
+            (None, None) => true,
+            (Some(old_fields), Some(new_fields)) => {
+                let old_fields: Vec<_> = old_fields.collect();
+                let new_fields: Vec<_> = new_fields.collect();
+                old_fields.len() == new_fields.len()
+                    && old_fields
+                        .iter()
+                        .zip(&new_fields)
+                        .all(|(old_field, new_field)| {
+                            old_field.name() == new_field.name()
+                                && self.tokens_match(
+                                    old_field.signature_token(),
+                                    new_field.signature_token(),
+                                )
+                        })
+            }
+            _ => false,
+        };
+        if layout_matches {
+            None
+        } else {
+            Some("changed layout")
+        }
+    }
+
+    fn function_mismatch(
+        &self,
+        old_function: &FunctionDefinitionView<'a, CompiledModule>,
+        new_function: &FunctionDefinitionView<'a, CompiledModule>,
+    ) -> Option<&'static str> {
+        let old_signature = old_function.signature().as_inner();
+        let new_signature = new_function.signature().as_inner();
+        let tokens_match = |old_tokens: &[SignatureToken], new_tokens: &[SignatureToken]| {
+            old_tokens.len() == new_tokens.len()
+                && old_tokens
+                    .iter()
+                    .zip(new_tokens)
+                    .all(|(old_token, new_token)| self.tokens_match(old_token, new_token))
+        };
+        if old_signature.type_formals == new_signature.type_formals
+            && tokens_match(&old_signature.arg_types, &new_signature.arg_types)
+            && tokens_match(&old_signature.return_types, &new_signature.return_types)
+        {
+            None
+        } else {
+            Some("changed signature")
+        }
+    }
+
+    /// Struct types are compared by the module and name of the struct, as the struct handle
+    /// indices of the two modules are unrelated.
+    fn tokens_match(&self, old_token: &SignatureToken, new_token: &SignatureToken) -> bool {
+        match (old_token, new_token) {
+            (
+                SignatureToken::Struct(old_idx, old_actuals),
+                SignatureToken::Struct(new_idx, new_actuals),
+            ) => {
+                let old_handle = StructHandleView::new(
+                    self.old_module,
+                    self.old_module.struct_handle_at(*old_idx),
+                );
+                let new_handle = StructHandleView::new(
+                    self.new_module,
+                    self.new_module.struct_handle_at(*new_idx),
+                );
+                old_handle.name() == new_handle.name()
+                    && old_handle.module_id() == new_handle.module_id()
+                    && old_actuals.len() == new_actuals.len()
+                    && old_actuals
+                        .iter()
+                        .zip(new_actuals)
+                        .all(|(old_actual, new_actual)| self.tokens_match(old_actual, new_actual))
+            }
+            (SignatureToken::Reference(old_inner), SignatureToken::Reference(new_inner))
+            | (
+                SignatureToken::MutableReference(old_inner),
+                SignatureToken::MutableReference(new_inner),
+            ) => self.tokens_match(old_inner, new_inner),
+            _ => old_token == new_token,
+        }
+    }
+}
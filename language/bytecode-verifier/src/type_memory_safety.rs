@@ -376,6 +376,21 @@ impl<'a> TypeAndMemorySafetyAnalysis<'a> {
         Ok(())
     }
 
+    fn cast(&mut self, offset: usize, target: SignatureToken) -> VMResult<()> {
+        let operand = self.stack.pop().unwrap();
+        if !operand.signature.is_integer() {
+            return Err(err_at_offset(
+                StatusCode::INTEGER_OP_TYPE_MISMATCH_ERROR,
+                offset,
+            ));
+        }
+        self.stack.push(StackAbstractValue {
+            signature: target,
+            value: AbstractValue::full_value(Kind::Unrestricted),
+        });
+        Ok(())
+    }
+
     fn execute_inner(
         &mut self,
         state: &mut AbstractState,
@@ -500,6 +515,14 @@ impl<'a> TypeAndMemorySafetyAnalysis<'a> {
                 self.borrow_field(state, offset, false, *field_definition_index)
             }
 
+            Bytecode::LdU8(_) => {
+                self.stack.push(StackAbstractValue {
+                    signature: SignatureToken::U8,
+                    value: AbstractValue::full_value(Kind::Unrestricted),
+                });
+                Ok(())
+            }
+
             Bytecode::LdConst(_) => {
                 self.stack.push(StackAbstractValue {
                     signature: SignatureToken::U64,
@@ -508,6 +531,20 @@ impl<'a> TypeAndMemorySafetyAnalysis<'a> {
                 Ok(())
             }
 
+            Bytecode::LdU128(_) => {
+                self.stack.push(StackAbstractValue {
+                    signature: SignatureToken::U128,
+                    value: AbstractValue::full_value(Kind::Unrestricted),
+                });
+                Ok(())
+            }
+
+            Bytecode::CastU8 => self.cast(offset, SignatureToken::U8),
+
+            Bytecode::CastU64 => self.cast(offset, SignatureToken::U64),
+
+            Bytecode::CastU128 => self.cast(offset, SignatureToken::U128),
+
             Bytecode::LdAddr(_) => {
                 self.stack.push(StackAbstractValue {
                     signature: SignatureToken::Address,
@@ -840,11 +877,9 @@ impl<'a> TypeAndMemorySafetyAnalysis<'a> {
             | Bytecode::Xor => {
                 let operand1 = self.stack.pop().unwrap();
                 let operand2 = self.stack.pop().unwrap();
-                if operand1.signature == SignatureToken::U64
-                    && operand2.signature == SignatureToken::U64
-                {
+                if operand1.signature.is_integer() && operand1.signature == operand2.signature {
                     self.stack.push(StackAbstractValue {
-                        signature: SignatureToken::U64,
+                        signature: operand1.signature,
                         value: AbstractValue::full_value(Kind::Unrestricted),
                     });
                     Ok(())
@@ -934,9 +969,7 @@ impl<'a> TypeAndMemorySafetyAnalysis<'a> {
             Bytecode::Lt | Bytecode::Gt | Bytecode::Le | Bytecode::Ge => {
                 let operand1 = self.stack.pop().unwrap();
                 let operand2 = self.stack.pop().unwrap();
-                if operand1.signature == SignatureToken::U64
-                    && operand2.signature == SignatureToken::U64
-                {
+                if operand1.signature.is_integer() && operand1.signature == operand2.signature {
                     self.stack.push(StackAbstractValue {
                         signature: SignatureToken::Bool,
                         value: AbstractValue::full_value(Kind::Unrestricted),
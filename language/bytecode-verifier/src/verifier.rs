@@ -16,14 +16,14 @@ use std::{collections::BTreeMap, fmt};
 use vm::{
     access::{ModuleAccess, ScriptAccess},
     errors::{append_err_info, verification_error},
-    file_format::{CompiledModule, CompiledProgram, CompiledScript},
+    file_format::{CompiledModule, CompiledProgram, CompiledScript, SignatureToken},
     resolver::Resolver,
     views::{ModuleView, ViewInternals},
     IndexKind,
 };
 use vm_runtime_types::{
     native_functions::dispatch::dispatch_native_function,
-    native_structs::dispatch::dispatch_native_struct,
+    native_structs::{def::NativeStructTag, dispatch::dispatch_native_struct},
 };
 
 /// A program that has been verified for internal consistency.
@@ -310,13 +310,54 @@ pub fn verify_main_signature(script: &CompiledScript) -> Vec<VMStatus> {
         return vec![VMStatus::new(StatusCode::INVALID_MAIN_FUNCTION_SIGNATURE)];
     }
     for arg_type in &function_signature.arg_types {
-        if !arg_type.is_primitive() {
+        if !is_main_arg_type(script, arg_type) {
             return vec![VMStatus::new(StatusCode::INVALID_MAIN_FUNCTION_SIGNATURE)];
         }
     }
     vec![]
 }
 
+/// Returns `true` if `main` can take an argument of type `arg_type`, that is a primitive type that
+/// a `TransactionArgument` can hold, or a `Vector.T` of such a type.
+fn is_main_arg_type(script: &CompiledScript, arg_type: &SignatureToken) -> bool {
+    match arg_type {
+        SignatureToken::Struct(idx, type_actuals) => {
+            let struct_handle = script.struct_handle_at(*idx);
+            let module_handle = script.module_handle_at(struct_handle.module);
+            let module_id = ModuleId::new(
+                *script.address_at(module_handle.address),
+                script.identifier_at(module_handle.name).to_owned(),
+            );
+            let struct_name = script.identifier_at(struct_handle.name);
+            let is_vector = match dispatch_native_struct(&module_id, struct_name) {
+                Some(native_struct) => native_struct.struct_type.tag == NativeStructTag::Vector,
+                None => false,
+            };
+            match type_actuals.as_slice() {
+                [element_type] => is_vector && is_main_primitive_arg_type(element_type),
+                _ => false,
+            }
+        }
+        _ => is_main_primitive_arg_type(arg_type),
+    }
+}
+
+fn is_main_primitive_arg_type(arg_type: &SignatureToken) -> bool {
+    match arg_type {
+        SignatureToken::Bool
+        | SignatureToken::U64
+        | SignatureToken::String
+        | SignatureToken::ByteArray
+        | SignatureToken::Address => true,
+        SignatureToken::U8
+        | SignatureToken::U128
+        | SignatureToken::Struct(_, _)
+        | SignatureToken::Reference(_)
+        | SignatureToken::MutableReference(_)
+        | SignatureToken::TypeParameter(_) => false,
+    }
+}
+
 /// Verification of a module in isolation (using `VerifiedModule::new`) trusts that struct and
 /// function handles not implemented in the module are declared correctly. The following procedure
 /// justifies this trust by checking that these declarations match the definitions in the module
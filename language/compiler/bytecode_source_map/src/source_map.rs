@@ -54,6 +54,13 @@ pub struct FunctionSourceMap<Location: Clone + Eq + Default> {
     pub code_map: BTreeMap<CodeOffset, Location>,
 }
 
+/// The name and explanation declared for a code a module aborts with.
+#[derive(Debug, Serialize, Deserialize, Clone, Eq, PartialEq)]
+pub struct AbortCodeDescription {
+    pub name: Identifier,
+    pub description: String,
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct ModuleSourceMap<Location: Clone + Eq + Default> {
     /// The name <address.module_name> for module that this source map is for
@@ -64,6 +71,10 @@ pub struct ModuleSourceMap<Location: Clone + Eq + Default> {
 
     // A mapping of FunctionDefinitionIndex to the soure map for that function.
     function_map: BTreeMap<TableIndex, FunctionSourceMap<Location>>,
+
+    // A mapping of the codes the module aborts with to their declared names and descriptions.
+    #[serde(default)]
+    abort_codes: BTreeMap<u64, AbortCodeDescription>,
 }
 
 impl<Location: Clone + Eq + Default> StructSourceMap<Location> {
@@ -208,6 +219,7 @@ impl<Location: Clone + Eq + Default> ModuleSourceMap<Location> {
             module_name: (module_name.address, module_name.name.into_inner()),
             struct_map: BTreeMap::new(),
             function_map: BTreeMap::new(),
+            abort_codes: BTreeMap::new(),
         }
     }
 
@@ -379,6 +391,30 @@ impl<Location: Clone + Eq + Default> ModuleSourceMap<Location> {
             .ok_or_else(|| format_err!("Unable to get struct source map"))
     }
 
+    pub fn add_abort_code(
+        &mut self,
+        code: u64,
+        name: Identifier,
+        description: String,
+    ) -> Result<()> {
+        match self
+            .abort_codes
+            .insert(code, AbortCodeDescription { name, description })
+        {
+            None => Ok(()),
+            Some(previous) => Err(format_err!(
+                "Abort code {} is declared multiple times, previously as {}",
+                code,
+                previous.name
+            )),
+        }
+    }
+
+    /// Returns the name and description the module declared for the abort code `code`, if any.
+    pub fn get_abort_code(&self, code: u64) -> Option<&AbortCodeDescription> {
+        self.abort_codes.get(&code)
+    }
+
     /// Create a 'dummy' source map for a compiled module. This is useful for e.g. disassembling
     /// with generated or real names depending upon if the source map is available or not.
     pub fn dummy_from_module(module: &CompiledModule) -> Result<Self> {
@@ -27,10 +27,11 @@ use std::{
 use vm::{
     access::ModuleAccess,
     file_format::{
-        self, Bytecode, CodeOffset, CodeUnit, CompiledModule, CompiledModuleMut, CompiledProgram,
-        CompiledScript, CompiledScriptMut, FieldDefinition, FieldDefinitionIndex,
-        FunctionDefinition, FunctionSignature, Kind, LocalsSignature, MemberCount, SignatureToken,
-        StructDefinition, StructFieldInformation, StructHandleIndex, TableIndex,
+        self, AbortCodeDefinition, Bytecode, CodeOffset, CodeUnit, CompiledModule,
+        CompiledModuleMut, CompiledProgram, CompiledScript, CompiledScriptMut, FieldDefinition,
+        FieldDefinitionIndex, FunctionDefinition, FunctionSignature, Kind, LocalsSignature,
+        MemberCount, SignatureToken, StructDefinition, StructFieldInformation, StructHandleIndex,
+        TableIndex,
     },
 };
 
@@ -138,7 +139,9 @@ enum InferredType {
 
     // Signature tokens
     Bool,
+    U8,
     U64,
+    U128,
     String,
     ByteArray,
     Address,
@@ -154,7 +157,9 @@ impl InferredType {
         use SignatureToken as S;
         match sig_token {
             S::Bool => I::Bool,
+            S::U8 => I::U8,
             S::U64 => I::U64,
+            S::U128 => I::U128,
             S::String => I::String,
             S::ByteArray => I::ByteArray,
             S::Address => I::Address,
@@ -175,7 +180,9 @@ impl InferredType {
         match self {
             InferredType::Anything => bail!("could not infer struct type"),
             InferredType::Bool => bail!("no struct type for Bool"),
+            InferredType::U8 => bail!("no struct type for U8"),
             InferredType::U64 => bail!("no struct type for U64"),
+            InferredType::U128 => bail!("no struct type for U128"),
             InferredType::String => bail!("no struct type for String"),
             InferredType::ByteArray => bail!("no struct type for ByteArray"),
             InferredType::Address => bail!("no struct type for Address"),
@@ -432,8 +439,23 @@ pub fn compile_module<'a, T: 'a + ModuleAccess>(
             byte_array_pool,
             address_pool,
         },
-        source_map,
+        mut source_map,
     ) = context.materialize_pools();
+    // The abort codes are published with the module, so that the clients can describe the aborts
+    // of any module they fetch.
+    let mut abort_codes = vec![];
+    for abort_code in module.abort_codes {
+        source_map.add_abort_code(
+            abort_code.code,
+            abort_code.name.clone(),
+            abort_code.description.clone(),
+        )?;
+        abort_codes.push(AbortCodeDefinition {
+            code: abort_code.code,
+            name: abort_code.name,
+            description: abort_code.description,
+        });
+    }
     let compiled_module = CompiledModuleMut {
         module_handles,
         struct_handles,
@@ -448,6 +470,7 @@ pub fn compile_module<'a, T: 'a + ModuleAccess>(
         struct_defs,
         field_defs,
         function_defs,
+        abort_codes,
     };
     compiled_module
         .freeze()
@@ -500,7 +523,9 @@ fn compile_types(context: &mut Context, tys: &[Type]) -> Result<Vec<SignatureTok
 fn compile_type(context: &mut Context, ty: &Type) -> Result<SignatureToken> {
     Ok(match ty {
         Type::Address => SignatureToken::Address,
+        Type::U8 => SignatureToken::U8,
         Type::U64 => SignatureToken::U64,
+        Type::U128 => SignatureToken::U128,
         Type::Bool => SignatureToken::Bool,
         Type::ByteArray => SignatureToken::ByteArray,
         Type::Reference(is_mutable, inner_type) => {
@@ -1006,11 +1031,21 @@ fn compile_expression(
                 function_frame.push()?;
                 vec_deque![InferredType::Address]
             }
+            CopyableVal::U8(i) => {
+                push_instr!(exp.span, Bytecode::LdU8(i));
+                function_frame.push()?;
+                vec_deque![InferredType::U8]
+            }
             CopyableVal::U64(i) => {
                 push_instr!(exp.span, Bytecode::LdConst(i));
                 function_frame.push()?;
                 vec_deque![InferredType::U64]
             }
+            CopyableVal::U128(i) => {
+                push_instr!(exp.span, Bytecode::LdU128(i));
+                function_frame.push()?;
+                vec_deque![InferredType::U128]
+            }
             CopyableVal::ByteArray(buf) => {
                 let buf_idx = context.byte_array_index(&buf)?;
                 push_instr!(exp.span, Bytecode::LdByteArray(buf_idx));
@@ -1070,41 +1105,42 @@ fn compile_expression(
             }
         }
         Exp::BinopExp(e1, op, e2) => {
-            compile_expression(context, function_frame, code, *e1)?;
+            // Arithmetic operations produce an integer of the same type as their operands.
+            let operand_types = compile_expression(context, function_frame, code, *e1)?;
             compile_expression(context, function_frame, code, *e2)?;
             function_frame.pop()?;
             match op {
                 BinOp::Add => {
                     push_instr!(exp.span, Bytecode::Add);
-                    vec_deque![InferredType::U64]
+                    operand_types
                 }
                 BinOp::Sub => {
                     push_instr!(exp.span, Bytecode::Sub);
-                    vec_deque![InferredType::U64]
+                    operand_types
                 }
                 BinOp::Mul => {
                     push_instr!(exp.span, Bytecode::Mul);
-                    vec_deque![InferredType::U64]
+                    operand_types
                 }
                 BinOp::Mod => {
                     push_instr!(exp.span, Bytecode::Mod);
-                    vec_deque![InferredType::U64]
+                    operand_types
                 }
                 BinOp::Div => {
                     push_instr!(exp.span, Bytecode::Div);
-                    vec_deque![InferredType::U64]
+                    operand_types
                 }
                 BinOp::BitOr => {
                     push_instr!(exp.span, Bytecode::BitOr);
-                    vec_deque![InferredType::U64]
+                    operand_types
                 }
                 BinOp::BitAnd => {
                     push_instr!(exp.span, Bytecode::BitAnd);
-                    vec_deque![InferredType::U64]
+                    operand_types
                 }
                 BinOp::Xor => {
                     push_instr!(exp.span, Bytecode::Xor);
-                    vec_deque![InferredType::U64]
+                    operand_types
                 }
                 BinOp::Or => {
                     push_instr!(exp.span, Bytecode::Or);
@@ -1309,6 +1345,24 @@ fn compile_call(
                     };
                     vec_deque![InferredType::Reference(inner_token)]
                 }
+                Builtin::ToU8 => {
+                    push_instr!(call.span, Bytecode::CastU8);
+                    function_frame.pop()?;
+                    function_frame.push()?;
+                    vec_deque![InferredType::U8]
+                }
+                Builtin::ToU64 => {
+                    push_instr!(call.span, Bytecode::CastU64);
+                    function_frame.pop()?;
+                    function_frame.push()?;
+                    vec_deque![InferredType::U64]
+                }
+                Builtin::ToU128 => {
+                    push_instr!(call.span, Bytecode::CastU128);
+                    function_frame.pop()?;
+                    function_frame.push()?;
+                    vec_deque![InferredType::U128]
+                }
             }
         }
         FunctionCall::ModuleFunctionCall {
@@ -585,7 +585,9 @@ impl<'a> Context<'a> {
     ) -> Result<SignatureToken> {
         Ok(match orig {
             x @ SignatureToken::Bool
+            | x @ SignatureToken::U8
             | x @ SignatureToken::U64
+            | x @ SignatureToken::U128
             | x @ SignatureToken::String
             | x @ SignatureToken::ByteArray
             | x @ SignatureToken::Address
@@ -14,6 +14,7 @@ use std::{
     collections::{HashSet, VecDeque},
     fmt,
     ops::Deref,
+    str::FromStr,
 };
 
 /// Generic wrapper that keeps file locations for any ast-node
@@ -92,6 +93,8 @@ pub struct ModuleDefinition {
     pub name: ModuleName,
     /// the module's dependencies
     pub imports: Vec<ImportDefinition>,
+    /// the names and descriptions of the codes the module aborts with
+    pub abort_codes: Vec<AbortCodeDefinition>,
     /// the structs (including resources) that the module defines
     pub structs: Vec<StructDefinition_>,
     /// the procedure that the module defines
@@ -106,6 +109,22 @@ pub enum ModuleIdent {
     Qualified(QualifiedModuleIdent),
 }
 
+//**************************************************************************************************
+// Abort codes
+//**************************************************************************************************
+
+/// The declaration of a code a module aborts with
+/// `abort_code ENAME = 10: "description";`
+#[derive(Clone, Debug, PartialEq)]
+pub struct AbortCodeDefinition {
+    /// the name of the abort code
+    pub name: Identifier,
+    /// the value passed to `abort`
+    pub code: u64,
+    /// a human readable explanation of the error
+    pub description: String,
+}
+
 //**************************************************************************************************
 // Imports
 //**************************************************************************************************
@@ -165,8 +184,12 @@ pub enum Kind {
 pub enum Type {
     /// `address`
     Address,
+    /// `u8`
+    U8,
     /// `u64`
     U64,
+    /// `u128`
+    U128,
     /// `bool`
     Bool,
     /// `bytearray`
@@ -335,6 +358,13 @@ pub enum Builtin {
 
     /// Convert a mutable reference into an immutable one
     Freeze,
+
+    /// Cast an integer into a `u8`, aborting if it doesn't fit
+    ToU8,
+    /// Cast an integer into a `u64`, aborting if it doesn't fit
+    ToU64,
+    /// Cast an integer into a `u128`
+    ToU128,
 }
 
 /// Enum for different function calls
@@ -447,8 +477,12 @@ pub type Block_ = Spanned<Block>;
 pub enum CopyableVal {
     /// An address in the global storage
     Address(AccountAddress),
+    /// An unsigned 8-bit integer, `<n>u8`
+    U8(u8),
     /// An unsigned 64-bit integer
     U64(u64),
+    /// An unsigned 128-bit integer, `<n>u128`
+    U128(u128),
     /// true or false
     Bool(bool),
     /// `b"<bytes>"`
@@ -659,18 +693,20 @@ impl ModuleIdent {
 }
 
 impl ModuleDefinition {
-    /// Creates a new `ModuleDefinition` from its string name, dependencies, structs+resources,
-    /// and procedures
+    /// Creates a new `ModuleDefinition` from its string name, dependencies, abort codes,
+    /// structs+resources, and procedures
     /// Does not verify the correctness of any internal properties of its elements
     pub fn new<L, T>(
         name: impl Into<Box<str>>,
         imports: Vec<ImportDefinition>,
+        abort_codes: Vec<AbortCodeDefinition>,
         structs: Vec<StructDefinition_>,
         functions: Vec<(FunctionName, Function_)>,
     ) -> Result<Self, ParseError<L, T, failure::Error>> {
         Ok(ModuleDefinition {
             name: ModuleName::parse(name.into())?,
             imports,
+            abort_codes,
             structs,
             functions,
         })
@@ -682,6 +718,22 @@ impl ModuleDefinition {
     }
 }
 
+impl AbortCodeDefinition {
+    /// Creates a new abort code declaration from its raw string name, code and description.
+    /// Intended for use by syntax.lalrpop.
+    pub fn parse<L, T>(
+        name: impl Into<Box<str>>,
+        code: u64,
+        description: String,
+    ) -> Result<Self, ParseError<L, T, failure::Error>> {
+        Ok(AbortCodeDefinition {
+            name: parse_identifier(name.into())?,
+            code,
+            description,
+        })
+    }
+}
+
 impl Type {
     /// Creates a new struct type
     pub fn r#struct(ident: QualifiedStructIdent, type_actuals: Vec<Type>) -> Type {
@@ -698,11 +750,21 @@ impl Type {
         Type::Address
     }
 
+    /// Creates a new u8 type
+    pub fn u8() -> Type {
+        Type::U8
+    }
+
     /// Creates a new u64 type
     pub fn u64() -> Type {
         Type::U64
     }
 
+    /// Creates a new u128 type
+    pub fn u128() -> Type {
+        Type::U128
+    }
+
     /// Creates a new bool type
     pub fn bool() -> Type {
         Type::Bool
@@ -1071,6 +1133,18 @@ fn parse_identifier<L, T>(s: Box<str>) -> Result<Identifier, ParseError<L, T, fa
     Identifier::new(s).map_err(|error| ParseError::User { error })
 }
 
+/// Parses the digits of an integer literal, failing if the value does not fit in the type.
+/// Intended for use by syntax.lalrpop.
+pub fn parse_integer<N, L, T>(digits: &str) -> Result<N, ParseError<L, T, failure::Error>>
+where
+    N: FromStr,
+    N::Err: fmt::Display,
+{
+    N::from_str(digits).map_err(|err| ParseError::User {
+        error: failure::format_err!("Invalid integer literal {}: {}", digits, err),
+    })
+}
+
 //**************************************************************************************************
 // Trait impls
 //**************************************************************************************************
@@ -1206,6 +1280,10 @@ impl fmt::Display for QualifiedModuleIdent {
 impl fmt::Display for ModuleDefinition {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         writeln!(f, "Module({}, ", self.name)?;
+        write!(f, "AbortCodes(")?;
+        for abort_code in &self.abort_codes {
+            write!(f, "{}, ", abort_code)?;
+        }
         write!(f, "Structs(")?;
         for struct_def in &self.structs {
             write!(f, "{}, ", struct_def)?;
@@ -1218,6 +1296,12 @@ impl fmt::Display for ModuleDefinition {
     }
 }
 
+impl fmt::Display for AbortCodeDefinition {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} = {}: {:?}", self.name, self.code, self.description)
+    }
+}
+
 impl fmt::Display for StructDefinition {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         writeln!(
@@ -1324,7 +1408,9 @@ fn format_type_formals(formals: &[(TypeVar_, Kind)]) -> String {
 impl fmt::Display for Type {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
+            Type::U8 => write!(f, "u8"),
             Type::U64 => write!(f, "u64"),
+            Type::U128 => write!(f, "u128"),
             Type::Bool => write!(f, "bool"),
             Type::Address => write!(f, "address"),
             Type::ByteArray => write!(f, "bytearray"),
@@ -1370,6 +1456,9 @@ impl fmt::Display for Builtin {
                 write!(f, "move_to_sender<{}{}>", t, format_type_actuals(tys))
             }
             Builtin::Freeze => write!(f, "freeze"),
+            Builtin::ToU8 => write!(f, "to_u8"),
+            Builtin::ToU64 => write!(f, "to_u64"),
+            Builtin::ToU128 => write!(f, "to_u128"),
         }
     }
 }
@@ -1496,7 +1585,9 @@ impl fmt::Display for Block {
 impl fmt::Display for CopyableVal {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
+            CopyableVal::U8(v) => write!(f, "{}u8", v),
             CopyableVal::U64(v) => write!(f, "{}", v),
+            CopyableVal::U128(v) => write!(f, "{}u128", v),
             CopyableVal::Bool(v) => write!(f, "{}", v),
             CopyableVal::ByteArray(v) => write!(f, "{}", v),
             CopyableVal::Address(v) => write!(f, "0x{}", hex::encode(&v)),
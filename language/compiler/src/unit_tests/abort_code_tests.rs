@@ -0,0 +1,61 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use ir_to_bytecode::{compiler::compile_module, parser::parse_module};
+use libra_types::{account_address::AccountAddress, identifier::Identifier};
+use vm::file_format::CompiledModule;
+
+#[test]
+fn compile_module_with_abort_codes() {
+    let code = String::from(
+        "
+        module Foobar {
+            abort_code ENOT_FOUND = 1: \"The foo wasn't found\";
+            abort_code ETOO_LARGE = 42: \"The foo is larger than the bar\";
+
+            public check(foo: u64, bar: u64) {
+                assert(move(foo) <= move(bar), 42);
+                return;
+            }
+        }
+        ",
+    );
+    let module = parse_module(&code).unwrap();
+    assert_eq!(module.abort_codes.len(), 2);
+
+    let deps: Vec<CompiledModule> = vec![];
+    let (compiled_module, source_map) =
+        compile_module(AccountAddress::default(), module, &deps).unwrap();
+    let abort_code = source_map.get_abort_code(42).unwrap();
+    assert_eq!(abort_code.name, Identifier::new("ETOO_LARGE").unwrap());
+    assert_eq!(abort_code.description, "The foo is larger than the bar");
+    assert_eq!(
+        source_map.get_abort_code(1).unwrap().name,
+        Identifier::new("ENOT_FOUND").unwrap()
+    );
+    assert!(source_map.get_abort_code(2).is_none());
+
+    // The abort codes are published along with the module.
+    let mut binary = vec![];
+    compiled_module.serialize(&mut binary).unwrap();
+    let published_module = CompiledModule::deserialize(&binary).unwrap();
+    let abort_code = published_module.abort_code(42).unwrap();
+    assert_eq!(abort_code.name, Identifier::new("ETOO_LARGE").unwrap());
+    assert_eq!(abort_code.description, "The foo is larger than the bar");
+    assert!(published_module.abort_code(2).is_none());
+}
+
+#[test]
+fn compile_module_with_duplicate_abort_codes() {
+    let code = String::from(
+        "
+        module Foobar {
+            abort_code EFOO = 1: \"Foo\";
+            abort_code EBAR = 1: \"Bar\";
+        }
+        ",
+    );
+    let module = parse_module(&code).unwrap();
+    let deps: Vec<CompiledModule> = vec![];
+    assert!(compile_module(AccountAddress::default(), module, &deps).is_err());
+}
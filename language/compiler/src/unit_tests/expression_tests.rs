@@ -5,6 +5,7 @@ use crate::unit_tests::testutils::{
     compile_module_string, compile_script_string, compile_script_string_and_assert_error,
     count_locals,
 };
+use ir_to_bytecode::parser::parse_program;
 use vm::{
     access::{ModuleAccess, ScriptAccess},
     file_format::Bytecode::*,
@@ -239,3 +240,63 @@ fn compile_borrow_field() {
     let compiled_module_res = compile_module_string(&code);
     let _compiled_module = compiled_module_res.unwrap();
 }
+
+#[test]
+fn compile_script_expr_sized_integers() {
+    let code = String::from(
+        "
+        main() {
+            let x: u8;
+            let y: u128;
+            let z: u64;
+            x = 255u8;
+            y = to_u128(copy(x)) * 340282366920938463463374607431768211455u128;
+            z = to_u64(to_u8(move(y))) + to_u64(move(x));
+            return;
+        }
+        ",
+    );
+    let compiled_script = compile_script_string(&code).unwrap();
+    let code = &compiled_script.main().code.code;
+    assert!(code.contains(&LdU8(255)));
+    assert!(code.contains(&LdU128(u128::max_value())));
+    assert!(code.contains(&CastU8));
+    assert!(code.contains(&CastU64));
+    assert!(code.contains(&CastU128));
+}
+
+#[test]
+fn compile_script_expr_mixed_integer_types() {
+    let code = String::from(
+        "
+        main() {
+            let x: u64;
+            x = 1u8 + 2;
+            return;
+        }
+        ",
+    );
+    let compiled_script_res = compile_script_string_and_assert_error(&code, vec![]);
+    assert!(compiled_script_res.is_ok());
+}
+
+#[test]
+fn compile_script_expr_out_of_range_literals() {
+    for literal in &[
+        "256u8",
+        "340282366920938463463374607431768211456u128",
+        "18446744073709551616",
+    ] {
+        let code = format!(
+            "
+            main() {{
+                let x: u64;
+                x = to_u64({});
+                return;
+            }}
+            ",
+            literal
+        );
+        assert!(parse_program(&code).is_err());
+    }
+}
@@ -5,6 +5,7 @@
 #[macro_use]
 pub(crate) mod testutils;
 
+mod abort_code_tests;
 mod branch_tests;
 mod cfg_tests;
 mod expression_tests;
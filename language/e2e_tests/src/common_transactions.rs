@@ -22,6 +22,8 @@ lazy_static! {
     pub static ref PEER_TO_PEER: Vec<u8> = { peer_to_peer() };
     /// A serialized transaction to change the keys for an account.
     pub static ref ROTATE_KEY: Vec<u8> = { rotate_key() };
+    /// A serialized transaction to update the gas schedule.
+    pub static ref UPDATE_GAS_SCHEDULE: Vec<u8> = { update_gas_schedule() };
 }
 
 /// Returns a transaction to create a new account with the given arguments.
@@ -120,3 +122,7 @@ fn peer_to_peer() -> Vec<u8> {
 fn rotate_key() -> Vec<u8> {
     compile_script(transaction_scripts::rotate_key())
 }
+
+fn update_gas_schedule() -> Vec<u8> {
+    compile_script(transaction_scripts::update_gas_schedule())
+}
@@ -18,7 +18,7 @@ use libra_types::{
 };
 use state_view::StateView;
 use vm::CompiledModule;
-use vm_runtime::{MoveVM, VMExecutor, VMVerifier};
+use vm_runtime::{tracer::TraceEvent, MoveVM, VMExecutor, VMVerifier};
 
 /// Provides an environment to run a VM instance.
 ///
@@ -89,6 +89,12 @@ impl FakeExecutor {
         }
     }
 
+    /// Sets the number of threads used to speculatively execute the transactions of a block. Blocks
+    /// are executed sequentially if this is 1 or less.
+    pub fn set_execution_concurrency_level(&mut self, concurrency_level: usize) {
+        self.config.vm_config.execution_concurrency_level = concurrency_level;
+    }
+
     /// Creates a number of [`Account`] instances all with the same balance and sequence number,
     /// and publishes them to this executor's data store.
     pub fn create_accounts(&mut self, size: usize, balance: u64, seq_num: u64) -> Vec<Account> {
@@ -150,6 +156,19 @@ impl FakeExecutor {
             .expect("A block with one transaction should have one output")
     }
 
+    /// Executes the given transaction and records a trace of every instruction it executes.
+    ///
+    /// Like `execute_transaction`, this doesn't apply the results to the data store.
+    pub fn trace_transaction(
+        &self,
+        txn: SignedTransaction,
+    ) -> (TransactionOutput, Vec<TraceEvent>) {
+        let vm = MoveVM::new(&self.config.vm_config);
+        let mut trace = vec![];
+        let output = vm.execute_transaction_with_trace(txn, &self.data_store, &mut trace);
+        (output, trace)
+    }
+
     /// Get the blob for the associated AccessPath
     pub fn read_from_access_path(&self, path: &AccessPath) -> Option<Vec<u8>> {
         StateView::get(&self.data_store, path).unwrap()
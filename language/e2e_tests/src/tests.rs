@@ -12,7 +12,9 @@
 mod account_universe;
 mod arithmetic;
 mod create_account;
+mod execution_trace;
 mod function_call;
+mod gas_schedule;
 mod genesis;
 mod mint;
 mod module_publishing;
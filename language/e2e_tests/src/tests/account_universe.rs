@@ -82,6 +82,18 @@ proptest! {
 
         run_and_assert_universe(universe, transactions)?;
     }
+
+    #[test]
+    fn parallel_execution_equivalence(
+        universe in AccountUniverseGen::strategy(
+            2..default_num_accounts(),
+            log_balance_strategy(10_000_000),
+        ),
+        transactions in vec(all_transactions_strategy(1, 1_000_000), 0..default_num_transactions()),
+        concurrency_level in 2usize..8,
+    ) {
+        run_and_assert_parallel_equivalence(universe, transactions, concurrency_level)?;
+    }
 }
 
 /// A strategy that returns a random transaction.
@@ -160,6 +172,31 @@ pub(crate) fn run_and_assert_universe(
     })
 }
 
+/// Run these transactions with both the sequential and the parallel executor, and make sure that
+/// they produce the same outputs.
+pub(crate) fn run_and_assert_parallel_equivalence(
+    universe: AccountUniverseGen,
+    transaction_gens: Vec<impl AUTransactionGen + Clone>,
+    concurrency_level: usize,
+) -> Result<(), TestCaseError> {
+    test_all_genesis_impl({
+        |mut executor| {
+            let mut universe = universe.clone().setup(&mut executor);
+            let transactions: Vec<_> = transaction_gens
+                .iter()
+                .map(|transaction_gen| transaction_gen.clone().apply(&mut universe).0)
+                .collect();
+            let sequential_outputs = executor.execute_block(transactions.clone());
+
+            executor.set_execution_concurrency_level(concurrency_level);
+            let parallel_outputs = executor.execute_block(transactions);
+
+            prop_assert_eq!(sequential_outputs, parallel_outputs);
+            Ok(())
+        }
+    })
+}
+
 /// Verify that the account information in the universe matches the information in the executor.
 pub(crate) fn assert_accounts_match(
     universe: &AccountUniverse,
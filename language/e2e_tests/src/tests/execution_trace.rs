@@ -0,0 +1,87 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{
+    account::AccountData, common_transactions::peer_to_peer_txn, compile::compile_script,
+    executor::FakeExecutor, transaction_status_eq,
+};
+use config::config::VMPublishingOption;
+use libra_types::{
+    transaction::TransactionStatus,
+    vm_error::{StatusCode, VMStatus},
+};
+use vm::file_format::Bytecode;
+
+#[test]
+fn trace_matches_execution() {
+    let mut executor = FakeExecutor::from_genesis_file();
+    let sender = AccountData::new(1_000_000, 10);
+    let receiver = AccountData::new(100_000, 10);
+    executor.add_account_data(&sender);
+    executor.add_account_data(&receiver);
+
+    let txn = peer_to_peer_txn(sender.account(), receiver.account(), 10, 1_000);
+    let output = executor.execute_transaction(txn.clone());
+    let (traced_output, trace) = executor.trace_transaction(txn);
+
+    // Tracing must not change the outcome of the transaction.
+    assert_eq!(output, traced_output);
+    assert!(!trace.is_empty());
+
+    // The prologue runs before the script, and the script runs before the epilogue.
+    let first_main = trace
+        .iter()
+        .position(|event| event.function.as_str() == "main")
+        .expect("the script should be traced");
+    let last_main = trace
+        .iter()
+        .rposition(|event| event.function.as_str() == "main")
+        .unwrap();
+    assert!(first_main > 0);
+    assert!(last_main < trace.len() - 1);
+    assert_eq!(trace[first_main].pc, 0);
+    assert_eq!(trace[last_main].instruction, Bytecode::Ret);
+
+    // Instructions are traced before they're charged for, so the remaining gas never goes up.
+    for events in trace.windows(2) {
+        assert!(events[0].gas_remaining >= events[1].gas_remaining);
+        assert!(events[0].stack.len() <= events[0].stack_height);
+    }
+}
+
+#[test]
+fn trace_ends_at_abort() {
+    let mut executor = FakeExecutor::from_genesis_with_options(VMPublishingOption::CustomScripts);
+    let sender = AccountData::new(1_000_000, 10);
+    executor.add_account_data(&sender);
+
+    let script = compile_script(
+        "
+        main() {
+            let x: u64;
+            x = 3;
+            assert(copy(x) == 4, 42);
+            return;
+        }
+        ",
+    );
+    let txn = sender
+        .account()
+        .create_signed_txn_with_args(script, vec![], 10, 100_000, 1);
+    let (output, trace) = executor.trace_transaction(txn);
+    assert!(transaction_status_eq(
+        output.status(),
+        &TransactionStatus::Keep(VMStatus::new(StatusCode::ABORTED).with_sub_status(42))
+    ));
+
+    let main_events: Vec<_> = trace
+        .iter()
+        .filter(|event| event.function.as_str() == "main")
+        .collect();
+    let abort = main_events.last().expect("the script should be traced");
+    assert_eq!(abort.instruction, Bytecode::Abort);
+    assert_eq!(abort.call_depth, 1);
+    // The abort code is on top of the stack when the abort is executed.
+    assert_eq!(abort.stack.len(), 1);
+    assert!(abort.stack[0].contains("42"));
+}
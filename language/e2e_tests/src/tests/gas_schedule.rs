@@ -0,0 +1,122 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{
+    account::{Account, AccountData},
+    common_transactions::peer_to_peer_txn,
+    executor::FakeExecutor,
+    gas_costs, transaction_status_eq,
+};
+use libra_types::{
+    byte_array::ByteArray,
+    transaction::{
+        Script, SignedTransaction, TransactionArgument, TransactionPayload, TransactionStatus,
+    },
+    vm_error::{StatusCode, VMStatus},
+};
+use transaction_builder::encode_update_gas_schedule_script;
+use vm::gas_schedule::{GasAlgebra, GasSchedule, GasUnits, DEFAULT_GAS_SCHEDULE};
+
+// Storing a whole gas schedule costs more than the gas usually reserved for a transaction.
+const UPDATE_GAS_SCHEDULE_MAX_GAS: u64 = 500_000;
+
+fn update_gas_schedule_txn(sender: &Account, script: Script, seq_num: u64) -> SignedTransaction {
+    sender.create_signed_txn(
+        TransactionPayload::Script(script),
+        seq_num,
+        UPDATE_GAS_SCHEDULE_MAX_GAS,
+        1,
+    )
+}
+
+/// Returns the default gas schedule with every instruction costing twice as much.
+fn doubled_gas_schedule() -> GasSchedule {
+    let mut schedule = DEFAULT_GAS_SCHEDULE.clone();
+    for cost in schedule.cost_table.compute_table.values_mut() {
+        *cost = GasUnits::new(cost.get() * 2);
+    }
+    schedule
+}
+
+fn assert_aborted(output_status: &TransactionStatus, code: u64) {
+    assert!(transaction_status_eq(
+        output_status,
+        &TransactionStatus::Keep(VMStatus::new(StatusCode::ABORTED).with_sub_status(code))
+    ));
+}
+
+#[test]
+fn update_gas_schedule_applies_from_next_block() {
+    let mut executor = FakeExecutor::from_genesis_file();
+    let association = Account::new_association();
+    let sender = AccountData::new(1_000_000, 10);
+    let receiver = AccountData::new(1_000_000, 10);
+    executor.add_account_data(&sender);
+    executor.add_account_data(&receiver);
+
+    let update = update_gas_schedule_txn(
+        &association,
+        encode_update_gas_schedule_script(&doubled_gas_schedule()),
+        1,
+    );
+    let transfer = peer_to_peer_txn(sender.account(), receiver.account(), 10, 1_000);
+    let output = executor.execute_block(vec![update, transfer]);
+    for txn_output in &output {
+        assert_eq!(
+            txn_output.status(),
+            &TransactionStatus::Keep(VMStatus::new(StatusCode::EXECUTED))
+        );
+        executor.apply_write_set(txn_output.write_set());
+    }
+    // The rest of the block is still charged according to the previous schedule.
+    assert_eq!(output[1].gas_used(), *gas_costs::PEER_TO_PEER);
+
+    let transfer = peer_to_peer_txn(sender.account(), receiver.account(), 11, 1_000);
+    let output = executor.execute_transaction(transfer);
+    assert_eq!(
+        output.status(),
+        &TransactionStatus::Keep(VMStatus::new(StatusCode::EXECUTED))
+    );
+    assert!(output.gas_used() > *gas_costs::PEER_TO_PEER);
+}
+
+#[test]
+fn update_gas_schedule_requires_association() {
+    let mut executor = FakeExecutor::from_genesis_file();
+    let sender = AccountData::new(1_000_000, 10);
+    executor.add_account_data(&sender);
+
+    let txn = update_gas_schedule_txn(
+        sender.account(),
+        encode_update_gas_schedule_script(&doubled_gas_schedule()),
+        10,
+    );
+    let output = executor.execute_transaction(txn);
+    assert_aborted(output.status(), 1);
+}
+
+#[test]
+fn update_gas_schedule_rejects_invalid_schedule() {
+    let executor = FakeExecutor::from_genesis_file();
+    let association = Account::new_association();
+    let code = encode_update_gas_schedule_script(&DEFAULT_GAS_SCHEDULE)
+        .code()
+        .to_vec();
+
+    let mut unbounded = DEFAULT_GAS_SCHEDULE.clone();
+    unbounded.maximum_number_of_gas_units = GasUnits::new(0);
+    let invalid_schedules = vec![
+        // Not a gas schedule at all.
+        vec![1, 2, 3],
+        // A gas schedule that doesn't pass validation.
+        unbounded.to_bytes().unwrap(),
+    ];
+    for schedule in invalid_schedules {
+        let script = Script::new(
+            code.clone(),
+            vec![TransactionArgument::ByteArray(ByteArray::new(schedule))],
+        );
+        let output = executor.execute_transaction(update_gas_schedule_txn(&association, script, 1));
+        assert_aborted(output.status(), 2);
+    }
+}
@@ -5,9 +5,11 @@ use crate::{
     account::AccountData, assert_prologue_parity, assert_status_eq,
     compile::compile_module_with_address, executor::FakeExecutor, transaction_status_eq,
 };
+use bytecode_verifier::VerifiedModule;
+use compiler::Compiler;
 use config::config::VMPublishingOption;
 use libra_types::{
-    transaction::TransactionStatus,
+    transaction::{Module, TransactionOutput, TransactionPayload, TransactionStatus},
     vm_error::{StatusCode, StatusType, VMStatus},
 };
 
@@ -165,3 +167,322 @@ pub fn test_publishing_allow_modules() {
         &TransactionStatus::Keep(VMStatus::new(StatusCode::EXECUTED))
     );
 }
+
+const MODULE_V1: &str = "
+    module M {
+        resource T { value: u64 }
+        struct S { flag: bool }
+
+        public double(x: u64): u64 {
+            return move(x) * 2;
+        }
+
+        helper(x: u64): u64 {
+            return move(x) + 1;
+        }
+    }
+";
+
+// Adds a struct and a function, changes the code of `double` and removes the private `helper`.
+const COMPATIBLE_MODULE_V2: &str = "
+    module M {
+        resource T { value: u64 }
+        struct S { flag: bool }
+        struct U { count: u64 }
+
+        public double(x: u64): u64 {
+            let y: u64;
+            y = copy(x);
+            return move(x) + move(y);
+        }
+
+        public triple(x: u64): u64 {
+            return move(x) * 3;
+        }
+    }
+";
+
+/// Publishes `MODULE_V1` from a new account, then publishes `new_code` as the same module from
+/// that account and returns the output.
+fn upgrade_module(publishing_option: VMPublishingOption, new_code: &str) -> TransactionOutput {
+    let mut executor = FakeExecutor::from_genesis_with_options(publishing_option);
+
+    let sequence_number = 2;
+    let account = AccountData::new(1_000_000, sequence_number);
+    executor.add_account_data(&account);
+
+    let publish = |code: &str, sequence_number: u64| {
+        account.account().create_signed_txn_impl(
+            *account.address(),
+            compile_module_with_address(account.address(), code),
+            sequence_number,
+            100_000,
+            1,
+        )
+    };
+
+    let output = executor.execute_transaction(publish(MODULE_V1, sequence_number));
+    assert!(transaction_status_eq(
+        &output.status(),
+        &TransactionStatus::Keep(VMStatus::new(StatusCode::EXECUTED)),
+    ));
+    executor.apply_write_set(output.write_set());
+
+    executor.execute_transaction(publish(new_code, sequence_number + 1))
+}
+
+#[test]
+fn upgrade_module_not_allowed() {
+    let output = upgrade_module(VMPublishingOption::Open, COMPATIBLE_MODULE_V2);
+    assert!(transaction_status_eq(
+        &output.status(),
+        &TransactionStatus::Keep(VMStatus::new(StatusCode::DUPLICATE_MODULE_NAME)),
+    ));
+}
+
+#[test]
+fn upgrade_module_compatible() {
+    let output = upgrade_module(VMPublishingOption::Upgradable, COMPATIBLE_MODULE_V2);
+    assert!(transaction_status_eq(
+        &output.status(),
+        &TransactionStatus::Keep(VMStatus::new(StatusCode::EXECUTED)),
+    ));
+}
+
+#[test]
+fn upgrade_module_incompatible() {
+    let incompatible_modules = vec![
+        // `double` changes signature.
+        "
+        module M {
+            resource T { value: u64 }
+            struct S { flag: bool }
+            public double(x: u64): bool {
+                return move(x) > 2;
+            }
+        }
+        ",
+        // `double` is no longer public.
+        "
+        module M {
+            resource T { value: u64 }
+            struct S { flag: bool }
+            double(x: u64): u64 {
+                return move(x) * 2;
+            }
+        }
+        ",
+        // `double` is removed.
+        "
+        module M {
+            resource T { value: u64 }
+            struct S { flag: bool }
+        }
+        ",
+        // `T` gets a new field.
+        "
+        module M {
+            resource T { value: u64, extra: u64 }
+            struct S { flag: bool }
+            public double(x: u64): u64 {
+                return move(x) * 2;
+            }
+        }
+        ",
+        // `S` changes the type of its field.
+        "
+        module M {
+            resource T { value: u64 }
+            struct S { flag: u64 }
+            public double(x: u64): u64 {
+                return move(x) * 2;
+            }
+        }
+        ",
+        // `T` is no longer a resource.
+        "
+        module M {
+            struct T { value: u64 }
+            struct S { flag: bool }
+            public double(x: u64): u64 {
+                return move(x) * 2;
+            }
+        }
+        ",
+        // `S` is removed.
+        "
+        module M {
+            resource T { value: u64 }
+            public double(x: u64): u64 {
+                return move(x) * 2;
+            }
+        }
+        ",
+    ];
+
+    for new_code in incompatible_modules {
+        let output = upgrade_module(VMPublishingOption::Upgradable, new_code);
+        assert!(transaction_status_eq(
+            &output.status(),
+            &TransactionStatus::Keep(VMStatus::new(StatusCode::INCOMPATIBLE_MODULE_UPGRADE)),
+        ));
+    }
+}
+
+// A script executed after an upgrade in the same block should link against the new version
+#[test]
+fn upgraded_module_reloaded() {
+    let mut executor = FakeExecutor::from_genesis_with_options(VMPublishingOption::Upgradable);
+
+    let sequence_number = 2;
+    let account = AccountData::new(1_000_000, sequence_number);
+    executor.add_account_data(&account);
+
+    let output = executor.execute_transaction(account.account().create_signed_txn_impl(
+        *account.address(),
+        compile_module_with_address(account.address(), MODULE_V1),
+        sequence_number,
+        100_000,
+        1,
+    ));
+    executor.apply_write_set(output.write_set());
+
+    let upgrade_txn = account.account().create_signed_txn_impl(
+        *account.address(),
+        compile_module_with_address(account.address(), COMPATIBLE_MODULE_V2),
+        sequence_number + 1,
+        100_000,
+        1,
+    );
+
+    let compiler = Compiler {
+        address: *account.address(),
+        ..Compiler::default()
+    };
+    let module_v2 = compiler
+        .into_compiled_module(COMPATIBLE_MODULE_V2)
+        .expect("Failed to compile");
+    let code = format!(
+        "
+        import 0x{}.M;
+
+        main() {{
+            assert(M.triple(2) == 6, 42);
+            assert(M.double(2) == 4, 43);
+            return;
+        }}
+        ",
+        account.address()
+    );
+    let compiler = Compiler {
+        address: *account.address(),
+        extra_deps: vec![VerifiedModule::new(module_v2).expect("Failed to verify")],
+        ..Compiler::default()
+    };
+    let script = compiler.into_script_blob(&code).expect("Failed to compile");
+    let script_txn = account.account().create_signed_txn_with_args(
+        script,
+        vec![],
+        sequence_number + 2,
+        100_000,
+        1,
+    );
+
+    let outputs = executor.execute_block(vec![upgrade_txn, script_txn]);
+    for output in &outputs {
+        assert!(transaction_status_eq(
+            &output.status(),
+            &TransactionStatus::Keep(VMStatus::new(StatusCode::EXECUTED)),
+        ));
+    }
+}
+
+// An upgrade importing a module that imports the upgraded module should be rejected
+#[test]
+fn upgrade_module_cyclic_dependency() {
+    let mut executor = FakeExecutor::from_genesis_with_options(VMPublishingOption::Upgradable);
+
+    let sequence_number = 2;
+    let account = AccountData::new(1_000_000, sequence_number);
+    executor.add_account_data(&account);
+
+    let compile = |code: &str, extra_deps: Vec<VerifiedModule>| {
+        let compiler = Compiler {
+            address: *account.address(),
+            extra_deps,
+            ..Compiler::default()
+        };
+        let module = compiler
+            .into_compiled_module(code)
+            .expect("Failed to compile");
+        VerifiedModule::new(module).expect("Failed to verify")
+    };
+    let publish = |module: &VerifiedModule, sequence_number: u64| {
+        let mut blob = vec![];
+        module
+            .serialize(&mut blob)
+            .expect("Failed to serialize module");
+        account.account().create_signed_txn_impl(
+            *account.address(),
+            TransactionPayload::Module(Module::new(blob)),
+            sequence_number,
+            100_000,
+            1,
+        )
+    };
+
+    let module_m = compile(MODULE_V1, vec![]);
+    let module_n = compile(
+        &format!(
+            "
+            module N {{
+                import 0x{}.M;
+
+                public quadruple(x: u64): u64 {{
+                    return M.double(M.double(move(x)));
+                }}
+            }}
+            ",
+            account.address()
+        ),
+        vec![module_m.clone()],
+    );
+    // `M` keeps everything it had, but now imports `N`.
+    let cyclic_module_m = compile(
+        &format!(
+            "
+            module M {{
+                import 0x{}.N;
+
+                resource T {{ value: u64 }}
+                struct S {{ flag: bool }}
+
+                public double(x: u64): u64 {{
+                    return move(x) * 2;
+                }}
+
+                public octuple(x: u64): u64 {{
+                    return N.quadruple(move(x)) * 2;
+                }}
+            }}
+            ",
+            account.address()
+        ),
+        vec![module_n.clone()],
+    );
+
+    for (i, module) in [module_m, module_n].iter().enumerate() {
+        let output = executor.execute_transaction(publish(module, sequence_number + i as u64));
+        assert!(transaction_status_eq(
+            &output.status(),
+            &TransactionStatus::Keep(VMStatus::new(StatusCode::EXECUTED)),
+        ));
+        executor.apply_write_set(output.write_set());
+    }
+
+    let output = executor.execute_transaction(publish(&cyclic_module_m, sequence_number + 2));
+    assert!(transaction_status_eq(
+        &output.status(),
+        &TransactionStatus::Keep(VMStatus::new(StatusCode::CYCLIC_MODULE_DEPENDENCY)),
+    ));
+}
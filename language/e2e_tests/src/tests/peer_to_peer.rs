@@ -9,11 +9,14 @@ use crate::{
 };
 use config::config::VMPublishingOption;
 use libra_types::{
-    account_config::AccountEvent,
+    account_config::{self, AccountEvent},
+    identifier::Identifier,
+    language_storage::{ModuleId, StructTag, TypeTag},
     transaction::{SignedTransaction, TransactionOutput, TransactionPayload, TransactionStatus},
     vm_error::{StatusCode, VMStatus},
 };
 use std::time::Instant;
+use stdlib::stdlib_abort_code;
 
 #[test]
 fn single_peer_to_peer_with_event() {
@@ -60,11 +63,21 @@ fn single_peer_to_peer_with_event() {
 
         let rec_ev_path = receiver.received_events_key().to_vec();
         let sent_ev_path = sender.sent_events_key().to_vec();
+        let event_type = |name: &str| {
+            TypeTag::Struct(StructTag {
+                address: account_config::core_code_address(),
+                module: account_config::account_module_name().to_owned(),
+                name: Identifier::new(name).unwrap(),
+                type_params: vec![],
+            })
+        };
         for event in txn_output.events() {
-            assert!(
-                rec_ev_path.as_slice() == event.key().as_bytes()
-                    || sent_ev_path.as_slice() == event.key().as_bytes()
-            );
+            if rec_ev_path.as_slice() == event.key().as_bytes() {
+                assert_eq!(event.type_tag(), &event_type("ReceivedPaymentEvent"));
+            } else {
+                assert_eq!(sent_ev_path.as_slice(), event.key().as_bytes());
+                assert_eq!(event.type_tag(), &event_type("SentPaymentEvent"));
+            }
         }
     });
 }
@@ -211,6 +224,24 @@ fn zero_amount_peer_to_peer() {
             &output.status(),
             &TransactionStatus::Keep(VMStatus::new(StatusCode::ABORTED).with_sub_status(7))
         ));
+
+        // The abort code resolves to the error declared by the module that aborted.
+        let location = output
+            .status()
+            .vm_status()
+            .abort_location
+            .clone()
+            .expect("an aborted transaction should have an abort location");
+        assert_eq!(
+            location.module,
+            ModuleId::new(
+                account_config::core_code_address(),
+                Identifier::new("LibraAccount").unwrap()
+            )
+        );
+        assert_eq!(location.function.as_str(), "deposit");
+        let abort_code = stdlib_abort_code(&location.module, 7).unwrap();
+        assert_eq!(abort_code.name.as_str(), "EZERO_AMOUNT_DEPOSIT");
     });
 }
 
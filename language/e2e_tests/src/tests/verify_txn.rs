@@ -81,6 +81,7 @@ fn verify_whitelist() {
         MINT.clone(),
         ROTATE_KEY.clone(),
         CREATE_ACCOUNT.clone(),
+        UPDATE_GAS_SCHEDULE.clone(),
     ]
     .into_iter()
     .map(|s| *HashValue::from_sha3_256(&s).as_ref())
@@ -368,6 +369,106 @@ pub fn test_arbitrary_script_execution() {
     );
 }
 
+#[test]
+pub fn test_bool_and_vector_script_args() {
+    // create a FakeExecutor with a genesis from file
+    let mut executor = FakeExecutor::from_genesis_with_options(VMPublishingOption::CustomScripts);
+
+    let sender = AccountData::new(1_000_000, 10);
+    executor.add_account_data(&sender);
+
+    let script = compile_script(
+        "
+        import 0x0.Vector;
+
+        main(flag: bool, amounts: Vector.T<u64>, payees: Vector.T<address>) {
+            assert(move(flag), 42);
+            assert(Vector.length<u64>(&amounts) == 2, 43);
+            assert(*Vector.borrow<u64>(&amounts, 0) == 10, 44);
+            assert(*Vector.borrow<u64>(&amounts, 1) == 20, 45);
+            assert(Vector.is_empty<address>(&payees), 46);
+            return;
+        }
+        ",
+    );
+    let args = vec![
+        TransactionArgument::Bool(true),
+        TransactionArgument::Vector(vec![
+            TransactionArgument::U64(10),
+            TransactionArgument::U64(20),
+        ]),
+        TransactionArgument::Vector(vec![]),
+    ];
+    let txn = sender
+        .account()
+        .create_signed_txn_with_args(script.clone(), args, 10, 100_000, 1);
+    assert_eq!(executor.verify_transaction(txn.clone()), None);
+    assert_eq!(
+        executor.execute_transaction(txn).status(),
+        &TransactionStatus::Keep(VMStatus::new(StatusCode::EXECUTED))
+    );
+
+    // The elements of a vector must match the element type of the formal.
+    let args = vec![
+        TransactionArgument::Bool(true),
+        TransactionArgument::Vector(vec![TransactionArgument::Bool(false)]),
+        TransactionArgument::Vector(vec![]),
+    ];
+    let txn = sender
+        .account()
+        .create_signed_txn_with_args(script, args, 10, 100_000, 1);
+    assert_eq!(
+        executor.verify_transaction(txn),
+        Some(
+            VMStatus::new(StatusCode::TYPE_MISMATCH)
+                .with_message("Actual Type Mismatch".to_string())
+        )
+    );
+
+    // Vectors of vectors can't be passed to main.
+    let script = compile_script(
+        "
+        import 0x0.Vector;
+
+        main(amounts: Vector.T<Vector.T<u64>>) {
+            return;
+        }
+        ",
+    );
+    let args = vec![TransactionArgument::Vector(vec![])];
+    let txn = sender
+        .account()
+        .create_signed_txn_with_args(script, args, 10, 100_000, 1);
+    assert_eq!(
+        executor
+            .verify_transaction(txn)
+            .map(|status| status.major_status),
+        Some(StatusCode::INVALID_MAIN_FUNCTION_SIGNATURE)
+    );
+
+    // Nor can integer types that no transaction argument holds.
+    let script = compile_script(
+        "
+        main(byte: u8) {
+            return;
+        }
+        ",
+    );
+    let txn = sender.account().create_signed_txn_with_args(
+        script,
+        vec![TransactionArgument::U64(1)],
+        10,
+        100_000,
+        1,
+    );
+    assert_eq!(
+        executor
+            .verify_transaction(txn)
+            .map(|status| status.major_status),
+        Some(StatusCode::INVALID_MAIN_FUNCTION_SIGNATURE)
+    );
+}
+
 #[test]
 pub fn test_no_publishing() {
     // create a FakeExecutor with a genesis from file
@@ -0,0 +1,195 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Decodes the payload of events emitted by Move programs.
+//!
+//! A `ContractEvent` carries the LCS bytes of the emitted value along with its `TypeTag`. The
+//! layout of a struct type is read from the module that declares it, which is fetched through a
+//! `ModuleFetcher`. Decoded values are returned as JSON:
+//! - `bool`, `u8` and `u64` are JSON booleans and numbers,
+//! - `u128` is a decimal string, as it doesn't fit in a JSON number,
+//! - `bytearray` and `address` are hex strings, `string` is a JSON string,
+//! - a struct is an object mapping each field name to its value,
+//! - a `Vector.T` is an array of its elements and a `Map.T` an array of `{"key", "value"}`
+//!   objects, ordered by the serialization of the keys.
+
+use failure::prelude::*;
+use libra_types::{
+    account_address::AccountAddress,
+    byte_array::ByteArray,
+    contract_event::ContractEvent,
+    identifier::Identifier,
+    language_storage::{ModuleId, StructTag, TypeTag},
+};
+use serde::Deserialize;
+use serde_json::{Map, Value};
+use std::{
+    cell::RefCell,
+    collections::{hash_map::Entry, HashMap},
+};
+use vm::{
+    access::ModuleAccess,
+    file_format::{CompiledModule, StructFieldInformation},
+};
+use vm_runtime::identifier::type_tag_for_token;
+use vm_runtime_types::native_structs::{def::NativeStructTag, dispatch::dispatch_native_struct};
+
+#[cfg(test)]
+mod unit_tests;
+
+/// Fetches published modules.
+pub trait ModuleFetcher {
+    fn get_module(&self, module_id: &ModuleId) -> Result<CompiledModule>;
+}
+
+impl<T: ModuleFetcher> ModuleFetcher for &T {
+    fn get_module(&self, module_id: &ModuleId) -> Result<CompiledModule> {
+        (*self).get_module(module_id)
+    }
+}
+
+/// The layout of a struct, with its type parameters instantiated.
+enum StructLayout {
+    Declared(Vec<(Identifier, TypeTag)>),
+    Vector(TypeTag),
+    Map(TypeTag, TypeTag),
+}
+
+/// Decodes Move values, caching the modules fetched to read struct layouts.
+pub struct EventDecoder<F> {
+    fetcher: F,
+    modules: RefCell<HashMap<ModuleId, CompiledModule>>,
+}
+
+impl<F: ModuleFetcher> EventDecoder<F> {
+    pub fn new(fetcher: F) -> Self {
+        Self {
+            fetcher,
+            modules: RefCell::new(HashMap::new()),
+        }
+    }
+
+    /// Decode the data of `event` according to its type.
+    pub fn decode_event(&self, event: &ContractEvent) -> Result<Value> {
+        self.decode(event.type_tag(), event.event_data())
+    }
+
+    /// Decode `bytes`, the serialization of a value of type `type_tag`.
+    pub fn decode(&self, type_tag: &TypeTag, bytes: &[u8]) -> Result<Value> {
+        let mut deserializer = lcs::Deserializer::new(bytes);
+        let value = self.decode_value(type_tag, &mut deserializer)?;
+        deserializer
+            .end()
+            .map_err(|err| format_err!("Invalid value of type {}: {}", type_tag, err))?;
+        Ok(value)
+    }
+
+    fn decode_value(
+        &self,
+        type_tag: &TypeTag,
+        deserializer: &mut lcs::Deserializer<'_>,
+    ) -> Result<Value> {
+        Ok(match type_tag {
+            TypeTag::Bool => Value::Bool(bool::deserialize(&mut *deserializer)?),
+            TypeTag::U8 => Value::from(u8::deserialize(&mut *deserializer)?),
+            TypeTag::U64 => Value::from(u64::deserialize(&mut *deserializer)?),
+            TypeTag::U128 => Value::String(u128::deserialize(&mut *deserializer)?.to_string()),
+            TypeTag::ByteArray => Value::String(hex::encode(
+                ByteArray::deserialize(&mut *deserializer)?.as_bytes(),
+            )),
+            TypeTag::Address => Value::String(format!(
+                "{:x}",
+                AccountAddress::deserialize(&mut *deserializer)?
+            )),
+            TypeTag::String => Value::String(String::deserialize(&mut *deserializer)?),
+            TypeTag::Struct(struct_tag) => match self.struct_layout(struct_tag)? {
+                StructLayout::Declared(fields) => {
+                    let mut object = Map::new();
+                    for (name, field_type) in fields {
+                        let value = self.decode_value(&field_type, deserializer)?;
+                        object.insert(name.into_string(), value);
+                    }
+                    Value::Object(object)
+                }
+                StructLayout::Vector(element_type) => {
+                    let len = u32::deserialize(&mut *deserializer)?;
+                    let mut elements = vec![];
+                    for _ in 0..len {
+                        elements.push(self.decode_value(&element_type, deserializer)?);
+                    }
+                    Value::Array(elements)
+                }
+                StructLayout::Map(key_type, value_type) => {
+                    let len = u32::deserialize(&mut *deserializer)?;
+                    let mut entries = vec![];
+                    for _ in 0..len {
+                        let key = self.decode_value(&key_type, deserializer)?;
+                        let value = self.decode_value(&value_type, deserializer)?;
+                        let mut entry = Map::new();
+                        entry.insert("key".to_string(), key);
+                        entry.insert("value".to_string(), value);
+                        entries.push(Value::Object(entry));
+                    }
+                    Value::Array(entries)
+                }
+            },
+        })
+    }
+
+    fn struct_layout(&self, struct_tag: &StructTag) -> Result<StructLayout> {
+        let module_id = struct_tag.module_id();
+        if let Some(native_struct) = dispatch_native_struct(&module_id, &struct_tag.name) {
+            return Ok(match native_struct.struct_type.tag {
+                NativeStructTag::Vector => StructLayout::Vector(type_param(struct_tag, 0)?),
+                NativeStructTag::Map => {
+                    StructLayout::Map(type_param(struct_tag, 0)?, type_param(struct_tag, 1)?)
+                }
+            });
+        }
+
+        let mut modules = self.modules.borrow_mut();
+        let module = &*match modules.entry(module_id) {
+            Entry::Occupied(entry) => entry.into_mut(),
+            Entry::Vacant(entry) => {
+                let module = self.fetcher.get_module(entry.key())?;
+                entry.insert(module)
+            }
+        };
+        let struct_def = module
+            .struct_defs()
+            .iter()
+            .find(|struct_def| {
+                let handle = module.struct_handle_at(struct_def.struct_handle);
+                module.identifier_at(handle.name) == struct_tag.name.as_ident_str()
+            })
+            .ok_or_else(|| format_err!("Struct {} is not defined", struct_tag))?;
+        match &struct_def.field_information {
+            StructFieldInformation::Native => bail!("Unknown native struct {}", struct_tag),
+            StructFieldInformation::Declared {
+                field_count,
+                fields,
+            } => module
+                .field_def_range(*field_count, *fields)
+                .iter()
+                .map(|field| {
+                    let name = module.identifier_at(field.name).to_owned();
+                    let token = &module.type_signature_at(field.signature).0;
+                    let field_type = type_tag_for_token(module, token, &struct_tag.type_params)
+                        .ok_or_else(|| {
+                            format_err!("Invalid type for field {} of {}", name, struct_tag)
+                        })?;
+                    Ok((name, field_type))
+                })
+                .collect::<Result<_>>()
+                .map(StructLayout::Declared),
+        }
+    }
+}
+
+fn type_param(struct_tag: &StructTag, idx: usize) -> Result<TypeTag> {
+    struct_tag
+        .type_params
+        .get(idx)
+        .cloned()
+        .ok_or_else(|| format_err!("Missing type parameter {} of {}", idx, struct_tag))
+}
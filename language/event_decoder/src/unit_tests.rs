@@ -0,0 +1,194 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{EventDecoder, ModuleFetcher};
+use compiler::Compiler;
+use failure::prelude::*;
+use libra_types::{
+    account_address::AccountAddress,
+    account_config,
+    byte_array::ByteArray,
+    contract_event::ContractEvent,
+    event::EventKey,
+    identifier::Identifier,
+    language_storage::{ModuleId, StructTag, TypeTag},
+};
+use serde_json::json;
+use std::collections::HashMap;
+use stdlib::stdlib_modules;
+use vm::{access::ModuleAccess, file_format::CompiledModule};
+
+const MODULE: &str = "
+module Events {
+    import 0x0.Map;
+    import 0x0.Vector;
+
+    struct Inner<T> { value: T, flag: bool }
+    struct Event<T> {
+        amount: u64,
+        big: u128,
+        payee: address,
+        metadata: bytearray,
+        inner: Self.Inner<T>,
+        values: Vector.T<T>,
+        balances: Map.T<address, u64>,
+    }
+}
+";
+
+struct Modules(HashMap<ModuleId, CompiledModule>);
+
+impl ModuleFetcher for Modules {
+    fn get_module(&self, module_id: &ModuleId) -> Result<CompiledModule> {
+        self.0
+            .get(module_id)
+            .cloned()
+            .ok_or_else(|| format_err!("Module {:?} not found", module_id))
+    }
+}
+
+fn decoder() -> (EventDecoder<Modules>, ModuleId) {
+    let module = Compiler::default()
+        .into_compiled_module(MODULE)
+        .expect("module should compile");
+    let module_id = module.self_id();
+    let mut modules: HashMap<_, _> = stdlib_modules()
+        .iter()
+        .map(|module| (module.self_id(), module.as_inner().clone()))
+        .collect();
+    modules.insert(module_id.clone(), module);
+    (EventDecoder::new(Modules(modules)), module_id)
+}
+
+fn struct_type(module_id: &ModuleId, name: &str, type_params: Vec<TypeTag>) -> TypeTag {
+    TypeTag::Struct(StructTag {
+        address: *module_id.address(),
+        module: module_id.name().to_owned(),
+        name: Identifier::new(name).unwrap(),
+        type_params,
+    })
+}
+
+fn vector_type(element_type: TypeTag) -> TypeTag {
+    let vector = ModuleId::new(
+        account_config::core_code_address(),
+        Identifier::new("Vector").unwrap(),
+    );
+    struct_type(&vector, "T", vec![element_type])
+}
+
+#[test]
+fn decode_primitives() {
+    let (decoder, _) = decoder();
+    let address = AccountAddress::random();
+    let cases = vec![
+        (TypeTag::Bool, lcs::to_bytes(&true).unwrap(), json!(true)),
+        (TypeTag::U8, lcs::to_bytes(&7u8).unwrap(), json!(7)),
+        (TypeTag::U64, lcs::to_bytes(&42u64).unwrap(), json!(42)),
+        (
+            TypeTag::U128,
+            lcs::to_bytes(&u128::max_value()).unwrap(),
+            json!(u128::max_value().to_string()),
+        ),
+        (
+            TypeTag::ByteArray,
+            lcs::to_bytes(&ByteArray::new(vec![0xca, 0xfe])).unwrap(),
+            json!("cafe"),
+        ),
+        (
+            TypeTag::Address,
+            lcs::to_bytes(&address).unwrap(),
+            json!(hex::encode(address)),
+        ),
+        (
+            TypeTag::String,
+            lcs::to_bytes("libra").unwrap(),
+            json!("libra"),
+        ),
+    ];
+    for (type_tag, bytes, expected) in cases {
+        assert_eq!(decoder.decode(&type_tag, &bytes).unwrap(), expected);
+    }
+}
+
+#[test]
+fn decode_struct() {
+    let (decoder, module_id) = decoder();
+    let payee = AccountAddress::random();
+    let owner = AccountAddress::random();
+    // Structs serialize as the concatenation of their fields.
+    let bytes = lcs::to_bytes(&(
+        10u64,
+        20u128,
+        payee,
+        ByteArray::new(vec![1, 2]),
+        (true, false),
+        vec![false, true],
+        vec![(owner, 5u64)],
+    ))
+    .unwrap();
+    let type_tag = struct_type(&module_id, "Event", vec![TypeTag::Bool]);
+    assert_eq!(
+        decoder.decode(&type_tag, &bytes).unwrap(),
+        json!({
+            "amount": 10,
+            "big": "20",
+            "payee": hex::encode(payee),
+            "metadata": "0102",
+            "inner": { "value": true, "flag": false },
+            "values": [false, true],
+            "balances": [{ "key": hex::encode(owner), "value": 5 }],
+        })
+    );
+}
+
+#[test]
+fn decode_nested_type_params() {
+    let (decoder, module_id) = decoder();
+    let inner_type = struct_type(&module_id, "Inner", vec![TypeTag::U64]);
+    let type_tag = vector_type(inner_type);
+    let bytes = lcs::to_bytes(&vec![(1u64, true), (2u64, false)]).unwrap();
+    assert_eq!(
+        decoder.decode(&type_tag, &bytes).unwrap(),
+        json!([{ "value": 1, "flag": true }, { "value": 2, "flag": false }])
+    );
+}
+
+#[test]
+fn decode_non_account_event() {
+    let (decoder, module_id) = decoder();
+    // An event emitted through a handle published by a module other than LibraAccount.
+    let inner_type = struct_type(&module_id, "Inner", vec![TypeTag::Address]);
+    let owner = AccountAddress::random();
+    let event = ContractEvent::new(
+        EventKey::new_from_address(&owner, 0),
+        3,
+        inner_type,
+        lcs::to_bytes(&(owner, true)).unwrap(),
+    );
+    assert_eq!(
+        decoder.decode_event(&event).unwrap(),
+        json!({ "value": hex::encode(owner), "flag": true })
+    );
+}
+
+#[test]
+fn decode_invalid_data() {
+    let (decoder, module_id) = decoder();
+    // Trailing bytes.
+    assert!(decoder
+        .decode(&TypeTag::U64, &lcs::to_bytes(&(1u64, 2u8)).unwrap())
+        .is_err());
+    // Truncated input.
+    assert!(decoder.decode(&TypeTag::U64, &[1, 2, 3]).is_err());
+    // Invalid bool.
+    assert!(decoder.decode(&TypeTag::Bool, &[2]).is_err());
+    // Missing type parameter.
+    let type_tag = struct_type(&module_id, "Inner", vec![]);
+    assert!(decoder
+        .decode(&type_tag, &lcs::to_bytes(&(1u64, true)).unwrap())
+        .is_err());
+    // Unknown struct.
+    let type_tag = struct_type(&module_id, "Missing", vec![]);
+    assert!(decoder.decode(&type_tag, &[]).is_err());
+}
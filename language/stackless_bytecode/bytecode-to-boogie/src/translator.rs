@@ -411,16 +411,41 @@ impl<'a> ModuleTranslator<'a> {
             }
             LdTrue(idx) => vec![format!("call t{} := LdTrue();", idx)],
             LdFalse(idx) => vec![format!("call t{} := LdFalse();", idx)],
+            LdU8(idx, num) => vec![format!("call t{} := LdConst({});", idx, num)],
             LdConst(idx, num) => vec![format!("call t{} := LdConst({});", idx, num)],
+            LdU128(idx, num) => vec![format!("call t{} := LdConst({});", idx, num)],
+            CastU8(dest, operand) => vec![format!("call t{} := CastU8(t{});", dest, operand)],
+            CastU64(dest, operand) => vec![format!("call t{} := CastU64(t{});", dest, operand)],
+            CastU128(dest, operand) => {
+                vec![format!("call t{} := CastU128(t{});", dest, operand)]
+            }
             LdAddr(idx, addr_idx) => {
                 let addr = self.module.address_pool()[(*addr_idx).into_index()];
                 let addr_int = BigInt::from_str_radix(&addr.to_string(), 16).unwrap();
                 vec![format!("call t{} := LdAddr({});", idx, addr_int)]
             }
             Not(dest, operand) => vec![format!("call t{} := Not(t{});", dest, operand)],
-            Add(dest, op1, op2) => vec![format!("call t{} := Add(t{}, t{});", dest, op1, op2)],
+            Add(dest, op1, op2) => {
+                let operand_type = self.get_local_type(*op1, func_idx);
+                vec![format!(
+                    "call t{} := Add{}(t{}, t{});",
+                    dest,
+                    integer_suffix(&operand_type),
+                    op1,
+                    op2
+                )]
+            }
             Sub(dest, op1, op2) => vec![format!("call t{} := Sub(t{}, t{});", dest, op1, op2)],
-            Mul(dest, op1, op2) => vec![format!("call t{} := Mul(t{}, t{});", dest, op1, op2)],
+            Mul(dest, op1, op2) => {
+                let operand_type = self.get_local_type(*op1, func_idx);
+                vec![format!(
+                    "call t{} := Mul{}(t{}, t{});",
+                    dest,
+                    integer_suffix(&operand_type),
+                    op1,
+                    op2
+                )]
+            }
             Div(dest, op1, op2) => vec![format!("call t{} := Div(t{}, t{});", dest, op1, op2)],
             Mod(dest, op1, op2) => vec![format!("call t{} := Mod(t{}, t{});", dest, op1, op2)],
             Lt(dest, op1, op2) => vec![format!("call t{} := Lt(t{}, t{});", dest, op1, op2)],
@@ -842,7 +867,7 @@ pub fn struct_name_from_handle_index(module: &VerifiedModule, idx: StructHandleI
 pub fn format_type(module: &VerifiedModule, sig: &SignatureToken) -> String {
     match sig {
         SignatureToken::Bool => "bool".into(),
-        SignatureToken::U64 => "int".into(),
+        SignatureToken::U8 | SignatureToken::U64 | SignatureToken::U128 => "int".into(),
         SignatureToken::String => "string".into(),
         SignatureToken::ByteArray => "bytearray".into(),
         SignatureToken::Address => "address".into(),
@@ -854,10 +879,20 @@ pub fn format_type(module: &VerifiedModule, sig: &SignatureToken) -> String {
     }
 }
 
+/// The suffix of the Boogie procedures of the arithmetic operations that can overflow, which
+/// depends on the width of the integers.
+fn integer_suffix(sig: &SignatureToken) -> &'static str {
+    match sig {
+        SignatureToken::U8 => "U8",
+        SignatureToken::U128 => "U128",
+        _ => "",
+    }
+}
+
 pub fn format_value_cons(sig: &SignatureToken) -> String {
     match sig {
         SignatureToken::Bool => "Boolean",
-        SignatureToken::U64 => "Integer",
+        SignatureToken::U8 | SignatureToken::U64 | SignatureToken::U128 => "Integer",
         SignatureToken::String => "Str",
         SignatureToken::ByteArray => "ByteArray",
         SignatureToken::Address => "Address",
@@ -4,12 +4,12 @@
 pub mod stdlib;
 pub mod transaction_scripts;
 
-use bytecode_source_map::source_map::{ModuleSourceMap, SourceMap};
+use bytecode_source_map::source_map::{AbortCodeDescription, ModuleSourceMap, SourceMap};
 use bytecode_verifier::{verify_module_dependencies, VerifiedModule};
 use ir_to_bytecode::compiler::compile_module;
 use ir_to_bytecode::parser::ast::Loc;
 use lazy_static::lazy_static;
-use libra_types::{account_address::AccountAddress, account_config};
+use libra_types::{account_address::AccountAddress, account_config, language_storage::ModuleId};
 
 lazy_static! {
     static ref ANNOTATED_STDLIB: (Vec<VerifiedModule>, SourceMap<Loc>) =
@@ -32,6 +32,18 @@ pub fn stdlib_source_map() -> &'static [ModuleSourceMap<Loc>] {
     &*ANNOTATED_STDLIB.1
 }
 
+/// Returns the name and description the standard library module `module` declared for the abort
+/// code `code`, if any.
+pub fn stdlib_abort_code(module: &ModuleId, code: u64) -> Option<&'static AbortCodeDescription> {
+    stdlib_source_map()
+        .iter()
+        .find(|source_map| {
+            source_map.module_name.0 == *module.address()
+                && source_map.module_name.1.as_ident_str() == module.name()
+        })
+        .and_then(|source_map| source_map.get_abort_code(code))
+}
+
 /// Builds and returns a copy of the standard library with this address as the self address.
 ///
 /// A copy of the stdlib built with the [default address](account_config::core_code_address) is
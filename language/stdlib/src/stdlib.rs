@@ -22,6 +22,8 @@ lazy_static! {
         make_module_definition!("../modules/hash.mvir");
     static ref SIGNATURE_MODULE: ModuleDefinition =
         make_module_definition!("../modules/signature.mvir");
+    static ref MERKLE_PROOF_MODULE: ModuleDefinition =
+        make_module_definition!("../modules/merkle_proof.mvir");
     static ref VALIDATOR_CONFIG_MODULE: ModuleDefinition =
         make_module_definition!("../modules/validator_config.mvir");
     static ref VALIDATOR_SET_MODULE: ModuleDefinition =
@@ -32,11 +34,14 @@ lazy_static! {
         make_module_definition!("../modules/u64_util.mvir");
     static ref VECTOR_MODULE: ModuleDefinition =
         make_module_definition!("../modules/vector.mvir");
+    static ref MAP_MODULE: ModuleDefinition = make_module_definition!("../modules/map.mvir");
     static ref BYTEARRAY_UTIL_MODULE: ModuleDefinition =
         make_module_definition!("../modules/bytearray_util.mvir");
     static ref TRANSACTION_FEE_DISTRIBUTION_MODULE: ModuleDefinition =
         make_module_definition!("../modules/transaction_fee_distribution.mvir");
     static ref EVENT_MODULE: ModuleDefinition = make_module_definition!("../modules/event.mvir");
+    static ref GAS_SCHEDULE_MODULE: ModuleDefinition =
+        make_module_definition!("../modules/gas_schedule.mvir");
     static ref MODULE_DEFS: Vec<&'static ModuleDefinition> = {
         // Note: a module can depend on earlier modules in the list, but not vice versa. Don't try
         // to rearrange without considering this!
@@ -47,9 +52,12 @@ lazy_static! {
             &*COIN_MODULE,
             &*NATIVE_HASH_MODULE,
             &*SIGNATURE_MODULE,
+            &*MERKLE_PROOF_MODULE,
             &*U64_UTIL_MODULE,
             &*VECTOR_MODULE,
+            &*MAP_MODULE,
             &*EVENT_MODULE, // depends on AddressUtil, BytearrayUtil, Hash, U64Util
+            &*GAS_SCHEDULE_MODULE,
             &*ACCOUNT_MODULE, // depends on LibraCoin, Event, AddressUtil, BytearrayUtil, U64Util
             &*VALIDATOR_CONFIG_MODULE,
             &*VALIDATOR_SET_MODULE, // depends on LibraAccount, ValidatorConfig
@@ -74,6 +82,10 @@ pub fn signature_module() -> ModuleDefinition {
     SIGNATURE_MODULE.clone()
 }
 
+pub fn merkle_proof_module() -> ModuleDefinition {
+    MERKLE_PROOF_MODULE.clone()
+}
+
 pub fn validator_config_module() -> ModuleDefinition {
     VALIDATOR_CONFIG_MODULE.clone()
 }
@@ -98,6 +110,10 @@ pub fn event_module() -> ModuleDefinition {
     EVENT_MODULE.clone()
 }
 
+pub fn gas_schedule_module() -> ModuleDefinition {
+    GAS_SCHEDULE_MODULE.clone()
+}
+
 pub fn module_defs() -> &'static [&'static ModuleDefinition] {
     &*MODULE_DEFS
 }
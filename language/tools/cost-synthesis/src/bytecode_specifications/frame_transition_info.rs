@@ -42,6 +42,6 @@ pub(crate) fn frame_transitions<'alloc, 'txn, P>(
     if let Some(function_idx) = module_info.1 {
         let frame = FunctionRef::new(module, function_idx);
         // NB: push_call will pop |function_args| number of values off of the value stack.
-        stk.push_call(frame).unwrap();
+        stk.push_call(frame, vec![]).unwrap();
     }
 }
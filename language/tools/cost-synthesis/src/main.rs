@@ -19,7 +19,8 @@ use libra_types::vm_error::StatusCode;
 use std::{
     collections::{HashMap, VecDeque},
     convert::TryFrom,
-    path::Path,
+    fs,
+    path::{Path, PathBuf},
     time::Instant,
     u64,
 };
@@ -30,7 +31,9 @@ use vm::{
         FunctionDefinitionIndex, FunctionHandleIndex, StructDefinitionIndex, UserStringIndex,
         NO_TYPE_ACTUALS,
     },
-    gas_schedule::{AbstractMemorySize, GasAlgebra, GasCarrier},
+    gas_schedule::{
+        AbstractMemorySize, GasAlgebra, GasCarrier, GasSchedule, GasUnits, DEFAULT_GAS_SCHEDULE,
+    },
     transaction_metadata::TransactionMetadata,
 };
 use vm_cache_map::Arena;
@@ -57,6 +60,12 @@ struct Opt {
 
     #[structopt(short = "o", long = "output")]
     output: bool,
+
+    /// Write a gas schedule using the averaged instruction costs to this path. The schedule is
+    /// serialized in the format stored on chain, and can be installed with the
+    /// update-gas-schedule transaction script.
+    #[structopt(long = "gas-schedule", parse(from_os_str))]
+    gas_schedule: Option<PathBuf>,
 }
 
 fn output_to_csv(path: &Path, data: HashMap<String, Vec<u64>>, output: bool) {
@@ -97,6 +106,22 @@ fn size_normalize_cost(instr: &Bytecode, cost: u64, size: AbstractMemorySize<Gas
     }
 }
 
+/// Write a gas schedule to `path` in which the computational cost of every instruction in
+/// `costs` is its average measured cost. Everything else is taken from the default schedule.
+fn output_gas_schedule(path: &Path, costs: &[(Bytecode, Vec<u64>)]) {
+    let mut gas_schedule: GasSchedule = DEFAULT_GAS_SCHEDULE.clone();
+    for (instr, instr_costs) in costs {
+        if instr_costs.is_empty() {
+            continue;
+        }
+        let average = instr_costs.iter().sum::<u64>() / instr_costs.len() as u64;
+        gas_schedule
+            .cost_table
+            .set_comp_gas(instr, GasUnits::new(average));
+    }
+    fs::write(path, gas_schedule.to_bytes().unwrap()).unwrap();
+}
+
 fn stack_instructions(options: &Opt) {
     use Bytecode::*;
     let stack_opcodes: Vec<Bytecode> = vec![
@@ -140,7 +165,12 @@ fn stack_instructions(options: &Opt) {
         Abort,
         LdFalse,
         LdTrue,
+        LdU8(0),
         LdConst(0),
+        LdU128(0),
+        CastU8,
+        CastU64,
+        CastU128,
         LdStr(UserStringIndex::new(0)),
         LdByteArray(ByteArrayPoolIndex::new(0)),
         LdAddr(AddressPoolIndex::new(0)),
@@ -159,7 +189,7 @@ fn stack_instructions(options: &Opt) {
     let mod_gen: ModuleGenerator = ModuleGenerator::new(options.num_iters as u16, 3);
     let mut account = Account::new();
     with_loaded_vm! (mod_gen, account => vm, loaded_module, module_cache);
-    let costs: HashMap<String, Vec<u64>> = stack_opcodes
+    let costs: Vec<(Bytecode, Vec<u64>)> = stack_opcodes
         .into_iter()
         .map(|instruction| {
             println!("Running: {:?}", instruction);
@@ -203,13 +233,19 @@ fn stack_instructions(options: &Opt) {
                     size_normalize_cost(&instruction, u64::try_from(time).unwrap(), size)
                 })
                 .collect();
-            (format!("{:?}", instruction), instr_costs)
+            (instruction, instr_costs)
         })
         .collect();
 
+    if let Some(path) = &options.gas_schedule {
+        output_gas_schedule(path, &costs);
+    }
     output_to_csv(
         Path::new("data/bytecode_instruction_costs.csv"),
-        costs,
+        costs
+            .into_iter()
+            .map(|(instruction, instr_costs)| (format!("{:?}", instruction), instr_costs))
+            .collect(),
         options.output,
     );
 }
@@ -249,6 +285,12 @@ fn natives(options: &Opt) {
         cost_table,
         options.num_iters
     );
+    bench_native!(
+        "native_keccak_256".to_string(),
+        hash::native_keccak_256,
+        cost_table,
+        options.num_iters
+    );
     output_to_csv(
         Path::new("data/native_function_costs.csv"),
         cost_table,
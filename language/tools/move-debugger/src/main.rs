@@ -0,0 +1,305 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! A step-through debugger for Move IR scripts.
+//!
+//! The script is executed as a transaction against a `FakeExecutor` started from the genesis file,
+//! and every instruction executed is recorded. The trace is then replayed with breakpoints, and
+//! the instructions of the script are mapped back to the lines of the IR source.
+
+use bytecode_source_map::source_map::ModuleSourceMap;
+use compiler::Compiler;
+use config::config::VMPublishingOption;
+use failure::prelude::*;
+use ir_to_bytecode_syntax::ast::Loc;
+use language_e2e_tests::{account::AccountData, executor::FakeExecutor};
+use libra_types::{
+    language_storage::ModuleId,
+    transaction::{parse_as_transaction_argument, TransactionArgument},
+};
+use std::{
+    fs,
+    io::{self, BufRead, Write},
+    path::PathBuf,
+    str::FromStr,
+};
+use structopt::StructOpt;
+use vm::file_format::CodeOffset;
+use vm_runtime::tracer::TraceEvent;
+
+#[derive(Debug, StructOpt)]
+#[structopt(
+    name = "Move Debugger",
+    about = "Step-through debugger for Move IR scripts"
+)]
+struct Args {
+    /// The path to the Move IR script.
+    #[structopt(parse(from_os_str))]
+    script_path: PathBuf,
+
+    /// Stops before the instruction at `function:pc`, or before the first instruction of a line
+    /// of the script. Can be given multiple times. If no breakpoint is given, execution stops
+    /// before the first instruction of the script.
+    #[structopt(short = "b", long = "break", number_of_values = 1)]
+    breakpoints: Vec<Breakpoint>,
+
+    /// The arguments passed to the script.
+    #[structopt(
+        short = "a",
+        long = "args",
+        number_of_values = 1,
+        parse(try_from_str = parse_as_transaction_argument)
+    )]
+    args: Vec<TransactionArgument>,
+
+    /// The maximum amount of gas the transaction can use.
+    #[structopt(long = "max-gas", default_value = "1000000")]
+    max_gas_amount: u64,
+
+    /// Prints every instruction executed instead of starting an interactive session.
+    #[structopt(short = "t", long = "trace")]
+    print_trace: bool,
+}
+
+#[derive(Clone, Debug)]
+enum Breakpoint {
+    Line(usize),
+    Instruction { function: String, pc: CodeOffset },
+}
+
+impl FromStr for Breakpoint {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.rfind(':') {
+            Some(idx) => Ok(Breakpoint::Instruction {
+                function: s[..idx].to_string(),
+                pc: s[idx + 1..].parse()?,
+            }),
+            None => Ok(Breakpoint::Line(s.parse()?)),
+        }
+    }
+}
+
+/// Maps the instructions of the compiled script back to the lines of its source.
+struct SourceLocator {
+    source: String,
+    script_id: ModuleId,
+    source_map: ModuleSourceMap<Loc>,
+}
+
+impl SourceLocator {
+    /// Returns the line of the script, starting at 1, the instruction of `event` was compiled from.
+    fn line(&self, event: &TraceEvent) -> Option<usize> {
+        if event.module != self.script_id {
+            return None;
+        }
+        let loc = self
+            .source_map
+            .get_code_location(event.function_index, event.pc)
+            .ok()?;
+        let offset = (loc.start().0 as usize).min(self.source.len());
+        Some(self.source[..offset].matches('\n').count() + 1)
+    }
+
+    fn source_line(&self, line: usize) -> &str {
+        self.source.lines().nth(line - 1).unwrap_or("").trim()
+    }
+}
+
+struct Debugger {
+    locator: SourceLocator,
+    breakpoints: Vec<Breakpoint>,
+    stepping: bool,
+}
+
+enum Command {
+    Step,
+    Continue,
+    Print,
+    Break(Breakpoint),
+    Quit,
+    Help,
+}
+
+impl Debugger {
+    fn hits_breakpoint(&self, event: &TraceEvent, previous: Option<&TraceEvent>) -> bool {
+        let line = self.locator.line(event);
+        self.breakpoints.iter().any(|breakpoint| match breakpoint {
+            Breakpoint::Instruction { function, pc } => {
+                event.function.as_str() == function && event.pc == *pc
+            }
+            // Only stop at the first instruction of the line.
+            Breakpoint::Line(target) => {
+                line == Some(*target)
+                    && previous.map_or(true, |previous| {
+                        previous.function != event.function || self.locator.line(previous) != line
+                    })
+            }
+        })
+    }
+
+    fn describe(&self, event: &TraceEvent) -> String {
+        let mut description = format!(
+            "[depth {}] {}::{} pc {}: {:?} (gas remaining: {})",
+            event.call_depth,
+            event.module.name(),
+            event.function,
+            event.pc,
+            event.instruction,
+            event.gas_remaining,
+        );
+        if let Some(line) = self.locator.line(event) {
+            description.push_str(&format!(
+                "\n    {}: {}",
+                line,
+                self.locator.source_line(line)
+            ));
+        }
+        description
+    }
+
+    fn print_state(event: &TraceEvent) {
+        println!("stack (height {}, top first):", event.stack_height);
+        for value in &event.stack {
+            println!("    {}", value);
+        }
+        println!("locals: {}", event.locals);
+    }
+
+    fn read_command() -> Result<Command> {
+        print!("(move-debugger) ");
+        io::stdout().flush()?;
+        let mut input = String::new();
+        if io::stdin().lock().read_line(&mut input)? == 0 {
+            return Ok(Command::Quit);
+        }
+        let mut words = input.split_whitespace();
+        Ok(match words.next() {
+            None | Some("s") | Some("step") => Command::Step,
+            Some("c") | Some("continue") => Command::Continue,
+            Some("p") | Some("print") => Command::Print,
+            Some("q") | Some("quit") => Command::Quit,
+            Some("b") | Some("break") => match words.next().map(str::parse) {
+                Some(Ok(breakpoint)) => Command::Break(breakpoint),
+                _ => Command::Help,
+            },
+            Some(_) => Command::Help,
+        })
+    }
+
+    /// Replays `trace`, stopping at breakpoints. Returns false if the user quit.
+    fn run(&mut self, trace: &[TraceEvent]) -> Result<bool> {
+        let mut previous: Option<&TraceEvent> = None;
+        let mut in_script = false;
+        for event in trace {
+            // Without breakpoints, stop before the first instruction of the script.
+            let entering_script = !in_script && event.module == self.locator.script_id;
+            in_script |= entering_script;
+            let stop = self.stepping
+                || self.hits_breakpoint(event, previous)
+                || (entering_script && self.breakpoints.is_empty());
+            previous = Some(event);
+            if !stop {
+                continue;
+            }
+            println!("{}", self.describe(event));
+            loop {
+                match Self::read_command()? {
+                    Command::Step => {
+                        self.stepping = true;
+                        break;
+                    }
+                    Command::Continue => {
+                        self.stepping = false;
+                        break;
+                    }
+                    Command::Print => Self::print_state(event),
+                    Command::Break(breakpoint) => self.breakpoints.push(breakpoint),
+                    Command::Quit => return Ok(false),
+                    Command::Help => println!(
+                        "commands: step, continue, print, break <line | function:pc>, quit"
+                    ),
+                }
+            }
+        }
+        Ok(true)
+    }
+}
+
+fn main() -> Result<()> {
+    let args = Args::from_args();
+    let source = fs::read_to_string(&args.script_path)?;
+
+    let mut executor = FakeExecutor::from_genesis_with_options(VMPublishingOption::CustomScripts);
+    let sender = AccountData::new(args.max_gas_amount.saturating_mul(2), 0);
+    executor.add_account_data(&sender);
+
+    let compiler = Compiler {
+        address: *sender.address(),
+        ..Compiler::default()
+    };
+    let (compiled_program, mut source_maps) =
+        compiler.into_compiled_program_and_source_maps(&source)?;
+    if !compiled_program.modules.is_empty() {
+        bail!("Only scripts can be debugged; publish the modules in the genesis first");
+    }
+    let mut script = vec![];
+    compiled_program.script.serialize(&mut script)?;
+    let locator = SourceLocator {
+        script_id: compiled_program.script.into_module().self_id(),
+        source_map: source_maps
+            .pop()
+            .ok_or_else(|| format_err!("Missing source map for the script"))?,
+        source,
+    };
+
+    let txn = sender.account().create_signed_txn_with_args(
+        script,
+        args.args,
+        sender.sequence_number(),
+        args.max_gas_amount,
+        1,
+    );
+    let (output, trace) = executor.trace_transaction(txn);
+
+    let mut debugger = Debugger {
+        locator,
+        breakpoints: args.breakpoints,
+        stepping: false,
+    };
+    if args.print_trace {
+        for event in &trace {
+            println!("{}", debugger.describe(event));
+        }
+    } else if !debugger.run(&trace)? {
+        return Ok(());
+    }
+
+    println!("Executed {} instructions", trace.len());
+    println!("status: {:?}", output.status());
+    println!("gas used: {}", output.gas_used());
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_breakpoints() {
+        match "12".parse::<Breakpoint>().unwrap() {
+            Breakpoint::Line(line) => assert_eq!(line, 12),
+            breakpoint => panic!("Unexpected breakpoint: {:?}", breakpoint),
+        }
+        match "main:3".parse::<Breakpoint>().unwrap() {
+            Breakpoint::Instruction { function, pc } => {
+                assert_eq!(function, "main");
+                assert_eq!(pc, 3);
+            }
+            breakpoint => panic!("Unexpected breakpoint: {:?}", breakpoint),
+        }
+        "main:x".parse::<Breakpoint>().unwrap_err();
+        "".parse::<Breakpoint>().unwrap_err();
+    }
+}
@@ -488,7 +488,9 @@ impl BytecodeGenerator {
                     let next_instruction = match abstract_value.token {
                         SignatureToken::String => Bytecode::LdStr(UserStringIndex::new(0)),
                         SignatureToken::Address => Bytecode::LdAddr(AddressPoolIndex::new(0)),
+                        SignatureToken::U8 => Bytecode::LdU8(0),
                         SignatureToken::U64 => Bytecode::LdConst(0),
+                        SignatureToken::U128 => Bytecode::LdU128(0),
                         SignatureToken::Bool => Bytecode::LdFalse,
                         SignatureToken::ByteArray => {
                             Bytecode::LdByteArray(ByteArrayPoolIndex::new(0))
@@ -565,7 +567,9 @@ impl BytecodeGenerator {
                     let next_instruction = match token_type {
                         SignatureToken::String => Bytecode::LdStr(UserStringIndex::new(0)),
                         SignatureToken::Address => Bytecode::LdAddr(AddressPoolIndex::new(0)),
+                        SignatureToken::U8 => Bytecode::LdU8(0),
                         SignatureToken::U64 => Bytecode::LdConst(0),
+                        SignatureToken::U128 => Bytecode::LdU128(0),
                         SignatureToken::Bool => Bytecode::LdFalse,
                         SignatureToken::ByteArray => {
                             Bytecode::LdByteArray(ByteArrayPoolIndex::new(0))
@@ -79,7 +79,9 @@ fn run_vm(module: VerifiedModule) -> Result<(), String> {
     for arg_type in entry_func.signature().arg_types.clone() {
         function_args.push(match arg_type {
             SignatureToken::Address => Value::address(AccountAddress::new([0; 32])),
+            SignatureToken::U8 => Value::u8(0),
             SignatureToken::U64 => Value::u64(0),
+            SignatureToken::U128 => Value::u128(0),
             SignatureToken::Bool => Value::bool(true),
             SignatureToken::String => Value::string("".into()),
             SignatureToken::ByteArray => Value::byte_array(ByteArray::new(vec![])),
@@ -348,6 +348,7 @@ fn build_common_tables(
             TableType::FUNCTION_DEFS
             | TableType::FIELD_DEFS
             | TableType::STRUCT_DEFS
+            | TableType::ABORT_CODES
             | TableType::MAIN => continue,
         }
     }
@@ -371,6 +372,9 @@ fn build_module_tables(
             TableType::FUNCTION_DEFS => {
                 load_function_defs(binary, table, &mut module.function_defs)?;
             }
+            TableType::ABORT_CODES => {
+                load_abort_codes(binary, table, &mut module.abort_codes)?;
+            }
             TableType::MODULE_HANDLES
             | TableType::STRUCT_HANDLES
             | TableType::FUNCTION_HANDLES
@@ -418,7 +422,10 @@ fn build_script_tables(
             | TableType::LOCALS_SIGNATURES => {
                 continue;
             }
-            TableType::STRUCT_DEFS | TableType::FIELD_DEFS | TableType::FUNCTION_DEFS => {
+            TableType::STRUCT_DEFS
+            | TableType::FIELD_DEFS
+            | TableType::FUNCTION_DEFS
+            | TableType::ABORT_CODES => {
                 return Err(VMStatus::new(StatusCode::MALFORMED));
             }
         }
@@ -707,7 +714,9 @@ fn load_signature_token(cursor: &mut Cursor<&[u8]>) -> BinaryLoaderResult<Signat
     if let Ok(byte) = cursor.read_u8() {
         match SerializedType::from_u8(byte)? {
             SerializedType::BOOL => Ok(SignatureToken::Bool),
+            SerializedType::U8 => Ok(SignatureToken::U8),
             SerializedType::INTEGER => Ok(SignatureToken::U64),
+            SerializedType::U128 => Ok(SignatureToken::U128),
             SerializedType::STRING => Ok(SignatureToken::String),
             SerializedType::BYTEARRAY => Ok(SignatureToken::ByteArray),
             SerializedType::ADDRESS => Ok(SignatureToken::Address),
@@ -842,6 +851,43 @@ fn load_field_defs(
     Ok(())
 }
 
+/// Builds the `AbortCodeDefinition` table.
+fn load_abort_codes(
+    binary: &[u8],
+    table: &Table,
+    abort_codes: &mut Vec<AbortCodeDefinition>,
+) -> BinaryLoaderResult<()> {
+    let start = table.offset as usize;
+    let end = start + table.count as usize;
+    let mut cursor = Cursor::new(&binary[start..end]);
+    while cursor.position() < u64::from(table.count) {
+        let code = read_u64_internal(&mut cursor)?;
+        let name = Identifier::from_utf8(load_string_bytes(&mut cursor)?)
+            .map_err(|_| VMStatus::new(StatusCode::MALFORMED))?;
+        let description = String::from_utf8(load_string_bytes(&mut cursor)?)
+            .map_err(|_| VMStatus::new(StatusCode::MALFORMED))?;
+        abort_codes.push(AbortCodeDefinition {
+            code,
+            name,
+            description,
+        });
+    }
+    Ok(())
+}
+
+/// Reads the bytes of a string prefixed with its size.
+fn load_string_bytes(cursor: &mut Cursor<&[u8]>) -> BinaryLoaderResult<Vec<u8>> {
+    let size = read_uleb_u32_internal(cursor)? as usize;
+    if size > std::u16::MAX as usize {
+        return Err(VMStatus::new(StatusCode::MALFORMED));
+    }
+    let mut buffer = vec![0u8; size];
+    cursor
+        .read_exact(&mut buffer)
+        .map_err(|_| VMStatus::new(StatusCode::MALFORMED))?;
+    Ok(buffer)
+}
+
 /// Builds the `FunctionDefinition` table.
 fn load_function_defs(
     binary: &[u8],
@@ -1056,6 +1102,19 @@ fn load_code(cursor: &mut Cursor<&[u8]>, code: &mut Vec<Bytecode>) -> BinaryLoad
             Opcodes::GET_TXN_SEQUENCE_NUMBER => Bytecode::GetTxnSequenceNumber,
             Opcodes::GET_TXN_PUBLIC_KEY => Bytecode::GetTxnPublicKey,
             Opcodes::FREEZE_REF => Bytecode::FreezeRef,
+            Opcodes::LD_U8 => {
+                let value = cursor
+                    .read_u8()
+                    .map_err(|_| VMStatus::new(StatusCode::MALFORMED))?;
+                Bytecode::LdU8(value)
+            }
+            Opcodes::LD_U128 => {
+                let value = read_u128_internal(cursor)?;
+                Bytecode::LdU128(value)
+            }
+            Opcodes::CAST_U8 => Bytecode::CastU8,
+            Opcodes::CAST_U64 => Bytecode::CastU64,
+            Opcodes::CAST_U128 => Bytecode::CastU128,
         };
         code.push(bytecode);
     }
@@ -1092,6 +1151,12 @@ fn read_u64_internal(cursor: &mut Cursor<&[u8]>) -> BinaryLoaderResult<u64> {
         .map_err(|_| VMStatus::new(StatusCode::MALFORMED))
 }
 
+fn read_u128_internal(cursor: &mut Cursor<&[u8]>) -> BinaryLoaderResult<u128> {
+    cursor
+        .read_u128::<LittleEndian>()
+        .map_err(|_| VMStatus::new(StatusCode::MALFORMED))
+}
+
 impl TableType {
     fn from_u8(value: u8) -> BinaryLoaderResult<TableType> {
         match value {
@@ -1109,6 +1174,7 @@ impl TableType {
             0xC => Ok(TableType::TYPE_SIGNATURES),
             0xD => Ok(TableType::FUNCTION_SIGNATURES),
             0xE => Ok(TableType::LOCALS_SIGNATURES),
+            0xF => Ok(TableType::ABORT_CODES),
             _ => Err(VMStatus::new(StatusCode::UNKNOWN_TABLE_TYPE)),
         }
     }
@@ -1138,6 +1204,8 @@ impl SerializedType {
             0x7 => Ok(SerializedType::STRUCT),
             0x8 => Ok(SerializedType::BYTEARRAY),
             0x9 => Ok(SerializedType::TYPE_PARAMETER),
+            0xA => Ok(SerializedType::U8),
+            0xB => Ok(SerializedType::U128),
             _ => Err(VMStatus::new(StatusCode::UNKNOWN_SERIALIZED_TYPE)),
         }
     }
@@ -1231,6 +1299,11 @@ impl Opcodes {
             0x34 => Ok(Opcodes::GET_TXN_SEQUENCE_NUMBER),
             0x35 => Ok(Opcodes::GET_TXN_PUBLIC_KEY),
             0x36 => Ok(Opcodes::FREEZE_REF),
+            0x37 => Ok(Opcodes::LD_U8),
+            0x38 => Ok(Opcodes::LD_U128),
+            0x39 => Ok(Opcodes::CAST_U8),
+            0x3A => Ok(Opcodes::CAST_U64),
+            0x3B => Ok(Opcodes::CAST_U128),
             _ => Err(VMStatus::new(StatusCode::UNKNOWN_OPCODE)),
         }
     }
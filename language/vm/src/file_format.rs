@@ -335,6 +335,21 @@ pub struct FieldDefinition {
     pub signature: TypeSignatureIndex,
 }
 
+/// An `AbortCodeDefinition` is the name and explanation a module declares for a code it aborts
+/// with. It is published along with the module for the clients to describe the aborts, and is not
+/// referred to by the bytecode.
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(any(test, feature = "testing"), derive(Arbitrary))]
+#[cfg_attr(any(test, feature = "testing"), proptest(no_params))]
+pub struct AbortCodeDefinition {
+    /// The code passed to `abort`.
+    pub code: u64,
+    /// The name of the code.
+    pub name: Identifier,
+    /// A human readable explanation of the code.
+    pub description: String,
+}
+
 /// A `FunctionDefinition` is the implementation of a function. It defines
 /// the *prototype* of the function and the function body.
 #[derive(Clone, Debug, Default, Eq, PartialEq)]
@@ -499,8 +514,12 @@ impl Kind {
 pub enum SignatureToken {
     /// Boolean, `true` or `false`.
     Bool,
+    /// Unsigned integers, 8 bits length.
+    U8,
     /// Unsigned integers, 64 bits length.
     U64,
+    /// Unsigned integers, 128 bits length.
+    U128,
     /// Strings, immutable, utf8 representation.
     String,
     /// ByteArray, variable size, immutable byte array.
@@ -528,7 +547,9 @@ impl Arbitrary for SignatureToken {
 
         let leaf = prop_oneof![
             Just(Bool),
+            Just(U8),
             Just(U64),
+            Just(U128),
             Just(String),
             Just(ByteArray),
             Just(Address),
@@ -557,7 +578,9 @@ impl ::std::fmt::Debug for SignatureToken {
     fn fmt(&self, f: &mut ::std::fmt::Formatter) -> ::std::fmt::Result {
         match self {
             SignatureToken::Bool => write!(f, "Bool"),
+            SignatureToken::U8 => write!(f, "U8"),
             SignatureToken::U64 => write!(f, "U64"),
+            SignatureToken::U128 => write!(f, "U128"),
             SignatureToken::String => write!(f, "String"),
             SignatureToken::ByteArray => write!(f, "ByteArray"),
             SignatureToken::Address => write!(f, "Address"),
@@ -612,7 +635,9 @@ impl SignatureToken {
         match self {
             Reference(_) => SignatureTokenKind::Reference,
             MutableReference(_) => SignatureTokenKind::MutableReference,
-            Bool | U64 | ByteArray | String | Address | Struct(_, _) => SignatureTokenKind::Value,
+            Bool | U8 | U64 | U128 | ByteArray | String | Address | Struct(_, _) => {
+                SignatureTokenKind::Value
+            }
             // TODO: This is a temporary hack to please the verifier. SignatureTokenKind will soon
             // be completely removed. `SignatureTokenView::kind()` should be used instead.
             TypeParameter(_) => SignatureTokenKind::Value,
@@ -628,7 +653,7 @@ impl SignatureToken {
         match self {
             Struct(sh_idx, _) => Some(*sh_idx),
             Reference(token) | MutableReference(token) => token.struct_index(),
-            Bool | U64 | ByteArray | String | Address | TypeParameter(_) => None,
+            Bool | U8 | U64 | U128 | ByteArray | String | Address | TypeParameter(_) => None,
         }
     }
 
@@ -636,16 +661,32 @@ impl SignatureToken {
     pub fn is_primitive(&self) -> bool {
         use SignatureToken::*;
         match self {
-            Bool | U64 | String | ByteArray | Address => true,
+            Bool | U8 | U64 | U128 | String | ByteArray | Address => true,
             Struct(_, _) | Reference(_) | MutableReference(_) | TypeParameter(_) => false,
         }
     }
 
+    /// Returns `true` if the `SignatureToken` is an unsigned integer type.
+    pub fn is_integer(&self) -> bool {
+        use SignatureToken::*;
+        match self {
+            U8 | U64 | U128 => true,
+            Bool
+            | String
+            | ByteArray
+            | Address
+            | Struct(_, _)
+            | Reference(_)
+            | MutableReference(_)
+            | TypeParameter(_) => false,
+        }
+    }
+
     /// Checks if the signature token is usable for Eq and Neq.
     ///
     /// Currently equality operations are only allowed on:
     /// - Bool
-    /// - U64
+    /// - U8, U64 and U128
     /// - String
     /// - ByteArray
     /// - Address
@@ -700,7 +741,9 @@ impl SignatureToken {
 
         match self {
             Bool => Bool,
+            U8 => U8,
             U64 => U64,
+            U128 => U128,
             String => String,
             ByteArray => ByteArray,
             Address => Address,
@@ -727,9 +770,8 @@ impl SignatureToken {
 
         match ty {
             // The primitive types & references have kind unrestricted.
-            Bool | U64 | String | ByteArray | Address | Reference(_) | MutableReference(_) => {
-                Kind::Unrestricted
-            }
+            Bool | U8 | U64 | U128 | String | ByteArray | Address | Reference(_)
+            | MutableReference(_) => Kind::Unrestricted,
 
             // To get the kind of a type parameter, we lookup its constraint in the formals.
             TypeParameter(idx) => type_formals[*idx as usize],
@@ -831,12 +873,44 @@ pub enum Bytecode {
     ///
     /// Stack transition: none
     Branch(CodeOffset),
+    /// Push a u8 constant onto the stack.
+    ///
+    /// Stack transition:
+    ///
+    /// ```... -> ..., u8_value```
+    LdU8(u8),
     /// Push integer constant onto the stack.
     ///
     /// Stack transition:
     ///
     /// ```... -> ..., u64_value```
     LdConst(u64),
+    /// Push a u128 constant onto the stack.
+    ///
+    /// Stack transition:
+    ///
+    /// ```... -> ..., u128_value```
+    LdU128(u128),
+    /// Convert the integer value at the top of the stack into a u8.
+    /// Abort execution with an arithmetic error if the value doesn't fit in a u8.
+    ///
+    /// Stack transition:
+    ///
+    /// ```..., integer_value -> ..., u8_value```
+    CastU8,
+    /// Convert the integer value at the top of the stack into a u64.
+    /// Abort execution with an arithmetic error if the value doesn't fit in a u64.
+    ///
+    /// Stack transition:
+    ///
+    /// ```..., integer_value -> ..., u64_value```
+    CastU64,
+    /// Convert the integer value at the top of the stack into a u128.
+    ///
+    /// Stack transition:
+    ///
+    /// ```..., integer_value -> ..., u128_value```
+    CastU128,
     /// Push a string literal onto the stack. The string is loaded from the `UserStrings` via
     /// `UserStringIndex`.
     ///
@@ -996,58 +1070,61 @@ pub enum Bytecode {
     ///
     /// ```..., address_value -> ..., reference_value```
     ImmBorrowGlobal(StructDefinitionIndex, LocalsSignatureIndex),
-    /// Add the 2 u64 at the top of the stack and pushes the result on the stack.
+    /// Add the 2 integers at the top of the stack and pushes the result on the stack.
     /// The operation aborts the transaction in case of overflow.
     ///
+    /// Like all the arithmetic, bitwise and comparison operations, both integers must have the same
+    /// type, which is also the type of the result.
+    ///
     /// Stack transition:
     ///
-    /// ```..., u64_value(1), u64_value(2) -> ..., u64_value```
+    /// ```..., integer_value(1), integer_value(2) -> ..., integer_value```
     Add,
-    /// Subtract the 2 u64 at the top of the stack and pushes the result on the stack.
+    /// Subtract the 2 integers at the top of the stack and pushes the result on the stack.
     /// The operation aborts the transaction in case of underflow.
     ///
     /// Stack transition:
     ///
-    /// ```..., u64_value(1), u64_value(2) -> ..., u64_value```
+    /// ```..., integer_value(1), integer_value(2) -> ..., integer_value```
     Sub,
-    /// Multiply the 2 u64 at the top of the stack and pushes the result on the stack.
+    /// Multiply the 2 integers at the top of the stack and pushes the result on the stack.
     /// The operation aborts the transaction in case of overflow.
     ///
     /// Stack transition:
     ///
-    /// ```..., u64_value(1), u64_value(2) -> ..., u64_value```
+    /// ```..., integer_value(1), integer_value(2) -> ..., integer_value```
     Mul,
-    /// Perform a modulo operation on the 2 u64 at the top of the stack and pushes the
+    /// Perform a modulo operation on the 2 integers at the top of the stack and pushes the
     /// result on the stack.
     ///
     /// Stack transition:
     ///
-    /// ```..., u64_value(1), u64_value(2) -> ..., u64_value```
+    /// ```..., integer_value(1), integer_value(2) -> ..., integer_value```
     Mod,
-    /// Divide the 2 u64 at the top of the stack and pushes the result on the stack.
+    /// Divide the 2 integers at the top of the stack and pushes the result on the stack.
     /// The operation aborts the transaction in case of "divide by 0".
     ///
     /// Stack transition:
     ///
-    /// ```..., u64_value(1), u64_value(2) -> ..., u64_value```
+    /// ```..., integer_value(1), integer_value(2) -> ..., integer_value```
     Div,
-    /// Bitwise OR the 2 u64 at the top of the stack and pushes the result on the stack.
+    /// Bitwise OR the 2 integers at the top of the stack and pushes the result on the stack.
     ///
     /// Stack transition:
     ///
-    /// ```..., u64_value(1), u64_value(2) -> ..., u64_value```
+    /// ```..., integer_value(1), integer_value(2) -> ..., integer_value```
     BitOr,
-    /// Bitwise AND the 2 u64 at the top of the stack and pushes the result on the stack.
+    /// Bitwise AND the 2 integers at the top of the stack and pushes the result on the stack.
     ///
     /// Stack transition:
     ///
-    /// ```..., u64_value(1), u64_value(2) -> ..., u64_value```
+    /// ```..., integer_value(1), integer_value(2) -> ..., integer_value```
     BitAnd,
-    /// Bitwise XOR the 2 u64 at the top of the stack and pushes the result on the stack.
+    /// Bitwise XOR the 2 integers at the top of the stack and pushes the result on the stack.
     ///
     /// Stack transition:
     ///
-    /// ```..., u64_value(1), u64_value(2) -> ..., u64_value```
+    /// ```..., integer_value(1), integer_value(2) -> ..., integer_value```
     Xor,
     /// Logical OR the 2 bool at the top of the stack and pushes the result on the stack.
     ///
@@ -1083,33 +1160,33 @@ pub enum Bytecode {
     ///
     /// ```..., value(1), value(2) -> ..., bool_value```
     Neq,
-    /// Perform a "less than" operation of the 2 u64 at the top of the stack and pushes the
+    /// Perform a "less than" operation of the 2 integers at the top of the stack and pushes the
     /// result on the stack.
     ///
     /// Stack transition:
     ///
-    /// ```..., u64_value(1), u64_value(2) -> ..., bool_value```
+    /// ```..., integer_value(1), integer_value(2) -> ..., bool_value```
     Lt,
-    /// Perform a "greater than" operation of the 2 u64 at the top of the stack and pushes the
+    /// Perform a "greater than" operation of the 2 integers at the top of the stack and pushes the
     /// result on the stack.
     ///
     /// Stack transition:
     ///
-    /// ```..., u64_value(1), u64_value(2) -> ..., bool_value```
+    /// ```..., integer_value(1), integer_value(2) -> ..., bool_value```
     Gt,
-    /// Perform a "less than or equal" operation of the 2 u64 at the top of the stack and pushes
-    /// the result on the stack.
+    /// Perform a "less than or equal" operation of the 2 integers at the top of the stack and
+    /// pushes the result on the stack.
     ///
     /// Stack transition:
     ///
-    /// ```..., u64_value(1), u64_value(2) -> ..., bool_value```
+    /// ```..., integer_value(1), integer_value(2) -> ..., bool_value```
     Le,
-    /// Perform a "greater than or equal" than operation of the 2 u64 at the top of the stack
+    /// Perform a "greater than or equal" than operation of the 2 integers at the top of the stack
     /// and pushes the result on the stack.
     ///
     /// Stack transition:
     ///
-    /// ```..., u64_value(1), u64_value(2) -> ..., bool_value```
+    /// ```..., integer_value(1), integer_value(2) -> ..., bool_value```
     Ge,
     /// Abort execution with errorcode
     ///
@@ -1187,7 +1264,7 @@ pub enum Bytecode {
 /// The number of bytecode instructions.
 /// This is necessary for checking that all instructions are covered since Rust
 /// does not provide a way of determining the number of variants of an enum.
-pub const NUMBER_OF_BYTECODE_INSTRUCTIONS: usize = 54;
+pub const NUMBER_OF_BYTECODE_INSTRUCTIONS: usize = 59;
 
 impl ::std::fmt::Debug for Bytecode {
     fn fmt(&self, f: &mut ::std::fmt::Formatter) -> ::std::fmt::Result {
@@ -1197,7 +1274,12 @@ impl ::std::fmt::Debug for Bytecode {
             Bytecode::BrTrue(a) => write!(f, "BrTrue({})", a),
             Bytecode::BrFalse(a) => write!(f, "BrFalse({})", a),
             Bytecode::Branch(a) => write!(f, "Branch({})", a),
+            Bytecode::LdU8(a) => write!(f, "LdU8({})", a),
             Bytecode::LdConst(a) => write!(f, "LdConst({})", a),
+            Bytecode::LdU128(a) => write!(f, "LdU128({})", a),
+            Bytecode::CastU8 => write!(f, "CastU8"),
+            Bytecode::CastU64 => write!(f, "CastU64"),
+            Bytecode::CastU128 => write!(f, "CastU128"),
             Bytecode::LdStr(a) => write!(f, "LdStr({})", a),
             Bytecode::LdByteArray(a) => write!(f, "LdByteArray({})", a),
             Bytecode::LdAddr(a) => write!(f, "LdAddr({})", a),
@@ -1430,6 +1512,7 @@ impl CompiledScriptMut {
             struct_defs: vec![],
             field_defs: vec![],
             function_defs: vec![self.main],
+            abort_codes: vec![],
         }
     }
 }
@@ -1479,6 +1562,8 @@ pub struct CompiledModuleMut {
     pub field_defs: Vec<FieldDefinition>,
     /// Function defined in this module.
     pub function_defs: Vec<FunctionDefinition>,
+    /// Names and explanations of the codes the module aborts with.
+    pub abort_codes: Vec<AbortCodeDefinition>,
 }
 
 // Need a custom implementation of Arbitrary because as of proptest-derive 0.1.1, the derivation
@@ -1564,6 +1649,7 @@ impl Arbitrary for CompiledModuleMut {
                 vec(any::<FieldDefinition>(), 0..=size),
                 vec(any_with::<FunctionDefinition>(size), 0..=size),
             ),
+            vec(any::<AbortCodeDefinition>(), 0..=size),
         )
             .prop_map(
                 |(
@@ -1571,6 +1657,7 @@ impl Arbitrary for CompiledModuleMut {
                     (type_signatures, function_signatures, locals_signatures),
                     (identifiers, user_strings, byte_array_pool, address_pool),
                     (struct_defs, field_defs, function_defs),
+                    abort_codes,
                 )| {
                     CompiledModuleMut {
                         module_handles,
@@ -1586,6 +1673,7 @@ impl Arbitrary for CompiledModuleMut {
                         struct_defs,
                         field_defs,
                         function_defs,
+                        abort_codes,
                     }
                 },
             )
@@ -1610,6 +1698,7 @@ impl CompiledModuleMut {
             IndexKind::UserString => self.user_strings.len(),
             IndexKind::ByteArrayPool => self.byte_array_pool.len(),
             IndexKind::AddressPool => self.address_pool.len(),
+            IndexKind::AbortCode => self.abort_codes.len(),
             // XXX these two don't seem to belong here
             other @ IndexKind::LocalPool
             | other @ IndexKind::CodeDefinition
@@ -1662,6 +1751,14 @@ impl CompiledModule {
         self.module_id_for_handle(self.self_handle())
     }
 
+    /// Returns the name and explanation the module declared for the abort code `code`, if any.
+    pub fn abort_code(&self, code: u64) -> Option<&AbortCodeDefinition> {
+        self.as_inner()
+            .abort_codes
+            .iter()
+            .find(|abort_code| abort_code.code == code)
+    }
+
     /// This function should only be called on an instance of CompiledModule obtained by invoking
     /// into_module on some instance of CompiledScript. This function is the inverse of
     /// into_module, i.e., script.into_module().into_script() == script.
@@ -1706,6 +1803,7 @@ pub fn empty_module() -> CompiledModuleMut {
         function_signatures: vec![],
         locals_signatures: vec![LocalsSignature(vec![])],
         byte_array_pool: vec![],
+        abort_codes: vec![],
     }
 }
 
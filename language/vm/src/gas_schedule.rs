@@ -6,6 +6,11 @@
 //! It is important to note that the cost schedule defined in this file does not track hashing
 //! operations or other native operations; the cost of each native operation will be returned by the
 //! native function itself.
+//!
+//! The costs of the instructions and the bounds on the gas of the transactions form the
+//! [`GasSchedule`], which is published on-chain at genesis and can be updated by the association.
+//! The values defined in this file are the ones of the [default schedule](DEFAULT_GAS_SCHEDULE),
+//! used at genesis and whenever no schedule is found on-chain.
 use crate::{
     file_format::{
         AddressPoolIndex, ByteArrayPoolIndex, Bytecode, FieldDefinitionIndex, FunctionHandleIndex,
@@ -13,10 +18,19 @@ use crate::{
     },
     serializer::serialize_instruction,
 };
+use failure::prelude::*;
 use lazy_static::lazy_static;
-use libra_types::transaction::MAX_TRANSACTION_SIZE_IN_BYTES;
+use libra_types::{
+    access_path::{AccessPath, Accesses},
+    account_config,
+    byte_array::ByteArray,
+    identifier::{IdentStr, Identifier},
+    language_storage::StructTag,
+    transaction::MAX_TRANSACTION_SIZE_IN_BYTES,
+};
+use serde::{Deserialize, Serialize};
 use std::{
-    collections::HashMap,
+    collections::BTreeMap,
     ops::{Add, Div, Mul, Sub},
     u64,
 };
@@ -105,7 +119,7 @@ macro_rules! define_gas_unit {
         carrier: $carrier: ty,
         doc: $comment: literal
     } => {
-        #[derive(Debug, Hash, Eq, PartialEq, Copy, Clone)]
+        #[derive(Debug, Hash, Eq, PartialEq, Copy, Clone, Serialize, Deserialize)]
         #[doc=$comment]
         pub struct $name<GasCarrier>(GasCarrier);
         impl GasAlgebra<$carrier> for $name<$carrier> {
@@ -139,7 +153,7 @@ define_gas_unit! {
 
 /// A newtype wrapper around the on-chain representation of an instruction key. This is the
 /// serialization of the instruction but disregarding any instruction arguments.
-#[derive(Debug, Hash, Eq, PartialEq, Copy, Clone)]
+#[derive(Debug, Hash, Eq, PartialEq, Ord, PartialOrd, Copy, Clone, Serialize, Deserialize)]
 pub struct InstructionKey(pub u8);
 
 lazy_static! {
@@ -192,12 +206,12 @@ lazy_static! {
 }
 
 /// The cost tables, keyed by the serialized form of the bytecode instruction.  We use the
-/// serialized form as opposed to the instruction enum itself as the key since this will be the
-/// on-chain representation of bytecode instructions in the future.
-#[derive(Debug)]
+/// serialized form as opposed to the instruction enum itself as the key since this is the
+/// on-chain representation of bytecode instructions.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
 pub struct CostTable {
-    pub compute_table: HashMap<InstructionKey, GasUnits<GasCarrier>>,
-    pub memory_table: HashMap<InstructionKey, GasUnits<GasCarrier>>,
+    pub compute_table: BTreeMap<InstructionKey, GasUnits<GasCarrier>>,
+    pub memory_table: BTreeMap<InstructionKey, GasUnits<GasCarrier>>,
 }
 
 impl InstructionKey {
@@ -212,8 +226,8 @@ impl InstructionKey {
 
 impl CostTable {
     pub fn new(instrs: Vec<(Bytecode, u64, u64)>) -> Self {
-        let mut compute_table = HashMap::new();
-        let mut memory_table = HashMap::new();
+        let mut compute_table = BTreeMap::new();
+        let mut memory_table = BTreeMap::new();
         let mut instructions_covered = 0;
         for (instr, comp_cost, mem_cost) in instrs.into_iter() {
             let code = InstructionKey::new(&instr);
@@ -238,11 +252,7 @@ impl CostTable {
         instr: &Bytecode,
         size_provider: AbstractMemorySize<GasCarrier>,
     ) -> GasUnits<GasCarrier> {
-        let code = InstructionKey::new(instr);
-        let memory_cost = self.memory_table.get(&code);
-        // CostTable initialization checks that every instruction is included in the memory_table
-        assume!(memory_cost.is_some());
-        memory_cost.unwrap().map2(size_provider, Mul::mul)
+        Self::instruction_cost(&self.memory_table, instr, size_provider)
     }
 
     pub fn comp_gas(
@@ -250,16 +260,62 @@ impl CostTable {
         instr: &Bytecode,
         size_provider: AbstractMemorySize<GasCarrier>,
     ) -> GasUnits<GasCarrier> {
-        let code = InstructionKey::new(instr);
-        let compute_cost = self.compute_table.get(&code);
-        // CostTable initialization checks that every instruction is included in the compute_table
-        assume!(compute_cost.is_some());
-        compute_cost.unwrap().map2(size_provider, Mul::mul)
+        Self::instruction_cost(&self.compute_table, instr, size_provider)
+    }
+
+    fn instruction_cost(
+        table: &BTreeMap<InstructionKey, GasUnits<GasCarrier>>,
+        instr: &Bytecode,
+        size_provider: AbstractMemorySize<GasCarrier>,
+    ) -> GasUnits<GasCarrier> {
+        // The tables are checked to cover every instruction when they are built or decoded.
+        // Should an instruction still be missing, it costs more than any transaction can pay for.
+        table
+            .get(&InstructionKey::new(instr))
+            .cloned()
+            .unwrap_or_else(|| GasUnits::new(GasCarrier::max_value()))
+            .map2(size_provider, GasCarrier::saturating_mul)
+    }
+
+    /// Check that both tables cost every instruction, and nothing else, and that no instruction
+    /// costs more than `max_cost`. The instructions must have a computational cost, otherwise a
+    /// loop could run forever without running out of gas.
+    fn validate(&self, max_cost: GasUnits<GasCarrier>) -> Result<()> {
+        ensure!(
+            self.compute_table.len() == NUMBER_OF_BYTECODE_INSTRUCTIONS
+                && self.memory_table.len() == NUMBER_OF_BYTECODE_INSTRUCTIONS,
+            "The cost table must cost exactly the {} instructions",
+            NUMBER_OF_BYTECODE_INSTRUCTIONS
+        );
+        for key in DEFAULT_COST_TABLE.compute_table.keys() {
+            let (compute_cost, memory_cost) =
+                match (self.compute_table.get(key), self.memory_table.get(key)) {
+                    (Some(compute_cost), Some(memory_cost)) => (compute_cost, memory_cost),
+                    _ => bail!("No cost for instruction {:?}", key),
+                };
+            ensure!(
+                compute_cost.get() > 0,
+                "No computational cost for instruction {:?}",
+                key
+            );
+            ensure!(
+                compute_cost.get() <= max_cost.get() && memory_cost.get() <= max_cost.get(),
+                "The cost of instruction {:?} exceeds {}",
+                key,
+                max_cost.get()
+            );
+        }
+        Ok(())
+    }
+
+    /// Replace the computational cost of `instr`, keeping its memory cost.
+    pub fn set_comp_gas(&mut self, instr: &Bytecode, cost: GasUnits<GasCarrier>) {
+        self.compute_table.insert(InstructionKey::new(instr), cost);
     }
 }
 
 lazy_static! {
-    static ref GAS_SCHEDULE: CostTable = {
+    static ref DEFAULT_COST_TABLE: CostTable = {
         use Bytecode::*;
         // Arguments to the instructions don't matter -- these will be removed in the
         // `encode_instruction` function.
@@ -289,7 +345,9 @@ lazy_static! {
             (StLoc(0), 28, 1),
             (Ret, 28, 1),
             (Lt, 49, 1),
+            (LdU8(0), 29, 1),
             (LdConst(0), 29, 1),
+            (LdU128(0), 29, 1),
             (Abort, 39, 1),
             (MutBorrowLoc(0), 45, 1),
             (ImmBorrowLoc(0), 45, 1),
@@ -322,6 +380,9 @@ lazy_static! {
             (Eq, 48, 1),
             (LdByteArray(ByteArrayPoolIndex::new(0)), 56, 1),
             (Gt, 46, 1),
+            (CastU8, 29, 1),
+            (CastU64, 29, 1),
+            (CastU128, 29, 1),
             (Pack(StructDefinitionIndex::new(0), NO_TYPE_ACTUALS), 73, 1),
         ];
         CostTable::new(instrs)
@@ -337,17 +398,177 @@ pub struct GasCost {
     pub memory_gas: GasUnits<GasCarrier>,
 }
 
-/// Statically cost a bytecode instruction.
+/// The gas schedule of the VM: the cost of every instruction along with the bounds that the gas
+/// of the transactions is checked against.
 ///
-/// Don't take into account current stack or memory size. Don't track whether references are to
-/// global or local storage.
-pub fn static_cost_instr(
-    instr: &Bytecode,
-    size_provider: AbstractMemorySize<GasCarrier>,
-) -> GasCost {
-    GasCost {
-        instruction_gas: GAS_SCHEDULE.comp_gas(instr, size_provider),
-        memory_gas: GAS_SCHEDULE.memory_gas(instr, size_provider),
+/// The schedule is stored in the `GasSchedule.T` resource of the association account, serialized
+/// with LCS. The VM reads it at the beginning of every block.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct GasSchedule {
+    pub cost_table: CostTable,
+    /// The cost per-byte written to global storage.
+    pub global_memory_per_byte_cost: GasUnits<GasCarrier>,
+    /// The cost per-byte written to storage.
+    pub global_memory_per_byte_write_cost: GasUnits<GasCarrier>,
+    /// The units of gas that should be charged per byte for every transaction.
+    pub intrinsic_gas_per_byte: GasUnits<GasCarrier>,
+    /// The minimum gas price that a transaction can be submitted with.
+    pub min_price_per_gas_unit: GasPrice<GasCarrier>,
+    /// The maximum gas unit price that a transaction can be submitted with.
+    pub max_price_per_gas_unit: GasPrice<GasCarrier>,
+    /// The maximum number of gas units that a transaction can be submitted with.
+    pub maximum_number_of_gas_units: GasUnits<GasCarrier>,
+    /// The minimum number of gas units that every transaction is charged.
+    pub min_transaction_gas_units: GasUnits<GasCarrier>,
+    /// Any transaction over this size will be charged `intrinsic_gas_per_byte` per byte
+    pub large_transaction_cutoff: AbstractMemorySize<GasCarrier>,
+}
+
+lazy_static! {
+    /// The gas schedule published at genesis.
+    pub static ref DEFAULT_GAS_SCHEDULE: GasSchedule = GasSchedule {
+        cost_table: DEFAULT_COST_TABLE.clone(),
+        global_memory_per_byte_cost: *GLOBAL_MEMORY_PER_BYTE_COST,
+        global_memory_per_byte_write_cost: *GLOBAL_MEMORY_PER_BYTE_WRITE_COST,
+        intrinsic_gas_per_byte: *INTRINSIC_GAS_PER_BYTE,
+        min_price_per_gas_unit: *MIN_PRICE_PER_GAS_UNIT,
+        max_price_per_gas_unit: *MAX_PRICE_PER_GAS_UNIT,
+        maximum_number_of_gas_units: *MAXIMUM_NUMBER_OF_GAS_UNITS,
+        min_transaction_gas_units: *MIN_TRANSACTION_GAS_UNITS,
+        large_transaction_cutoff: *LARGE_TRANSACTION_CUTOFF,
+    };
+
+    static ref GAS_SCHEDULE_MODULE_NAME: Identifier = Identifier::new("GasSchedule").unwrap();
+    static ref GAS_SCHEDULE_STRUCT_NAME: Identifier = Identifier::new("T").unwrap();
+}
+
+/// The on-chain representation of the gas schedule: the `GasSchedule.T` resource.
+#[derive(Debug, Deserialize)]
+struct GasScheduleResource {
+    schedule: ByteArray,
+}
+
+pub fn gas_schedule_module_name() -> &'static IdentStr {
+    &*GAS_SCHEDULE_MODULE_NAME
+}
+
+pub fn gas_schedule_struct_name() -> &'static IdentStr {
+    &*GAS_SCHEDULE_STRUCT_NAME
+}
+
+pub fn gas_schedule_struct_tag() -> StructTag {
+    StructTag {
+        address: account_config::core_code_address(),
+        module: gas_schedule_module_name().to_owned(),
+        name: gas_schedule_struct_name().to_owned(),
+        type_params: vec![],
+    }
+}
+
+/// Return the access path of the gas schedule resource, published under the association account.
+pub fn gas_schedule_access_path() -> AccessPath {
+    AccessPath::new(
+        account_config::association_address(),
+        AccessPath::resource_access_vec(&gas_schedule_struct_tag(), &Accesses::empty()),
+    )
+}
+
+impl GasSchedule {
+    /// Serialize the schedule into the format stored in the gas schedule resource.
+    pub fn to_bytes(&self) -> Result<Vec<u8>> {
+        lcs::to_bytes(self).map_err(Into::into)
+    }
+
+    /// Deserialize a schedule serialized with [`GasSchedule::to_bytes`], rejecting it if it is
+    /// not [valid](GasSchedule::validate).
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        let schedule: Self = lcs::from_bytes(bytes)?;
+        schedule.validate()?;
+        Ok(schedule)
+    }
+
+    /// Check that the schedule costs every instruction and that its bounds are consistent, so
+    /// that no transaction can overflow the gas computations or run without paying for it.
+    pub fn validate(&self) -> Result<()> {
+        let max_gas_units = self.maximum_number_of_gas_units;
+        ensure!(
+            max_gas_units.get() > 0,
+            "The maximum number of gas units must be positive"
+        );
+        ensure!(
+            self.min_price_per_gas_unit.get() <= self.max_price_per_gas_unit.get(),
+            "The minimum gas price exceeds the maximum one"
+        );
+        ensure!(
+            max_gas_units
+                .get()
+                .checked_mul(self.max_price_per_gas_unit.get())
+                .is_some(),
+            "The maximum transaction fee overflows"
+        );
+        ensure!(
+            self.min_transaction_gas_units.get() <= max_gas_units.get(),
+            "The minimum transaction gas exceeds the maximum number of gas units"
+        );
+        ensure!(
+            self.intrinsic_gas_per_byte.get() <= max_gas_units.get()
+                && self.global_memory_per_byte_cost.get() <= max_gas_units.get()
+                && self.global_memory_per_byte_write_cost.get() <= max_gas_units.get(),
+            "A per-byte cost exceeds the maximum number of gas units"
+        );
+        ensure!(
+            self.large_transaction_cutoff.get() <= MAX_TRANSACTION_SIZE_IN_BYTES as GasCarrier,
+            "The large transaction cutoff exceeds the maximum transaction size"
+        );
+        let max_excess = words_in(AbstractMemorySize::new(
+            MAX_TRANSACTION_SIZE_IN_BYTES as GasCarrier - self.large_transaction_cutoff.get(),
+        ));
+        ensure!(
+            self.intrinsic_gas_per_byte
+                .get()
+                .checked_mul(max_excess.get())
+                .and_then(|excess_gas| excess_gas.checked_add(self.min_transaction_gas_units.get()))
+                .is_some(),
+            "The intrinsic gas of the largest transaction overflows"
+        );
+        self.cost_table.validate(max_gas_units)
+    }
+
+    /// Decode the schedule from the blob of the gas schedule resource.
+    pub fn from_resource_blob(blob: &[u8]) -> Result<Self> {
+        let resource: GasScheduleResource = lcs::from_bytes(blob)?;
+        Self::from_bytes(resource.schedule.as_bytes())
+    }
+
+    /// Statically cost a bytecode instruction.
+    ///
+    /// Don't take into account current stack or memory size. Don't track whether references are
+    /// to global or local storage.
+    pub fn static_cost_instr(
+        &self,
+        instr: &Bytecode,
+        size_provider: AbstractMemorySize<GasCarrier>,
+    ) -> GasCost {
+        GasCost {
+            instruction_gas: self.cost_table.comp_gas(instr, size_provider),
+            memory_gas: self.cost_table.memory_gas(instr, size_provider),
+        }
+    }
+
+    /// Calculate the intrinsic gas for the transaction based upon its size in bytes/words.
+    pub fn calculate_intrinsic_gas(
+        &self,
+        transaction_size: AbstractMemorySize<GasCarrier>,
+    ) -> GasUnits<GasCarrier> {
+        precondition!(transaction_size.get() <= MAX_TRANSACTION_SIZE_IN_BYTES as GasCarrier);
+        let min_transaction_fee = self.min_transaction_gas_units;
+
+        if transaction_size.get() > self.large_transaction_cutoff.get() {
+            let excess = words_in(transaction_size.sub(self.large_transaction_cutoff));
+            min_transaction_fee.add(self.intrinsic_gas_per_byte.mul(excess))
+        } else {
+            min_transaction_fee.unitary_cast()
+        }
     }
 }
 
@@ -359,18 +580,3 @@ pub fn words_in(size: AbstractMemorySize<GasCarrier>) -> AbstractMemorySize<GasC
         (size + (word_size - 1)) / word_size
     })
 }
-
-/// Calculate the intrinsic gas for the transaction based upon its size in bytes/words.
-pub fn calculate_intrinsic_gas(
-    transaction_size: AbstractMemorySize<GasCarrier>,
-) -> GasUnits<GasCarrier> {
-    precondition!(transaction_size.get() <= MAX_TRANSACTION_SIZE_IN_BYTES as GasCarrier);
-    let min_transaction_fee = *MIN_TRANSACTION_GAS_UNITS;
-
-    if transaction_size.get() > LARGE_TRANSACTION_CUTOFF.get() {
-        let excess = words_in(transaction_size.sub(*LARGE_TRANSACTION_CUTOFF));
-        min_transaction_fee.add(INTRINSIC_GAS_PER_BYTE.mul(excess))
-    } else {
-        min_transaction_fee.unitary_cast()
-    }
-}
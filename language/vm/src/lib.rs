@@ -51,6 +51,7 @@ pub enum IndexKind {
     LocalPool,
     CodeDefinition,
     TypeParameter,
+    AbortCode,
 }
 
 impl IndexKind {
@@ -75,6 +76,7 @@ impl IndexKind {
             LocalPool,
             CodeDefinition,
             TypeParameter,
+            AbortCode,
         ]
     }
 }
@@ -100,6 +102,7 @@ impl fmt::Display for IndexKind {
             LocalPool => "local pool",
             CodeDefinition => "code definition pool",
             TypeParameter => "type parameter",
+            AbortCode => "abort code",
         };
 
         f.write_str(desc)
@@ -594,7 +594,9 @@ fn display_signature_token<T: TableAccess>(
 ) -> fmt::Result {
     match token {
         SignatureToken::Bool => write!(f, "Bool"),
+        SignatureToken::U8 => write!(f, "U8"),
         SignatureToken::U64 => write!(f, "Integer"),
+        SignatureToken::U128 => write!(f, "U128"),
         SignatureToken::String => write!(f, "String"),
         SignatureToken::ByteArray => write!(f, "ByteArray"),
         SignatureToken::Address => write!(f, "Address"),
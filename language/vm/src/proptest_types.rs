@@ -348,6 +348,7 @@ impl CompiledModuleStrategyGen {
                         user_strings,
                         byte_array_pool,
                         address_pool,
+                        abort_codes: vec![],
                     }
                     .freeze()
                     .expect("valid modules should satisfy the bounds checker")
@@ -403,7 +404,7 @@ impl StDefnMaterializeState {
                     || targs.iter().any(|t| self.contains_nominal_resource(t))
             }
             Reference(token) | MutableReference(token) => self.contains_nominal_resource(token),
-            Bool | U64 | ByteArray | String | Address | TypeParameter(_) => false,
+            Bool | U8 | U64 | U128 | ByteArray | String | Address | TypeParameter(_) => false,
         }
     }
 }
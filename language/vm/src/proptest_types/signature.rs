@@ -78,7 +78,9 @@ impl FunctionSignatureGen {
 pub enum SignatureTokenGen {
     // Atomic signature tokens.
     Bool,
+    U8,
     Integer,
+    U128,
     String,
     ByteArray,
     Address,
@@ -121,7 +123,7 @@ impl SignatureTokenGen {
         use SignatureTokenGen::*;
 
         static OWNED_NON_STRUCTS: &[SignatureTokenGen] =
-            &[Bool, Integer, String, ByteArray, Address];
+            &[Bool, U8, Integer, U128, String, ByteArray, Address];
 
         select(OWNED_NON_STRUCTS)
     }
@@ -154,7 +156,9 @@ impl SignatureTokenGen {
 
         match self {
             Bool => SignatureToken::Bool,
+            U8 => SignatureToken::U8,
             Integer => SignatureToken::U64,
+            U128 => SignatureToken::U128,
             String => SignatureToken::String,
             ByteArray => SignatureToken::ByteArray,
             Address => SignatureToken::Address,
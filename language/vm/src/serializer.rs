@@ -93,6 +93,7 @@ struct ModuleSerializer {
     struct_defs: (u32, u32),
     field_defs: (u32, u32),
     function_defs: (u32, u32),
+    abort_codes: (u32, u32),
 }
 
 /// Holds data to compute the header of a transaction script binary.
@@ -391,6 +392,18 @@ fn serialize_field_definition(
     Ok(())
 }
 
+/// Serializes an `AbortCodeDefinition`.
+///
+/// An `AbortCodeDefinition` gets serialized as follows:
+/// - `AbortCodeDefinition.code` as a u64
+/// - `AbortCodeDefinition.name` as a string
+/// - `AbortCodeDefinition.description` as a string
+fn serialize_abort_code(binary: &mut BinaryData, abort_code: &AbortCodeDefinition) -> Result<()> {
+    write_u64(binary, abort_code.code)?;
+    serialize_string(binary, abort_code.name.as_str())?;
+    serialize_string(binary, &abort_code.description)
+}
+
 /// Serializes a `FunctionDefinition`.
 ///
 /// A `FunctionDefinition` gets serialized as follows:
@@ -487,7 +500,9 @@ fn serialize_signature_tokens(binary: &mut BinaryData, tokens: &[SignatureToken]
 fn serialize_signature_token(binary: &mut BinaryData, token: &SignatureToken) -> Result<()> {
     match token {
         SignatureToken::Bool => binary.push(SerializedType::BOOL as u8)?,
+        SignatureToken::U8 => binary.push(SerializedType::U8 as u8)?,
         SignatureToken::U64 => binary.push(SerializedType::INTEGER as u8)?,
+        SignatureToken::U128 => binary.push(SerializedType::U128 as u8)?,
         SignatureToken::String => binary.push(SerializedType::STRING as u8)?,
         SignatureToken::ByteArray => binary.push(SerializedType::BYTEARRAY as u8)?,
         SignatureToken::Address => binary.push(SerializedType::ADDRESS as u8)?,
@@ -566,6 +581,17 @@ pub(crate) fn serialize_instruction(binary: &mut Vec<u8>, opcode: &Bytecode) ->
 fn serialize_instruction_inner(binary: &mut BinaryData, opcode: &Bytecode) -> Result<()> {
     let res = match opcode {
         Bytecode::FreezeRef => binary.push(Opcodes::FREEZE_REF as u8),
+        Bytecode::LdU8(value) => {
+            binary.push(Opcodes::LD_U8 as u8)?;
+            binary.push(*value)
+        }
+        Bytecode::LdU128(value) => {
+            binary.push(Opcodes::LD_U128 as u8)?;
+            write_u128(binary, *value)
+        }
+        Bytecode::CastU8 => binary.push(Opcodes::CAST_U8 as u8),
+        Bytecode::CastU64 => binary.push(Opcodes::CAST_U64 as u8),
+        Bytecode::CastU128 => binary.push(Opcodes::CAST_U128 as u8),
         Bytecode::Pop => binary.push(Opcodes::POP as u8),
         Bytecode::Ret => binary.push(Opcodes::RET as u8),
         Bytecode::BrTrue(code_offset) => {
@@ -1043,6 +1069,7 @@ impl ModuleSerializer {
             struct_defs: (0, 0),
             field_defs: (0, 0),
             function_defs: (0, 0),
+            abort_codes: (0, 0),
         }
     }
 
@@ -1050,7 +1077,8 @@ impl ModuleSerializer {
         self.common.serialize_common(binary, module)?;
         self.serialize_struct_definitions(binary, &module.struct_defs)?;
         self.serialize_field_definitions(binary, &module.field_defs)?;
-        self.serialize_function_definitions(binary, &module.function_defs)
+        self.serialize_function_definitions(binary, &module.function_defs)?;
+        self.serialize_abort_codes(binary, &module.abort_codes)
     }
 
     fn serialize_header(&mut self, binary: &mut BinaryData) -> Result<()> {
@@ -1076,6 +1104,13 @@ impl ModuleSerializer {
             start_offset,
             self.function_defs.1,
         )?;
+        checked_serialize_table(
+            binary,
+            TableType::ABORT_CODES,
+            self.abort_codes.0,
+            start_offset,
+            self.abort_codes.1,
+        )?;
         Ok(())
     }
 
@@ -1129,6 +1164,23 @@ impl ModuleSerializer {
         }
         Ok(())
     }
+
+    /// Serializes `AbortCodeDefinition` table.
+    fn serialize_abort_codes(
+        &mut self,
+        binary: &mut BinaryData,
+        abort_codes: &[AbortCodeDefinition],
+    ) -> Result<()> {
+        if !abort_codes.is_empty() {
+            self.common.table_count += 1;
+            self.abort_codes.0 = check_index_in_binary(binary.len())?;
+            for abort_code in abort_codes {
+                serialize_abort_code(binary, abort_code)?;
+            }
+            self.abort_codes.1 = checked_calculate_table_size(binary, self.abort_codes.0)?;
+        }
+        Ok(())
+    }
 }
 
 impl ScriptSerializer {
@@ -0,0 +1,98 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{
+    file_format::Bytecode,
+    gas_schedule::{
+        AbstractMemorySize, GasAlgebra, GasPrice, GasSchedule, GasUnits, InstructionKey,
+        DEFAULT_GAS_SCHEDULE,
+    },
+};
+use libra_types::byte_array::ByteArray;
+
+#[test]
+fn gas_schedule_roundtrip() {
+    let bytes = DEFAULT_GAS_SCHEDULE.to_bytes().unwrap();
+    let schedule = GasSchedule::from_bytes(&bytes).unwrap();
+    assert_eq!(schedule, *DEFAULT_GAS_SCHEDULE);
+}
+
+#[test]
+fn gas_schedule_from_resource_blob() {
+    let mut schedule = DEFAULT_GAS_SCHEDULE.clone();
+    schedule.maximum_number_of_gas_units =
+        schedule.maximum_number_of_gas_units.sub(GasUnits::new(42));
+    // The resource holds the serialized schedule in its single bytearray field.
+    let blob = lcs::to_bytes(&ByteArray::new(schedule.to_bytes().unwrap())).unwrap();
+    assert_eq!(GasSchedule::from_resource_blob(&blob).unwrap(), schedule);
+
+    assert!(GasSchedule::from_resource_blob(&blob[..blob.len() - 1]).is_err());
+}
+
+#[test]
+fn invalid_gas_schedules_rejected() {
+    assert!(DEFAULT_GAS_SCHEDULE.validate().is_ok());
+    let rejected = |schedule: GasSchedule| {
+        assert!(schedule.validate().is_err());
+        assert!(GasSchedule::from_bytes(&schedule.to_bytes().unwrap()).is_err());
+    };
+
+    let mut schedule = DEFAULT_GAS_SCHEDULE.clone();
+    schedule
+        .cost_table
+        .compute_table
+        .remove(&InstructionKey::new(&Bytecode::Branch(0)));
+    rejected(schedule);
+
+    let mut schedule = DEFAULT_GAS_SCHEDULE.clone();
+    schedule
+        .cost_table
+        .set_comp_gas(&Bytecode::Branch(0), GasUnits::new(0));
+    rejected(schedule);
+
+    let mut schedule = DEFAULT_GAS_SCHEDULE.clone();
+    schedule
+        .cost_table
+        .set_comp_gas(&Bytecode::Add, GasUnits::new(u64::max_value()));
+    rejected(schedule);
+
+    let mut schedule = DEFAULT_GAS_SCHEDULE.clone();
+    schedule.min_price_per_gas_unit = GasPrice::new(schedule.max_price_per_gas_unit.get() + 1);
+    rejected(schedule);
+
+    let mut schedule = DEFAULT_GAS_SCHEDULE.clone();
+    schedule.maximum_number_of_gas_units = GasUnits::new(u64::max_value());
+    rejected(schedule);
+
+    let mut schedule = DEFAULT_GAS_SCHEDULE.clone();
+    schedule.min_transaction_gas_units = schedule.maximum_number_of_gas_units.add(GasUnits::new(1));
+    rejected(schedule);
+}
+
+#[test]
+fn missing_instruction_cost_exhausts_gas() {
+    let mut schedule = DEFAULT_GAS_SCHEDULE.clone();
+    let key = InstructionKey::new(&Bytecode::Pop);
+    schedule.cost_table.compute_table.remove(&key);
+    let cost = schedule.static_cost_instr(&Bytecode::Pop, AbstractMemorySize::new(2));
+    assert_eq!(cost.instruction_gas.get(), u64::max_value());
+}
+
+#[test]
+fn intrinsic_gas_follows_schedule() {
+    let mut schedule = DEFAULT_GAS_SCHEDULE.clone();
+    let small_txn = AbstractMemorySize::new(10);
+    let large_txn = schedule
+        .large_transaction_cutoff
+        .add(AbstractMemorySize::new(16));
+    assert_eq!(
+        schedule.calculate_intrinsic_gas(small_txn),
+        schedule.min_transaction_gas_units
+    );
+
+    schedule.min_transaction_gas_units = GasUnits::new(1000);
+    schedule.intrinsic_gas_per_byte = GasUnits::new(3);
+    assert_eq!(schedule.calculate_intrinsic_gas(small_txn).get(), 1000);
+    // The 16 bytes over the cutoff are charged as 2 words
+    assert_eq!(schedule.calculate_intrinsic_gas(large_txn).get(), 1006);
+}
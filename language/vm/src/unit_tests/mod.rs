@@ -4,5 +4,6 @@
 mod binary_tests;
 mod deserializer_tests;
 mod fixture_tests;
+mod gas_schedule_tests;
 mod number_tests;
 mod vm_string_tests;
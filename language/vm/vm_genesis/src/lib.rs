@@ -1,6 +1,7 @@
 // Copyright (c) The Libra Core Contributors
 // SPDX-License-Identifier: Apache-2.0
 
+use config::trusted_peers::ConfigHelpers;
 use crypto::{ed25519::*, traits::ValidKey};
 use failure::prelude::*;
 use lazy_static::lazy_static;
@@ -17,7 +18,10 @@ use rand::{rngs::StdRng, SeedableRng};
 use state_view::StateView;
 use std::time::Duration;
 use stdlib::stdlib_modules;
-use vm::{access::ModuleAccess, transaction_metadata::TransactionMetadata};
+use vm::{
+    access::ModuleAccess, gas_schedule::DEFAULT_GAS_SCHEDULE,
+    transaction_metadata::TransactionMetadata,
+};
 use vm_cache_map::Arena;
 use vm_runtime::{
     code_cache::{
@@ -26,7 +30,7 @@ use vm_runtime::{
     },
     data_cache::BlockDataCache,
     txn_executor::{
-        TransactionExecutor, ACCOUNT_MODULE, BLOCK_MODULE, COIN_MODULE,
+        TransactionExecutor, ACCOUNT_MODULE, BLOCK_MODULE, COIN_MODULE, GAS_SCHEDULE_MODULE,
         TRANSACTION_FEE_DISTRIBUTION_MODULE, VALIDATOR_CONFIG_MODULE, VALIDATOR_SET_MODULE,
     },
 };
@@ -182,6 +186,17 @@ impl StateView for FakeStateView {
     }
 }
 
+/// Encodes the transaction stored in the genesis blob: it's signed with `GENESIS_KEYPAIR` and
+/// sets up the validator set of the default 10 validator nodes.
+pub fn encode_default_genesis_transaction() -> SignatureCheckedTransaction {
+    let (_, consensus_config, network_config) = ConfigHelpers::gen_validator_nodes(10, None);
+    encode_genesis_transaction_with_validator(
+        &GENESIS_KEYPAIR.0,
+        GENESIS_KEYPAIR.1.clone(),
+        consensus_config.get_validator_set(&network_config),
+    )
+}
+
 pub fn encode_genesis_transaction(
     private_key: &Ed25519PrivateKey,
     public_key: Ed25519PublicKey,
@@ -207,7 +222,8 @@ pub fn encode_genesis_transaction_with_validator(
     let genesis_write_set = {
         let fake_fetcher = FakeFetcher::new(modules.iter().map(|m| m.as_inner().clone()).collect());
         let data_cache = BlockDataCache::new(&state_view);
-        let block_cache = BlockModuleCache::new(&vm_cache, fake_fetcher);
+        let block_arena = Arena::new();
+        let block_cache = BlockModuleCache::new(&vm_cache, &block_arena, fake_fetcher);
         {
             let mut txn_data = TransactionMetadata::default();
             txn_data.sender = genesis_addr;
@@ -227,6 +243,16 @@ pub fn encode_genesis_transaction_with_validator(
                 .execute_function(&COIN_MODULE, &INITIALIZE, vec![])
                 .unwrap();
 
+            // Publish the default gas schedule under the Association account.
+            let gas_schedule = ByteArray::new(DEFAULT_GAS_SCHEDULE.to_bytes().unwrap());
+            txn_executor
+                .execute_function(
+                    &GAS_SCHEDULE_MODULE,
+                    &INITIALIZE,
+                    vec![Value::byte_array(gas_schedule)],
+                )
+                .unwrap();
+
             txn_executor
                 .execute_function(
                     &ACCOUNT_MODULE,
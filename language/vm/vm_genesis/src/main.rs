@@ -1,25 +1,19 @@
 // Copyright (c) The Libra Core Contributors
 // SPDX-License-Identifier: Apache-2.0
 
-use config::{config::PersistableConfig, trusted_peers::ConfigHelpers};
+use config::config::PersistableConfig;
 use prost_ext::MessageExt;
 use std::{fs::File, io::prelude::*};
 use transaction_builder::default_config;
-use vm_genesis::{encode_genesis_transaction_with_validator, GENESIS_KEYPAIR};
+use vm_genesis::encode_default_genesis_transaction;
 
 const CONFIG_LOCATION: &str = "genesis/vm_config.toml";
 const GENESIS_LOCATION: &str = "genesis/genesis.blob";
 
 /// Generate the genesis blob used by the Libra blockchain
 fn generate_genesis_blob() -> Vec<u8> {
-    let (_, consensus_config, network_config) = ConfigHelpers::gen_validator_nodes(10, None);
     let genesis_txn: libra_types::proto::types::SignedTransaction =
-        encode_genesis_transaction_with_validator(
-            &GENESIS_KEYPAIR.0,
-            GENESIS_KEYPAIR.1.clone(),
-            consensus_config.get_validator_set(&network_config),
-        )
-        .into();
+        encode_default_genesis_transaction().into();
 
     genesis_txn
         .to_vec()
@@ -10,26 +10,38 @@ use crate::{
     counters::*,
     data_cache::BlockDataCache,
     process_txn::{execute::ExecutedTransaction, validate::ValidationMode, ProcessTransaction},
+    speculative_executor::execute_speculatively,
+    tracer::TraceSink,
 };
 use config::config::VMPublishingOption;
 use libra_logger::prelude::*;
 use libra_types::{
     transaction::{
-        SignatureCheckedTransaction, SignedTransaction, TransactionOutput, TransactionStatus,
+        SignatureCheckedTransaction, SignedTransaction, TransactionOutput, TransactionPayload,
+        TransactionStatus,
     },
     vm_error::{StatusCode, VMStatus},
     write_set::WriteSet,
 };
 use rayon::prelude::*;
 use state_view::StateView;
+use std::collections::BTreeSet;
+use vm::gas_schedule::{gas_schedule_access_path, GasSchedule, DEFAULT_GAS_SCHEDULE};
 use vm_cache_map::Arena;
 
+/// Executes a block of transactions in order.
+///
+/// If `concurrency_level` is greater than 1, the transactions are first executed speculatively
+/// on that many threads, and the speculative outputs that can't have been affected by earlier
+/// transactions in the block are kept. The outputs are the same as if the block had been executed
+/// sequentially.
 pub fn execute_block<'alloc>(
     txn_block: Vec<SignedTransaction>,
     code_cache: &VMModuleCache<'alloc>,
     script_cache: &ScriptCache<'alloc>,
     data_view: &dyn StateView,
     publishing_option: &VMPublishingOption,
+    concurrency_level: usize,
 ) -> Vec<TransactionOutput> {
     trace!("[VM] Execute block, transaction count: {}", txn_block.len());
     report_block_count(txn_block.len());
@@ -56,7 +68,12 @@ pub fn execute_block<'alloc>(
         ValidationMode::Executing
     };
 
-    let module_cache = BlockModuleCache::new(code_cache, ModuleFetcherImpl::new(data_view));
+    // The gas schedule is read once per block, so an update only takes effect from the next block.
+    let gas_schedule = load_gas_schedule(data_view);
+    // The modules published in the block are allocated in `block_arena`, which is freed with it.
+    let block_arena = Arena::new();
+    let module_cache =
+        BlockModuleCache::new(code_cache, &block_arena, ModuleFetcherImpl::new(data_view));
     let mut data_cache = BlockDataCache::new(data_view);
     let mut result = vec![];
 
@@ -68,21 +85,52 @@ pub fn execute_block<'alloc>(
         })
         .collect();
 
-    for transaction in signature_verified_block {
+    // Modules published by a transaction are visible to the following ones through the module
+    // cache rather than the data cache, so a block publishing modules is executed sequentially.
+    let mut speculative_outputs = if concurrency_level > 1
+        && mode == ValidationMode::Executing
+        && !publishes_modules(&signature_verified_block)
+    {
+        execute_speculatively(
+            &signature_verified_block,
+            code_cache,
+            script_cache,
+            data_view,
+            &gas_schedule,
+            publishing_option,
+            concurrency_level,
+        )
+    } else {
+        vec![]
+    };
+    // The access paths written so far in the block.
+    let mut write_set_paths = BTreeSet::new();
+
+    for (idx, transaction) in signature_verified_block.into_iter().enumerate() {
         record_stats! {time_hist | TXN_TOTAL_TIME_TAKEN | {
-                let output = match transaction {
-                    Ok(t) => transaction_flow(
+                // The speculative output is only kept if nothing the transaction read was written
+                // earlier in the block, as executing it again would then produce the same output.
+                let speculative_output = speculative_outputs
+                    .get_mut(idx)
+                    .and_then(Option::take)
+                    .filter(|speculative| speculative.read_set.is_disjoint(&write_set_paths));
+                let output = match (transaction, speculative_output) {
+                    (Ok(_), Some(speculative)) => speculative.output,
+                    (Ok(t), _) => transaction_flow(
                         t,
                         &module_cache,
                         script_cache,
                         &data_cache,
+                        &gas_schedule,
                         mode,
                         publishing_option,
+                        None,
                     ),
-                    Err(vm_status) => ExecutedTransaction::discard_error_output(vm_status),
+                    (Err(vm_status), _) => ExecutedTransaction::discard_error_output(vm_status),
                 };
                 report_execution_status(output.status());
                 data_cache.push_write_set(&output.write_set());
+                write_set_paths.extend(output.write_set().iter().map(|(ap, _)| ap.clone()));
 
                 // `result` is initally empty, a single element is pushed per loop iteration and
                 // the number of iterations is bound to the max size of `signature_verified_block`
@@ -96,6 +144,80 @@ pub fn execute_block<'alloc>(
     result
 }
 
+/// Executes a single transaction like `execute_block`, recording every instruction executed to
+/// `trace_sink`.
+pub fn execute_transaction_with_trace<'alloc>(
+    txn: SignedTransaction,
+    code_cache: &VMModuleCache<'alloc>,
+    script_cache: &ScriptCache<'alloc>,
+    data_view: &dyn StateView,
+    publishing_option: &VMPublishingOption,
+    trace_sink: &mut dyn TraceSink,
+) -> TransactionOutput {
+    let txn = match txn.check_signature() {
+        Ok(txn) => txn,
+        Err(_) => {
+            return ExecutedTransaction::discard_error_output(VMStatus::new(
+                StatusCode::INVALID_SIGNATURE,
+            ))
+        }
+    };
+    let mode = if data_view.is_genesis() {
+        ValidationMode::Genesis
+    } else {
+        ValidationMode::Executing
+    };
+
+    let gas_schedule = load_gas_schedule(data_view);
+    let block_arena = Arena::new();
+    let module_cache =
+        BlockModuleCache::new(code_cache, &block_arena, ModuleFetcherImpl::new(data_view));
+    let data_cache = BlockDataCache::new(data_view);
+    transaction_flow(
+        txn,
+        &module_cache,
+        script_cache,
+        &data_cache,
+        &gas_schedule,
+        mode,
+        publishing_option,
+        Some(trace_sink),
+    )
+}
+
+/// Returns whether any transaction in the block publishes a module.
+fn publishes_modules(txn_block: &[Result<SignatureCheckedTransaction, VMStatus>]) -> bool {
+    txn_block.iter().any(|txn| match txn {
+        Ok(txn) => match txn.payload() {
+            TransactionPayload::Module(_) => true,
+            TransactionPayload::Program(program) => !program.modules().is_empty(),
+            _ => false,
+        },
+        Err(_) => false,
+    })
+}
+
+/// Load the gas schedule published on chain under the association account.
+///
+/// Falls back to `DEFAULT_GAS_SCHEDULE` if no schedule has been published yet (e.g. before
+/// genesis), or if the published schedule can't be read or fails validation.
+pub(crate) fn load_gas_schedule(data_view: &dyn StateView) -> GasSchedule {
+    match data_view.get(&gas_schedule_access_path()) {
+        Ok(Some(blob)) => match GasSchedule::from_resource_blob(&blob) {
+            Ok(gas_schedule) => gas_schedule,
+            Err(err) => {
+                crit!("[VM] Malformed or invalid on-chain gas schedule: {:?}", err);
+                DEFAULT_GAS_SCHEDULE.clone()
+            }
+        },
+        Ok(None) => DEFAULT_GAS_SCHEDULE.clone(),
+        Err(err) => {
+            crit!("[VM] Error getting gas schedule from storage: {:?}", err);
+            DEFAULT_GAS_SCHEDULE.clone()
+        }
+    }
+}
+
 /// Process a transaction and emit a TransactionOutput.
 ///
 /// A successful execution will have `TransactionStatus::Keep` in the TransactionOutput and a
@@ -109,19 +231,26 @@ pub fn execute_block<'alloc>(
 /// and this transaction is executed successfully, this function will update `module_cache` to
 /// include those newly published modules. This function will also update the `script_cache` to
 /// cache this `txn`
-fn transaction_flow<'alloc, P>(
+pub(crate) fn transaction_flow<'alloc, 'blk, P>(
     txn: SignatureCheckedTransaction,
     module_cache: P,
     script_cache: &ScriptCache<'alloc>,
     data_cache: &BlockDataCache<'_>,
+    gas_schedule: &GasSchedule,
     mode: ValidationMode,
     publishing_option: &VMPublishingOption,
+    trace_sink: Option<&mut dyn TraceSink>,
 ) -> TransactionOutput
 where
-    P: ModuleCache<'alloc>,
+    'alloc: 'blk,
+    P: ModuleCache<'blk>,
 {
     let arena = Arena::new();
-    let process_txn = ProcessTransaction::new(txn, &module_cache, data_cache, &arena);
+    let mut process_txn =
+        ProcessTransaction::new(txn, &module_cache, data_cache, gas_schedule, &arena);
+    if let Some(trace_sink) = trace_sink {
+        process_txn = process_txn.with_trace_sink(trace_sink);
+    }
 
     let validated_txn = record_stats! {time_hist | TXN_VALIDATION_TIME_TAKEN | {
     match process_txn.validate(mode, publishing_option) {
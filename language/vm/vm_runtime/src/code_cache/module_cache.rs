@@ -11,17 +11,18 @@ use crate::{
     },
 };
 use bytecode_verifier::VerifiedModule;
+use crypto::HashValue;
 use libra_types::{
     language_storage::ModuleId,
     vm_error::{StatusCode, VMStatus},
 };
-use std::marker::PhantomData;
+use std::{collections::HashMap, marker::PhantomData, sync::RwLock};
 use vm::{
     access::ModuleAccess,
     errors::*,
     file_format::{
-        FunctionHandleIndex, SignatureToken, StructDefinitionIndex, StructFieldInformation,
-        StructHandleIndex,
+        CompiledModule, FunctionHandleIndex, SignatureToken, StructDefinitionIndex,
+        StructFieldInformation, StructHandleIndex,
     },
     views::{FunctionHandleView, StructHandleView},
 };
@@ -81,6 +82,9 @@ pub trait ModuleCache<'alloc> {
 
     /// Recache the list of previously resolved modules. Think of the cache as a generational
     /// cache and we need to move modules across generations.
+    ///
+    /// For a `BlockModuleCache`, a module replaces the cached module with the same id, if any, so
+    /// that an upgraded module is reloaded by the transactions that follow in the block.
     fn reclaim_cached_module(&self, v: Vec<LoadedModule>);
 }
 
@@ -119,91 +123,25 @@ where
     }
 }
 
-/// Cache for modules that resides in a VM. It is an internally mutable map from module
-/// identifier to a reference to loaded module, where the actual module is owned by the Arena
-/// allocator so that it will guarantee to outlive the lifetime of the transaction.
-pub struct VMModuleCache<'alloc> {
-    map: CacheRefMap<'alloc, ModuleId, LoadedModule>,
-}
-
-impl<'alloc> VMModuleCache<'alloc> {
-    /// In order
-    /// to get a cleaner lifetime, the loaded program trait will take an input parameter of Arena
-    /// allocator to store so that every allocated element in the loaded program can have the same
-    /// lifetime.
-    pub fn new(allocator: &'alloc Arena<LoadedModule>) -> Self {
-        VMModuleCache {
-            map: CacheRefMap::new(allocator),
-        }
-    }
+/// Resolves the module ids a module links against into loaded modules. The caches share the
+/// resolution of functions and structs through this trait, so that the modules a module depends on
+/// are looked up the same way as the module itself.
+trait ModuleLoader<'alloc> {
+    /// Resolve a ModuleId into a LoadedModule, loading it if needed.
+    fn load_module(&self, id: &ModuleId) -> VMResult<Option<&'alloc LoadedModule>>;
 
-    /// Resolve a ModuleId into a LoadedModule. If there is a cache miss, try to fetch the module
-    /// from the `fetcher` and insert it into the cache if found. If nothing is found, it will
-    /// return Ok(None).
-    pub fn get_loaded_module_with_fetcher<F: ModuleFetcher>(
-        &self,
-        id: &ModuleId,
-        fetcher: &F,
-    ) -> VMResult<Option<&'alloc LoadedModule>> {
-        // Currently it is still possible for a script to invoke a nonsense module id function.
-        // However, once we have the verifier that checks the well-formedness of the all the linked
-        // module id, we should get rid of that ok_or_else case here.
-        if let Some(m) = self.map.get(id) {
-            return Ok(Some(&*m));
-        }
-        let module = match fetcher.get_module(id) {
-            Some(module) => module,
-            None => return Ok(None),
-        };
-
-        // Verify the module before using it.
-        let module = match VerifiedModule::new(module) {
-            Ok(module) => module,
-            Err((_, mut errors)) => {
-                // If there are errors there should be at least one otherwise there's an internal
-                // error in the verifier. We only give back the first error. If the user wants to
-                // debug things, they can do that offline.
-                let error = if errors.is_empty() {
-                    VMStatus::new(StatusCode::VERIFIER_INVARIANT_VIOLATION)
-                } else {
-                    errors.remove(0)
-                };
-                return Err(error);
-            }
-        };
-
-        let loaded_module = LoadedModule::new(module);
-        Ok(Some(self.map.or_insert(id.clone(), loaded_module)))
-    }
-
-    #[cfg(test)]
-    pub fn new_from_module(
-        module: VerifiedModule,
-        allocator: &'alloc Arena<LoadedModule>,
-    ) -> VMResult<Self> {
-        let module_id = module.self_id();
-        let map = CacheRefMap::new(allocator);
-        let loaded_module = LoadedModule::new(module);
-        map.or_insert(module_id, loaded_module);
-        Ok(VMModuleCache { map })
-    }
-
-    /// Resolve a FunctionHandleIndex into a FunctionRef in either the cache or the `fetcher`.
-    /// An Ok(None) will be returned if no such function is found.
-    pub fn resolve_function_ref_with_fetcher<F>(
+    /// Resolve a FunctionHandleIndex into a FunctionRef. An Ok(None) will be returned if no such
+    /// function is found.
+    fn resolve_function_handle(
         &self,
         caller_module: &LoadedModule,
         idx: FunctionHandleIndex,
-        fetcher: &F,
-    ) -> VMResult<Option<FunctionRef<'alloc>>>
-    where
-        F: ModuleFetcher,
-    {
+    ) -> VMResult<Option<FunctionRef<'alloc>>> {
         let function_handle = caller_module.function_handle_at(idx);
         let callee_name = caller_module.identifier_at(function_handle.name);
         let callee_module_id = FunctionHandleView::new(caller_module, function_handle).module_id();
 
-        match self.get_loaded_module_with_fetcher(&callee_module_id, fetcher) {
+        match self.load_module(&callee_module_id) {
             Ok(Some(callee_module)) => {
                 let callee_func_id = callee_module
                     .function_defs_table
@@ -216,42 +154,42 @@ impl<'alloc> VMModuleCache<'alloc> {
         }
     }
 
-    /// Resolve a StructHandle into a StructDef recursively in either the cache or the `fetcher`.
-    pub fn resolve_struct_handle_with_fetcher<F: ModuleFetcher>(
+    /// Resolve a StructHandle into a StructDef recursively.
+    fn resolve_struct_handle(
         &self,
         module: &LoadedModule,
         idx: StructHandleIndex,
         gas_meter: &GasMeter,
-        fetcher: &F,
     ) -> VMResult<Option<StructDef>> {
         let struct_handle = module.struct_handle_at(idx);
         let struct_name = module.identifier_at(struct_handle.name);
         let struct_def_module_id = StructHandleView::new(module, struct_handle).module_id();
-        match self.get_loaded_module_with_fetcher(&struct_def_module_id, fetcher) {
+        match self.load_module(&struct_def_module_id) {
             Ok(Some(module)) => {
                 let struct_def_idx = module
                     .struct_defs_table
                     .get(struct_name)
                     .ok_or_else(|| VMStatus::new(StatusCode::LINKER_ERROR))?;
-                self.resolve_struct_def_with_fetcher(module, *struct_def_idx, gas_meter, fetcher)
+                self.resolve_struct_definition(module, *struct_def_idx, gas_meter)
             }
             Ok(None) => Ok(None),
             Err(errors) => Err(errors),
         }
     }
 
-    /// Resolve a SignatureToken into a Type recursively in either the cache or the `fetcher`.
-    pub fn resolve_signature_token_with_fetcher<'txn, F: ModuleFetcher>(
-        &'txn self,
+    /// Resolve a SignatureToken into a Type recursively.
+    fn resolve_signature_token(
+        &self,
         module: &LoadedModule,
         tok: &SignatureToken,
         type_context: &TypeContext,
         gas_meter: &GasMeter,
-        fetcher: &F,
     ) -> VMResult<Option<Type>> {
         match tok {
             SignatureToken::Bool => Ok(Some(Type::Bool)),
+            SignatureToken::U8 => Ok(Some(Type::U8)),
             SignatureToken::U64 => Ok(Some(Type::U64)),
+            SignatureToken::U128 => Ok(Some(Type::U128)),
             SignatureToken::String => Ok(Some(Type::String)),
             SignatureToken::ByteArray => Ok(Some(Type::ByteArray)),
             SignatureToken::Address => Ok(Some(Type::Address)),
@@ -260,13 +198,8 @@ impl<'alloc> VMModuleCache<'alloc> {
                 let ctx = {
                     let mut ctx = vec![];
                     for ty in tys.iter() {
-                        let resolved_type = self.resolve_signature_token_with_fetcher(
-                            module,
-                            ty,
-                            type_context,
-                            gas_meter,
-                            fetcher,
-                        )?;
+                        let resolved_type =
+                            self.resolve_signature_token(module, ty, type_context, gas_meter)?;
                         if let Some(t) = resolved_type {
                             ctx.push(t);
                         } else {
@@ -276,42 +209,30 @@ impl<'alloc> VMModuleCache<'alloc> {
                     TypeContext::new(ctx)
                 };
                 let struct_def = self
-                    .resolve_struct_handle_with_fetcher(module, *sh_idx, gas_meter, fetcher)?
+                    .resolve_struct_handle(module, *sh_idx, gas_meter)?
                     .map(|def| ctx.subst_struct_def(&def))
                     .transpose()?;
                 Ok(struct_def.map(Type::Struct))
             }
             SignatureToken::Reference(sub_tok) => {
-                let inner_ty = self.resolve_signature_token_with_fetcher(
-                    module,
-                    sub_tok,
-                    type_context,
-                    gas_meter,
-                    fetcher,
-                )?;
+                let inner_ty =
+                    self.resolve_signature_token(module, sub_tok, type_context, gas_meter)?;
                 Ok(inner_ty.map(|t| Type::Reference(Box::new(t))))
             }
             SignatureToken::MutableReference(sub_tok) => {
-                let inner_ty = self.resolve_signature_token_with_fetcher(
-                    module,
-                    sub_tok,
-                    type_context,
-                    gas_meter,
-                    fetcher,
-                )?;
+                let inner_ty =
+                    self.resolve_signature_token(module, sub_tok, type_context, gas_meter)?;
                 Ok(inner_ty.map(|t| Type::MutableReference(Box::new(t))))
             }
         }
     }
 
-    /// Resolve a StructDefinition into a StructDef recursively in either the cache or the
-    /// `fetcher`.
-    pub fn resolve_struct_def_with_fetcher<'txn, F: ModuleFetcher>(
-        &'txn self,
+    /// Resolve a StructDefinition into a StructDef recursively.
+    fn resolve_struct_definition(
+        &self,
         module: &LoadedModule,
         idx: StructDefinitionIndex,
         gas_meter: &GasMeter,
-        fetcher: &F,
     ) -> VMResult<Option<StructDef>> {
         if let Some(def) = module.cached_struct_def_at(idx) {
             return Ok(Some(def));
@@ -340,12 +261,11 @@ impl<'alloc> VMModuleCache<'alloc> {
                 } => {
                     let mut field_types = vec![];
                     for field in module.field_def_range(*field_count, *fields) {
-                        let ty = self.resolve_signature_token_with_fetcher(
+                        let ty = self.resolve_signature_token(
                             module,
                             &module.type_signature_at(field.signature).0,
                             &type_context,
                             gas_meter,
-                            fetcher,
                         )?;
                         if let Some(t) = ty {
                             // `field_types` is initally empty, a single element is pushed
@@ -371,13 +291,123 @@ impl<'alloc> VMModuleCache<'alloc> {
     }
 }
 
+/// Cache for modules that resides in a VM. It is an internally mutable map from module
+/// identifier to a reference to loaded module, where the actual module is owned by the Arena
+/// allocator so that it will guarantee to outlive the lifetime of the transaction.
+pub struct VMModuleCache<'alloc> {
+    map: CacheRefMap<'alloc, ModuleId, LoadedModule>,
+    /// The versions of the modules loaded from storage, keyed by the hash of the serialized
+    /// module, so that every version of an upgraded module is only verified once.
+    versions: CacheRefMap<'alloc, HashValue, LoadedModule>,
+}
+
+impl<'alloc> VMModuleCache<'alloc> {
+    /// In order
+    /// to get a cleaner lifetime, the loaded program trait will take an input parameter of Arena
+    /// allocator to store so that every allocated element in the loaded program can have the same
+    /// lifetime.
+    pub fn new(allocator: &'alloc Arena<LoadedModule>) -> Self {
+        VMModuleCache {
+            map: CacheRefMap::new(allocator),
+            versions: CacheRefMap::new(allocator),
+        }
+    }
+
+    /// Resolve a module fetched from storage into a LoadedModule, verifying it unless the same
+    /// version was loaded before. The first version loaded is also the one resolved by its id.
+    fn get_loaded_module_version(&self, module: CompiledModule) -> VMResult<&'alloc LoadedModule> {
+        let mut bytes = vec![];
+        module
+            .serialize(&mut bytes)
+            .map_err(|_| VMStatus::new(StatusCode::UNKNOWN_INVARIANT_VIOLATION_ERROR))?;
+        let hash = HashValue::from_sha3_256(&bytes);
+        if let Some(m) = self.versions.get(&hash) {
+            return Ok(m);
+        }
+        let m = self.versions.or_insert(hash, load_verified_module(module)?);
+        self.map.or_insert_ref(m.self_id(), m);
+        Ok(m)
+    }
+
+    /// Resolve a ModuleId into a LoadedModule. If there is a cache miss, try to fetch the module
+    /// from the `fetcher` and insert it into the cache if found. If nothing is found, it will
+    /// return Ok(None).
+    pub fn get_loaded_module_with_fetcher<F: ModuleFetcher>(
+        &self,
+        id: &ModuleId,
+        fetcher: &F,
+    ) -> VMResult<Option<&'alloc LoadedModule>> {
+        // Currently it is still possible for a script to invoke a nonsense module id function.
+        // However, once we have the verifier that checks the well-formedness of the all the linked
+        // module id, we should get rid of that ok_or_else case here.
+        if let Some(m) = self.map.get(id) {
+            return Ok(Some(&*m));
+        }
+        let module = match fetcher.get_module(id) {
+            Some(module) => module,
+            None => return Ok(None),
+        };
+        self.get_loaded_module_version(module).map(Some)
+    }
+
+    #[cfg(test)]
+    pub fn new_from_module(
+        module: VerifiedModule,
+        allocator: &'alloc Arena<LoadedModule>,
+    ) -> VMResult<Self> {
+        let module_id = module.self_id();
+        let map = CacheRefMap::new(allocator);
+        let loaded_module = LoadedModule::new(module);
+        map.or_insert(module_id, loaded_module);
+        Ok(VMModuleCache {
+            map,
+            versions: CacheRefMap::new(allocator),
+        })
+    }
+}
+
+/// Verifies a module fetched from storage and loads it.
+fn load_verified_module(module: CompiledModule) -> VMResult<LoadedModule> {
+    let module = match VerifiedModule::new(module) {
+        Ok(module) => module,
+        Err((_, mut errors)) => {
+            // If there are errors there should be at least one otherwise there's an internal
+            // error in the verifier. We only give back the first error. If the user wants to
+            // debug things, they can do that offline.
+            let error = if errors.is_empty() {
+                VMStatus::new(StatusCode::VERIFIER_INVARIANT_VIOLATION)
+            } else {
+                errors.remove(0)
+            };
+            return Err(error);
+        }
+    };
+    Ok(LoadedModule::new(module))
+}
+
+/// A `ModuleLoader` that looks up modules in `cache`, fetching the missing ones from `fetcher`.
+struct FetchingLoader<'a, 'alloc, F> {
+    cache: &'a VMModuleCache<'alloc>,
+    fetcher: &'a F,
+}
+
+impl<'a, 'alloc, F: ModuleFetcher> ModuleLoader<'alloc> for FetchingLoader<'a, 'alloc, F> {
+    fn load_module(&self, id: &ModuleId) -> VMResult<Option<&'alloc LoadedModule>> {
+        self.cache.get_loaded_module_with_fetcher(id, self.fetcher)
+    }
+}
+
 impl<'alloc> ModuleCache<'alloc> for VMModuleCache<'alloc> {
     fn resolve_function_ref(
         &self,
         caller_module: &LoadedModule,
         idx: FunctionHandleIndex,
     ) -> VMResult<Option<FunctionRef<'alloc>>> {
-        self.resolve_function_ref_with_fetcher(caller_module, idx, &NullFetcher())
+        let loader = FetchingLoader {
+            cache: self,
+            fetcher: &NullFetcher(),
+        };
+        loader.resolve_function_handle(caller_module, idx)
     }
 
     fn resolve_struct_def(
@@ -386,7 +416,11 @@ impl<'alloc> ModuleCache<'alloc> for VMModuleCache<'alloc> {
         idx: StructDefinitionIndex,
         gas_meter: &GasMeter,
     ) -> VMResult<Option<StructDef>> {
-        self.resolve_struct_def_with_fetcher(module, idx, gas_meter, &NullFetcher())
+        let loader = FetchingLoader {
+            cache: self,
+            fetcher: &NullFetcher(),
+        };
+        loader.resolve_struct_definition(module, idx, gas_meter)
     }
 
     fn get_loaded_module(&self, id: &ModuleId) -> VMResult<Option<&'alloc LoadedModule>> {
@@ -416,12 +450,21 @@ impl<'alloc> ModuleCache<'alloc> for VMModuleCache<'alloc> {
 /// cache yet. In production, it will usually provide a connection to the StateStore client to fetch
 /// the needed data. `alloc` is the lifetime for the entire VM and `blk` is the lifetime for the
 /// current block we are executing.
+///
+/// `vm_cache` outlives the block and is shared with the blocks of other forks, so the modules
+/// published or upgraded in the block are kept in `block_cache` instead, which is freed with the
+/// block. For the same reason, a module is fetched from `storage` the first time it's used in the
+/// block, and the version found there is looked up in `vm_cache` by hash. Once an upgrade is
+/// committed, it's verified by the first block using it and shared with the following ones.
 pub struct BlockModuleCache<'alloc, 'blk, F>
 where
     'alloc: 'blk,
     F: ModuleFetcher,
 {
     vm_cache: &'blk VMModuleCache<'alloc>,
+    block_cache: VMModuleCache<'blk>,
+    /// The versions of the modules of `vm_cache` found in `storage` for this block.
+    loaded_modules: RwLock<HashMap<ModuleId, &'blk LoadedModule>>,
     storage: F,
 }
 
@@ -430,9 +473,15 @@ where
     'alloc: 'blk,
     F: ModuleFetcher,
 {
-    pub fn new(vm_cache: &'blk VMModuleCache<'alloc>, module_fetcher: F) -> Self {
+    pub fn new(
+        vm_cache: &'blk VMModuleCache<'alloc>,
+        allocator: &'blk Arena<LoadedModule>,
+        module_fetcher: F,
+    ) -> Self {
         BlockModuleCache {
             vm_cache,
+            block_cache: VMModuleCache::new(allocator),
+            loaded_modules: RwLock::new(HashMap::new()),
             storage: module_fetcher,
         }
     }
@@ -445,14 +494,36 @@ impl<'alloc, 'blk> BlockModuleCache<'alloc, 'blk, FakeFetcher> {
     }
 }
 
-impl<'alloc, 'blk, F: ModuleFetcher> ModuleCache<'alloc> for BlockModuleCache<'alloc, 'blk, F> {
+impl<'alloc, 'blk, F: ModuleFetcher> ModuleLoader<'blk> for BlockModuleCache<'alloc, 'blk, F> {
+    fn load_module(&self, id: &ModuleId) -> VMResult<Option<&'blk LoadedModule>> {
+        if let Some(m) = self.block_cache.map.get(id) {
+            return Ok(Some(m));
+        }
+        if let Some(m) = self.loaded_modules.read().expect("lock poisoned").get(id) {
+            return Ok(Some(*m));
+        }
+
+        // The module is fetched even if it's cached, as it may have been upgraded since.
+        let module = match self.storage.get_module(id) {
+            Some(module) => module,
+            None => return Ok(None),
+        };
+        let m = self.vm_cache.get_loaded_module_version(module)?;
+        self.loaded_modules
+            .write()
+            .expect("lock poisoned")
+            .insert(id.clone(), m);
+        Ok(Some(m))
+    }
+}
+
+impl<'alloc, 'blk, F: ModuleFetcher> ModuleCache<'blk> for BlockModuleCache<'alloc, 'blk, F> {
     fn resolve_function_ref(
         &self,
         caller_module: &LoadedModule,
         idx: FunctionHandleIndex,
-    ) -> VMResult<Option<FunctionRef<'alloc>>> {
-        self.vm_cache
-            .resolve_function_ref_with_fetcher(caller_module, idx, &self.storage)
+    ) -> VMResult<Option<FunctionRef<'blk>>> {
+        self.resolve_function_handle(caller_module, idx)
     }
 
     fn resolve_struct_def(
@@ -461,21 +532,26 @@ impl<'alloc, 'blk, F: ModuleFetcher> ModuleCache<'alloc> for BlockModuleCache<'a
         idx: StructDefinitionIndex,
         gas_meter: &GasMeter,
     ) -> VMResult<Option<StructDef>> {
-        self.vm_cache
-            .resolve_struct_def_with_fetcher(module, idx, gas_meter, &self.storage)
+        self.resolve_struct_definition(module, idx, gas_meter)
     }
 
-    fn get_loaded_module(&self, id: &ModuleId) -> VMResult<Option<&'alloc LoadedModule>> {
-        self.vm_cache
-            .get_loaded_module_with_fetcher(id, &self.storage)
+    fn get_loaded_module(&self, id: &ModuleId) -> VMResult<Option<&'blk LoadedModule>> {
+        self.load_module(id)
     }
 
     fn cache_module(&self, module: VerifiedModule) {
-        self.vm_cache.cache_module(module)
+        self.block_cache.cache_module(module)
     }
 
     fn reclaim_cached_module(&self, v: Vec<LoadedModule>) {
-        self.vm_cache.reclaim_cached_module(v)
+        for m in v.into_iter() {
+            let module_id = m.self_id();
+            // A module upgraded again replaces the version published earlier in the block. The
+            // previous version stays allocated until the end of the block, so the references to it
+            // that were handed out are still valid.
+            self.block_cache.map.remove(&module_id);
+            self.block_cache.map.or_insert(module_id, m);
+        }
     }
 }
 
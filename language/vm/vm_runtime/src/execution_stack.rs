@@ -7,7 +7,10 @@ use crate::{
     loaded_data::function::{FunctionRef, FunctionReference},
     IndexKind,
 };
-use libra_types::vm_error::{StatusCode, VMStatus};
+use libra_types::{
+    language_storage::TypeTag,
+    vm_error::{StatusCode, VMStatus},
+};
 use std::{fmt, marker::PhantomData};
 use vm::errors::*;
 use vm_runtime_types::value::{Locals, Value};
@@ -43,14 +46,19 @@ where
         }
     }
 
-    pub fn push_call(&mut self, function: FunctionRef<'txn>) -> VMResult<()> {
+    pub fn push_call(
+        &mut self,
+        function: FunctionRef<'txn>,
+        type_actuals: Vec<TypeTag>,
+    ) -> VMResult<()> {
         let mut locals = Locals::new(function.local_count());
         let arg_count = function.arg_count();
         for i in 0..arg_count {
             locals.store_loc(arg_count - i - 1, self.pop()?)?;
         }
         if self.function_stack.len() < (FUNCTION_STACK_SIZE_LIMIT as usize) {
-            self.function_stack.push(Frame::new(function, locals));
+            self.function_stack
+                .push(Frame::new(function, type_actuals, locals));
             Ok(())
         } else {
             Err(vm_error(self.location()?, StatusCode::CALL_STACK_OVERFLOW))
@@ -171,7 +179,7 @@ where
         if self.function_stack.len() < (FUNCTION_STACK_SIZE_LIMIT as usize) {
             let count = func.local_count();
             self.function_stack
-                .push(Frame::new(func, Locals::new(count)));
+                .push(Frame::new(func, vec![], Locals::new(count)));
             Ok(())
         } else {
             Err(vm_error(self.location()?, StatusCode::CALL_STACK_OVERFLOW))
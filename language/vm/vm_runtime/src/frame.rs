@@ -2,6 +2,7 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use crate::loaded_data::{function::FunctionReference, loaded_module::LoadedModule};
+use libra_types::language_storage::TypeTag;
 use std::{fmt, marker::PhantomData};
 use vm::{
     errors::{Location, VMResult},
@@ -13,6 +14,7 @@ pub struct Frame<'txn, F: 'txn> {
     pc: u16,
     locals: Locals,
     function: F,
+    type_actuals: Vec<TypeTag>,
     phantom: PhantomData<&'txn F>,
 }
 
@@ -20,11 +22,12 @@ impl<'txn, F> Frame<'txn, F>
 where
     F: FunctionReference<'txn>,
 {
-    pub fn new(function: F, locals: Locals) -> Self {
+    pub fn new(function: F, type_actuals: Vec<TypeTag>, locals: Locals) -> Self {
         Frame {
             pc: 0,
             locals,
             function,
+            type_actuals,
             phantom: PhantomData,
         }
     }
@@ -45,6 +48,19 @@ where
         self.function.module()
     }
 
+    pub fn function(&self) -> &F {
+        &self.function
+    }
+
+    /// The types the type parameters of the function are instantiated with.
+    pub fn type_actuals(&self) -> &[TypeTag] {
+        &self.type_actuals
+    }
+
+    pub fn get_locals(&self) -> &Locals {
+        &self.locals
+    }
+
     pub fn copy_loc(&self, idx: LocalIndex) -> VMResult<Value> {
         self.locals.copy_loc(idx as usize)
     }
@@ -88,8 +104,4 @@ where
         self.pc = pc;
         self.locals = locals;
     }
-
-    pub fn get_locals(&self) -> &Locals {
-        &self.locals
-    }
 }
@@ -18,10 +18,13 @@ use vm::{
 };
 
 /// Holds the state of the gas meter.
-pub struct GasMeter {
+pub struct GasMeter<'a> {
     // The current amount of gas that is left ("unburnt gas") in the gas meter.
     current_gas_left: GasUnits<GasCarrier>,
 
+    // The gas schedule that the instructions are charged with.
+    gas_schedule: &'a GasSchedule,
+
     // We need to disable and enable gas metering for both the prologue and epilogue of the Account
     // contract. The VM will then internally unset/set this flag before executing either of them.
     meter_on: bool,
@@ -30,15 +33,22 @@ pub struct GasMeter {
 // NB: A number of the functions/methods in this struct will return a VMResult<T>
 // since we will need to access stack and memory states, and we need to be able
 // to report errors properly from these accesses.
-impl GasMeter {
-    /// Create a new gas meter with starting gas amount `gas_amount`
-    pub fn new(gas_amount: GasUnits<GasCarrier>) -> Self {
+impl<'a> GasMeter<'a> {
+    /// Create a new gas meter with starting gas amount `gas_amount`, charging the instructions
+    /// according to `gas_schedule`.
+    pub fn new(gas_amount: GasUnits<GasCarrier>, gas_schedule: &'a GasSchedule) -> Self {
         GasMeter {
             current_gas_left: gas_amount,
+            gas_schedule,
             meter_on: true,
         }
     }
 
+    /// The gas schedule used by the gas meter.
+    pub fn gas_schedule(&self) -> &'a GasSchedule {
+        self.gas_schedule
+    }
+
     /// Charges additional gas for the transaction based upon the total size (in bytes) of the
     /// submitted transaction. It is important that we charge for the transaction size since a
     /// transaction can contain arbitrary amounts of bytes in the `note` field. We also want to
@@ -54,7 +64,7 @@ impl GasMeter {
         P: ModuleCache<'alloc>,
     {
         precondition!(transaction_size.get() <= (MAX_TRANSACTION_SIZE_IN_BYTES as u64));
-        let cost = calculate_intrinsic_gas(transaction_size);
+        let cost = self.gas_schedule.calculate_intrinsic_gas(transaction_size);
         self.consume_gas(cost, stk)
     }
 
@@ -132,7 +142,12 @@ impl GasMeter {
             | Bytecode::Le
             | Bytecode::LdTrue
             | Bytecode::LdFalse
+            | Bytecode::LdU8(_)
             | Bytecode::LdConst(_)
+            | Bytecode::LdU128(_)
+            | Bytecode::CastU8
+            | Bytecode::CastU64
+            | Bytecode::CastU128
             | Bytecode::Branch(_)
             | Bytecode::Abort
             | Bytecode::Pop
@@ -157,7 +172,8 @@ impl GasMeter {
             // value stack.  Because of this, the cost of the instruction is not dependent upon the
             // size of the value being returned.
             | Bytecode::Ret => {
-                let default_gas = static_cost_instr(instr, AbstractMemorySize::new(1));
+                let default_gas =
+                    self.gas_schedule.static_cost_instr(instr, AbstractMemorySize::new(1));
                 Self::gas_of(default_gas)
             }
             Bytecode::Eq
@@ -165,18 +181,18 @@ impl GasMeter {
                 let lhs_size = stk.peek()?.size();
                 let rhs_size = stk.peek_at(1)?.size();
                 let max_size = lhs_size.map2(rhs_size, std::cmp::max);
-                Self::gas_of(static_cost_instr(instr, max_size))
+                Self::gas_of(self.gas_schedule.static_cost_instr(instr, max_size))
             }
             Bytecode::LdAddr(_) => {
                 let size = AbstractMemorySize::new(ADDRESS_LENGTH as GasCarrier);
-                let default_gas = static_cost_instr(instr, size);
+                let default_gas = self.gas_schedule.static_cost_instr(instr, size);
                 Self::gas_of(default_gas)
             }
             Bytecode::LdByteArray(idx) => {
                 let byte_array_ref = stk.top_frame()?.module().byte_array_at(*idx);
                 let byte_array_len = AbstractMemorySize::new(byte_array_ref.len() as GasCarrier);
                 let byte_array_len = words_in(byte_array_len);
-                let default_gas = static_cost_instr(instr, byte_array_len);
+                let default_gas = self.gas_schedule.static_cost_instr(instr, byte_array_len);
                 Self::gas_of(default_gas)
             }
             // We charge by the length of the string being stored on the stack.
@@ -184,7 +200,7 @@ impl GasMeter {
                 let string_ref = stk.top_frame()?.module().user_string_at(*idx);
                 let str_len = AbstractMemorySize::new(string_ref.len() as GasCarrier);
                 let str_len = words_in(str_len);
-                let default_gas = static_cost_instr(instr, str_len);
+                let default_gas = self.gas_schedule.static_cost_instr(instr, str_len);
                 Self::gas_of(default_gas)
             }
             Bytecode::StLoc(_) => {
@@ -192,14 +208,14 @@ impl GasMeter {
                 let local = stk.peek()?;
                 // Get the size of the local
                 let size = local.size();
-                let default_gas = static_cost_instr(instr, size);
+                let default_gas = self.gas_schedule.static_cost_instr(instr, size);
                 Self::gas_of(default_gas)
             }
             // Note that a moveLoc incurs a copy overhead
             Bytecode::CopyLoc(local_idx) | Bytecode::MoveLoc(local_idx) => {
                 let local = stk.top_frame()?.copy_loc(*local_idx)?;
                 let size = local.size();
-                let default_gas = static_cost_instr(instr, size);
+                let default_gas = self.gas_schedule.static_cost_instr(instr, size);
                 Self::gas_of(default_gas)
             }
             Bytecode::Call(call_idx, _) => {
@@ -212,13 +228,13 @@ impl GasMeter {
                     GasUnits::new(0) // This will be costed at the call site/by the native function
                 } else {
                     let call_size = AbstractMemorySize::new(function_ref.arg_count() as GasCarrier);
-                    let call_gas = static_cost_instr(instr, call_size);
+                    let call_gas = self.gas_schedule.static_cost_instr(instr, call_size);
                     Self::gas_of(call_gas)
                 }
             }
             Bytecode::Unpack(_, _) => {
                 let size = stk.peek()?.size();
-                Self::gas_of(static_cost_instr(instr, size))
+                Self::gas_of(self.gas_schedule.static_cost_instr(instr, size))
             }
             Bytecode::Pack(struct_idx, _) => {
                 let struct_def = &stk.top_frame()?.module().struct_def_at(*struct_idx);
@@ -229,7 +245,7 @@ impl GasMeter {
                 let arg_count = AbstractMemorySize::new(u64::from(member_count));
 
                 let total_size = arg_count.add(*STRUCT_SIZE);
-                let new_gas = static_cost_instr(instr, total_size);
+                let new_gas = self.gas_schedule.static_cost_instr(instr, total_size);
                 Self::gas_of(new_gas)
             }
             Bytecode::WriteRef => {
@@ -239,7 +255,7 @@ impl GasMeter {
                 let ref_val = stk.peek()?;
                 // Get the size of this value and charge accordingly.
                 let size = write_val.size();
-                let mut default_gas = static_cost_instr(instr, size);
+                let mut default_gas = self.gas_schedule.static_cost_instr(instr, size);
                 // Determine if the reference is global. If so charge for any expansion of global
                 // memory along with the write operation that will be incurred.
                 if ref_val.is_global_ref() {
@@ -253,9 +269,9 @@ impl GasMeter {
                     };
                     default_gas.memory_gas = default_gas.memory_gas
                         // Charge for the iops on global memory
-                        .add(size.mul(*GLOBAL_MEMORY_PER_BYTE_WRITE_COST))
+                        .add(size.mul(self.gas_schedule.global_memory_per_byte_write_cost))
                         // Charge for any memory expansion
-                        .add(size_difference.mul(*GLOBAL_MEMORY_PER_BYTE_COST));
+                        .add(size_difference.mul(self.gas_schedule.global_memory_per_byte_cost));
                 };
                 Self::gas_of(default_gas)
             }
@@ -264,10 +280,12 @@ impl GasMeter {
                 // from global memory that is performed by a BorrowGlobal operation. After this,
                 // all ReadRefs will be reading from local cache and we don't need to distinguish.
                 let size = stk.peek()?.size();
-                let default_gas = static_cost_instr(instr, size);
+                let default_gas = self.gas_schedule.static_cost_instr(instr, size);
                 Self::gas_of(default_gas)
             }
-            Bytecode::CreateAccount => Self::gas_of(static_cost_instr(instr, *DEFAULT_ACCOUNT_SIZE)),
+            Bytecode::CreateAccount => {
+                Self::gas_of(self.gas_schedule.static_cost_instr(instr, *DEFAULT_ACCOUNT_SIZE))
+            }
             // Note that we charge twice for these operations; once at the start of
             // `execute_single_instruction` we charge once with size 1. This then covers the cost
             // of accessing the value and guards (somewhat) against abusive memory accesses. Once
@@ -294,7 +312,7 @@ impl GasMeter {
                 } else {
                     AbstractMemorySize::new(0) // We already charged for size 1
                 };
-                Self::gas_of(static_cost_instr(instr, mem_size))
+                Self::gas_of(self.gas_schedule.static_cost_instr(instr, mem_size))
             }
         };
         Ok(instruction_reqs)
@@ -5,9 +5,12 @@
 use libra_types::{
     access_path::{AccessPath, Accesses},
     account_address::AccountAddress,
-    language_storage::{ResourceKey, StructTag},
+    language_storage::{ResourceKey, StructTag, TypeTag},
+};
+use vm::{
+    access::ModuleAccess,
+    file_format::{SignatureToken, StructDefinitionIndex, StructHandleIndex},
 };
-use vm::{access::ModuleAccess, file_format::StructDefinitionIndex};
 
 /// Get the StructTag for a StructDefinition defined in a published module.
 pub fn resource_storage_key(module: &impl ModuleAccess, idx: StructDefinitionIndex) -> StructTag {
@@ -25,6 +28,49 @@ pub fn resource_storage_key(module: &impl ModuleAccess, idx: StructDefinitionInd
     }
 }
 
+/// Get the StructTag of the struct `idx` in `module`, instantiated with `type_params`.
+fn struct_tag(
+    module: &impl ModuleAccess,
+    idx: StructHandleIndex,
+    type_params: Vec<TypeTag>,
+) -> StructTag {
+    let struct_handle = module.struct_handle_at(idx);
+    let struct_module = module.module_handle_at(struct_handle.module);
+    StructTag {
+        module: module.identifier_at(struct_module.name).into(),
+        address: *module.address_at(struct_module.address),
+        name: module.identifier_at(struct_handle.name).into(),
+        type_params,
+    }
+}
+
+/// Get the TypeTag for a signature token appearing in `module`, substituting the type parameters
+/// with `type_actuals`. Returns `None` for references and out of bounds type parameters.
+pub fn type_tag_for_token(
+    module: &impl ModuleAccess,
+    token: &SignatureToken,
+    type_actuals: &[TypeTag],
+) -> Option<TypeTag> {
+    Some(match token {
+        SignatureToken::Bool => TypeTag::Bool,
+        SignatureToken::U8 => TypeTag::U8,
+        SignatureToken::U64 => TypeTag::U64,
+        SignatureToken::U128 => TypeTag::U128,
+        SignatureToken::String => TypeTag::String,
+        SignatureToken::ByteArray => TypeTag::ByteArray,
+        SignatureToken::Address => TypeTag::Address,
+        SignatureToken::Struct(idx, tokens) => {
+            let type_params = tokens
+                .iter()
+                .map(|token| type_tag_for_token(module, token, type_actuals))
+                .collect::<Option<_>>()?;
+            TypeTag::Struct(struct_tag(module, *idx, type_params))
+        }
+        SignatureToken::TypeParameter(idx) => type_actuals.get(*idx as usize)?.clone(),
+        SignatureToken::Reference(_) | SignatureToken::MutableReference(_) => return None,
+    })
+}
+
 /// Get the AccessPath to a resource stored under `address` with type name `tag`
 pub fn create_access_path(address: &AccountAddress, tag: StructTag) -> AccessPath {
     let resource_tag = ResourceKey::new(*address, tag);
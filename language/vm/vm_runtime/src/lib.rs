@@ -118,6 +118,7 @@ mod gas_meter;
 mod move_vm;
 mod process_txn;
 mod runtime;
+mod speculative_executor;
 #[cfg(test)]
 mod unit_tests;
 
@@ -125,6 +126,7 @@ pub mod code_cache;
 pub mod data_cache;
 pub mod identifier;
 pub mod loaded_data;
+pub mod tracer;
 pub mod txn_executor;
 
 #[cfg(feature = "instruction_synthesis")]
@@ -2,8 +2,8 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use crate::{
-    counters::*, loaded_data::loaded_module::LoadedModule, runtime::VMRuntime, VMExecutor,
-    VMVerifier,
+    counters::*, loaded_data::loaded_module::LoadedModule, runtime::VMRuntime, tracer::TraceSink,
+    VMExecutor, VMVerifier,
 };
 use libra_types::{
     transaction::{SignedTransaction, Transaction, TransactionOutput},
@@ -43,6 +43,18 @@ impl MoveVM {
             inner: Arc::new(inner),
         }
     }
+
+    /// Executes a single transaction, recording every instruction executed to `trace_sink`.
+    pub fn execute_transaction_with_trace(
+        &self,
+        transaction: SignedTransaction,
+        state_view: &dyn StateView,
+        trace_sink: &mut dyn TraceSink,
+    ) -> TransactionOutput {
+        self.inner.rent(move |runtime| {
+            runtime.execute_transaction_with_trace(transaction, state_view, trace_sink)
+        })
+    }
 }
 
 impl VMVerifier for MoveVM {
@@ -2,15 +2,18 @@ use crate::{
     code_cache::module_cache::ModuleCache,
     process_txn::verify::{VerTxn, VerifiedTransaction, VerifiedTransactionState},
 };
+use bytecode_verifier::{CompatibilityChecker, VerifiedModule};
 use libra_logger::prelude::*;
 use libra_types::{
+    language_storage::ModuleId,
     transaction::{TransactionOutput, TransactionPayload, TransactionStatus},
     vm_error::{StatusCode, StatusType, VMStatus},
     write_set::WriteSet,
 };
+use std::collections::HashSet;
 use vm::{
     access::ModuleAccess,
-    errors::{vm_error, Location},
+    errors::{vm_error, Location, VMResult},
 };
 
 /// Represents a transaction that has been executed.
@@ -43,6 +46,7 @@ where
     P: ModuleCache<'alloc>,
 {
     let txn_state = verified_txn.take_state();
+    let allow_module_upgrades = verified_txn.allow_module_upgrades;
 
     match verified_txn
         .into_inner()
@@ -67,36 +71,17 @@ where
             for (module, raw_bytes) in modules.into_iter().zip(module_bytes) {
                 let module_id = module.self_id();
 
-                // Make sure that there is not already a module with this name published
-                // under the transaction sender's account.
-                // Note: although this reads from the "module cache", `get_loaded_module`
-                // will read through the cache to fetch the module from the global storage
-                // if it is not already cached.
-                match txn_executor.module_cache().get_loaded_module(&module_id) {
-                    Ok(None) => (), // No module with this name exists. safe to publish one
-                    Err(ref err) if err.is(StatusType::InvariantViolation) => {
-                        error!(
-                            "[VM] VM internal error while checking for duplicate module {:?}: {:?}",
-                            module_id, err
-                        );
-                        return ExecutedTransaction::discard_error_output(err.clone());
-                    }
-                    Ok(Some(_)) | Err(_) => {
-                        // A module with this name already exists (the error case is when the module
-                        // couldn't be verified, but it still exists so we should fail similarly).
-                        // It is not safe to publish another one; it would clobber the old module.
-                        // This would break code that links against the module and make published
-                        // resources from the old module inaccessible (or worse, accessible and not
-                        // typesafe).
-                        //
-                        // We are currently developing a versioning scheme for safe updates of
-                        // modules and resources.
-                        warn!("[VM] VM error duplicate module {:?}", module_id);
-                        return txn_executor.failed_transaction_cleanup(Err(vm_error(
-                            Location::default(),
-                            StatusCode::DUPLICATE_MODULE_NAME,
-                        )));
-                    }
+                if let Err(err) = check_module_publishing(
+                    txn_executor.module_cache(),
+                    &module,
+                    allow_module_upgrades,
+                ) {
+                    return match err.status_type() {
+                        StatusType::InvariantViolation => {
+                            ExecutedTransaction::discard_error_output(err)
+                        }
+                        _ => txn_executor.failed_transaction_cleanup(Err(err)),
+                    };
                 }
 
                 txn_executor.module_cache().cache_module(module);
@@ -143,37 +128,21 @@ where
                 _ => unreachable!("TransactionPayload::Module expects VerTxn::Module"),
             };
             let module_id = ver_module.self_id();
-            // Make sure that there is not already a module with this name published
-            // under the transaction sender's account.
-            // Note: although this reads from the "module cache", `get_loaded_module`
-            // will read through the cache to fetch the module from the global storage
-            // if it is not already cached.
-            match txn_executor.module_cache().get_loaded_module(&module_id) {
-                Ok(None) => (), // No module with this name exists. safe to publish one
-                Err(ref err) if err.is(StatusType::InvariantViolation) => {
-                    error!(
-                        "[VM] VM internal error while checking for duplicate module {:?}: {:?}",
-                        module_id, err
-                    );
-                    return ExecutedTransaction::discard_error_output(err.clone());
-                }
-                Ok(Some(_)) | Err(_) => {
-                    // A module with this name already exists (the error case is when the module
-                    // couldn't be verified, but it still exists so we should fail similarly).
-                    // It is not safe to publish another one; it would clobber the old module.
-                    // This would break code that links against the module and make published
-                    // resources from the old module inaccessible (or worse, accessible and not
-                    // typesafe).
-                    //
-                    // We are currently developing a versioning scheme for safe updates of
-                    // modules and resources.
-                    warn!("[VM] VM error duplicate module {:?}", module_id);
-                    return txn_executor.failed_transaction_cleanup(Err(vm_error(
-                        Location::default(),
-                        StatusCode::DUPLICATE_MODULE_NAME,
-                    )));
-                }
+            if let Err(err) = check_module_publishing(
+                txn_executor.module_cache(),
+                &ver_module,
+                allow_module_upgrades,
+            ) {
+                return match err.status_type() {
+                    StatusType::InvariantViolation => {
+                        ExecutedTransaction::discard_error_output(err)
+                    }
+                    _ => txn_executor.failed_transaction_cleanup(Err(err)),
+                };
             }
+            // Cache the module so that it replaces the published version, if any, once the
+            // transaction is done.
+            txn_executor.module_cache().cache_module(*ver_module);
             let module_bytes = module.into_inner();
             txn_executor.transaction_cleanup(vec![(module_id, module_bytes)])
         }
@@ -206,6 +175,112 @@ where
     }
 }
 
+/// Checks that `module` can be published under the sender's account.
+///
+/// This fails if a module with the same name is already published, unless `allow_module_upgrades`
+/// is set and `module` is compatible with the published version.
+fn check_module_publishing<'alloc, P>(
+    module_cache: &P,
+    module: &VerifiedModule,
+    allow_module_upgrades: bool,
+) -> VMResult<()>
+where
+    P: ModuleCache<'alloc>,
+{
+    let module_id = module.self_id();
+    // Note: although this reads from the "module cache", `get_loaded_module`
+    // will read through the cache to fetch the module from the global storage
+    // if it is not already cached.
+    let published_module = match module_cache.get_loaded_module(&module_id) {
+        // No module with this name exists. safe to publish one
+        Ok(None) => return check_no_dependency_cycle(module_cache, module),
+        Ok(Some(published_module)) if allow_module_upgrades => published_module,
+        Err(err) => {
+            if err.is(StatusType::InvariantViolation) {
+                error!(
+                    "[VM] VM internal error while checking for duplicate module {:?}: {:?}",
+                    module_id, err
+                );
+                return Err(err);
+            }
+            // The module couldn't be verified, but it still exists so we should fail similarly
+            // to the case below.
+            warn!("[VM] VM error duplicate module {:?}", module_id);
+            return Err(vm_error(
+                Location::default(),
+                StatusCode::DUPLICATE_MODULE_NAME,
+            ));
+        }
+        Ok(Some(_)) => {
+            // A module with this name already exists and upgrades are not allowed. It is not
+            // safe to publish another one unchecked; it would clobber the old module. This would
+            // break code that links against the module and make published resources from the
+            // old module inaccessible (or worse, accessible and not typesafe).
+            warn!("[VM] VM error duplicate module {:?}", module_id);
+            return Err(vm_error(
+                Location::default(),
+                StatusCode::DUPLICATE_MODULE_NAME,
+            ));
+        }
+    };
+
+    // The new version must keep everything the code linked against the published version and the
+    // resources stored under its types rely on.
+    let mut errors =
+        CompatibilityChecker::new(published_module.as_module(), module.as_inner()).verify();
+    if errors.is_empty() {
+        check_no_dependency_cycle(module_cache, module)
+    } else {
+        warn!(
+            "[VM] VM error incompatible module upgrade {:?}: {:?}",
+            module_id, errors
+        );
+        Err(errors.remove(0))
+    }
+}
+
+/// Checks that publishing `module` doesn't introduce a cyclic dependency, i.e. that none of the
+/// modules it depends on, directly or not, depends on it.
+///
+/// An upgrade can import modules that were published after the previous version, and a module may
+/// be imported by published modules before it's published itself.
+fn check_no_dependency_cycle<'alloc, P>(module_cache: &P, module: &VerifiedModule) -> VMResult<()>
+where
+    P: ModuleCache<'alloc>,
+{
+    let module_id = module.self_id();
+    let mut visited = HashSet::new();
+    let mut to_visit = module_dependencies(module);
+    while let Some(dependency_id) = to_visit.pop() {
+        if dependency_id == module_id {
+            warn!("[VM] VM error cyclic dependency on module {:?}", module_id);
+            return Err(vm_error(
+                Location::default(),
+                StatusCode::CYCLIC_MODULE_DEPENDENCY,
+            ));
+        }
+        if !visited.insert(dependency_id.clone()) {
+            continue;
+        }
+        // A module that isn't published can't depend on `module`.
+        if let Some(dependency) = module_cache.get_loaded_module(&dependency_id)? {
+            to_visit.extend(module_dependencies(dependency));
+        }
+    }
+    Ok(())
+}
+
+/// Returns the ids of the modules that `module` imports.
+fn module_dependencies(module: &impl ModuleAccess) -> Vec<ModuleId> {
+    let self_id = module.self_id();
+    module
+        .module_handles()
+        .iter()
+        .map(|handle| module.module_id_for_handle(handle))
+        .filter(|id| id != &self_id)
+        .collect()
+}
+
 impl ExecutedTransaction {
     #[inline]
     pub(crate) fn discard_error_output(err: VMStatus) -> TransactionOutput {
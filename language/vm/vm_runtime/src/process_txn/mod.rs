@@ -1,11 +1,11 @@
 use crate::{
     code_cache::module_cache::ModuleCache, data_cache::RemoteCache,
-    loaded_data::loaded_module::LoadedModule,
+    loaded_data::loaded_module::LoadedModule, tracer::TraceSink,
 };
 use config::config::VMPublishingOption;
 use libra_types::transaction::SignatureCheckedTransaction;
 use std::marker::PhantomData;
-use vm::errors::VMResult;
+use vm::{errors::VMResult, gas_schedule::GasSchedule};
 use vm_cache_map::Arena;
 
 pub mod execute;
@@ -24,7 +24,9 @@ where
     txn: SignatureCheckedTransaction,
     module_cache: P,
     data_cache: &'txn dyn RemoteCache,
+    gas_schedule: &'txn GasSchedule,
     allocator: &'txn Arena<LoadedModule>,
+    trace_sink: Option<&'txn mut dyn TraceSink>,
     phantom: PhantomData<&'alloc ()>,
 }
 
@@ -33,22 +35,33 @@ where
     'alloc: 'txn,
     P: ModuleCache<'alloc>,
 {
-    /// Creates a new instance of `ProcessTransaction`.
+    /// Creates a new instance of `ProcessTransaction`. The transaction is validated and charged
+    /// according to `gas_schedule`.
     pub fn new(
         txn: SignatureCheckedTransaction,
         module_cache: P,
         data_cache: &'txn dyn RemoteCache,
+        gas_schedule: &'txn GasSchedule,
         allocator: &'txn Arena<LoadedModule>,
     ) -> Self {
         Self {
             txn,
             module_cache,
             data_cache,
+            gas_schedule,
             allocator,
+            trace_sink: None,
             phantom: PhantomData,
         }
     }
 
+    /// Records every instruction executed while processing this transaction to `trace_sink`,
+    /// including the prologue and the epilogue.
+    pub fn with_trace_sink(mut self, trace_sink: &'txn mut dyn TraceSink) -> Self {
+        self.trace_sink = Some(trace_sink);
+        self
+    }
+
     /// Validates this transaction. Returns a `ValidatedTransaction` on success or `VMStatus` on
     /// failure.
     pub fn validate(
@@ -6,6 +6,7 @@ use crate::{
     data_cache::RemoteCache,
     loaded_data::loaded_module::LoadedModule,
     process_txn::{verify::VerifiedTransaction, ProcessTransaction},
+    tracer::TraceSink,
     txn_executor::TransactionExecutor,
 };
 use config::config::VMPublishingOption;
@@ -17,14 +18,16 @@ use libra_types::{
 };
 use vm::{
     errors::convert_prologue_runtime_error,
-    gas_schedule::{self, AbstractMemorySize, GasAlgebra, GasCarrier},
+    gas_schedule::{AbstractMemorySize, GasAlgebra, GasCarrier, GasSchedule},
     transaction_metadata::TransactionMetadata,
 };
 use vm_cache_map::Arena;
 
 pub fn is_allowed_script(publishing_option: &VMPublishingOption, program: &[u8]) -> bool {
     match publishing_option {
-        VMPublishingOption::Open | VMPublishingOption::CustomScripts => true,
+        VMPublishingOption::Open
+        | VMPublishingOption::Upgradable
+        | VMPublishingOption::CustomScripts => true,
         VMPublishingOption::Locked(whitelist) => {
             let hash_value = HashValue::from_sha3_256(program);
             whitelist.contains(hash_value.as_ref())
@@ -41,6 +44,8 @@ where
 {
     txn: SignatureCheckedTransaction,
     txn_state: Option<ValidatedTransactionState<'alloc, 'txn, P>>,
+    /// Whether the modules published by this transaction can replace published modules.
+    pub(super) allow_module_upgrades: bool,
 }
 
 /// The mode to validate transactions in.
@@ -74,7 +79,9 @@ where
             txn,
             module_cache,
             data_cache,
+            gas_schedule,
             allocator,
+            trace_sink,
             ..
         } = process_txn;
 
@@ -84,7 +91,9 @@ where
                     &txn,
                     module_cache,
                     data_cache,
+                    gas_schedule,
                     allocator,
+                    trace_sink,
                     mode,
                     || {
                         // Verify against whitelist if we are locked. Otherwise allow.
@@ -109,7 +118,9 @@ where
                     &txn,
                     module_cache,
                     data_cache,
+                    gas_schedule,
                     allocator,
+                    trace_sink,
                     mode,
                     || {
                         // Verify against whitelist if we are locked. Otherwise allow.
@@ -127,7 +138,9 @@ where
                     &txn,
                     module_cache,
                     data_cache,
+                    gas_schedule,
                     allocator,
+                    trace_sink,
                     mode,
                     || {
                         if !publishing_option.is_open() {
@@ -161,14 +174,21 @@ where
             }
         };
 
-        Ok(Self { txn, txn_state })
+        Ok(Self {
+            txn,
+            txn_state,
+            allow_module_upgrades: publishing_option.allows_module_upgrades(),
+        })
     }
 
     /// Verifies the bytecode in this transaction.
-    pub fn verify(
+    pub fn verify<'script>(
         self,
-        script_cache: &'txn ScriptCache<'alloc>,
-    ) -> Result<VerifiedTransaction<'alloc, 'txn, P>, VMStatus> {
+        script_cache: &'txn ScriptCache<'script>,
+    ) -> Result<VerifiedTransaction<'alloc, 'txn, P>, VMStatus>
+    where
+        'script: 'alloc,
+    {
         VerifiedTransaction::new(self, script_cache)
     }
 
@@ -192,7 +212,9 @@ where
         txn: &SignatureCheckedTransaction,
         module_cache: P,
         data_cache: &'txn dyn RemoteCache,
+        gas_schedule: &'txn GasSchedule,
         allocator: &'txn Arena<LoadedModule>,
+        trace_sink: Option<&'txn mut dyn TraceSink>,
         mode: ValidationMode,
         payload_check: impl Fn() -> Result<(), VMStatus>,
     ) -> Result<ValidatedTransactionState<'alloc, 'txn, P>, VMStatus> {
@@ -221,15 +243,15 @@ where
         // The submitted max gas units that the transaction can consume is greater than the
         // maximum number of gas units bound that we have set for any
         // transaction.
-        if txn.max_gas_amount() > gas_schedule::MAXIMUM_NUMBER_OF_GAS_UNITS.get() {
+        if txn.max_gas_amount() > gas_schedule.maximum_number_of_gas_units.get() {
             let error_str = format!(
                 "max gas units: {}, gas units submitted: {}",
-                gas_schedule::MAXIMUM_NUMBER_OF_GAS_UNITS.get(),
+                gas_schedule.maximum_number_of_gas_units.get(),
                 txn.max_gas_amount()
             );
             warn!(
                 "[VM] Gas unit error; max {}, submitted {}",
-                gas_schedule::MAXIMUM_NUMBER_OF_GAS_UNITS.get(),
+                gas_schedule.maximum_number_of_gas_units.get(),
                 txn.max_gas_amount()
             );
             return Err(
@@ -241,7 +263,7 @@ where
         // The submitted transactions max gas units needs to be at least enough to cover the
         // intrinsic cost of the transaction as calculated against the size of the
         // underlying `RawTransaction`
-        let min_txn_fee = gas_schedule::calculate_intrinsic_gas(raw_bytes_len);
+        let min_txn_fee = gas_schedule.calculate_intrinsic_gas(raw_bytes_len);
         if txn.max_gas_amount() < min_txn_fee.get() {
             let error_str = format!(
                 "min gas required for txn: {}, gas submitted: {}",
@@ -263,16 +285,16 @@ where
         // NB: MIN_PRICE_PER_GAS_UNIT may equal zero, but need not in the future. Hence why
         // we turn off the clippy warning.
         #[allow(clippy::absurd_extreme_comparisons)]
-        let below_min_bound = txn.gas_unit_price() < gas_schedule::MIN_PRICE_PER_GAS_UNIT.get();
+        let below_min_bound = txn.gas_unit_price() < gas_schedule.min_price_per_gas_unit.get();
         if below_min_bound {
             let error_str = format!(
                 "gas unit min price: {}, submitted price: {}",
-                gas_schedule::MIN_PRICE_PER_GAS_UNIT.get(),
+                gas_schedule.min_price_per_gas_unit.get(),
                 txn.gas_unit_price()
             );
             warn!(
                 "[VM] Gas unit error; min {}, submitted {}",
-                gas_schedule::MIN_PRICE_PER_GAS_UNIT.get(),
+                gas_schedule.min_price_per_gas_unit.get(),
                 txn.gas_unit_price()
             );
             return Err(
@@ -281,15 +303,15 @@ where
         }
 
         // The submitted gas price is greater than the maximum gas unit price set by the VM.
-        if txn.gas_unit_price() > gas_schedule::MAX_PRICE_PER_GAS_UNIT.get() {
+        if txn.gas_unit_price() > gas_schedule.max_price_per_gas_unit.get() {
             let error_str = format!(
                 "gas unit max price: {}, submitted price: {}",
-                gas_schedule::MAX_PRICE_PER_GAS_UNIT.get(),
+                gas_schedule.max_price_per_gas_unit.get(),
                 txn.gas_unit_price()
             );
             warn!(
                 "[VM] Gas unit error; min {}, submitted {}",
-                gas_schedule::MAX_PRICE_PER_GAS_UNIT.get(),
+                gas_schedule.max_price_per_gas_unit.get(),
                 txn.gas_unit_price()
             );
             return Err(
@@ -300,8 +322,16 @@ where
         payload_check()?;
 
         let metadata = TransactionMetadata::new(&txn);
-        let mut txn_state =
-            ValidatedTransactionState::new(metadata, module_cache, data_cache, allocator);
+        let mut txn_state = ValidatedTransactionState::new(
+            metadata,
+            module_cache,
+            data_cache,
+            gas_schedule,
+            allocator,
+        );
+        if let Some(trace_sink) = trace_sink {
+            txn_state.txn_executor.set_trace_sink(trace_sink);
+        }
 
         // Run the prologue to ensure that clients have enough gas and aren't tricking us by
         // sending us garbage.
@@ -350,11 +380,17 @@ where
         metadata: TransactionMetadata,
         module_cache: P,
         data_cache: &'txn dyn RemoteCache,
+        gas_schedule: &'txn GasSchedule,
         allocator: &'txn Arena<LoadedModule>,
     ) -> Self {
         // This temporary cache is used for modules published by a single transaction.
         let txn_module_cache = TransactionModuleCache::new(module_cache, allocator);
-        let txn_executor = TransactionExecutor::new(txn_module_cache, data_cache, metadata);
+        let txn_executor = TransactionExecutor::new_with_gas_schedule(
+            txn_module_cache,
+            data_cache,
+            metadata,
+            gas_schedule,
+        );
         Self { txn_executor }
     }
 }
@@ -34,6 +34,8 @@ where
     txn: SignatureCheckedTransaction,
     #[allow(dead_code)]
     txn_state: Option<VerifiedTransactionState<'alloc, 'txn, P>>,
+    /// Whether the modules published by this transaction can replace published modules.
+    pub(super) allow_module_upgrades: bool,
 }
 
 impl<'alloc, 'txn, P> VerifiedTransaction<'alloc, 'txn, P>
@@ -42,10 +44,13 @@ where
     P: ModuleCache<'alloc>,
 {
     /// Creates a new instance by verifying the bytecode in this validated transaction.
-    pub(super) fn new(
+    pub(super) fn new<'script>(
         mut validated_txn: ValidatedTransaction<'alloc, 'txn, P>,
-        script_cache: &'txn ScriptCache<'alloc>,
-    ) -> Result<Self, VMStatus> {
+        script_cache: &'txn ScriptCache<'script>,
+    ) -> Result<Self, VMStatus>
+    where
+        'script: 'alloc,
+    {
         let txn_state = validated_txn.take_state();
         let txn = validated_txn.as_inner();
         let txn_state = match txn.payload() {
@@ -90,16 +95,20 @@ where
         };
 
         Ok(Self {
+            allow_module_upgrades: validated_txn.allow_module_upgrades,
             txn: validated_txn.into_inner(),
             txn_state,
         })
     }
 
-    fn verify_program(
+    fn verify_program<'script>(
         sender_address: &AccountAddress,
         program: &Program,
-        script_cache: &'txn ScriptCache<'alloc>,
-    ) -> VMResult<(FunctionRef<'alloc>, Vec<VerifiedModule>)> {
+        script_cache: &'txn ScriptCache<'script>,
+    ) -> VMResult<(FunctionRef<'alloc>, Vec<VerifiedModule>)>
+    where
+        'script: 'alloc,
+    {
         // Ensure the script can correctly be resolved into main.
         let main = script_cache.cache_script(&program.code())?;
 
@@ -176,10 +185,13 @@ where
         }
     }
 
-    fn verify_script(
+    fn verify_script<'script>(
         script: &Script,
-        script_cache: &'txn ScriptCache<'alloc>,
-    ) -> Result<FunctionRef<'alloc>, VMStatus> {
+        script_cache: &'txn ScriptCache<'script>,
+    ) -> Result<FunctionRef<'alloc>, VMStatus>
+    where
+        'script: 'alloc,
+    {
         // Ensure the script can correctly be resolved into main.
         let main = script_cache.cache_script(&script.code())?;
 
@@ -343,19 +355,33 @@ fn verify_actuals(signature: &FunctionSignature, args: &[TransactionArgument]) -
         return false;
     }
     for (ty, arg) in signature.arg_types.iter().zip(args.iter()) {
-        match (ty, arg) {
-            (SignatureToken::U64, TransactionArgument::U64(_)) => (),
-            (SignatureToken::Address, TransactionArgument::Address(_)) => (),
-            (SignatureToken::ByteArray, TransactionArgument::ByteArray(_)) => (),
-            (SignatureToken::String, TransactionArgument::String(_)) => (),
-            _ => {
-                warn!(
-                    "[VM] different argument type: formal {:?}, actual {:?}",
-                    ty, arg
-                );
-                return false;
-            }
+        if !verify_actual(ty, arg) {
+            warn!(
+                "[VM] different argument type: formal {:?}, actual {:?}",
+                ty, arg
+            );
+            return false;
         }
     }
     true
 }
+
+fn verify_actual(ty: &SignatureToken, arg: &TransactionArgument) -> bool {
+    match (ty, arg) {
+        (SignatureToken::U64, TransactionArgument::U64(_))
+        | (SignatureToken::Address, TransactionArgument::Address(_))
+        | (SignatureToken::ByteArray, TransactionArgument::ByteArray(_))
+        | (SignatureToken::String, TransactionArgument::String(_))
+        | (SignatureToken::Bool, TransactionArgument::Bool(_)) => true,
+        // The bytecode verifier ensures that the only struct `main` takes is `Vector.T`.
+        (SignatureToken::Struct(_, type_actuals), TransactionArgument::Vector(elements)) => {
+            match type_actuals.as_slice() {
+                [element_type] => elements
+                    .iter()
+                    .all(|element| verify_actual(element_type, element)),
+                _ => false,
+            }
+        }
+        _ => false,
+    }
+}
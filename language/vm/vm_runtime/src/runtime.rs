@@ -2,7 +2,7 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use crate::{
-    block_processor::execute_block,
+    block_processor::{execute_block, execute_transaction_with_trace, load_gas_schedule},
     code_cache::{
         module_adapter::ModuleFetcherImpl,
         module_cache::{BlockModuleCache, VMModuleCache},
@@ -12,6 +12,7 @@ use crate::{
     data_cache::BlockDataCache,
     loaded_data::loaded_module::LoadedModule,
     process_txn::{validate::ValidationMode, ProcessTransaction},
+    tracer::TraceSink,
 };
 use config::config::{VMConfig, VMPublishingOption};
 use libra_logger::prelude::*;
@@ -31,10 +32,14 @@ use vm_cache_map::Arena;
 ///   in the whitelist, the VM will just reject it in `verify_transaction`.
 /// * Custom scripts, which will allow arbitrary valid scripts, but no module publishing
 /// * Open script and module publishing
+/// * Upgradable, which also allows republishing a module with a compatible version
+/// `execution_concurrency_level` is the number of threads used to execute the transactions of a
+/// block speculatively. Blocks are executed sequentially if it's 1 or less.
 pub struct VMRuntime<'alloc> {
     code_cache: VMModuleCache<'alloc>,
     script_cache: ScriptCache<'alloc>,
     publishing_option: VMPublishingOption,
+    execution_concurrency_level: usize,
 }
 
 impl<'alloc> VMRuntime<'alloc> {
@@ -45,6 +50,7 @@ impl<'alloc> VMRuntime<'alloc> {
             code_cache: VMModuleCache::new(allocator),
             script_cache: ScriptCache::new(allocator),
             publishing_option: config.publishing_options.clone(),
+            execution_concurrency_level: config.execution_concurrency_level,
         }
     }
 
@@ -69,9 +75,14 @@ impl<'alloc> VMRuntime<'alloc> {
     ) -> Option<VMStatus> {
         trace!("[VM] Verify transaction: {:?}", txn);
         // Treat a transaction as a single block.
-        let module_cache =
-            BlockModuleCache::new(&self.code_cache, ModuleFetcherImpl::new(data_view));
+        let block_arena = Arena::new();
+        let module_cache = BlockModuleCache::new(
+            &self.code_cache,
+            &block_arena,
+            ModuleFetcherImpl::new(data_view),
+        );
         let data_cache = BlockDataCache::new(data_view);
+        let gas_schedule = load_gas_schedule(data_view);
 
         let arena = Arena::new();
         let signature_verified_txn = match txn.check_signature() {
@@ -79,8 +90,13 @@ impl<'alloc> VMRuntime<'alloc> {
             Err(_) => return Some(VMStatus::new(StatusCode::INVALID_SIGNATURE)),
         };
 
-        let process_txn =
-            ProcessTransaction::new(signature_verified_txn, module_cache, &data_cache, &arena);
+        let process_txn = ProcessTransaction::new(
+            signature_verified_txn,
+            module_cache,
+            &data_cache,
+            &gas_schedule,
+            &arena,
+        );
         let mode = if data_view.is_genesis() {
             ValidationMode::Genesis
         } else {
@@ -126,6 +142,26 @@ impl<'alloc> VMRuntime<'alloc> {
             &self.script_cache,
             data_view,
             &self.publishing_option,
+            self.execution_concurrency_level,
+        )
+    }
+
+    /// Execute a single transaction, recording every instruction executed to `trace_sink`. This is
+    /// meant for debugging: the output is the same as executing the transaction in a block of its
+    /// own.
+    pub fn execute_transaction_with_trace(
+        &self,
+        txn: SignedTransaction,
+        data_view: &dyn StateView,
+        trace_sink: &mut dyn TraceSink,
+    ) -> TransactionOutput {
+        execute_transaction_with_trace(
+            txn,
+            &self.code_cache,
+            &self.script_cache,
+            data_view,
+            &self.publishing_option,
+            trace_sink,
         )
     }
 }
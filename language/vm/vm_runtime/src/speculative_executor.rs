@@ -0,0 +1,189 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Speculative execution of the transactions of a block on multiple threads.
+//!
+//! Every transaction is executed against the state at the beginning of the block, as if it were
+//! the first transaction of the block, and the access paths it reads are recorded. The block
+//! processor then walks the block in order and only keeps a speculative output if none of the
+//! access paths it read were written by an earlier transaction of the block. Otherwise the
+//! transaction is executed again against the up-to-date state.
+
+use crate::{
+    block_processor::transaction_flow,
+    code_cache::{
+        module_adapter::ModuleFetcherImpl,
+        module_cache::{BlockModuleCache, VMModuleCache},
+        script_cache::ScriptCache,
+    },
+    data_cache::BlockDataCache,
+    process_txn::validate::ValidationMode,
+};
+use config::config::VMPublishingOption;
+use crossbeam::channel::{bounded, unbounded, Sender};
+use failure::prelude::*;
+use libra_logger::prelude::*;
+use libra_types::{
+    access_path::AccessPath,
+    transaction::{SignatureCheckedTransaction, TransactionOutput},
+    vm_error::VMStatus,
+};
+use state_view::StateView;
+use std::{
+    cell::RefCell,
+    collections::BTreeSet,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+use vm::gas_schedule::GasSchedule;
+use vm_cache_map::Arena;
+
+/// A read of `AccessPath` from the block's `StateView`, along with where to send the result.
+type ReadRequest = (AccessPath, Sender<Result<Option<Vec<u8>>>>);
+
+/// The output of a transaction executed against the state at the beginning of the block.
+pub(crate) struct SpeculativeOutput {
+    pub output: TransactionOutput,
+    /// Every access path read while executing the transaction.
+    pub read_set: BTreeSet<AccessPath>,
+}
+
+/// The `StateView` of a speculative execution thread.
+///
+/// The block's `StateView` isn't required to be thread safe, so reads are forwarded to the thread
+/// that owns it. Every access path read is recorded.
+struct SpeculativeStateView {
+    requests: Sender<ReadRequest>,
+    read_set: RefCell<BTreeSet<AccessPath>>,
+}
+
+impl SpeculativeStateView {
+    fn new(requests: Sender<ReadRequest>) -> Self {
+        Self {
+            requests,
+            read_set: RefCell::new(BTreeSet::new()),
+        }
+    }
+
+    fn into_read_set(self) -> BTreeSet<AccessPath> {
+        self.read_set.into_inner()
+    }
+}
+
+impl StateView for SpeculativeStateView {
+    fn get(&self, access_path: &AccessPath) -> Result<Option<Vec<u8>>> {
+        self.read_set.borrow_mut().insert(access_path.clone());
+        let (reply_tx, reply_rx) = bounded(1);
+        self.requests
+            .send((access_path.clone(), reply_tx))
+            .map_err(|_| format_err!("Block state view is no longer available"))?;
+        reply_rx.recv()?
+    }
+
+    fn multi_get(&self, access_paths: &[AccessPath]) -> Result<Vec<Option<Vec<u8>>>> {
+        access_paths
+            .iter()
+            .map(|access_path| self.get(access_path))
+            .collect()
+    }
+
+    fn is_genesis(&self) -> bool {
+        // The genesis transaction is never executed speculatively.
+        false
+    }
+}
+
+/// Executes every valid transaction of `txn_block` against `data_view` on up to
+/// `concurrency_level` threads.
+///
+/// Returns the speculative output of every transaction, in block order. The output is `None` for
+/// transactions that failed the signature check or whose execution thread panicked.
+///
+/// The transactions must not publish modules: the modules published by a speculative execution
+/// aren't visible to the transactions that follow it.
+pub(crate) fn execute_speculatively<'alloc>(
+    txn_block: &[std::result::Result<SignatureCheckedTransaction, VMStatus>],
+    code_cache: &VMModuleCache<'alloc>,
+    script_cache: &ScriptCache<'alloc>,
+    data_view: &dyn StateView,
+    gas_schedule: &GasSchedule,
+    publishing_option: &VMPublishingOption,
+    concurrency_level: usize,
+) -> Vec<Option<SpeculativeOutput>> {
+    let next_txn = AtomicUsize::new(0);
+    let (requests_tx, requests_rx) = unbounded::<ReadRequest>();
+
+    let worker_outputs = crossbeam::scope(|scope| {
+        let workers: Vec<_> = (0..concurrency_level.min(txn_block.len()))
+            .map(|_| {
+                let requests = requests_tx.clone();
+                let next_txn = &next_txn;
+                scope.spawn(move |_| {
+                    let mut outputs = vec![];
+                    loop {
+                        let idx = next_txn.fetch_add(1, Ordering::Relaxed);
+                        let txn = match txn_block.get(idx) {
+                            Some(Ok(txn)) => txn,
+                            Some(Err(_)) => continue,
+                            None => break,
+                        };
+                        let view = SpeculativeStateView::new(requests.clone());
+                        let output = {
+                            let block_arena = Arena::new();
+                            let module_cache = BlockModuleCache::new(
+                                code_cache,
+                                &block_arena,
+                                ModuleFetcherImpl::new(&view),
+                            );
+                            let data_cache = BlockDataCache::new(&view);
+                            transaction_flow(
+                                txn.clone(),
+                                &module_cache,
+                                script_cache,
+                                &data_cache,
+                                gas_schedule,
+                                ValidationMode::Executing,
+                                publishing_option,
+                                None,
+                            )
+                        };
+                        outputs.push((
+                            idx,
+                            SpeculativeOutput {
+                                output,
+                                read_set: view.into_read_set(),
+                            },
+                        ));
+                    }
+                    outputs
+                })
+            })
+            .collect();
+
+        // From now on only the workers hold a sender, so the requests stop once they're all done.
+        drop(requests_tx);
+        for (access_path, reply) in requests_rx.iter() {
+            // The worker is blocked until it gets a reply, so the send can't fail.
+            let _ = reply.send(data_view.get(&access_path));
+        }
+
+        workers
+            .into_iter()
+            .map(|worker| worker.join())
+            .collect::<Vec<_>>()
+    })
+    .unwrap_or_else(|_| vec![]);
+
+    let mut speculative_outputs: Vec<Option<SpeculativeOutput>> =
+        txn_block.iter().map(|_| None).collect();
+    for outputs in worker_outputs {
+        match outputs {
+            Ok(outputs) => {
+                for (idx, output) in outputs {
+                    speculative_outputs[idx] = Some(output);
+                }
+            }
+            Err(_) => crit!("[VM] Speculative execution thread panicked"),
+        }
+    }
+    speculative_outputs
+}
@@ -0,0 +1,98 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Tracing of the instructions executed by the interpreter.
+//!
+//! A [`TraceSink`] set on a `TransactionExecutor` receives a [`TraceEvent`] before every
+//! instruction is executed. When no sink is set the interpreter doesn't build any event.
+
+use crate::{
+    code_cache::module_cache::ModuleCache, execution_stack::ExecutionStack,
+    loaded_data::function::FunctionReference,
+};
+use libra_types::{identifier::Identifier, language_storage::ModuleId};
+use vm::{
+    access::ModuleAccess,
+    errors::VMResult,
+    file_format::{Bytecode, CodeOffset, FunctionDefinitionIndex},
+    gas_schedule::{GasAlgebra, GasCarrier, GasUnits},
+};
+
+/// The maximum number of values from the top of the operand stack recorded in a `TraceEvent`.
+pub const STACK_SUMMARY_SIZE: usize = 8;
+
+/// The state of the interpreter right before an instruction is executed.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct TraceEvent {
+    /// The module defining the function being executed.
+    pub module: ModuleId,
+    /// The function being executed.
+    pub function: Identifier,
+    /// The index of the function definition in `module`.
+    pub function_index: FunctionDefinitionIndex,
+    /// The offset of the instruction in the function's code.
+    pub pc: CodeOffset,
+    pub instruction: Bytecode,
+    /// The number of frames on the call stack.
+    pub call_depth: usize,
+    /// The height of the operand stack.
+    pub stack_height: usize,
+    /// The values at the top of the operand stack, top first. At most `STACK_SUMMARY_SIZE` values
+    /// are recorded.
+    pub stack: Vec<String>,
+    /// The locals of the function being executed.
+    pub locals: String,
+    pub gas_remaining: GasCarrier,
+}
+
+impl TraceEvent {
+    pub(crate) fn new<'alloc, 'txn, P>(
+        execution_stack: &ExecutionStack<'alloc, 'txn, P>,
+        pc: CodeOffset,
+        instruction: &Bytecode,
+        gas_remaining: GasUnits<GasCarrier>,
+    ) -> VMResult<Self>
+    where
+        'alloc: 'txn,
+        P: ModuleCache<'alloc>,
+    {
+        let frame = execution_stack.top_frame()?;
+        let function = frame.function();
+        let module = function.module();
+        let function_index = module
+            .function_defs_table
+            .get(function.name())
+            .copied()
+            .unwrap_or_else(|| FunctionDefinitionIndex::new(0));
+        let stack = execution_stack.get_value_stack();
+        Ok(Self {
+            module: module.self_id(),
+            function: function.name().to_owned(),
+            function_index,
+            pc,
+            instruction: instruction.clone(),
+            call_depth: execution_stack.call_stack_height(),
+            stack_height: stack.len(),
+            stack: stack
+                .iter()
+                .rev()
+                .take(STACK_SUMMARY_SIZE)
+                .map(|value| format!("{:?}", value))
+                .collect(),
+            locals: format!("{:?}", frame.get_locals()),
+            gas_remaining: gas_remaining.get(),
+        })
+    }
+}
+
+/// A destination for the events recorded while tracing execution.
+pub trait TraceSink {
+    fn record(&mut self, event: TraceEvent);
+}
+
+/// Collects the events in memory, in execution order.
+impl TraceSink for Vec<TraceEvent> {
+    fn record(&mut self, event: TraceEvent) {
+        self.push(event)
+    }
+}
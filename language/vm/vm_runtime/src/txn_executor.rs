@@ -8,11 +8,12 @@ use crate::{
     data_cache::{RemoteCache, TransactionDataCache},
     execution_stack::ExecutionStack,
     gas_meter::GasMeter,
-    identifier::{create_access_path, resource_storage_key},
+    identifier::{create_access_path, resource_storage_key, type_tag_for_token},
     loaded_data::{
         function::{FunctionRef, FunctionReference},
         loaded_module::LoadedModule,
     },
+    tracer::{TraceEvent, TraceSink},
 };
 use bytecode_verifier::{VerifiedModule, VerifiedScript};
 use libra_types::{
@@ -23,7 +24,7 @@ use libra_types::{
     contract_event::ContractEvent,
     event::EventKey,
     identifier::{IdentStr, Identifier},
-    language_storage::ModuleId,
+    language_storage::{ModuleId, TypeTag},
     transaction::{
         TransactionArgument, TransactionOutput, TransactionStatus, MAX_TRANSACTION_SIZE_IN_BYTES,
     },
@@ -34,15 +35,21 @@ use std::{collections::VecDeque, convert::TryFrom};
 use vm::{
     access::ModuleAccess,
     errors::*,
-    file_format::{Bytecode, CodeOffset, CompiledScript, StructDefinitionIndex},
-    gas_schedule::{AbstractMemorySize, GasAlgebra, GasUnits},
+    file_format::{
+        Bytecode, CodeOffset, CompiledScript, LocalsSignatureIndex, StructDefinitionIndex,
+    },
+    gas_schedule::{
+        gas_schedule_module_name, AbstractMemorySize, GasAlgebra, GasCarrier, GasSchedule,
+        GasUnits, DEFAULT_GAS_SCHEDULE,
+    },
     transaction_metadata::TransactionMetadata,
     vm_string::VMString,
 };
 use vm_cache_map::Arena;
 use vm_runtime_types::{
     native_functions::dispatch::{dispatch_native_function, NativeReturnStatus},
-    value::{ReferenceValue, Struct, Value},
+    native_structs::{vector::NativeVector, NativeStructValue},
+    value::{IntegerValue, ReferenceValue, Struct, Value},
 };
 
 // Metadata needed for resolving the account module.
@@ -65,6 +72,9 @@ lazy_static! {
     /// The ModuleId for the validator set
     pub static ref VALIDATOR_SET_MODULE: ModuleId =
         { ModuleId::new(account_config::core_code_address(), Identifier::new("ValidatorSet").unwrap()) };
+    /// The ModuleId for the gas schedule
+    pub static ref GAS_SCHEDULE_MODULE: ModuleId =
+        { ModuleId::new(account_config::core_code_address(), gas_schedule_module_name().to_owned()) };
 
     /// The ModuleId for the transaction fee distribution module
     pub static ref TRANSACTION_FEE_DISTRIBUTION_MODULE: ModuleId =
@@ -89,6 +99,20 @@ fn make_access_path(
     create_access_path(&address, struct_tag)
 }
 
+/// Convert a transaction argument into the move value passed to `main`.
+fn main_arg_value(arg: TransactionArgument) -> Value {
+    match arg {
+        TransactionArgument::U64(i) => Value::u64(i),
+        TransactionArgument::Address(a) => Value::address(a),
+        TransactionArgument::ByteArray(b) => Value::byte_array(b),
+        TransactionArgument::String(s) => Value::string(VMString::new(s)),
+        TransactionArgument::Bool(b) => Value::bool(b),
+        TransactionArgument::Vector(elements) => Value::native_struct(NativeStructValue::Vector(
+            NativeVector::new(elements.into_iter().map(main_arg_value).collect()),
+        )),
+    }
+}
+
 /// A struct that executes one single transaction.
 /// 'alloc is the lifetime for the code cache, which is the argument type P here. Hence the P should
 /// live as long as alloc.
@@ -97,6 +121,7 @@ fn make_access_path(
 /// `txn_data` contains the information of this transaction, such as sender, sequence number, etc.
 /// `event_data` is the vector that stores all events emitted during execution.
 /// `data_view` is the scratchpad for the local writes emitted by this transaction.
+/// `trace_sink` receives an event for every instruction executed, if set.
 pub struct TransactionExecutor<'alloc, 'txn, P>
 where
     'alloc: 'txn,
@@ -107,10 +132,11 @@ where
 
     #[cfg(not(any(test, feature = "instruction_synthesis")))]
     execution_stack: ExecutionStack<'alloc, 'txn, P>,
-    gas_meter: GasMeter,
+    gas_meter: GasMeter<'txn>,
     txn_data: TransactionMetadata,
     event_data: Vec<ContractEvent>,
     data_view: TransactionDataCache<'txn>,
+    trace_sink: Option<&'txn mut dyn TraceSink>,
 }
 
 impl<'alloc, 'txn, P> TransactionExecutor<'alloc, 'txn, P>
@@ -122,20 +148,39 @@ where
     /// cache that stores the modules previously read from the blockchain. `data_cache` is the cache
     /// that holds read-only connection to the state store as well as the changes made by previous
     /// transactions within the same block.
+    ///
+    /// The instructions are charged according to the [default gas schedule](DEFAULT_GAS_SCHEDULE).
     pub fn new(
         module_cache: P,
         data_cache: &'txn dyn RemoteCache,
         txn_data: TransactionMetadata,
+    ) -> Self {
+        Self::new_with_gas_schedule(module_cache, data_cache, txn_data, &DEFAULT_GAS_SCHEDULE)
+    }
+
+    /// Create a new `TransactionExecutor` charging the instructions according to `gas_schedule`,
+    /// which is usually the one read from the chain at the beginning of the block.
+    pub fn new_with_gas_schedule(
+        module_cache: P,
+        data_cache: &'txn dyn RemoteCache,
+        txn_data: TransactionMetadata,
+        gas_schedule: &'txn GasSchedule,
     ) -> Self {
         TransactionExecutor {
             execution_stack: ExecutionStack::new(module_cache),
-            gas_meter: GasMeter::new(txn_data.max_gas_amount()),
+            gas_meter: GasMeter::new(txn_data.max_gas_amount(), gas_schedule),
             txn_data,
             event_data: Vec::new(),
             data_view: TransactionDataCache::new(data_cache),
+            trace_sink: None,
         }
     }
 
+    /// Record a [`TraceEvent`] to `trace_sink` before executing every instruction.
+    pub fn set_trace_sink(&mut self, trace_sink: &'txn mut dyn TraceSink) {
+        self.trace_sink = Some(trace_sink);
+    }
+
     /// Returns the module cache for this executor.
     pub fn module_cache(&self) -> &P {
         &self.execution_stack.module_cache
@@ -161,12 +206,27 @@ where
         }
     }
 
-    fn binop_int<F, T>(&mut self, f: F) -> VMResult<()>
+    /// Perform an arithmetic operation on two integers of the same type at the top of the stack.
+    fn binop_int<F>(&mut self, f: F) -> VMResult<()>
     where
-        Option<T>: From<Value>,
-        F: FnOnce(T, T) -> Option<u64>,
+        F: FnOnce(IntegerValue, IntegerValue) -> Option<IntegerValue>,
     {
-        self.binop(|lhs, rhs| f(lhs, rhs).map(Value::u64))
+        self.binop(|lhs, rhs| f(lhs, rhs).map(IntegerValue::into_value))
+    }
+
+    /// Cast the integer at the top of the stack, failing if it doesn't fit in the target type.
+    fn cast<F>(&mut self, f: F) -> VMResult<()>
+    where
+        F: FnOnce(IntegerValue) -> Option<Value>,
+    {
+        let integer = self.execution_stack.pop_as::<IntegerValue>()?;
+        match f(integer) {
+            Some(value) => self.execution_stack.push(value),
+            None => Err(vm_error(
+                self.execution_stack.location()?,
+                StatusCode::ARITHMETIC_ERROR,
+            )),
+        }
     }
 
     fn binop_bool<F, T>(&mut self, f: F) -> VMResult<()>
@@ -189,6 +249,15 @@ where
     ) -> VMResult<CodeOffset> {
         let mut pc = beginning_offset;
         for instruction in &code[beginning_offset as usize..] {
+            if let Some(trace_sink) = self.trace_sink.as_mut() {
+                trace_sink.record(TraceEvent::new(
+                    &self.execution_stack,
+                    pc,
+                    instruction,
+                    self.gas_meter.remaining_gas(),
+                )?);
+            }
+
             // FIXME: Once we add in memory ops, we will need to pass in the current memory size to
             // this function.
             self.gas_meter.calculate_and_consume(
@@ -221,9 +290,18 @@ where
                     }
                 }
                 Bytecode::Branch(offset) => return Ok(*offset),
+                Bytecode::LdU8(int_const) => {
+                    self.execution_stack.push(Value::u8(*int_const))?;
+                }
                 Bytecode::LdConst(int_const) => {
                     self.execution_stack.push(Value::u64(*int_const))?;
                 }
+                Bytecode::LdU128(int_const) => {
+                    self.execution_stack.push(Value::u128(*int_const))?;
+                }
+                Bytecode::CastU8 => self.cast(|i| i.cast_u8().map(Value::u8))?,
+                Bytecode::CastU64 => self.cast(|i| i.cast_u64().map(Value::u64))?,
+                Bytecode::CastU128 => self.cast(|i| Some(Value::u128(i.cast_u128())))?,
                 Bytecode::LdAddr(idx) => {
                     let top_frame = self.execution_stack.top_frame()?;
                     let addr_ref = top_frame.module().address_at(*idx);
@@ -261,7 +339,7 @@ where
                         .top_frame_mut()?
                         .store_loc(*idx, value)?;
                 }
-                Bytecode::Call(idx, _) => {
+                Bytecode::Call(idx, type_actuals_idx) => {
                     let self_module = &self.execution_stack.top_frame()?.module();
                     let callee_function_ref = self
                         .execution_stack
@@ -279,6 +357,11 @@ where
                         if module_id == *EVENT_MODULE
                             && function_name == EMIT_EVENT_NAME.as_ident_str()
                         {
+                            let type_tag = self
+                                .call_type_actuals(*type_actuals_idx)?
+                                .into_iter()
+                                .next()
+                                .ok_or_else(|| VMStatus::new(StatusCode::INTERNAL_TYPE_ERROR))?;
                             let msg = self
                                 .execution_stack
                                 .pop()?
@@ -289,11 +372,19 @@ where
                             let guid = EventKey::try_from(key.as_bytes())
                                 .map_err(|_| VMStatus::new(StatusCode::EVENT_KEY_MISMATCH))?;
 
-                            // TODO:
-                            // 1. Rename the AccessPath here to a new type that represents such
-                            //    globally unique id for event streams.
-                            // 2. Charge gas for the msg emitted.
-                            self.event_data.push(ContractEvent::new(guid, count, msg))
+                            // The emitted message ends up in the transaction output, so it is
+                            // charged like a write to global storage.
+                            let cost = self
+                                .gas_meter
+                                .gas_schedule()
+                                .global_memory_per_byte_write_cost
+                                .mul(AbstractMemorySize::new(msg.len() as GasCarrier));
+                            self.gas_meter.consume_gas(cost, &self.execution_stack)?;
+
+                            // TODO: Rename the AccessPath here to a new type that represents such
+                            // globally unique id for event streams.
+                            self.event_data
+                                .push(ContractEvent::new(guid, count, type_tag, msg))
                         } else {
                             let mut arguments = VecDeque::new();
                             let expected_args = native_function.num_args();
@@ -336,8 +427,17 @@ where
                     // Call stack is not reconstructed for a native call, so we just
                     // proceed on to next instruction.
                     } else {
+                        // Only generic callees need their type actuals; the common
+                        // non-generic call skips the type conversion entirely.
+                        let type_actuals =
+                            if callee_function_ref.signature().type_formals.is_empty() {
+                                vec![]
+                            } else {
+                                self.call_type_actuals(*type_actuals_idx)?
+                            };
                         self.execution_stack.top_frame_mut()?.save_pc(pc);
-                        self.execution_stack.push_call(callee_function_ref)?;
+                        self.execution_stack
+                            .push_call(callee_function_ref, type_actuals)?;
                         // Call stack is reconstructed, the next instruction to execute will be the
                         // first instruction of the callee function. Thus we should break here to
                         // restart the instruction sequence from there.
@@ -387,25 +487,30 @@ where
                     reference.write_ref(value);
                 }
                 // Arithmetic Operations
-                Bytecode::Add => self.binop_int(u64::checked_add)?,
-                Bytecode::Sub => self.binop_int(u64::checked_sub)?,
-                Bytecode::Mul => self.binop_int(u64::checked_mul)?,
-                Bytecode::Mod => self.binop_int(u64::checked_rem)?,
-                Bytecode::Div => self.binop_int(u64::checked_div)?,
-                Bytecode::BitOr => self.binop_int(|l: u64, r| Some(l | r))?,
-                Bytecode::BitAnd => self.binop_int(|l: u64, r| Some(l & r))?,
-                Bytecode::Xor => self.binop_int(|l: u64, r| Some(l ^ r))?,
+                Bytecode::Add => self.binop_int(IntegerValue::checked_add)?,
+                Bytecode::Sub => self.binop_int(IntegerValue::checked_sub)?,
+                Bytecode::Mul => self.binop_int(IntegerValue::checked_mul)?,
+                Bytecode::Mod => self.binop_int(IntegerValue::checked_rem)?,
+                Bytecode::Div => self.binop_int(IntegerValue::checked_div)?,
+                Bytecode::BitOr => self.binop_int(IntegerValue::bit_or)?,
+                Bytecode::BitAnd => self.binop_int(IntegerValue::bit_and)?,
+                Bytecode::Xor => self.binop_int(IntegerValue::bit_xor)?,
                 Bytecode::Or => self.binop_bool(|l, r| l || r)?,
                 Bytecode::And => self.binop_bool(|l, r| l && r)?,
-                Bytecode::Lt => self.binop_bool(|l: u64, r| l < r)?,
-                Bytecode::Gt => self.binop_bool(|l: u64, r| l > r)?,
-                Bytecode::Le => self.binop_bool(|l: u64, r| l <= r)?,
-                Bytecode::Ge => self.binop_bool(|l: u64, r| l >= r)?,
+                Bytecode::Lt => self.binop_bool(|l: IntegerValue, r| l < r)?,
+                Bytecode::Gt => self.binop_bool(|l: IntegerValue, r| l > r)?,
+                Bytecode::Le => self.binop_bool(|l: IntegerValue, r| l <= r)?,
+                Bytecode::Ge => self.binop_bool(|l: IntegerValue, r| l >= r)?,
                 Bytecode::Abort => {
                     let error_code = self.execution_stack.pop_as::<u64>()?;
+                    let function = self.execution_stack.top_frame()?.function();
                     return Err(
                         vm_error(self.execution_stack.location()?, StatusCode::ABORTED)
-                            .with_sub_status(error_code),
+                            .with_sub_status(error_code)
+                            .with_abort_location(
+                                function.module().self_id(),
+                                function.name().to_owned(),
+                            ),
                     );
                 }
 
@@ -555,12 +660,7 @@ where
     /// Convert the transaction arguments into move values and push them to the top of the stack.
     pub(crate) fn setup_main_args(&mut self, args: Vec<TransactionArgument>) {
         for arg in args.into_iter() {
-            let push_result = self.execution_stack.push(match arg {
-                TransactionArgument::U64(i) => Value::u64(i),
-                TransactionArgument::Address(a) => Value::address(a),
-                TransactionArgument::ByteArray(b) => Value::byte_array(b),
-                TransactionArgument::String(s) => Value::string(VMString::new(s)),
-            });
+            let push_result = self.execution_stack.push(main_arg_value(arg));
             assume!(push_result.is_ok());
             push_result.expect("Stack should be empty at beginning of function");
         }
@@ -695,10 +795,24 @@ where
         ret
     }
 
+    /// Convert the type actuals of a `Call` in the current frame into `TypeTag`s, substituting
+    /// the type actuals of the current frame for its type parameters.
+    fn call_type_actuals(&self, type_actuals_idx: LocalsSignatureIndex) -> VMResult<Vec<TypeTag>> {
+        let top_frame = self.execution_stack.top_frame()?;
+        let self_module = top_frame.module();
+        self_module
+            .locals_signature_at(type_actuals_idx)
+            .0
+            .iter()
+            .map(|token| type_tag_for_token(self_module, token, top_frame.type_actuals()))
+            .collect::<Option<Vec<TypeTag>>>()
+            .ok_or_else(|| VMStatus::new(StatusCode::INTERNAL_TYPE_ERROR))
+    }
+
     /// Execute a function given a FunctionRef.
     fn execute_function_impl(&mut self, func: FunctionRef<'txn>) -> VMResult<()> {
         let beginning_height = self.execution_stack.call_stack_height();
-        self.execution_stack.push_call(func)?;
+        self.execution_stack.push_call(func, vec![])?;
         // We always start execution from the first instruction.
         let mut pc = 0;
 
@@ -831,10 +945,11 @@ pub fn execute_function(
     }
     let mut vm = TransactionExecutor {
         execution_stack: ExecutionStack::new(&module_cache),
-        gas_meter: GasMeter::new(txn_metadata.max_gas_amount()),
+        gas_meter: GasMeter::new(txn_metadata.max_gas_amount(), &DEFAULT_GAS_SCHEDULE),
         txn_data: txn_metadata,
         event_data: Vec::new(),
         data_view: TransactionDataCache::new(data_cache),
+        trace_sink: None,
     };
     vm.execute_function_impl(entry_func)
 }
@@ -24,7 +24,7 @@ use libra_types::{
 use vm::{
     access::ModuleAccess,
     file_format::*,
-    gas_schedule::{GasAlgebra, GasUnits},
+    gas_schedule::{GasAlgebra, GasUnits, DEFAULT_GAS_SCHEDULE},
 };
 use vm_cache_map::Arena;
 use vm_runtime_types::loaded_data::{struct_def::StructDef, types::Type};
@@ -94,6 +94,7 @@ fn test_module(name: &'static str) -> VerifiedModule {
         user_strings: vec![],
         byte_array_pool: vec![],
         address_pool: vec![AccountAddress::default()],
+        abort_codes: vec![],
     }
     .freeze()
     .expect("test module should satisfy bounds checker");
@@ -264,7 +265,8 @@ fn test_cache_with_storage() {
 
     {
         let fetcher = FakeFetcher::new(vec![test_module("module").into_inner()]);
-        let mut block_cache = BlockModuleCache::new(&vm_cache, fetcher);
+        let block_allocator = Arena::new();
+        let mut block_cache = BlockModuleCache::new(&vm_cache, &block_allocator, fetcher);
 
         // Make sure the block cache fetches the code from the view.
         let func1 = block_cache
@@ -523,11 +525,12 @@ fn test_same_module_struct_resolution() {
 
     let module = parse_and_compile_modules(code);
     let fetcher = FakeFetcher::new(module);
-    let block_cache = BlockModuleCache::new(&vm_cache, fetcher);
+    let block_allocator = Arena::new();
+    let block_cache = BlockModuleCache::new(&vm_cache, &block_allocator, fetcher);
     {
         let module_id = ModuleId::new(AccountAddress::default(), ident("M1"));
         let module_ref = block_cache.get_loaded_module(&module_id).unwrap().unwrap();
-        let gas = GasMeter::new(GasUnits::new(100_000_000));
+        let gas = GasMeter::new(GasUnits::new(100_000_000), &DEFAULT_GAS_SCHEDULE);
         let struct_x = block_cache
             .resolve_struct_def(module_ref, StructDefinitionIndex::new(0), &gas)
             .unwrap()
@@ -569,7 +572,8 @@ fn test_multi_module_struct_resolution() {
 
     let module = parse_and_compile_modules(&code);
     let fetcher = FakeFetcher::new(module);
-    let block_cache = BlockModuleCache::new(&vm_cache, fetcher);
+    let block_allocator = Arena::new();
+    let block_cache = BlockModuleCache::new(&vm_cache, &block_allocator, fetcher);
     {
         let module_id_2 = ModuleId::new(AccountAddress::default(), ident("M2"));
         let module2_ref = block_cache
@@ -577,7 +581,7 @@ fn test_multi_module_struct_resolution() {
             .unwrap()
             .unwrap();
 
-        let gas = GasMeter::new(GasUnits::new(100_000_000));
+        let gas = GasMeter::new(GasUnits::new(100_000_000), &DEFAULT_GAS_SCHEDULE);
         let struct_t = block_cache
             .resolve_struct_def(module2_ref, StructDefinitionIndex::new(0), &gas)
             .unwrap()
@@ -608,7 +612,8 @@ fn test_field_offset_resolution() {
 
     let module = parse_and_compile_modules(code);
     let fetcher = FakeFetcher::new(module);
-    let block_cache = BlockModuleCache::new(&vm_cache, fetcher);
+    let block_allocator = Arena::new();
+    let block_cache = BlockModuleCache::new(&vm_cache, &block_allocator, fetcher);
     {
         let module_id = ModuleId::new(AccountAddress::default(), ident("M1"));
         let module_ref = block_cache.get_loaded_module(&module_id).unwrap().unwrap();
@@ -660,10 +665,83 @@ fn test_dependency_fails_verification() {
 
     let module = parse_and_compile_modules(code);
     let fetcher = FakeFetcher::new(module);
-    let block_cache = BlockModuleCache::new(&vm_cache, fetcher);
+    let block_allocator = Arena::new();
+    let block_cache = BlockModuleCache::new(&vm_cache, &block_allocator, fetcher);
 
     let module_id = ModuleId::new(AccountAddress::default(), ident("Test"));
     let err = block_cache.get_loaded_module(&module_id).unwrap_err();
     assert!(err.is(StatusType::Verification));
     assert!(err.major_status == StatusCode::INVALID_RESOURCE_FIELD);
 }
+
+#[test]
+fn test_upgraded_module_scoped_to_block() {
+    let allocator = Arena::new();
+    let vm_cache = VMModuleCache::new(&allocator);
+
+    let mut versions = vec!["", "public g() { return; }"]
+        .into_iter()
+        .map(|extra_function| {
+            let code = format!(
+                "
+                modules:
+                module M {{
+                    public f() {{ return; }}
+                    {}
+                }}
+                script:
+                main() {{
+                    return;
+                }}
+                ",
+                extra_function
+            );
+            parse_and_compile_modules(&code).remove(0)
+        });
+    let module_v1 = versions.next().unwrap();
+    let module_v2 = versions.next().unwrap();
+    let module_id = ModuleId::new(AccountAddress::default(), ident("M"));
+    let has_g = |module: &LoadedModule| module.function_defs_table.contains_key(&ident("g"));
+
+    {
+        let block_allocator = Arena::new();
+        let fetcher = FakeFetcher::new(vec![module_v1.clone()]);
+        let block_cache = BlockModuleCache::new(&vm_cache, &block_allocator, fetcher);
+        let module = block_cache.get_loaded_module(&module_id).unwrap().unwrap();
+        assert!(!has_g(module));
+
+        // The upgrade is visible to the rest of the block, but not to the VM cache.
+        let upgraded = VerifiedModule::new(module_v2.clone()).unwrap();
+        block_cache.reclaim_cached_module(vec![LoadedModule::new(upgraded)]);
+        let module = block_cache.get_loaded_module(&module_id).unwrap().unwrap();
+        assert!(has_g(module));
+        let module = vm_cache.get_loaded_module(&module_id).unwrap().unwrap();
+        assert!(!has_g(module));
+    }
+
+    // Once the upgrade is in storage, the outdated version in the VM cache isn't used.
+    let upgraded_module = {
+        let block_allocator = Arena::new();
+        let fetcher = FakeFetcher::new(vec![module_v2.clone()]);
+        let block_cache = BlockModuleCache::new(&vm_cache, &block_allocator, fetcher);
+        let module = block_cache.get_loaded_module(&module_id).unwrap().unwrap();
+        assert!(has_g(module));
+        module as *const LoadedModule
+    };
+
+    // The upgrade is kept in the VM cache, so the following blocks don't load it again.
+    {
+        let block_allocator = Arena::new();
+        let fetcher = FakeFetcher::new(vec![module_v2]);
+        let block_cache = BlockModuleCache::new(&vm_cache, &block_allocator, fetcher);
+        let module = block_cache.get_loaded_module(&module_id).unwrap().unwrap();
+        assert_eq!(module as *const LoadedModule, upgraded_module);
+    }
+
+    // A block executed against a state without the upgrade still gets the previous version.
+    let block_allocator = Arena::new();
+    let fetcher = FakeFetcher::new(vec![module_v1]);
+    let block_cache = BlockModuleCache::new(&vm_cache, &block_allocator, fetcher);
+    let module = block_cache.get_loaded_module(&module_id).unwrap().unwrap();
+    assert!(!has_g(module));
+}
@@ -579,6 +579,132 @@ fn test_arith_instructions() {
     );
 }
 
+#[test]
+fn test_sized_integer_instructions() {
+    let allocator = Arena::new();
+    let module_cache = VMModuleCache::new(&allocator);
+    let main_module = fake_script().into_module();
+    let loaded_main = LoadedModule::new(main_module);
+    let entry_func = FunctionRef::new(&loaded_main, CompiledScript::MAIN_INDEX);
+    let data_cache = FakeDataCache::new();
+
+    let mut vm =
+        TransactionExecutor::new(module_cache, &data_cache, TransactionMetadata::default());
+
+    vm.execution_stack
+        .push_frame(entry_func)
+        .expect("push to empty execution stack should succeed");
+
+    test_binop_instruction(
+        &mut vm,
+        Bytecode::Add,
+        vec![Value::u8(1), Value::u8(2)],
+        Value::u8(3),
+    );
+    test_binop_instruction_overflow(
+        &mut vm,
+        Bytecode::Add,
+        vec![Value::u8(u8::max_value()), Value::u8(1)],
+    );
+    test_binop_instruction(
+        &mut vm,
+        Bytecode::Mul,
+        vec![Value::u128(u128::from(u64::max_value())), Value::u128(2)],
+        Value::u128(u128::from(u64::max_value()) * 2),
+    );
+    test_binop_instruction_overflow(
+        &mut vm,
+        Bytecode::Mul,
+        vec![Value::u128(u128::max_value() / 2), Value::u128(3)],
+    );
+    test_binop_instruction(
+        &mut vm,
+        Bytecode::Lt,
+        vec![Value::u128(5), Value::u128(6)],
+        Value::bool(true),
+    );
+    test_binop_instruction(
+        &mut vm,
+        Bytecode::Eq,
+        vec![Value::u8(5), Value::u8(5)],
+        Value::bool(true),
+    );
+
+    test_simple_instruction(
+        &mut vm,
+        Bytecode::LdU8(7),
+        vec![],
+        vec![Value::u8(7)],
+        Locals::new(0),
+        Locals::new(0),
+        1,
+    );
+    test_simple_instruction(
+        &mut vm,
+        Bytecode::LdU128(u128::max_value()),
+        vec![],
+        vec![Value::u128(u128::max_value())],
+        Locals::new(0),
+        Locals::new(0),
+        1,
+    );
+    test_simple_instruction(
+        &mut vm,
+        Bytecode::CastU8,
+        vec![Value::u128(255)],
+        vec![Value::u8(255)],
+        Locals::new(0),
+        Locals::new(0),
+        1,
+    );
+    test_simple_instruction(
+        &mut vm,
+        Bytecode::CastU64,
+        vec![Value::u8(255)],
+        vec![Value::u64(255)],
+        Locals::new(0),
+        Locals::new(0),
+        1,
+    );
+    test_simple_instruction(
+        &mut vm,
+        Bytecode::CastU128,
+        vec![Value::u64(u64::max_value())],
+        vec![Value::u128(u128::from(u64::max_value()))],
+        Locals::new(0),
+        Locals::new(0),
+        1,
+    );
+    assert_eq!(
+        test_simple_instruction_impl(
+            &mut vm,
+            Bytecode::CastU8,
+            vec![Value::u64(256)],
+            vec![],
+            Locals::new(0),
+            Locals::new(0),
+            1,
+        )
+        .unwrap_err()
+        .major_status,
+        StatusCode::ARITHMETIC_ERROR
+    );
+    assert_eq!(
+        test_simple_instruction_impl(
+            &mut vm,
+            Bytecode::CastU64,
+            vec![Value::u128(u128::from(u64::max_value()) + 1)],
+            vec![],
+            Locals::new(0),
+            Locals::new(0),
+            1,
+        )
+        .unwrap_err()
+        .major_status,
+        StatusCode::ARITHMETIC_ERROR
+    );
+}
+
 fn fake_module_with_calls(sigs: Vec<(Vec<SignatureToken>, FunctionSignature)>) -> VerifiedModule {
     let mut names: Vec<Identifier> = sigs
         .iter()
@@ -628,6 +754,7 @@ fn fake_module_with_calls(sigs: Vec<(Vec<SignatureToken>, FunctionSignature)>) -
         user_strings: vec![],
         byte_array_pool: vec![],
         address_pool: vec![AccountAddress::default()],
+        abort_codes: vec![],
     }
     .freeze()
     .expect("test module should satisfy the bounds checker");
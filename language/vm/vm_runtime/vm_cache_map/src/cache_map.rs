@@ -13,7 +13,8 @@ pub type CacheRefMap<'a, K, V> = CacheMap<'a, K, V, &'a V>;
 /// borrowed out.
 ///
 /// TODO: Entry-like API? Current one is somewhat awkward to use.
-/// TODO: eviction -- how to do it safely?
+/// TODO: eviction -- removed values stay allocated until the arena is dropped, how to free them
+/// earlier safely?
 /// TODO: should the map own the arena?
 pub struct CacheMap<'a, K, V, W> {
     alloc: &'a Arena<V>,
@@ -43,11 +44,24 @@ where
         self.map.get(key).map(|value| (*value).clone())
     }
 
+    /// Remove the given key from the map, returning its value if it was present.
+    ///
+    /// The value stays allocated until the arena is dropped, so references to it that were handed
+    /// out earlier remain valid. The next insert for this key stores a new value.
+    #[inline]
+    pub fn remove<Q: ?Sized>(&self, key: &Q) -> Option<W>
+    where
+        K: Borrow<Q>,
+        Q: Hash + PartialEq,
+    {
+        self.map.remove(key)
+    }
+
     /// Try inserting the value V if missing. The insert function is not called if the value is
     /// present.
     ///
     /// The first value is picked to avoid multiple cached results floating around. This assumes
-    /// that the cache is immutable (i.e. there's no invalidation).
+    /// that a cached value is only replaced after an explicit `remove`.
     ///
     /// Returns a reference to the inserted value.
     pub fn or_insert_with_transform<F, G>(&self, key: K, insert: F, transform: G) -> W
@@ -113,7 +127,7 @@ where
     /// Insert the value if not present. Discard the value if present.
     ///
     /// The first value is picked to avoid multiple cached results floating around. This assumes
-    /// that the cache is immutable (i.e. there's no invalidation).
+    /// that a cached value is only replaced after an explicit `remove`.
     ///
     /// Returns the address of the inserted value.
     #[inline]
@@ -128,6 +142,21 @@ where
     {
         self.or_insert_with_transform(key, insert, |value_ref| value_ref)
     }
+
+    /// Insert a reference to a value allocated elsewhere if not present, so that the same value
+    /// can be looked up through several maps. Discard the reference if present.
+    ///
+    /// Returns the address of the value in the map.
+    pub fn or_insert_ref(&self, key: K, value: &'a V) -> &'a V {
+        let mut ret: Option<&'a V> = None;
+        let ret_mut = &mut ret;
+        self.map.alter(key, move |old_value| {
+            let value = old_value.unwrap_or(value);
+            ret_mut.replace(value);
+            Some(value)
+        });
+        ret.expect("return value should always be initialized")
+    }
 }
 
 #[test]
@@ -42,6 +42,38 @@ proptest! {
         }
     }
 
+    #[test]
+    fn remove(kv_lists in hash_map(".*", vec(".*", 2), 0..100)) {
+        let arena = Arena::new();
+        let map = CacheRefMap::new(&arena);
+        for (key, values) in kv_lists {
+            let first = map.or_insert(key.clone(), values[0].clone());
+            prop_assert_eq!(map.remove(&key), Some(&values[0]));
+            prop_assert_eq!(map.get(&key), None);
+
+            // The removed value is still allocated and a new value can be inserted.
+            prop_assert_eq!(first, &values[0]);
+            prop_assert_eq!(map.or_insert(key.clone(), values[1].clone()), &values[1]);
+            prop_assert_eq!(map.get(&key), Some(&values[1]));
+        }
+    }
+
+    #[test]
+    fn or_insert_ref(kv_lists in hash_map(".*", vec(".*", 2), 0..100)) {
+        let arena = Arena::new();
+        let map = CacheRefMap::new(&arena);
+        let other_map = CacheRefMap::new(&arena);
+        for (key, values) in kv_lists {
+            let first = other_map.or_insert(key.clone(), values[0].clone());
+            prop_assert!(std::ptr::eq(map.or_insert_ref(key.clone(), first), first));
+            prop_assert!(std::ptr::eq(map.get(&key).unwrap(), first));
+
+            // Further references for the same key should be ignored.
+            let second = arena.alloc(values[1].clone());
+            prop_assert!(std::ptr::eq(map.or_insert_ref(key.clone(), second), first));
+        }
+    }
+
     #[test]
     fn or_insert_many_threads(kv_lists in hash_map(".*", vec(".*", NUM_THREADS), 0..50)) {
         // Try inserting to the list concurrently with NUM_THREADS threads.
@@ -1,9 +1,9 @@
 // Copyright (c) The Libra Core Contributors
 // SPDX-License-Identifier: Apache-2.0
 
-use super::{hash, primitive_helpers, signature};
+use super::{gas_schedule, hash, merkle, primitive_helpers, signature};
 use crate::{
-    native_structs::{dispatch::dispatch_native_struct, vector::NativeVector},
+    native_structs::{dispatch::dispatch_native_struct, map::NativeMap, vector::NativeVector},
     value::Value,
 };
 use libra_types::{
@@ -100,6 +100,19 @@ fn tstruct(
     SignatureToken::Struct(idx, args)
 }
 
+/// Helper for the `Map.T<K, V>` type used by the `Map` native functions
+fn tmap(addr: AccountAddress) -> SignatureToken {
+    tstruct(
+        addr,
+        "Map",
+        "T",
+        vec![
+            SignatureToken::TypeParameter(0),
+            SignatureToken::TypeParameter(1),
+        ],
+    )
+}
+
 type NativeFunctionMap = HashMap<ModuleId, HashMap<Identifier, NativeFunction>>;
 
 lazy_static! {
@@ -118,6 +131,11 @@ lazy_static! {
             vec![ByteArray],
             vec![ByteArray]
         );
+        add!(m, addr, "Hash", "keccak_256",
+            hash::native_keccak_256,
+            vec![ByteArray],
+            vec![ByteArray]
+        );
         // Signature
         add!(m, addr, "Signature", "ed25519_verify",
             signature::native_ed25519_signature_verification,
@@ -129,6 +147,28 @@ lazy_static! {
             vec![ByteArray, ByteArray, ByteArray, ByteArray],
             vec![U64]
         );
+        add!(m, addr, "Signature", "bls12381_verify",
+            signature::native_bls12381_signature_verification,
+            vec![ByteArray, ByteArray, ByteArray],
+            vec![Bool]
+        );
+        add!(m, addr, "Signature", "bls12381_aggregate_verify",
+            signature::native_bls12381_aggregate_signature_verification,
+            vec![ByteArray, ByteArray, ByteArray],
+            vec![Bool]
+        );
+        // GasSchedule
+        add!(m, addr, "GasSchedule", "is_valid",
+            gas_schedule::native_is_valid,
+            vec![ByteArray],
+            vec![Bool]
+        );
+        // MerkleProof
+        add!(m, addr, "MerkleProof", "verify_sparse_merkle_inclusion",
+            merkle::native_verify_sparse_merkle_inclusion,
+            vec![ByteArray, ByteArray, ByteArray, ByteArray],
+            vec![Bool]
+        );
         // AddressUtil
         add!(m, addr, "AddressUtil", "address_to_bytes",
             primitive_helpers::native_address_to_bytes,
@@ -220,6 +260,55 @@ lazy_static! {
             vec![ByteArray, U64, TypeParameter(0)],
             vec![]
         );
+        // Map
+        add!(m, addr, "Map", "empty",
+            NativeMap::native_empty,
+            vec![Kind::Unrestricted, Kind::All],
+            vec![],
+            vec![tmap(addr)]
+        );
+        add!(m, addr, "Map", "length",
+            NativeMap::native_length,
+            vec![Kind::Unrestricted, Kind::All],
+            vec![Reference(Box::new(tmap(addr)))],
+            vec![U64]
+        );
+        add!(m, addr, "Map", "contains",
+            NativeMap::native_contains,
+            vec![Kind::Unrestricted, Kind::All],
+            vec![Reference(Box::new(tmap(addr))), TypeParameter(0)],
+            vec![Bool]
+        );
+        add!(m, addr, "Map", "insert",
+            NativeMap::native_insert,
+            vec![Kind::Unrestricted, Kind::All],
+            vec![MutableReference(Box::new(tmap(addr))), TypeParameter(0), TypeParameter(1)],
+            vec![]
+        );
+        add!(m, addr, "Map", "remove",
+            NativeMap::native_remove,
+            vec![Kind::Unrestricted, Kind::All],
+            vec![MutableReference(Box::new(tmap(addr))), TypeParameter(0)],
+            vec![TypeParameter(1)]
+        );
+        add!(m, addr, "Map", "borrow",
+            NativeMap::native_borrow,
+            vec![Kind::Unrestricted, Kind::All],
+            vec![Reference(Box::new(tmap(addr))), TypeParameter(0)],
+            vec![Reference(Box::new(TypeParameter(1)))]
+        );
+        add!(m, addr, "Map", "borrow_mut",
+            NativeMap::native_borrow,
+            vec![Kind::Unrestricted, Kind::All],
+            vec![MutableReference(Box::new(tmap(addr))), TypeParameter(0)],
+            vec![MutableReference(Box::new(TypeParameter(1)))]
+        );
+        add!(m, addr, "Map", "destroy_empty",
+            NativeMap::native_destroy_empty,
+            vec![Kind::Unrestricted, Kind::All],
+            vec![tmap(addr)],
+            vec![]
+        );
         m
     };
 }
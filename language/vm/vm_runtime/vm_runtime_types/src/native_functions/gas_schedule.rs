@@ -0,0 +1,27 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use super::dispatch::NativeReturnStatus;
+use crate::value::Value;
+use libra_types::byte_array::ByteArray;
+use std::collections::VecDeque;
+use vm::gas_schedule::GasSchedule;
+
+const IS_VALID_COST: u64 = 1;
+
+/// Checks that the bytes decode to a valid gas schedule, so that an invalid one is never
+/// published and the VM doesn't have to fall back to the default schedule.
+pub fn native_is_valid(mut arguments: VecDeque<Value>) -> NativeReturnStatus {
+    if arguments.len() != 1 {
+        return NativeReturnStatus::InvalidArguments;
+    }
+    let schedule = pop_arg!(arguments, ByteArray);
+    let cost = IS_VALID_COST * schedule.len() as u64;
+
+    let is_valid = GasSchedule::from_bytes(schedule.as_bytes()).is_ok();
+    let return_values = vec![Value::bool(is_valid)];
+    NativeReturnStatus::Success {
+        cost,
+        return_values,
+    }
+}
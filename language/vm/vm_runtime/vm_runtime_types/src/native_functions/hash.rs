@@ -6,10 +6,12 @@ use crate::value::Value;
 use crypto::HashValue;
 use libra_types::byte_array::ByteArray;
 use sha2::{Digest, Sha256};
+use sha3::Keccak256;
 use std::collections::VecDeque;
 
 const SHA2_COST: u64 = 30;
 const SHA3_COST: u64 = 30;
+const KECCAK_COST: u64 = 30;
 
 pub fn native_sha2_256(mut arguments: VecDeque<Value>) -> NativeReturnStatus {
     if arguments.len() != 1 {
@@ -40,3 +42,19 @@ pub fn native_sha3_256(mut arguments: VecDeque<Value>) -> NativeReturnStatus {
         return_values,
     }
 }
+
+/// Keccak-256 as used by Ethereum, which differs from the standardized SHA3-256 in its padding.
+pub fn native_keccak_256(mut arguments: VecDeque<Value>) -> NativeReturnStatus {
+    if arguments.len() != 1 {
+        return NativeReturnStatus::InvalidArguments;
+    }
+    let hash_arg = pop_arg!(arguments, ByteArray);
+    let cost = KECCAK_COST * hash_arg.len() as u64;
+
+    let hash_vec = Keccak256::digest(hash_arg.as_bytes()).to_vec();
+    let return_values = vec![Value::byte_array(ByteArray::new(hash_vec))];
+    NativeReturnStatus::Success {
+        cost,
+        return_values,
+    }
+}
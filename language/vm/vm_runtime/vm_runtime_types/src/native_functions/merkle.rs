@@ -0,0 +1,73 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use super::dispatch::NativeReturnStatus;
+use crate::value::Value;
+use crypto::{hash::SPARSE_MERKLE_PLACEHOLDER_HASH, HashValue};
+use libra_types::{byte_array::ByteArray, proof::SparseMerkleProof};
+use std::collections::VecDeque;
+
+const SPARSE_MERKLE_PROOF_COST: u64 = 30;
+
+/// Starting error code number
+const DEFAULT_ERROR_CODE: u64 = 0x0005_3D7E;
+/// The root hash, the key or the value hash isn't a 32 bytes hash
+const INVALID_HASH_LENGTH_FAILURE: u64 = DEFAULT_ERROR_CODE + 1;
+/// The siblings aren't a concatenation of at most 256 hashes
+const INVALID_SIBLINGS_LENGTH_FAILURE: u64 = DEFAULT_ERROR_CODE + 2;
+
+/// Verify that a sparse Merkle tree with root `root_hash` contains a leaf for `key` whose value
+/// hashes to `value_hash`. `siblings` is the concatenation of the hashes of the siblings on the
+/// path from the root to the leaf, the sibling nearest to the root first.
+pub fn native_verify_sparse_merkle_inclusion(mut arguments: VecDeque<Value>) -> NativeReturnStatus {
+    if arguments.len() != 4 {
+        return NativeReturnStatus::InvalidArguments;
+    }
+    let siblings = pop_arg!(arguments, ByteArray);
+    let value_hash = pop_arg!(arguments, ByteArray);
+    let key = pop_arg!(arguments, ByteArray);
+    let root_hash = pop_arg!(arguments, ByteArray);
+
+    let num_of_siblings = siblings.len() / HashValue::LENGTH;
+    let cost = SPARSE_MERKLE_PROOF_COST * (num_of_siblings as u64 + 1);
+
+    let (root_hash, key, value_hash) = match (
+        HashValue::from_slice(root_hash.as_bytes()),
+        HashValue::from_slice(key.as_bytes()),
+        HashValue::from_slice(value_hash.as_bytes()),
+    ) {
+        (Ok(root_hash), Ok(key), Ok(value_hash)) => (root_hash, key, value_hash),
+        _ => {
+            return NativeReturnStatus::Aborted {
+                cost,
+                error_code: INVALID_HASH_LENGTH_FAILURE,
+            }
+        }
+    };
+    if siblings.len() % HashValue::LENGTH != 0 || num_of_siblings > HashValue::LENGTH_IN_BITS {
+        return NativeReturnStatus::Aborted {
+            cost,
+            error_code: INVALID_SIBLINGS_LENGTH_FAILURE,
+        };
+    }
+    let siblings: Vec<HashValue> = siblings
+        .as_bytes()
+        .chunks(HashValue::LENGTH)
+        .map(|sibling| {
+            // The length was checked above, so this can't fail.
+            HashValue::from_slice(sibling).expect("sibling should be a hash")
+        })
+        .collect();
+
+    // A proof whose bottom sibling is the placeholder is never produced, as the leaf would have
+    // been moved up the tree. `SparseMerkleProof::new` refuses such a proof.
+    let bool_value = siblings.last() != Some(&*SPARSE_MERKLE_PLACEHOLDER_HASH)
+        && SparseMerkleProof::new(Some((key, value_hash)), siblings)
+            .verify_by_hash(root_hash, key, Some(value_hash))
+            .is_ok();
+    let return_values = vec![Value::bool(bool_value)];
+    NativeReturnStatus::Success {
+        cost,
+        return_values,
+    }
+}
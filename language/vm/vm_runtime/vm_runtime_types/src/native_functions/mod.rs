@@ -3,6 +3,12 @@
 
 #[macro_use]
 pub mod dispatch;
+pub mod gas_schedule;
 pub mod hash;
+pub mod merkle;
 pub mod primitive_helpers;
 pub mod signature;
+
+#[cfg(test)]
+#[path = "../unit_tests/native_functions_tests.rs"]
+mod native_functions_tests;
@@ -5,6 +5,9 @@ use super::dispatch::NativeReturnStatus;
 use crate::value::Value;
 use bit_vec::BitVec;
 use crypto::{
+    bls12381::{
+        BLS12381PublicKey, BLS12381Signature, BLS12381_PUBLIC_KEY_LENGTH, BLS12381_SIGNATURE_LENGTH,
+    },
     ed25519::{self, Ed25519PublicKey, Ed25519Signature},
     traits::*,
     HashValue,
@@ -15,6 +18,13 @@ use std::{collections::VecDeque, convert::TryFrom};
 // TODO: Talk to Crypto to determine these costs
 const ED25519_COST: u64 = 35;
 const BATCH_ED25519_COST: u64 = 30;
+const BLS12381_COST: u64 = 5000;
+const BLS12381_COST_PER_BYTE: u64 = 80;
+const BLS12381_AGGREGATE_COST: u64 = 50;
+// Decompressing a point and checking it is in the prime order subgroup, for every public key (G1)
+// and signature (G2) of an aggregate verification: about a tenth and a fifth of a verification.
+const BLS12381_PUBLIC_KEY_DECOMPRESSION_COST: u64 = 500;
+const BLS12381_SIGNATURE_DECOMPRESSION_COST: u64 = 1000;
 
 const BITMAP_SIZE: usize = 32;
 
@@ -39,6 +49,15 @@ const OVERSIZED_PUBLIC_KEY_SIZE_FAILURE: u64 = DEFAULT_ERROR_CODE + 8;
 /// Concatenated Ed25519 public keys should be a multiple of 32 bytes
 const INVALID_PUBLIC_KEY_SIZE_FAILURE: u64 = DEFAULT_ERROR_CODE + 9;
 
+/// Starting error code number for BLS12-381 signatures
+const BLS12381_ERROR_CODE: u64 = 0x0B15_1238;
+/// Public keys deserialization error
+const BLS12381_PUBLIC_KEY_DESERIALIZATION_FAILURE: u64 = BLS12381_ERROR_CODE + 1;
+/// Signatures deserialization error
+const BLS12381_SIGNATURE_DESERIALIZATION_FAILURE: u64 = BLS12381_ERROR_CODE + 2;
+/// No signature, or a mismatch between the number of signatures and public keys
+const BLS12381_SIGNATURE_SIZE_FAILURE: u64 = BLS12381_ERROR_CODE + 3;
+
 pub fn native_ed25519_signature_verification(mut arguments: VecDeque<Value>) -> NativeReturnStatus {
     if arguments.len() != 3 {
         return NativeReturnStatus::InvalidArguments;
@@ -76,6 +95,124 @@ pub fn native_ed25519_signature_verification(mut arguments: VecDeque<Value>) ->
     }
 }
 
+pub fn native_bls12381_signature_verification(
+    mut arguments: VecDeque<Value>,
+) -> NativeReturnStatus {
+    if arguments.len() != 3 {
+        return NativeReturnStatus::InvalidArguments;
+    }
+    let msg = pop_arg!(arguments, ByteArray);
+    let pubkey = pop_arg!(arguments, ByteArray);
+    let signature = pop_arg!(arguments, ByteArray);
+
+    let cost = bls12381_signature_verification_cost(1, msg.len());
+
+    let sig = match BLS12381Signature::try_from(signature.as_bytes()) {
+        Ok(sig) => sig,
+        Err(_) => {
+            return NativeReturnStatus::Aborted {
+                cost,
+                error_code: BLS12381_SIGNATURE_DESERIALIZATION_FAILURE,
+            }
+        }
+    };
+    let pk = match BLS12381PublicKey::try_from(pubkey.as_bytes()) {
+        Ok(pk) => pk,
+        Err(_) => {
+            return NativeReturnStatus::Aborted {
+                cost,
+                error_code: BLS12381_PUBLIC_KEY_DESERIALIZATION_FAILURE,
+            }
+        }
+    };
+
+    let bool_value = sig.verify_arbitrary_msg(msg.as_bytes(), &pk).is_ok();
+    let return_values = vec![Value::bool(bool_value)];
+    NativeReturnStatus::Success {
+        cost,
+        return_values,
+    }
+}
+
+/// Verify a concatenation of signatures of `message` against the concatenation of the public
+/// keys of their signers, in the same order.
+///
+/// The signatures and the public keys are aggregated and checked with a single pairing, which is
+/// only sound if the public keys were registered with a proof of possession of their private key
+/// (see `Signature.bls12381_aggregate_verify`).
+pub fn native_bls12381_aggregate_signature_verification(
+    mut arguments: VecDeque<Value>,
+) -> NativeReturnStatus {
+    if arguments.len() != 3 {
+        return NativeReturnStatus::InvalidArguments;
+    }
+    let message = pop_arg!(arguments, ByteArray);
+    let public_keys = pop_arg!(arguments, ByteArray);
+    let signatures = pop_arg!(arguments, ByteArray);
+
+    let num_of_sigs = signatures.len() / BLS12381_SIGNATURE_LENGTH;
+    let cost = bls12381_aggregate_signature_verification_cost(num_of_sigs as u64, message.len());
+    if num_of_sigs == 0
+        || signatures.len() % BLS12381_SIGNATURE_LENGTH != 0
+        || public_keys.len() != num_of_sigs * BLS12381_PUBLIC_KEY_LENGTH
+    {
+        return NativeReturnStatus::Aborted {
+            cost,
+            error_code: BLS12381_SIGNATURE_SIZE_FAILURE,
+        };
+    }
+
+    let sigs: ::std::result::Result<Vec<_>, _> = signatures
+        .as_bytes()
+        .chunks(BLS12381_SIGNATURE_LENGTH)
+        .map(BLS12381Signature::try_from)
+        .collect();
+    let sigs = match sigs {
+        Ok(sigs) => sigs,
+        Err(_) => {
+            return NativeReturnStatus::Aborted {
+                cost,
+                error_code: BLS12381_SIGNATURE_DESERIALIZATION_FAILURE,
+            }
+        }
+    };
+    let keys: ::std::result::Result<Vec<_>, _> = public_keys
+        .as_bytes()
+        .chunks(BLS12381_PUBLIC_KEY_LENGTH)
+        .map(BLS12381PublicKey::try_from)
+        .collect();
+    let keys = match keys {
+        Ok(keys) => keys,
+        Err(_) => {
+            return NativeReturnStatus::Aborted {
+                cost,
+                error_code: BLS12381_PUBLIC_KEY_DESERIALIZATION_FAILURE,
+            }
+        }
+    };
+
+    let bool_value = BLS12381Signature::verify_aggregate(&sigs, message.as_bytes(), &keys).is_ok();
+    let return_values = vec![Value::bool(bool_value)];
+    NativeReturnStatus::Success {
+        cost,
+        return_values,
+    }
+}
+
+/// An aggregate verification is charged a single verification, plus the deserialization and the
+/// aggregation of every signature and public key.
+fn bls12381_aggregate_signature_verification_cost(num_of_sigs: u64, message_len: usize) -> u64 {
+    let cost_per_signer = BLS12381_PUBLIC_KEY_DECOMPRESSION_COST
+        + BLS12381_SIGNATURE_DECOMPRESSION_COST
+        + BLS12381_AGGREGATE_COST;
+    bls12381_signature_verification_cost(1, message_len) + cost_per_signer * num_of_sigs
+}
+
+/// Every BLS12-381 signature is charged a base cost, on top of the cost of the message bytes.
+fn bls12381_signature_verification_cost(num_of_sigs: u64, message_len: usize) -> u64 {
+    num_of_sigs * (BLS12381_COST + BLS12381_COST_PER_BYTE * message_len as u64)
+}
+
 /// Batch verify a collection of signatures using a bitmap for matching signatures to keys.
 pub fn native_ed25519_threshold_signature_verification(
     mut arguments: VecDeque<Value>,
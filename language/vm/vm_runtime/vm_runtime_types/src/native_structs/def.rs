@@ -1,6 +1,6 @@
 use crate::{
     loaded_data::{struct_def::StructDef, types::Type},
-    native_structs::vector::NativeVector,
+    native_structs::{map::NativeMap, vector::NativeVector},
 };
 use serde::{ser, Deserialize, Serialize};
 use vm::gas_schedule::{AbstractMemorySize, GasCarrier};
@@ -8,6 +8,7 @@ use vm::gas_schedule::{AbstractMemorySize, GasCarrier};
 #[derive(Debug, Eq, PartialEq, Clone, Copy, Serialize, Deserialize)]
 pub enum NativeStructTag {
     Vector = 0,
+    Map = 1,
 }
 
 #[derive(Debug, Eq, PartialEq, Clone, Serialize, Deserialize)]
@@ -19,6 +20,7 @@ pub struct NativeStructType {
 #[derive(Debug, Clone, Eq, PartialEq)]
 pub enum NativeStructValue {
     Vector(NativeVector),
+    Map(NativeMap),
 }
 
 // TODO(#1307)
@@ -29,6 +31,7 @@ impl ser::Serialize for NativeStructValue {
     {
         match self {
             NativeStructValue::Vector(v) => v.serialize(serializer),
+            NativeStructValue::Map(m) => m.serialize(serializer),
         }
     }
 }
@@ -53,6 +56,7 @@ impl NativeStructValue {
     pub fn size(&self) -> AbstractMemorySize<GasCarrier> {
         match self {
             NativeStructValue::Vector(v) => v.size(),
+            NativeStructValue::Map(m) => m.size(),
         }
     }
 
@@ -67,6 +71,18 @@ impl NativeStructValue {
                     .map(|v| v.to_type_FOR_TESTING())
                     .unwrap_or(Type::Bool),
             )),
+            NativeStructValue::Map(m) => {
+                let (key_type, value_type) = m
+                    .0
+                    .values()
+                    .next()
+                    .map(|(key, value)| (key.to_type_FOR_TESTING(), value.to_type_FOR_TESTING()))
+                    .unwrap_or((Type::Bool, Type::Bool));
+                StructDef::Native(NativeStructType::new(
+                    NativeStructTag::Map,
+                    vec![key_type, value_type],
+                ))
+            }
         }
     }
 }
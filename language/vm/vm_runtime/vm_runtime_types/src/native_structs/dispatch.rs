@@ -72,6 +72,15 @@ lazy_static! {
             vec![Kind::All],
             NativeStructTag::Vector
         );
+        add!(
+            m,
+            addr,
+            "Map",
+            "T",
+            false,
+            vec![Kind::Unrestricted, Kind::All],
+            NativeStructTag::Map
+        );
         m
     };
 }
@@ -0,0 +1,234 @@
+use crate::{
+    native_functions::dispatch::NativeReturnStatus,
+    native_structs::NativeStructValue,
+    value::{MutVal, ReferenceValue, Value},
+};
+use libra_types::vm_error::sub_status::NFE_MAP_ERROR_BASE;
+use serde::{Serialize, Serializer};
+use std::{
+    collections::{BTreeMap, VecDeque},
+    ops::Add,
+};
+use vm::gas_schedule::{AbstractMemorySize, GasAlgebra, GasCarrier, STRUCT_SIZE};
+
+/// A map from keys to values.
+///
+/// Entries are indexed by the canonical serialization of their key, which gives the map a
+/// deterministic iteration order. The map serializes as its number of entries followed by the
+/// key and value of every entry, in that order.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct NativeMap(pub(crate) BTreeMap<Vec<u8>, (MutVal, MutVal)>);
+
+// Operations looking up a key are charged per byte of the serialized key.
+const BORROW_COST: u64 = 4; // TODO: determine experimentally
+const CONTAINS_COST: u64 = 4; // TODO: determine experimentally
+const INSERT_COST: u64 = 4; // TODO: determine experimentally
+const REMOVE_COST: u64 = 4; // TODO: determine experimentally
+const EMPTY_COST: u64 = 30; // TODO: determine experimentally
+const LENGTH_COST: u64 = 30; // TODO: determine experimentally
+const DESTROY_EMPTY_MAP_COST: u64 = 30; // TODO: determine experimentally
+
+pub const KEY_ALREADY_EXISTS: u64 = NFE_MAP_ERROR_BASE + 1;
+pub const KEY_NOT_FOUND: u64 = NFE_MAP_ERROR_BASE + 2;
+pub const DESTROY_NON_EMPTY_MAP: u64 = NFE_MAP_ERROR_BASE + 3;
+
+fn get_mut_map(v: &mut NativeStructValue) -> Option<&mut NativeMap> {
+    match v {
+        NativeStructValue::Map(m) => Some(m),
+        _ => None,
+    }
+}
+
+fn get_map(v: &NativeStructValue) -> Option<&NativeMap> {
+    match v {
+        NativeStructValue::Map(m) => Some(m),
+        _ => None,
+    }
+}
+
+macro_rules! get_map_ref {
+    ($args: expr) => {
+        match $args
+            .pop_front()
+            .and_then(|v| v.value_as::<ReferenceValue>())
+        {
+            Some(v) => v,
+            None => return NativeReturnStatus::InvalidArguments,
+        }
+    };
+}
+
+/// Pops the key argument, returning the key along with its canonical serialization.
+macro_rules! pop_key {
+    ($args: expr) => {
+        match $args
+            .pop_front()
+            .and_then(|key| Some((key.simple_serialize()?, key)))
+        {
+            Some(key) => key,
+            None => return NativeReturnStatus::InvalidArguments,
+        }
+    };
+}
+
+fn key_cost(cost_per_byte: u64, key_bytes: &[u8]) -> u64 {
+    cost_per_byte * key_bytes.len() as u64
+}
+
+impl NativeMap {
+    pub fn native_empty(_args: VecDeque<Value>) -> NativeReturnStatus {
+        NativeReturnStatus::Success {
+            return_values: vec![Value::native_struct(NativeStructValue::Map(NativeMap(
+                BTreeMap::new(),
+            )))],
+            cost: EMPTY_COST,
+        }
+    }
+
+    pub fn native_length(mut args: VecDeque<Value>) -> NativeReturnStatus {
+        if args.len() != 1 {
+            return NativeReturnStatus::InvalidArguments;
+        }
+        let reference = get_map_ref!(args);
+        reference
+            .read_native_struct(|native_val| Some(get_map(native_val)?.0.len()))
+            .map(|len| NativeReturnStatus::Success {
+                cost: LENGTH_COST,
+                return_values: vec![Value::u64(len as u64)],
+            })
+            .unwrap_or(NativeReturnStatus::InvalidArguments)
+    }
+
+    pub fn native_contains(mut args: VecDeque<Value>) -> NativeReturnStatus {
+        if args.len() != 2 {
+            return NativeReturnStatus::InvalidArguments;
+        }
+        let reference = get_map_ref!(args);
+        let (key_bytes, _) = pop_key!(args);
+        reference
+            .read_native_struct(|native_val| Some(get_map(native_val)?.0.contains_key(&key_bytes)))
+            .map(|contains| NativeReturnStatus::Success {
+                cost: key_cost(CONTAINS_COST, &key_bytes),
+                return_values: vec![Value::bool(contains)],
+            })
+            .unwrap_or(NativeReturnStatus::InvalidArguments)
+    }
+
+    pub fn native_insert(mut args: VecDeque<Value>) -> NativeReturnStatus {
+        if args.len() != 3 {
+            return NativeReturnStatus::InvalidArguments;
+        }
+        let reference = get_map_ref!(args);
+        let (key_bytes, key) = pop_key!(args);
+        let value = match args.pop_front() {
+            Some(v) => MutVal::new(v),
+            None => return NativeReturnStatus::InvalidArguments,
+        };
+        let cost = key_cost(INSERT_COST, &key_bytes);
+        match reference.mutate_native_struct(|native_val| {
+            let map = get_mut_map(native_val)?;
+            if map.0.contains_key(&key_bytes) {
+                return Some(false);
+            }
+            map.0.insert(key_bytes, (MutVal::new(key), value));
+            Some(true)
+        }) {
+            Some(true) => NativeReturnStatus::Success {
+                cost,
+                return_values: vec![],
+            },
+            Some(false) => NativeReturnStatus::Aborted {
+                cost,
+                error_code: KEY_ALREADY_EXISTS,
+            },
+            None => NativeReturnStatus::InvalidArguments,
+        }
+    }
+
+    pub fn native_remove(mut args: VecDeque<Value>) -> NativeReturnStatus {
+        if args.len() != 2 {
+            return NativeReturnStatus::InvalidArguments;
+        }
+        let reference = get_map_ref!(args);
+        let (key_bytes, _) = pop_key!(args);
+        let cost = key_cost(REMOVE_COST, &key_bytes);
+        match reference.mutate_native_struct(|native_val| {
+            Some(
+                get_mut_map(native_val)?
+                    .0
+                    .remove(&key_bytes)
+                    .map(|(_, value)| value.into_value()),
+            )
+        }) {
+            Some(Some(Ok(v))) => NativeReturnStatus::Success {
+                cost,
+                return_values: vec![v],
+            },
+            Some(None) => NativeReturnStatus::Aborted {
+                cost,
+                error_code: KEY_NOT_FOUND,
+            },
+            // Not a map, or the removed value has dangling references.
+            _ => NativeReturnStatus::InvalidArguments,
+        }
+    }
+
+    pub fn native_borrow(mut args: VecDeque<Value>) -> NativeReturnStatus {
+        if args.len() != 2 {
+            return NativeReturnStatus::InvalidArguments;
+        }
+        let reference = get_map_ref!(args);
+        let (key_bytes, _) = pop_key!(args);
+        let cost = key_cost(BORROW_COST, &key_bytes);
+        match reference.get_native_struct_reference(|native_val| {
+            get_map(native_val)?
+                .0
+                .get(&key_bytes)
+                .map(|(_, value)| MutVal::clone(value))
+        }) {
+            Some(v) => NativeReturnStatus::Success {
+                cost,
+                return_values: vec![v],
+            },
+            None => NativeReturnStatus::Aborted {
+                cost,
+                error_code: KEY_NOT_FOUND,
+            },
+        }
+    }
+
+    pub fn native_destroy_empty(mut args: VecDeque<Value>) -> NativeReturnStatus {
+        if let Some(v) = args.pop_front() {
+            if let Some(NativeStructValue::Map(NativeMap(m))) = v.value_as::<NativeStructValue>() {
+                return if m.is_empty() {
+                    NativeReturnStatus::Success {
+                        cost: DESTROY_EMPTY_MAP_COST,
+                        return_values: vec![],
+                    }
+                } else {
+                    NativeReturnStatus::Aborted {
+                        cost: DESTROY_EMPTY_MAP_COST,
+                        error_code: DESTROY_NON_EMPTY_MAP,
+                    }
+                };
+            }
+        }
+        NativeReturnStatus::InvalidArguments
+    }
+
+    pub fn size(&self) -> AbstractMemorySize<GasCarrier> {
+        self.0.values().fold(*STRUCT_SIZE, |acc, (key, value)| {
+            acc.map2(key.size(), Add::add).map2(value.size(), Add::add)
+        })
+    }
+}
+
+// TODO(#1307)
+impl Serialize for NativeMap {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.collect_seq(self.0.values())
+    }
+}
@@ -3,6 +3,7 @@
 
 pub mod def;
 pub mod dispatch;
+pub mod map;
 pub mod serializer;
 pub mod vector;
 pub use def::{NativeStructType, NativeStructValue};
@@ -1,11 +1,13 @@
 use crate::{
     native_structs::{
-        def::NativeStructTag, vector::NativeVector, NativeStructType, NativeStructValue,
+        def::NativeStructTag, map::NativeMap, vector::NativeVector, NativeStructType,
+        NativeStructValue,
     },
     value::{deserialize_value, MutVal},
 };
 use libra_types::vm_error::StatusCode;
 use serde::Deserialize;
+use std::collections::BTreeMap;
 use vm::errors::*;
 
 pub(crate) fn deserialize_vector(
@@ -27,6 +29,38 @@ pub(crate) fn deserialize_vector(
     Ok(NativeVector(val))
 }
 
+pub(crate) fn deserialize_map(
+    deserializer: &mut lcs::Deserializer<'_>,
+    ty: &NativeStructType,
+) -> VMResult<NativeMap> {
+    let len = u32::deserialize(&mut *deserializer)
+        .map_err(|_| vm_error(Location::new(), StatusCode::DATA_FORMAT_ERROR))?
+        as usize;
+    if ty.type_actuals().len() != 2 {
+        return Err(vm_error(Location::new(), StatusCode::DATA_FORMAT_ERROR));
+    };
+    let key_type = &ty.type_actuals()[0];
+    let value_type = &ty.type_actuals()[1];
+    let mut val = BTreeMap::new();
+    let mut last_key_bytes: Option<Vec<u8>> = None;
+    for _i in 0..len {
+        let key = deserialize_value(deserializer, key_type)?;
+        let value = deserialize_value(deserializer, value_type)?;
+        let key_bytes = key
+            .simple_serialize()
+            .ok_or_else(|| vm_error(Location::new(), StatusCode::DATA_FORMAT_ERROR))?;
+        // Entries are serialized in increasing key order, which also rules out duplicate keys.
+        if let Some(last_key_bytes) = &last_key_bytes {
+            if *last_key_bytes >= key_bytes {
+                return Err(vm_error(Location::new(), StatusCode::DATA_FORMAT_ERROR));
+            }
+        }
+        last_key_bytes = Some(key_bytes.clone());
+        val.insert(key_bytes, (MutVal::new(key), MutVal::new(value)));
+    }
+    Ok(NativeMap(val))
+}
+
 pub(crate) fn deserialize_native(
     deserializer: &mut lcs::Deserializer<'_>,
     ty: &NativeStructType,
@@ -36,5 +70,6 @@ pub(crate) fn deserialize_native(
             deserializer,
             ty,
         )?)),
+        NativeStructTag::Map => Ok(NativeStructValue::Map(deserialize_map(deserializer, ty)?)),
     }
 }
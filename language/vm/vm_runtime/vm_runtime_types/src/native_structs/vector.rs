@@ -28,12 +28,14 @@ pub const DESTROY_NON_EMPTY_VEC: u64 = NFE_VECTOR_ERROR_BASE + 3;
 fn get_mut_vector(v: &mut NativeStructValue) -> Option<&mut NativeVector> {
     match v {
         NativeStructValue::Vector(v) => Some(v),
+        _ => None,
     }
 }
 
 fn get_vector(v: &NativeStructValue) -> Option<&NativeVector> {
     match v {
         NativeStructValue::Vector(v) => Some(v),
+        _ => None,
     }
 }
 
@@ -50,6 +52,11 @@ macro_rules! get_vector_ref {
 }
 
 impl NativeVector {
+    /// Creates a vector from a vector of `Value`s.
+    pub fn new(values: Vec<Value>) -> Self {
+        NativeVector(values.into_iter().map(MutVal::new).collect())
+    }
+
     pub fn native_empty(_args: VecDeque<Value>) -> NativeReturnStatus {
         NativeReturnStatus::Success {
             return_values: vec![Value::native_struct(NativeStructValue::Vector(
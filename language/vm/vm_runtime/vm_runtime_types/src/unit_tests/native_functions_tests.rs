@@ -0,0 +1,213 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use super::{dispatch::NativeReturnStatus, gas_schedule, hash, merkle, signature};
+use crate::value::Value;
+use crypto::{
+    bls12381::{BLS12381PrivateKey, BLS12381PublicKey},
+    hash::{CryptoHash, SPARSE_MERKLE_PLACEHOLDER_HASH},
+    traits::*,
+    HashValue,
+};
+use libra_types::{
+    byte_array::ByteArray,
+    proof::{SparseMerkleInternalNode, SparseMerkleLeafNode},
+};
+use rand::{rngs::StdRng, SeedableRng};
+use std::collections::VecDeque;
+use vm::gas_schedule::{GasAlgebra, GasUnits, DEFAULT_GAS_SCHEDULE};
+
+fn byte_arrays(args: Vec<Vec<u8>>) -> VecDeque<Value> {
+    args.into_iter()
+        .map(|bytes| Value::byte_array(ByteArray::new(bytes)))
+        .collect()
+}
+
+fn expect_bool(status: NativeReturnStatus) -> bool {
+    match status {
+        NativeReturnStatus::Success {
+            mut return_values, ..
+        } => {
+            assert_eq!(return_values.len(), 1);
+            return_values
+                .pop()
+                .unwrap()
+                .value_as::<bool>()
+                .expect("must return a bool")
+        }
+        _ => panic!("native function should succeed"),
+    }
+}
+
+fn expect_abort(status: NativeReturnStatus) -> u64 {
+    match status {
+        NativeReturnStatus::Aborted { error_code, .. } => error_code,
+        _ => panic!("native function should abort"),
+    }
+}
+
+fn bls12381_keys(seed: u8) -> (BLS12381PrivateKey, BLS12381PublicKey) {
+    let mut rng = StdRng::from_seed([seed; 32]);
+    let private_key = BLS12381PrivateKey::generate_for_testing(&mut rng);
+    let public_key = (&private_key).into();
+    (private_key, public_key)
+}
+
+#[test]
+fn keccak_256() {
+    let status = hash::native_keccak_256(byte_arrays(vec![b"abc".to_vec()]));
+    let hash = match status {
+        NativeReturnStatus::Success {
+            mut return_values, ..
+        } => return_values
+            .pop()
+            .unwrap()
+            .value_as::<ByteArray>()
+            .expect("must return a bytearray"),
+        _ => panic!("keccak_256 should succeed"),
+    };
+    assert_eq!(
+        hex::encode(hash.as_bytes()),
+        "4e03657aea45a94fc7d47ba826c8d667c0d1e6e33a64a036ec44f58fa12d6c45"
+    );
+}
+
+#[test]
+fn bls12381_verify() {
+    let (private_key, public_key) = bls12381_keys(0);
+    let message = HashValue::random();
+    let signature = private_key.sign_message(&message);
+
+    let verify = |signature: &[u8], message: &[u8]| {
+        expect_bool(signature::native_bls12381_signature_verification(
+            byte_arrays(vec![
+                signature.to_vec(),
+                public_key.to_bytes().to_vec(),
+                message.to_vec(),
+            ]),
+        ))
+    };
+    assert!(verify(&signature.to_bytes(), message.as_ref()));
+    assert!(!verify(&signature.to_bytes(), HashValue::random().as_ref()));
+
+    // The verification is charged even if the message is empty.
+    let status = signature::native_bls12381_signature_verification(byte_arrays(vec![
+        signature.to_bytes().to_vec(),
+        public_key.to_bytes().to_vec(),
+        vec![],
+    ]));
+    match status {
+        NativeReturnStatus::Success { cost, .. } => assert!(cost > 0),
+        _ => panic!("bls12381_verify should succeed"),
+    }
+
+    let status = signature::native_bls12381_signature_verification(byte_arrays(vec![
+        vec![0; 3],
+        public_key.to_bytes().to_vec(),
+        message.to_vec(),
+    ]));
+    expect_abort(status);
+}
+
+#[test]
+fn bls12381_aggregate_verify() {
+    let message = HashValue::random();
+    let keys: Vec<_> = (0..3).map(bls12381_keys).collect();
+    let signatures: Vec<u8> = keys
+        .iter()
+        .flat_map(|(private_key, _)| private_key.sign_message(&message).to_bytes().to_vec())
+        .collect();
+    let public_keys: Vec<u8> = keys
+        .iter()
+        .flat_map(|(_, public_key)| public_key.to_bytes().to_vec())
+        .collect();
+
+    let verify = |signatures: &[u8], public_keys: &[u8]| {
+        signature::native_bls12381_aggregate_signature_verification(byte_arrays(vec![
+            signatures.to_vec(),
+            public_keys.to_vec(),
+            message.to_vec(),
+        ]))
+    };
+    assert!(expect_bool(verify(&signatures, &public_keys)));
+    // A subset of the signers verifies against their own public keys only.
+    assert!(expect_bool(verify(&signatures[96..], &public_keys[48..])));
+    assert!(!expect_bool(verify(&signatures[96..], &public_keys[..96])));
+
+    // Every signer's public key must be part of the aggregate.
+    let mut other_keys = bls12381_keys(3).1.to_bytes().to_vec();
+    other_keys.extend_from_slice(&public_keys[48..]);
+    assert!(!expect_bool(verify(&signatures, &other_keys)));
+
+    // Each signature needs a public key, and at least one signature is required.
+    let size_failure = expect_abort(verify(&signatures, &public_keys[48..]));
+    assert_eq!(expect_abort(verify(&[], &[])), size_failure);
+}
+
+#[test]
+fn sparse_merkle_inclusion() {
+    // A tree with two leaves, whose keys differ in their first bit.
+    let mut key_bytes = [0u8; HashValue::LENGTH];
+    let left_key = HashValue::new(key_bytes);
+    key_bytes[0] = 0x80;
+    let right_key = HashValue::new(key_bytes);
+    let left_value_hash = HashValue::random();
+    let right_value_hash = HashValue::random();
+    let left_leaf = SparseMerkleLeafNode::new(left_key, left_value_hash).hash();
+    let right_leaf = SparseMerkleLeafNode::new(right_key, right_value_hash).hash();
+    let root_hash = SparseMerkleInternalNode::new(left_leaf, right_leaf).hash();
+
+    let verify = |key: HashValue, value_hash: HashValue, siblings: Vec<u8>| {
+        merkle::native_verify_sparse_merkle_inclusion(byte_arrays(vec![
+            root_hash.to_vec(),
+            key.to_vec(),
+            value_hash.to_vec(),
+            siblings,
+        ]))
+    };
+    assert!(expect_bool(verify(
+        left_key,
+        left_value_hash,
+        right_leaf.to_vec()
+    )));
+    assert!(expect_bool(verify(
+        right_key,
+        right_value_hash,
+        left_leaf.to_vec()
+    )));
+    assert!(!expect_bool(verify(
+        left_key,
+        right_value_hash,
+        right_leaf.to_vec()
+    )));
+    assert!(!expect_bool(verify(
+        left_key,
+        left_value_hash,
+        SPARSE_MERKLE_PLACEHOLDER_HASH.to_vec()
+    )));
+
+    let invalid_siblings = expect_abort(verify(left_key, left_value_hash, vec![0; 31]));
+    let invalid_hash = expect_abort(merkle::native_verify_sparse_merkle_inclusion(byte_arrays(
+        vec![
+            root_hash.to_vec(),
+            vec![0; 31],
+            left_value_hash.to_vec(),
+            right_leaf.to_vec(),
+        ],
+    )));
+    assert_ne!(invalid_siblings, invalid_hash);
+}
+
+#[test]
+fn gas_schedule_is_valid() {
+    let is_valid =
+        |bytes: Vec<u8>| expect_bool(gas_schedule::native_is_valid(byte_arrays(vec![bytes])));
+
+    assert!(is_valid(DEFAULT_GAS_SCHEDULE.to_bytes().unwrap()));
+    // Not a gas schedule.
+    assert!(!is_valid(vec![1, 2, 3]));
+    // A gas schedule that doesn't pass validation.
+    let mut schedule = DEFAULT_GAS_SCHEDULE.clone();
+    schedule.maximum_number_of_gas_units = GasUnits::new(0);
+    assert!(!is_valid(schedule.to_bytes().unwrap()));
+}
@@ -1,9 +1,37 @@
 // Copyright (c) The Libra Core Contributors
 // SPDX-License-Identifier: Apache-2.0
 
-use crate::{loaded_data::types::Type, value::Value};
+use crate::{
+    loaded_data::{struct_def::StructDef, types::Type},
+    native_structs::{def::NativeStructTag, map::NativeMap, NativeStructType, NativeStructValue},
+    value::{Locals, Value},
+};
+use libra_types::byte_array::ByteArray;
+use std::collections::BTreeMap;
 
-use proptest::prelude::*;
+use proptest::{
+    collection::{btree_map, vec},
+    prelude::*,
+};
+
+fn map_with_entries<'a>(entries: impl Iterator<Item = (&'a u64, &'a Vec<u8>)>) -> Value {
+    let mut locals = Locals::new(1);
+    locals
+        .store_loc(
+            0,
+            Value::native_struct(NativeStructValue::Map(NativeMap(BTreeMap::new()))),
+        )
+        .expect("local 0 must exist");
+    for (key, value) in entries {
+        let args = vec![
+            locals.borrow_loc(0).expect("local 0 must be valid"),
+            Value::u64(*key),
+            Value::byte_array(ByteArray::new(value.clone())),
+        ];
+        NativeMap::native_insert(args.into_iter().collect());
+    }
+    locals.move_loc(0).expect("local 0 must be valid")
+}
 
 proptest! {
     #[test]
@@ -16,4 +44,23 @@ proptest! {
         let value1 = Value::simple_deserialize(&blob, struct_def).expect("must deserialize");
         assert!(value.equals(&value1).unwrap());
     }
+
+    #[test]
+    fn map_test(entries in btree_map(any::<u64>(), vec(any::<u8>(), 0..10), 0..10)) {
+        // The serialization of a map doesn't depend on the order its entries were inserted in.
+        let blob = map_with_entries(entries.iter())
+            .simple_serialize()
+            .expect("must serialize");
+        let reversed_blob = map_with_entries(entries.iter().rev())
+            .simple_serialize()
+            .expect("must serialize");
+        assert_eq!(blob, reversed_blob);
+
+        let struct_def = StructDef::Native(NativeStructType::new(
+            NativeStructTag::Map,
+            vec![Type::U64, Type::ByteArray],
+        ));
+        let value = Value::simple_deserialize(&blob, struct_def).expect("must deserialize");
+        assert_eq!(value.simple_serialize().expect("must serialize"), blob);
+    }
 }
@@ -14,6 +14,8 @@ use libra_types::{
 use serde::{Deserialize, Serialize};
 use std::{
     cell::{Ref, RefCell},
+    cmp::Ordering,
+    convert::TryFrom,
     mem::replace,
     ops::Add,
     rc::Rc,
@@ -52,7 +54,9 @@ enum ValueImpl {
     /// Locals are invalid on entry of a function and when moved out.
     Invalid,
     // Primitive types
+    U8(u8),
     U64(u64),
+    U128(u128),
     Address(AccountAddress),
     Bool(bool),
     ByteArray(ByteArray),
@@ -164,7 +168,11 @@ impl ValueImpl {
 
     fn size(&self) -> AbstractMemorySize<GasCarrier> {
         match self {
-            ValueImpl::Invalid | ValueImpl::U64(_) | ValueImpl::Bool(_) => *CONST_SIZE,
+            ValueImpl::Invalid
+            | ValueImpl::U8(_)
+            | ValueImpl::U64(_)
+            | ValueImpl::U128(_)
+            | ValueImpl::Bool(_) => *CONST_SIZE,
             ValueImpl::Address(_) => AbstractMemorySize::new(ADDRESS_LENGTH as u64),
             // Possible debate topic: Should we charge based upon the size of the string.
             // At this moment, we take the view that you should be charged as though you are
@@ -188,7 +196,9 @@ impl ValueImpl {
             // TODO: this does not look right to me....
             (ValueImpl::Invalid, ValueImpl::Invalid) => Ok(true),
             // values
+            (ValueImpl::U8(u1), ValueImpl::U8(u2)) => Ok(u1 == u2),
             (ValueImpl::U64(u1), ValueImpl::U64(u2)) => Ok(u1 == u2),
+            (ValueImpl::U128(u1), ValueImpl::U128(u2)) => Ok(u1 == u2),
             (ValueImpl::Bool(b1), ValueImpl::Bool(b2)) => Ok(b1 == b2),
             (ValueImpl::Address(a1), ValueImpl::Address(a2)) => Ok(a1 == a2),
             (ValueImpl::ByteArray(ba1), ValueImpl::ByteArray(ba2)) => Ok(ba1 == ba2),
@@ -211,7 +221,9 @@ impl ValueImpl {
     fn to_type_FOR_TESTING(&self) -> Type {
         match self {
             ValueImpl::Invalid => unreachable!("Cannot ask type of invalid location"),
+            ValueImpl::U8(_) => Type::U8,
             ValueImpl::U64(_) => Type::U64,
+            ValueImpl::U128(_) => Type::U128,
             ValueImpl::Address(_) => Type::Address,
             ValueImpl::Bool(_) => Type::Bool,
             ValueImpl::ByteArray(_) => Type::ByteArray,
@@ -235,11 +247,21 @@ impl Value {
         Value(value)
     }
 
+    /// Return a `Value` representing a `u8` in the VM.
+    pub fn u8(value: u8) -> Self {
+        Value(ValueImpl::U8(value))
+    }
+
     /// Return a `Value` representing a `u64` in the VM.
     pub fn u64(value: u64) -> Self {
         Value(ValueImpl::U64(value))
     }
 
+    /// Return a `Value` representing a `u128` in the VM.
+    pub fn u128(value: u128) -> Self {
+        Value(ValueImpl::U128(value))
+    }
+
     /// Return a `Value` representing an `AccountAddress` in the VM.
     pub fn address(address: AccountAddress) -> Self {
         Value(ValueImpl::Address(address))
@@ -327,12 +349,145 @@ impl Value {
     }
 }
 
+/// An integer popped from the stack by the arithmetic, comparison and cast instructions.
+///
+/// The operations on two integers return `None` if the integers are of different types. The
+/// bytecode verifier guarantees that never happens.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum IntegerValue {
+    U8(u8),
+    U64(u64),
+    U128(u128),
+}
+
+macro_rules! integer_binop {
+    ($(#[$attr:meta])* $name:ident, |$l:ident, $r:ident| $body:expr) => {
+        $(#[$attr])*
+        pub fn $name(self, other: Self) -> Option<Self> {
+            use IntegerValue::*;
+            match (self, other) {
+                (U8($l), U8($r)) => $body.map(U8),
+                (U64($l), U64($r)) => $body.map(U64),
+                (U128($l), U128($r)) => $body.map(U128),
+                _ => None,
+            }
+        }
+    };
+}
+
+impl IntegerValue {
+    integer_binop!(
+        /// `Add` bytecode. Returns `None` on overflow.
+        checked_add,
+        |l, r| l.checked_add(r)
+    );
+
+    integer_binop!(
+        /// `Sub` bytecode. Returns `None` on underflow.
+        checked_sub,
+        |l, r| l.checked_sub(r)
+    );
+
+    integer_binop!(
+        /// `Mul` bytecode. Returns `None` on overflow.
+        checked_mul,
+        |l, r| l.checked_mul(r)
+    );
+
+    integer_binop!(
+        /// `Div` bytecode. Returns `None` on division by zero.
+        checked_div,
+        |l, r| l.checked_div(r)
+    );
+
+    integer_binop!(
+        /// `Mod` bytecode. Returns `None` on division by zero.
+        checked_rem,
+        |l, r| l.checked_rem(r)
+    );
+
+    integer_binop!(
+        /// `BitOr` bytecode.
+        bit_or,
+        |l, r| Some(l | r)
+    );
+
+    integer_binop!(
+        /// `BitAnd` bytecode.
+        bit_and,
+        |l, r| Some(l & r)
+    );
+
+    integer_binop!(
+        /// `Xor` bytecode.
+        bit_xor,
+        |l, r| Some(l ^ r)
+    );
+
+    /// `CastU8` bytecode. Returns `None` if the integer doesn't fit in a `u8`.
+    pub fn cast_u8(self) -> Option<u8> {
+        match self {
+            IntegerValue::U8(x) => Some(x),
+            IntegerValue::U64(x) => u8::try_from(x).ok(),
+            IntegerValue::U128(x) => u8::try_from(x).ok(),
+        }
+    }
+
+    /// `CastU64` bytecode. Returns `None` if the integer doesn't fit in a `u64`.
+    pub fn cast_u64(self) -> Option<u64> {
+        match self {
+            IntegerValue::U8(x) => Some(u64::from(x)),
+            IntegerValue::U64(x) => Some(x),
+            IntegerValue::U128(x) => u64::try_from(x).ok(),
+        }
+    }
+
+    /// `CastU128` bytecode.
+    pub fn cast_u128(self) -> u128 {
+        match self {
+            IntegerValue::U8(x) => u128::from(x),
+            IntegerValue::U64(x) => u128::from(x),
+            IntegerValue::U128(x) => x,
+        }
+    }
+
+    pub fn into_value(self) -> Value {
+        match self {
+            IntegerValue::U8(x) => Value::u8(x),
+            IntegerValue::U64(x) => Value::u64(x),
+            IntegerValue::U128(x) => Value::u128(x),
+        }
+    }
+}
+
+/// Integers of different types can't be compared.
+impl PartialOrd for IntegerValue {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        use IntegerValue::*;
+        match (self, other) {
+            (U8(l), U8(r)) => l.partial_cmp(r),
+            (U64(l), U64(r)) => l.partial_cmp(r),
+            (U128(l), U128(r)) => l.partial_cmp(r),
+            _ => None,
+        }
+    }
+}
+
 //
 // From/Into implementation to read known values off the stack.
 // A pop from the stack returns a `Value` that is owned by the caller of pop. For many opcodes
 // (e.g. Add) the values popped from the stack are expected to be u64 and should fail otherwise.
 //
 
+impl From<Value> for Option<u8> {
+    fn from(value: Value) -> Option<u8> {
+        match value.0 {
+            ValueImpl::U8(i) => Some(i),
+            _ => None,
+        }
+    }
+}
+
 impl From<Value> for Option<u64> {
     fn from(value: Value) -> Option<u64> {
         match value.0 {
@@ -342,6 +497,26 @@ impl From<Value> for Option<u64> {
     }
 }
 
+impl From<Value> for Option<u128> {
+    fn from(value: Value) -> Option<u128> {
+        match value.0 {
+            ValueImpl::U128(i) => Some(i),
+            _ => None,
+        }
+    }
+}
+
+impl From<Value> for Option<IntegerValue> {
+    fn from(value: Value) -> Option<IntegerValue> {
+        match value.0 {
+            ValueImpl::U8(i) => Some(IntegerValue::U8(i)),
+            ValueImpl::U64(i) => Some(IntegerValue::U64(i)),
+            ValueImpl::U128(i) => Some(IntegerValue::U128(i)),
+            _ => None,
+        }
+    }
+}
+
 impl From<Value> for Option<bool> {
     fn from(value: Value) -> Option<bool> {
         match value.0 {
@@ -960,7 +1135,9 @@ pub(crate) fn deserialize_value(
 ) -> VMResult<Value> {
     match ty {
         Type::Bool => bool::deserialize(deserializer).map(Value::bool),
+        Type::U8 => u8::deserialize(deserializer).map(Value::u8),
         Type::U64 => u64::deserialize(deserializer).map(Value::u64),
+        Type::U128 => u128::deserialize(deserializer).map(Value::u128),
         Type::String => VMString::deserialize(deserializer).map(Value::string),
         Type::ByteArray => ByteArray::deserialize(deserializer).map(Value::byte_array),
         Type::Address => AccountAddress::deserialize(deserializer).map(Value::address),
@@ -998,7 +1175,9 @@ impl Serialize for ValueImpl {
     {
         use serde::ser::SerializeTuple;
         match self {
+            ValueImpl::U8(val) => serializer.serialize_u8(*val),
             ValueImpl::U64(val) => serializer.serialize_u64(*val),
+            ValueImpl::U128(val) => serializer.serialize_u128(*val),
             ValueImpl::Address(addr) => {
                 // TODO: this is serializing as a vector but we want just raw bytes
                 // however the AccountAddress story is a bit difficult to work with right now
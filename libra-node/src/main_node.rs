@@ -2,11 +2,20 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use admission_control_service::runtime::AdmissionControlRuntime;
-use config::config::{NetworkConfig, NodeConfig, RoleType};
+use config::{
+    config::{NetworkConfig, NodeConfig, PersistableConfig, RoleType},
+    keys::NetworkKeyPairs,
+    trusted_peers::NetworkPeersConfig,
+};
 use consensus::consensus_provider::{make_consensus_provider, ConsensusProvider};
 use crypto::{ed25519::*, ValidKey};
-use debug_interface::{node_debug_service::NodeDebugService, proto::create_node_debug_interface};
+use debug_interface::{
+    node_debug_service::{EquivocationEvidenceSlot, NodeDebugService},
+    proto::create_node_debug_interface,
+};
 use executor::Executor;
+use failure::prelude::*;
+use futures::{SinkExt, StreamExt};
 use grpc_helpers::ServerHandle;
 use grpcio::EnvBuilder;
 use libra_logger::prelude::*;
@@ -14,32 +23,43 @@ use libra_mempool::MempoolRuntime;
 use libra_types::account_address::AccountAddress as PeerId;
 use metrics::metric_server;
 use network::{
+    interface::NetworkRequest,
     validator_network::{
         network_builder::{NetworkBuilder, TransportType},
         LibraNetworkProvider,
-        // when you add a new protocol const, you must add this in either
-        // .direct_send_protocols or .rpc_protocols vector of network_builder in setup_network()
+        // when you add a new protocol const, you must add this in either .direct_send_protocols,
+        // .rpc_protocols or .streaming_rpc_protocols vector of network_builder in setup_network()
         ADMISSION_CONTROL_RPC_PROTOCOL,
         CONSENSUS_DIRECT_SEND_PROTOCOL,
         CONSENSUS_RPC_PROTOCOL,
+        CONSENSUS_STREAMING_RPC_PROTOCOL,
         MEMPOOL_DIRECT_SEND_PROTOCOL,
         STATE_SYNCHRONIZER_MSG_PROTOCOL,
     },
-    NetworkPublicKeys, ProtocolId,
+    DnsSeedResolver, InboundConnectionLimits, NetworkPublicKeys, PeerMetrics, PeerReputation,
+    ProtocolId,
 };
 use state_synchronizer::StateSynchronizer;
 use std::{
+    collections::HashMap,
     convert::{TryFrom, TryInto},
+    fs,
     str::FromStr,
-    sync::Arc,
+    sync::{Arc, RwLock},
     thread,
-    time::Instant,
+    time::{Duration, Instant},
 };
 use storage_client::{StorageRead, StorageReadServiceClient, StorageWriteServiceClient};
 use storage_service::start_storage_service;
-use tokio::runtime::{Builder, Runtime};
+use tokio::{
+    runtime::{Builder, Runtime},
+    timer::Interval,
+};
 use vm_runtime::MoveVM;
 
+/// Interval at which the network keypairs and peers files are checked for rotated keys.
+const NETWORK_KEYS_RELOAD_INTERVAL: Duration = Duration::from_secs(60);
+
 pub struct LibraHandle {
     _ac: AdmissionControlRuntime,
     _mempool: Option<MempoolRuntime>,
@@ -79,10 +99,19 @@ fn setup_executor(config: &NodeConfig) -> Arc<Executor<MoveVM>> {
     ))
 }
 
-fn setup_debug_interface(config: &NodeConfig) -> ::grpcio::Server {
+fn setup_debug_interface(
+    config: &NodeConfig,
+    peer_reputations: HashMap<PeerId, Arc<RwLock<PeerReputation>>>,
+    peer_metrics: HashMap<PeerId, PeerMetrics>,
+    equivocation_evidence: EquivocationEvidenceSlot,
+) -> ::grpcio::Server {
     let env = Arc::new(EnvBuilder::new().name_prefix("grpc-debug-").build());
     // Start Debug interface
-    let debug_service = create_node_debug_interface(NodeDebugService::new());
+    let debug_service = create_node_debug_interface(
+        NodeDebugService::with_peer_reputations(peer_reputations)
+            .with_peer_metrics(peer_metrics)
+            .with_equivocation_evidence(equivocation_evidence),
+    );
     ::grpcio::ServerBuilder::new(env)
         .register_service(debug_service)
         .bind(
@@ -93,6 +122,24 @@ fn setup_debug_interface(config: &NodeConfig) -> ::grpcio::Server {
         .expect("Unable to create grpc server")
 }
 
+// The network keys of the trusted peers of a permissioned network.
+fn trusted_peers(network_peers: &NetworkPeersConfig) -> Result<HashMap<PeerId, NetworkPublicKeys>> {
+    network_peers
+        .peers
+        .iter()
+        .map(|(peer_id, keys)| {
+            ensure!(!peer_id.is_empty(), "Empty peer id");
+            Ok((
+                PeerId::from_str(peer_id)?,
+                NetworkPublicKeys {
+                    signing_public_key: keys.network_signing_pubkey.clone(),
+                    identity_public_key: keys.network_identity_pubkey.clone(),
+                },
+            ))
+        })
+        .collect()
+}
+
 // TODO(abhayb): Move to network crate (similar to consensus).
 pub fn setup_network(
     peer_id: PeerId,
@@ -103,15 +150,19 @@ pub fn setup_network(
         .build()
         .expect("Failed to start runtime. Won't be able to start networking.");
     let role: RoleType = (&config.role).into();
-    let mut network_builder = NetworkBuilder::new(
-        runtime.executor(),
-        peer_id,
-        config.listen_address.clone(),
-        role,
-    );
+    // A listener is bound on each of the listen addresses (e.g. both an IPv4 and an IPv6 one): the
+    // first one only seeds the builder, and is replaced along with the others below.
+    let first_listen_address = config
+        .listen_addresses
+        .first()
+        .cloned()
+        .expect("At least one listen address is required");
+    let mut network_builder =
+        NetworkBuilder::new(runtime.executor(), peer_id, first_listen_address, role);
     network_builder
         .permissioned(config.is_permissioned)
-        .advertised_address(config.advertised_address.clone())
+        .listen_addresses(config.listen_addresses.clone())
+        .advertised_addresses(config.advertised_addresses.clone())
         .direct_send_protocols(vec![
             ProtocolId::from_static(CONSENSUS_DIRECT_SEND_PROTOCOL),
             ProtocolId::from_static(MEMPOOL_DIRECT_SEND_PROTOCOL),
@@ -120,7 +171,36 @@ pub fn setup_network(
         .rpc_protocols(vec![
             ProtocolId::from_static(CONSENSUS_RPC_PROTOCOL),
             ProtocolId::from_static(ADMISSION_CONTROL_RPC_PROTOCOL),
-        ]);
+        ])
+        .streaming_rpc_protocols(vec![ProtocolId::from_static(
+            CONSENSUS_STREAMING_RPC_PROTOCOL,
+        )]);
+    let seed_peers = config
+        .seed_peers
+        .seed_peers
+        .clone()
+        .into_iter()
+        .map(|(peer_id, addrs)| (peer_id.try_into().expect("Invalid PeerId"), addrs))
+        .collect();
+    let network_signing_private = config.network_keypairs.take_network_signing_private()
+        .expect("Failed to move network signing private key out of NodeConfig, key not set or moved already");
+    let network_signing_public: Ed25519PublicKey = (&network_signing_private).into();
+    network_builder
+        .connectivity_check_interval_ms(config.connectivity_check_interval_ms)
+        .seed_peers(seed_peers)
+        .signing_keys((network_signing_private, network_signing_public))
+        .discovery_interval_ms(config.discovery_interval_ms)
+        .dns_seed_addr(config.dns_seed_addr.clone());
+    if role == RoleType::FullNode {
+        // Full nodes find the public full nodes through the seed records of the validators.
+        network_builder.seed_resolver(Box::new(DnsSeedResolver::new()));
+    }
+    if let Some(ban_list_file) = &config.ban_list_file {
+        network_builder.peer_reputation(
+            PeerReputation::with_ban_list(ban_list_file.clone())
+                .expect("Unable to load the ban list"),
+        );
+    }
     if config.is_permissioned {
         // If the node wants to run in permissioned mode, it should also have authentication and
         // encryption.
@@ -128,49 +208,114 @@ pub fn setup_network(
             config.enable_encryption_and_authentication,
             "Permissioned network end-points should use authentication"
         );
-        let trusted_peers = config
-            .network_peers
-            .peers
-            .iter()
-            .map(|(peer_id, keys)| {
-                (
-                    PeerId::from_str(peer_id).unwrap(),
-                    NetworkPublicKeys {
-                        signing_public_key: keys.network_signing_pubkey.clone(),
-                        identity_public_key: keys.network_identity_pubkey.clone(),
-                    },
-                )
-            })
-            .collect();
-        let seed_peers = config
-            .seed_peers
-            .seed_peers
-            .clone()
-            .into_iter()
-            .map(|(peer_id, addrs)| (peer_id.try_into().expect("Invalid PeerId"), addrs))
-            .collect();
-        let network_signing_private = config.network_keypairs.take_network_signing_private()
-            .expect("Failed to move network signing private key out of NodeConfig, key not set or moved already");
-        let network_signing_public: Ed25519PublicKey = (&network_signing_private).into();
         network_builder
             .transport(TransportType::TcpNoise(Some(
                 config.network_keypairs.get_network_identity_keypair(),
             )))
-            .connectivity_check_interval_ms(config.connectivity_check_interval_ms)
-            .seed_peers(seed_peers)
-            .trusted_peers(trusted_peers)
-            .signing_keys((network_signing_private, network_signing_public))
-            .discovery_interval_ms(config.discovery_interval_ms);
-    } else if config.enable_encryption_and_authentication {
-        // Even if a network end-point is permissionless, it might want to prove its identity to
-        // another peer it connects to. For this, we use TCP + Noise but in a permission-less way.
-        network_builder.transport(TransportType::PermissionlessTcpNoise(Some(
-            config.network_keypairs.get_network_identity_keypair(),
-        )));
+            .trusted_peers(
+                trusted_peers(&config.network_peers).expect("Invalid trusted peers config"),
+            );
     } else {
-        network_builder.transport(TransportType::Tcp);
+        // A permissionless end-point dials the peers it discovers until it reaches its target
+        // number of connections, and limits the inbound connections from a single IP or subnet.
+        network_builder
+            .target_connections(config.target_connections)
+            .inbound_connection_limits(InboundConnectionLimits::new(
+                config.max_inbound_connections_per_ip,
+                config.max_inbound_connections_per_subnet,
+            ));
+        if config.enable_encryption_and_authentication {
+            // Even if a network end-point is permissionless, it might want to prove its identity
+            // to another peer it connects to. For this, we use TCP + Noise but in a
+            // permission-less way.
+            network_builder.transport(TransportType::PermissionlessTcpNoise(Some(
+                config.network_keypairs.get_network_identity_keypair(),
+            )));
+        } else {
+            network_builder.transport(TransportType::Tcp);
+        }
+    }
+    let (_listen_addr, mut network_provider) = network_builder.build();
+    if let Some(config_path) = config.config_path.clone() {
+        // The operator rotates the network keys by editing the keypairs and peers files, without
+        // restarting the node: the new identity key is used by the handshakes from then on, and
+        // the previous key of a trusted peer is still accepted during a grace period after the
+        // update of the trusted peers.
+        let noise_config = network_builder.noise_config();
+        let mut control_sender = network_provider.add_control_sender();
+        // The peer id of a permissionless end-point is derived from its identity key, which
+        // therefore can't be rotated.
+        let keypairs_path = match noise_config {
+            Some(_)
+                if config.is_permissioned
+                    && !config.network_keypairs_file.as_os_str().is_empty() =>
+            {
+                Some(config_path.with_file_name(&config.network_keypairs_file))
+            }
+            _ => None,
+        };
+        let peers_path =
+            if config.is_permissioned && !config.network_peers_file.as_os_str().is_empty() {
+                Some(config_path.with_file_name(&config.network_peers_file))
+            } else {
+                None
+            };
+        let mut peers_contents = peers_path
+            .as_ref()
+            .and_then(|path| fs::read_to_string(path).ok());
+        runtime.executor().spawn(async move {
+            let mut interval = Interval::new_interval(NETWORK_KEYS_RELOAD_INTERVAL);
+            while interval.next().await.is_some() {
+                if let (Some(noise_config), Some(path)) = (&noise_config, &keypairs_path) {
+                    match tokio::fs::read_to_string(path)
+                        .await
+                        .map_err(Into::into)
+                        .and_then(|contents| NetworkKeyPairs::parse(&contents))
+                    {
+                        Ok(keypairs) => {
+                            let (private_key, public_key) = keypairs.get_network_identity_keypair();
+                            if public_key.to_bytes() != noise_config.public_key() {
+                                info!("Rotating the network identity key to {:?}", public_key);
+                                noise_config.rotate_keypair((private_key, public_key));
+                            }
+                        }
+                        Err(e) => error!("Failed to reload {:?}: {:?}", path, e),
+                    }
+                }
+                if let Some(path) = &peers_path {
+                    let contents = match tokio::fs::read_to_string(path).await {
+                        Ok(contents) => contents,
+                        Err(e) => {
+                            error!("Failed to reload {:?}: {:?}", path, e);
+                            continue;
+                        }
+                    };
+                    if peers_contents.as_ref() == Some(&contents) {
+                        continue;
+                    }
+                    // The previous trusted peers are kept until the file is valid again.
+                    let peers = match NetworkPeersConfig::parse(&contents)
+                        .and_then(|network_peers| trusted_peers(&network_peers))
+                    {
+                        Ok(peers) => peers,
+                        Err(e) => {
+                            error!("Failed to parse {:?}: {:?}", path, e);
+                            continue;
+                        }
+                    };
+                    info!("Updating the trusted peers from {:?}", path);
+                    if let Err(e) = control_sender
+                        .send(NetworkRequest::UpdateEligibleNodes(peers))
+                        .await
+                    {
+                        error!("Failed to update the trusted peers: {:?}", e);
+                        break;
+                    }
+                    peers_contents = Some(contents);
+                }
+            }
+        });
     }
-    let (_listen_addr, network_provider) = network_builder.build();
     (runtime, network_provider)
 }
 
@@ -199,6 +344,8 @@ pub fn setup_environment(node_config: &mut NodeConfig) -> LibraHandle {
     let mut ac_network_sender = None;
     let mut ac_network_events = vec![];
     let mut validator_network_provider = None;
+    let mut peer_reputations = HashMap::new();
+    let mut peer_metrics = HashMap::new();
 
     for i in 0..node_config.networks.len() {
         let peer_id =
@@ -213,6 +360,8 @@ pub fn setup_environment(node_config: &mut NodeConfig) -> LibraHandle {
                 ADMISSION_CONTROL_RPC_PROTOCOL,
             )]);
         ac_network_events.push(ac_events);
+        peer_reputations.insert(peer_id, network_provider.peer_reputation());
+        peer_metrics.insert(peer_id, network_provider.peer_metrics());
 
         let network = &node_config.networks[i];
         if let RoleType::Validator = (&network.role).into() {
@@ -241,7 +390,14 @@ pub fn setup_environment(node_config: &mut NodeConfig) -> LibraHandle {
         }
     }
 
-    let debug_if = ServerHandle::setup(setup_debug_interface(&node_config));
+    // Filled once consensus is created.
+    let equivocation_evidence = EquivocationEvidenceSlot::default();
+    let debug_if = ServerHandle::setup(setup_debug_interface(
+        &node_config,
+        peer_reputations,
+        peer_metrics,
+        Arc::clone(&equivocation_evidence),
+    ));
 
     let metrics_port = node_config.debug_interface.metrics_server_port;
     let metric_host = node_config.debug_interface.address.clone();
@@ -275,6 +431,7 @@ pub fn setup_environment(node_config: &mut NodeConfig) -> LibraHandle {
         let (consensus_network_sender, consensus_network_events) =
             network_provider.add_consensus(vec![
                 ProtocolId::from_static(CONSENSUS_RPC_PROTOCOL),
+                ProtocolId::from_static(CONSENSUS_STREAMING_RPC_PROTOCOL),
                 ProtocolId::from_static(CONSENSUS_DIRECT_SEND_PROTOCOL),
             ]);
         runtime.executor().spawn(network_provider.start());
@@ -299,6 +456,8 @@ pub fn setup_environment(node_config: &mut NodeConfig) -> LibraHandle {
             executor,
             state_synchronizer.create_client(),
         );
+        *equivocation_evidence.write().unwrap() =
+            Some(consensus_provider.equivocation_evidence_source());
         consensus_provider
             .start()
             .expect("Failed to start consensus. Can't proceed.");
@@ -10,10 +10,11 @@ use config::config::{MempoolConfig, NodeConfig};
 use futures::sync::mpsc::UnboundedSender;
 use futures_preview::{compat::Future01CompatExt, future::join_all, Stream, StreamExt};
 use libra_logger::prelude::*;
-use libra_types::{transaction::SignedTransaction, PeerId};
+use libra_types::{transaction::SignedTransaction, vm_error::StatusCode, PeerId};
 use network::{
     proto::MempoolSyncMsg,
     validator_network::{Event, MempoolNetworkEvents, MempoolNetworkSender},
+    Misbehavior,
 };
 use std::{
     collections::HashMap,
@@ -227,6 +228,7 @@ async fn process_incoming_transactions<V>(
     )
     .await;
 
+    let mut num_invalid_signatures = 0;
     {
         let mut mempool = smp
             .mempool
@@ -248,6 +250,13 @@ async fn process_incoming_transactions<V>(
                     insertion_result.code, peer_id
                 ));
             } else {
+                // A transaction may be rejected by the VM for reasons that are not the fault of
+                // the peer (e.g. a balance spent meanwhile): only an invalid signature is.
+                if let Ok(Some(status)) = &validations[idx] {
+                    if status.major_status == StatusCode::INVALID_SIGNATURE {
+                        num_invalid_signatures += 1;
+                    }
+                }
                 OP_COUNTERS.inc(&format!(
                     "smp.transactions.status.validation_failed.{:?}",
                     peer_id
@@ -255,6 +264,14 @@ async fn process_incoming_transactions<V>(
             }
         }
     }
+    if num_invalid_signatures > 0 {
+        report_peer(
+            &mut smp.network_sender.clone(),
+            peer_id,
+            Misbehavior::InvalidSignature,
+        )
+        .await;
+    }
     notify_subscribers(SharedMempoolNotification::NewTransactions, &smp.subscribers);
 }
 
@@ -289,6 +306,7 @@ async fn inbound_network_task<V>(
 {
     let peer_info = smp.peer_info.clone();
     let subscribers = smp.subscribers.clone();
+    let mut network_sender = smp.network_sender.clone();
 
     // Use a BoundedExecutor to restrict only `workers_available` concurrent
     // worker tasks that can process incoming transactions.
@@ -311,6 +329,7 @@ async fn inbound_network_task<V>(
                 }
                 Event::Message((peer_id, msg)) => {
                     OP_COUNTERS.inc("smp.event.message");
+                    let mut is_malformed = false;
                     let transactions: Vec<_> = msg
                         .transactions
                         .clone()
@@ -322,10 +341,15 @@ async fn inbound_network_task<V>(
                                     .error(&e)
                                     .data(&msg)
                                     .log();
+                                is_malformed = true;
                                 None
                             }
                         })
                         .collect();
+                    if is_malformed {
+                        report_peer(&mut network_sender, peer_id, Misbehavior::MalformedMessage)
+                            .await;
+                    }
                     OP_COUNTERS.inc_by(
                         &format!("smp.transactions.received.{:?}", peer_id),
                         transactions.len(),
@@ -356,6 +380,17 @@ async fn inbound_network_task<V>(
     crit!("SharedMempool inbound_network_task terminated");
 }
 
+/// Reports a misbehavior of the given peer to the network.
+async fn report_peer(
+    network_sender: &mut MempoolNetworkSender,
+    peer_id: PeerId,
+    misbehavior: Misbehavior,
+) {
+    if let Err(e) = network_sender.report_peer(peer_id, misbehavior).await {
+        warn!("Failed to report peer {:?}: {:?}", peer_id, e);
+    }
+}
+
 /// GC all expired transactions by SystemTTL
 async fn gc_task(mempool: Arc<Mutex<CoreMempool>>, gc_interval_ms: u64) {
     let mut interval = Interval::new_interval(Duration::from_millis(gc_interval_ms));
@@ -26,7 +26,8 @@ use network::{
     protocols::rpc::error::RpcError,
     validator_network::{
         network_builder::{NetworkBuilder, TransportType},
-        ConsensusNetworkSender, Event, CONSENSUS_DIRECT_SEND_PROTOCOL, CONSENSUS_RPC_PROTOCOL,
+        ConsensusNetworkSender, Event, CONSENSUS_DIRECT_SEND_PROTOCOL,
+        CONSENSUS_STREAMING_RPC_PROTOCOL,
     },
     NetworkPublicKeys, ProtocolId,
 };
@@ -247,10 +248,14 @@ fn rpc_bench(b: &mut Bencher, msg_len: &usize) {
     .trusted_peers(trusted_peers.clone())
     .signing_keys((listener_signing_private_key, listener_signing_public_key))
     .discovery_interval_ms(HOUR_IN_MS)
-    .rpc_protocols(vec![ProtocolId::from_static(CONSENSUS_RPC_PROTOCOL)])
+    .streaming_rpc_protocols(vec![ProtocolId::from_static(
+        CONSENSUS_STREAMING_RPC_PROTOCOL,
+    )])
     .build();
     let (_listener_sender, mut listener_events) =
-        network_provider.add_consensus(vec![ProtocolId::from_static(CONSENSUS_RPC_PROTOCOL)]);
+        network_provider.add_consensus(vec![ProtocolId::from_static(
+            CONSENSUS_STREAMING_RPC_PROTOCOL,
+        )]);
     runtime.executor().spawn(network_provider.start());
 
     // Set up the dialer network
@@ -273,10 +278,14 @@ fn rpc_bench(b: &mut Bencher, msg_len: &usize) {
             .collect(),
     )
     .discovery_interval_ms(HOUR_IN_MS)
-    .rpc_protocols(vec![ProtocolId::from_static(CONSENSUS_RPC_PROTOCOL)])
+    .streaming_rpc_protocols(vec![ProtocolId::from_static(
+        CONSENSUS_STREAMING_RPC_PROTOCOL,
+    )])
     .build();
     let (dialer_sender, mut dialer_events) =
-        network_provider.add_consensus(vec![ProtocolId::from_static(CONSENSUS_RPC_PROTOCOL)]);
+        network_provider.add_consensus(vec![ProtocolId::from_static(
+            CONSENSUS_STREAMING_RPC_PROTOCOL,
+        )]);
     runtime.executor().spawn(network_provider.start());
 
     // Wait for establishing connection
@@ -293,8 +302,9 @@ fn rpc_bench(b: &mut Bencher, msg_len: &usize) {
     let f_listener = async move {
         while let Some(Ok(event)) = listener_events.next().await {
             match event {
-                Event::RpcRequest((_, _, res_tx)) => res_tx
+                Event::StreamingRpcRequest((_, _, mut res_tx)) => res_tx
                     .send(Ok(res.clone().to_bytes().expect("fail to serialize proto")))
+                    .await
                     .expect("fail to send rpc response to network"),
                 event => panic!("Unexpected event: {:?}", event),
             }
@@ -333,6 +343,7 @@ async fn request_block(
 fn compose_request_block() -> RequestBlock {
     let mut req = RequestBlock::default();
     req.block_id = vec![0u8; 32];
+    req.num_blocks = 1;
     req
 }
 
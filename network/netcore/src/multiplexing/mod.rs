@@ -6,12 +6,14 @@
 //! The main component of this module is the [`StreamMultiplexer`] trait, which
 //! provides an interface for multiplexing multiple [`AsyncRead`]/[`AsyncWrite`] substreams over a
 //! single underlying [`AsyncRead`]/[`AsyncWrite`] stream. [`Yamux`], an implementation of this
-//! trait over [`TcpStream`], is also provided.
+//! trait over [`TcpStream`], is also provided, along with [`UdpConnection`], which multiplexes
+//! independently retransmitted substreams directly over UDP datagrams.
 //!
 //! [`StreamMultiplexer`]: crate::multiplexing::StreamMultiplexer
 //! [`AsyncRead`]: futures::io::AsyncRead
 //! [`AsyncWrite`]: futures::io::AsyncWrite
 //! [`TcpStream`]: tokio::net::tcp::TcpStream
+//! [`UdpConnection`]: crate::multiplexing::udp::UdpConnection
 //! [`Yamux`]: crate::multiplexing::yamux::Yamux
 
 use futures::{
@@ -21,6 +23,7 @@ use futures::{
 };
 use std::{fmt::Debug, io};
 
+pub mod udp;
 pub mod yamux;
 
 /// A StreamMultiplexer is responsible for multiplexing multiple [`AsyncRead`]/[`AsyncWrite`]
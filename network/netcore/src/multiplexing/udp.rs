@@ -0,0 +1,1223 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Implementation of [`StreamMultiplexer`] over UDP
+//!
+//! A [`UdpConnection`] multiplexes reliable and ordered substreams over the datagrams exchanged
+//! with a single remote address. Unlike [`Yamux`], which runs over a single ordered byte stream,
+//! every substream acknowledges and retransmits its own frames, so the loss of a datagram only
+//! delays the substream it belongs to instead of stalling all of them.
+//!
+//! Every datagram carries a single frame, prefixed with a flags byte and a packet number:
+//!
+//! ```text
+//! datagram := flags: u8 | packet number: u64 | frame
+//! frame    := CONNECT cookie: u64 | CONNECT_ACK | RETRY cookie: u64 | PING | CLOSE
+//!           | DATA stream: u32 | seq: u32 | fin: u8 | data
+//!           | ACK stream: u32 | next seq: u32
+//!           | RESET stream: u32
+//! ```
+//!
+//! A substream is opened implicitly by its first DATA frame: the dialer uses odd ids and the
+//! listener even ones. The substream 0 exists on both sides from the start and carries the
+//! [handshake](UdpConnection::handshake_stream) deriving the protection of the datagrams. Once a
+//! [`PacketCipher`] is [installed](UdpConnection::set_packet_cipher), every frame except the
+//! connection requests and the handshake messages is sealed with the packet number as nonce, and
+//! the unprotected frames received from the remote are dropped. The receiver keeps a sliding
+//! window of the packet numbers it authenticated, so that a replayed datagram is dropped as well.
+//!
+//! A listener does not keep any state for a dialer before the dialer proved that it receives the
+//! datagrams sent to its address: it answers a CONNECT without a valid cookie with a RETRY carrying
+//! one, which the dialer echoes in its next CONNECT.
+//!
+//! The receiver acknowledges the frames of a substream cumulatively and buffers a bounded number
+//! of them, dropping the ones which do not fit. The sender keeps a bounded number of frames in
+//! flight, and retransmits them with an exponential backoff until they are acknowledged. There is
+//! no congestion control.
+//!
+//! The connections do not own their socket: the [`UdpTransport`] feeds them the datagrams received
+//! from their remote and drives their timers.
+//!
+//! [`StreamMultiplexer`]: crate::multiplexing::StreamMultiplexer
+//! [`UdpTransport`]: crate::transport::udp::UdpTransport
+//! [`Yamux`]: crate::multiplexing::yamux::Yamux
+
+use crate::{multiplexing::StreamMultiplexer, transport::ConnectionOrigin};
+use bytes::Bytes;
+use futures::{
+    future,
+    io::{AsyncRead, AsyncWrite},
+    stream::Stream,
+};
+use std::{
+    cmp,
+    collections::{BTreeMap, HashMap, HashSet, VecDeque},
+    convert::TryInto,
+    fmt, io,
+    net::{SocketAddr, UdpSocket},
+    pin::Pin,
+    sync::{Arc, Mutex, MutexGuard},
+    task::{Context, Poll, Waker},
+    time::{Duration, Instant},
+};
+
+/// Maximum size of the data carried by a DATA frame, which keeps the datagrams below the usual
+/// path MTU.
+pub const MAX_FRAME_DATA: usize = 1200;
+/// Size of the buffer large enough to receive any datagram of a connection.
+pub const MAX_DATAGRAM_SIZE: usize = 1500;
+
+/// Maximum number of unacknowledged frames of a substream.
+const SEND_WINDOW: usize = 64;
+/// Maximum number of frames of a substream buffered by the receiver.
+const RECV_WINDOW: usize = 64;
+/// Retransmission timeout of a frame sent for the first time, doubled on every retransmission.
+const INITIAL_RTO: Duration = Duration::from_millis(200);
+const MAX_RTO: Duration = Duration::from_secs(5);
+/// Number of retransmissions of a frame after which the remote is considered unreachable.
+const MAX_RETRANSMISSIONS: u32 = 10;
+/// Interval after which an idle connection sends a PING to keep the remote from timing out.
+const KEEPALIVE_INTERVAL: Duration = Duration::from_secs(5);
+/// Duration without any datagram from the remote after which the connection is closed.
+const IDLE_TIMEOUT: Duration = Duration::from_secs(30);
+/// Maximum number of inbound substreams waiting to be accepted.
+const MAX_PENDING_INBOUND_SUBSTREAMS: u32 = 64;
+const HANDSHAKE_STREAM_ID: u32 = 0;
+
+/// Size of the header of a datagram: the flags and the packet number.
+const HEADER_LEN: usize = 9;
+/// Flag of the datagrams sealed with the packet cipher.
+const PROTECTED: u8 = 1;
+
+// Frame types
+const CONNECT: u8 = 0;
+const CONNECT_ACK: u8 = 1;
+const DATA: u8 = 2;
+const ACK: u8 = 3;
+const RESET: u8 = 4;
+const CLOSE: u8 = 5;
+const PING: u8 = 6;
+const RETRY: u8 = 7;
+/// Number of packet numbers below the highest one received which are checked for replays.
+const REPLAY_WINDOW: u64 = 128;
+
+/// Protection of the datagrams of a [`UdpConnection`], usually derived from the handshake
+/// performed over its [handshake substream](UdpConnection::handshake_stream).
+pub trait PacketCipher: Send {
+    /// Encrypt and authenticate a frame, using the packet number as nonce.
+    fn seal(&self, nonce: u64, plaintext: &[u8]) -> io::Result<Vec<u8>>;
+
+    /// Decrypt and authenticate a frame sealed by the remote with the given nonce.
+    fn open(&self, nonce: u64, ciphertext: &[u8]) -> io::Result<Vec<u8>>;
+}
+
+#[derive(Debug, PartialEq)]
+enum Frame {
+    /// The cookie is the one of the last RETRY received, if any.
+    Connect {
+        cookie: Option<u64>,
+    },
+    ConnectAck,
+    Retry {
+        cookie: u64,
+    },
+    Data {
+        stream_id: u32,
+        seq: u32,
+        fin: bool,
+        data: Bytes,
+    },
+    Ack {
+        stream_id: u32,
+        next_seq: u32,
+    },
+    Reset {
+        stream_id: u32,
+    },
+    Close,
+    Ping,
+}
+
+impl Frame {
+    fn encode(&self) -> Vec<u8> {
+        let mut buf = Vec::new();
+        match self {
+            Frame::Connect { cookie } => {
+                buf.push(CONNECT);
+                buf.extend_from_slice(&cookie.unwrap_or(0).to_be_bytes());
+            }
+            Frame::ConnectAck => buf.push(CONNECT_ACK),
+            Frame::Retry { cookie } => {
+                buf.push(RETRY);
+                buf.extend_from_slice(&cookie.to_be_bytes());
+            }
+            Frame::Data {
+                stream_id,
+                seq,
+                fin,
+                data,
+            } => {
+                buf.reserve(10 + data.len());
+                buf.push(DATA);
+                buf.extend_from_slice(&stream_id.to_be_bytes());
+                buf.extend_from_slice(&seq.to_be_bytes());
+                buf.push(*fin as u8);
+                buf.extend_from_slice(data);
+            }
+            Frame::Ack {
+                stream_id,
+                next_seq,
+            } => {
+                buf.push(ACK);
+                buf.extend_from_slice(&stream_id.to_be_bytes());
+                buf.extend_from_slice(&next_seq.to_be_bytes());
+            }
+            Frame::Reset { stream_id } => {
+                buf.push(RESET);
+                buf.extend_from_slice(&stream_id.to_be_bytes());
+            }
+            Frame::Close => buf.push(CLOSE),
+            Frame::Ping => buf.push(PING),
+        }
+        buf
+    }
+
+    fn decode(buf: &[u8]) -> io::Result<Self> {
+        let (frame_type, body) = buf
+            .split_first()
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "Empty frame"))?;
+        let frame = match *frame_type {
+            CONNECT if body.len() == 8 => Frame::Connect {
+                cookie: Some(read_u64(body)).filter(|cookie| *cookie != 0),
+            },
+            CONNECT_ACK if body.is_empty() => Frame::ConnectAck,
+            RETRY if body.len() == 8 => Frame::Retry {
+                cookie: read_u64(body),
+            },
+            DATA if body.len() >= 9 => Frame::Data {
+                stream_id: read_u32(&body[0..4]),
+                seq: read_u32(&body[4..8]),
+                fin: body[8] != 0,
+                data: Bytes::from(&body[9..]),
+            },
+            ACK if body.len() == 8 => Frame::Ack {
+                stream_id: read_u32(&body[0..4]),
+                next_seq: read_u32(&body[4..8]),
+            },
+            RESET if body.len() == 4 => Frame::Reset {
+                stream_id: read_u32(body),
+            },
+            CLOSE if body.is_empty() => Frame::Close,
+            PING if body.is_empty() => Frame::Ping,
+            _ => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("Malformed frame of type {}", frame_type),
+                ))
+            }
+        };
+        Ok(frame)
+    }
+
+    /// Whether the frame may be exchanged before the packet protection is set up, and is
+    /// therefore never sealed. The handshake messages stay unprotected even once the cipher is
+    /// installed, since the remote may still need their retransmission to derive it.
+    fn is_handshake(&self) -> bool {
+        match self {
+            Frame::Connect { .. } | Frame::ConnectAck | Frame::Retry { .. } => true,
+            Frame::Data { stream_id, .. } => *stream_id == HANDSHAKE_STREAM_ID,
+            _ => false,
+        }
+    }
+}
+
+/// Whether the datagram is the connection request of a dialer.
+pub(crate) fn is_connect_request(datagram: &[u8]) -> bool {
+    datagram.len() == HEADER_LEN + 9 && datagram[0] == 0 && datagram[HEADER_LEN] == CONNECT
+}
+
+/// Cookie echoed by a connection request, if any.
+pub(crate) fn connect_cookie(datagram: &[u8]) -> Option<u64> {
+    if !is_connect_request(datagram) {
+        return None;
+    }
+    match Frame::decode(&datagram[HEADER_LEN..]) {
+        Ok(Frame::Connect { cookie }) => cookie,
+        _ => None,
+    }
+}
+
+/// Datagram answering a connection request without a valid cookie. It is no larger than the
+/// request, so that it cannot amplify the traffic sent to a spoofed address.
+pub(crate) fn retry_datagram(cookie: u64) -> Vec<u8> {
+    let mut datagram = vec![0; HEADER_LEN];
+    datagram.extend_from_slice(&Frame::Retry { cookie }.encode());
+    datagram
+}
+
+fn read_u32(buf: &[u8]) -> u32 {
+    u32::from_be_bytes(buf.try_into().expect("Slice of 4 bytes"))
+}
+
+fn read_u64(buf: &[u8]) -> u64 {
+    u64::from_be_bytes(buf.try_into().expect("Slice of 8 bytes"))
+}
+
+/// Retransmission timeout of a frame which was already retransmitted the given number of times.
+fn rto(retransmissions: u32) -> Duration {
+    cmp::min(
+        INITIAL_RTO * 2u32.pow(cmp::min(retransmissions, 8)),
+        MAX_RTO,
+    )
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Status {
+    /// The dialer waits for the listener to acknowledge the connection.
+    Connecting,
+    Established,
+    Closed,
+}
+
+/// A DATA frame waiting to be acknowledged by the remote.
+struct SentFrame {
+    seq: u32,
+    data: Bytes,
+    fin: bool,
+    sent_at: Instant,
+    retransmissions: u32,
+}
+
+#[derive(Default)]
+struct SubstreamState {
+    /// Sequence number of the next frame sent.
+    next_seq: u32,
+    unacked: VecDeque<SentFrame>,
+    fin_sent: bool,
+    write_waker: Option<Waker>,
+    /// Sequence number of the next frame to receive in order.
+    next_expected: u32,
+    out_of_order: BTreeMap<u32, (Bytes, bool)>,
+    readable: VecDeque<Bytes>,
+    fin_received: bool,
+    read_waker: Option<Waker>,
+    reset: bool,
+    /// The handle of the substream was dropped: the received data is discarded.
+    dropped: bool,
+}
+
+impl SubstreamState {
+    fn wake(&mut self) {
+        if let Some(waker) = self.read_waker.take() {
+            waker.wake();
+        }
+        if let Some(waker) = self.write_waker.take() {
+            waker.wake();
+        }
+    }
+
+    /// Whether the substream can be forgotten: its handle was dropped and the remote received all
+    /// of its data.
+    fn is_finished(&self) -> bool {
+        self.dropped && (self.reset || (self.fin_sent && self.unacked.is_empty()))
+    }
+
+    /// Queue a new DATA frame for sending, and return it.
+    fn push_frame(&mut self, stream_id: u32, data: Bytes, fin: bool) -> Frame {
+        let seq = self.next_seq;
+        self.next_seq += 1;
+        self.fin_sent |= fin;
+        self.unacked.push_back(SentFrame {
+            seq,
+            data: data.clone(),
+            fin,
+            sent_at: Instant::now(),
+            retransmissions: 0,
+        });
+        Frame::Data {
+            stream_id,
+            seq,
+            fin,
+            data,
+        }
+    }
+
+    fn on_data(&mut self, seq: u32, fin: bool, data: Bytes) {
+        if self.dropped {
+            // Nobody reads the substream anymore: acknowledge the frames without buffering them.
+            self.next_expected = cmp::max(self.next_expected, seq.saturating_add(1));
+            return;
+        }
+        // Frames which do not fit in the buffer are dropped, and retransmitted by the remote once
+        // the reader made some room.
+        let window = RECV_WINDOW.saturating_sub(self.readable.len()) as u64;
+        if seq < self.next_expected || u64::from(seq) >= u64::from(self.next_expected) + window {
+            return;
+        }
+        self.out_of_order.insert(seq, (data, fin));
+        let mut readable = false;
+        while let Some((data, fin)) = self.out_of_order.remove(&self.next_expected) {
+            self.next_expected += 1;
+            if !data.is_empty() {
+                self.readable.push_back(data);
+            }
+            self.fin_received |= fin;
+            readable = true;
+        }
+        if readable {
+            if let Some(waker) = self.read_waker.take() {
+                waker.wake();
+            }
+        }
+    }
+
+    fn on_ack(&mut self, next_seq: u32) {
+        let unacked = self.unacked.len();
+        while self
+            .unacked
+            .front()
+            .map_or(false, |frame| frame.seq < next_seq)
+        {
+            self.unacked.pop_front();
+        }
+        if self.unacked.len() < unacked {
+            if let Some(waker) = self.write_waker.take() {
+                waker.wake();
+            }
+        }
+    }
+}
+
+/// Packet numbers authenticated recently, used to drop the replayed datagrams.
+#[derive(Default)]
+struct ReplayWindow {
+    /// Highest packet number received plus one, or 0 if none was received yet.
+    next: u64,
+    /// Bit `i` is set if the packet number `next - 1 - i` was received.
+    received: u128,
+}
+
+impl ReplayWindow {
+    /// Whether the packet number was not received yet and is recent enough to be checked.
+    fn is_fresh(&self, packet_number: u64) -> bool {
+        if packet_number >= self.next {
+            return true;
+        }
+        let offset = self.next - 1 - packet_number;
+        offset < REPLAY_WINDOW && self.received & (1 << offset) == 0
+    }
+
+    fn insert(&mut self, packet_number: u64) {
+        if packet_number >= self.next {
+            let shift = packet_number + 1 - self.next;
+            self.received = if shift >= REPLAY_WINDOW {
+                0
+            } else {
+                self.received << shift
+            };
+            self.received |= 1;
+            self.next = packet_number + 1;
+        } else {
+            self.received |= 1 << (self.next - 1 - packet_number);
+        }
+    }
+}
+
+/// Outcome of the first frame of an unknown substream.
+enum RemoteSubstream {
+    /// The remote opened a new substream.
+    Opened,
+    /// The substream was already finished on our side.
+    Finished,
+    /// The substream is refused.
+    Refused,
+}
+
+struct State {
+    socket: Arc<UdpSocket>,
+    remote_addr: SocketAddr,
+    origin: ConnectionOrigin,
+    status: Status,
+    cipher: Option<Box<dyn PacketCipher>>,
+    next_packet_number: u64,
+    received_packets: ReplayWindow,
+    next_stream_id: u32,
+    /// Lowest id the remote did not use yet.
+    next_remote_stream_id: u32,
+    /// Ids below `next_remote_stream_id` whose first frame was not received yet.
+    missing_remote_stream_ids: HashSet<u32>,
+    substreams: HashMap<u32, SubstreamState>,
+    handshake_stream_taken: bool,
+    /// Inbound substreams waiting to be accepted.
+    inbound: VecDeque<u32>,
+    inbound_waker: Option<Waker>,
+    connect_waker: Option<Waker>,
+    /// Cookie of the last RETRY received by the dialer.
+    connect_cookie: Option<u64>,
+    connect_sent_at: Instant,
+    connect_retransmissions: u32,
+    last_sent: Instant,
+    last_received: Instant,
+}
+
+impl State {
+    fn send_frame(&mut self, frame: &Frame) {
+        let packet_number = self.next_packet_number;
+        self.next_packet_number += 1;
+        let plaintext = frame.encode();
+        let mut datagram = Vec::with_capacity(HEADER_LEN + plaintext.len() + 16);
+        match self.cipher {
+            Some(ref cipher) if !frame.is_handshake() => {
+                let ciphertext = match cipher.seal(packet_number, &plaintext) {
+                    Ok(ciphertext) => ciphertext,
+                    Err(_) => return,
+                };
+                datagram.push(PROTECTED);
+                datagram.extend_from_slice(&packet_number.to_be_bytes());
+                datagram.extend_from_slice(&ciphertext);
+            }
+            _ => {
+                datagram.push(0);
+                datagram.extend_from_slice(&packet_number.to_be_bytes());
+                datagram.extend_from_slice(&plaintext);
+            }
+        }
+        // Sending is best effort: a datagram which does not fit in the send buffer of the socket
+        // is lost, and retransmitted like any other lost datagram.
+        let _ = self.socket.send_to(&datagram, self.remote_addr);
+        self.last_sent = Instant::now();
+    }
+
+    fn close(&mut self) {
+        if self.status == Status::Closed {
+            return;
+        }
+        self.status = Status::Closed;
+        for substream in self.substreams.values_mut() {
+            substream.wake();
+        }
+        if let Some(waker) = self.inbound_waker.take() {
+            waker.wake();
+        }
+        if let Some(waker) = self.connect_waker.take() {
+            waker.wake();
+        }
+    }
+
+    fn remove_if_finished(&mut self, stream_id: u32) {
+        if self
+            .substreams
+            .get(&stream_id)
+            .map_or(false, SubstreamState::is_finished)
+        {
+            self.substreams.remove(&stream_id);
+        }
+    }
+
+    fn on_datagram(&mut self, datagram: &[u8]) {
+        if self.status == Status::Closed || datagram.len() < HEADER_LEN {
+            return;
+        }
+        let flags = datagram[0];
+        let packet_number = u64::from_be_bytes(
+            datagram[1..HEADER_LEN]
+                .try_into()
+                .expect("Slice of 8 bytes"),
+        );
+        let payload = &datagram[HEADER_LEN..];
+        let frame = if flags & PROTECTED != 0 {
+            // The protected datagrams received before the cipher is installed are dropped, and
+            // retransmitted by the remote later on.
+            let cipher = match self.cipher {
+                Some(ref cipher) => cipher,
+                None => return,
+            };
+            if !self.received_packets.is_fresh(packet_number) {
+                return;
+            }
+            let frame = match cipher
+                .open(packet_number, payload)
+                .and_then(|plaintext| Frame::decode(&plaintext))
+            {
+                Ok(frame) => frame,
+                Err(_) => return,
+            };
+            self.received_packets.insert(packet_number);
+            // The remote derived the cipher, so it received all of our handshake messages.
+            if let Some(substream) = self.substreams.get_mut(&HANDSHAKE_STREAM_ID) {
+                if !substream.unacked.is_empty() {
+                    substream.unacked.clear();
+                    substream.wake();
+                }
+            }
+            self.remove_if_finished(HANDSHAKE_STREAM_ID);
+            frame
+        } else {
+            // Once the cipher is installed, the unprotected frames may have been injected by a
+            // third party: they are dropped, including the retransmitted handshake messages.
+            if self.cipher.is_some() {
+                return;
+            }
+            match Frame::decode(payload) {
+                Ok(Frame::Retry { cookie }) => {
+                    if self.status == Status::Connecting {
+                        self.connect_cookie = Some(cookie);
+                        self.send_frame(&Frame::Connect {
+                            cookie: Some(cookie),
+                        });
+                    }
+                    return;
+                }
+                Ok(frame) => frame,
+                Err(_) => return,
+            }
+        };
+        self.last_received = Instant::now();
+        // The listener only sends frames once it accepted the connection.
+        if self.status == Status::Connecting {
+            self.status = Status::Established;
+            if let Some(waker) = self.connect_waker.take() {
+                waker.wake();
+            }
+        }
+
+        match frame {
+            Frame::Connect { .. } => {
+                if self.origin == ConnectionOrigin::Inbound {
+                    self.send_frame(&Frame::ConnectAck);
+                }
+            }
+            Frame::ConnectAck | Frame::Retry { .. } | Frame::Ping => {}
+            Frame::Data {
+                stream_id,
+                seq,
+                fin,
+                data,
+            } => self.on_data(stream_id, seq, fin, data),
+            Frame::Ack {
+                stream_id,
+                next_seq,
+            } => {
+                if let Some(substream) = self.substreams.get_mut(&stream_id) {
+                    substream.on_ack(next_seq);
+                }
+                self.remove_if_finished(stream_id);
+            }
+            Frame::Reset { stream_id } => {
+                if let Some(substream) = self.substreams.get_mut(&stream_id) {
+                    substream.reset = true;
+                    substream.unacked.clear();
+                    substream.wake();
+                }
+                self.remove_if_finished(stream_id);
+            }
+            Frame::Close => self.close(),
+        }
+    }
+
+    fn on_data(&mut self, stream_id: u32, seq: u32, fin: bool, data: Bytes) {
+        if !self.substreams.contains_key(&stream_id) {
+            match self.open_remote_substream(stream_id) {
+                RemoteSubstream::Opened => {}
+                RemoteSubstream::Finished => {
+                    // Our acknowledgement was lost: acknowledge the frame again so that the remote
+                    // stops retransmitting it.
+                    self.send_frame(&Frame::Ack {
+                        stream_id,
+                        next_seq: seq.saturating_add(1),
+                    });
+                    return;
+                }
+                RemoteSubstream::Refused => {
+                    self.send_frame(&Frame::Reset { stream_id });
+                    return;
+                }
+            }
+        }
+        let next_seq = match self.substreams.get_mut(&stream_id) {
+            Some(substream) => {
+                substream.on_data(seq, fin, data);
+                substream.next_expected
+            }
+            None => return,
+        };
+        self.send_frame(&Frame::Ack {
+            stream_id,
+            next_seq,
+        });
+    }
+
+    fn open_remote_substream(&mut self, stream_id: u32) -> RemoteSubstream {
+        let is_remote = stream_id != HANDSHAKE_STREAM_ID
+            && (stream_id % 2 == 1) == (self.origin == ConnectionOrigin::Inbound);
+        if !is_remote {
+            return RemoteSubstream::Finished;
+        }
+        if stream_id >= self.next_remote_stream_id {
+            if stream_id - self.next_remote_stream_id > 2 * MAX_PENDING_INBOUND_SUBSTREAMS {
+                return RemoteSubstream::Refused;
+            }
+            // The first frames of the substreams opened in between may still be in flight.
+            let mut missing_stream_id = self.next_remote_stream_id;
+            while missing_stream_id < stream_id {
+                self.missing_remote_stream_ids.insert(missing_stream_id);
+                missing_stream_id += 2;
+            }
+            self.next_remote_stream_id = stream_id + 2;
+        } else if !self.missing_remote_stream_ids.remove(&stream_id) {
+            return RemoteSubstream::Finished;
+        }
+        if self.inbound.len() >= MAX_PENDING_INBOUND_SUBSTREAMS as usize {
+            return RemoteSubstream::Refused;
+        }
+        self.substreams.insert(stream_id, SubstreamState::default());
+        self.inbound.push_back(stream_id);
+        if let Some(waker) = self.inbound_waker.take() {
+            waker.wake();
+        }
+        RemoteSubstream::Opened
+    }
+
+    fn on_timer(&mut self, now: Instant) {
+        match self.status {
+            Status::Closed => return,
+            Status::Connecting => {
+                if now >= self.connect_sent_at + rto(self.connect_retransmissions) {
+                    if self.connect_retransmissions >= MAX_RETRANSMISSIONS {
+                        self.close();
+                        return;
+                    }
+                    self.connect_retransmissions += 1;
+                    self.connect_sent_at = now;
+                    self.send_frame(&Frame::Connect {
+                        cookie: self.connect_cookie,
+                    });
+                }
+                return;
+            }
+            Status::Established => {}
+        }
+        if now.duration_since(self.last_received) >= IDLE_TIMEOUT {
+            self.close();
+            return;
+        }
+
+        let mut retransmissions = vec![];
+        let mut unreachable = false;
+        for (stream_id, substream) in self.substreams.iter_mut() {
+            for frame in substream.unacked.iter_mut() {
+                if now < frame.sent_at + rto(frame.retransmissions) {
+                    continue;
+                }
+                unreachable |= frame.retransmissions >= MAX_RETRANSMISSIONS;
+                frame.retransmissions += 1;
+                frame.sent_at = now;
+                retransmissions.push(Frame::Data {
+                    stream_id: *stream_id,
+                    seq: frame.seq,
+                    fin: frame.fin,
+                    data: frame.data.clone(),
+                });
+            }
+        }
+        if unreachable {
+            self.close();
+            return;
+        }
+        for frame in retransmissions {
+            self.send_frame(&frame);
+        }
+        if now.duration_since(self.last_sent) >= KEEPALIVE_INTERVAL {
+            self.send_frame(&Frame::Ping);
+        }
+    }
+}
+
+/// State of a connection shared by its handles and the endpoint driving it.
+pub(crate) struct Inner {
+    state: Mutex<State>,
+}
+
+impl Inner {
+    pub(crate) fn new(
+        socket: Arc<UdpSocket>,
+        remote_addr: SocketAddr,
+        origin: ConnectionOrigin,
+    ) -> Arc<Self> {
+        let now = Instant::now();
+        let (status, next_stream_id, next_remote_stream_id) = match origin {
+            ConnectionOrigin::Inbound => (Status::Established, 2, 1),
+            ConnectionOrigin::Outbound => (Status::Connecting, 1, 2),
+        };
+        let mut substreams = HashMap::new();
+        substreams.insert(HANDSHAKE_STREAM_ID, SubstreamState::default());
+        Arc::new(Self {
+            state: Mutex::new(State {
+                socket,
+                remote_addr,
+                origin,
+                status,
+                cipher: None,
+                next_packet_number: 0,
+                received_packets: ReplayWindow::default(),
+                next_stream_id,
+                next_remote_stream_id,
+                missing_remote_stream_ids: HashSet::new(),
+                substreams,
+                handshake_stream_taken: false,
+                inbound: VecDeque::new(),
+                inbound_waker: None,
+                connect_waker: None,
+                connect_cookie: None,
+                connect_sent_at: now,
+                connect_retransmissions: 0,
+                last_sent: now,
+                last_received: now,
+            }),
+        })
+    }
+
+    fn lock(&self) -> MutexGuard<State> {
+        self.state.lock().expect("Connection lock poisoned")
+    }
+
+    pub(crate) fn remote_addr(&self) -> SocketAddr {
+        self.lock().remote_addr
+    }
+
+    pub(crate) fn is_closed(&self) -> bool {
+        self.lock().status == Status::Closed
+    }
+
+    /// Send the connection request of the dialer.
+    pub(crate) fn connect(&self) {
+        let mut state = self.lock();
+        state.connect_sent_at = Instant::now();
+        state.send_frame(&Frame::Connect { cookie: None });
+    }
+
+    /// Resolves once the listener acknowledged the connection request.
+    pub(crate) fn poll_connected(&self, context: &mut Context) -> Poll<io::Result<()>> {
+        let mut state = self.lock();
+        match state.status {
+            Status::Established => Poll::Ready(Ok(())),
+            Status::Closed => Poll::Ready(Err(io::Error::new(
+                io::ErrorKind::TimedOut,
+                "Remote did not accept the connection",
+            ))),
+            Status::Connecting => {
+                state.connect_waker = Some(context.waker().clone());
+                Poll::Pending
+            }
+        }
+    }
+
+    pub(crate) fn on_datagram(&self, datagram: &[u8]) {
+        self.lock().on_datagram(datagram);
+    }
+
+    pub(crate) fn on_timer(&self, now: Instant) {
+        self.lock().on_timer(now);
+    }
+
+    /// Close the connection, notifying the remote.
+    pub(crate) fn shutdown(&self) {
+        let mut state = self.lock();
+        if state.status != Status::Closed {
+            state.send_frame(&Frame::Close);
+            state.close();
+        }
+    }
+}
+
+impl fmt::Debug for Inner {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let state = self.lock();
+        f.debug_struct("Inner")
+            .field("remote_addr", &state.remote_addr)
+            .field("origin", &state.origin)
+            .field("status", &state.status)
+            .field("protected", &state.cipher.is_some())
+            .field("substreams", &state.substreams.len())
+            .finish()
+    }
+}
+
+/// A connection multiplexing substreams over UDP, closed when dropped.
+#[derive(Debug)]
+pub struct UdpConnection {
+    inner: Arc<Inner>,
+}
+
+impl UdpConnection {
+    pub(crate) fn new(inner: Arc<Inner>) -> Self {
+        Self { inner }
+    }
+
+    pub fn remote_addr(&self) -> SocketAddr {
+        self.inner.remote_addr()
+    }
+
+    /// Take the handshake substream, which can be taken only once. Its data is never sealed, so
+    /// that it can carry the handshake deriving the [`PacketCipher`].
+    pub fn handshake_stream(&self) -> io::Result<UdpSubstream> {
+        let mut state = self.inner.lock();
+        if state.handshake_stream_taken {
+            return Err(io::Error::new(
+                io::ErrorKind::Other,
+                "Handshake substream already taken",
+            ));
+        }
+        state.handshake_stream_taken = true;
+        Ok(UdpSubstream::new(self.inner.clone(), HANDSHAKE_STREAM_ID))
+    }
+
+    /// Protect the datagrams of the connection with the given cipher. The substreams opened
+    /// before, which a third party may have injected, are reset.
+    pub fn set_packet_cipher(&self, cipher: Box<dyn PacketCipher>) {
+        let mut state = self.inner.lock();
+        state.cipher = Some(cipher);
+        let pending_stream_ids: Vec<_> = state.inbound.drain(..).collect();
+        for stream_id in pending_stream_ids {
+            if let Some(substream) = state.substreams.get_mut(&stream_id) {
+                substream.dropped = true;
+            }
+        }
+        let stream_ids: Vec<_> = state
+            .substreams
+            .keys()
+            .filter(|stream_id| **stream_id != HANDSHAKE_STREAM_ID)
+            .cloned()
+            .collect();
+        for stream_id in stream_ids {
+            if let Some(substream) = state.substreams.get_mut(&stream_id) {
+                substream.reset = true;
+                substream.unacked.clear();
+                substream.wake();
+            }
+            state.send_frame(&Frame::Reset { stream_id });
+            state.remove_if_finished(stream_id);
+        }
+    }
+}
+
+impl Drop for UdpConnection {
+    fn drop(&mut self) {
+        self.inner.shutdown();
+    }
+}
+
+impl StreamMultiplexer for UdpConnection {
+    type Substream = UdpSubstream;
+    type Listener = Listener;
+    type Outbound = future::Ready<io::Result<Self::Substream>>;
+    type Close = future::Ready<io::Result<()>>;
+
+    fn listen_for_inbound(&self) -> Self::Listener {
+        Listener {
+            inner: self.inner.clone(),
+        }
+    }
+
+    fn open_outbound(&self) -> Self::Outbound {
+        let mut state = self.inner.lock();
+        if state.status == Status::Closed {
+            return future::ready(Err(io::Error::new(
+                io::ErrorKind::Other,
+                "Unable to open substream; underlying connection is closed",
+            )));
+        }
+        let stream_id = state.next_stream_id;
+        state.next_stream_id += 2;
+        state
+            .substreams
+            .insert(stream_id, SubstreamState::default());
+        future::ready(Ok(UdpSubstream::new(self.inner.clone(), stream_id)))
+    }
+
+    fn close(&self) -> Self::Close {
+        self.inner.shutdown();
+        future::ready(Ok(()))
+    }
+}
+
+/// Stream of the substreams opened by the remote.
+#[derive(Debug)]
+#[must_use = "streams do nothing unless polled"]
+pub struct Listener {
+    inner: Arc<Inner>,
+}
+
+impl Stream for Listener {
+    type Item = io::Result<UdpSubstream>;
+
+    fn poll_next(self: Pin<&mut Self>, context: &mut Context) -> Poll<Option<Self::Item>> {
+        let mut state = self.inner.lock();
+        if let Some(stream_id) = state.inbound.pop_front() {
+            return Poll::Ready(Some(Ok(UdpSubstream::new(self.inner.clone(), stream_id))));
+        }
+        if state.status == Status::Closed {
+            return Poll::Ready(None);
+        }
+        state.inbound_waker = Some(context.waker().clone());
+        Poll::Pending
+    }
+}
+
+/// A reliable and ordered substream of a [`UdpConnection`]. Dropping it without closing it first
+/// closes it.
+#[derive(Debug)]
+pub struct UdpSubstream {
+    inner: Arc<Inner>,
+    stream_id: u32,
+}
+
+impl UdpSubstream {
+    fn new(inner: Arc<Inner>, stream_id: u32) -> Self {
+        Self { inner, stream_id }
+    }
+
+    fn poll_send(
+        &mut self,
+        context: &mut Context,
+        data: &[u8],
+        fin: bool,
+    ) -> Poll<io::Result<usize>> {
+        let mut state = self.inner.lock();
+        if state.status == Status::Closed {
+            return Poll::Ready(Err(io::Error::new(
+                io::ErrorKind::BrokenPipe,
+                "Connection closed",
+            )));
+        }
+        let frame = match state.substreams.get_mut(&self.stream_id) {
+            Some(ref substream) if substream.reset => {
+                return Poll::Ready(Err(io::ErrorKind::ConnectionReset.into()));
+            }
+            Some(ref substream) if substream.fin_sent => {
+                return Poll::Ready(Err(io::Error::new(
+                    io::ErrorKind::BrokenPipe,
+                    "Substream closed",
+                )));
+            }
+            Some(substream) => {
+                if substream.unacked.len() >= SEND_WINDOW {
+                    substream.write_waker = Some(context.waker().clone());
+                    return Poll::Pending;
+                }
+                let len = cmp::min(data.len(), MAX_FRAME_DATA);
+                substream.push_frame(self.stream_id, Bytes::from(&data[..len]), fin)
+            }
+            None => return Poll::Ready(Err(io::ErrorKind::ConnectionReset.into())),
+        };
+        state.send_frame(&frame);
+        match frame {
+            Frame::Data { data, .. } => Poll::Ready(Ok(data.len())),
+            _ => unreachable!(),
+        }
+    }
+}
+
+impl AsyncRead for UdpSubstream {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        context: &mut Context,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        let mut state = self.inner.lock();
+        let closed = state.status == Status::Closed;
+        let substream = match state.substreams.get_mut(&self.stream_id) {
+            Some(substream) => substream,
+            None => return Poll::Ready(Err(io::ErrorKind::ConnectionReset.into())),
+        };
+        if let Some(data) = substream.readable.front_mut() {
+            let len = cmp::min(data.len(), buf.len());
+            buf[..len].copy_from_slice(&data[..len]);
+            data.advance(len);
+            if data.is_empty() {
+                substream.readable.pop_front();
+            }
+            return Poll::Ready(Ok(len));
+        }
+        if substream.reset {
+            return Poll::Ready(Err(io::ErrorKind::ConnectionReset.into()));
+        }
+        if substream.fin_received || closed {
+            return Poll::Ready(Ok(0));
+        }
+        substream.read_waker = Some(context.waker().clone());
+        Poll::Pending
+    }
+}
+
+impl AsyncWrite for UdpSubstream {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        context: &mut Context,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        self.poll_send(context, buf, false)
+    }
+
+    /// The data is sent as soon as it is written.
+    fn poll_flush(self: Pin<&mut Self>, _context: &mut Context) -> Poll<io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+
+    /// Half-close the substream. The remote reads EOF once it received all of the data.
+    fn poll_close(mut self: Pin<&mut Self>, context: &mut Context) -> Poll<io::Result<()>> {
+        {
+            let state = self.inner.lock();
+            if state
+                .substreams
+                .get(&self.stream_id)
+                .map_or(false, |substream| substream.fin_sent)
+            {
+                return Poll::Ready(Ok(()));
+            }
+        }
+        self.poll_send(context, &[], true).map_ok(|_| ())
+    }
+}
+
+impl Drop for UdpSubstream {
+    fn drop(&mut self) {
+        let mut state = self.inner.lock();
+        let open = state.status != Status::Closed;
+        let frame = match state.substreams.get_mut(&self.stream_id) {
+            Some(substream) => {
+                substream.dropped = true;
+                substream.readable.clear();
+                substream.out_of_order.clear();
+                // The remote still receives the data written so far, even beyond the send window.
+                if open && !substream.fin_sent && !substream.reset {
+                    Some(substream.push_frame(self.stream_id, Bytes::new(), true))
+                } else {
+                    None
+                }
+            }
+            None => return,
+        };
+        if let Some(frame) = frame {
+            state.send_frame(&frame);
+        }
+        if !open {
+            state.substreams.remove(&self.stream_id);
+        } else {
+            state.remove_if_finished(self.stream_id);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{connect_cookie, Frame, ReplayWindow, HANDSHAKE_STREAM_ID, HEADER_LEN};
+    use bytes::Bytes;
+
+    #[test]
+    fn frame_roundtrip() {
+        let frames = vec![
+            Frame::Connect { cookie: None },
+            Frame::Connect { cookie: Some(42) },
+            Frame::ConnectAck,
+            Frame::Retry { cookie: 42 },
+            Frame::Data {
+                stream_id: 3,
+                seq: 7,
+                fin: false,
+                data: Bytes::from_static(b"hello"),
+            },
+            Frame::Data {
+                stream_id: 4,
+                seq: 0,
+                fin: true,
+                data: Bytes::new(),
+            },
+            Frame::Ack {
+                stream_id: 3,
+                next_seq: 8,
+            },
+            Frame::Reset { stream_id: 5 },
+            Frame::Close,
+            Frame::Ping,
+        ];
+        for frame in frames {
+            assert_eq!(Frame::decode(&frame.encode()).unwrap(), frame);
+        }
+    }
+
+    #[test]
+    fn malformed_frames() {
+        assert!(Frame::decode(&[]).is_err());
+        assert!(Frame::decode(&[42]).is_err());
+        // Truncated ACK
+        assert!(Frame::decode(&[3, 0, 0, 0, 1]).is_err());
+        // CLOSE with a body
+        assert!(Frame::decode(&[5, 0]).is_err());
+        // CONNECT without a cookie field
+        assert!(Frame::decode(&[0]).is_err());
+    }
+
+    #[test]
+    fn connect_cookies() {
+        let mut datagram = vec![0; HEADER_LEN];
+        datagram.extend_from_slice(&Frame::Connect { cookie: None }.encode());
+        assert_eq!(connect_cookie(&datagram), None);
+
+        let mut datagram = vec![0; HEADER_LEN];
+        datagram.extend_from_slice(&Frame::Connect { cookie: Some(7) }.encode());
+        assert_eq!(connect_cookie(&datagram), Some(7));
+        // The answer to a request is not larger than the request.
+        assert_eq!(super::retry_datagram(7).len(), datagram.len());
+    }
+
+    #[test]
+    fn replay_window() {
+        let mut window = ReplayWindow::default();
+        assert!(window.is_fresh(0));
+        window.insert(0);
+        assert!(!window.is_fresh(0));
+
+        // Reordered packets are accepted once.
+        window.insert(5);
+        assert!(window.is_fresh(3));
+        window.insert(3);
+        assert!(!window.is_fresh(3));
+        assert!(!window.is_fresh(5));
+        assert!(window.is_fresh(4));
+
+        // Packets which fell out of the window are dropped.
+        window.insert(200);
+        assert!(!window.is_fresh(4));
+        assert!(!window.is_fresh(72));
+        assert!(window.is_fresh(73));
+        assert!(!window.is_fresh(200));
+        assert!(window.is_fresh(201));
+    }
+
+    #[test]
+    fn handshake_frames() {
+        assert!(Frame::Connect { cookie: None }.is_handshake());
+        assert!(Frame::Data {
+            stream_id: HANDSHAKE_STREAM_ID,
+            seq: 0,
+            fin: false,
+            data: Bytes::new(),
+        }
+        .is_handshake());
+        // The acknowledgements are sealed once the cipher is installed.
+        assert!(!Frame::Ack {
+            stream_id: HANDSHAKE_STREAM_ID,
+            next_seq: 1,
+        }
+        .is_handshake());
+        assert!(!Frame::Data {
+            stream_id: 1,
+            seq: 0,
+            fin: false,
+            data: Bytes::new(),
+        }
+        .is_handshake());
+        assert!(!Frame::Close.is_handshake());
+    }
+}
@@ -20,6 +20,7 @@ pub mod boxed;
 pub mod memory;
 pub mod tcp;
 pub mod timeout;
+pub mod udp;
 
 /// Origin of how a Connection was established.
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
@@ -0,0 +1,472 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! UDP Transport
+//!
+//! The connections established by the [`UdpTransport`] are [`UdpConnection`]s, which multiplex
+//! their substreams by themselves and therefore do not need any further multiplexing upgrade.
+//!
+//! Every socket is driven by an endpoint task, which hands the datagrams it receives to the
+//! connection with the remote that sent them, and periodically fires the timers of the
+//! connections. The endpoint of a listening socket also accepts the connection requests of new
+//! remotes once they echoed a cookie bound to their address, while the endpoint of a dialing
+//! socket serves a single connection and terminates along with it.
+//!
+//! [`UdpConnection`]: crate::multiplexing::udp::UdpConnection
+
+use crate::{
+    multiplexing::udp::{
+        connect_cookie, is_connect_request, retry_datagram, Inner, UdpConnection, MAX_DATAGRAM_SIZE,
+    },
+    transport::{ConnectionOrigin, Transport},
+};
+use futures::{
+    channel::mpsc,
+    future::{self, Future, FutureExt},
+    pin_mut, ready, select,
+    stream::{Stream, StreamExt},
+};
+use parity_multiaddr::{Multiaddr, Protocol};
+use std::{
+    cmp,
+    collections::{hash_map::RandomState, HashMap},
+    convert::TryFrom,
+    hash::{BuildHasher, Hash, Hasher},
+    io,
+    net::{self, SocketAddr},
+    pin::Pin,
+    sync::Arc,
+    task::{Context, Poll},
+    time::{Duration, Instant},
+};
+use tokio::{net::udp::UdpSocket, timer::Interval};
+
+/// Interval at which the endpoints fire the timers of their connections.
+const TIMER_INTERVAL: Duration = Duration::from_millis(50);
+/// Maximum number of inbound connections waiting to be accepted by the listener.
+const ACCEPT_QUEUE_SIZE: usize = 32;
+/// Period after which the listener issues new cookies. A cookie remains valid during the next
+/// period as well.
+const COOKIE_PERIOD: Duration = Duration::from_secs(30);
+
+/// Transport to build connections multiplexing independent substreams over UDP
+#[derive(Debug, Clone, Default)]
+pub struct UdpTransport;
+
+impl Transport for UdpTransport {
+    type Output = UdpConnection;
+    type Error = io::Error;
+    type Listener = UdpListenerStream;
+    type Inbound = future::Ready<io::Result<UdpConnection>>;
+    type Outbound = UdpOutbound;
+
+    fn listen_on(&self, addr: Multiaddr) -> Result<(Self::Listener, Multiaddr), Self::Error> {
+        let socket_addr = multiaddr_to_socketaddr(&addr)?;
+        let socket = net::UdpSocket::bind(&socket_addr)?;
+        let local_addr = socketaddr_to_multiaddr(socket.local_addr()?);
+        let (accept_tx, accept_rx) = mpsc::channel(ACCEPT_QUEUE_SIZE);
+        let endpoint = Endpoint::new(socket, Some(accept_tx))?;
+
+        Ok((
+            UdpListenerStream {
+                endpoint: Some(endpoint),
+                incoming: accept_rx,
+            },
+            local_addr,
+        ))
+    }
+
+    fn dial(&self, addr: Multiaddr) -> Result<Self::Outbound, Self::Error> {
+        let remote_addr = multiaddr_to_socketaddr(&addr)?;
+        let bind_addr: SocketAddr = if remote_addr.is_ipv4() {
+            ([0, 0, 0, 0], 0).into()
+        } else {
+            ([0, 0, 0, 0, 0, 0, 0, 0], 0).into()
+        };
+        let mut endpoint = Endpoint::new(net::UdpSocket::bind(&bind_addr)?, None)?;
+        let inner = endpoint.connect(remote_addr);
+        Ok(UdpOutbound {
+            endpoint: Some(endpoint),
+            inner: Some(inner),
+        })
+    }
+}
+
+/// Owner of a socket, driving the connections established over it.
+struct Endpoint {
+    /// Socket used by the connections to send their datagrams. It is non-blocking, so that the
+    /// datagrams which do not fit in its send buffer are simply lost.
+    socket: Arc<net::UdpSocket>,
+    recv_socket: UdpSocket,
+    connections: HashMap<SocketAddr, Arc<Inner>>,
+    /// Queue of the accepted connections, or `None` if the endpoint does not listen.
+    accept_tx: Option<mpsc::Sender<Arc<Inner>>>,
+    /// Secret keying the cookies of the connection requests.
+    cookie_key: RandomState,
+    created_at: Instant,
+}
+
+impl Endpoint {
+    fn new(
+        socket: net::UdpSocket,
+        accept_tx: Option<mpsc::Sender<Arc<Inner>>>,
+    ) -> io::Result<Self> {
+        socket.set_nonblocking(true)?;
+        let recv_socket = UdpSocket::try_from(socket.try_clone()?)?;
+        Ok(Self {
+            socket: Arc::new(socket),
+            recv_socket,
+            connections: HashMap::new(),
+            accept_tx,
+            cookie_key: RandomState::new(),
+            created_at: Instant::now(),
+        })
+    }
+
+    /// Cookie proving that a dialer receives the datagrams sent to its address, during the given
+    /// period.
+    fn cookie(&self, remote_addr: SocketAddr, period: u64) -> u64 {
+        let mut hasher = self.cookie_key.build_hasher();
+        remote_addr.hash(&mut hasher);
+        period.hash(&mut hasher);
+        // 0 stands for the absence of cookie.
+        cmp::max(hasher.finish(), 1)
+    }
+
+    fn current_cookie_period(&self) -> u64 {
+        self.created_at.elapsed().as_secs() / COOKIE_PERIOD.as_secs()
+    }
+
+    fn connect(&mut self, remote_addr: SocketAddr) -> Arc<Inner> {
+        let inner = Inner::new(self.socket.clone(), remote_addr, ConnectionOrigin::Outbound);
+        inner.connect();
+        self.connections.insert(remote_addr, inner.clone());
+        inner
+    }
+
+    fn on_datagram(&mut self, remote_addr: SocketAddr, datagram: &[u8]) {
+        if let Some(inner) = self.connections.get(&remote_addr) {
+            inner.on_datagram(datagram);
+            return;
+        }
+        // Only the connection requests are accepted from unknown remotes.
+        if !is_connect_request(datagram) {
+            return;
+        }
+        if self.accept_tx.is_none() {
+            return;
+        }
+        // Nothing is allocated for a dialer before it echoed a cookie, so that spoofed requests
+        // cannot exhaust the connections or use the listener to flood a third party.
+        let period = self.current_cookie_period();
+        let valid_cookie = connect_cookie(datagram).map_or(false, |cookie| {
+            cookie == self.cookie(remote_addr, period)
+                || (period > 0 && cookie == self.cookie(remote_addr, period - 1))
+        });
+        if !valid_cookie {
+            let retry = retry_datagram(self.cookie(remote_addr, period));
+            let _ = self.socket.send_to(&retry, remote_addr);
+            return;
+        }
+        let accept_tx = match self.accept_tx {
+            Some(ref mut accept_tx) => accept_tx,
+            None => return,
+        };
+        let inner = Inner::new(self.socket.clone(), remote_addr, ConnectionOrigin::Inbound);
+        match accept_tx.try_send(inner.clone()) {
+            Ok(()) => {
+                inner.on_datagram(datagram);
+                self.connections.insert(remote_addr, inner);
+            }
+            // The listener is gone: stop accepting connections.
+            Err(ref e) if e.is_disconnected() => self.accept_tx = None,
+            // The accept queue is full: the dialer retries later on.
+            Err(_) => {}
+        }
+    }
+
+    fn on_timer(&mut self) {
+        let now = Instant::now();
+        for inner in self.connections.values() {
+            inner.on_timer(now);
+        }
+        self.connections.retain(|_, inner| !inner.is_closed());
+    }
+
+    async fn run(mut self) {
+        let mut buf = vec![0; MAX_DATAGRAM_SIZE];
+        let mut timer = Interval::new_interval(TIMER_INTERVAL).fuse();
+        loop {
+            let datagram = {
+                let f_recv = self.recv_socket.recv_from(&mut buf).fuse();
+                pin_mut!(f_recv);
+                select! {
+                    res = f_recv => Some(res),
+                    _ = timer.select_next_some() => None,
+                }
+            };
+            match datagram {
+                Some(Ok((len, remote_addr))) => self.on_datagram(remote_addr, &buf[..len]),
+                // Some platforms report the ICMP errors caused by earlier datagrams on the next
+                // receive. The connections notice unreachable remotes through their own timers.
+                Some(Err(_)) => {}
+                None => {
+                    self.on_timer();
+                    if self.accept_tx.is_none() && self.connections.is_empty() {
+                        break;
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[must_use = "streams do nothing unless polled"]
+pub struct UdpListenerStream {
+    /// Endpoint of the listening socket, spawned on the first poll of the listener so that it
+    /// runs within the runtime polling the listener.
+    endpoint: Option<Endpoint>,
+    incoming: mpsc::Receiver<Arc<Inner>>,
+}
+
+impl Stream for UdpListenerStream {
+    type Item = io::Result<(future::Ready<io::Result<UdpConnection>>, Multiaddr)>;
+
+    fn poll_next(mut self: Pin<&mut Self>, context: &mut Context) -> Poll<Option<Self::Item>> {
+        if let Some(endpoint) = self.endpoint.take() {
+            tokio::spawn(endpoint.run());
+        }
+        match ready!(Pin::new(&mut self.incoming).poll_next(context)) {
+            Some(inner) => {
+                let dialer_addr = socketaddr_to_multiaddr(inner.remote_addr());
+                Poll::Ready(Some(Ok((
+                    future::ready(Ok(UdpConnection::new(inner))),
+                    dialer_addr,
+                ))))
+            }
+            None => Poll::Ready(None),
+        }
+    }
+}
+
+#[must_use = "futures do nothing unless polled"]
+pub struct UdpOutbound {
+    /// Endpoint of the dialing socket, spawned on the first poll of the dial.
+    endpoint: Option<Endpoint>,
+    inner: Option<Arc<Inner>>,
+}
+
+impl Future for UdpOutbound {
+    type Output = io::Result<UdpConnection>;
+
+    fn poll(mut self: Pin<&mut Self>, context: &mut Context) -> Poll<Self::Output> {
+        if let Some(endpoint) = self.endpoint.take() {
+            tokio::spawn(endpoint.run());
+        }
+        let inner = self
+            .inner
+            .as_ref()
+            .expect("UdpOutbound polled after completion");
+        ready!(inner.poll_connected(context))?;
+        Poll::Ready(Ok(UdpConnection::new(
+            self.inner
+                .take()
+                .expect("UdpOutbound polled after completion"),
+        )))
+    }
+}
+
+impl Drop for UdpOutbound {
+    fn drop(&mut self) {
+        // Abort the pending connection.
+        if let Some(inner) = self.inner.take() {
+            inner.shutdown();
+        }
+    }
+}
+
+fn socketaddr_to_multiaddr(socketaddr: SocketAddr) -> Multiaddr {
+    let ipaddr: Multiaddr = socketaddr.ip().into();
+    ipaddr.with(Protocol::Udp(socketaddr.port()))
+}
+
+fn multiaddr_to_socketaddr(addr: &Multiaddr) -> io::Result<SocketAddr> {
+    let invalid_addr = || {
+        io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("Invalid Multiaddr '{:?}'", addr),
+        )
+    };
+    let mut iter = addr.iter();
+    let proto1 = iter.next().ok_or_else(invalid_addr)?;
+    let proto2 = iter.next().ok_or_else(invalid_addr)?;
+    if iter.next().is_some() {
+        return Err(invalid_addr());
+    }
+
+    match (proto1, proto2) {
+        (Protocol::Ip4(ip), Protocol::Udp(port)) => Ok(SocketAddr::new(ip.into(), port)),
+        (Protocol::Ip6(ip), Protocol::Udp(port)) => Ok(SocketAddr::new(ip.into(), port)),
+        _ => Err(invalid_addr()),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        multiplexing::{
+            udp::{PacketCipher, UdpConnection},
+            StreamMultiplexer,
+        },
+        transport::{udp::UdpTransport, Transport},
+    };
+    use futures::{
+        future::join,
+        io::{AsyncReadExt, AsyncWriteExt},
+        stream::StreamExt,
+    };
+    use std::io;
+
+    // Establish a connection over loopback, and return the dialer and listener sides.
+    async fn connect() -> io::Result<(UdpConnection, UdpConnection)> {
+        let transport = UdpTransport::default();
+        let (mut listener, addr) = transport.listen_on("/ip4/127.0.0.1/udp/0".parse().unwrap())?;
+        let f_dialer = transport.dial(addr)?;
+        let f_listener = async move {
+            let (inbound, _dialer_addr) = listener.next().await.unwrap()?;
+            inbound.await
+        };
+        let (dialer, listener) = join(f_dialer, f_listener).await;
+        Ok((dialer?, listener?))
+    }
+
+    #[tokio::test]
+    async fn simple_listen_and_dial() -> io::Result<()> {
+        let (dialer, listener) = connect().await?;
+
+        let f_dialer = async {
+            let mut substream = dialer.open_outbound().await?;
+            substream.write_all(b"Earth").await?;
+            let mut buf = [0; 3];
+            substream.read_exact(&mut buf).await?;
+            assert_eq!(&buf, b"Air");
+            substream.close().await?;
+            Ok::<_, io::Error>(())
+        };
+        let f_listener = async {
+            let mut substream = listener.listen_for_inbound().next().await.unwrap()?;
+            let mut buf = [0; 5];
+            substream.read_exact(&mut buf).await?;
+            assert_eq!(&buf, b"Earth");
+            substream.write_all(b"Air").await?;
+            // The dialer closed its side after reading the response
+            let mut rest = vec![];
+            substream.read_to_end(&mut rest).await?;
+            assert!(rest.is_empty());
+            Ok::<_, io::Error>(())
+        };
+        let (dialer_res, listener_res) = join(f_dialer, f_listener).await;
+        dialer_res?;
+        listener_res
+    }
+
+    // Transfer more data than the windows hold over several concurrent substreams.
+    #[tokio::test]
+    async fn concurrent_substreams() -> io::Result<()> {
+        const NUM_SUBSTREAMS: usize = 4;
+        const LEN: usize = 256 * 1024;
+        let (dialer, listener) = connect().await?;
+
+        let f_dialer = futures::future::try_join_all((0..NUM_SUBSTREAMS).map(|i| {
+            let f_substream = dialer.open_outbound();
+            async move {
+                let mut substream = f_substream.await?;
+                substream.write_all(&vec![i as u8; LEN]).await?;
+                substream.close().await
+            }
+        }));
+        let f_listener = async {
+            let mut inbound = listener.listen_for_inbound();
+            let mut received = vec![];
+            for _ in 0..NUM_SUBSTREAMS {
+                let mut substream = inbound.next().await.unwrap()?;
+                received.push(async move {
+                    let mut buf = vec![];
+                    substream.read_to_end(&mut buf).await?;
+                    Ok::<_, io::Error>(buf)
+                });
+            }
+            futures::future::try_join_all(received).await
+        };
+        let (dialer_res, listener_res) = join(f_dialer, f_listener).await;
+        dialer_res?;
+        let mut received = listener_res?;
+        received.sort();
+        for (i, buf) in received.into_iter().enumerate() {
+            assert_eq!(buf, vec![i as u8; LEN]);
+        }
+        Ok(())
+    }
+
+    // A toy cipher which does not need a handshake.
+    struct XorCipher(u8);
+
+    impl PacketCipher for XorCipher {
+        fn seal(&self, nonce: u64, plaintext: &[u8]) -> io::Result<Vec<u8>> {
+            let key = self.0 ^ nonce as u8;
+            Ok(plaintext.iter().map(|byte| byte ^ key).collect())
+        }
+
+        fn open(&self, nonce: u64, ciphertext: &[u8]) -> io::Result<Vec<u8>> {
+            self.seal(nonce, ciphertext)
+        }
+    }
+
+    #[tokio::test]
+    async fn packet_protection() -> io::Result<()> {
+        let (dialer, listener) = connect().await?;
+
+        // The handshake substream is available on both sides, and only once.
+        let mut dialer_handshake = dialer.handshake_stream()?;
+        let mut listener_handshake = listener.handshake_stream()?;
+        assert!(dialer.handshake_stream().is_err());
+        let f_handshake = async {
+            dialer_handshake.write_all(b"hello").await?;
+            let mut buf = [0; 5];
+            listener_handshake.read_exact(&mut buf).await?;
+            assert_eq!(&buf, b"hello");
+            Ok::<_, io::Error>(())
+        };
+        f_handshake.await?;
+
+        dialer.set_packet_cipher(Box::new(XorCipher(42)));
+        listener.set_packet_cipher(Box::new(XorCipher(42)));
+        let f_dialer = async {
+            let mut substream = dialer.open_outbound().await?;
+            substream.write_all(b"protected").await?;
+            substream.close().await
+        };
+        let f_listener = async {
+            let mut substream = listener.listen_for_inbound().next().await.unwrap()?;
+            let mut buf = vec![];
+            substream.read_to_end(&mut buf).await?;
+            assert_eq!(buf, b"protected");
+            Ok::<_, io::Error>(())
+        };
+        let (dialer_res, listener_res) = join(f_dialer, f_listener).await;
+        dialer_res?;
+        listener_res
+    }
+
+    #[tokio::test]
+    async fn close_connection() -> io::Result<()> {
+        let (dialer, listener) = connect().await?;
+        let mut inbound = listener.listen_for_inbound();
+
+        dialer.close().await?;
+        // The listener notices that the connection is closed
+        assert!(inbound.next().await.is_none());
+        assert!(dialer.open_outbound().await.is_err());
+        Ok(())
+    }
+}
@@ -6,20 +6,30 @@
 //! The main feature of this module is [`NoiseSocket`](crate::socket::NoiseSocket) which
 //! provides wire-framing for noise payloads.  Currently the only handshake pattern supported is IX.
 //!
+//! The datagrams of a [`UdpConnection`] can also be protected with noise: the handshake is
+//! performed over its handshake substream, and the resulting session seals every datagram with a
+//! [`NoisePacketCipher`](crate::NoisePacketCipher), using the packet number as nonce.
+//!
+//! The static key of a [`NoiseConfig`] can be rotated while it is in use: the handshakes started
+//! after the rotation use the new key, while the sessions already established are left untouched.
+//!
 //! [noise]: http://noiseprotocol.org/
+//! [`UdpConnection`]: netcore::multiplexing::udp::UdpConnection
 
 use crypto::x25519::{X25519StaticPrivateKey, X25519StaticPublicKey};
 use futures::io::{AsyncRead, AsyncWrite};
 use netcore::{
+    multiplexing::udp::UdpConnection,
     negotiate::{negotiate_inbound, negotiate_outbound_interactive},
     transport::ConnectionOrigin,
 };
 use snow::{self, params::NoiseParams, Keypair};
-use std::io;
+use std::{io, sync::RwLock};
 
+mod packet;
 mod socket;
 
-pub use self::socket::NoiseSocket;
+pub use self::{packet::NoisePacketCipher, socket::NoiseSocket};
 use crypto::ValidKey;
 
 const NOISE_IX_25519_AESGCM_SHA256_PROTOCOL_NAME: &[u8] = b"/noise_ix_25519_aesgcm_sha256/1.0.0";
@@ -28,7 +38,7 @@ const NOISE_IX_PARAMETER: &str = "Noise_IX_25519_AESGCM_SHA256";
 /// The Noise protocol configuration to be used to perform a protocol upgrade on an underlying
 /// socket.
 pub struct NoiseConfig {
-    keypair: Keypair,
+    keypair: RwLock<Keypair>,
     parameters: NoiseParams,
 }
 
@@ -41,7 +51,7 @@ impl NoiseConfig {
             public: keypair.1.to_bytes().to_vec(),
         };
         Self {
-            keypair,
+            keypair: RwLock::new(keypair),
             parameters,
         }
     }
@@ -53,11 +63,25 @@ impl NoiseConfig {
             .generate_keypair()
             .expect("Noise failed to generate a random static keypair");
         Self {
-            keypair,
+            keypair: RwLock::new(keypair),
             parameters,
         }
     }
 
+    /// Replace the static keypair used by the handshakes performed from now on. The sessions
+    /// established with the previous keypair are not affected.
+    pub fn rotate_keypair(&self, keypair: (X25519StaticPrivateKey, X25519StaticPublicKey)) {
+        *self.keypair.write().unwrap() = Keypair {
+            private: keypair.0.to_bytes().to_vec(),
+            public: keypair.1.to_bytes().to_vec(),
+        };
+    }
+
+    /// The static public key currently used by the handshakes.
+    pub fn public_key(&self) -> Vec<u8> {
+        self.keypair.read().unwrap().public.clone()
+    }
+
     /// Perform a protocol upgrade on an underlying connection. In addition perform the noise IX
     /// handshake to establish a noise session and exchange static public keys. Upon success,
     /// returns the static public key of the remote as well as a NoiseSocket.
@@ -82,19 +106,7 @@ impl NoiseConfig {
 
         assert_eq!(proto, NOISE_IX_25519_AESGCM_SHA256_PROTOCOL_NAME);
 
-        // Instantiate the snow session
-        // Note: We need to scope the Builder struct so that the compiler doesn't over eagerly
-        // capture it into the Async State-machine.
-        let session = {
-            let builder = snow::Builder::new(self.parameters.clone())
-                .local_private_key(&self.keypair.private);
-            match origin {
-                ConnectionOrigin::Inbound => builder.build_responder(),
-                ConnectionOrigin::Outbound => builder.build_initiator(),
-            }
-            .map_err(|e| io::Error::new(io::ErrorKind::Other, format!("{}", e)))?
-        };
-
+        let session = self.build_session(origin)?;
         let handshake = socket::Handshake::new(socket, session);
 
         let socket = handshake.handshake_1rt().await?;
@@ -104,4 +116,145 @@ impl NoiseConfig {
             .to_owned();
         Ok((remote_static_key, socket))
     }
+
+    /// Perform the noise IX handshake over the handshake substream of a UDP connection, and
+    /// protect its datagrams with the resulting session. Upon success, returns the static public
+    /// key of the remote as well as the protected connection.
+    pub async fn upgrade_udp_connection(
+        &self,
+        connection: UdpConnection,
+        origin: ConnectionOrigin,
+    ) -> io::Result<(Vec<u8>, UdpConnection)> {
+        let stream = connection.handshake_stream()?;
+
+        // Perform protocol negotiation
+        let (stream, proto) = match origin {
+            ConnectionOrigin::Inbound => {
+                negotiate_inbound(stream, [NOISE_IX_25519_AESGCM_SHA256_PROTOCOL_NAME]).await?
+            }
+            ConnectionOrigin::Outbound => {
+                negotiate_outbound_interactive(stream, [NOISE_IX_25519_AESGCM_SHA256_PROTOCOL_NAME])
+                    .await?
+            }
+        };
+
+        assert_eq!(proto, NOISE_IX_25519_AESGCM_SHA256_PROTOCOL_NAME);
+
+        let session = self.build_session(origin)?;
+        let handshake = socket::Handshake::new(stream, session);
+
+        // The handshake substream is closed once the handshake is over
+        let (remote_static_key, session) = handshake.handshake_1rt_stateless().await?;
+        connection.set_packet_cipher(Box::new(NoisePacketCipher::new(session)));
+        Ok((remote_static_key, connection))
+    }
+
+    /// Instantiate the snow session for a handshake with the current static keypair.
+    // Note: The Builder struct must not outlive this function so that the compiler doesn't over
+    // eagerly capture it into the Async State-machine of the callers.
+    fn build_session(&self, origin: ConnectionOrigin) -> io::Result<snow::HandshakeState> {
+        let keypair = self.keypair.read().unwrap();
+        let builder =
+            snow::Builder::new(self.parameters.clone()).local_private_key(&keypair.private);
+        match origin {
+            ConnectionOrigin::Inbound => builder.build_responder(),
+            ConnectionOrigin::Outbound => builder.build_initiator(),
+        }
+        .map_err(|e| io::Error::new(io::ErrorKind::Other, format!("{}", e)))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::NoiseConfig;
+    use crypto::{x25519, ValidKey};
+    use futures::{
+        executor::block_on,
+        future::join,
+        io::{AsyncReadExt, AsyncWriteExt},
+        stream::StreamExt,
+    };
+    use memsocket::MemorySocket;
+    use netcore::{
+        multiplexing::StreamMultiplexer,
+        transport::{udp::UdpTransport, ConnectionOrigin, Transport},
+    };
+    use std::io;
+
+    // Perform a handshake between the dialer and the listener, and return the static keys they
+    // received from each other.
+    fn connect(dialer: &NoiseConfig, listener: &NoiseConfig) -> (Vec<u8>, Vec<u8>) {
+        let (dialer_socket, listener_socket) = MemorySocket::new_pair();
+        let (dialer_result, listener_result) = block_on(join(
+            dialer.upgrade_connection(dialer_socket, ConnectionOrigin::Outbound),
+            listener.upgrade_connection(listener_socket, ConnectionOrigin::Inbound),
+        ));
+        (dialer_result.unwrap().0, listener_result.unwrap().0)
+    }
+
+    #[test]
+    fn test_rotate_keypair() {
+        let dialer = NoiseConfig::new_random();
+        let listener = NoiseConfig::new_random();
+        let old_public_key = listener.public_key();
+
+        let (listener_key, dialer_key) = connect(&dialer, &listener);
+        assert_eq!(listener_key, old_public_key);
+        assert_eq!(dialer_key, dialer.public_key());
+
+        // The handshakes after the rotation use the new key.
+        let new_keypair = x25519::compat::generate_keypair(None);
+        let new_public_key = new_keypair.1.to_bytes().to_vec();
+        listener.rotate_keypair(new_keypair);
+        assert_eq!(listener.public_key(), new_public_key);
+        let (listener_key, _) = connect(&dialer, &listener);
+        assert_eq!(listener_key, new_public_key);
+    }
+
+    #[tokio::test]
+    async fn test_udp_connection() -> io::Result<()> {
+        let dialer = NoiseConfig::new_random();
+        let listener = NoiseConfig::new_random();
+
+        let transport = UdpTransport::default();
+        let (mut inbound, addr) = transport.listen_on("/ip4/127.0.0.1/udp/0".parse().unwrap())?;
+        let f_dialer = async {
+            let connection = transport.dial(addr)?.await?;
+            dialer
+                .upgrade_udp_connection(connection, ConnectionOrigin::Outbound)
+                .await
+        };
+        let f_listener = async {
+            let (connection, _addr) = inbound.next().await.unwrap()?;
+            listener
+                .upgrade_udp_connection(connection.await?, ConnectionOrigin::Inbound)
+                .await
+        };
+        let (dialer_result, listener_result) = join(f_dialer, f_listener).await;
+        let (listener_key, dialer_connection) = dialer_result?;
+        let (dialer_key, listener_connection) = listener_result?;
+        assert_eq!(listener_key, listener.public_key());
+        assert_eq!(dialer_key, dialer.public_key());
+
+        // The substreams opened after the handshake are carried by protected datagrams
+        let f_dialer = async {
+            let mut substream = dialer_connection.open_outbound().await?;
+            substream.write_all(b"protected").await?;
+            substream.close().await
+        };
+        let f_listener = async {
+            let mut substream = listener_connection
+                .listen_for_inbound()
+                .next()
+                .await
+                .unwrap()?;
+            let mut buf = vec![];
+            substream.read_to_end(&mut buf).await?;
+            Ok::<_, io::Error>(buf)
+        };
+        let (dialer_result, listener_result) = join(f_dialer, f_listener).await;
+        dialer_result?;
+        assert_eq!(listener_result?, b"protected");
+        Ok(())
+    }
 }
@@ -0,0 +1,48 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Noise Packet Protection
+
+use netcore::multiplexing::udp::PacketCipher;
+use std::io;
+
+/// Size of the authentication tag appended to every sealed packet.
+const TAG_LENGTH: usize = 16;
+
+/// Protection of the datagrams of a [`UdpConnection`] with the stateless transport session
+/// established by a noise handshake over its handshake substream.
+///
+/// [`UdpConnection`]: netcore::multiplexing::udp::UdpConnection
+pub struct NoisePacketCipher {
+    session: snow::StatelessTransportState,
+}
+
+impl NoisePacketCipher {
+    pub(crate) fn new(session: snow::StatelessTransportState) -> Self {
+        Self { session }
+    }
+}
+
+impl PacketCipher for NoisePacketCipher {
+    fn seal(&self, nonce: u64, plaintext: &[u8]) -> io::Result<Vec<u8>> {
+        let mut ciphertext = vec![0; plaintext.len() + TAG_LENGTH];
+        let len = self
+            .session
+            .write_message(nonce, plaintext, &mut ciphertext)
+            .map_err(|e| io::Error::new(io::ErrorKind::Other, format!("Noise error: {}", e)))?;
+        ciphertext.truncate(len);
+        Ok(ciphertext)
+    }
+
+    fn open(&self, nonce: u64, ciphertext: &[u8]) -> io::Result<Vec<u8>> {
+        let mut plaintext = vec![0; ciphertext.len()];
+        let len = self
+            .session
+            .read_message(nonce, ciphertext, &mut plaintext)
+            .map_err(|e| {
+                io::Error::new(io::ErrorKind::InvalidData, format!("Noise error: {}", e))
+            })?;
+        plaintext.truncate(len);
+        Ok(plaintext)
+    }
+}
@@ -143,6 +143,18 @@ impl Session {
             )),
         }
     }
+
+    pub fn into_stateless_transport_mode(self) -> Result<snow::StatelessTransportState, io::Error> {
+        match self {
+            Session::Handshake(session) => session
+                .into_stateless_transport_mode()
+                .map_err(|e| io::Error::new(io::ErrorKind::Other, format!("Noise error: {}", e))),
+            Session::Transport(_) => Err(io::Error::new(
+                io::ErrorKind::Other,
+                "Session not in handshake state".to_string(),
+            )),
+        }
+    }
 }
 
 /// A Noise session with a remote
@@ -570,6 +582,28 @@ where
     /// Perform a Single Round-Trip noise IX handshake returning the underlying [NoiseSocket]
     /// (switched to transport mode) upon success.
     pub async fn handshake_1rt(mut self) -> io::Result<NoiseSocket<TSocket>> {
+        self.exchange_1rt().await?;
+        self.finish()
+    }
+
+    /// Perform a Single Round-Trip noise IX handshake returning the static public key of the
+    /// remote and the stateless transport session, which uses explicit nonces and can therefore
+    /// protect messages delivered out of order or not at all. The underlying socket is dropped.
+    pub async fn handshake_1rt_stateless(
+        mut self,
+    ) -> io::Result<(Vec<u8>, snow::StatelessTransportState)> {
+        self.exchange_1rt().await?;
+        let remote_static_key = self
+            .0
+            .get_remote_static()
+            .expect("Noise remote static key missing after handshake")
+            .to_owned();
+        let session = self.0.session.into_stateless_transport_mode()?;
+        Ok((remote_static_key, session))
+    }
+
+    /// Exchange the handshake messages with the remote.
+    async fn exchange_1rt(&mut self) -> io::Result<()> {
         // The Dialer
         if self.0.session.is_initiator() {
             // -> e, s
@@ -587,7 +621,7 @@ where
             self.flush().await?;
         }
 
-        self.finish()
+        Ok(())
     }
 
     /// Send handshake message to remote.
@@ -0,0 +1,110 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Compression of the messages of the direct-send and rpc protocols.
+//!
+//! Each end-point advertises the protocols whose messages it compresses with LZ4 in the
+//! `compressed_protocols` of its [`IdentityMsg`](crate::proto::IdentityMsg). The messages of a
+//! protocol are compressed with a peer only if both end-points advertise the protocol, so both
+//! sides agree without any additional round trip, and peers unaware of compression keep
+//! exchanging raw messages.
+//!
+//! A compressed message is prefixed with the size of the original message. The size is checked
+//! against [`MAX_DECOMPRESSED_SIZE`] before anything is decompressed, so that a small malicious
+//! message cannot make us allocate an arbitrary amount of memory.
+use crate::{protocols::identity::Identity, ProtocolId};
+use bytes::Bytes;
+use libra_types::PeerId;
+use lz4::block::CompressionMode;
+use std::{
+    collections::HashMap,
+    convert::TryInto,
+    io,
+    sync::{Arc, RwLock},
+};
+
+#[cfg(test)]
+mod test;
+
+/// The maximum size of a decompressed message. It is the maximum size of a frame of the
+/// `LengthDelimitedCodec`, so compression never lets a peer send larger messages.
+pub const MAX_DECOMPRESSED_SIZE: usize = 8 * 1024 * 1024;
+
+/// Size of the prefix holding the size of the original message.
+const SIZE_PREFIX_LENGTH: usize = 4;
+
+/// Protocols whose messages are compressed with each of the connected peers.
+#[derive(Clone, Debug, Default)]
+pub struct Compression {
+    /// Protocols whose messages this end-point compresses.
+    own_protocols: Vec<ProtocolId>,
+    /// Protocols whose messages are compressed with each of the connected peers.
+    peers: Arc<RwLock<HashMap<PeerId, Vec<ProtocolId>>>>,
+}
+
+impl Compression {
+    pub fn new(own_protocols: Vec<ProtocolId>) -> Self {
+        Self {
+            own_protocols,
+            peers: Arc::new(RwLock::new(HashMap::new())),
+        }
+    }
+
+    /// Records the protocols whose messages are compressed with a newly connected peer, i.e. the
+    /// ones both end-points compress.
+    pub fn add_peer(&self, identity: &Identity) {
+        let protocols = self
+            .own_protocols
+            .iter()
+            .filter(|protocol| identity.is_compression_supported(protocol))
+            .cloned()
+            .collect();
+        self.peers
+            .write()
+            .unwrap()
+            .insert(identity.peer_id(), protocols);
+    }
+
+    pub fn remove_peer(&self, peer_id: &PeerId) {
+        self.peers.write().unwrap().remove(peer_id);
+    }
+
+    /// Whether the messages of the protocol are compressed with the peer.
+    pub fn is_compressed(&self, peer_id: &PeerId, protocol: &ProtocolId) -> bool {
+        self.peers
+            .read()
+            .unwrap()
+            .get(peer_id)
+            .map_or(false, |protocols| protocols.contains(protocol))
+    }
+}
+
+/// Compresses a message, and prefixes it with its size.
+pub fn compress(data: &[u8]) -> io::Result<Bytes> {
+    if data.len() > MAX_DECOMPRESSED_SIZE {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("Message of {} bytes is too large", data.len()),
+        ));
+    }
+    lz4::block::compress(data, Some(CompressionMode::DEFAULT), true).map(Into::into)
+}
+
+/// Decompresses a message, after checking that its size is at most [`MAX_DECOMPRESSED_SIZE`].
+pub fn decompress(data: &[u8]) -> io::Result<Bytes> {
+    if data.len() < SIZE_PREFIX_LENGTH {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "Compressed message is truncated",
+        ));
+    }
+    let (size, compressed) = data.split_at(SIZE_PREFIX_LENGTH);
+    let size = i32::from_le_bytes(size.try_into().expect("Size prefix has 4 bytes"));
+    if size < 0 || size as usize > MAX_DECOMPRESSED_SIZE {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("Decompressed message of {} bytes is too large", size),
+        ));
+    }
+    lz4::block::decompress(compressed, Some(size)).map(Into::into)
+}
@@ -0,0 +1,79 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use super::*;
+use config::config::RoleType;
+
+const PROTOCOL_1: &[u8] = b"/compression/1.0.0";
+const PROTOCOL_2: &[u8] = b"/compression/2.0.0";
+const PROTOCOL_3: &[u8] = b"/compression/3.0.0";
+
+#[test]
+fn test_compress_decompress() {
+    let data = vec![42u8; 100_000];
+    let compressed = compress(&data).unwrap();
+    assert!(compressed.len() < data.len() / 10);
+    assert_eq!(decompress(&compressed).unwrap().as_ref(), &data[..]);
+
+    let compressed = compress(&[]).unwrap();
+    assert!(decompress(&compressed).unwrap().is_empty());
+}
+
+#[test]
+fn test_decompress_too_large() {
+    // A tiny message claiming to decompress into more than the limit is rejected upfront.
+    let mut bomb = ((MAX_DECOMPRESSED_SIZE + 1) as i32).to_le_bytes().to_vec();
+    bomb.extend_from_slice(&[0u8; 16]);
+    assert_eq!(
+        decompress(&bomb).unwrap_err().kind(),
+        io::ErrorKind::InvalidData
+    );
+
+    let mut negative = (-1i32).to_le_bytes().to_vec();
+    negative.extend_from_slice(&[0u8; 16]);
+    assert!(decompress(&negative).is_err());
+
+    assert!(compress(&vec![0u8; MAX_DECOMPRESSED_SIZE + 1]).is_err());
+}
+
+#[test]
+fn test_decompress_invalid() {
+    assert!(decompress(&[1, 2]).is_err());
+
+    // The message is larger than its advertised size.
+    let mut compressed = compress(&vec![7u8; 1000]).unwrap().to_vec();
+    compressed[..SIZE_PREFIX_LENGTH].copy_from_slice(&10i32.to_le_bytes());
+    assert!(decompress(&compressed).is_err());
+}
+
+#[test]
+fn test_negotiation() {
+    let compression = Compression::new(vec![
+        ProtocolId::from_static(PROTOCOL_1),
+        ProtocolId::from_static(PROTOCOL_2),
+    ]);
+    let peer_id = PeerId::random();
+    let identity = Identity::new(
+        peer_id,
+        vec![
+            ProtocolId::from_static(PROTOCOL_1),
+            ProtocolId::from_static(PROTOCOL_2),
+            ProtocolId::from_static(PROTOCOL_3),
+        ],
+        RoleType::Validator,
+    )
+    .with_compressed_protocols(vec![
+        ProtocolId::from_static(PROTOCOL_2),
+        ProtocolId::from_static(PROTOCOL_3),
+    ]);
+
+    compression.add_peer(&identity);
+    // Only the protocols compressed by both end-points are compressed.
+    assert!(!compression.is_compressed(&peer_id, &ProtocolId::from_static(PROTOCOL_1)));
+    assert!(compression.is_compressed(&peer_id, &ProtocolId::from_static(PROTOCOL_2)));
+    assert!(!compression.is_compressed(&peer_id, &ProtocolId::from_static(PROTOCOL_3)));
+    assert!(!compression.is_compressed(&PeerId::random(), &ProtocolId::from_static(PROTOCOL_2)));
+
+    compression.remove_peer(&peer_id);
+    assert!(!compression.is_compressed(&peer_id, &ProtocolId::from_static(PROTOCOL_2)));
+}
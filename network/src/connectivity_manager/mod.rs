@@ -11,10 +11,21 @@
 //! nodes.
 //!
 //! When dialing a peer with a given list of addresses, we attempt each address
-//! in order with a capped exponential backoff delay until we eventually connect
-//! to the peer.
+//! in order of priority (i.e. in the order advertised by the peer) until we eventually connect
+//! to the peer. Each address has its own capped exponential backoff delay, so that an address
+//! which keeps failing (e.g. a private address unreachable from our network) does not delay the
+//! first attempts on the other ones.
+//!
+//! In a permissionless network, there is no set of eligible nodes: any peer reported by the
+//! Discovery actor is eligible, and peers are dialed until the node is connected to a target
+//! number of peers (see [`ConnectivityPolicy`]).
+//!
+//! When an update of the eligible nodes changes the identity key of a node, the connections to
+//! the node are kept, and its previous key is still accepted during a grace period (see
+//! [`RotatedKeys`]).
 use crate::{
     common::NetworkPublicKeys,
+    key_rotation::RotatedKeys,
     peer_manager::{PeerManagerError, PeerManagerNotification, PeerManagerRequestSender},
 };
 use channel;
@@ -42,6 +53,10 @@ mod test;
 pub struct ConnectivityManager<TTicker, TSubstream, TBackoff> {
     /// Nodes which are eligible to join the network.
     eligible: Arc<RwLock<HashMap<PeerId, NetworkPublicKeys>>>,
+    /// Identity keys of the eligible nodes replaced by the updates of the eligible nodes.
+    rotated_keys: Arc<RwLock<RotatedKeys>>,
+    /// Policy deciding which peers are dialed.
+    policy: ConnectivityPolicy,
     /// PeerId and address of remote peers to which this peer is connected.
     connected: HashMap<PeerId, Multiaddr>,
     /// Addresses of peers received from Discovery module.
@@ -69,6 +84,17 @@ pub struct ConnectivityManager<TTicker, TSubstream, TBackoff> {
     event_id: u32,
 }
 
+/// The policy deciding which peers the [`ConnectivityManager`] connects to.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ConnectivityPolicy {
+    /// Only the eligible nodes are dialed, and the connections to the nodes which are no longer
+    /// eligible are closed.
+    Permissioned,
+    /// Any peer with a known address is dialed as long as fewer than `target_connections` peers
+    /// are connected or being dialed. Connections are never closed for eligibility reasons.
+    Permissionless { target_connections: usize },
+}
+
 /// Requests received by the [`ConnectivityManager`] manager actor from upstream modules.
 #[derive(Debug)]
 pub enum ConnectivityRequest {
@@ -91,8 +117,11 @@ enum DialResult {
 /// peer.
 #[derive(Debug, Clone)]
 struct DialState<TBackoff> {
-    /// The current state of this peer's backoff delay.
+    /// The initial state of the backoff delay of an address.
     backoff: TBackoff,
+    /// The current state of the backoff delay of each address, by index of the address in the
+    /// peer's `peer_addresses` entry.
+    addr_backoffs: Vec<TBackoff>,
     /// The index of the next address to dial. Index of an address in the peer's
     /// `peer_addresses` entry.
     addr_idx: usize,
@@ -107,6 +136,8 @@ where
     /// Creates a new instance of the [`ConnectivityManager`] actor.
     pub fn new(
         eligible: Arc<RwLock<HashMap<PeerId, NetworkPublicKeys>>>,
+        rotated_keys: Arc<RwLock<RotatedKeys>>,
+        policy: ConnectivityPolicy,
         ticker: TTicker,
         peer_mgr_reqs_tx: PeerManagerRequestSender<TSubstream>,
         peer_mgr_notifs_rx: channel::Receiver<PeerManagerNotification<TSubstream>>,
@@ -116,6 +147,8 @@ where
     ) -> Self {
         Self {
             eligible,
+            rotated_keys,
+            policy,
             connected: HashMap::new(),
             peer_addresses: HashMap::new(),
             ticker,
@@ -215,28 +248,39 @@ where
         pending_dials: &'a mut FuturesUnordered<BoxFuture<'static, PeerId>>,
     ) {
         let eligible = self.eligible.read().unwrap().clone();
-        let to_connect: Vec<_> = self
+        let policy = self.policy;
+        let mut to_connect: Vec<_> = self
             .peer_addresses
             .iter()
             .filter(|(peer_id, addrs)| {
-                eligible.contains_key(peer_id)  // The node is eligible to be dialed.
+                // The node is eligible to be dialed.
+                (policy != ConnectivityPolicy::Permissioned || eligible.contains_key(peer_id))
                     && self.connected.get(peer_id).is_none() // The node is not already connected.
                     && self.dial_queue.get(peer_id).is_none() // There is no pending dial to this node.
                     && !addrs.is_empty() // There is an address to dial.
             })
             .collect();
+        let num_eligible = match policy {
+            ConnectivityPolicy::Permissioned => eligible
+                .iter()
+                .filter(|(peer_id, _)| self.peer_addresses.contains_key(peer_id))
+                .count(),
+            ConnectivityPolicy::Permissionless { target_connections } => {
+                // Only dial as many peers as needed to reach the target number of connections.
+                let num_missing =
+                    target_connections.saturating_sub(self.connected.len() + self.dial_queue.len());
+                to_connect.truncate(num_missing);
+                target_connections
+            }
+        };
 
         // We tune max delay depending on the number of peers to which we're not connected. This
         // ensures that if we're disconnected from a large fraction of peers, we keep the retry
         // window smaller.
         let max_delay = Duration::from_millis(
             (self.max_delay_ms as f64
-                * (1.0
-                    - ((self.dial_queue.len() + to_connect.len()) as f64
-                        / eligible
-                            .iter()
-                            .filter(|(peer_id, _)| self.peer_addresses.contains_key(peer_id))
-                            .count() as f64))) as u64,
+                * (1.0 - ((self.dial_queue.len() + to_connect.len()) as f64 / num_eligible as f64)))
+                as u64,
         );
 
         // The initial dial state; it has zero dial delay and uses the first
@@ -251,15 +295,13 @@ where
                 .entry(peer_id)
                 .or_insert_with(|| init_dial_state.clone());
 
-            // Choose the next addr to dial for this peer. Currently, we just
-            // round-robin the selection, i.e., try the sequence:
-            // addr[0], .., addr[len-1], addr[0], ..
-            let addr = dial_state.next_addr(&addrs).clone();
-
-            // Using the DialState's backoff strategy, compute the delay until
-            // the next dial attempt for this peer.
+            // Choose the next addr to dial for this peer. The addresses are tried in order of
+            // priority, i.e., we try the sequence: addr[0], .., addr[len-1], addr[0], ..
+            // Using the backoff strategy of the chosen address, compute the delay until the next
+            // dial attempt for this peer.
             let now = Instant::now();
-            let dial_delay = dial_state.next_backoff_delay(max_delay);
+            let (addr, dial_delay) = dial_state.next_dial(&addrs, max_delay);
+            let addr = addr.clone();
             let f_delay = timer::delay_for(dial_delay);
 
             let (cancel_tx, cancel_rx) = oneshot::channel();
@@ -310,10 +352,12 @@ where
         &'a mut self,
         pending_dials: &'a mut FuturesUnordered<BoxFuture<'static, PeerId>>,
     ) {
-        // Cancel dials to peers that are no longer eligible.
-        self.cancel_stale_dials().await;
-        // Disconnect from connected peers that are no longer eligible.
-        self.close_stale_connections().await;
+        if self.policy == ConnectivityPolicy::Permissioned {
+            // Cancel dials to peers that are no longer eligible.
+            self.cancel_stale_dials().await;
+            // Disconnect from connected peers that are no longer eligible.
+            self.close_stale_connections().await;
+        }
         // Dial peers which are eligible but are neither connected nor queued for dialing in the
         // future.
         self.dial_eligible_peers(pending_dials).await;
@@ -334,7 +378,9 @@ where
             }
             ConnectivityRequest::UpdateEligibleNodes(nodes) => {
                 trace!("Received updated list of eligible nodes",);
-                *self.eligible.write().unwrap() = nodes;
+                let mut eligible = self.eligible.write().unwrap();
+                self.rotated_keys.write().unwrap().update(&eligible, &nodes);
+                *eligible = nodes;
             }
             ConnectivityRequest::GetDialQueueSize(sender) => {
                 sender.send(self.dial_queue.len()).unwrap();
@@ -411,22 +457,33 @@ where
     fn new(backoff: TBackoff) -> Self {
         Self {
             backoff,
+            addr_backoffs: vec![],
             addr_idx: 0,
         }
     }
 
+    /// Restarts from the first address, with fresh backoff delays since the addresses changed.
     fn reset_addr(&mut self) {
         self.addr_idx = 0;
+        self.addr_backoffs.clear();
     }
 
-    fn next_addr<'a>(&mut self, addrs: &'a [Multiaddr]) -> &'a Multiaddr {
-        let addr_idx = self.addr_idx;
-        self.addr_idx = self.addr_idx.wrapping_add(1);
-
-        &addrs[addr_idx % addrs.len()]
-    }
+    /// Returns the next address to dial, and the delay before dialing it.
+    fn next_dial<'a>(
+        &mut self,
+        addrs: &'a [Multiaddr],
+        max_delay: Duration,
+    ) -> (&'a Multiaddr, Duration) {
+        let addr_idx = self.addr_idx % addrs.len();
+        self.addr_idx = addr_idx.wrapping_add(1);
 
-    fn next_backoff_delay(&mut self, max_delay: Duration) -> Duration {
-        min(max_delay, self.backoff.next().unwrap_or(max_delay))
+        if self.addr_backoffs.len() < addrs.len() {
+            self.addr_backoffs.resize(addrs.len(), self.backoff.clone());
+        }
+        let delay = min(
+            max_delay,
+            self.addr_backoffs[addr_idx].next().unwrap_or(max_delay),
+        );
+        (&addrs[addr_idx], delay)
     }
 }
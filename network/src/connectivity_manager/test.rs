@@ -20,6 +20,19 @@ fn setup_conn_mgr(
     channel::Sender<PeerManagerNotification<MemorySocket>>,
     channel::Sender<ConnectivityRequest>,
     channel::Sender<()>,
+) {
+    setup_conn_mgr_with_policy(rt, seed_peer_id, ConnectivityPolicy::Permissioned)
+}
+
+fn setup_conn_mgr_with_policy(
+    rt: &mut Runtime,
+    seed_peer_id: PeerId,
+    policy: ConnectivityPolicy,
+) -> (
+    channel::Receiver<PeerManagerRequest<MemorySocket>>,
+    channel::Sender<PeerManagerNotification<MemorySocket>>,
+    channel::Sender<ConnectivityRequest>,
+    channel::Sender<()>,
 ) {
     let (peer_mgr_reqs_tx, peer_mgr_reqs_rx): (
         channel::Sender<PeerManagerRequest<MemorySocket>>,
@@ -44,6 +57,8 @@ fn setup_conn_mgr(
                 .into_iter()
                 .collect(),
             )),
+            Arc::new(RwLock::new(RotatedKeys::new(Duration::from_secs(60)))),
+            policy,
             ticker_rx,
             PeerManagerRequestSender::new(peer_mgr_reqs_tx),
             peer_mgr_notifs_rx,
@@ -894,3 +909,106 @@ fn multiple_addrs_shrinking() {
     };
     rt.block_on(f_peer_mgr);
 }
+
+// Test that each address of a peer backs off independently of the other ones.
+#[test]
+fn per_address_backoff() {
+    let addr_1 = Multiaddr::from_str("/ip4/127.0.0.1/tcp/9091").unwrap();
+    let addr_2 = Multiaddr::from_str("/ip4/127.0.0.1/tcp/9092").unwrap();
+    let addrs = vec![addr_1.clone(), addr_2.clone()];
+    let max_delay = Duration::from_secs(3);
+    let backoff = (1..).map(Duration::from_secs as fn(u64) -> Duration);
+    let mut dial_state = DialState::new(backoff);
+
+    // The addresses are tried in order, and the first attempt on the second address is not
+    // delayed by the failures of the first one.
+    let mut next_dial = || {
+        let (addr, delay) = dial_state.next_dial(&addrs, max_delay);
+        (addr.clone(), delay)
+    };
+    assert_eq!(next_dial(), (addr_1.clone(), Duration::from_secs(1)));
+    assert_eq!(next_dial(), (addr_2.clone(), Duration::from_secs(1)));
+    assert_eq!(next_dial(), (addr_1.clone(), Duration::from_secs(2)));
+    assert_eq!(next_dial(), (addr_2.clone(), Duration::from_secs(2)));
+    assert_eq!(next_dial(), (addr_1.clone(), Duration::from_secs(3)));
+    assert_eq!(next_dial(), (addr_2, max_delay));
+
+    // New addresses start with fresh backoff delays.
+    dial_state.reset_addr();
+    let (addr, delay) = dial_state.next_dial(&addrs, max_delay);
+    assert_eq!((addr, delay), (&addr_1, Duration::from_secs(1)));
+}
+
+#[test]
+fn permissionless_target_connections() {
+    ::libra_logger::try_init_for_testing();
+    let mut rt = Runtime::new().unwrap();
+    // Neither peer is eligible: any discovered peer is dialed in a permissionless network.
+    let (peer_a, _) = gen_peer();
+    let (peer_b, _) = gen_peer();
+    let (mut peer_mgr_reqs_rx, mut peer_mgr_notifs_tx, mut conn_mgr_reqs_tx, mut ticker_tx) =
+        setup_conn_mgr_with_policy(
+            &mut rt,
+            PeerId::random(),
+            ConnectivityPolicy::Permissionless {
+                target_connections: 1,
+            },
+        );
+
+    // Fake peer manager and discovery.
+    let f_peer_mgr = async move {
+        let address_a = Multiaddr::from_str("/ip4/127.0.0.1/tcp/9090").unwrap();
+        let address_b = Multiaddr::from_str("/ip4/127.0.0.1/tcp/9091").unwrap();
+
+        // Peer A is dialed once its address is discovered.
+        conn_mgr_reqs_tx
+            .send(ConnectivityRequest::UpdateAddresses(
+                peer_a,
+                vec![address_a.clone()],
+            ))
+            .await
+            .unwrap();
+        ticker_tx.send(()).await.unwrap();
+        expect_dial_request(
+            &mut peer_mgr_reqs_rx,
+            &mut peer_mgr_notifs_tx,
+            &mut conn_mgr_reqs_tx,
+            peer_a,
+            address_a.clone(),
+            Ok(()),
+        )
+        .await;
+
+        // Peer B is not dialed since the target number of connections is reached.
+        conn_mgr_reqs_tx
+            .send(ConnectivityRequest::UpdateAddresses(
+                peer_b,
+                vec![address_b.clone()],
+            ))
+            .await
+            .unwrap();
+        ticker_tx.send(()).await.unwrap();
+
+        // Once peer A is lost and peer B has no address anymore, peer A is dialed again. A dial
+        // to peer B would arrive in place of it.
+        conn_mgr_reqs_tx
+            .send(ConnectivityRequest::UpdateAddresses(peer_b, vec![]))
+            .await
+            .unwrap();
+        peer_mgr_notifs_tx
+            .send(PeerManagerNotification::LostPeer(peer_a, address_a.clone()))
+            .await
+            .unwrap();
+        ticker_tx.send(()).await.unwrap();
+        expect_dial_request(
+            &mut peer_mgr_reqs_rx,
+            &mut peer_mgr_notifs_tx,
+            &mut conn_mgr_reqs_tx,
+            peer_a,
+            address_a,
+            Ok(()),
+        )
+        .await;
+    };
+    rt.block_on(f_peer_mgr);
+}
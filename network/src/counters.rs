@@ -12,6 +12,18 @@ lazy_static::lazy_static! {
     /// Counter of currently connected peers
     pub static ref CONNECTED_PEERS: IntGauge = OP_COUNTERS.gauge("connected_peers");
 
+    /// Counter of inbound connections rejected due to the per IP address or subnet limits
+    pub static ref INBOUND_CONNECTIONS_REJECTED: IntCounter = OP_COUNTERS.counter("inbound_connections_rejected");
+
+    /// Counter of misbehaviors reported against peers
+    pub static ref PEER_MISBEHAVIORS_REPORTED: IntCounter = OP_COUNTERS.counter("peer_misbehaviors_reported");
+
+    /// Counter of peers banned for misbehaving
+    pub static ref PEERS_BANNED: IntCounter = OP_COUNTERS.counter("peers_banned");
+
+    /// Counter of connections refused because the peer is banned
+    pub static ref BANNED_PEER_CONNECTIONS_REFUSED: IntCounter = OP_COUNTERS.counter("banned_peer_connections_refused");
+
     /// Counter of rpc requests sent
     pub static ref RPC_REQUESTS_SENT: IntCounter = OP_COUNTERS.counter("rpc_requests_sent");
 
@@ -48,6 +60,33 @@ lazy_static::lazy_static! {
     /// Counter of messages dropped via the direct send protocol
     pub static ref DIRECT_SEND_MESSAGES_DROPPED: IntCounter = OP_COUNTERS.counter("direct_send_messages_dropped");
 
+    /// Counter of messages dropped for each protocol because its outbound queue is full
+    pub static ref PROTOCOL_MESSAGES_DROPPED: &'static str = "protocol_messages_dropped";
+
+    /// Counter of bytes sent to each remote peer for each protocol
+    pub static ref PEER_BYTES_SENT: &'static str = "peer_bytes_sent";
+
+    /// Counter of messages sent to each remote peer for each protocol
+    pub static ref PEER_MESSAGES_SENT: &'static str = "peer_messages_sent";
+
+    /// Counter of bytes received from each remote peer for each protocol
+    pub static ref PEER_BYTES_RECEIVED: &'static str = "peer_bytes_received";
+
+    /// Counter of messages received from each remote peer for each protocol
+    pub static ref PEER_MESSAGES_RECEIVED: &'static str = "peer_messages_received";
+
+    /// Counter of failed rpcs with each remote peer for each protocol
+    pub static ref PEER_RPC_ERRORS: &'static str = "peer_rpc_errors";
+
+    /// Histogram of the latency of the outbound rpcs to each remote peer for each protocol
+    pub static ref PEER_RPC_LATENCY: &'static str = "peer_rpc_latency";
+
+    /// Histogram of the ping round-trip time to each remote peer
+    pub static ref PEER_PING_RTT: &'static str = "peer_ping_rtt";
+
+    /// Counter of open substreams with each remote peer
+    pub static ref PEER_OPEN_SUBSTREAMS: &'static str = "peer_open_substreams";
+
     /// Counter of messages received via the direct send protocol
     pub static ref DIRECT_SEND_MESSAGES_RECEIVED: IntCounter = OP_COUNTERS.counter("direct_send_messages_received");
 
@@ -73,14 +112,14 @@ lazy_static::lazy_static! {
     /// Counter of pending network events to Admission Control
     pub static ref PENDING_ADMISSION_CONTROL_NETWORK_EVENTS: IntGauge = OP_COUNTERS.gauge("pending_admission_control_network_events");
 
-    /// Counter of pending requests in Peer Manager
-    pub static ref PENDING_PEER_MANAGER_REQUESTS: IntGauge = OP_COUNTERS.gauge("pending_peer_manager_requests");
+    /// Counter of pending requests in Peer Manager for each protocol
+    pub static ref PENDING_PEER_MANAGER_REQUESTS: &'static str = "pending_peer_manager_requests";
 
     /// Counter of pending Peer Manager notifications in Network Provider
     pub static ref PENDING_PEER_MANAGER_NET_NOTIFICATIONS: IntGauge = OP_COUNTERS.gauge("pending_peer_manager_net_notifications");
 
-    /// Counter of pending requests in Direct Send
-    pub static ref PENDING_DIRECT_SEND_REQUESTS: IntGauge = OP_COUNTERS.gauge("pending_direct_send_requests");
+    /// Counter of pending requests in Direct Send for each protocol
+    pub static ref PENDING_DIRECT_SEND_REQUESTS: &'static str = "pending_direct_send_requests";
 
     /// Counter of pending Direct Send notifications to Network Provider
     pub static ref PENDING_DIRECT_SEND_NOTIFICATIONS: IntGauge = OP_COUNTERS.gauge("pending_direct_send_notifications");
@@ -88,8 +127,8 @@ lazy_static::lazy_static! {
     /// Counter of pending requests in Connectivity Manager
     pub static ref PENDING_CONNECTIVITY_MANAGER_REQUESTS: IntGauge = OP_COUNTERS.gauge("pending_connectivity_manager_requests");
 
-    /// Counter of pending requests in RPC
-    pub static ref PENDING_RPC_REQUESTS: IntGauge = OP_COUNTERS.gauge("pending_rpc_requests");
+    /// Counter of pending requests in RPC for each protocol
+    pub static ref PENDING_RPC_REQUESTS: &'static str = "pending_rpc_requests";
 
     /// Counter of pending RPC notifications to Network Provider
     pub static ref PENDING_RPC_NOTIFICATIONS: IntGauge = OP_COUNTERS.gauge("pending_rpc_notifications");
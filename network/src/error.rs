@@ -51,6 +51,9 @@ pub enum NetworkErrorKind {
 
     #[fail(display = "Peer not connected")]
     NotConnected,
+
+    #[fail(display = "Unsupported streaming rpc")]
+    UnsupportedStreamingRpc,
 }
 
 impl Fail for NetworkError {
@@ -11,14 +11,27 @@
 //! [`NetworkRequest::SendRpc`](crate::interface::NetworkRequest::SendRpc) message to the
 //! [`NetworkProvider`] actor. Inbound RPC requests are forwarded to the appropriate
 //! handler, determined using the protocol negotiated on the RPC substream.
+//!
+//! Each client gets its own queue of outbound requests, which is drained according to the
+//! [`ProtocolPriority`] of its protocols, so that e.g. a flood of mempool broadcasts does not
+//! delay consensus messages.
+//!
+//! The clients also report the misbehaviors of the peers to the [`NetworkProvider`], which keeps
+//! track of the [`PeerReputation`] and disconnects the peers once they get banned.
 use crate::{
     common::NetworkPublicKeys,
     connectivity_manager::ConnectivityRequest,
     counters,
-    peer_manager::PeerManagerNotification,
+    peer_manager::{PeerManagerNotification, PeerManagerRequestSender},
+    peer_metrics::PeerMetrics,
+    peer_reputation::{Misbehavior, PeerReputation},
+    priority_queue::{PriorityReceiver, PrioritySender, ProtocolPriority},
     protocols::{
         direct_send::{DirectSendNotification, DirectSendRequest, Message},
-        rpc::{InboundRpcRequest, OutboundRpcRequest, RpcNotification, RpcRequest},
+        rpc::{
+            streaming::{InboundStreamingRpcRequest, OutboundStreamingRpcRequest},
+            InboundRpcRequest, OutboundRpcRequest, RpcNotification, RpcRequest,
+        },
     },
     validator_network::{
         AdmissionControlNetworkEvents, AdmissionControlNetworkSender, ConsensusNetworkEvents,
@@ -31,7 +44,12 @@ use channel;
 use futures::{future::BoxFuture, FutureExt, SinkExt, StreamExt};
 use libra_logger::prelude::*;
 use libra_types::PeerId;
-use std::{collections::HashMap, fmt::Debug, time::Duration};
+use std::{
+    collections::HashMap,
+    fmt::Debug,
+    sync::{Arc, RwLock},
+    time::Duration,
+};
 
 pub const CONSENSUS_INBOUND_MSG_TIMEOUT_MS: u64 = 60 * 1000; // 1 minute
 pub const MEMPOOL_INBOUND_MSG_TIMEOUT_MS: u64 = 60 * 1000; // 1 minute
@@ -43,10 +61,14 @@ pub const ADMISSION_CONTROL_INBOUND_MSG_TIMEOUT_MS: u64 = 60 * 1000; // 1 minute
 pub enum NetworkRequest {
     /// Send an RPC request to a remote peer.
     SendRpc(PeerId, OutboundRpcRequest),
+    /// Send a streaming RPC request to a remote peer.
+    SendStreamingRpc(PeerId, OutboundStreamingRpcRequest),
     /// Fire-and-forget style message send to a remote peer.
     SendMessage(PeerId, Message),
     /// Update set of nodes eligible to join the network.
     UpdateEligibleNodes(HashMap<PeerId, NetworkPublicKeys>),
+    /// Report a misbehavior of a remote peer.
+    ReportPeer(PeerId, Misbehavior),
 }
 
 /// Notifications that [`NetworkProvider`] sends to consumers of its API. The
@@ -60,6 +82,8 @@ pub enum NetworkNotification {
     LostPeer(PeerId),
     /// A new RPC request has been received from a remote peer.
     RecvRpc(PeerId, InboundRpcRequest),
+    /// A new streaming RPC request has been received from a remote peer.
+    RecvStreamingRpc(PeerId, InboundStreamingRpcRequest),
     /// A new message has been received from a remote peer.
     RecvMessage(PeerId, Message),
 }
@@ -82,6 +106,13 @@ pub trait LibraNetworkProvider {
         &mut self,
         ac_protocols: Vec<ProtocolId>,
     ) -> (AdmissionControlNetworkSender, AdmissionControlNetworkEvents);
+    /// Returns a sender of the requests which are not tied to a protocol, e.g. the updates of the
+    /// eligible nodes of a permissioned network.
+    fn add_control_sender(&mut self) -> channel::Sender<NetworkRequest>;
+    /// Returns the reputation of the peers, e.g. to inspect and clear their bans.
+    fn peer_reputation(&self) -> Arc<RwLock<PeerReputation>>;
+    /// Returns the connection and protocol-level metrics of the connected peers.
+    fn peer_metrics(&self) -> PeerMetrics;
     fn start(self: Box<Self>) -> BoxFuture<'static, ()>;
 }
 
@@ -90,20 +121,26 @@ pub struct NetworkProvider<TSubstream> {
     upstream_handlers: HashMap<ProtocolId, channel::Sender<NetworkNotification>>,
     /// Channel over which we receive notifications from PeerManager.
     peer_mgr_notifs_rx: channel::Receiver<PeerManagerNotification<TSubstream>>,
-    /// Channel over which we send requets to RPC actor.
-    rpc_reqs_tx: channel::Sender<RpcRequest>,
+    /// Prioritized queues over which we send requets to RPC actor.
+    rpc_reqs_tx: PrioritySender<RpcRequest>,
     /// Channel over which we receive notifications from RPC actor.
     rpc_notifs_rx: channel::Receiver<RpcNotification>,
-    /// Channel over which we send requests to DirectSend actor.
-    ds_reqs_tx: channel::Sender<DirectSendRequest>,
+    /// Prioritized queues over which we send requests to DirectSend actor.
+    ds_reqs_tx: PrioritySender<DirectSendRequest>,
     /// Channel over which we receive notifications from DirectSend actor.
     ds_notifs_rx: channel::Receiver<DirectSendNotification>,
     /// Channel over which we send requests to the ConnectivityManager actor.
     conn_mgr_reqs_tx: Option<channel::Sender<ConnectivityRequest>>,
-    /// Channel to receive requests from other actors.
-    requests_rx: channel::Receiver<NetworkRequest>,
-    /// Channel over which other actors send requests to network.
-    requests_tx: channel::Sender<NetworkRequest>,
+    /// Channel over which we send requests to the PeerManager, to disconnect banned peers.
+    peer_mgr_reqs_tx: PeerManagerRequestSender<TSubstream>,
+    /// Reputation of the peers, updated with the misbehaviors reported by the clients.
+    peer_reputation: Arc<RwLock<PeerReputation>>,
+    /// Metrics of the connected peers, collected by the PeerManager and the protocol actors.
+    peer_metrics: PeerMetrics,
+    /// Priority of the protocols, unlisted protocols have the default priority.
+    protocol_priorities: HashMap<ProtocolId, ProtocolPriority>,
+    /// Channels to receive requests from other actors, one per actor, alongside their priority.
+    requests_rxs: Vec<(ProtocolPriority, channel::Receiver<NetworkRequest>)>,
     /// The maximum number of concurrent NetworkRequests that can be handled.
     /// Back-pressure takes effect via bounded mpsc channel beyond the limit.
     max_concurrent_reqs: u32,
@@ -129,7 +166,8 @@ where
             &counters::PENDING_MEMPOOL_NETWORK_EVENTS,
             Duration::from_millis(MEMPOOL_INBOUND_MSG_TIMEOUT_MS),
         );
-        let mempool_network_sender = MempoolNetworkSender::new(self.requests_tx.clone());
+        let mempool_network_sender =
+            MempoolNetworkSender::new(self.add_requests_queue(&mempool_protocols));
         let mempool_network_events = MempoolNetworkEvents::new(mempool_rx);
         let mempool_handlers = mempool_protocols
             .iter()
@@ -148,7 +186,8 @@ where
             &counters::PENDING_CONSENSUS_NETWORK_EVENTS,
             Duration::from_millis(CONSENSUS_INBOUND_MSG_TIMEOUT_MS),
         );
-        let consensus_network_sender = ConsensusNetworkSender::new(self.requests_tx.clone());
+        let consensus_network_sender =
+            ConsensusNetworkSender::new(self.add_requests_queue(&consensus_protocols));
         let consensus_network_events = ConsensusNetworkEvents::new(consensus_rx);
         let consensus_handlers = consensus_protocols
             .iter()
@@ -167,7 +206,8 @@ where
             &counters::PENDING_STATE_SYNCHRONIZER_NETWORK_EVENTS,
             Duration::from_millis(STATE_SYNCHRONIZER_INBOUND_MSG_TIMEOUT_MS),
         );
-        let state_sync_network_sender = StateSynchronizerSender::new(self.requests_tx.clone());
+        let state_sync_network_sender =
+            StateSynchronizerSender::new(self.add_requests_queue(&state_sync_protocols));
         let state_sync_network_events = StateSynchronizerEvents::new(state_sync_rx);
         let state_sync_handlers = state_sync_protocols
             .iter()
@@ -186,26 +226,42 @@ where
             &counters::PENDING_ADMISSION_CONTROL_NETWORK_EVENTS,
             Duration::from_millis(ADMISSION_CONTROL_INBOUND_MSG_TIMEOUT_MS),
         );
-        let ac_network_sender = AdmissionControlNetworkSender::new(self.requests_tx.clone());
+        let ac_network_sender =
+            AdmissionControlNetworkSender::new(self.add_requests_queue(&ac_protocols));
         let ac_network_events = AdmissionControlNetworkEvents::new(ac_rx);
         let ac_handlers = ac_protocols.iter().map(|p| (p.clone(), ac_tx.clone()));
         self.upstream_handlers.extend(ac_handlers);
         (ac_network_sender, ac_network_events)
     }
 
+    fn add_control_sender(&mut self) -> channel::Sender<NetworkRequest> {
+        self.add_requests_queue(&[])
+    }
+
+    fn peer_reputation(&self) -> Arc<RwLock<PeerReputation>> {
+        self.peer_reputation.clone()
+    }
+
+    fn peer_metrics(&self) -> PeerMetrics {
+        self.peer_metrics.clone()
+    }
+
     fn start(self: Box<Self>) -> BoxFuture<'static, ()> {
         let f = async move {
             let rpc_reqs_tx = self.rpc_reqs_tx.clone();
             let ds_reqs_tx = self.ds_reqs_tx.clone();
             let conn_mgr_reqs_tx = self.conn_mgr_reqs_tx.clone();
-            let mut reqs = self
-                .requests_rx
+            let peer_mgr_reqs_tx = self.peer_mgr_reqs_tx.clone();
+            let peer_reputation = self.peer_reputation.clone();
+            let mut reqs = PriorityReceiver::new(self.requests_rxs)
                 .map(move |req| {
                     Self::handle_network_request(
                         req,
                         rpc_reqs_tx.clone(),
                         ds_reqs_tx.clone(),
                         conn_mgr_reqs_tx.clone(),
+                        peer_mgr_reqs_tx.clone(),
+                        peer_reputation.clone(),
                     )
                     .boxed()
                 })
@@ -256,13 +312,15 @@ where
 {
     pub fn new(
         peer_mgr_notifs_rx: channel::Receiver<PeerManagerNotification<TSubstream>>,
-        rpc_reqs_tx: channel::Sender<RpcRequest>,
+        rpc_reqs_tx: PrioritySender<RpcRequest>,
         rpc_notifs_rx: channel::Receiver<RpcNotification>,
-        ds_reqs_tx: channel::Sender<DirectSendRequest>,
+        ds_reqs_tx: PrioritySender<DirectSendRequest>,
         ds_notifs_rx: channel::Receiver<DirectSendNotification>,
         conn_mgr_reqs_tx: Option<channel::Sender<ConnectivityRequest>>,
-        requests_rx: channel::Receiver<NetworkRequest>,
-        requests_tx: channel::Sender<NetworkRequest>,
+        peer_mgr_reqs_tx: PeerManagerRequestSender<TSubstream>,
+        peer_reputation: Arc<RwLock<PeerReputation>>,
+        peer_metrics: PeerMetrics,
+        protocol_priorities: HashMap<ProtocolId, ProtocolPriority>,
         max_concurrent_reqs: u32,
         max_concurrent_notifs: u32,
         channel_size: usize,
@@ -275,35 +333,72 @@ where
             ds_reqs_tx,
             ds_notifs_rx,
             conn_mgr_reqs_tx,
-            requests_rx,
-            requests_tx,
+            peer_mgr_reqs_tx,
+            peer_reputation,
+            peer_metrics,
+            protocol_priorities,
+            requests_rxs: vec![],
             max_concurrent_reqs,
             max_concurrent_notifs,
             channel_size,
         }
     }
 
+    /// Creates the queue of the requests of a client, with the highest priority among its
+    /// protocols.
+    fn add_requests_queue(&mut self, protocols: &[ProtocolId]) -> channel::Sender<NetworkRequest> {
+        let priority = protocols
+            .iter()
+            .map(|p| self.protocol_priorities.get(p).cloned().unwrap_or_default())
+            .max()
+            .unwrap_or_default();
+        let (requests_tx, requests_rx) =
+            channel::new(self.channel_size, &counters::PENDING_NETWORK_REQUESTS);
+        self.requests_rxs.push((priority, requests_rx));
+        requests_tx
+    }
+
     async fn handle_network_request(
         req: NetworkRequest,
-        mut rpc_reqs_tx: channel::Sender<RpcRequest>,
-        mut ds_reqs_tx: channel::Sender<DirectSendRequest>,
+        mut rpc_reqs_tx: PrioritySender<RpcRequest>,
+        mut ds_reqs_tx: PrioritySender<DirectSendRequest>,
         conn_mgr_reqs_tx: Option<channel::Sender<ConnectivityRequest>>,
+        mut peer_mgr_reqs_tx: PeerManagerRequestSender<TSubstream>,
+        peer_reputation: Arc<RwLock<PeerReputation>>,
     ) {
         trace!("NetworkRequest::{:?}", req);
         match req {
             NetworkRequest::SendRpc(peer_id, req) => {
                 rpc_reqs_tx
+                    .queue(&req.protocol)
                     .send(RpcRequest::SendRpc(peer_id, req))
                     .await
                     .unwrap();
             }
+            NetworkRequest::SendStreamingRpc(peer_id, req) => {
+                rpc_reqs_tx
+                    .queue(&req.protocol)
+                    .send(RpcRequest::SendStreamingRpc(peer_id, req))
+                    .await
+                    .unwrap();
+            }
             NetworkRequest::SendMessage(peer_id, msg) => {
                 counters::DIRECT_SEND_MESSAGES_SENT.inc();
                 counters::DIRECT_SEND_BYTES_SENT.inc_by(msg.mdata.len() as i64);
-                ds_reqs_tx
-                    .send(DirectSendRequest::SendMessage(peer_id, msg))
-                    .await
-                    .unwrap();
+                let protocol = msg.protocol.clone();
+                // A message which does not fit in the queue of its protocol is dropped, rather
+                // than delaying the messages of the other protocols.
+                if let Err(e) =
+                    ds_reqs_tx.try_send(&protocol, DirectSendRequest::SendMessage(peer_id, msg))
+                {
+                    assert!(!e.is_disconnected(), "DirectSend actor terminated");
+                    counters::DIRECT_SEND_MESSAGES_DROPPED.inc();
+                    debug!(
+                        "Dropped message of protocol {:?} to peer {}",
+                        protocol,
+                        peer_id.short_str()
+                    );
+                }
             }
             NetworkRequest::UpdateEligibleNodes(nodes) => {
                 let mut conn_mgr_reqs_tx = conn_mgr_reqs_tx
@@ -314,6 +409,22 @@ where
                     .await
                     .unwrap();
             }
+            NetworkRequest::ReportPeer(peer_id, misbehavior) => {
+                let banned = peer_reputation
+                    .write()
+                    .unwrap()
+                    .report(peer_id, misbehavior);
+                if banned {
+                    warn!("Disconnecting banned peer {}", peer_id.short_str());
+                    if let Err(e) = peer_mgr_reqs_tx.disconnect_peer(peer_id).await {
+                        debug!(
+                            "Failed to disconnect banned peer {}: {:?}",
+                            peer_id.short_str(),
+                            e
+                        );
+                    }
+                }
+            }
         }
     }
 
@@ -360,6 +471,15 @@ where
                     unreachable!();
                 }
             }
+            RpcNotification::RecvStreamingRpc(peer_id, req) => {
+                if let Some(ch) = upstream_handlers.get_mut(&req.protocol) {
+                    ch.send(NetworkNotification::RecvStreamingRpc(peer_id, req))
+                        .await
+                        .unwrap();
+                } else {
+                    unreachable!();
+                }
+            }
         }
     }
 
@@ -0,0 +1,74 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Rotation of the network identity keys.
+//!
+//! A peer rotating its identity key starts using the new key for the handshakes performed after
+//! the rotation (see [`NoiseConfig::rotate_keypair`](noise::NoiseConfig::rotate_keypair)). The
+//! other peers learn about the new key when their trusted peers are updated, e.g. when the
+//! operator edits the network peers file, which libra-node reloads periodically. These two events
+//! are not synchronized, so the key replaced by an update of the trusted peers is still accepted
+//! during a grace period.
+//!
+//! The connections established with the previous key are not dropped: the peer keeps the same
+//! `PeerId`, so it remains an eligible node.
+use crate::common::NetworkPublicKeys;
+use crypto::ValidKey;
+use libra_types::PeerId;
+use std::{
+    collections::HashMap,
+    time::{Duration, Instant},
+};
+
+#[cfg(test)]
+mod test;
+
+/// Identity keys recently replaced by an update of the trusted peers.
+#[derive(Debug)]
+pub struct RotatedKeys {
+    /// How long a replaced key is still accepted.
+    grace_period: Duration,
+    /// Replaced keys, with the peer they belong to and the time they stop being accepted.
+    keys: HashMap<Vec<u8>, (PeerId, Instant)>,
+}
+
+impl RotatedKeys {
+    pub fn new(grace_period: Duration) -> Self {
+        Self {
+            grace_period,
+            keys: HashMap::new(),
+        }
+    }
+
+    /// Records the identity keys replaced by the update of the trusted peers from `trusted_peers`
+    /// to `updated_trusted_peers`, and forgets the keys whose grace period is over.
+    pub fn update(
+        &mut self,
+        trusted_peers: &HashMap<PeerId, NetworkPublicKeys>,
+        updated_trusted_peers: &HashMap<PeerId, NetworkPublicKeys>,
+    ) {
+        let now = Instant::now();
+        self.keys.retain(|_, (_, expiration)| *expiration > now);
+        for (peer_id, public_keys) in updated_trusted_peers {
+            let key = public_keys.identity_public_key.to_bytes();
+            // A key back in use, e.g. after a rotation is rolled back, is no longer a replaced key.
+            self.keys.remove(&key);
+            if let Some(previous_keys) = trusted_peers.get(peer_id) {
+                let previous_key = previous_keys.identity_public_key.to_bytes();
+                if previous_key != key {
+                    self.keys
+                        .insert(previous_key, (*peer_id, now + self.grace_period));
+                }
+            }
+        }
+    }
+
+    /// Returns the peer the identity key belonged to, if the key was replaced less than the grace
+    /// period ago.
+    pub fn peer_id(&self, identity_key: &[u8]) -> Option<PeerId> {
+        match self.keys.get(identity_key) {
+            Some((peer_id, expiration)) if *expiration > Instant::now() => Some(*peer_id),
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,66 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use super::*;
+use crypto::{ed25519, test_utils::TEST_SEED, x25519};
+use rand::{rngs::StdRng, SeedableRng};
+use std::thread;
+
+fn trusted_peers(
+    keys: &[(PeerId, &x25519::X25519StaticPublicKey)],
+) -> HashMap<PeerId, NetworkPublicKeys> {
+    keys.iter()
+        .map(|(peer_id, identity_public_key)| {
+            (
+                *peer_id,
+                NetworkPublicKeys {
+                    signing_public_key: ed25519::compat::generate_keypair(None).1,
+                    identity_public_key: (*identity_public_key).clone(),
+                },
+            )
+        })
+        .collect()
+}
+
+#[test]
+fn test_replaced_key_accepted_during_grace_period() {
+    let mut rng = StdRng::from_seed(TEST_SEED);
+    let (_, old_key) = x25519::compat::generate_keypair(&mut rng);
+    let (_, new_key) = x25519::compat::generate_keypair(&mut rng);
+    let (_, other_key) = x25519::compat::generate_keypair(&mut rng);
+    let peer_id = PeerId::random();
+    let other_peer_id = PeerId::random();
+
+    let mut rotated_keys = RotatedKeys::new(Duration::from_millis(100));
+    rotated_keys.update(
+        &trusted_peers(&[(peer_id, &old_key), (other_peer_id, &other_key)]),
+        &trusted_peers(&[(peer_id, &new_key), (other_peer_id, &other_key)]),
+    );
+    assert_eq!(rotated_keys.peer_id(&old_key.to_bytes()), Some(peer_id));
+    // Neither the keys in use nor the unknown keys are replaced keys.
+    assert_eq!(rotated_keys.peer_id(&new_key.to_bytes()), None);
+    assert_eq!(rotated_keys.peer_id(&other_key.to_bytes()), None);
+
+    thread::sleep(Duration::from_millis(150));
+    assert_eq!(rotated_keys.peer_id(&old_key.to_bytes()), None);
+}
+
+#[test]
+fn test_rollback() {
+    let mut rng = StdRng::from_seed(TEST_SEED);
+    let (_, old_key) = x25519::compat::generate_keypair(&mut rng);
+    let (_, new_key) = x25519::compat::generate_keypair(&mut rng);
+    let peer_id = PeerId::random();
+
+    let mut rotated_keys = RotatedKeys::new(Duration::from_secs(60));
+    rotated_keys.update(
+        &trusted_peers(&[(peer_id, &old_key)]),
+        &trusted_peers(&[(peer_id, &new_key)]),
+    );
+    rotated_keys.update(
+        &trusted_peers(&[(peer_id, &new_key)]),
+        &trusted_peers(&[(peer_id, &old_key)]),
+    );
+    assert_eq!(rotated_keys.peer_id(&old_key.to_bytes()), None);
+    assert_eq!(rotated_keys.peer_id(&new_key.to_bytes()), Some(peer_id));
+}
@@ -9,6 +9,13 @@
 // Public exports
 pub use common::NetworkPublicKeys;
 pub use interface::NetworkProvider;
+pub use peer_manager::InboundConnectionLimits;
+pub use peer_metrics::{
+    LatencyHistogram, PeerMetrics, PeerStats, ProtocolStats, LATENCY_BUCKETS_MS,
+};
+pub use peer_reputation::{Misbehavior, PeerReputation};
+pub use priority_queue::ProtocolPriority;
+pub use protocols::discovery::{DnsSeedResolver, SeedResolver};
 
 pub mod interface;
 pub mod proto;
@@ -16,10 +23,15 @@ pub mod protocols;
 pub mod validator_network;
 
 mod common;
+mod compression;
 mod connectivity_manager;
 mod counters;
 mod error;
+mod key_rotation;
 mod peer_manager;
+mod peer_metrics;
+mod peer_reputation;
+mod priority_queue;
 mod sink;
 mod transport;
 mod utils;
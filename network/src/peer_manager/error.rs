@@ -25,6 +25,9 @@ pub enum PeerManagerError {
     #[fail(display = "Already connected at {}", _0)]
     AlreadyConnected(Multiaddr),
 
+    #[fail(display = "Peer {} is banned", _0)]
+    Banned(PeerId),
+
     #[fail(display = "Sending end of oneshot dropped")]
     OneshotSenderDropped,
 }
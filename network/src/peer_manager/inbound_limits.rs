@@ -0,0 +1,76 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Limits on the inbound connections of a permissionless network end-point, which prevent a
+//! single host (or a single network) from taking all of its connection slots.
+use parity_multiaddr::{Multiaddr, Protocol};
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+
+/// Length of the prefix identifying the subnet of an IPv4 address.
+const IPV4_SUBNET_PREFIX_LEN: u32 = 24;
+/// Length of the prefix identifying the subnet of an IPv6 address.
+const IPV6_SUBNET_PREFIX_LEN: u32 = 48;
+
+/// Max number of inbound connections accepted from the same IP address and from the same
+/// subnet. Connections over non-IP transports (e.g. memory) are not limited.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct InboundConnectionLimits {
+    pub max_per_ip: usize,
+    pub max_per_subnet: usize,
+}
+
+impl InboundConnectionLimits {
+    pub fn new(max_per_ip: usize, max_per_subnet: usize) -> Self {
+        Self {
+            max_per_ip,
+            max_per_subnet,
+        }
+    }
+
+    /// Returns whether a new inbound connection from `address` is allowed, given the addresses
+    /// of the current inbound connections.
+    pub fn allows<'a>(
+        &self,
+        address: &Multiaddr,
+        inbound_addresses: impl Iterator<Item = &'a Multiaddr>,
+    ) -> bool {
+        let ip = match ip_address(address) {
+            Some(ip) => ip,
+            None => return true,
+        };
+        let (mut num_same_ip, mut num_same_subnet) = (0, 0);
+        for other_ip in inbound_addresses.filter_map(ip_address) {
+            if other_ip == ip {
+                num_same_ip += 1;
+            }
+            if subnet(other_ip) == subnet(ip) {
+                num_same_subnet += 1;
+            }
+        }
+        num_same_ip < self.max_per_ip && num_same_subnet < self.max_per_subnet
+    }
+}
+
+/// Returns the IP address of a multiaddr, if any.
+fn ip_address(address: &Multiaddr) -> Option<IpAddr> {
+    address.iter().find_map(|protocol| match protocol {
+        Protocol::Ip4(ip) => Some(IpAddr::V4(ip)),
+        Protocol::Ip6(ip) => Some(IpAddr::V6(ip)),
+        _ => None,
+    })
+}
+
+/// Returns the subnet of an IP address, i.e. the address with all the bits beyond the subnet
+/// prefix cleared.
+fn subnet(ip: IpAddr) -> IpAddr {
+    match ip {
+        IpAddr::V4(ip) => {
+            let mask = !0u32 << (32 - IPV4_SUBNET_PREFIX_LEN);
+            IpAddr::V4(Ipv4Addr::from(u32::from(ip) & mask))
+        }
+        IpAddr::V6(ip) => {
+            let mask = !0u128 << (128 - IPV6_SUBNET_PREFIX_LEN);
+            IpAddr::V6(Ipv6Addr::from(u128::from(ip) & mask))
+        }
+    }
+}
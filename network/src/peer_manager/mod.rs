@@ -12,13 +12,22 @@
 //!  * An actor responsible for dialing and listening for new connections.
 //!  * An actor per Peer which owns the underlying connection and is responsible for listening for
 //!  and opening substreams as well as negotiating particular protocols on those substreams.
-use crate::{common::NegotiatedSubstream, counters, protocols::identity::Identity, ProtocolId};
+use crate::{
+    common::NegotiatedSubstream,
+    compression::Compression,
+    counters,
+    peer_metrics::PeerMetrics,
+    peer_reputation::PeerReputation,
+    priority_queue::{PriorityReceiver, PrioritySender},
+    protocols::identity::Identity,
+    ProtocolId,
+};
 use channel;
 use futures::{
     channel::oneshot,
     future::{BoxFuture, FutureExt},
     sink::SinkExt,
-    stream::{Fuse, FuturesUnordered, StreamExt},
+    stream::{select_all, Fuse, FuturesUnordered, SelectAll, StreamExt},
 };
 use libra_logger::prelude::*;
 use libra_types::PeerId;
@@ -28,14 +37,19 @@ use netcore::{
     transport::{ConnectionOrigin, Transport},
 };
 use parity_multiaddr::Multiaddr;
-use std::{collections::HashMap, marker::PhantomData};
+use std::{
+    collections::HashMap,
+    marker::PhantomData,
+    sync::{Arc, RwLock},
+};
 use tokio::runtime::TaskExecutor;
 
 mod error;
+mod inbound_limits;
 #[cfg(test)]
 mod tests;
 
-pub use self::error::PeerManagerError;
+pub use self::{error::PeerManagerError, inbound_limits::InboundConnectionLimits};
 
 /// Notifications about new/lost peers.
 #[derive(Debug)]
@@ -61,10 +75,11 @@ pub enum PeerManagerRequest<TSubstream> {
     ),
 }
 
-/// Convenience wrapper around a `channel::Sender<PeerManagerRequest>` which makes it easy to issue
-/// requests and await the responses from PeerManager
+/// Convenience wrapper around the prioritized queues of `PeerManagerRequest`s which makes it easy
+/// to issue requests and await the responses from PeerManager. Requests to open a substream are
+/// enqueued in the queue of their protocol, the other ones in the default queue.
 pub struct PeerManagerRequestSender<TSubstream> {
-    inner: channel::Sender<PeerManagerRequest<TSubstream>>,
+    inner: PrioritySender<PeerManagerRequest<TSubstream>>,
 }
 
 impl<TSubstream> Clone for PeerManagerRequestSender<TSubstream> {
@@ -74,9 +89,14 @@ impl<TSubstream> Clone for PeerManagerRequestSender<TSubstream> {
 }
 
 impl<TSubstream> PeerManagerRequestSender<TSubstream> {
-    /// Construct a new PeerManagerRequestSender with a raw channel::Sender
-    pub fn new(sender: channel::Sender<PeerManagerRequest<TSubstream>>) -> Self {
-        Self { inner: sender }
+    /// Construct a new PeerManagerRequestSender with a raw channel::Sender or prioritized queues
+    pub fn new<S>(sender: S) -> Self
+    where
+        S: Into<PrioritySender<PeerManagerRequest<TSubstream>>>,
+    {
+        Self {
+            inner: sender.into(),
+        }
     }
 
     /// Request that a given Peer be dialed at the provided `Multiaddr` and synchronously wait for
@@ -88,7 +108,7 @@ impl<TSubstream> PeerManagerRequestSender<TSubstream> {
     ) -> Result<(), PeerManagerError> {
         let (oneshot_tx, oneshot_rx) = oneshot::channel();
         let request = PeerManagerRequest::DialPeer(peer_id, addr, oneshot_tx);
-        self.inner.send(request).await.unwrap();
+        self.inner.default_queue().send(request).await.unwrap();
         oneshot_rx.await?
     }
 
@@ -97,7 +117,7 @@ impl<TSubstream> PeerManagerRequestSender<TSubstream> {
     pub async fn disconnect_peer(&mut self, peer_id: PeerId) -> Result<(), PeerManagerError> {
         let (oneshot_tx, oneshot_rx) = oneshot::channel();
         let request = PeerManagerRequest::DisconnectPeer(peer_id, oneshot_tx);
-        self.inner.send(request).await.unwrap();
+        self.inner.default_queue().send(request).await.unwrap();
         oneshot_rx.await?
     }
 
@@ -109,8 +129,9 @@ impl<TSubstream> PeerManagerRequestSender<TSubstream> {
         protocol: ProtocolId,
     ) -> Result<TSubstream, PeerManagerError> {
         let (oneshot_tx, oneshot_rx) = oneshot::channel();
+        let queue = self.inner.queue(&protocol);
         let request = PeerManagerRequest::OpenSubstream(peer_id, protocol, oneshot_tx);
-        self.inner.send(request).await.unwrap();
+        queue.send(request).await.unwrap();
         oneshot_rx
             .await
             // The open_substream request can get dropped/canceled if the peer
@@ -145,14 +166,14 @@ where
     executor: TaskExecutor,
     /// PeerId of "self".
     own_peer_id: PeerId,
-    /// Address to listen on for incoming connections.
-    listen_addr: Multiaddr,
-    /// Connection Listener, listening on `listen_addr`
+    /// Addresses to listen on for incoming connections.
+    listen_addrs: Vec<Multiaddr>,
+    /// Connection Listener, listening on `listen_addrs`
     connection_handler: Option<ConnectionHandler<TTransport, TMuxer>>,
     /// Map from PeerId to corresponding Peer object.
     active_peers: HashMap<PeerId, PeerHandle<TMuxer::Substream>>,
-    /// Channel to receive requests from other actors.
-    requests_rx: channel::Receiver<PeerManagerRequest<TMuxer::Substream>>,
+    /// Prioritized queues to receive requests from other actors.
+    requests_rx: PriorityReceiver<PeerManagerRequest<TMuxer::Substream>>,
     /// Map from protocol to handler for substreams which want to "speak" that protocol.
     protocol_handlers:
         HashMap<ProtocolId, channel::Sender<PeerManagerNotification<TMuxer::Substream>>>,
@@ -167,6 +188,14 @@ where
     internal_event_tx: channel::Sender<InternalEvent<TMuxer>>,
     /// A map of outstanding disconnect requests
     outstanding_disconnect_requests: HashMap<PeerId, oneshot::Sender<Result<(), PeerManagerError>>>,
+    /// Limits on the inbound connections from the same IP address or subnet, if any.
+    inbound_limits: Option<InboundConnectionLimits>,
+    /// Reputation of the peers: banned peers are neither dialed nor accepted.
+    peer_reputation: Arc<RwLock<PeerReputation>>,
+    /// Protocols whose messages are compressed with each of the connected peers.
+    compression: Compression,
+    /// Metrics of the connected peers.
+    peer_metrics: PeerMetrics,
     /// Pin the transport type corresponding to this PeerManager instance
     phantom_transport: PhantomData<TTransport>,
 }
@@ -181,21 +210,25 @@ where
         transport: TTransport,
         executor: TaskExecutor,
         own_peer_id: PeerId,
-        listen_addr: Multiaddr,
-        requests_rx: channel::Receiver<PeerManagerRequest<TMuxer::Substream>>,
+        listen_addrs: Vec<Multiaddr>,
+        requests_rx: PriorityReceiver<PeerManagerRequest<TMuxer::Substream>>,
         protocol_handlers: HashMap<
             ProtocolId,
             channel::Sender<PeerManagerNotification<TMuxer::Substream>>,
         >,
         peer_event_handlers: Vec<channel::Sender<PeerManagerNotification<TMuxer::Substream>>>,
+        inbound_limits: Option<InboundConnectionLimits>,
+        peer_reputation: Arc<RwLock<PeerReputation>>,
+        compression: Compression,
+        peer_metrics: PeerMetrics,
     ) -> Self {
         let (internal_event_tx, internal_event_rx) =
             channel::new(1024, &counters::PENDING_PEER_MANAGER_INTERNAL_EVENTS);
         let (dial_request_tx, dial_request_rx) =
             channel::new(1024, &counters::PENDING_PEER_MANAGER_DIAL_REQUESTS);
-        let (connection_handler, listen_addr) = ConnectionHandler::new(
+        let (connection_handler, listen_addrs) = ConnectionHandler::new(
             transport,
-            listen_addr,
+            listen_addrs,
             dial_request_rx,
             internal_event_tx.clone(),
         );
@@ -203,7 +236,7 @@ where
         Self {
             executor,
             own_peer_id,
-            listen_addr,
+            listen_addrs,
             connection_handler: Some(connection_handler),
             active_peers: HashMap::new(),
             requests_rx,
@@ -213,16 +246,20 @@ where
             internal_event_tx,
             internal_event_rx,
             outstanding_disconnect_requests: HashMap::new(),
+            inbound_limits,
+            peer_reputation,
+            compression,
+            peer_metrics,
             phantom_transport: PhantomData,
         }
     }
 
-    /// Get the [`Multiaddr`] we're listening for incoming connections on
-    pub fn listen_addr(&self) -> &Multiaddr {
-        &self.listen_addr
+    /// Get the [`Multiaddr`]s we're listening for incoming connections on
+    pub fn listen_addrs(&self) -> &[Multiaddr] {
+        &self.listen_addrs
     }
 
-    /// Start listening on the set address and return a future which runs PeerManager
+    /// Start listening on the set addresses and return a future which runs PeerManager
     pub async fn start(mut self) {
         // Start listening for connections.
         self.start_connection_listener();
@@ -275,6 +312,8 @@ where
                     return;
                 }
                 info!("Disconnected from peer: {}", peer_id.short_str());
+                self.compression.remove_peer(&peer_id);
+                self.peer_metrics.remove_peer(&peer_id);
                 if let Some(oneshot_tx) = self.outstanding_disconnect_requests.remove(&peer_id) {
                     if oneshot_tx.send(Ok(())).is_err() {
                         error!("oneshot channel receiver dropped");
@@ -297,8 +336,24 @@ where
         trace!("PeerManagerRequest::{:?}", request);
         match request {
             PeerManagerRequest::DialPeer(requested_peer_id, addr, response_tx) => {
-                // Only dial peers which we aren't already connected with
-                if let Some(peer) = self.active_peers.get(&requested_peer_id) {
+                // Only dial peers which aren't banned and which we aren't already connected with
+                if self.is_banned(&requested_peer_id) {
+                    counters::BANNED_PEER_CONNECTIONS_REFUSED.inc();
+                    debug!(
+                        "Peer {} is banned, not dialing address {}",
+                        requested_peer_id.short_str(),
+                        addr
+                    );
+                    if response_tx
+                        .send(Err(PeerManagerError::Banned(requested_peer_id)))
+                        .is_err()
+                    {
+                        warn!(
+                            "Receiver for DialPeer {} dropped",
+                            requested_peer_id.short_str()
+                        );
+                    }
+                } else if let Some(peer) = self.active_peers.get(&requested_peer_id) {
                     let error = if peer.is_shutting_down() {
                         PeerManagerError::ShuttingDownPeer
                     } else {
@@ -389,6 +444,34 @@ where
         let peer_id = identity.peer_id();
         assert_ne!(self.own_peer_id, peer_id);
 
+        let rejection = if self.is_banned(&peer_id) {
+            counters::BANNED_PEER_CONNECTIONS_REFUSED.inc();
+            Some("peer is banned")
+        } else if origin == ConnectionOrigin::Inbound && !self.is_inbound_allowed(peer_id, &address)
+        {
+            counters::INBOUND_CONNECTIONS_REJECTED.inc();
+            Some("inbound limits reached")
+        } else {
+            None
+        };
+        if let Some(reason) = rejection {
+            warn!(
+                "Rejecting {:?} connection with peer {} at {}: {}",
+                origin,
+                peer_id.short_str(),
+                address,
+                reason
+            );
+            connection.close().await.unwrap_or_else(|e| {
+                error!(
+                    "Closing connection with Peer {} failed with error: {}",
+                    peer_id.short_str(),
+                    e
+                )
+            });
+            return;
+        }
+
         let mut send_new_peer_notification = true;
 
         // Check for and handle simultaneous dialing
@@ -430,6 +513,8 @@ where
             &counters::OP_COUNTERS
                 .peer_gauge(&counters::PENDING_PEER_REQUESTS, &peer_id.short_str()),
         );
+        self.compression.add_peer(&identity);
+        self.peer_metrics.add_peer(peer_id);
         let peer = Peer::new(
             identity,
             connection,
@@ -456,6 +541,27 @@ where
         }
     }
 
+    fn is_banned(&self, peer_id: &PeerId) -> bool {
+        self.peer_reputation.read().unwrap().is_banned(peer_id)
+    }
+
+    // Checks the inbound connection limits. A connection replacing the existing connection of the
+    // same peer is not counted twice.
+    fn is_inbound_allowed(&self, peer_id: PeerId, address: &Multiaddr) -> bool {
+        match self.inbound_limits {
+            Some(limits) => limits.allows(
+                address,
+                self.active_peers
+                    .values()
+                    .filter(|peer| {
+                        peer.origin() == ConnectionOrigin::Inbound && peer.peer_id() != peer_id
+                    })
+                    .map(PeerHandle::address),
+            ),
+            None => true,
+        }
+    }
+
     async fn dial_peer(
         &mut self,
         peer_id: PeerId,
@@ -505,7 +611,8 @@ where
 {
     /// [`Transport`] that is used to establish connections
     transport: TTransport,
-    listener: Fuse<TTransport::Listener>,
+    /// Listeners of all the listen addresses, merged into a single stream.
+    listener: Fuse<SelectAll<TTransport::Listener>>,
     dial_request_rx: channel::Receiver<ConnectionHandlerRequest>,
     internal_event_tx: channel::Sender<InternalEvent<TMuxer>>,
 }
@@ -520,23 +627,29 @@ where
 {
     fn new(
         transport: TTransport,
-        listen_addr: Multiaddr,
+        listen_addrs: Vec<Multiaddr>,
         dial_request_rx: channel::Receiver<ConnectionHandlerRequest>,
         internal_event_tx: channel::Sender<InternalEvent<TMuxer>>,
-    ) -> (Self, Multiaddr) {
-        let (listener, listen_addr) = transport
-            .listen_on(listen_addr)
-            .expect("Transport listen on fails");
-        debug!("listening on {:?}", listen_addr);
+    ) -> (Self, Vec<Multiaddr>) {
+        let mut listeners = vec![];
+        let mut bound_addrs = vec![];
+        for listen_addr in listen_addrs {
+            let (listener, listen_addr) = transport
+                .listen_on(listen_addr)
+                .expect("Transport listen on fails");
+            debug!("listening on {:?}", listen_addr);
+            listeners.push(listener);
+            bound_addrs.push(listen_addr);
+        }
 
         (
             Self {
                 transport,
-                listener: listener.fuse(),
+                listener: select_all(listeners).fuse(),
                 dial_request_rx,
                 internal_event_tx,
             },
-            listen_addr,
+            bound_addrs,
         )
     }
 
@@ -2,10 +2,13 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use crate::{
+    compression::Compression,
     peer_manager::{
-        DisconnectReason, InternalEvent, Peer, PeerHandle, PeerManager, PeerManagerNotification,
-        PeerManagerRequest,
+        DisconnectReason, InboundConnectionLimits, InternalEvent, Peer, PeerHandle, PeerManager,
+        PeerManagerError, PeerManagerNotification, PeerManagerRequest,
     },
+    peer_metrics::PeerMetrics,
+    peer_reputation::{Misbehavior, PeerReputation},
     protocols::identity::{exchange_identity, Identity},
     ProtocolId,
 };
@@ -26,10 +29,17 @@ use netcore::{
         StreamMultiplexer,
     },
     negotiate::{negotiate_inbound, negotiate_outbound_interactive},
-    transport::{boxed::BoxedTransport, memory::MemoryTransport, ConnectionOrigin, TransportExt},
+    transport::{
+        boxed::BoxedTransport, memory::MemoryTransport, ConnectionOrigin, Transport, TransportExt,
+    },
 };
 use parity_multiaddr::Multiaddr;
-use std::{collections::HashMap, io, time::Duration};
+use std::{
+    collections::HashMap,
+    io,
+    sync::{Arc, RwLock},
+    time::Duration,
+};
 use tokio::{runtime::TaskExecutor, timer::Timeout};
 
 const HELLO_PROTOCOL: &[u8] = b"/hello-world/1.0.0";
@@ -298,10 +308,14 @@ fn build_test_peer_manager(
         build_test_transport(Identity::new(peer_id, vec![], RoleType::Validator)),
         executor.clone(),
         peer_id,
-        "/memory/0".parse().unwrap(),
-        peer_manager_request_rx,
+        vec!["/memory/0".parse().unwrap()],
+        peer_manager_request_rx.into(),
         protocol_handlers,
         Vec::new(),
+        None,
+        Arc::new(RwLock::new(PeerReputation::new())),
+        Compression::default(),
+        PeerMetrics::default(),
     );
 
     (peer_manager, peer_manager_request_tx, hello_rx)
@@ -698,3 +712,135 @@ fn peer_manager_simultaneous_dial_disconnect_event() {
 
     runtime.block_on(test);
 }
+
+#[test]
+fn peer_manager_inbound_connection_limits() {
+    let runtime = ::tokio::runtime::Runtime::new().unwrap();
+
+    let ids = ordered_peer_ids(5);
+    let (mut peer_manager, _request_tx, _hello_rx) =
+        build_test_peer_manager(runtime.executor(), ids[4]);
+    peer_manager.inbound_limits = Some(InboundConnectionLimits::new(1, 2));
+
+    let test = async move {
+        let addresses = [
+            "/ip4/10.0.0.1/tcp/6180",
+            // Same IP address as the first connection.
+            "/ip4/10.0.0.1/tcp/6181",
+            // Same subnet as the first connection.
+            "/ip4/10.0.0.2/tcp/6180",
+            // The subnet is full.
+            "/ip4/10.0.0.3/tcp/6180",
+        ];
+        let mut outbound_connections = vec![];
+        for (peer_id, address) in ids.iter().zip(addresses.iter()) {
+            let (outbound, inbound) = build_test_connection();
+            outbound_connections.push(outbound);
+            peer_manager
+                .add_peer(
+                    build_test_identity(*peer_id),
+                    address.parse().unwrap(),
+                    ConnectionOrigin::Inbound,
+                    inbound,
+                )
+                .await;
+        }
+        assert!(peer_manager.active_peers.contains_key(&ids[0]));
+        assert!(!peer_manager.active_peers.contains_key(&ids[1]));
+        assert!(peer_manager.active_peers.contains_key(&ids[2]));
+        assert!(!peer_manager.active_peers.contains_key(&ids[3]));
+
+        // Outbound connections are not limited.
+        let (_outbound, inbound) = build_test_connection();
+        peer_manager
+            .add_peer(
+                build_test_identity(ids[3]),
+                addresses[3].parse().unwrap(),
+                ConnectionOrigin::Outbound,
+                inbound,
+            )
+            .await;
+        assert!(peer_manager.active_peers.contains_key(&ids[3]));
+    };
+
+    runtime.block_on(test);
+}
+
+#[test]
+fn peer_manager_refuses_banned_peers() {
+    let runtime = ::tokio::runtime::Runtime::new().unwrap();
+
+    let ids = ordered_peer_ids(2);
+    let (mut peer_manager, _request_tx, _hello_rx) =
+        build_test_peer_manager(runtime.executor(), ids[1]);
+    let banned_peer_id = ids[0];
+    {
+        let mut reputation = peer_manager.peer_reputation.write().unwrap();
+        while !reputation.report(banned_peer_id, Misbehavior::InvalidSignature) {}
+    }
+
+    let test = async move {
+        // Connections with a banned peer are closed.
+        for origin in &[ConnectionOrigin::Inbound, ConnectionOrigin::Outbound] {
+            let (_outbound, inbound) = build_test_connection();
+            peer_manager
+                .add_peer(
+                    build_test_identity(banned_peer_id),
+                    Multiaddr::empty(),
+                    *origin,
+                    inbound,
+                )
+                .await;
+            assert!(!peer_manager.active_peers.contains_key(&banned_peer_id));
+        }
+
+        // A banned peer is not dialed.
+        let (response_tx, response_rx) = oneshot::channel();
+        peer_manager
+            .handle_request(PeerManagerRequest::DialPeer(
+                banned_peer_id,
+                "/memory/1".parse().unwrap(),
+                response_tx,
+            ))
+            .await;
+        match response_rx.await.unwrap() {
+            Err(PeerManagerError::Banned(peer_id)) => assert_eq!(peer_id, banned_peer_id),
+            result => panic!("Expected a Banned error, received: {:?}", result),
+        }
+    };
+
+    runtime.block_on(test);
+}
+
+#[test]
+fn peer_manager_multiple_listen_addrs() {
+    let runtime = ::tokio::runtime::Runtime::new().unwrap();
+
+    let ids = ordered_peer_ids(2);
+    let (_request_tx, request_rx) = channel::new_test(0);
+    let peer_manager = PeerManager::new(
+        build_test_transport(build_test_identity(ids[0])),
+        runtime.executor(),
+        ids[0],
+        vec!["/memory/0".parse().unwrap(), "/memory/0".parse().unwrap()],
+        request_rx.into(),
+        HashMap::new(),
+        Vec::new(),
+        None,
+        Arc::new(RwLock::new(PeerReputation::new())),
+        Compression::default(),
+        PeerMetrics::default(),
+    );
+    // A listener is bound on each of the addresses.
+    let listen_addrs = peer_manager.listen_addrs().to_vec();
+    assert_eq!(listen_addrs.len(), 2);
+    assert_ne!(listen_addrs[0], listen_addrs[1]);
+    runtime.spawn(peer_manager.start());
+
+    // Connections are accepted on all the listen addresses.
+    let transport = build_test_transport(build_test_identity(ids[1]));
+    for addr in listen_addrs {
+        let (identity, _connection) = runtime.block_on(transport.dial(addr).unwrap()).unwrap();
+        assert_eq!(identity.peer_id(), ids[0]);
+    }
+}
@@ -0,0 +1,302 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Connection and protocol-level metrics of each connected peer.
+//!
+//! The [`PeerManager`](crate::peer_manager::PeerManager) starts tracking a peer when a connection
+//! is established, and forgets it when the connection is lost. While the peer is connected,
+//!
+//! * the DirectSend and Rpc actors count the bytes and messages sent to and received from the
+//!   peer for each protocol, the open substreams, and the latency and the failures of the rpcs,
+//! * the HealthChecker records the round-trip time of the pings.
+//!
+//! The metrics are also exported to Prometheus, labelled with the peer id (and the protocol,
+//! which is appended to the name of the metric), and the admin interface reads them through
+//! [`PeerMetrics::peers`].
+use crate::{counters, ProtocolId};
+use libra_types::PeerId;
+use std::{
+    collections::HashMap,
+    sync::{Arc, RwLock},
+    time::Duration,
+};
+
+#[cfg(test)]
+mod test;
+
+/// Upper bounds of the buckets of the latency histograms, in milliseconds. The last bucket of a
+/// histogram counts the latencies above the last bound.
+pub const LATENCY_BUCKETS_MS: [u64; 12] = [1, 2, 5, 10, 20, 50, 100, 200, 500, 1000, 2000, 5000];
+
+/// Histogram of latencies.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LatencyHistogram {
+    /// Number of latencies in each of the buckets of [`LATENCY_BUCKETS_MS`], followed by the
+    /// number of latencies above the last bound.
+    pub bucket_counts: Vec<u64>,
+    /// Number of latencies recorded.
+    pub count: u64,
+    /// Sum of the latencies recorded.
+    pub sum: Duration,
+}
+
+impl Default for LatencyHistogram {
+    fn default() -> Self {
+        Self {
+            bucket_counts: vec![0; LATENCY_BUCKETS_MS.len() + 1],
+            count: 0,
+            sum: Duration::from_secs(0),
+        }
+    }
+}
+
+impl LatencyHistogram {
+    pub fn observe(&mut self, latency: Duration) {
+        let millis = latency.as_millis();
+        let bucket = LATENCY_BUCKETS_MS
+            .iter()
+            .position(|bound| millis <= u128::from(*bound))
+            .unwrap_or_else(|| LATENCY_BUCKETS_MS.len());
+        self.bucket_counts[bucket] += 1;
+        self.count += 1;
+        self.sum += latency;
+    }
+
+    /// Mean of the latencies recorded, if any.
+    pub fn mean(&self) -> Option<Duration> {
+        if self.count == 0 {
+            None
+        } else {
+            Some(Duration::from_nanos(
+                (self.sum.as_nanos() / u128::from(self.count)) as u64,
+            ))
+        }
+    }
+}
+
+/// Metrics of a protocol with a peer.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ProtocolStats {
+    /// Bytes sent on the wire, i.e. after compression.
+    pub bytes_sent: u64,
+    pub messages_sent: u64,
+    /// Bytes received on the wire, i.e. before decompression.
+    pub bytes_received: u64,
+    pub messages_received: u64,
+    /// Number of inbound and outbound rpcs which failed, including timeouts.
+    pub rpc_errors: u64,
+    /// Latency of the outbound rpcs which succeeded.
+    pub rpc_latency: LatencyHistogram,
+}
+
+/// Metrics of a connected peer.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct PeerStats {
+    /// Number of substreams currently open with the peer by the DirectSend and Rpc actors.
+    pub open_substreams: u64,
+    /// Round-trip time of the pings which succeeded.
+    pub ping_rtt: LatencyHistogram,
+    pub protocols: HashMap<ProtocolId, ProtocolStats>,
+}
+
+/// Metrics of the connected peers of a network end-point. It is cloned into the PeerManager and
+/// the protocol actors which update the metrics, and into the admin interface which reads them.
+#[derive(Clone, Debug, Default)]
+pub struct PeerMetrics {
+    peers: Arc<RwLock<HashMap<PeerId, PeerStats>>>,
+}
+
+impl PeerMetrics {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Starts tracking a newly connected peer.
+    pub fn add_peer(&self, peer_id: PeerId) {
+        self.peers
+            .write()
+            .unwrap()
+            .insert(peer_id, PeerStats::default());
+    }
+
+    /// Stops tracking a disconnected peer, and removes its series from the Prometheus metrics.
+    pub fn remove_peer(&self, peer_id: &PeerId) {
+        let stats = match self.peers.write().unwrap().remove(peer_id) {
+            Some(stats) => stats,
+            None => return,
+        };
+        let peer = peer_id.short_str();
+        for protocol in stats.protocols.keys() {
+            for name in &[
+                *counters::PEER_BYTES_SENT,
+                *counters::PEER_MESSAGES_SENT,
+                *counters::PEER_BYTES_RECEIVED,
+                *counters::PEER_MESSAGES_RECEIVED,
+                *counters::PEER_RPC_ERRORS,
+            ] {
+                counters::OP_COUNTERS.remove_peer_counter(&labelled(name, protocol), &peer);
+            }
+            counters::OP_COUNTERS.remove_peer_duration_histogram(
+                &labelled(&counters::PEER_RPC_LATENCY, protocol),
+                &peer,
+            );
+        }
+        counters::OP_COUNTERS.remove_peer_duration_histogram(&counters::PEER_PING_RTT, &peer);
+        counters::OP_COUNTERS.remove_peer_gauge(&counters::PEER_OPEN_SUBSTREAMS, &peer);
+    }
+
+    /// Returns a snapshot of the metrics of the connected peers.
+    pub fn peers(&self) -> HashMap<PeerId, PeerStats> {
+        self.peers.read().unwrap().clone()
+    }
+
+    /// Returns a snapshot of the metrics of a peer, if it is connected.
+    pub fn peer(&self, peer_id: &PeerId) -> Option<PeerStats> {
+        self.peers.read().unwrap().get(peer_id).cloned()
+    }
+
+    /// Records a message of `num_bytes` bytes sent to the peer.
+    pub fn record_sent(&self, peer_id: PeerId, protocol: &ProtocolId, num_bytes: usize) {
+        if !self.update_protocol(peer_id, protocol, |stats| {
+            stats.bytes_sent += num_bytes as u64;
+            stats.messages_sent += 1;
+        }) {
+            return;
+        }
+        counters::OP_COUNTERS
+            .peer_counter(
+                &labelled(&counters::PEER_BYTES_SENT, protocol),
+                &peer_id.short_str(),
+            )
+            .inc_by(num_bytes as i64);
+        counters::OP_COUNTERS
+            .peer_counter(
+                &labelled(&counters::PEER_MESSAGES_SENT, protocol),
+                &peer_id.short_str(),
+            )
+            .inc();
+    }
+
+    /// Records a message of `num_bytes` bytes received from the peer.
+    pub fn record_received(&self, peer_id: PeerId, protocol: &ProtocolId, num_bytes: usize) {
+        if !self.update_protocol(peer_id, protocol, |stats| {
+            stats.bytes_received += num_bytes as u64;
+            stats.messages_received += 1;
+        }) {
+            return;
+        }
+        counters::OP_COUNTERS
+            .peer_counter(
+                &labelled(&counters::PEER_BYTES_RECEIVED, protocol),
+                &peer_id.short_str(),
+            )
+            .inc_by(num_bytes as i64);
+        counters::OP_COUNTERS
+            .peer_counter(
+                &labelled(&counters::PEER_MESSAGES_RECEIVED, protocol),
+                &peer_id.short_str(),
+            )
+            .inc();
+    }
+
+    /// Records the latency of a successful outbound rpc to the peer.
+    pub fn record_rpc_latency(&self, peer_id: PeerId, protocol: &ProtocolId, latency: Duration) {
+        if !self.update_protocol(peer_id, protocol, |stats| {
+            stats.rpc_latency.observe(latency)
+        }) {
+            return;
+        }
+        counters::OP_COUNTERS
+            .peer_duration_histogram(
+                &labelled(&counters::PEER_RPC_LATENCY, protocol),
+                &peer_id.short_str(),
+            )
+            .observe_duration(latency);
+    }
+
+    /// Records an inbound or outbound rpc with the peer which failed.
+    pub fn record_rpc_error(&self, peer_id: PeerId, protocol: &ProtocolId) {
+        if !self.update_protocol(peer_id, protocol, |stats| stats.rpc_errors += 1) {
+            return;
+        }
+        counters::OP_COUNTERS
+            .peer_counter(
+                &labelled(&counters::PEER_RPC_ERRORS, protocol),
+                &peer_id.short_str(),
+            )
+            .inc();
+    }
+
+    /// Records the round-trip time of a successful ping to the peer.
+    pub fn record_ping_rtt(&self, peer_id: PeerId, rtt: Duration) {
+        if let Some(stats) = self.peers.write().unwrap().get_mut(&peer_id) {
+            stats.ping_rtt.observe(rtt);
+            counters::OP_COUNTERS
+                .peer_duration_histogram(&counters::PEER_PING_RTT, &peer_id.short_str())
+                .observe_duration(rtt);
+        }
+    }
+
+    /// Counts a substream opened with the peer, until the returned guard is dropped.
+    pub fn open_substream(&self, peer_id: PeerId) -> SubstreamGuard {
+        self.update_open_substreams(peer_id, true);
+        SubstreamGuard {
+            peer_metrics: self.clone(),
+            peer_id,
+        }
+    }
+
+    fn update_open_substreams(&self, peer_id: PeerId, opened: bool) {
+        if let Some(stats) = self.peers.write().unwrap().get_mut(&peer_id) {
+            stats.open_substreams = if opened {
+                stats.open_substreams + 1
+            } else {
+                // The substream may have been opened before the peer reconnected.
+                stats.open_substreams.saturating_sub(1)
+            };
+            counters::OP_COUNTERS
+                .peer_gauge(&counters::PEER_OPEN_SUBSTREAMS, &peer_id.short_str())
+                .set(stats.open_substreams as i64);
+        }
+    }
+
+    // Updates the metrics of a protocol with the peer, and returns whether the peer is connected.
+    // Peers which are not connected are ignored, so that the messages in flight when a connection
+    // is lost do not resurrect the peer (nor its Prometheus series).
+    fn update_protocol<F>(&self, peer_id: PeerId, protocol: &ProtocolId, update: F) -> bool
+    where
+        F: FnOnce(&mut ProtocolStats),
+    {
+        match self.peers.write().unwrap().get_mut(&peer_id) {
+            Some(stats) => {
+                update(
+                    stats
+                        .protocols
+                        .entry(protocol.clone())
+                        .or_insert_with(ProtocolStats::default),
+                );
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+/// Keeps a substream counted in the open substreams of a peer while it is alive.
+#[derive(Debug)]
+pub struct SubstreamGuard {
+    peer_metrics: PeerMetrics,
+    peer_id: PeerId,
+}
+
+impl Drop for SubstreamGuard {
+    fn drop(&mut self) {
+        self.peer_metrics
+            .update_open_substreams(self.peer_id, false);
+    }
+}
+
+// Appends the protocol to the name of a Prometheus metric.
+fn labelled(name: &str, protocol: &ProtocolId) -> String {
+    format!("{}.{}", name, String::from_utf8_lossy(protocol))
+}
@@ -0,0 +1,125 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use super::*;
+
+#[test]
+fn test_latency_histogram() {
+    let mut histogram = LatencyHistogram::default();
+    assert_eq!(histogram.mean(), None);
+
+    histogram.observe(Duration::from_micros(500));
+    histogram.observe(Duration::from_millis(1));
+    histogram.observe(Duration::from_millis(30));
+    histogram.observe(Duration::from_secs(10));
+
+    let mut bucket_counts = vec![0; LATENCY_BUCKETS_MS.len() + 1];
+    // Up to 1ms.
+    bucket_counts[0] = 2;
+    // Between 20ms and 50ms.
+    bucket_counts[5] = 1;
+    // Above the last bound.
+    bucket_counts[LATENCY_BUCKETS_MS.len()] = 1;
+    assert_eq!(histogram.bucket_counts, bucket_counts);
+    assert_eq!(histogram.count, 4);
+    assert_eq!(histogram.mean(), Some(Duration::from_micros(2_507_875)));
+}
+
+#[test]
+fn test_protocol_stats() {
+    let peer_metrics = PeerMetrics::new();
+    let peer_id = PeerId::random();
+    let consensus = ProtocolId::from_static(b"/libra/consensus/rpc/0.1.0");
+    let mempool = ProtocolId::from_static(b"/libra/mempool/direct_send/0.1.0");
+
+    peer_metrics.add_peer(peer_id);
+    peer_metrics.record_sent(peer_id, &consensus, 100);
+    peer_metrics.record_received(peer_id, &consensus, 200);
+    peer_metrics.record_rpc_latency(peer_id, &consensus, Duration::from_millis(15));
+    peer_metrics.record_rpc_error(peer_id, &consensus);
+    peer_metrics.record_sent(peer_id, &mempool, 10);
+    peer_metrics.record_sent(peer_id, &mempool, 20);
+    peer_metrics.record_ping_rtt(peer_id, Duration::from_millis(3));
+
+    let stats = peer_metrics.peer(&peer_id).unwrap();
+    let consensus_stats = &stats.protocols[&consensus];
+    assert_eq!(consensus_stats.bytes_sent, 100);
+    assert_eq!(consensus_stats.messages_sent, 1);
+    assert_eq!(consensus_stats.bytes_received, 200);
+    assert_eq!(consensus_stats.messages_received, 1);
+    assert_eq!(consensus_stats.rpc_errors, 1);
+    assert_eq!(consensus_stats.rpc_latency.count, 1);
+    let mempool_stats = &stats.protocols[&mempool];
+    assert_eq!(mempool_stats.bytes_sent, 30);
+    assert_eq!(mempool_stats.messages_sent, 2);
+    assert_eq!(mempool_stats.messages_received, 0);
+    assert_eq!(stats.ping_rtt.count, 1);
+}
+
+#[test]
+fn test_disconnected_peers_are_ignored() {
+    let peer_metrics = PeerMetrics::new();
+    let peer_id = PeerId::random();
+    let protocol = ProtocolId::from_static(b"/libra/consensus/rpc/0.1.0");
+
+    // The metrics of a peer which is not connected are not tracked.
+    peer_metrics.record_sent(peer_id, &protocol, 100);
+    assert_eq!(peer_metrics.peer(&peer_id), None);
+
+    peer_metrics.add_peer(peer_id);
+    peer_metrics.record_sent(peer_id, &protocol, 100);
+    assert_eq!(peer_metrics.peers().len(), 1);
+
+    // The metrics are forgotten when the peer disconnects.
+    peer_metrics.remove_peer(&peer_id);
+    peer_metrics.record_sent(peer_id, &protocol, 100);
+    assert!(peer_metrics.peers().is_empty());
+}
+
+#[test]
+fn test_open_substreams() {
+    let peer_metrics = PeerMetrics::new();
+    let peer_id = PeerId::random();
+    peer_metrics.add_peer(peer_id);
+
+    let guard_a = peer_metrics.open_substream(peer_id);
+    let guard_b = peer_metrics.open_substream(peer_id);
+    assert_eq!(peer_metrics.peer(&peer_id).unwrap().open_substreams, 2);
+    drop(guard_a);
+    assert_eq!(peer_metrics.peer(&peer_id).unwrap().open_substreams, 1);
+
+    // A substream opened before the peer reconnected is not counted after the reconnection.
+    peer_metrics.add_peer(peer_id);
+    drop(guard_b);
+    assert_eq!(peer_metrics.peer(&peer_id).unwrap().open_substreams, 0);
+}
+
+#[test]
+fn test_remove_peer_series() {
+    let peer_metrics = PeerMetrics::new();
+    let peer_id = PeerId::random();
+    let protocol = ProtocolId::from_static(b"/libra/consensus/rpc/0.1.0");
+    let label = format!("remote_peer_id={}", peer_id.short_str());
+    let num_series = || {
+        metrics::get_all_metrics()
+            .keys()
+            .filter(|name| name.contains(&label))
+            .count()
+    };
+
+    peer_metrics.add_peer(peer_id);
+    peer_metrics.record_sent(peer_id, &protocol, 100);
+    peer_metrics.record_received(peer_id, &protocol, 100);
+    peer_metrics.record_rpc_latency(peer_id, &protocol, Duration::from_millis(15));
+    peer_metrics.record_rpc_error(peer_id, &protocol);
+    peer_metrics.record_ping_rtt(peer_id, Duration::from_millis(3));
+    let guard = peer_metrics.open_substream(peer_id);
+    assert_eq!(num_series(), 8);
+
+    // The Prometheus series of the peer are removed when it disconnects, and are not recreated
+    // by the substreams or the messages still in flight.
+    peer_metrics.remove_peer(&peer_id);
+    drop(guard);
+    peer_metrics.record_sent(peer_id, &protocol, 100);
+    assert_eq!(num_series(), 0);
+}
@@ -0,0 +1,298 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Reputation of the peers of a network end-point.
+//!
+//! The protocols running on top of the network (consensus, mempool, state synchronizer...)
+//! report the misbehaviors of the peers they interact with through
+//! [`NetworkRequest::ReportPeer`](crate::interface::NetworkRequest::ReportPeer). Each report adds
+//! a penalty to the score of the peer, which decays over time. A peer whose penalty reaches the
+//! [`BAN_THRESHOLD`] is banned for the [`TEMPORARY_BAN_DURATION`], and a peer which keeps
+//! misbehaving after [`MAX_TEMPORARY_BANS`] temporary bans is banned permanently.
+//!
+//! Only the misbehaviors an honest peer cannot commit (e.g. invalid signatures) lead to a
+//! permanent ban: a slow peer timing out is only ever banned temporarily. The trusted peers
+//! (e.g. the eligible validators) are never banned permanently either, since disconnecting them
+//! for good would threaten the liveness of the protocols.
+//!
+//! The permanent bans are persisted in a ban list file, one peer id per line, which can also be
+//! edited by the operator. The [`PeerManager`](crate::peer_manager::PeerManager) refuses to dial
+//! or to accept connections from banned peers.
+use crate::{common::NetworkPublicKeys, counters};
+use failure::prelude::*;
+use libra_logger::prelude::*;
+use libra_types::PeerId;
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+    str::FromStr,
+    sync::{Arc, RwLock},
+    time::{Duration, SystemTime},
+};
+
+#[cfg(test)]
+mod test;
+
+/// Penalty at which a peer gets banned.
+pub const BAN_THRESHOLD: u64 = 100;
+/// Penalty forgiven for each second since the last report of a peer.
+pub const PENALTY_DECAY_PER_SEC: u64 = 1;
+/// Duration of the ban of a peer whose penalty reached the threshold.
+pub const TEMPORARY_BAN_DURATION: Duration = Duration::from_secs(10 * 60);
+/// Number of temporary bans after which a peer is banned permanently.
+pub const MAX_TEMPORARY_BANS: u32 = 3;
+
+/// Misbehaviors of a peer, reported by the protocols running on top of the network.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Misbehavior {
+    /// The peer sent a message which could not be deserialized or is not well-formed.
+    MalformedMessage,
+    /// The peer sent a message carrying an invalid signature.
+    InvalidSignature,
+    /// The peer did not respond to an RPC request in time.
+    RpcTimeout,
+    /// The peer sent unsolicited or useless messages, e.g. invalid transactions.
+    Spam,
+}
+
+impl Misbehavior {
+    /// Penalty added to the score of a peer for this misbehavior.
+    pub fn penalty(self) -> u64 {
+        match self {
+            Misbehavior::MalformedMessage => 20,
+            Misbehavior::InvalidSignature => 50,
+            Misbehavior::RpcTimeout => 5,
+            Misbehavior::Spam => 10,
+        }
+    }
+
+    /// Whether the bans caused by this misbehavior count towards a permanent ban. Timeouts do
+    /// not: an honest but slow peer times out as well.
+    pub fn escalates(self) -> bool {
+        match self {
+            Misbehavior::RpcTimeout => false,
+            Misbehavior::MalformedMessage | Misbehavior::InvalidSignature | Misbehavior::Spam => {
+                true
+            }
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct PeerScore {
+    penalty: u64,
+    reported_at: SystemTime,
+    num_bans: u32,
+}
+
+/// Scores and bans of the peers of a network end-point. It is shared (behind a lock) between the
+/// NetworkProvider, which receives the reports, the PeerManager, which enforces the bans, and the
+/// admin interface.
+#[derive(Debug, Default)]
+pub struct PeerReputation {
+    scores: HashMap<PeerId, PeerScore>,
+    /// Banned peers, alongside the expiry of their ban. Permanent bans never expire.
+    bans: HashMap<PeerId, Option<SystemTime>>,
+    /// File in which the permanent bans are persisted.
+    ban_list_file: Option<PathBuf>,
+    /// Peers which are never banned permanently, shared with the connectivity manager which
+    /// keeps them up to date with the eligible validators.
+    trusted_peers: Arc<RwLock<HashMap<PeerId, NetworkPublicKeys>>>,
+}
+
+impl PeerReputation {
+    /// Creates a reputation which does not persist its bans.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates a reputation which persists its permanent bans in the given file, and loads the
+    /// bans it already holds.
+    pub fn with_ban_list(ban_list_file: PathBuf) -> Result<Self> {
+        let bans = if ban_list_file.exists() {
+            load_ban_list(&ban_list_file)?
+                .into_iter()
+                .map(|peer_id| (peer_id, None))
+                .collect()
+        } else {
+            HashMap::new()
+        };
+        Ok(Self {
+            scores: HashMap::new(),
+            bans,
+            ban_list_file: Some(ban_list_file),
+            trusted_peers: Arc::new(RwLock::new(HashMap::new())),
+        })
+    }
+
+    /// Sets the trusted peers, which are only ever banned temporarily.
+    pub fn set_trusted_peers(
+        &mut self,
+        trusted_peers: Arc<RwLock<HashMap<PeerId, NetworkPublicKeys>>>,
+    ) {
+        self.trusted_peers = trusted_peers;
+    }
+
+    /// Adds the penalty of the given misbehavior to the score of the peer, and returns whether
+    /// the peer got banned as a result.
+    pub fn report(&mut self, peer_id: PeerId, misbehavior: Misbehavior) -> bool {
+        self.report_at(peer_id, misbehavior, SystemTime::now())
+    }
+
+    pub fn is_banned(&self, peer_id: &PeerId) -> bool {
+        self.is_banned_at(peer_id, SystemTime::now())
+    }
+
+    /// Returns the banned peers, alongside the expiry of their ban (`None` for permanent bans).
+    pub fn bans(&self) -> Vec<(PeerId, Option<SystemTime>)> {
+        let now = SystemTime::now();
+        let mut bans: Vec<_> = self
+            .bans
+            .iter()
+            .filter(|(peer_id, _)| self.is_banned_at(peer_id, now))
+            .map(|(peer_id, expiry)| (*peer_id, *expiry))
+            .collect();
+        bans.sort();
+        bans
+    }
+
+    /// Lifts the ban of the peer and forgives its past misbehaviors. Returns whether the peer was
+    /// banned.
+    pub fn clear_ban(&mut self, peer_id: &PeerId) -> bool {
+        self.scores.remove(peer_id);
+        match self.bans.remove(peer_id) {
+            Some(expiry) => {
+                if expiry.is_none() {
+                    self.persist();
+                }
+                info!("Cleared the ban of peer {}", peer_id.short_str());
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn report_at(&mut self, peer_id: PeerId, misbehavior: Misbehavior, now: SystemTime) -> bool {
+        counters::PEER_MISBEHAVIORS_REPORTED.inc();
+        self.prune_at(now);
+        if self.is_banned_at(&peer_id, now) {
+            return false;
+        }
+        let is_trusted = self.trusted_peers.read().unwrap().contains_key(&peer_id);
+        let score = self.scores.entry(peer_id).or_insert(PeerScore {
+            penalty: 0,
+            reported_at: now,
+            num_bans: 0,
+        });
+        let elapsed_secs = now
+            .duration_since(score.reported_at)
+            .unwrap_or_default()
+            .as_secs();
+        score.penalty = score
+            .penalty
+            .saturating_sub(elapsed_secs.saturating_mul(PENALTY_DECAY_PER_SEC))
+            .saturating_add(misbehavior.penalty());
+        score.reported_at = now;
+        debug!(
+            "Peer {} reported for {:?}, penalty: {}",
+            peer_id.short_str(),
+            misbehavior,
+            score.penalty
+        );
+        if score.penalty < BAN_THRESHOLD {
+            return false;
+        }
+
+        score.penalty = 0;
+        if misbehavior.escalates() {
+            score.num_bans += 1;
+        }
+        let expiry = if score.num_bans > MAX_TEMPORARY_BANS && !is_trusted {
+            None
+        } else {
+            Some(now + TEMPORARY_BAN_DURATION)
+        };
+        security_log(SecurityEvent::PeerBanned)
+            .error(&misbehavior)
+            .data(&peer_id)
+            .data(&expiry)
+            .log();
+        counters::PEERS_BANNED.inc();
+        self.bans.insert(peer_id, expiry);
+        if expiry.is_none() {
+            self.persist();
+        }
+        true
+    }
+
+    // Drops the expired temporary bans, as well as the scores of the peers whose penalty fully
+    // decayed and which were never banned.
+    fn prune_at(&mut self, now: SystemTime) {
+        self.bans
+            .retain(|_, expiry| expiry.map_or(true, |expiry| expiry > now));
+        self.scores.retain(|_, score| {
+            let elapsed_secs = now
+                .duration_since(score.reported_at)
+                .unwrap_or_default()
+                .as_secs();
+            score.num_bans > 0 || score.penalty > elapsed_secs.saturating_mul(PENALTY_DECAY_PER_SEC)
+        });
+    }
+
+    fn is_banned_at(&self, peer_id: &PeerId, now: SystemTime) -> bool {
+        match self.bans.get(peer_id) {
+            Some(Some(expiry)) => *expiry > now,
+            Some(None) => true,
+            None => false,
+        }
+    }
+
+    // Writes the permanent bans to the ban list file, if any.
+    fn persist(&self) {
+        let ban_list_file = match &self.ban_list_file {
+            Some(ban_list_file) => ban_list_file,
+            None => return,
+        };
+        let mut permanent_bans: Vec<_> = self
+            .bans
+            .iter()
+            .filter(|(_, expiry)| expiry.is_none())
+            .map(|(peer_id, _)| *peer_id)
+            .collect();
+        permanent_bans.sort();
+        if let Err(e) = save_ban_list(ban_list_file, &permanent_bans) {
+            error!(
+                "Failed to persist the ban list to {:?}: {:?}",
+                ban_list_file, e
+            );
+        }
+    }
+}
+
+// Reads a ban list file: one peer id per line, ignoring empty lines and comments starting with
+// '#'.
+fn load_ban_list(path: &Path) -> Result<Vec<PeerId>> {
+    fs::read_to_string(path)?
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(|line| {
+            PeerId::from_str(line)
+                .with_context(|_| format!("Invalid peer id in ban list: {}", line))
+                .map_err(Into::into)
+        })
+        .collect()
+}
+
+fn save_ban_list(path: &Path, peer_ids: &[PeerId]) -> Result<()> {
+    let contents: String = peer_ids
+        .iter()
+        .map(|peer_id| format!("{:x}\n", peer_id))
+        .collect();
+    // Write to a temporary file first, so that the ban list is never left half written.
+    let tmp_path = path.with_extension("tmp");
+    fs::write(&tmp_path, contents)?;
+    fs::rename(&tmp_path, path)?;
+    Ok(())
+}
@@ -0,0 +1,149 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use super::*;
+use crypto::{ed25519, x25519};
+use libra_tools::tempdir::TempPath;
+
+// Reports the misbehavior until the peer gets banned, and returns the number of reports.
+fn report_until_banned(
+    reputation: &mut PeerReputation,
+    peer_id: PeerId,
+    misbehavior: Misbehavior,
+    now: SystemTime,
+) -> usize {
+    let mut num_reports = 1;
+    while !reputation.report_at(peer_id, misbehavior, now) {
+        num_reports += 1;
+    }
+    num_reports
+}
+
+#[test]
+fn test_temporary_ban() {
+    let mut reputation = PeerReputation::new();
+    let peer_id = PeerId::random();
+    let now = SystemTime::now();
+
+    assert_eq!(
+        report_until_banned(&mut reputation, peer_id, Misbehavior::InvalidSignature, now),
+        2
+    );
+    assert!(reputation.is_banned_at(&peer_id, now));
+    assert_eq!(
+        reputation.bans(),
+        vec![(peer_id, Some(now + TEMPORARY_BAN_DURATION))]
+    );
+    // The reports against a banned peer are ignored.
+    assert!(!reputation.report_at(peer_id, Misbehavior::InvalidSignature, now));
+
+    // The ban expires.
+    let later = now + TEMPORARY_BAN_DURATION;
+    assert!(!reputation.is_banned_at(&peer_id, later));
+    assert!(!reputation.is_banned_at(&PeerId::random(), now));
+
+    // The expired ban is pruned upon the next report.
+    assert!(!reputation.report_at(PeerId::random(), Misbehavior::RpcTimeout, later));
+    assert!(reputation.bans.is_empty());
+    assert_eq!(reputation.scores.len(), 2);
+}
+
+#[test]
+fn test_penalty_decay() {
+    let mut reputation = PeerReputation::new();
+    let peer_id = PeerId::random();
+    let mut now = SystemTime::now();
+
+    // Malformed messages spread over time never get the peer banned.
+    let interval =
+        Duration::from_secs(Misbehavior::MalformedMessage.penalty() / PENALTY_DECAY_PER_SEC);
+    for _ in 0..100 {
+        assert!(!reputation.report_at(peer_id, Misbehavior::MalformedMessage, now));
+        now += interval;
+    }
+    assert_eq!(
+        report_until_banned(&mut reputation, peer_id, Misbehavior::MalformedMessage, now),
+        5
+    );
+}
+
+#[test]
+fn test_permanent_ban_persistence() {
+    let ban_list_file = TempPath::new();
+    let mut reputation = PeerReputation::with_ban_list(ban_list_file.path().to_path_buf()).unwrap();
+    let peer_id = PeerId::random();
+    let mut now = SystemTime::now();
+
+    // The peer keeps misbehaving after its temporary bans.
+    for _ in 0..MAX_TEMPORARY_BANS {
+        report_until_banned(&mut reputation, peer_id, Misbehavior::Spam, now);
+        assert!(reputation.is_banned_at(&peer_id, now));
+        now += TEMPORARY_BAN_DURATION;
+    }
+    report_until_banned(&mut reputation, peer_id, Misbehavior::Spam, now);
+    assert_eq!(reputation.bans(), vec![(peer_id, None)]);
+
+    // The permanent ban survives a restart.
+    let mut reputation = PeerReputation::with_ban_list(ban_list_file.path().to_path_buf()).unwrap();
+    assert!(reputation.is_banned(&peer_id));
+
+    // Clearing the ban removes it from the ban list.
+    assert!(reputation.clear_ban(&peer_id));
+    assert!(!reputation.clear_ban(&peer_id));
+    assert!(!reputation.is_banned(&peer_id));
+    let reputation = PeerReputation::with_ban_list(ban_list_file.path().to_path_buf()).unwrap();
+    assert!(reputation.bans().is_empty());
+}
+
+#[test]
+fn test_load_ban_list() {
+    let ban_list_file = TempPath::new();
+    let peer_id = PeerId::random();
+    fs::write(
+        ban_list_file.path(),
+        format!("# Banned by the operator\n\n{:x}\n", peer_id),
+    )
+    .unwrap();
+    let reputation = PeerReputation::with_ban_list(ban_list_file.path().to_path_buf()).unwrap();
+    assert_eq!(reputation.bans(), vec![(peer_id, None)]);
+
+    fs::write(ban_list_file.path(), "not a peer id\n").unwrap();
+    assert!(PeerReputation::with_ban_list(ban_list_file.path().to_path_buf()).is_err());
+}
+
+#[test]
+fn test_no_permanent_ban_for_timeouts_or_trusted_peers() {
+    let mut reputation = PeerReputation::new();
+    let slow_peer = PeerId::random();
+    let trusted_peer = PeerId::random();
+    let trusted_peers = vec![(
+        trusted_peer,
+        NetworkPublicKeys {
+            signing_public_key: ed25519::compat::generate_keypair(None).1,
+            identity_public_key: x25519::compat::generate_keypair(None).1,
+        },
+    )]
+    .into_iter()
+    .collect();
+    reputation.set_trusted_peers(Arc::new(RwLock::new(trusted_peers)));
+    let mut now = SystemTime::now();
+
+    // Both peers keep getting banned, yet the bans stay temporary: the slow peer only times out,
+    // and the trusted peer is exempt from permanent bans.
+    for _ in 0..=MAX_TEMPORARY_BANS {
+        report_until_banned(&mut reputation, slow_peer, Misbehavior::RpcTimeout, now);
+        report_until_banned(
+            &mut reputation,
+            trusted_peer,
+            Misbehavior::InvalidSignature,
+            now,
+        );
+        let mut expected_bans = vec![
+            (slow_peer, Some(now + TEMPORARY_BAN_DURATION)),
+            (trusted_peer, Some(now + TEMPORARY_BAN_DURATION)),
+        ];
+        expected_bans.sort();
+        assert_eq!(reputation.bans(), expected_bans);
+        now += TEMPORARY_BAN_DURATION;
+    }
+}
@@ -0,0 +1,208 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Prioritized per-protocol queues.
+//!
+//! All the protocols share the substream multiplexer of a peer, and the actors in between. To
+//! keep a flood of messages of one protocol (e.g. mempool broadcasts) from delaying the messages
+//! of another one (e.g. consensus votes), every protocol gets its own bounded queue. The
+//! receiving end drains the queues in strict [`ProtocolPriority`] order: a queue is only polled
+//! once all the queues of higher priority are empty. The queues of the same priority are polled
+//! in a round-robin fashion, so that none of them starves the others.
+//!
+//! A message which does not fit in the queue of its protocol is dropped, and the drop is counted
+//! per protocol.
+use crate::{counters, ProtocolId};
+use channel;
+use futures::{
+    channel::mpsc,
+    stream::{FusedStream, Stream},
+    task::{Context, Poll},
+};
+use std::{cmp::Reverse, collections::HashMap, fmt::Debug, pin::Pin};
+
+#[cfg(test)]
+mod test;
+
+/// Priority of the traffic of a protocol.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum ProtocolPriority {
+    Low,
+    Normal,
+    High,
+}
+
+impl Default for ProtocolPriority {
+    fn default() -> Self {
+        ProtocolPriority::Normal
+    }
+}
+
+/// Creates a queue for each of the given protocols, and a default queue of normal priority for
+/// the other ones. Each queue holds up to `size` items, and reports its length through the gauge
+/// `<name>.<protocol>`.
+pub fn new<T>(
+    priorities: &HashMap<ProtocolId, ProtocolPriority>,
+    size: usize,
+    name: &str,
+) -> (PrioritySender<T>, PriorityReceiver<T>) {
+    let mut queues = HashMap::new();
+    let mut receivers = vec![];
+    for (protocol, priority) in priorities {
+        let gauge = counters::OP_COUNTERS.gauge(&format!("{}.{}", name, protocol_name(protocol)));
+        let (queue_tx, queue_rx) = channel::new(size, &gauge);
+        queues.insert(protocol.clone(), queue_tx);
+        receivers.push((*priority, queue_rx));
+    }
+    let (default_queue, default_queue_rx) = channel::new(size, &counters::OP_COUNTERS.gauge(name));
+    receivers.push((ProtocolPriority::default(), default_queue_rx));
+    (
+        PrioritySender {
+            queues,
+            default_queue,
+        },
+        PriorityReceiver::new(receivers),
+    )
+}
+
+/// Sending end of the prioritized queues, which routes the items to the queue of their protocol.
+pub struct PrioritySender<T> {
+    queues: HashMap<ProtocolId, channel::Sender<T>>,
+    /// Queue of the protocols without a queue of their own, and of the items not bound to any
+    /// protocol.
+    default_queue: channel::Sender<T>,
+}
+
+impl<T> Clone for PrioritySender<T> {
+    fn clone(&self) -> Self {
+        Self {
+            queues: self.queues.clone(),
+            default_queue: self.default_queue.clone(),
+        }
+    }
+}
+
+impl<T> PrioritySender<T> {
+    /// Returns the queue of the given protocol.
+    pub fn queue(&mut self, protocol: &ProtocolId) -> &mut channel::Sender<T> {
+        match self.queues.get_mut(protocol) {
+            Some(queue) => queue,
+            None => &mut self.default_queue,
+        }
+    }
+
+    /// Returns the queue of the items not bound to any protocol.
+    pub fn default_queue(&mut self) -> &mut channel::Sender<T> {
+        &mut self.default_queue
+    }
+
+    /// Enqueues the item in the queue of its protocol without waiting. The item is dropped (and
+    /// the drop counted) if the queue is full.
+    pub fn try_send(&mut self, protocol: &ProtocolId, item: T) -> Result<(), mpsc::SendError> {
+        self.queue(protocol).try_send(item).map_err(|e| {
+            if e.is_full() {
+                count_dropped(protocol);
+            }
+            e
+        })
+    }
+}
+
+/// A single queue, e.g. in tests.
+impl<T> From<channel::Sender<T>> for PrioritySender<T> {
+    fn from(default_queue: channel::Sender<T>) -> Self {
+        Self {
+            queues: HashMap::new(),
+            default_queue,
+        }
+    }
+}
+
+/// Receiving end of the prioritized queues.
+pub struct PriorityReceiver<T> {
+    /// Queues grouped by priority, from the highest to the lowest priority, alongside the index
+    /// of the queue to poll first in the group.
+    groups: Vec<(Vec<channel::Receiver<T>>, usize)>,
+}
+
+impl<T> PriorityReceiver<T> {
+    pub fn new(queues: Vec<(ProtocolPriority, channel::Receiver<T>)>) -> Self {
+        let mut groups: Vec<(ProtocolPriority, Vec<channel::Receiver<T>>)> = vec![];
+        for (priority, queue) in queues {
+            match groups.iter_mut().find(|(p, _)| *p == priority) {
+                Some((_, group)) => group.push(queue),
+                None => groups.push((priority, vec![queue])),
+            }
+        }
+        groups.sort_by_key(|(priority, _)| Reverse(*priority));
+        Self {
+            groups: groups.into_iter().map(|(_, group)| (group, 0)).collect(),
+        }
+    }
+}
+
+impl<T> From<channel::Receiver<T>> for PriorityReceiver<T> {
+    fn from(queue: channel::Receiver<T>) -> Self {
+        Self::new(vec![(ProtocolPriority::default(), queue)])
+    }
+}
+
+impl<T> Stream for PriorityReceiver<T>
+where
+    T: Debug,
+{
+    type Item = T;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Option<Self::Item>> {
+        let mut is_terminated = true;
+        for (group, next) in self.groups.iter_mut() {
+            let len = group.len();
+            for i in 0..len {
+                let index = (*next + i) % len;
+                let queue = &mut group[index];
+                if queue.is_terminated() {
+                    continue;
+                }
+                match Pin::new(queue).poll_next(cx) {
+                    Poll::Ready(Some(item)) => {
+                        *next = (index + 1) % len;
+                        return Poll::Ready(Some(item));
+                    }
+                    Poll::Ready(None) => {}
+                    Poll::Pending => is_terminated = false,
+                }
+            }
+        }
+        if is_terminated {
+            Poll::Ready(None)
+        } else {
+            Poll::Pending
+        }
+    }
+}
+
+impl<T> FusedStream for PriorityReceiver<T>
+where
+    T: Debug,
+{
+    fn is_terminated(&self) -> bool {
+        self.groups
+            .iter()
+            .all(|(group, _)| group.iter().all(|queue| queue.is_terminated()))
+    }
+}
+
+/// Counts a message of the given protocol dropped because its queue is full.
+pub fn count_dropped(protocol: &ProtocolId) {
+    counters::OP_COUNTERS
+        .counter(&format!(
+            "{}.{}",
+            *counters::PROTOCOL_MESSAGES_DROPPED,
+            protocol_name(protocol)
+        ))
+        .inc();
+}
+
+fn protocol_name(protocol: &ProtocolId) -> String {
+    String::from_utf8_lossy(protocol).into_owned()
+}
@@ -0,0 +1,106 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use super::*;
+use futures::{executor::block_on, stream::StreamExt};
+
+const CONSENSUS: &[u8] = b"/test/consensus/1.0.0";
+const MEMPOOL: &[u8] = b"/test/mempool/1.0.0";
+const STATE_SYNC: &[u8] = b"/test/state_sync/1.0.0";
+
+fn test_queues(size: usize) -> (PrioritySender<u32>, PriorityReceiver<u32>) {
+    let priorities = [
+        (ProtocolId::from_static(CONSENSUS), ProtocolPriority::High),
+        (ProtocolId::from_static(MEMPOOL), ProtocolPriority::Low),
+        (
+            ProtocolId::from_static(STATE_SYNC),
+            ProtocolPriority::Normal,
+        ),
+    ]
+    .iter()
+    .cloned()
+    .collect();
+    new(&priorities, size, "test_queues")
+}
+
+#[test]
+fn test_higher_priority_first() {
+    let (mut sender, mut receiver) = test_queues(8);
+    let consensus = ProtocolId::from_static(CONSENSUS);
+    let mempool = ProtocolId::from_static(MEMPOOL);
+    let state_sync = ProtocolId::from_static(STATE_SYNC);
+
+    for i in 0..4 {
+        sender.try_send(&mempool, i).unwrap();
+    }
+    sender.try_send(&state_sync, 10).unwrap();
+    sender.try_send(&consensus, 20).unwrap();
+
+    assert_eq!(block_on(receiver.next()), Some(20));
+    assert_eq!(block_on(receiver.next()), Some(10));
+    for i in 0..4 {
+        assert_eq!(block_on(receiver.next()), Some(i));
+    }
+}
+
+#[test]
+fn test_full_queue_does_not_block_others() {
+    let (mut sender, mut receiver) = test_queues(2);
+    let consensus = ProtocolId::from_static(CONSENSUS);
+    let mempool = ProtocolId::from_static(MEMPOOL);
+
+    // Flood the mempool queue until it drops messages.
+    let mut num_sent = 0;
+    while sender.try_send(&mempool, num_sent).is_ok() {
+        num_sent += 1;
+    }
+    assert!(sender.try_send(&mempool, num_sent).unwrap_err().is_full());
+
+    // The consensus queue still accepts messages, which are received first.
+    sender.try_send(&consensus, 100).unwrap();
+    assert_eq!(block_on(receiver.next()), Some(100));
+    assert_eq!(block_on(receiver.next()), Some(0));
+}
+
+#[test]
+fn test_round_robin_within_priority() {
+    let (tx_1, rx_1) = channel::new_test(8);
+    let (tx_2, rx_2) = channel::new_test(8);
+    let mut receiver = PriorityReceiver::new(vec![
+        (ProtocolPriority::Normal, rx_1),
+        (ProtocolPriority::Normal, rx_2),
+    ]);
+    let mut sender_1 = PrioritySender::from(tx_1);
+    let mut sender_2 = PrioritySender::from(tx_2);
+    for i in 0..3 {
+        sender_1.default_queue().try_send(i).unwrap();
+        sender_2.default_queue().try_send(10 + i).unwrap();
+    }
+
+    let received: Vec<_> = (0..6).map(|_| block_on(receiver.next()).unwrap()).collect();
+    assert_eq!(received, vec![0, 10, 1, 11, 2, 12]);
+}
+
+#[test]
+fn test_unknown_protocol_uses_default_queue() {
+    let (mut sender, mut receiver) = test_queues(8);
+    let unknown = ProtocolId::from_static(b"/test/unknown/1.0.0");
+
+    sender.try_send(&unknown, 1).unwrap();
+    sender.default_queue().try_send(2).unwrap();
+    assert_eq!(block_on(receiver.next()), Some(1));
+    assert_eq!(block_on(receiver.next()), Some(2));
+}
+
+#[test]
+fn test_terminates_when_all_senders_dropped() {
+    let (mut sender, mut receiver) = test_queues(8);
+    sender
+        .try_send(&ProtocolId::from_static(MEMPOOL), 1)
+        .unwrap();
+    drop(sender);
+
+    assert_eq!(block_on(receiver.next()), Some(1));
+    assert_eq!(block_on(receiver.next()), None);
+    assert!(receiver.is_terminated());
+}
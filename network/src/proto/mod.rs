@@ -22,9 +22,11 @@ use ::libra_types::proto::types;
 
 pub use self::{
     consensus::{
-        consensus_msg::Message as ConsensusMsg_oneof, Block, BlockInfo, BlockRetrievalStatus,
-        ConsensusMsg, Proposal, QuorumCert, RequestBlock, RespondBlock, SyncInfo,
-        TimeoutCertificate, Vote, VoteData,
+        consensus_msg::Message as ConsensusMsg_oneof,
+        equivocation_evidence::Evidence as EquivocationEvidence_oneof, Batch, BatchInfo, Block,
+        BlockInfo, BlockRetrievalStatus, ConsensusMsg, DoubleProposal, DoubleVote,
+        EquivocationEvidence, ProofOfStore, Proposal, QuorumCert, RequestBatch, RequestBlock,
+        RespondBatch, RespondBlock, SignedBatchInfo, SyncInfo, TimeoutCertificate, Vote, VoteData,
     },
     mempool::MempoolSyncMsg,
     network::{
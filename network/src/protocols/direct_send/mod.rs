@@ -44,9 +44,12 @@
 //! [substream negotiation]: ../../../netcore/negotiate/index.html
 //! [`protocol-select`]: ../../../netcore/negotiate/index.html
 use crate::{
+    compression::{self, Compression},
     counters,
     error::NetworkError,
     peer_manager::{PeerManagerNotification, PeerManagerRequestSender},
+    peer_metrics::PeerMetrics,
+    priority_queue::{self, PriorityReceiver},
     ProtocolId,
 };
 use bytes::Bytes;
@@ -110,8 +113,8 @@ impl Debug for Message {
 pub struct DirectSend<TSubstream> {
     /// A handle to a tokio executor.
     executor: TaskExecutor,
-    /// Channel to receive requests from other upstream actors.
-    ds_requests_rx: channel::Receiver<DirectSendRequest>,
+    /// Prioritized queues to receive requests from other upstream actors, one per protocol.
+    ds_requests_rx: PriorityReceiver<DirectSendRequest>,
     /// Channels to send notifictions to upstream actors.
     ds_notifs_tx: channel::Sender<DirectSendNotification>,
     /// Channel to receive notifications from PeerManager.
@@ -120,6 +123,10 @@ pub struct DirectSend<TSubstream> {
     peer_mgr_reqs_tx: PeerManagerRequestSender<TSubstream>,
     /// Outbound message queues for each (PeerId, ProtocolId) pair.
     message_queues: HashMap<(PeerId, ProtocolId), channel::Sender<Bytes>>,
+    /// Protocols whose messages are compressed with each of the connected peers.
+    compression: Compression,
+    /// Metrics of the connected peers.
+    peer_metrics: PeerMetrics,
 }
 
 impl<TSubstream> DirectSend<TSubstream>
@@ -128,10 +135,12 @@ where
 {
     pub fn new(
         executor: TaskExecutor,
-        ds_requests_rx: channel::Receiver<DirectSendRequest>,
+        ds_requests_rx: PriorityReceiver<DirectSendRequest>,
         ds_notifs_tx: channel::Sender<DirectSendNotification>,
         peer_mgr_notifs_rx: channel::Receiver<PeerManagerNotification<TSubstream>>,
         peer_mgr_reqs_tx: PeerManagerRequestSender<TSubstream>,
+        compression: Compression,
+        peer_metrics: PeerMetrics,
     ) -> Self {
         Self {
             executor,
@@ -140,6 +149,8 @@ where
             peer_mgr_notifs_rx,
             peer_mgr_reqs_tx,
             message_queues: HashMap::new(),
+            compression,
+            peer_metrics,
         }
     }
 
@@ -165,33 +176,50 @@ where
         trace!("PeerManagerNotification::{:?}", notif);
         match notif {
             PeerManagerNotification::NewInboundSubstream(peer_id, substream) => {
+                let compressed = self
+                    .compression
+                    .is_compressed(&peer_id, &substream.protocol);
                 self.executor.spawn(Self::handle_inbound_substream(
                     peer_id,
                     substream.protocol,
                     substream.substream,
+                    compressed,
                     self.ds_notifs_tx.clone(),
+                    self.peer_metrics.clone(),
                 ));
             }
             _ => unreachable!("Unexpected PeerManagerNotification"),
         }
     }
 
-    // Handle a new inbound substream. Keep forwarding the messages to the NetworkProvider.
+    // Handle a new inbound substream. Keep forwarding the messages to the NetworkProvider,
+    // decompressing them if compression is negotiated for the protocol.
     async fn handle_inbound_substream(
         peer_id: PeerId,
         protocol: ProtocolId,
         substream: TSubstream,
+        compressed: bool,
         mut ds_notifs_tx: channel::Sender<DirectSendNotification>,
+        peer_metrics: PeerMetrics,
     ) {
+        let _substream_guard = peer_metrics.open_substream(peer_id);
         let mut substream = Framed::new(IoCompat::new(substream), LengthDelimitedCodec::new());
         while let Some(item) = substream.next().await {
+            let item = item.and_then(|data| {
+                peer_metrics.record_received(peer_id, &protocol, data.len());
+                if compressed {
+                    compression::decompress(&data)
+                } else {
+                    Ok(data.freeze())
+                }
+            });
             match item {
-                Ok(data) => {
+                Ok(mdata) => {
                     let notif = DirectSendNotification::RecvMessage(
                         peer_id,
                         Message {
                             protocol: protocol.clone(),
-                            mdata: data.freeze(),
+                            mdata,
                         },
                     );
                     ds_notifs_tx
@@ -216,12 +244,14 @@ where
     }
 
     // Create a new message queue and spawn a task to forward the messages from the queue to the
-    // corresponding substream.
+    // corresponding substream, compressing them if compression is negotiated for the protocol.
     async fn start_message_queue_handler(
         executor: TaskExecutor,
         mut peer_mgr_reqs_tx: PeerManagerRequestSender<TSubstream>,
         peer_id: PeerId,
         protocol: ProtocolId,
+        compressed: bool,
+        peer_metrics: PeerMetrics,
     ) -> Result<channel::Sender<Bytes>, NetworkError> {
         // Create a channel for the (PeerId, ProtocolId) pair.
         let (msg_tx, msg_rx) = channel::new::<Bytes>(
@@ -233,12 +263,24 @@ where
         );
 
         // Open a new substream for the (PeerId, ProtocolId) pair
-        let raw_substream = peer_mgr_reqs_tx.open_substream(peer_id, protocol).await?;
+        let raw_substream = peer_mgr_reqs_tx
+            .open_substream(peer_id, protocol.clone())
+            .await?;
         let substream = Framed::new(IoCompat::new(raw_substream), LengthDelimitedCodec::new());
 
         // Spawn a task to forward the messages from the queue to the substream.
         let f_substream = async move {
-            if let Err(e) = msg_rx.map(Ok).forward(substream).await {
+            let _substream_guard = peer_metrics.open_substream(peer_id);
+            let msgs = msg_rx.map(|mdata| {
+                let mdata = if compressed {
+                    compression::compress(&mdata)?
+                } else {
+                    mdata
+                };
+                peer_metrics.record_sent(peer_id, &protocol, mdata.len());
+                Ok(mdata)
+            });
+            if let Err(e) = msgs.forward(substream).await {
                 warn!(
                     "Forward messages to peer {} error {:?}",
                     peer_id.short_str(),
@@ -277,6 +319,8 @@ where
                     peer_mgr_reqs_tx,
                     peer_id,
                     protocol.clone(),
+                    self.compression.is_compressed(&peer_id, &protocol),
+                    self.peer_metrics.clone(),
                 )
                 .await?;
                 entry.insert(msg_tx)
@@ -286,7 +330,9 @@ where
         substream_queue_tx.try_send(msg.mdata).map_err(|e| {
             // If the channel is full, simply drop the message on the floor;
             // If the channel is disconnected, remove the message queue from the collection.
-            if e.is_disconnected() {
+            if e.is_full() {
+                priority_queue::count_dropped(&protocol);
+            } else if e.is_disconnected() {
                 self.message_queues.remove(&(peer_id, protocol));
             }
             e.into()
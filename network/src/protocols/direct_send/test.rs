@@ -3,9 +3,11 @@
 
 use crate::{
     common::NegotiatedSubstream,
+    compression::Compression,
     peer_manager::{
         PeerManagerError, PeerManagerNotification, PeerManagerRequest, PeerManagerRequestSender,
     },
+    peer_metrics::PeerMetrics,
     protocols::direct_send::{DirectSend, DirectSendNotification, DirectSendRequest, Message},
     ProtocolId,
 };
@@ -40,10 +42,12 @@ fn start_direct_send_actor(
     let (peer_mgr_reqs_tx, peer_mgr_reqs_rx) = channel::new_test(8);
     let direct_send = DirectSend::new(
         executor.clone(),
-        ds_requests_rx,
+        ds_requests_rx.into(),
         ds_notifs_tx,
         peer_mgr_notifs_rx,
         PeerManagerRequestSender::new(peer_mgr_reqs_tx),
+        Compression::default(),
+        PeerMetrics::default(),
     );
     executor.spawn(direct_send.start());
 
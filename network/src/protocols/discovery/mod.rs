@@ -20,6 +20,27 @@
 //! Currently we do not use this mechanism to detect peer failures - instead, we simply connect to
 //! all the peers in the network, and hope to learn about their failure on connection errors.
 //!
+//! ## Permissionless networks
+//!
+//! In a permissionless network (e.g. the public full node network), the signing keys of the peers
+//! are not known in advance: each note carries the signing public key of its peer. Since anyone
+//! can produce such a note for any peer id, the signing key must be bound to the peer id before
+//! the note is accepted. A key is bound if it is the key of a trusted peer, or if it was
+//! received from the peer itself, whose identity is authenticated by the Noise handshake of the
+//! connection. The notes relayed by other peers are only accepted if they are signed with the
+//! bound key of their peer: the notes of a peer we have not heard from yet are rejected rather
+//! than pinning whatever key the first relayer presents.
+//!
+//! ## Full node seed records
+//!
+//! Each note also carries the signed `FullNodePayload` of its peer, through which validators
+//! publish a seed record (e.g. a DNS name) listing the public full nodes. A full node configured
+//! with a [`SeedResolver`] resolves the seed record of each updated payload, and passes the
+//! resolved full node addresses to the [`ConnectivityManager`]. Only the seed records signed by
+//! trusted peers (i.e. validators) are resolved: anyone can join a permissionless network and
+//! publish a seed record, which would otherwise steer our connections. The addresses of the peers for
+//! which we already hold a note are ignored, since the note is more authoritative.
+//!
 //! TODO: We need to handle to case of peers who may no longer be a part of the network.
 //!
 //! ## Future work
@@ -45,20 +66,18 @@ use channel;
 use crypto::{
     ed25519::*,
     hash::{CryptoHasher, DiscoveryMsgHasher},
-    HashValue,
+    HashValue, Signature, ValidKey,
 };
 use failure::{format_err, Fail};
 use futures::{
-    future::{Future, FutureExt, TryFutureExt},
+    future::{BoxFuture, Future, FutureExt, TryFutureExt},
     io::{AsyncRead, AsyncWrite},
     sink::SinkExt,
     stream::{FusedStream, FuturesUnordered, Stream, StreamExt},
 };
 use libra_logger::prelude::*;
 use libra_types::{
-    crypto_proxies::{ValidatorSigner as Signer, ValidatorVerifier as SignatureValidator},
-    validator_verifier::ValidatorInfo as SignatureInfo,
-    PeerId,
+    crypto_proxies::ValidatorSigner as Signer, validator_verifier::VerifyError, PeerId,
 };
 use netcore::compat::IoCompat;
 use parity_multiaddr::Multiaddr;
@@ -77,6 +96,10 @@ use tokio::{
     future::FutureExt as _,
 };
 
+mod seed_resolver;
+
+pub use self::seed_resolver::{DnsSeedResolver, SeedResolver};
+
 #[cfg(test)]
 mod test;
 
@@ -88,6 +111,11 @@ pub struct Discovery<TTicker, TSubstream> {
     self_note: Note,
     /// Validator for verifying signatures on messages.
     trusted_peers: Arc<RwLock<HashMap<PeerId, NetworkPublicKeys>>>,
+    /// If the network is permissioned, notes are verified with the keys of the trusted peers.
+    /// Otherwise they are verified with the signing key they carry.
+    is_permissioned: bool,
+    /// Resolver of the seed records published by validators. Only set on full nodes.
+    seed_resolver: Option<Box<dyn SeedResolver>>,
     /// Current state, maintaining the most recent Note for each peer, alongside parsed PeerInfo.
     known_peers: HashMap<PeerId, (PeerInfo, Note)>,
     /// Info for seed peers.
@@ -117,20 +145,20 @@ where
     pub fn new(
         self_peer_id: PeerId,
         self_addrs: Vec<Multiaddr>,
+        dns_seed_addr: Vec<u8>,
         signer: Signer,
         seed_peers: HashMap<PeerId, PeerInfo>,
         trusted_peers: Arc<RwLock<HashMap<PeerId, NetworkPublicKeys>>>,
+        is_permissioned: bool,
+        seed_resolver: Option<Box<dyn SeedResolver>>,
         ticker: TTicker,
         peer_mgr_reqs_tx: PeerManagerRequestSender<TSubstream>,
         peer_mgr_notifs_rx: channel::Receiver<PeerManagerNotification<TSubstream>>,
         conn_mgr_reqs_tx: channel::Sender<ConnectivityRequest>,
         msg_timeout: Duration,
     ) -> Self {
-        // TODO(philiphayes): wire through config
-        let dns_seed_addr = b"example.com";
-
         let self_peer_info = create_peer_info(self_addrs);
-        let self_full_node_payload = create_full_node_payload(dns_seed_addr);
+        let self_full_node_payload = create_full_node_payload(&dns_seed_addr);
         let self_note = create_note(
             &signer,
             self_peer_id,
@@ -145,6 +173,8 @@ where
             self_note,
             seed_peers,
             trusted_peers,
+            is_permissioned,
+            seed_resolver,
             known_peers,
             connected_peers: HashMap::new(),
             ticker,
@@ -188,11 +218,13 @@ where
     // - an incoming substream from a peer wishing to send its state
     // - an internal task once it has processed incoming messages from a peer, and wishes for
     // discovery actor to update its state.
+    // - an internal task once it has resolved a seed record.
     pub async fn start(mut self) {
         // Bootstrap by connecting to seed peers.
         self.connect_to_seed_peers().await;
         let mut unprocessed_inbound = FuturesUnordered::new();
         let mut unprocessed_outbound = FuturesUnordered::new();
+        let mut unprocessed_resolutions = FuturesUnordered::new();
         loop {
             futures::select! {
                 _ = self.ticker.select_next_some() => {
@@ -204,7 +236,8 @@ where
                 (peer_id, stream_result) = unprocessed_inbound.select_next_some() => {
                     match stream_result {
                         Ok(remote_notes) => {
-                            self.reconcile(peer_id, remote_notes).await;
+                            self.reconcile(peer_id, remote_notes, &mut unprocessed_resolutions)
+                                .await;
                         }
                         Err(e) => {
                             warn!("Failure in processing stream from peer: {}. Error: {:?}",
@@ -213,6 +246,9 @@ where
                     }
                 },
                 _ = unprocessed_outbound.select_next_some() => {}
+                (seed_addr, resolution) = unprocessed_resolutions.select_next_some() => {
+                    self.handle_seed_resolution(seed_addr, resolution).await;
+                },
                 complete => {
                     crit!("Discovery actor terminated");
                     break;
@@ -284,6 +320,7 @@ where
                 unprocessed_inbound.push(
                     handle_inbound_substream(
                         self.trusted_peers.clone(),
+                        self.is_permissioned,
                         peer_id,
                         substream,
                         self.msg_timeout,
@@ -316,7 +353,12 @@ where
 
     // Updates local state by reconciling with notes received from some remote peer.
     // Assumption: `remote_notes` have already been verified for signature validity and content.
-    async fn reconcile(&mut self, remote_peer: PeerId, remote_notes: Vec<Note>) {
+    async fn reconcile<'a>(
+        &'a mut self,
+        remote_peer: PeerId,
+        remote_notes: Vec<Note>,
+        unprocessed_resolutions: &'a mut FuturesUnordered<BoxFuture<'static, SeedResolution>>,
+    ) {
         // If a peer is previously unknown, or has a newer epoch number, we update its
         // corresponding entry in the map.
         let self_peer_id =
@@ -326,6 +368,15 @@ where
             let peer_info_bytes = &note.signed_peer_info.as_ref().unwrap().peer_info;
             let peer_info = PeerInfo::decode(peer_info_bytes).expect("PeerInfo parsing fails");
 
+            if !self.is_permissioned && !self.is_bound_key(remote_peer, peer_id, &note) {
+                security_log(SecurityEvent::InvalidDiscoveryMsg)
+                    .error("UnboundSigningKey")
+                    .data(&remote_peer)
+                    .data(&note)
+                    .log();
+                continue;
+            }
+
             match self.known_peers.get_mut(&peer_id) {
                 // If we know about this peer, and receive the same or an older epoch, we do
                 // nothing.
@@ -348,6 +399,14 @@ where
                     // We can never receive a note with a higher epoch number on us than what we
                     // ourselves have broadcasted.
                     assert_ne!(peer_id, self_peer_id);
+                    // Resolve the seed record of the peer if its FullNodePayload was updated.
+                    if let Some(seed_addr) = self.updated_seed_addr(peer_id, &note) {
+                        if let Some(seed_resolver) = &self.seed_resolver {
+                            let resolution = seed_resolver.resolve(&seed_addr);
+                            unprocessed_resolutions
+                                .push(async move { (seed_addr, resolution.await) }.boxed());
+                        }
+                    }
                     // Update internal state of the peer with new Note.
                     self.known_peers.insert(peer_id, (peer_info.clone(), note));
 
@@ -376,6 +435,93 @@ where
             }
         }
     }
+
+    // Returns the seed record of the FullNodePayload of a note, unless the peer is not trusted,
+    // the seed record is empty or the payload is not newer than the one of the note we already
+    // know for the peer. The note of a trusted peer is signed with its trusted key, as checked by
+    // `is_valid` and `is_bound_key`.
+    fn updated_seed_addr(&self, peer_id: PeerId, note: &Note) -> Option<Vec<u8>> {
+        if !self.trusted_peers.read().unwrap().contains_key(&peer_id) {
+            return None;
+        }
+        let payload = full_node_payload(note)?;
+        if payload.dns_seed_addr.is_empty() {
+            return None;
+        }
+        match self
+            .known_peers
+            .get(&peer_id)
+            .and_then(|(_, known_note)| full_node_payload(known_note))
+        {
+            Some(known_payload) if payload.epoch <= known_payload.epoch => None,
+            _ => Some(payload.dns_seed_addr),
+        }
+    }
+
+    // Passes the full node addresses resolved from a seed record to the connectivity manager.
+    async fn handle_seed_resolution(
+        &mut self,
+        seed_addr: Vec<u8>,
+        resolution: Result<HashMap<PeerId, Vec<Multiaddr>>, failure::Error>,
+    ) {
+        let resolved_peers = match resolution {
+            Ok(resolved_peers) => resolved_peers,
+            Err(err) => {
+                warn!(
+                    "Failed to resolve seed record {}: {:?}",
+                    String::from_utf8_lossy(&seed_addr),
+                    err
+                );
+                return;
+            }
+        };
+        debug!(
+            "Resolved {} full nodes from seed record {}",
+            resolved_peers.len(),
+            String::from_utf8_lossy(&seed_addr)
+        );
+        for (peer_id, addrs) in resolved_peers {
+            if addrs.is_empty() || self.known_peers.contains_key(&peer_id) {
+                continue;
+            }
+            self.conn_mgr_reqs_tx
+                .send(ConnectivityRequest::UpdateAddresses(peer_id, addrs))
+                .await
+                .expect("ConnectivityRequest::UpdateAddresses send");
+        }
+    }
+
+    // In a permissionless network, checks that the signing key of a note is bound to its peer
+    // id: it is the key of a trusted peer, the note comes from the peer itself (authenticated by
+    // the connection), or it is the key of the note we already accepted for the peer. Our own
+    // note is never replaced.
+    fn is_bound_key(&self, remote_peer: PeerId, peer_id: PeerId, note: &Note) -> bool {
+        if note.peer_id == self.self_note.peer_id {
+            return note.signing_public_key == self.self_note.signing_public_key;
+        }
+        if let Some(keys) = self.trusted_peers.read().unwrap().get(&peer_id) {
+            return keys.signing_public_key.to_bytes()[..] == note.signing_public_key[..];
+        }
+        if remote_peer == peer_id {
+            return true;
+        }
+        match self.known_peers.get(&peer_id) {
+            Some((_, known_note)) => known_note.signing_public_key == note.signing_public_key,
+            None => false,
+        }
+    }
+}
+
+// The seed record of a resolution, alongside the addresses of the full nodes it lists.
+type SeedResolution = (
+    Vec<u8>,
+    Result<HashMap<PeerId, Vec<Multiaddr>>, failure::Error>,
+);
+
+// Decodes the FullNodePayload of a note, which has already been validated, if any.
+fn full_node_payload(note: &Note) -> Option<FullNodePayload> {
+    let signed_full_node_payload = note.signed_full_node_payload.as_ref()?;
+    FullNodePayload::decode(&signed_full_node_payload.payload).ok()
 }
 
 // Creates a PeerInfo combining the given addresses with the current unix timestamp as epoch.
@@ -435,6 +581,7 @@ fn create_note(
     note.peer_id = peer_id.into();
     note.signed_peer_info = Some(signed_peer_info);
     note.signed_full_node_payload = Some(signed_full_node_payload);
+    note.signing_public_key = signer.public_key().to_bytes().to_vec();
     note
 }
 
@@ -443,6 +590,7 @@ fn create_note(
 // 2. Verifies signatures on all notes contained in the message.
 async fn handle_inbound_substream<TSubstream>(
     trusted_peers: Arc<RwLock<HashMap<PeerId, NetworkPublicKeys>>>,
+    is_permissioned: bool,
     peer_id: PeerId,
     substream: NegotiatedSubstream<TSubstream>,
     timeout: Duration,
@@ -465,7 +613,7 @@ where
     // if any `Note` is invalid.
     let res_notes = res_msg.and_then(|msg| {
         msg.notes.iter().try_for_each(|note| {
-            is_valid(&note, &trusted_peers).map_err(|err| {
+            is_valid(&note, &trusted_peers, is_permissioned).map_err(|err| {
                 security_log(SecurityEvent::InvalidDiscoveryMsg)
                     .error(&err)
                     .data(&peer_id)
@@ -483,12 +631,16 @@ where
 
 // Verifies validity of notes. Following conditions should be met for validity:
 // 1. We should be able to correctly parse the peer id in each note.
-// 2. The signature of the serialized peer info should be valid for the given peer_id.
+// 2. The signature of the serialized peer info should be valid for the given peer_id. If the
+// network is permissionless, the peers that are not trusted sign with the key carried by the
+// note, whose binding to the peer_id is checked upon reconciliation.
 // 3. The address(es) in the PeerInfo should be correctly parsable as Multiaddrs.
-// 4. The signature of the serialized full node payload should be valid for the given peer_id.
+// 4. The signature of the serialized full node payload should be valid for the given peer_id,
+// and its seed record should be valid UTF-8.
 fn is_valid(
     note: &Note,
     trusted_peers: &RwLock<HashMap<PeerId, NetworkPublicKeys>>,
+    is_permissioned: bool,
 ) -> Result<(), NetworkError> {
     // validate PeerId
 
@@ -504,12 +656,13 @@ fn is_valid(
 
     let peer_info_bytes = &signed_peer_info.peer_info;
     let peer_info_signature = &signed_peer_info.signature;
-    verify_signature(
-        trusted_peers,
-        peer_id,
-        &peer_info_signature,
-        &peer_info_bytes,
-    )?;
+    let signing_public_key = match trusted_public_key(trusted_peers, peer_id) {
+        Ok(signing_public_key) => signing_public_key,
+        Err(err) if is_permissioned => return Err(err),
+        Err(_) => Ed25519PublicKey::try_from(&note.signing_public_key[..])
+            .map_err(|err| err.context(NetworkErrorKind::ParsingError))?,
+    };
+    verify_signature(&signing_public_key, &peer_info_signature, &peer_info_bytes)?;
 
     let peer_info = PeerInfo::decode(peer_info_bytes)?;
     for addr in peer_info.addrs {
@@ -517,19 +670,17 @@ fn is_valid(
     }
 
     // validate FullNodePayload (optional)
-    // TODO(philiphayes): actually use the FullNodePayload
 
     if let Some(signed_full_node_payload) = &note.signed_full_node_payload {
         verify_signature(
-            trusted_peers,
-            peer_id,
+            &signing_public_key,
             &signed_full_node_payload.signature,
             &signed_full_node_payload.payload,
         )?;
 
-        let _ = FullNodePayload::decode(&signed_full_node_payload.payload)?;
-
-        // TODO(philiphayes): validate internal fields
+        let full_node_payload = FullNodePayload::decode(&signed_full_node_payload.payload)?;
+        std::str::from_utf8(&full_node_payload.dns_seed_addr)
+            .map_err(|err| err.context(NetworkErrorKind::ParsingError))?;
     }
 
     Ok(())
@@ -541,30 +692,28 @@ fn get_hash(msg: &[u8]) -> HashValue {
     hasher.finish()
 }
 
-fn verify_signature(
+fn trusted_public_key(
     trusted_peers: &RwLock<HashMap<PeerId, NetworkPublicKeys>>,
-    signer: PeerId,
+    peer_id: PeerId,
+) -> Result<Ed25519PublicKey, NetworkError> {
+    trusted_peers
+        .read()
+        .unwrap()
+        .get(&peer_id)
+        .map(|network_public_keys| network_public_keys.signing_public_key.clone())
+        .ok_or_else(|| VerifyError::UnknownAuthor.into())
+}
+
+fn verify_signature(
+    signing_public_key: &Ed25519PublicKey,
     signature: &[u8],
     msg: &[u8],
 ) -> Result<(), NetworkError> {
-    let verifier = SignatureValidator::new_with_quorum_voting_power(
-        trusted_peers
-            .read()
-            .unwrap()
-            .iter()
-            .map(|(peer_id, network_public_keys)| {
-                (
-                    *peer_id,
-                    SignatureInfo::new(network_public_keys.signing_public_key.clone(), 1),
-                )
-            })
-            .collect(),
-        1, /* quorum size */
-    )
-    .expect("Quorum size should be valid.");
     let signature = Ed25519Signature::try_from(signature)
         .map_err(|err| err.context(NetworkErrorKind::SignatureError))?;
-    verifier.verify_signature(signer, get_hash(msg), &signature)?;
+    signature
+        .verify(&get_hash(msg), signing_public_key)
+        .map_err(|err| err.context(NetworkErrorKind::SignatureError))?;
     Ok(())
 }
 
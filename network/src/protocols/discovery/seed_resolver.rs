@@ -0,0 +1,130 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Resolution of the seed records published by the validators in their `FullNodePayload`.
+//!
+//! A seed record (e.g. a DNS name) resolves into the network addresses of public full nodes,
+//! which a full node can dial to join the public network. The resolution mechanism is pluggable
+//! through the [`SeedResolver`] trait, and [`DnsSeedResolver`] resolves the seed records through
+//! the DNS resolver of the system.
+use failure::prelude::*;
+use futures::{
+    channel::oneshot,
+    future::{BoxFuture, FutureExt},
+};
+use libra_types::PeerId;
+use parity_multiaddr::Multiaddr;
+use std::{
+    collections::HashMap,
+    net::{SocketAddr, ToSocketAddrs},
+    str::FromStr,
+    sync::mpsc::{self, SyncSender, TrySendError},
+    thread,
+};
+
+/// Resolves a seed record into the addresses of the full nodes it lists.
+pub trait SeedResolver: Send + Sync {
+    /// Resolves the given seed record. The resolution happens asynchronously, so that a slow
+    /// resolver does not hold up the discovery actor.
+    fn resolve(
+        &self,
+        seed_addr: &[u8],
+    ) -> BoxFuture<'static, Result<HashMap<PeerId, Vec<Multiaddr>>, Error>>;
+}
+
+/// Maximum number of seed records waiting to be resolved by the [`DnsSeedResolver`].
+const MAX_PENDING_RESOLUTIONS: usize = 16;
+
+/// Resolves the seed records made of comma-separated `<peer id>@<host>:<port>` entries: the host
+/// of each entry is looked up through the DNS resolver of the system, and every address it
+/// resolves into is dialed on the given TCP port.
+///
+/// The lookups are blocking, hence they run one at a time on a dedicated worker thread. At most
+/// [`MAX_PENDING_RESOLUTIONS`] seed records wait for the worker: the resolutions requested beyond
+/// that fail right away. The worker exits once the resolver is dropped.
+pub struct DnsSeedResolver {
+    resolution_tx: SyncSender<PendingResolution>,
+}
+
+// A seed record waiting to be resolved, alongside the channel to send its resolution on.
+type PendingResolution = (
+    Vec<u8>,
+    oneshot::Sender<Result<HashMap<PeerId, Vec<Multiaddr>>>>,
+);
+
+impl DnsSeedResolver {
+    pub fn new() -> Self {
+        let (resolution_tx, resolution_rx) = mpsc::sync_channel(MAX_PENDING_RESOLUTIONS);
+        thread::Builder::new()
+            .name("seed-resolver".to_string())
+            .spawn(move || {
+                for (seed_addr, result_tx) in resolution_rx {
+                    let _ = result_tx.send(resolve_seed_record(&seed_addr));
+                }
+            })
+            .expect("Failed to spawn the seed resolver");
+        Self { resolution_tx }
+    }
+}
+
+impl Default for DnsSeedResolver {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SeedResolver for DnsSeedResolver {
+    fn resolve(
+        &self,
+        seed_addr: &[u8],
+    ) -> BoxFuture<'static, Result<HashMap<PeerId, Vec<Multiaddr>>, Error>> {
+        let (result_tx, result_rx) = oneshot::channel();
+        let queued = match self.resolution_tx.try_send((seed_addr.to_vec(), result_tx)) {
+            Ok(()) => Ok(()),
+            Err(TrySendError::Full(_)) => Err(format_err!(
+                "Too many seed records pending resolution, dropping this one"
+            )),
+            Err(TrySendError::Disconnected(_)) => Err(format_err!("The seed resolver has stopped")),
+        };
+        async move {
+            queued?;
+            result_rx.await?
+        }
+        .boxed()
+    }
+}
+
+fn resolve_seed_record(seed_addr: &[u8]) -> Result<HashMap<PeerId, Vec<Multiaddr>>> {
+    let seed_record = std::str::from_utf8(seed_addr)?;
+    let mut resolved_peers = HashMap::new();
+    for entry in seed_record
+        .split(',')
+        .map(str::trim)
+        .filter(|e| !e.is_empty())
+    {
+        let mut parts = entry.splitn(2, '@');
+        let (peer_id, host) = match (parts.next(), parts.next()) {
+            (Some(peer_id), Some(host)) if !peer_id.is_empty() => (peer_id, host),
+            _ => bail!("Malformed seed record entry: {}", entry),
+        };
+        let peer_id = PeerId::from_str(peer_id)?;
+        let addrs = host
+            .to_socket_addrs()
+            .map_err(|e| format_err!("Failed to resolve {}: {}", host, e))?
+            .map(|addr| socket_addr_to_multiaddr(&addr))
+            .collect::<Result<Vec<_>>>()?;
+        resolved_peers
+            .entry(peer_id)
+            .or_insert_with(Vec::new)
+            .extend(addrs);
+    }
+    Ok(resolved_peers)
+}
+
+fn socket_addr_to_multiaddr(addr: &SocketAddr) -> Result<Multiaddr> {
+    let multiaddr = match addr {
+        SocketAddr::V4(addr) => format!("/ip4/{}/tcp/{}", addr.ip(), addr.port()),
+        SocketAddr::V6(addr) => format!("/ip6/{}/tcp/{}", addr.ip(), addr.port()),
+    };
+    Ok(multiaddr.parse()?)
+}
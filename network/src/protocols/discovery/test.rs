@@ -7,6 +7,7 @@ use core::str::FromStr;
 use crypto::{test_utils::TEST_SEED, *};
 use memsocket::MemorySocket;
 use rand::{rngs::StdRng, SeedableRng};
+use std::sync::atomic::{AtomicUsize, Ordering};
 use tokio::runtime::Runtime;
 
 fn gen_peer_info() -> PeerInfo {
@@ -28,6 +29,27 @@ fn gen_full_node_payload() -> FullNodePayload {
     payload
 }
 
+// Resolves seed records from an in-process table, and counts the resolutions.
+struct FakeSeedResolver {
+    records: HashMap<Vec<u8>, HashMap<PeerId, Vec<Multiaddr>>>,
+    num_resolutions: Arc<AtomicUsize>,
+}
+
+impl SeedResolver for FakeSeedResolver {
+    fn resolve(
+        &self,
+        seed_addr: &[u8],
+    ) -> BoxFuture<'static, Result<HashMap<PeerId, Vec<Multiaddr>>, failure::Error>> {
+        self.num_resolutions.fetch_add(1, Ordering::SeqCst);
+        let resolution = self
+            .records
+            .get(seed_addr)
+            .cloned()
+            .ok_or_else(|| format_err!("Unknown seed record"));
+        futures::future::ready(resolution).boxed()
+    }
+}
+
 fn get_addrs_from_note(note: &Note) -> Vec<Multiaddr> {
     let signed_peer_info = note.signed_peer_info.as_ref().unwrap();
     let peer_info = PeerInfo::decode(&signed_peer_info.peer_info).unwrap();
@@ -54,6 +76,8 @@ fn setup_discovery(
     seed_peer_info: PeerInfo,
     signer: Signer,
     trusted_peers: Arc<RwLock<HashMap<PeerId, NetworkPublicKeys>>>,
+    is_permissioned: bool,
+    seed_resolver: Option<Box<dyn SeedResolver>>,
 ) -> (
     channel::Receiver<PeerManagerRequest<MemorySocket>>,
     channel::Receiver<ConnectivityRequest>,
@@ -68,9 +92,12 @@ fn setup_discovery(
         Discovery::new(
             peer_id,
             addrs,
+            b"example.com".to_vec(),
             signer,
             vec![(seed_peer_id, seed_peer_info)].into_iter().collect(),
             trusted_peers,
+            is_permissioned,
+            seed_resolver,
             ticker_rx,
             PeerManagerRequestSender::new(peer_mgr_reqs_tx),
             peer_mgr_notifs_rx,
@@ -103,6 +130,34 @@ async fn expect_address_update(
     }
 }
 
+async fn send_notes(
+    peer_mgr_notifs_tx: &mut channel::Sender<PeerManagerNotification<MemorySocket>>,
+    sender: PeerId,
+    notes: Vec<Note>,
+) {
+    let (dialer_substream, listener_substream) = MemorySocket::new_pair();
+    // Notify discovery actor of inbound substream.
+    peer_mgr_notifs_tx
+        .send(PeerManagerNotification::NewInboundSubstream(
+            sender,
+            NegotiatedSubstream {
+                protocol: ProtocolId::from_static(DISCOVERY_PROTOCOL_NAME),
+                substream: listener_substream,
+            },
+        ))
+        .await
+        .unwrap();
+    // Wrap dialer substream in a framed substream.
+    let mut dialer_substream =
+        Framed::new(IoCompat::new(dialer_substream), LengthDelimitedCodec::new());
+    let mut msg = DiscoveryMsg::default();
+    msg.notes = notes;
+    dialer_substream
+        .send(msg.to_bytes().unwrap())
+        .await
+        .unwrap();
+}
+
 fn generate_network_pub_keys_and_signer(peer_id: PeerId) -> (NetworkPublicKeys, Signer) {
     let mut rng = StdRng::from_seed(TEST_SEED);
     let (signing_priv_key, _) = compat::generate_keypair(&mut rng);
@@ -148,6 +203,8 @@ fn inbound() {
         seed_peer_info.clone(),
         self_signer,
         trusted_peers.clone(),
+        true, /* is_permissioned */
+        None,
     );
 
     // Fake connectivity manager and dialer.
@@ -304,6 +361,8 @@ fn outbound() {
             seed_peer_info.clone(),
             self_signer,
             trusted_peers.clone(),
+            true, /* is_permissioned */
+            None,
         );
 
     // Fake connectivity manager and dialer.
@@ -375,6 +434,8 @@ fn addr_update_includes_seed_addrs() {
         seed_peer_info,
         self_signer,
         trusted_peers,
+        true, /* is_permissioned */
+        None,
     );
 
     // Fake connectivity manager and dialer.
@@ -427,3 +488,280 @@ fn addr_update_includes_seed_addrs() {
     };
     rt.block_on(f_peer_mgr);
 }
+
+#[test]
+// Test that the signing keys of the peers are bound to their peer ids in a permissionless network.
+fn permissionless_binds_signing_keys() {
+    ::libra_logger::try_init_for_testing();
+    let mut rt = Runtime::new().unwrap();
+
+    // Setup self and seed. There are no trusted peers.
+    let peer_id = PeerId::random();
+    let addrs = vec![Multiaddr::from_str("/ip4/127.0.0.1/tcp/9090").unwrap()];
+    let (_, self_signer) = generate_network_pub_keys_and_signer(peer_id);
+    let seed_peer_id = PeerId::random();
+    let seed_peer_info = gen_peer_info();
+    let seed_peer_addrs = get_addrs_from_info(&seed_peer_info);
+    let (_, seed_signer) = generate_network_pub_keys_and_signer(seed_peer_id);
+    let (_, mut conn_mgr_reqs_rx, mut peer_mgr_notifs_tx, _) = setup_discovery(
+        &mut rt,
+        peer_id,
+        addrs,
+        seed_peer_id,
+        seed_peer_info.clone(),
+        self_signer,
+        Arc::new(RwLock::new(HashMap::new())),
+        false, /* is_permissioned */
+        None,
+    );
+
+    // The other peer and a forger using a different signing key for the same peer id.
+    let peer_id_other = PeerId::random();
+    let signer_other = Signer::new(
+        peer_id_other,
+        compat::generate_keypair(&mut StdRng::from_seed([1u8; 32])).0,
+    );
+    let forger = Signer::new(
+        peer_id_other,
+        compat::generate_keypair(&mut StdRng::from_seed([2u8; 32])).0,
+    );
+    let note_other = |signer: &Signer, epoch: u64, addr: &str| {
+        let mut peer_info = PeerInfo::default();
+        peer_info.epoch = epoch;
+        peer_info.addrs = vec![Multiaddr::from_str(addr).unwrap().as_ref().into()];
+        create_note(signer, peer_id_other, peer_info, gen_full_node_payload())
+    };
+
+    let f_peer_mgr = async move {
+        expect_address_update(&mut conn_mgr_reqs_rx, seed_peer_id, &seed_peer_addrs[..]).await;
+
+        // The seed peer relays a note of the other peer, whose key is not bound yet: only the
+        // note of the seed peer itself is accepted.
+        let mut expected_seed_addrs = seed_peer_addrs.clone();
+        expected_seed_addrs.extend_from_slice(&seed_peer_addrs[..]);
+        send_notes(
+            &mut peer_mgr_notifs_tx,
+            seed_peer_id,
+            vec![
+                note_other(&forger, 1, "/ip4/10.0.0.1/tcp/8080"),
+                create_note(
+                    &seed_signer,
+                    seed_peer_id,
+                    seed_peer_info,
+                    gen_full_node_payload(),
+                ),
+            ],
+        )
+        .await;
+        expect_address_update(
+            &mut conn_mgr_reqs_rx,
+            seed_peer_id,
+            &expected_seed_addrs[..],
+        )
+        .await;
+
+        // The note sent by the other peer itself, authenticated by its connection, binds its key.
+        send_notes(
+            &mut peer_mgr_notifs_tx,
+            peer_id_other,
+            vec![note_other(&signer_other, 2, "/ip4/172.29.52.192/tcp/8080")],
+        )
+        .await;
+        expect_address_update(
+            &mut conn_mgr_reqs_rx,
+            peer_id_other,
+            &[Multiaddr::from_str("/ip4/172.29.52.192/tcp/8080").unwrap()],
+        )
+        .await;
+
+        // From then on, the relayed notes signed with the bound key are accepted, unlike the ones
+        // signed with another key.
+        send_notes(
+            &mut peer_mgr_notifs_tx,
+            seed_peer_id,
+            vec![
+                note_other(&forger, 4, "/ip4/10.0.0.1/tcp/8080"),
+                note_other(&signer_other, 3, "/ip4/172.29.52.193/tcp/8080"),
+            ],
+        )
+        .await;
+        expect_address_update(
+            &mut conn_mgr_reqs_rx,
+            peer_id_other,
+            &[Multiaddr::from_str("/ip4/172.29.52.193/tcp/8080").unwrap()],
+        )
+        .await;
+
+        // The peer itself can rotate its key.
+        send_notes(
+            &mut peer_mgr_notifs_tx,
+            peer_id_other,
+            vec![note_other(&forger, 5, "/ip4/172.29.52.194/tcp/8080")],
+        )
+        .await;
+        expect_address_update(
+            &mut conn_mgr_reqs_rx,
+            peer_id_other,
+            &[Multiaddr::from_str("/ip4/172.29.52.194/tcp/8080").unwrap()],
+        )
+        .await;
+    };
+    rt.block_on(f_peer_mgr);
+}
+
+#[test]
+// Test that a full node resolves the seed records published by validators.
+fn resolves_seed_records() {
+    ::libra_logger::try_init_for_testing();
+    let mut rt = Runtime::new().unwrap();
+
+    // Setup a full node knowing a validator as seed peer.
+    let peer_id = PeerId::random();
+    let addrs = vec![Multiaddr::from_str("/ip4/127.0.0.1/tcp/9090").unwrap()];
+    let (_, self_signer) = generate_network_pub_keys_and_signer(peer_id);
+    let seed_peer_id = PeerId::random();
+    let seed_peer_info = gen_peer_info();
+    let seed_peer_addrs = get_addrs_from_info(&seed_peer_info);
+    let (seed_pub_keys, seed_signer) = generate_network_pub_keys_and_signer(seed_peer_id);
+    let trusted_peers = Arc::new(RwLock::new(
+        vec![(seed_peer_id, seed_pub_keys)].into_iter().collect(),
+    ));
+
+    // The seed record lists 2 full nodes, one of which is the validator itself.
+    let full_node_id = PeerId::random();
+    let full_node_addrs = vec![Multiaddr::from_str("/ip4/172.29.52.195/tcp/8080").unwrap()];
+    let seed_record = vec![
+        (full_node_id, full_node_addrs.clone()),
+        (
+            seed_peer_id,
+            vec![Multiaddr::from_str("/ip4/10.0.0.1/tcp/8080").unwrap()],
+        ),
+    ]
+    .into_iter()
+    .collect();
+    let num_resolutions = Arc::new(AtomicUsize::new(0));
+    let seed_resolver = FakeSeedResolver {
+        records: vec![(b"example.com".to_vec(), seed_record)]
+            .into_iter()
+            .collect(),
+        num_resolutions: num_resolutions.clone(),
+    };
+    let (_, mut conn_mgr_reqs_rx, mut peer_mgr_notifs_tx, _) = setup_discovery(
+        &mut rt,
+        peer_id,
+        addrs,
+        seed_peer_id,
+        seed_peer_info.clone(),
+        self_signer,
+        trusted_peers,
+        false, /* is_permissioned */
+        Some(Box::new(seed_resolver)),
+    );
+
+    let f_peer_mgr = async move {
+        expect_address_update(&mut conn_mgr_reqs_rx, seed_peer_id, &seed_peer_addrs[..]).await;
+
+        // The validator sends its note, publishing the seed record in its FullNodePayload.
+        let mut seed_peer_info = seed_peer_info;
+        let mut expected_seed_addrs = seed_peer_addrs.clone();
+        expected_seed_addrs.extend_from_slice(&seed_peer_addrs[..]);
+        send_notes(
+            &mut peer_mgr_notifs_tx,
+            seed_peer_id,
+            vec![create_note(
+                &seed_signer,
+                seed_peer_id,
+                seed_peer_info.clone(),
+                gen_full_node_payload(),
+            )],
+        )
+        .await;
+        expect_address_update(
+            &mut conn_mgr_reqs_rx,
+            seed_peer_id,
+            &expected_seed_addrs[..],
+        )
+        .await;
+        assert_eq!(num_resolutions.load(Ordering::SeqCst), 1);
+
+        // The full node listed by the seed record is passed to the connectivity manager, unlike
+        // the validator whose note is already known.
+        expect_address_update(&mut conn_mgr_reqs_rx, full_node_id, &full_node_addrs[..]).await;
+
+        // A newer note with the same FullNodePayload does not trigger a new resolution.
+        seed_peer_info.epoch = 2;
+        send_notes(
+            &mut peer_mgr_notifs_tx,
+            seed_peer_id,
+            vec![create_note(
+                &seed_signer,
+                seed_peer_id,
+                seed_peer_info,
+                gen_full_node_payload(),
+            )],
+        )
+        .await;
+        expect_address_update(
+            &mut conn_mgr_reqs_rx,
+            seed_peer_id,
+            &expected_seed_addrs[..],
+        )
+        .await;
+        assert_eq!(num_resolutions.load(Ordering::SeqCst), 1);
+
+        // The seed record published by a peer that is not trusted is not resolved.
+        let untrusted_peer_id = PeerId::random();
+        let (_, untrusted_signer) = generate_network_pub_keys_and_signer(untrusted_peer_id);
+        let untrusted_peer_info = gen_peer_info();
+        send_notes(
+            &mut peer_mgr_notifs_tx,
+            untrusted_peer_id,
+            vec![create_note(
+                &untrusted_signer,
+                untrusted_peer_id,
+                untrusted_peer_info.clone(),
+                gen_full_node_payload(),
+            )],
+        )
+        .await;
+        expect_address_update(
+            &mut conn_mgr_reqs_rx,
+            untrusted_peer_id,
+            &get_addrs_from_info(&untrusted_peer_info)[..],
+        )
+        .await;
+        assert_eq!(num_resolutions.load(Ordering::SeqCst), 1);
+    };
+    rt.block_on(f_peer_mgr);
+}
+
+#[test]
+fn test_dns_seed_resolver() {
+    let mut rt = Runtime::new().unwrap();
+    let resolver = DnsSeedResolver::new();
+    let (peer_a, peer_b) = (PeerId::random(), PeerId::random());
+    let seed_record = format!(
+        "{:x}@127.0.0.1:8080, {:x}@[::1]:9090,{:x}@10.0.0.1:8080",
+        peer_a, peer_b, peer_a
+    );
+    let resolved = rt
+        .block_on(resolver.resolve(seed_record.as_bytes()))
+        .unwrap();
+    assert_eq!(resolved.len(), 2);
+    assert_eq!(
+        resolved[&peer_a],
+        vec![
+            Multiaddr::from_str("/ip4/127.0.0.1/tcp/8080").unwrap(),
+            Multiaddr::from_str("/ip4/10.0.0.1/tcp/8080").unwrap(),
+        ]
+    );
+    assert_eq!(
+        resolved[&peer_b],
+        vec![Multiaddr::from_str("/ip6/::1/tcp/9090").unwrap()]
+    );
+
+    // The entries without a peer id or a port are rejected.
+    assert!(rt.block_on(resolver.resolve(b"127.0.0.1:8080")).is_err());
+    let no_port = format!("{:x}@127.0.0.1", peer_a);
+    assert!(rt.block_on(resolver.resolve(no_port.as_bytes())).is_err());
+}
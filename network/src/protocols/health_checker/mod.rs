@@ -20,6 +20,7 @@
 use crate::{
     error::NetworkError,
     peer_manager::{PeerManagerNotification, PeerManagerRequestSender},
+    peer_metrics::PeerMetrics,
     proto::{Ping, Pong},
     utils::{read_proto, MessageExt},
     ProtocolId,
@@ -35,7 +36,11 @@ use libra_logger::prelude::*;
 use libra_types::PeerId;
 use netcore::compat::IoCompat;
 use rand::{rngs::SmallRng, seq::SliceRandom, FromEntropy};
-use std::{collections::HashMap, fmt::Debug, time::Duration};
+use std::{
+    collections::HashMap,
+    fmt::Debug,
+    time::{Duration, Instant},
+};
 use tokio::{
     codec::{Framed, LengthDelimitedCodec},
     future::FutureExt as _,
@@ -69,6 +74,8 @@ pub struct HealthChecker<TTicker, TSubstream> {
     ping_failures_tolerated: u64,
     /// Counter incremented in each round of health checks
     round: u64,
+    /// Metrics of the connected peers, which record the round-trip time of the pings.
+    peer_metrics: PeerMetrics,
 }
 
 impl<TTicker, TSubstream> HealthChecker<TTicker, TSubstream>
@@ -83,6 +90,7 @@ where
         peer_mgr_notifs_rx: channel::Receiver<PeerManagerNotification<TSubstream>>,
         ping_timeout: Duration,
         ping_failures_tolerated: u64,
+        peer_metrics: PeerMetrics,
     ) -> Self {
         HealthChecker {
             ticker,
@@ -93,6 +101,7 @@ where
             ping_timeout,
             ping_failures_tolerated,
             round: 0,
+            peer_metrics,
         }
     }
 
@@ -126,7 +135,8 @@ where
                                     peer_id,
                                     self.round,
                                     self.peer_mgr_reqs_tx.clone(),
-                                    self.ping_timeout.clone()));
+                                    self.ping_timeout.clone(),
+                                    self.peer_metrics.clone()));
                         }
                         None => {
                             debug!("No connected peer to ping");
@@ -208,6 +218,7 @@ where
         round: u64,
         mut peer_mgr_reqs_tx: PeerManagerRequestSender<TSubstream>,
         ping_timeout: Duration,
+        peer_metrics: PeerMetrics,
     ) -> (PeerId, u64, Result<(), NetworkError>) {
         let ping_result = async move {
             // Request a new substream to peer.
@@ -222,6 +233,7 @@ where
             let mut substream = Framed::new(IoCompat::new(substream), LengthDelimitedCodec::new());
             // Send Ping.
             debug!("Sending Ping to peer: {}", peer_id.short_str());
+            let start = Instant::now();
             substream
                 .send(
                     Ping::default()
@@ -232,6 +244,7 @@ where
             // Read Pong.
             debug!("Waiting for Pong from peer: {}", peer_id.short_str());
             let _: Pong = read_proto(&mut substream).await?;
+            peer_metrics.record_ping_rtt(peer_id, start.elapsed());
             // Return success.
             Ok(())
         };
@@ -27,6 +27,7 @@ fn setup_permissive_health_checker(
         peer_mgr_notifs_rx,
         PING_TIMEOUT,
         ping_failures_tolerated,
+        PeerMetrics::default(),
     );
     rt.spawn(health_checker.start());
     (peer_mgr_reqs_rx, peer_mgr_notifs_tx, ticker_tx)
@@ -48,6 +49,7 @@ fn setup_default_health_checker(
         peer_mgr_notifs_rx,
         PING_TIMEOUT,
         0,
+        PeerMetrics::default(),
     );
     rt.spawn(health_checker.start());
     (peer_mgr_reqs_rx, peer_mgr_notifs_tx, ticker_tx)
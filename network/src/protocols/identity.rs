@@ -3,8 +3,8 @@
 
 //! Protocol used to identify key information about a remote
 //!
-//! Currently, the information shared as part of this protocol includes the peer identity and a
-//! list of protocols supported by the peer.
+//! Currently, the information shared as part of this protocol includes the peer identity, a
+//! list of protocols supported by the peer, and the protocols whose messages the peer compresses.
 use crate::{
     proto::{IdentityMsg, IdentityMsg_Role},
     utils::MessageExt,
@@ -31,6 +31,7 @@ pub struct Identity {
     peer_id: PeerId,
     role: RoleType,
     supported_protocols: Vec<ProtocolId>,
+    compressed_protocols: Vec<ProtocolId>,
 }
 
 impl Identity {
@@ -39,9 +40,17 @@ impl Identity {
             peer_id,
             role,
             supported_protocols,
+            compressed_protocols: vec![],
         }
     }
 
+    /// Set the protocols whose messages are compressed, if the remote end-point compresses them
+    /// too.
+    pub fn with_compressed_protocols(mut self, compressed_protocols: Vec<ProtocolId>) -> Self {
+        self.compressed_protocols = compressed_protocols;
+        self
+    }
+
     pub fn peer_id(&self) -> PeerId {
         self.peer_id
     }
@@ -59,6 +68,16 @@ impl Identity {
     pub fn supported_protocols(&self) -> &[ProtocolId] {
         &self.supported_protocols
     }
+
+    pub fn is_compression_supported(&self, protocol: &ProtocolId) -> bool {
+        self.compressed_protocols
+            .iter()
+            .any(|proto| proto == protocol)
+    }
+
+    pub fn compressed_protocols(&self) -> &[ProtocolId] {
+        &self.compressed_protocols
+    }
 }
 
 /// The Identity exchange protocol
@@ -102,6 +121,11 @@ where
         .iter()
         .map(|proto_id| proto_id.to_vec())
         .collect();
+    msg.compressed_protocols = own_identity
+        .compressed_protocols()
+        .iter()
+        .map(|proto_id| proto_id.to_vec())
+        .collect();
     msg.peer_id = own_identity.peer_id().into();
     msg.set_role(if own_identity.role() == RoleType::Validator {
         IdentityMsg_Role::Validator
@@ -140,7 +164,13 @@ where
         .into_iter()
         .map(Into::into)
         .collect();
-    let identity = Identity::new(peer_id, supported_protocols, role);
+    let compressed_protocols = response
+        .compressed_protocols
+        .into_iter()
+        .map(Into::into)
+        .collect();
+    let identity = Identity::new(peer_id, supported_protocols, role)
+        .with_compressed_protocols(compressed_protocols);
     Ok((identity, connection))
 }
 
@@ -187,7 +217,8 @@ mod tests {
                 ProtocolId::from_static(b"/proto/3.0.0"),
             ],
             RoleType::Validator,
-        );
+        )
+        .with_compressed_protocols(vec![ProtocolId::from_static(b"/proto/3.0.0")]);
         let server_identity_config = server_identity.clone();
         let client_identity_config = client_identity.clone();
 
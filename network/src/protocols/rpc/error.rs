@@ -33,6 +33,9 @@ pub enum RpcError {
     #[fail(display = "Received unexpected rpc request message; expected remote to half-close.")]
     UnexpectedRpcRequest,
 
+    #[fail(display = "Received malformed streaming rpc frame")]
+    InvalidStreamingRpcFrame,
+
     #[fail(display = "Application layer unexpectedly dropped response channel")]
     UnexpectedResponseChannelCancel,
 
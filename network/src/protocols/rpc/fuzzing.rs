@@ -3,7 +3,9 @@
 
 use crate::{
     common::NegotiatedSubstream,
+    compression::Compression,
     peer_manager::PeerManagerNotification,
+    peer_metrics::PeerMetrics,
     protocols::rpc::{self, RpcNotification},
     ProtocolId,
 };
@@ -76,9 +78,14 @@ pub fn fuzzer(data: &[u8]) {
         PeerManagerNotification::NewInboundSubstream(MOCK_PEER_ID, listener_substream);
 
     // run the rpc inbound protocol using the in-memory substream
-    let f_handle_inbound =
-        rpc::handle_inbound_substream(notification_tx, peer_mgr_notif, INBOUND_RPC_TIMEOUT)
-            .map(|_| io::Result::Ok(()));
+    let f_handle_inbound = rpc::handle_inbound_substream(
+        notification_tx,
+        peer_mgr_notif,
+        INBOUND_RPC_TIMEOUT,
+        Compression::default(),
+        PeerMetrics::default(),
+    )
+    .map(|_| io::Result::Ok(()));
 
     // mock the notification channel to echo the fuzzer data back to the dialer
     // as an rpc response
@@ -94,6 +101,7 @@ pub fn fuzzer(data: &[u8]) {
                     assert_eq!(protocol.as_ref(), MOCK_PROTOCOL_ID);
                     res_tx.send(Ok(data)).unwrap();
                 }
+                notif => panic!("Unexpected RpcNotification: {:?}", notif),
             }
         }
 
@@ -51,13 +51,19 @@
 //! 6. Sends the serialized response message to the dialer.
 //! 7. Half-closes their output side to complete the substream close.
 //!
+//! Rpc methods whose responses are too large for a single message can instead be
+//! [`streaming`] methods, whose listener sends the response in a sequence of chunks.
+//!
 //! [muxers]: ../../../netcore/multiplexing/index.html
 //! [substream negotiation]: ../../../netcore/negotiate/index.html
 //! [`protocol-select`]: ../../../netcore/negotiate/index.html
 
 use crate::{
+    compression::{self, Compression},
     counters,
     peer_manager::{PeerManagerNotification, PeerManagerRequestSender},
+    peer_metrics::PeerMetrics,
+    priority_queue::PriorityReceiver,
     sink::NetworkSinkExt,
     ProtocolId,
 };
@@ -76,7 +82,14 @@ use futures::{
 use libra_logger::prelude::*;
 use libra_types::PeerId;
 use netcore::compat::IoCompat;
-use std::{fmt::Debug, io, time::Duration};
+use std::{
+    collections::HashSet,
+    fmt::Debug,
+    io,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+use streaming::{InboundStreamingRpcRequest, OutboundStreamingRpcRequest};
 use tokio::{
     codec::{Framed, LengthDelimitedCodec},
     future::FutureExt as _,
@@ -84,6 +97,7 @@ use tokio::{
 };
 
 pub mod error;
+pub mod streaming;
 pub mod utils;
 
 #[cfg(test)]
@@ -145,6 +159,8 @@ pub struct OutboundRpcRequest {
 pub enum RpcRequest {
     /// Send an outbound rpc request to a remote peer.
     SendRpc(PeerId, OutboundRpcRequest),
+    /// Send an outbound streaming rpc request to a remote peer.
+    SendStreamingRpc(PeerId, OutboundStreamingRpcRequest),
 }
 
 /// Events sent from the [`Rpc`] actor to the
@@ -153,14 +169,16 @@ pub enum RpcRequest {
 pub enum RpcNotification {
     /// A new inbound rpc request has been received from a remote peer.
     RecvRpc(PeerId, InboundRpcRequest),
+    /// A new inbound streaming rpc request has been received from a remote peer.
+    RecvStreamingRpc(PeerId, InboundStreamingRpcRequest),
 }
 
 /// The rpc actor.
 pub struct Rpc<TSubstream> {
     /// Executor to spawn inbound and outbound handler tasks.
     executor: TaskExecutor,
-    /// Channel to receive requests from other upstream actors.
-    requests_rx: channel::Receiver<RpcRequest>,
+    /// Prioritized queues to receive requests from other upstream actors, one per protocol.
+    requests_rx: PriorityReceiver<RpcRequest>,
     /// Channel to receive notifications from [`PeerManager`](crate::peer_manager::PeerManager).
     peer_mgr_notifs_rx: channel::Receiver<PeerManagerNotification<TSubstream>>,
     /// Channel to send requests to [`PeerManager`](crate::peer_manager::PeerManager).
@@ -177,6 +195,12 @@ pub struct Rpc<TSubstream> {
     // TODO(philiphayes): partition inbound queue by peer to prevent one peer
     // from starving other peers' rpcs?
     max_concurrent_inbound_rpcs: u32,
+    /// Protocols whose messages are compressed with each of the connected peers.
+    compression: Compression,
+    /// Metrics of the connected peers.
+    peer_metrics: PeerMetrics,
+    /// Rpc methods whose responses are streamed, see [`streaming`].
+    streaming_protocols: HashSet<ProtocolId>,
 }
 
 impl<TSubstream> Rpc<TSubstream>
@@ -186,13 +210,16 @@ where
     /// Create a new instance of the [`Rpc`] protocol actor.
     pub fn new(
         executor: TaskExecutor,
-        requests_rx: channel::Receiver<RpcRequest>,
+        requests_rx: PriorityReceiver<RpcRequest>,
         peer_mgr_notifs_rx: channel::Receiver<PeerManagerNotification<TSubstream>>,
         peer_mgr_reqs_tx: PeerManagerRequestSender<TSubstream>,
         rpc_handler_tx: channel::Sender<RpcNotification>,
         inbound_rpc_timeout: Duration,
         max_concurrent_outbound_rpcs: u32,
         max_concurrent_inbound_rpcs: u32,
+        compression: Compression,
+        peer_metrics: PeerMetrics,
+        streaming_protocols: HashSet<ProtocolId>,
     ) -> Self {
         Self {
             executor,
@@ -203,6 +230,9 @@ where
             inbound_rpc_timeout,
             max_concurrent_outbound_rpcs,
             max_concurrent_inbound_rpcs,
+            compression,
+            peer_metrics,
+            streaming_protocols,
         }
     }
 
@@ -217,6 +247,9 @@ where
         let inbound_rpc_timeout = self.inbound_rpc_timeout;
         let max_concurrent_outbound_rpcs = self.max_concurrent_outbound_rpcs;
         let max_concurrent_inbound_rpcs = self.max_concurrent_inbound_rpcs;
+        let compression = self.compression;
+        let peer_metrics = self.peer_metrics;
+        let streaming_protocols = Arc::new(self.streaming_protocols);
 
         // inbound and outbound requests use separate bounded executors to ensure
         // backpressure propagates independently and doesn't starve the other
@@ -226,6 +259,8 @@ where
             BoundedExecutor::new(max_concurrent_outbound_rpcs as usize, executor.clone()),
             requests_rx,
             peer_mgr_reqs_tx,
+            compression.clone(),
+            peer_metrics.clone(),
         );
 
         let inbound_handler = handle_inbounds(
@@ -233,6 +268,9 @@ where
             peer_mgr_notifs_rx,
             rpc_handler_tx,
             inbound_rpc_timeout,
+            compression,
+            peer_metrics,
+            streaming_protocols,
         );
 
         // drive inbound and outbound handlers to completion
@@ -245,14 +283,21 @@ where
 /// Handle all outbound rpcs.
 async fn handle_outbounds<TSubstream>(
     executor: BoundedExecutor,
-    mut requests_rx: channel::Receiver<RpcRequest>,
+    mut requests_rx: PriorityReceiver<RpcRequest>,
     peer_mgr_tx: PeerManagerRequestSender<TSubstream>,
+    compression: Compression,
+    peer_metrics: PeerMetrics,
 ) where
     TSubstream: AsyncRead + AsyncWrite + Send + Unpin + 'static,
 {
     while let Some(req) = requests_rx.next().await {
         executor
-            .spawn(handle_outbound_rpc(peer_mgr_tx.clone(), req))
+            .spawn(handle_outbound_rpc(
+                peer_mgr_tx.clone(),
+                compression.clone(),
+                peer_metrics.clone(),
+                req,
+            ))
             .await;
     }
 }
@@ -263,17 +308,41 @@ async fn handle_inbounds<TSubstream>(
     mut peer_mgr_notifs_rx: channel::Receiver<PeerManagerNotification<TSubstream>>,
     rpc_handler_tx: channel::Sender<RpcNotification>,
     inbound_rpc_timeout: Duration,
+    compression: Compression,
+    peer_metrics: PeerMetrics,
+    streaming_protocols: Arc<HashSet<ProtocolId>>,
 ) where
     TSubstream: AsyncRead + AsyncWrite + Debug + Send + Unpin + 'static,
 {
     while let Some(notif) = peer_mgr_notifs_rx.next().await {
-        executor
-            .spawn(handle_inbound_substream(
-                rpc_handler_tx.clone(),
-                notif,
-                inbound_rpc_timeout,
-            ))
-            .await;
+        match notif {
+            PeerManagerNotification::NewInboundSubstream(peer_id, substream)
+                if streaming_protocols.contains(&substream.protocol) =>
+            {
+                executor
+                    .spawn(streaming::handle_inbound_streaming_substream(
+                        rpc_handler_tx.clone(),
+                        peer_id,
+                        substream.protocol,
+                        substream.substream,
+                        inbound_rpc_timeout,
+                        compression.clone(),
+                        peer_metrics.clone(),
+                    ))
+                    .await;
+            }
+            notif => {
+                executor
+                    .spawn(handle_inbound_substream(
+                        rpc_handler_tx.clone(),
+                        notif,
+                        inbound_rpc_timeout,
+                        compression.clone(),
+                        peer_metrics.clone(),
+                    ))
+                    .await;
+            }
+        }
     }
 }
 
@@ -289,6 +358,8 @@ async fn handle_inbounds<TSubstream>(
 /// [`req.res_tx`]: OutboundRpcRequest::res_tx
 async fn handle_outbound_rpc<TSubstream>(
     peer_mgr_tx: PeerManagerRequestSender<TSubstream>,
+    compression: Compression,
+    peer_metrics: PeerMetrics,
     req: RpcRequest,
 ) where
     TSubstream: AsyncRead + AsyncWrite + Send + Unpin,
@@ -299,18 +370,26 @@ async fn handle_outbound_rpc<TSubstream>(
             let req_data = req.data;
             let mut res_tx = req.res_tx;
             let timeout = req.timeout;
+            let compressed = compression.is_compressed(&peer_id, &protocol);
 
             // Future to run the actual outbound rpc protocol and get the results.
-            let mut f_rpc_res = handle_outbound_rpc_inner(peer_mgr_tx, peer_id, protocol, req_data)
-                .timeout(timeout)
-                .map_err(Into::<RpcError>::into)
-                .map(|r| match r {
-                    Ok(Ok(x)) => Ok(x),
-                    Ok(Err(e)) => Err(e),
-                    Err(e) => Err(e),
-                })
-                .boxed()
-                .fuse();
+            let mut f_rpc_res = handle_outbound_rpc_inner(
+                peer_mgr_tx,
+                peer_id,
+                protocol.clone(),
+                req_data,
+                compressed,
+                peer_metrics.clone(),
+            )
+            .timeout(timeout)
+            .map_err(Into::<RpcError>::into)
+            .map(|r| match r {
+                Ok(Ok(x)) => Ok(x),
+                Ok(Err(e)) => Err(e),
+                Err(e) => Err(e),
+            })
+            .boxed()
+            .fuse();
 
             // If the rpc client drops their oneshot receiver, this future should
             // cancel the request.
@@ -322,6 +401,7 @@ async fn handle_outbound_rpc<TSubstream>(
                     // Log any errors.
                     if let Err(err) = &res {
                         counters::RPC_REQUESTS_FAILED.inc();
+                        peer_metrics.record_rpc_error(peer_id, &protocol);
                         warn!(
                             "Error making outbound rpc request to {}: {:?}",
                             peer_id.short_str(), err
@@ -341,6 +421,16 @@ async fn handle_outbound_rpc<TSubstream>(
                 },
             }
         }
+        RpcRequest::SendStreamingRpc(peer_id, req) => {
+            streaming::handle_outbound_streaming_rpc(
+                peer_mgr_tx,
+                compression,
+                peer_metrics,
+                peer_id,
+                req,
+            )
+            .await;
+        }
     }
 }
 
@@ -349,27 +439,48 @@ async fn handle_outbound_rpc_inner<TSubstream>(
     peer_id: PeerId,
     protocol: ProtocolId,
     req_data: Bytes,
+    compressed: bool,
+    peer_metrics: PeerMetrics,
 ) -> Result<Bytes, RpcError>
 where
     TSubstream: AsyncRead + AsyncWrite + Send + Unpin,
 {
     let _timer = counters::RPC_LATENCY.start_timer();
+    let start = Instant::now();
     // Request a new substream with the peer.
-    let substream = peer_mgr_tx.open_substream(peer_id, protocol).await?;
+    let substream = peer_mgr_tx
+        .open_substream(peer_id, protocol.clone())
+        .await?;
+    let _substream_guard = peer_metrics.open_substream(peer_id);
     // Rpc messages are length-prefixed.
     let mut substream = Framed::new(IoCompat::new(substream), LengthDelimitedCodec::new());
     // Send the rpc request data.
     let req_len = req_data.len();
+    let req_data = if compressed {
+        compression::compress(&req_data)?
+    } else {
+        req_data
+    };
+    let req_wire_len = req_data.len();
     substream.buffered_send(req_data).await?;
     // We won't send anything else on this substream, so we can half-close our
     // output side.
     substream.close().await?;
     counters::RPC_REQUESTS_SENT.inc();
     counters::RPC_REQUEST_BYTES_SENT.inc_by(req_len as i64);
+    peer_metrics.record_sent(peer_id, &protocol, req_wire_len);
 
     // Wait for listener's response.
     let res_data = match substream.next().await {
-        Some(res_data) => res_data?.freeze(),
+        Some(res_data) => {
+            let res_data = res_data?;
+            peer_metrics.record_received(peer_id, &protocol, res_data.len());
+            if compressed {
+                compression::decompress(&res_data)?
+            } else {
+                res_data.freeze()
+            }
+        }
         None => return Err(io::Error::from(io::ErrorKind::UnexpectedEof).into()),
     };
 
@@ -378,7 +489,10 @@ where
         // Remote should never send more than one response; we'll consider this
         // a protocol violation and ignore their response.
         Some(_) => Err(RpcError::UnexpectedRpcResponse),
-        None => Ok(res_data),
+        None => {
+            peer_metrics.record_rpc_latency(peer_id, &protocol, start.elapsed());
+            Ok(res_data)
+        }
     }
 }
 
@@ -388,17 +502,23 @@ async fn handle_inbound_substream<TSubstream>(
     notification_tx: channel::Sender<RpcNotification>,
     notif: PeerManagerNotification<TSubstream>,
     timeout: Duration,
+    compression: Compression,
+    peer_metrics: PeerMetrics,
 ) where
     TSubstream: AsyncRead + AsyncWrite + Debug + Send + Unpin,
 {
     match notif {
         PeerManagerNotification::NewInboundSubstream(peer_id, substream) => {
+            let protocol = substream.protocol;
+            let compressed = compression.is_compressed(&peer_id, &protocol);
             // Run the actual inbound rpc protocol.
             let res = handle_inbound_substream_inner(
                 notification_tx,
                 peer_id,
-                substream.protocol,
+                protocol.clone(),
                 substream.substream,
+                compressed,
+                peer_metrics.clone(),
             )
             .timeout(timeout)
             .map_err(Into::<RpcError>::into)
@@ -412,6 +532,7 @@ async fn handle_inbound_substream<TSubstream>(
             // Log any errors.
             if let Err(err) = res {
                 counters::RPC_RESPONSES_FAILED.inc();
+                peer_metrics.record_rpc_error(peer_id, &protocol);
                 warn!(
                     "Error handling inbound rpc request from {}: {:?}",
                     peer_id.short_str(),
@@ -432,15 +553,26 @@ async fn handle_inbound_substream_inner<TSubstream>(
     peer_id: PeerId,
     protocol: ProtocolId,
     substream: TSubstream,
+    compressed: bool,
+    peer_metrics: PeerMetrics,
 ) -> Result<(), RpcError>
 where
     TSubstream: AsyncRead + AsyncWrite + Send + Unpin,
 {
+    let _substream_guard = peer_metrics.open_substream(peer_id);
     // Rpc messages are length-prefixed.
     let mut substream = Framed::new(IoCompat::new(substream), LengthDelimitedCodec::new());
     // Read the rpc request data.
     let req_data = match substream.next().await {
-        Some(req_data) => req_data?.freeze(),
+        Some(req_data) => {
+            let req_data = req_data?;
+            peer_metrics.record_received(peer_id, &protocol, req_data.len());
+            if compressed {
+                compression::decompress(&req_data)?
+            } else {
+                req_data.freeze()
+            }
+        }
         None => return Err(io::Error::from(io::ErrorKind::UnexpectedEof).into()),
     };
     counters::RPC_REQUESTS_RECEIVED.inc();
@@ -457,7 +589,7 @@ where
     let notification = RpcNotification::RecvRpc(
         peer_id,
         InboundRpcRequest {
-            protocol,
+            protocol: protocol.clone(),
             data: req_data,
             res_tx,
         },
@@ -469,8 +601,14 @@ where
     // Wait for response from upper layer.
     let res_data = res_rx.await??;
     let res_len = res_data.len();
+    let res_data = if compressed {
+        compression::compress(&res_data)?
+    } else {
+        res_data
+    };
 
     // Send the response to remote
+    let res_wire_len = res_data.len();
     substream.buffered_send(res_data).await?;
 
     // We won't send anything else on this substream, so we can half-close
@@ -479,6 +617,7 @@ where
     substream.close().await?;
     counters::RPC_RESPONSES_SENT.inc();
     counters::RPC_RESPONSE_BYTES_SENT.inc_by(res_len as i64);
+    peer_metrics.record_sent(peer_id, &protocol, res_wire_len);
 
     Ok(())
 }
@@ -0,0 +1,330 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Streaming variant of the rpc protocol, for responses too large to fit in a single message,
+//! e.g., a range of blocks or a chunk of transactions for state sync.
+//!
+//! Streaming rpc methods are negotiated like unary rpc methods, but the listener answers with a
+//! sequence of chunks followed by an end marker, all on the same substream. Each frame on the
+//! wire starts with a one byte tag: [`CHUNK_FRAME`] for a chunk of the response, followed by the
+//! (possibly compressed) chunk, and [`END_FRAME`] for the end marker, so that a dialer never
+//! mistakes a truncated response for a complete one.
+//!
+//! ## Wire Protocol (dialer):
+//!
+//! 1. Requests a new outbound substream from the muxer.
+//! 2. Negotiates the substream to the streaming rpc method it wishes to call.
+//! 3. Sends the serialized request arguments, and half-closes its output side.
+//! 4. Receives chunk frames until the end frame.
+//! 5. Awaits the listener's half-close to complete the substream close.
+//!
+//! ## Wire Protocol (listener):
+//!
+//! 1. Negotiates the inbound substream to a supported streaming rpc method.
+//! 2. Awaits the serialized request arguments and the dialer's half-close.
+//! 3. Sends the request up to the upper layer, along with a channel for the chunks of the
+//!    response.
+//! 4. Sends a chunk frame for each chunk, then the end frame once the upper layer drops the
+//!    channel.
+//! 5. Half-closes its output side to complete the substream close.
+//!
+//! ## Flow control, timeouts and cancellation
+//!
+//! The chunks go through channels of [`CHUNK_QUEUE_SIZE`] chunks on both ends, and the muxer only
+//! lets the listener write up to the receive window of the substream, so a slow dialer makes the
+//! upper layer of the listener wait to send the next chunk instead of buffering the response.
+//!
+//! Timeouts apply to each chunk rather than to the whole call: the dialer fails the call if it
+//! waits for the next chunk for longer than the `chunk_timeout` of the request, and the listener
+//! aborts the response if its upper layer takes longer than the inbound rpc timeout to produce
+//! the next chunk, or if the dialer does not read it in time. Aborting drops the substream
+//! without an end marker, and so does the dialer when its client drops the receiver of the
+//! chunks, which cancels the call.
+use super::{error::RpcError, RpcNotification};
+use crate::{
+    compression::{self, Compression},
+    counters,
+    peer_manager::PeerManagerRequestSender,
+    peer_metrics::PeerMetrics,
+    sink::NetworkSinkExt,
+    ProtocolId,
+};
+use bytes::{BufMut, Bytes, BytesMut};
+use channel;
+use futures::{
+    channel::mpsc,
+    io::{AsyncRead, AsyncWrite},
+    sink::SinkExt,
+    stream::StreamExt,
+};
+use libra_logger::prelude::*;
+use libra_types::PeerId;
+use netcore::compat::IoCompat;
+use std::{io, time::Duration};
+use tokio::{
+    codec::{Framed, LengthDelimitedCodec},
+    future::FutureExt as _,
+};
+
+/// Number of chunks buffered between the rpc layer and the upper layer, on each end.
+pub const CHUNK_QUEUE_SIZE: usize = 8;
+
+/// Tag of the frames carrying a chunk of the response.
+pub const CHUNK_FRAME: u8 = 0;
+/// Tag of the frame marking the end of the response.
+pub const END_FRAME: u8 = 1;
+
+/// A wrapper struct for an outbound streaming rpc request and its associated context.
+#[derive(Debug)]
+pub struct OutboundStreamingRpcRequest {
+    /// Streaming rpc method identifier, e.g., `/libra/state-synchronizer/streaming-rpc/0.1.0`.
+    pub protocol: ProtocolId,
+    /// The serialized request data to be sent to the receiver.
+    pub data: Bytes,
+    /// Channel over which the chunks of the response are sent from the rpc layer to the upper
+    /// client layer. The channel is closed after the last chunk, or after an [`RpcError`] if the
+    /// call fails. The client cancels the call by dropping the receiver.
+    pub res_tx: mpsc::Sender<Result<Bytes, RpcError>>,
+    /// The maximum time to wait for each chunk of the response.
+    pub chunk_timeout: Duration,
+}
+
+/// A wrapper struct for an inbound streaming rpc request and its associated context.
+#[derive(Debug)]
+pub struct InboundStreamingRpcRequest {
+    /// Streaming rpc method identifier, used to dispatch the request to the corresponding
+    /// client handler.
+    pub protocol: ProtocolId,
+    /// The serialized request data received from the sender.
+    pub data: Bytes,
+    /// Channel over which the upper client layer sends the chunks of the response to the rpc
+    /// layer. Dropping the channel after the last chunk ends the response, while sending an
+    /// [`RpcError`] aborts it.
+    pub res_tx: mpsc::Sender<Result<Bytes, RpcError>>,
+}
+
+/// Handle an outbound streaming rpc request: open a new substream, then forward the chunks of
+/// the response to the rpc client.
+pub(super) async fn handle_outbound_streaming_rpc<TSubstream>(
+    peer_mgr_tx: PeerManagerRequestSender<TSubstream>,
+    compression: Compression,
+    peer_metrics: PeerMetrics,
+    peer_id: PeerId,
+    req: OutboundStreamingRpcRequest,
+) where
+    TSubstream: AsyncRead + AsyncWrite + Send + Unpin,
+{
+    let protocol = req.protocol;
+    let mut res_tx = req.res_tx;
+    let compressed = compression.is_compressed(&peer_id, &protocol);
+    let res = handle_outbound_streaming_rpc_inner(
+        peer_mgr_tx,
+        peer_id,
+        protocol.clone(),
+        req.data,
+        req.chunk_timeout,
+        compressed,
+        peer_metrics.clone(),
+        &mut res_tx,
+    )
+    .await;
+
+    match res {
+        Ok(()) => {}
+        // The rpc client dropped the receiver of the chunks.
+        Err(RpcError::MpscSendError(ref e)) if e.is_disconnected() => {
+            counters::RPC_REQUESTS_CANCELLED.inc();
+            debug!(
+                "Rpc client canceled outbound streaming rpc call to {}",
+                peer_id.short_str()
+            );
+        }
+        Err(err) => {
+            counters::RPC_REQUESTS_FAILED.inc();
+            peer_metrics.record_rpc_error(peer_id, &protocol);
+            warn!(
+                "Error making outbound streaming rpc request to {}: {:?}",
+                peer_id.short_str(),
+                err
+            );
+            // Propagate the error to the rpc client layer.
+            if res_tx.send(Err(err)).await.is_err() {
+                counters::RPC_REQUESTS_CANCELLED.inc();
+            }
+        }
+    }
+}
+
+async fn handle_outbound_streaming_rpc_inner<TSubstream>(
+    mut peer_mgr_tx: PeerManagerRequestSender<TSubstream>,
+    peer_id: PeerId,
+    protocol: ProtocolId,
+    req_data: Bytes,
+    chunk_timeout: Duration,
+    compressed: bool,
+    peer_metrics: PeerMetrics,
+    res_tx: &mut mpsc::Sender<Result<Bytes, RpcError>>,
+) -> Result<(), RpcError>
+where
+    TSubstream: AsyncRead + AsyncWrite + Send + Unpin,
+{
+    // Request a new substream with the peer.
+    let substream = peer_mgr_tx
+        .open_substream(peer_id, protocol.clone())
+        .timeout(chunk_timeout)
+        .await??;
+    let _substream_guard = peer_metrics.open_substream(peer_id);
+    let mut substream = Framed::new(IoCompat::new(substream), LengthDelimitedCodec::new());
+    // Send the rpc request data, then half-close our output side.
+    let req_len = req_data.len();
+    let req_data = if compressed {
+        compression::compress(&req_data)?
+    } else {
+        req_data
+    };
+    peer_metrics.record_sent(peer_id, &protocol, req_data.len());
+    substream.buffered_send(req_data).await?;
+    substream.close().await?;
+    counters::RPC_REQUESTS_SENT.inc();
+    counters::RPC_REQUEST_BYTES_SENT.inc_by(req_len as i64);
+
+    // Forward the chunks to the rpc client until the end frame.
+    loop {
+        let mut frame = match substream.next().timeout(chunk_timeout).await? {
+            Some(frame) => frame?,
+            None => return Err(io::Error::from(io::ErrorKind::UnexpectedEof).into()),
+        };
+        peer_metrics.record_received(peer_id, &protocol, frame.len());
+        match frame.first().cloned() {
+            Some(CHUNK_FRAME) => {
+                frame.advance(1);
+                let chunk = if compressed {
+                    compression::decompress(&frame)?
+                } else {
+                    frame.freeze()
+                };
+                res_tx.send(Ok(chunk)).await?;
+            }
+            Some(END_FRAME) if frame.len() == 1 => break,
+            _ => return Err(RpcError::InvalidStreamingRpcFrame),
+        }
+    }
+
+    // Wait for listener to half-close their side.
+    match substream.next().timeout(chunk_timeout).await? {
+        Some(_) => Err(RpcError::UnexpectedRpcResponse),
+        None => Ok(()),
+    }
+}
+
+/// Handle a new inbound substream of a streaming rpc method. Run the listener side of the
+/// streaming rpc protocol over the substream.
+pub(super) async fn handle_inbound_streaming_substream<TSubstream>(
+    notification_tx: channel::Sender<RpcNotification>,
+    peer_id: PeerId,
+    protocol: ProtocolId,
+    substream: TSubstream,
+    chunk_timeout: Duration,
+    compression: Compression,
+    peer_metrics: PeerMetrics,
+) where
+    TSubstream: AsyncRead + AsyncWrite + Send + Unpin,
+{
+    let compressed = compression.is_compressed(&peer_id, &protocol);
+    let res = handle_inbound_streaming_substream_inner(
+        notification_tx,
+        peer_id,
+        protocol.clone(),
+        substream,
+        chunk_timeout,
+        compressed,
+        peer_metrics.clone(),
+    )
+    .await;
+
+    // Log any errors.
+    if let Err(err) = res {
+        counters::RPC_RESPONSES_FAILED.inc();
+        peer_metrics.record_rpc_error(peer_id, &protocol);
+        warn!(
+            "Error handling inbound streaming rpc request from {}: {:?}",
+            peer_id.short_str(),
+            err
+        );
+    }
+}
+
+async fn handle_inbound_streaming_substream_inner<TSubstream>(
+    mut notification_tx: channel::Sender<RpcNotification>,
+    peer_id: PeerId,
+    protocol: ProtocolId,
+    substream: TSubstream,
+    chunk_timeout: Duration,
+    compressed: bool,
+    peer_metrics: PeerMetrics,
+) -> Result<(), RpcError>
+where
+    TSubstream: AsyncRead + AsyncWrite + Send + Unpin,
+{
+    let _substream_guard = peer_metrics.open_substream(peer_id);
+    let mut substream = Framed::new(IoCompat::new(substream), LengthDelimitedCodec::new());
+    // Read the rpc request data.
+    let req_data = match substream.next().timeout(chunk_timeout).await? {
+        Some(req_data) => {
+            let req_data = req_data?;
+            peer_metrics.record_received(peer_id, &protocol, req_data.len());
+            if compressed {
+                compression::decompress(&req_data)?
+            } else {
+                req_data.freeze()
+            }
+        }
+        None => return Err(io::Error::from(io::ErrorKind::UnexpectedEof).into()),
+    };
+    counters::RPC_REQUESTS_RECEIVED.inc();
+
+    // Wait for dialer to half-close their side.
+    if substream.next().timeout(chunk_timeout).await?.is_some() {
+        return Err(RpcError::UnexpectedRpcRequest);
+    };
+
+    // Forward request to upper layer, along with the channel for the chunks of the response.
+    let (res_tx, mut res_rx) = mpsc::channel(CHUNK_QUEUE_SIZE);
+    let notification = RpcNotification::RecvStreamingRpc(
+        peer_id,
+        InboundStreamingRpcRequest {
+            protocol: protocol.clone(),
+            data: req_data,
+            res_tx,
+        },
+    );
+    notification_tx.send(notification).await.unwrap();
+
+    // Send the chunks to remote until the upper layer drops the channel. Sending a chunk waits
+    // for the dialer to make room in the receive window of the substream.
+    while let Some(chunk) = res_rx.next().timeout(chunk_timeout).await? {
+        let chunk = chunk?;
+        let chunk = if compressed {
+            compression::compress(&chunk)?
+        } else {
+            chunk
+        };
+        let mut frame = BytesMut::with_capacity(1 + chunk.len());
+        frame.put_u8(CHUNK_FRAME);
+        frame.put_slice(&chunk);
+        peer_metrics.record_sent(peer_id, &protocol, frame.len());
+        substream
+            .send(frame.freeze())
+            .timeout(chunk_timeout)
+            .await??;
+    }
+    substream
+        .buffered_send(Bytes::from_static(&[END_FRAME]))
+        .await?;
+
+    // We won't send anything else on this substream, so we can half-close our output.
+    substream.close().await?;
+    counters::RPC_RESPONSES_SENT.inc();
+
+    Ok(())
+}
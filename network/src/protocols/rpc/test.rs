@@ -1,12 +1,21 @@
 // Copyright (c) The Libra Core Contributors
 // SPDX-License-Identifier: Apache-2.0
 
-use super::{error::RpcError, *};
+use super::{
+    error::RpcError,
+    streaming::{self, CHUNK_QUEUE_SIZE},
+    *,
+};
 use crate::{
     common::NegotiatedSubstream,
+    compression::Compression,
     peer_manager::{PeerManagerNotification, PeerManagerRequest},
+    peer_metrics::PeerMetrics,
+};
+use futures::{
+    channel::mpsc,
+    future::{join, join3, join4, Future},
 };
-use futures::future::{join, join3, join4};
 use memsocket::MemorySocket;
 use tokio::runtime::Runtime;
 
@@ -28,7 +37,13 @@ where
         timeout,
     };
     let rpc_req = RpcRequest::SendRpc(recipient, outbound_req);
-    handle_outbound_rpc(peer_mgr_tx, rpc_req).await;
+    handle_outbound_rpc(
+        peer_mgr_tx,
+        Compression::default(),
+        PeerMetrics::default(),
+        rpc_req,
+    )
+    .await;
     res_rx.await.unwrap()
 }
 
@@ -78,6 +93,7 @@ fn upgrades() {
                 assert_eq!(req.data.as_ref(), req_data);
                 req.res_tx.send(Ok(Bytes::from_static(res_data))).unwrap();
             }
+            notif => panic!("Unexpected RpcNotification: {:?}", notif),
         }
     };
 
@@ -92,6 +108,8 @@ fn upgrades() {
         listener_rpc_notifs_tx,
         inbound_notif,
         Duration::from_millis(500),
+        Compression::default(),
+        PeerMetrics::default(),
     );
 
     // Make an outbound substream request
@@ -119,6 +137,106 @@ fn upgrades() {
     Runtime::new().unwrap().block_on(f);
 }
 
+// Test an rpc call whose request and response are compressed.
+#[test]
+fn compressed_upgrades() {
+    ::libra_logger::try_init_for_testing();
+
+    let listener_peer_id = PeerId::random();
+    let dialer_peer_id = PeerId::random();
+    let protocol_id = b"/get_blocks/1.0.0";
+    let req_data = Bytes::from(vec![1u8; 10_000]);
+    let res_data = Bytes::from(vec![2u8; 100_000]);
+
+    let (dialer_substream, listener_substream) = MemorySocket::new_pair();
+    let dialer_peer_metrics = PeerMetrics::new();
+    dialer_peer_metrics.add_peer(listener_peer_id);
+    let listener_peer_metrics = PeerMetrics::new();
+    listener_peer_metrics.add_peer(dialer_peer_id);
+
+    // Fake the dialer NetworkProvider
+    let (dialer_peer_mgr_reqs_tx, dialer_peer_mgr_reqs_rx) = channel::new_test(8);
+    let dialer_peer_mgr_reqs_tx = PeerManagerRequestSender::new(dialer_peer_mgr_reqs_tx);
+    let f_dialer_peer_mgr = mock_peer_manager(dialer_peer_mgr_reqs_rx, dialer_substream);
+
+    // Fake the listener NetworkProvider
+    let (listener_rpc_notifs_tx, mut listener_rpc_notifs_rx) = channel::new_test(8);
+    let expected_req_data = req_data.clone();
+    let listener_res_data = res_data.clone();
+    let f_listener_network = async move {
+        // Handle the inbound rpc request
+        match listener_rpc_notifs_rx.next().await.unwrap() {
+            RpcNotification::RecvRpc(peer_id, req) => {
+                assert_eq!(peer_id, dialer_peer_id);
+                assert_eq!(req.data, expected_req_data);
+                req.res_tx.send(Ok(listener_res_data)).unwrap();
+            }
+            notif => panic!("Unexpected RpcNotification: {:?}", notif),
+        }
+    };
+
+    // Handle the inbound substream
+    let f_listener_upgrade = async move {
+        handle_inbound_substream_inner(
+            listener_rpc_notifs_tx,
+            dialer_peer_id,
+            ProtocolId::from_static(protocol_id),
+            listener_substream,
+            true,
+            listener_peer_metrics.clone(),
+        )
+        .await
+        .unwrap();
+    };
+
+    // Make an outbound rpc request
+    let f_dialer_upgrade = async move {
+        let data = handle_outbound_rpc_inner(
+            dialer_peer_mgr_reqs_tx,
+            listener_peer_id,
+            ProtocolId::from_static(protocol_id),
+            req_data,
+            true,
+            dialer_peer_metrics.clone(),
+        )
+        .await
+        .unwrap();
+        assert_eq!(data, res_data);
+    };
+
+    let f = join4(
+        f_dialer_peer_mgr,
+        f_dialer_upgrade,
+        f_listener_network,
+        f_listener_upgrade,
+    );
+    Runtime::new().unwrap().block_on(f);
+
+    // The peer metrics count the compressed bytes on the wire.
+    let protocol = ProtocolId::from_static(protocol_id);
+    let dialer_stats = dialer_peer_metrics.peer(&listener_peer_id).unwrap();
+    let dialer_protocol_stats = &dialer_stats.protocols[&protocol];
+    assert_eq!(dialer_stats.open_substreams, 0);
+    assert_eq!(dialer_protocol_stats.messages_sent, 1);
+    assert!(dialer_protocol_stats.bytes_sent < 10_000);
+    assert_eq!(dialer_protocol_stats.messages_received, 1);
+    assert!(dialer_protocol_stats.bytes_received < 100_000);
+    assert_eq!(dialer_protocol_stats.rpc_latency.count, 1);
+    assert_eq!(dialer_protocol_stats.rpc_errors, 0);
+    let listener_stats = listener_peer_metrics.peer(&dialer_peer_id).unwrap();
+    let listener_protocol_stats = &listener_stats.protocols[&protocol];
+    assert_eq!(listener_protocol_stats.messages_received, 1);
+    assert_eq!(
+        listener_protocol_stats.bytes_received,
+        dialer_protocol_stats.bytes_sent
+    );
+    assert_eq!(listener_protocol_stats.messages_sent, 1);
+    assert_eq!(
+        listener_protocol_stats.bytes_sent,
+        dialer_protocol_stats.bytes_received
+    );
+}
+
 // An outbound rpc request should fail if the listener drops the connection after
 // receiving the request.
 #[test]
@@ -244,6 +362,7 @@ fn dialer_close_before_listener_recv() {
             dialer_peer_id,
             ProtocolId::from_static(protocol_id),
             listener_substream,
+            false,
         )
         .await;
 
@@ -282,6 +401,7 @@ fn dialer_close_before_listener_send() {
                 assert_eq!(req.data.as_ref(), req_data);
                 req.res_tx.send(Ok(Bytes::from_static(res_data))).unwrap();
             }
+            notif => panic!("Unexpected RpcNotification: {:?}", notif),
         }
     };
 
@@ -293,6 +413,7 @@ fn dialer_close_before_listener_send() {
             dialer_peer_id,
             ProtocolId::from_static(protocol_id),
             listener_substream,
+            false,
         )
         .await;
 
@@ -341,6 +462,7 @@ fn dialer_sends_two_requests_err() {
             dialer_peer_id,
             ProtocolId::from_static(protocol_id),
             listener_substream,
+            false,
         )
         .await;
 
@@ -441,6 +563,8 @@ fn inbound_rpc_timeout() {
         listener_rpc_notifs_tx,
         inbound_notif,
         Duration::from_millis(100),
+        Compression::default(),
+        PeerMetrics::default(),
     );
 
     // The listener future should complete (with a timeout) despite the dialer
@@ -471,7 +595,12 @@ fn outbound_cancellation_before_send() {
         timeout: Duration::from_secs(1),
     };
     let rpc_req = RpcRequest::SendRpc(listener_peer_id, outbound_req);
-    let f_rpc = handle_outbound_rpc(dialer_peer_mgr_reqs_tx, rpc_req);
+    let f_rpc = handle_outbound_rpc(
+        dialer_peer_mgr_reqs_tx,
+        Compression::default(),
+        PeerMetrics::default(),
+        rpc_req,
+    );
 
     // drop res_rx to cancel the rpc request
     drop(res_rx);
@@ -519,8 +648,13 @@ fn outbound_cancellation_recv() {
             timeout: Duration::from_secs(1),
         };
         let rpc_req = RpcRequest::SendRpc(listener_peer_id, outbound_req);
-        let (f_rpc, f_rpc_done) =
-            handle_outbound_rpc(dialer_peer_mgr_reqs_tx, rpc_req).remote_handle();
+        let (f_rpc, f_rpc_done) = handle_outbound_rpc(
+            dialer_peer_mgr_reqs_tx,
+            Compression::default(),
+            PeerMetrics::default(),
+            rpc_req,
+        )
+        .remote_handle();
         executor.spawn(f_rpc);
 
         futures::select! {
@@ -597,13 +731,16 @@ fn rpc_protocol() {
     let (rpc_handler_tx, _) = channel::new_test(8);
     let dialer_rpc = Rpc::new(
         rt.executor(),
-        dialer_rpc_rx,
+        dialer_rpc_rx.into(),
         dialer_peer_mgr_notifs_rx,
         dialer_peer_mgr_reqs_tx,
         rpc_handler_tx,
         Duration::from_millis(500),
         10,
         10,
+        Compression::default(),
+        PeerMetrics::default(),
+        HashSet::new(),
     );
 
     // Fake the dialer NetworkProvider
@@ -648,13 +785,16 @@ fn rpc_protocol() {
     let (listener_rpc_notifs_tx, mut listener_rpc_notifs_rx) = channel::new_test(8);
     let listener_rpc = Rpc::new(
         rt.executor(),
-        listener_rpc_reqs_rx,
+        listener_rpc_reqs_rx.into(),
         listener_peer_mgr_notifs_rx,
         listener_peer_mgr_reqs_tx,
         listener_rpc_notifs_tx,
         Duration::from_millis(500),
         10,
         10,
+        Compression::default(),
+        PeerMetrics::default(),
+        HashSet::new(),
     );
 
     // Fake the listener NetworkProvider
@@ -680,6 +820,7 @@ fn rpc_protocol() {
                 assert_eq!(req.data.as_ref(), req_data);
                 req.res_tx.send(Ok(Bytes::from_static(res_data))).unwrap();
             }
+            notif => panic!("Unexpected RpcNotification: {:?}", notif),
         }
     };
 
@@ -691,3 +832,138 @@ fn rpc_protocol() {
     );
     rt.block_on(f);
 }
+
+// Run a streaming rpc call from the dialer to the listener, where the given handler serves the
+// inbound request on the listener side. Return the chunks received by the dialer.
+fn do_streaming_rpc<F, Fut>(chunk_timeout: Duration, handler: F) -> Vec<Result<Bytes, RpcError>>
+where
+    F: FnOnce(InboundStreamingRpcRequest) -> Fut,
+    Fut: Future<Output = ()>,
+{
+    ::libra_logger::try_init_for_testing();
+
+    let listener_peer_id = PeerId::random();
+    let dialer_peer_id = PeerId::random();
+    let protocol_id = b"/get_blocks/streaming/1.0.0";
+    let req_data = b"hello";
+
+    let (dialer_substream, listener_substream) = MemorySocket::new_pair();
+
+    // Fake the dialer NetworkProvider
+    let (dialer_peer_mgr_reqs_tx, dialer_peer_mgr_reqs_rx) = channel::new_test(8);
+    let dialer_peer_mgr_reqs_tx = PeerManagerRequestSender::new(dialer_peer_mgr_reqs_tx);
+    let f_dialer_peer_mgr = mock_peer_manager(dialer_peer_mgr_reqs_rx, dialer_substream);
+
+    // Make the outbound streaming rpc call and collect the chunks of the response
+    let (res_tx, res_rx) = mpsc::channel(CHUNK_QUEUE_SIZE);
+    let req = OutboundStreamingRpcRequest {
+        protocol: ProtocolId::from_static(protocol_id),
+        data: Bytes::from_static(req_data),
+        res_tx,
+        chunk_timeout,
+    };
+    let f_dialer_upgrade = streaming::handle_outbound_streaming_rpc(
+        dialer_peer_mgr_reqs_tx,
+        Compression::default(),
+        PeerMetrics::default(),
+        listener_peer_id,
+        req,
+    );
+    let f_dialer_client = res_rx.collect::<Vec<_>>();
+
+    // Run the listener side of the protocol over the inbound substream
+    let (listener_rpc_notifs_tx, mut listener_rpc_notifs_rx) = channel::new_test(8);
+    let f_listener_upgrade = streaming::handle_inbound_streaming_substream(
+        listener_rpc_notifs_tx,
+        dialer_peer_id,
+        ProtocolId::from_static(protocol_id),
+        listener_substream,
+        chunk_timeout,
+        Compression::default(),
+        PeerMetrics::default(),
+    );
+
+    // Fake the listener NetworkProvider
+    let f_listener_network = async move {
+        match listener_rpc_notifs_rx.next().await.unwrap() {
+            RpcNotification::RecvStreamingRpc(peer_id, req) => {
+                assert_eq!(peer_id, dialer_peer_id);
+                assert_eq!(req.protocol.as_ref(), protocol_id);
+                assert_eq!(req.data.as_ref(), req_data);
+                handler(req).await;
+            }
+            notif => panic!("Unexpected RpcNotification: {:?}", notif),
+        }
+    };
+
+    let f = async move {
+        let (_, _, chunks) = join3(
+            join(f_dialer_peer_mgr, f_dialer_upgrade),
+            join(f_listener_upgrade, f_listener_network),
+            f_dialer_client,
+        )
+        .await;
+        chunks
+    };
+    Runtime::new().unwrap().block_on(f)
+}
+
+// Test that the chunks of a streaming rpc response reach the dialer in order.
+#[test]
+fn streaming_rpc() {
+    let chunks = do_streaming_rpc(Duration::from_secs(1), |mut req| async move {
+        for chunk in &[&b"foo"[..], b"bar", b"baz"] {
+            req.res_tx.send(Ok(Bytes::from(*chunk))).await.unwrap();
+        }
+    });
+
+    let chunks = chunks
+        .into_iter()
+        .map(|chunk| chunk.unwrap())
+        .collect::<Vec<_>>();
+    assert_eq!(chunks, vec!["foo", "bar", "baz"]);
+}
+
+// Test that aborting a streaming rpc response on the listener fails the stream on the dialer.
+#[test]
+fn streaming_rpc_abort() {
+    let chunks = do_streaming_rpc(Duration::from_secs(1), |mut req| async move {
+        req.res_tx
+            .send(Ok(Bytes::from_static(b"foo")))
+            .await
+            .unwrap();
+        req.res_tx
+            .send(Err(RpcError::ApplicationError(failure::format_err!(
+                "abort"
+            ))))
+            .await
+            .unwrap();
+    });
+
+    assert_eq!(chunks.len(), 2);
+    assert_eq!(chunks[0].as_ref().unwrap().as_ref(), b"foo");
+    assert!(chunks[1].is_err());
+}
+
+// Test that a streaming rpc call times out when the listener stalls between chunks.
+#[test]
+fn streaming_rpc_chunk_timeout() {
+    let chunk_timeout = Duration::from_millis(100);
+    let chunks = do_streaming_rpc(chunk_timeout, |mut req| {
+        async move {
+            req.res_tx
+                .send(Ok(Bytes::from_static(b"foo")))
+                .await
+                .unwrap();
+            // Stall for longer than the chunk timeout before ending the response.
+            tokio::timer::delay_for(chunk_timeout * 3).await;
+        }
+    });
+
+    assert_eq!(chunks.len(), 2);
+    assert_eq!(chunks[0].as_ref().unwrap().as_ref(), b"foo");
+    match chunks[1] {
+        Err(RpcError::TimedOut) => {}
+        ref res => panic!("Unexpected result: {:?}, expected TimedOut", res),
+    }
+}
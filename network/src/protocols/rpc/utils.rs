@@ -1,10 +1,17 @@
 use crate::{
     interface::NetworkRequest,
-    protocols::rpc::{error::RpcError, OutboundRpcRequest},
+    protocols::rpc::{
+        error::RpcError,
+        streaming::{OutboundStreamingRpcRequest, CHUNK_QUEUE_SIZE},
+        OutboundRpcRequest,
+    },
     utils::MessageExt,
     ProtocolId,
 };
-use futures::{channel::oneshot, SinkExt};
+use futures::{
+    channel::{mpsc, oneshot},
+    SinkExt, Stream, StreamExt,
+};
 use libra_types::PeerId;
 use std::time::Duration;
 
@@ -36,3 +43,34 @@ pub async fn unary_rpc<T: prost::Message + Default>(
     let res_msg = T::decode(res_data.as_ref())?;
     Ok(res_msg)
 }
+
+/// Send a streaming rpc request to remote peer `recipient`. Returns the stream of the chunks of
+/// the response, each deserialized into the same message type as the request. The stream ends
+/// after the last chunk, or after the first error.
+pub async fn streaming_rpc<T: prost::Message + Default>(
+    mut inner: channel::Sender<NetworkRequest>,
+    recipient: PeerId,
+    protocol: ProtocolId,
+    req_msg: T,
+    chunk_timeout: Duration,
+) -> Result<impl Stream<Item = Result<T, RpcError>>, RpcError> {
+    // serialize request
+    let req_data = req_msg.to_bytes()?;
+
+    // ask network to fulfill streaming rpc request
+    let (res_tx, res_rx) = mpsc::channel(CHUNK_QUEUE_SIZE);
+    let req = OutboundStreamingRpcRequest {
+        protocol,
+        data: req_data,
+        res_tx,
+        chunk_timeout,
+    };
+    inner
+        .send(NetworkRequest::SendStreamingRpc(recipient, req))
+        .await?;
+    // deserialize the chunks as they arrive
+    Ok(res_rx.map(|chunk| {
+        let chunk = chunk?;
+        Ok(T::decode(chunk.as_ref())?)
+    }))
+}
@@ -3,17 +3,15 @@
 
 use crate::{
     common::NetworkPublicKeys,
+    key_rotation::RotatedKeys,
     protocols::identity::{exchange_identity, Identity},
 };
-use crypto::{
-    x25519::{X25519StaticPrivateKey, X25519StaticPublicKey},
-    ValidKey,
-};
+use crypto::ValidKey;
 use libra_logger::prelude::*;
 use libra_types::PeerId;
 use netcore::{
     multiplexing::{yamux::Yamux, StreamMultiplexer},
-    transport::{boxed, memory, tcp, TransportExt},
+    transport::{boxed, memory, tcp, udp, TransportExt},
 };
 use noise::NoiseConfig;
 use std::{
@@ -27,8 +25,11 @@ use std::{
 /// A timeout for the connection to open and complete all of the upgrade steps.
 const TRANSPORT_TIMEOUT: Duration = Duration::from_secs(30);
 
+// Finds the trusted peer with the given identity key. The keys replaced by a recent update of the
+// trusted peers are still accepted during their grace period.
 fn identity_key_to_peer_id(
     trusted_peers: &RwLock<HashMap<PeerId, NetworkPublicKeys>>,
+    rotated_keys: &RwLock<RotatedKeys>,
     remote_static_key: &[u8],
 ) -> Option<PeerId> {
     for (peer_id, public_keys) in trusted_peers.read().unwrap().iter() {
@@ -37,7 +38,7 @@ fn identity_key_to_peer_id(
         }
     }
 
-    None
+    rotated_keys.read().unwrap().peer_id(remote_static_key)
 }
 
 // Ensures that peer id in received identity is same as peer id derived from noise handshake.
@@ -80,18 +81,20 @@ fn check_role(own_identity: &Identity, other_identity: Identity) -> Result<Ident
 
 pub fn build_memory_noise_transport(
     own_identity: Identity,
-    identity_keypair: (X25519StaticPrivateKey, X25519StaticPublicKey),
+    noise_config: Arc<NoiseConfig>,
     trusted_peers: Arc<RwLock<HashMap<PeerId, NetworkPublicKeys>>>,
+    rotated_keys: Arc<RwLock<RotatedKeys>>,
 ) -> boxed::BoxedTransport<(Identity, impl StreamMultiplexer), impl ::std::error::Error> {
     let memory_transport = memory::MemoryTransport::default();
-    let noise_config = Arc::new(NoiseConfig::new(identity_keypair));
 
     memory_transport
         .and_then(move |socket, origin| {
             async move {
                 let (remote_static_key, socket) =
                     noise_config.upgrade_connection(socket, origin).await?;
-                if let Some(peer_id) = identity_key_to_peer_id(&trusted_peers, &remote_static_key) {
+                if let Some(peer_id) =
+                    identity_key_to_peer_id(&trusted_peers, &rotated_keys, &remote_static_key)
+                {
                     Ok((peer_id, socket))
                 } else {
                     Err(io::Error::new(io::ErrorKind::Other, "Not a trusted peer"))
@@ -118,10 +121,9 @@ pub fn build_memory_noise_transport(
 
 pub fn build_permissionless_memory_noise_transport(
     own_identity: Identity,
-    identity_keypair: (X25519StaticPrivateKey, X25519StaticPublicKey),
+    noise_config: Arc<NoiseConfig>,
 ) -> boxed::BoxedTransport<(Identity, impl StreamMultiplexer), impl ::std::error::Error> {
     let memory_transport = memory::MemoryTransport::default();
-    let noise_config = Arc::new(NoiseConfig::new(identity_keypair));
     memory_transport
         .and_then(move |socket, origin| {
             async move {
@@ -181,18 +183,20 @@ pub fn build_memory_transport(
 //TODO(bmwill) Maybe create an Either Transport so we can merge the building of Memory + Tcp
 pub fn build_tcp_noise_transport(
     own_identity: Identity,
-    identity_keypair: (X25519StaticPrivateKey, X25519StaticPublicKey),
+    noise_config: Arc<NoiseConfig>,
     trusted_peers: Arc<RwLock<HashMap<PeerId, NetworkPublicKeys>>>,
+    rotated_keys: Arc<RwLock<RotatedKeys>>,
 ) -> boxed::BoxedTransport<(Identity, impl StreamMultiplexer), impl ::std::error::Error> {
     let tcp_transport = tcp::TcpTransport::default();
-    let noise_config = Arc::new(NoiseConfig::new(identity_keypair));
 
     tcp_transport
         .and_then(move |socket, origin| {
             async move {
                 let (remote_static_key, socket) =
                     noise_config.upgrade_connection(socket, origin).await?;
-                if let Some(peer_id) = identity_key_to_peer_id(&trusted_peers, &remote_static_key) {
+                if let Some(peer_id) =
+                    identity_key_to_peer_id(&trusted_peers, &rotated_keys, &remote_static_key)
+                {
                     Ok((peer_id, socket))
                 } else {
                     security_log(SecurityEvent::InvalidNetworkPeer)
@@ -225,10 +229,9 @@ pub fn build_tcp_noise_transport(
 // Transport based on TCP + Noise, but permissionless -- i.e., any node is allowed to connect.
 pub fn build_permissionless_tcp_noise_transport(
     own_identity: Identity,
-    identity_keypair: (X25519StaticPrivateKey, X25519StaticPublicKey),
+    noise_config: Arc<NoiseConfig>,
 ) -> boxed::BoxedTransport<(Identity, impl StreamMultiplexer), impl ::std::error::Error> {
     let tcp_transport = tcp::TcpTransport::default();
-    let noise_config = Arc::new(NoiseConfig::new(identity_keypair));
     tcp_transport
         .and_then(move |socket, origin| {
             async move {
@@ -284,3 +287,44 @@ pub fn build_tcp_transport(
         .with_timeout(TRANSPORT_TIMEOUT)
         .boxed()
 }
+
+// Transport based on UDP + Noise. The UDP connections multiplex their substreams by themselves, and
+// the noise session protects every datagram instead of a byte stream.
+pub fn build_udp_noise_transport(
+    own_identity: Identity,
+    noise_config: Arc<NoiseConfig>,
+    trusted_peers: Arc<RwLock<HashMap<PeerId, NetworkPublicKeys>>>,
+    rotated_keys: Arc<RwLock<RotatedKeys>>,
+) -> boxed::BoxedTransport<(Identity, impl StreamMultiplexer), impl ::std::error::Error> {
+    let udp_transport = udp::UdpTransport::default();
+
+    udp_transport
+        .and_then(move |connection, origin| {
+            async move {
+                let (remote_static_key, muxer) =
+                    noise_config.upgrade_udp_connection(connection, origin).await?;
+                if let Some(peer_id) =
+                    identity_key_to_peer_id(&trusted_peers, &rotated_keys, &remote_static_key)
+                {
+                    Ok((peer_id, muxer))
+                } else {
+                    security_log(SecurityEvent::InvalidNetworkPeer)
+                        .error("UntrustedPeer")
+                        .data(&trusted_peers)
+                        .data(&remote_static_key)
+                        .log();
+                    Err(io::Error::new(io::ErrorKind::Other, "Not a trusted peer"))
+                }
+            }
+        })
+        .and_then(move |(peer_id, muxer), origin| {
+            async move {
+                let (identity, muxer) = exchange_identity(&own_identity, muxer, origin).await?;
+                match_peer_id(identity, peer_id)
+                    .and_then(|identity| check_role(&own_identity, identity))
+                    .and_then(|identity| Ok((identity, muxer)))
+            }
+        })
+        .with_timeout(TRANSPORT_TIMEOUT)
+        .boxed()
+}
@@ -6,6 +6,7 @@
 use crate::{
     error::NetworkError,
     interface::{NetworkNotification, NetworkRequest},
+    peer_reputation::Misbehavior,
     protocols::rpc::{self, error::RpcError},
     validator_network::Event,
     ProtocolId,
@@ -18,7 +19,7 @@ use channel;
 use futures::{
     stream::Map,
     task::{Context, Poll},
-    Stream, StreamExt,
+    SinkExt, Stream, StreamExt,
 };
 use libra_types::PeerId;
 use pin_project::pin_project;
@@ -52,6 +53,14 @@ impl AdmissionControlNetworkEvents {
                 let req_msg = AdmissionControlMsg::decode(rpc_req.data.as_ref())?;
                 Ok(Event::RpcRequest((peer_id, req_msg, rpc_req.res_tx)))
             }
+            NetworkNotification::RecvStreamingRpc(peer_id, rpc_req) => {
+                let req_msg = AdmissionControlMsg::decode(rpc_req.data.as_ref())?;
+                Ok(Event::StreamingRpcRequest((
+                    peer_id,
+                    req_msg,
+                    rpc_req.res_tx,
+                )))
+            }
             NetworkNotification::RecvMessage(peer_id, msg) => {
                 let msg = AdmissionControlMsg::decode(msg.mdata.as_ref())?;
                 Ok(Event::Message((peer_id, msg)))
@@ -88,6 +97,19 @@ impl AdmissionControlNetworkSender {
         Self { inner }
     }
 
+    /// Report a misbehavior of the remote peer `peer_id`. The peer is disconnected and banned
+    /// once its accumulated penalty reaches the ban threshold.
+    pub async fn report_peer(
+        &mut self,
+        peer_id: PeerId,
+        misbehavior: Misbehavior,
+    ) -> Result<(), NetworkError> {
+        self.inner
+            .send(NetworkRequest::ReportPeer(peer_id, misbehavior))
+            .await?;
+        Ok(())
+    }
+
     /// Send a SubmitTransactionRequest RPC request to remote peer `recipient`. Returns the
     /// future `SubmitTransactionResponse` returned by the remote peer.
     ///
@@ -129,7 +151,7 @@ mod tests {
     use super::*;
     use crate::protocols::rpc::InboundRpcRequest;
     use crate::utils::MessageExt;
-    use futures::{channel::oneshot, executor::block_on, future::try_join, SinkExt};
+    use futures::{channel::oneshot, executor::block_on, future::try_join};
 
     // `AdmissionControlNetworkEvents` should deserialize inbound RPC requests
     #[test]
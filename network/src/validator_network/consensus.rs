@@ -6,7 +6,10 @@
 use crate::{
     error::NetworkError,
     interface::{NetworkNotification, NetworkRequest},
-    proto::{ConsensusMsg, ConsensusMsg_oneof, RequestBlock, RespondBlock},
+    peer_reputation::Misbehavior,
+    proto::{
+        ConsensusMsg, ConsensusMsg_oneof, RequestBatch, RequestBlock, RespondBatch, RespondBlock,
+    },
     protocols::{
         direct_send::Message,
         rpc::{self, error::RpcError},
@@ -26,9 +29,12 @@ use libra_types::{validator_public_keys::ValidatorPublicKeys, PeerId};
 use pin_project::pin_project;
 use prost::Message as _;
 use std::{pin::Pin, time::Duration};
+use tokio::future::FutureExt as _;
 
 /// Protocol id for consensus RPC calls
 pub const CONSENSUS_RPC_PROTOCOL: &[u8] = b"/libra/consensus/rpc/0.1.0";
+/// Protocol id for consensus streaming RPC calls
+pub const CONSENSUS_STREAMING_RPC_PROTOCOL: &[u8] = b"/libra/consensus/streaming-rpc/0.1.0";
 /// Protocol id for consensus direct-send calls
 pub const CONSENSUS_DIRECT_SEND_PROTOCOL: &[u8] = b"/libra/consensus/direct-send/0.1.0";
 
@@ -56,6 +62,14 @@ impl ConsensusNetworkEvents {
                 let req_msg = ConsensusMsg::decode(rpc_req.data.as_ref())?;
                 Ok(Event::RpcRequest((peer_id, req_msg, rpc_req.res_tx)))
             }
+            NetworkNotification::RecvStreamingRpc(peer_id, rpc_req) => {
+                let req_msg = ConsensusMsg::decode(rpc_req.data.as_ref())?;
+                Ok(Event::StreamingRpcRequest((
+                    peer_id,
+                    req_msg,
+                    rpc_req.res_tx,
+                )))
+            }
             NetworkNotification::RecvMessage(peer_id, msg) => {
                 let msg = ConsensusMsg::decode(msg.mdata.as_ref())?;
                 Ok(Event::Message((peer_id, msg)))
@@ -92,6 +106,19 @@ impl ConsensusNetworkSender {
         Self { inner }
     }
 
+    /// Report a misbehavior of the remote peer `peer_id`. The peer is disconnected and banned
+    /// once its accumulated penalty reaches the ban threshold.
+    pub async fn report_peer(
+        &mut self,
+        peer_id: PeerId,
+        misbehavior: Misbehavior,
+    ) -> Result<(), NetworkError> {
+        self.inner
+            .send(NetworkRequest::ReportPeer(peer_id, misbehavior))
+            .await?;
+        Ok(())
+    }
+
     /// Send a fire-and-forget "direct-send" message to remote peer `recipient`.
     ///
     /// Currently, the returned Future simply resolves when the message has been
@@ -123,8 +150,32 @@ impl ConsensusNetworkSender {
         Ok(())
     }
 
-    /// Send a RequestBlock RPC request to remote peer `recipient`. Returns the
-    /// future `RespondBlock` returned by the remote peer.
+    /// Send a streaming RPC request to remote peer `recipient`. Returns the stream of
+    /// the chunks of the response, which ends after the last chunk or after the first
+    /// error.
+    ///
+    /// The rpc request can be canceled at any point by dropping the returned stream.
+    pub async fn send_streaming_rpc(
+        &mut self,
+        recipient: PeerId,
+        req_msg: ConsensusMsg,
+        chunk_timeout: Duration,
+    ) -> Result<impl Stream<Item = Result<ConsensusMsg, RpcError>>, RpcError> {
+        let protocol = ProtocolId::from_static(CONSENSUS_STREAMING_RPC_PROTOCOL);
+        rpc::utils::streaming_rpc(
+            self.inner.clone(),
+            recipient,
+            protocol,
+            req_msg,
+            chunk_timeout,
+        )
+        .await
+    }
+
+    /// Send a RequestBlock streaming RPC request to remote peer `recipient`. The
+    /// blocks are streamed back in `RespondBlock` chunks, which must all arrive
+    /// within `timeout`, and are returned in a single `RespondBlock`. A response
+    /// with more blocks than requested is invalid.
     ///
     /// The rpc request can be canceled at any point by dropping the returned
     /// future.
@@ -134,10 +185,51 @@ impl ConsensusNetworkSender {
         req_msg: RequestBlock,
         timeout: Duration,
     ) -> Result<RespondBlock, RpcError> {
-        let protocol = ProtocolId::from_static(CONSENSUS_RPC_PROTOCOL);
+        let num_blocks = req_msg.num_blocks;
         let req_msg_enum = ConsensusMsg {
             message: Some(ConsensusMsg_oneof::RequestBlock(req_msg)),
         };
+        let f_response = async move {
+            let mut chunks = self
+                .send_streaming_rpc(recipient, req_msg_enum, timeout)
+                .await?;
+
+            let mut response: Option<RespondBlock> = None;
+            while let Some(chunk) = chunks.next().await {
+                match (chunk?.message, response.as_mut()) {
+                    (Some(ConsensusMsg_oneof::RespondBlock(chunk)), None) => response = Some(chunk),
+                    (Some(ConsensusMsg_oneof::RespondBlock(chunk)), Some(response))
+                        if chunk.status == response.status =>
+                    {
+                        response.blocks.extend(chunk.blocks)
+                    }
+                    _ => return Err(RpcError::InvalidRpcResponse),
+                }
+                let num_received = response.as_ref().map_or(0, |r| r.blocks.len());
+                if num_received as u64 > num_blocks {
+                    return Err(RpcError::InvalidRpcResponse);
+                }
+            }
+            response.ok_or(RpcError::InvalidRpcResponse)
+        };
+        f_response.timeout(timeout).await?
+    }
+
+    /// Send a RequestBatch RPC request to remote peer `recipient`. Returns the
+    /// future `RespondBatch` returned by the remote peer.
+    ///
+    /// The rpc request can be canceled at any point by dropping the returned
+    /// future.
+    pub async fn request_batch(
+        &mut self,
+        recipient: PeerId,
+        req_msg: RequestBatch,
+        timeout: Duration,
+    ) -> Result<RespondBatch, RpcError> {
+        let protocol = ProtocolId::from_static(CONSENSUS_RPC_PROTOCOL);
+        let req_msg_enum = ConsensusMsg {
+            message: Some(ConsensusMsg_oneof::RequestBatch(req_msg)),
+        };
         let res_msg_enum = rpc::utils::unary_rpc(
             self.inner.clone(),
             recipient,
@@ -147,10 +239,9 @@ impl ConsensusNetworkSender {
         )
         .await?;
 
-        if let Some(ConsensusMsg_oneof::RespondBlock(response)) = res_msg_enum.message {
+        if let Some(ConsensusMsg_oneof::RespondBatch(response)) = res_msg_enum.message {
             Ok(response)
         } else {
-            // TODO: context
             Err(RpcError::InvalidRpcResponse)
         }
     }
@@ -183,10 +274,11 @@ impl ConsensusNetworkSender {
 mod tests {
     use super::*;
     use crate::{
-        proto::{Vote, VoteData},
+        proto::{Block, Vote, VoteData},
         protocols::rpc::InboundRpcRequest,
     };
-    use futures::{channel::oneshot, executor::block_on, future::try_join};
+    use futures::{channel::oneshot, executor::block_on, future::join};
+    use tokio::runtime::Runtime;
 
     fn new_test_vote() -> ConsensusMsg {
         let vote_data = VoteData::default();
@@ -287,31 +379,44 @@ mod tests {
         assert_eq!(event, expected_event);
     }
 
-    // When consensus sends an rpc request, network should get a `NetworkRequest::SendRpc`
-    // with the serialized request.
-    #[test]
-    fn test_consensus_outbound_rpc() {
+    // Requests `num_blocks` blocks, to which the remote replies with `num_chunks` chunks of one
+    // block each, and returns the response put back together.
+    fn request_block_in_chunks(
+        num_blocks: u64,
+        num_chunks: usize,
+    ) -> (RespondBlock, Result<RespondBlock, RpcError>) {
         let (network_reqs_tx, mut network_reqs_rx) = channel::new_test(8);
         let mut sender = ConsensusNetworkSender::new(network_reqs_tx);
 
         // send get_block rpc request
         let peer_id = PeerId::random();
-        let req_msg = RequestBlock::default();
+        let mut req_msg = RequestBlock::default();
+        req_msg.num_blocks = num_blocks;
         let f_res_msg = sender.request_block(peer_id, req_msg.clone(), Duration::from_secs(5));
 
-        // build rpc response
-        let res_msg = RespondBlock::default();
-        let res_msg_enum = ConsensusMsg {
-            message: Some(ConsensusMsg_oneof::RespondBlock(res_msg.clone())),
-        };
-        let res_data = res_msg_enum.to_bytes().unwrap();
+        // build the response, in chunks
+        let mut res_msg = RespondBlock::default();
+        res_msg.blocks = vec![Block::default(); num_chunks];
+        let res_chunks: Vec<_> = res_msg
+            .blocks
+            .iter()
+            .map(|block| {
+                let mut chunk = res_msg.clone();
+                chunk.blocks = vec![block.clone()];
+                ConsensusMsg {
+                    message: Some(ConsensusMsg_oneof::RespondBlock(chunk)),
+                }
+                .to_bytes()
+                .unwrap()
+            })
+            .collect();
 
         // the future response
         let f_recv = async move {
             match network_reqs_rx.next().await.unwrap() {
-                NetworkRequest::SendRpc(recv_peer_id, req) => {
+                NetworkRequest::SendStreamingRpc(recv_peer_id, mut req) => {
                     assert_eq!(recv_peer_id, peer_id);
-                    assert_eq!(req.protocol.as_ref(), CONSENSUS_RPC_PROTOCOL);
+                    assert_eq!(req.protocol.as_ref(), CONSENSUS_STREAMING_RPC_PROTOCOL);
 
                     // check request deserializes
                     let req_msg_enum = ConsensusMsg::decode(req.data.as_ref()).unwrap();
@@ -320,15 +425,39 @@ mod tests {
                         Some(ConsensusMsg_oneof::RequestBlock(req_msg))
                     );
 
-                    // remote replies with some response message
-                    req.res_tx.send(Ok(res_data)).unwrap();
-                    Ok(())
+                    // remote replies with the chunks of the response, then ends it, unless the
+                    // requester has already given up
+                    for chunk in res_chunks {
+                        if req.res_tx.send(Ok(chunk)).await.is_err() {
+                            break;
+                        }
+                    }
                 }
                 event => panic!("Unexpected event: {:?}", event),
             }
         };
 
-        let (recv_res_msg, _) = block_on(try_join(f_res_msg, f_recv)).unwrap();
-        assert_eq!(recv_res_msg, res_msg);
+        // the request waits for the response with a timer
+        let runtime = Runtime::new().unwrap();
+        let (recv_res_msg, _) = runtime.block_on(join(f_res_msg, f_recv));
+        (res_msg, recv_res_msg)
+    }
+
+    // When consensus requests blocks, network should get a `NetworkRequest::SendStreamingRpc`
+    // with the serialized request, and the chunks of the response should be put back together.
+    #[test]
+    fn test_consensus_outbound_streaming_rpc() {
+        let (res_msg, recv_res_msg) = request_block_in_chunks(2, 2);
+        assert_eq!(recv_res_msg.unwrap(), res_msg);
+    }
+
+    // A response with more blocks than requested is rejected as soon as the extra block arrives.
+    #[test]
+    fn test_consensus_outbound_streaming_rpc_too_many_blocks() {
+        let (_, recv_res_msg) = request_block_in_chunks(2, 3);
+        match recv_res_msg {
+            Err(RpcError::InvalidRpcResponse) => (),
+            res => panic!("Unexpected response: {:?}", res),
+        }
     }
 }
@@ -6,10 +6,11 @@
 use crate::{
     error::NetworkError,
     interface::{NetworkNotification, NetworkRequest},
+    peer_reputation::Misbehavior,
     proto::MempoolSyncMsg,
     protocols::direct_send::Message,
     utils::MessageExt,
-    validator_network::Event,
+    validator_network::{drop_streaming_rpc, Event},
     ProtocolId,
 };
 use channel;
@@ -53,6 +54,9 @@ impl MempoolNetworkEvents {
                 NetworkNotification::RecvRpc(_, _) => {
                     unimplemented!("Mempool does not currently use RPC");
                 }
+                NetworkNotification::RecvStreamingRpc(peer_id, rpc_req) => {
+                    Err(drop_streaming_rpc("Mempool", peer_id, rpc_req))
+                }
                 NetworkNotification::RecvMessage(peer_id, msg) => {
                     let msg = MempoolSyncMsg::decode(msg.mdata.as_ref())?;
                     Ok(Event::Message((peer_id, msg)))
@@ -90,6 +94,19 @@ impl MempoolNetworkSender {
         Self { inner }
     }
 
+    /// Report a misbehavior of the remote peer `peer_id`. The peer is disconnected and banned
+    /// once its accumulated penalty reaches the ban threshold.
+    pub async fn report_peer(
+        &mut self,
+        peer_id: PeerId,
+        misbehavior: Misbehavior,
+    ) -> Result<(), NetworkError> {
+        self.inner
+            .send(NetworkRequest::ReportPeer(peer_id, misbehavior))
+            .await?;
+        Ok(())
+    }
+
     /// Send a fire-and-forget "direct-send" message to remote peer `recipient`.
     ///
     /// Currently, the returned Future simply resolves when the message has been
@@ -173,4 +190,23 @@ mod tests {
             event => panic!("Unexpected event: {:?}", event),
         }
     }
+
+    // `MempoolNetworkSender` should forward the reported misbehaviors
+    #[test]
+    fn test_mempool_network_sender_report_peer() {
+        let (network_reqs_tx, mut network_reqs_rx) = channel::new_test(8);
+        let mut sender = MempoolNetworkSender::new(network_reqs_tx);
+
+        let peer_id = PeerId::random();
+        block_on(sender.report_peer(peer_id, Misbehavior::Spam)).unwrap();
+
+        let event = block_on(network_reqs_rx.next()).unwrap();
+        match event {
+            NetworkRequest::ReportPeer(recv_peer_id, misbehavior) => {
+                assert_eq!(recv_peer_id, peer_id);
+                assert_eq!(misbehavior, Misbehavior::Spam);
+            }
+            event => panic!("Unexpected event: {:?}", event),
+        }
+    }
 }
@@ -4,8 +4,14 @@
 //! Network API for [`Consensus`](/consensus/index.html) and [`Mempool`](/mempool/index.html)
 
 pub use crate::protocols::rpc::error::RpcError;
+use crate::{
+    error::{NetworkError, NetworkErrorKind},
+    protocols::rpc::streaming::InboundStreamingRpcRequest,
+};
 use bytes::Bytes;
-use futures::channel::oneshot;
+use failure::err_msg;
+use futures::channel::{mpsc, oneshot};
+use libra_logger::prelude::*;
 
 pub mod network_builder;
 
@@ -23,7 +29,7 @@ pub use admission_control::{
 };
 pub use consensus::{
     ConsensusNetworkEvents, ConsensusNetworkSender, CONSENSUS_DIRECT_SEND_PROTOCOL,
-    CONSENSUS_RPC_PROTOCOL,
+    CONSENSUS_RPC_PROTOCOL, CONSENSUS_STREAMING_RPC_PROTOCOL,
 };
 use libra_types::PeerId;
 pub use mempool::{MempoolNetworkEvents, MempoolNetworkSender, MEMPOOL_DIRECT_SEND_PROTOCOL};
@@ -48,6 +54,9 @@ pub enum Event<TMessage> {
     /// serialized response `Bytes` over the `onshot::Sender`, where the network
     /// layer will handle sending the response over-the-wire.
     RpcRequest((PeerId, TMessage, oneshot::Sender<Result<Bytes, RpcError>>)),
+    /// New inbound streaming rpc request. The response is streamed back by sending its serialized
+    /// chunks over the `mpsc::Sender`, and ends when the sender is dropped.
+    StreamingRpcRequest((PeerId, TMessage, mpsc::Sender<Result<Bytes, RpcError>>)),
     /// Peer which we have a newly established connection with.
     NewPeer(PeerId),
     /// Peer with which we've lost our connection.
@@ -64,9 +73,32 @@ impl<TMessage: PartialEq> PartialEq for Event<TMessage> {
             (RpcRequest((pid1, msg1, _)), RpcRequest((pid2, msg2, _))) => {
                 pid1 == pid2 && msg1 == msg2
             }
+            // ignore mpsc::Sender in comparison
+            (StreamingRpcRequest((pid1, msg1, _)), StreamingRpcRequest((pid2, msg2, _))) => {
+                pid1 == pid2 && msg1 == msg2
+            }
             (NewPeer(pid1), NewPeer(pid2)) => pid1 == pid2,
             (LostPeer(pid1), LostPeer(pid2)) => pid1 == pid2,
             _ => false,
         }
     }
 }
+
+/// Drops a streaming rpc request sent to a client which does not use streaming rpc. The request
+/// is aborted rather than ended, so that the caller does not take it for an empty response.
+fn drop_streaming_rpc(
+    client: &str,
+    peer_id: PeerId,
+    mut rpc_req: InboundStreamingRpcRequest,
+) -> NetworkError {
+    warn!(
+        "{} does not use streaming RPC, dropping request from {}",
+        client,
+        peer_id.short_str()
+    );
+    let err = err_msg(format!("{} does not use streaming RPC", client));
+    let _ = rpc_req
+        .res_tx
+        .try_send(Err(RpcError::ApplicationError(err)));
+    NetworkErrorKind::UnsupportedStreamingRpc.into()
+}
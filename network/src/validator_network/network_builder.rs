@@ -9,21 +9,35 @@
 //! a network end-point running in permissioned mode will connect to or accept connections from
 //! an end-point running in permissionless mode as long as the latter is in its trusted peers
 //! set.
+//!
+//! A permissionless end-point (e.g. a public full node) discovers its peers through the gossip
+//! of self-certified discovery notes, bootstrapped from its seed peers. It dials the discovered
+//! peers until it is connected to a target number of them, and it limits the number of inbound
+//! connections from the same IP address or subnet.
 use crate::{
     common::NetworkPublicKeys,
-    connectivity_manager::ConnectivityManager,
+    compression::Compression,
+    connectivity_manager::{ConnectivityManager, ConnectivityPolicy},
     counters,
     interface::{LibraNetworkProvider, NetworkProvider},
-    peer_manager::{PeerManager, PeerManagerRequestSender},
+    key_rotation::RotatedKeys,
+    peer_manager::{InboundConnectionLimits, PeerManager, PeerManagerRequestSender},
+    peer_metrics::PeerMetrics,
+    peer_reputation::PeerReputation,
+    priority_queue::{self, ProtocolPriority},
     proto::PeerInfo,
     protocols::{
         direct_send::DirectSend,
-        discovery::{Discovery, DISCOVERY_PROTOCOL_NAME},
+        discovery::{Discovery, SeedResolver, DISCOVERY_PROTOCOL_NAME},
         health_checker::{HealthChecker, PING_PROTOCOL_NAME},
         identity::Identity,
         rpc::Rpc,
     },
     transport::*,
+    validator_network::{
+        CONSENSUS_DIRECT_SEND_PROTOCOL, CONSENSUS_RPC_PROTOCOL, CONSENSUS_STREAMING_RPC_PROTOCOL,
+        MEMPOOL_DIRECT_SEND_PROTOCOL, STATE_SYNCHRONIZER_MSG_PROTOCOL,
+    },
     ProtocolId,
 };
 use channel;
@@ -36,6 +50,7 @@ use futures::StreamExt;
 use libra_logger::prelude::*;
 use libra_types::{validator_signer::ValidatorSigner, PeerId};
 use netcore::{multiplexing::StreamMultiplexer, transport::boxed::BoxedTransport};
+use noise::NoiseConfig;
 use parity_multiaddr::Multiaddr;
 use std::{
     collections::HashMap,
@@ -59,8 +74,10 @@ pub const PING_FAILURES_TOLERATED: u64 = 10;
 pub const MAX_CONCURRENT_NETWORK_REQS: u32 = 100;
 pub const MAX_CONCURRENT_NETWORK_NOTIFS: u32 = 100;
 pub const MAX_CONNECTION_DELAY_MS: u64 = 10 * 60 * 1000 /* 10 minutes */;
+pub const TARGET_CONNECTIONS: usize = 8;
+pub const IDENTITY_KEY_GRACE_PERIOD_MS: u64 = 60 * 60 * 1000 /* 1 hour */;
 
-/// The type of the transport layer, i.e., running on memory or TCP stream,
+/// The type of the transport layer, i.e., running on memory, TCP stream or UDP datagrams,
 /// with or without Noise encryption
 pub enum TransportType {
     Memory,
@@ -69,6 +86,7 @@ pub enum TransportType {
     Tcp,
     TcpNoise(Option<(X25519StaticPrivateKey, X25519StaticPublicKey)>),
     PermissionlessTcpNoise(Option<(X25519StaticPrivateKey, X25519StaticPublicKey)>),
+    UdpNoise(Option<(X25519StaticPrivateKey, X25519StaticPublicKey)>),
 }
 
 /// Build Network module with custom configuration values.
@@ -79,15 +97,18 @@ pub enum TransportType {
 pub struct NetworkBuilder {
     executor: TaskExecutor,
     peer_id: PeerId,
-    addr: Multiaddr,
+    listen_addresses: Vec<Multiaddr>,
     role: RoleType,
-    advertised_address: Option<Multiaddr>,
+    advertised_addresses: Vec<Multiaddr>,
     seed_peers: HashMap<PeerId, PeerInfo>,
     trusted_peers: Arc<RwLock<HashMap<PeerId, NetworkPublicKeys>>>,
     transport: TransportType,
     channel_size: usize,
     direct_send_protocols: Vec<ProtocolId>,
     rpc_protocols: Vec<ProtocolId>,
+    streaming_rpc_protocols: Vec<ProtocolId>,
+    protocol_priorities: HashMap<ProtocolId, ProtocolPriority>,
+    compressed_protocols: Vec<ProtocolId>,
     discovery_interval_ms: u64,
     discovery_msg_timeout_ms: u64,
     ping_interval_ms: u64,
@@ -102,6 +123,13 @@ pub struct NetworkBuilder {
     max_connection_delay_ms: u64,
     signing_keys: Option<(Ed25519PrivateKey, Ed25519PublicKey)>,
     is_permissioned: bool,
+    target_connections: usize,
+    inbound_connection_limits: Option<InboundConnectionLimits>,
+    dns_seed_addr: String,
+    seed_resolver: Option<Box<dyn SeedResolver>>,
+    peer_reputation: Arc<RwLock<PeerReputation>>,
+    rotated_keys: Arc<RwLock<RotatedKeys>>,
+    noise_config: Option<Arc<NoiseConfig>>,
 }
 
 impl NetworkBuilder {
@@ -115,14 +143,17 @@ impl NetworkBuilder {
         NetworkBuilder {
             executor,
             peer_id,
-            addr,
+            listen_addresses: vec![addr],
             role,
-            advertised_address: None,
+            advertised_addresses: vec![],
             seed_peers: HashMap::new(),
             trusted_peers: Arc::new(RwLock::new(HashMap::new())),
             channel_size: NETWORK_CHANNEL_SIZE,
             direct_send_protocols: vec![],
             rpc_protocols: vec![],
+            streaming_rpc_protocols: vec![],
+            protocol_priorities: default_protocol_priorities(),
+            compressed_protocols: default_compressed_protocols(),
             transport: TransportType::Memory,
             discovery_interval_ms: DISCOVERY_INTERVAL_MS,
             discovery_msg_timeout_ms: DISOVERY_MSG_TIMEOUT_MS,
@@ -138,18 +169,40 @@ impl NetworkBuilder {
             max_connection_delay_ms: MAX_CONNECTION_DELAY_MS,
             signing_keys: None,
             is_permissioned: true,
+            target_connections: TARGET_CONNECTIONS,
+            inbound_connection_limits: None,
+            dns_seed_addr: String::new(),
+            seed_resolver: None,
+            peer_reputation: Arc::new(RwLock::new(PeerReputation::new())),
+            rotated_keys: Arc::new(RwLock::new(RotatedKeys::new(Duration::from_millis(
+                IDENTITY_KEY_GRACE_PERIOD_MS,
+            )))),
+            noise_config: None,
         }
     }
 
-    /// Set transport type, i.e., Memory or Tcp transports.
+    /// Set transport type, i.e., Memory, Tcp or Udp transports.
     pub fn transport(&mut self, transport: TransportType) -> &mut Self {
         self.transport = transport;
         self
     }
 
-    /// Set and address to advertise, if different from the listen address
-    pub fn advertised_address(&mut self, advertised_address: Multiaddr) -> &mut Self {
-        self.advertised_address = Some(advertised_address);
+    /// Set the addresses to listen on, e.g. both an IPv4 and an IPv6 address, replacing the one
+    /// given to [`NetworkBuilder::new`]. A listener is bound for each of them.
+    pub fn listen_addresses(&mut self, listen_addresses: Vec<Multiaddr>) -> &mut Self {
+        assert!(
+            !listen_addresses.is_empty(),
+            "At least one listen address is required"
+        );
+        self.listen_addresses = listen_addresses;
+        self
+    }
+
+    /// Set the addresses to advertise, by decreasing priority, if different from the listen
+    /// addresses. For instance, a node behind a NAT advertises both its public and its private
+    /// addresses.
+    pub fn advertised_addresses(&mut self, advertised_addresses: Vec<Multiaddr>) -> &mut Self {
+        self.advertised_addresses = advertised_addresses;
         self
     }
 
@@ -283,58 +336,184 @@ impl NetworkBuilder {
         self
     }
 
+    /// Set the protocol IDs that RPC actor subscribes and serves as streaming RPCs, whose
+    /// responses are sent back in chunks.
+    pub fn streaming_rpc_protocols(&mut self, protocols: Vec<ProtocolId>) -> &mut Self {
+        self.streaming_rpc_protocols = protocols;
+        self
+    }
+
+    /// Set the priority of the outbound messages of a protocol. Each protocol has its own queues,
+    /// and the queues of higher priority are drained first. By default, consensus has a high
+    /// priority, mempool a low one, and the other protocols a normal one.
+    pub fn protocol_priority(
+        &mut self,
+        protocol: ProtocolId,
+        priority: ProtocolPriority,
+    ) -> &mut Self {
+        self.protocol_priorities.insert(protocol, priority);
+        self
+    }
+
+    /// Set the protocols whose messages are compressed, with the peers which compress them too.
+    /// By default, the large mempool and state synchronizer messages are compressed.
+    pub fn compressed_protocols(&mut self, protocols: Vec<ProtocolId>) -> &mut Self {
+        self.compressed_protocols = protocols;
+        self
+    }
+
     /// Set the is_permissioned flag to make the network permissioned or permission-less.
     pub fn permissioned(&mut self, is_permissioned: bool) -> &mut Self {
         self.is_permissioned = is_permissioned;
         self
     }
 
+    /// Set the number of connected peers a permissionless end-point dials discovered peers for.
+    pub fn target_connections(&mut self, target_connections: usize) -> &mut Self {
+        self.target_connections = target_connections;
+        self
+    }
+
+    /// Set the limits on the inbound connections from the same IP address or subnet.
+    pub fn inbound_connection_limits(&mut self, limits: InboundConnectionLimits) -> &mut Self {
+        self.inbound_connection_limits = Some(limits);
+        self
+    }
+
+    /// Set the seed record, listing the public full nodes, which is published in the
+    /// `FullNodePayload` of the discovery note of this end-point.
+    pub fn dns_seed_addr(&mut self, dns_seed_addr: String) -> &mut Self {
+        self.dns_seed_addr = dns_seed_addr;
+        self
+    }
+
+    /// Set the resolver of the seed records published by the other peers. Full nodes use it to
+    /// find the public full nodes to connect to.
+    pub fn seed_resolver(&mut self, seed_resolver: Box<dyn SeedResolver>) -> &mut Self {
+        self.seed_resolver = Some(seed_resolver);
+        self
+    }
+
+    /// Set the reputation of the peers, e.g. one loaded from a ban list file.
+    pub fn peer_reputation(&mut self, peer_reputation: PeerReputation) -> &mut Self {
+        self.peer_reputation = Arc::new(RwLock::new(peer_reputation));
+        self
+    }
+
+    /// Set how long the previous identity key of a trusted peer is still accepted after an update
+    /// of the trusted peers replaces it.
+    pub fn identity_key_grace_period_ms(&mut self, grace_period_ms: u64) -> &mut Self {
+        self.rotated_keys = Arc::new(RwLock::new(RotatedKeys::new(Duration::from_millis(
+            grace_period_ms,
+        ))));
+        self
+    }
+
+    /// Return the Noise configuration of the built network, whose static key can be rotated
+    /// without restarting the node, or None if the transport does not use Noise. The key of a
+    /// permissionless network can't be rotated, as its peer id is derived from the static key.
+    pub fn noise_config(&self) -> Option<Arc<NoiseConfig>> {
+        self.noise_config.clone()
+    }
+
     fn supported_protocols(&self) -> Vec<ProtocolId> {
-        let mut supported_protocols: Vec<ProtocolId> = self
-            .direct_send_protocols
+        self.direct_send_protocols
             .iter()
             .chain(&self.rpc_protocols)
-            .chain(&vec![ProtocolId::from_static(PING_PROTOCOL_NAME)])
+            .chain(&self.streaming_rpc_protocols)
+            .chain(&vec![
+                ProtocolId::from_static(PING_PROTOCOL_NAME),
+                ProtocolId::from_static(DISCOVERY_PROTOCOL_NAME),
+            ])
             .cloned()
-            .collect();
-        // TODO: This check is performed at 2 places to modify how protocols are setup. Ideally we
-        // should do it at only 1 place.
-        if self.is_permissioned {
-            supported_protocols.push(ProtocolId::from_static(DISCOVERY_PROTOCOL_NAME));
-        }
-        supported_protocols
+            .collect()
+    }
+
+    /// Priority of each of the supported protocols.
+    fn supported_protocol_priorities(&self) -> HashMap<ProtocolId, ProtocolPriority> {
+        self.supported_protocols()
+            .into_iter()
+            .map(|protocol| {
+                let priority = self
+                    .protocol_priorities
+                    .get(&protocol)
+                    .cloned()
+                    .unwrap_or_default();
+                (protocol, priority)
+            })
+            .collect()
+    }
+
+    /// Compressed protocols among the supported ones.
+    fn supported_compressed_protocols(&self) -> Vec<ProtocolId> {
+        let supported_protocols = self.supported_protocols();
+        self.compressed_protocols
+            .iter()
+            .filter(|protocol| supported_protocols.contains(protocol))
+            .cloned()
+            .collect()
     }
 
     /// Create the configured `NetworkBuilder`
-    /// Return the constructed Mempool and Consensus Sender+Events
+    /// Return the first of the bound listen addresses, and the constructed Mempool and Consensus
+    /// Sender+Events
     pub fn build(&mut self) -> (Multiaddr, Box<dyn LibraNetworkProvider>) {
-        let identity = Identity::new(self.peer_id, self.supported_protocols(), self.role);
+        let identity = Identity::new(self.peer_id, self.supported_protocols(), self.role)
+            .with_compressed_protocols(self.supported_compressed_protocols());
         // Build network based on the transport type
         let trusted_peers = self.trusted_peers.clone();
+        let rotated_keys = self.rotated_keys.clone();
         match self.transport {
             TransportType::Memory => self.build_with_transport(build_memory_transport(identity)),
             TransportType::MemoryNoise(ref mut keys) => {
                 let keys = keys.take().expect("Identity keys not set");
+                let noise_config = Arc::new(NoiseConfig::new(keys));
+                self.noise_config = Some(noise_config.clone());
                 self.build_with_transport(build_memory_noise_transport(
                     identity,
-                    keys,
+                    noise_config,
                     trusted_peers,
+                    rotated_keys,
                 ))
             }
             TransportType::PermissionlessMemoryNoise(ref mut keys) => {
                 let keys = keys.take().expect("Identity keys not set");
+                let noise_config = Arc::new(NoiseConfig::new(keys));
                 self.build_with_transport(build_permissionless_memory_noise_transport(
-                    identity, keys,
+                    identity,
+                    noise_config,
                 ))
             }
             TransportType::Tcp => self.build_with_transport(build_tcp_transport(identity)),
             TransportType::TcpNoise(ref mut keys) => {
                 let keys = keys.take().expect("Identity keys not set");
-                self.build_with_transport(build_tcp_noise_transport(identity, keys, trusted_peers))
+                let noise_config = Arc::new(NoiseConfig::new(keys));
+                self.noise_config = Some(noise_config.clone());
+                self.build_with_transport(build_tcp_noise_transport(
+                    identity,
+                    noise_config,
+                    trusted_peers,
+                    rotated_keys,
+                ))
             }
             TransportType::PermissionlessTcpNoise(ref mut keys) => {
                 let keys = keys.take().expect("Identity keys not set");
-                self.build_with_transport(build_permissionless_tcp_noise_transport(identity, keys))
+                let noise_config = Arc::new(NoiseConfig::new(keys));
+                self.build_with_transport(build_permissionless_tcp_noise_transport(
+                    identity,
+                    noise_config,
+                ))
+            }
+            TransportType::UdpNoise(ref mut keys) => {
+                let keys = keys.take().expect("Identity keys not set");
+                let noise_config = Arc::new(NoiseConfig::new(keys));
+                self.noise_config = Some(noise_config.clone());
+                self.build_with_transport(build_udp_noise_transport(
+                    identity,
+                    noise_config,
+                    trusted_peers,
+                    rotated_keys,
+                ))
             }
         }
     }
@@ -351,9 +530,17 @@ impl NetworkBuilder {
         // Initialize lists of protocol handlers and peer event handlers.
         let mut peer_event_handlers = vec![];
         let mut protocol_handlers = HashMap::new();
-        // Setup channel to send requests to peer manager.
-        let (pm_reqs_tx, pm_reqs_rx) =
-            channel::new(self.channel_size, &counters::PENDING_PEER_MANAGER_REQUESTS);
+        let protocol_priorities = self.supported_protocol_priorities();
+        // Protocols compressed with each peer, negotiated during the identity exchange.
+        let compression = Compression::new(self.supported_compressed_protocols());
+        // Metrics of the connected peers, collected by the PeerManager and the protocol actors.
+        let peer_metrics = PeerMetrics::new();
+        // Setup prioritized queues to send requests to peer manager.
+        let (pm_reqs_tx, pm_reqs_rx) = priority_queue::new(
+            &protocol_priorities,
+            self.channel_size,
+            &counters::PENDING_PEER_MANAGER_REQUESTS,
+        );
 
         // Initialize and start DirectSend actor.
         let (pm_ds_notifs_tx, pm_ds_notifs_rx) = channel::new(
@@ -365,8 +552,11 @@ impl NetworkBuilder {
             .iter()
             .map(|p| (p.clone(), pm_ds_notifs_tx.clone()));
         protocol_handlers.extend(direct_send_handlers);
-        let (ds_reqs_tx, ds_reqs_rx) =
-            channel::new(self.channel_size, &counters::PENDING_DIRECT_SEND_REQUESTS);
+        let (ds_reqs_tx, ds_reqs_rx) = priority_queue::new(
+            &protocol_priorities,
+            self.channel_size,
+            &counters::PENDING_DIRECT_SEND_REQUESTS,
+        );
         let (ds_net_notifs_tx, ds_net_notifs_rx) = channel::new(
             self.channel_size,
             &counters::PENDING_DIRECT_SEND_NOTIFICATIONS,
@@ -377,6 +567,8 @@ impl NetworkBuilder {
             ds_net_notifs_tx,
             pm_ds_notifs_rx,
             PeerManagerRequestSender::new(pm_reqs_tx.clone()),
+            compression.clone(),
+            peer_metrics.clone(),
         );
         self.executor.spawn(ds.start());
         debug!("Started direct send actor");
@@ -389,12 +581,16 @@ impl NetworkBuilder {
         let rpc_handlers = self
             .rpc_protocols
             .iter()
+            .chain(&self.streaming_rpc_protocols)
             .map(|p| (p.clone(), pm_rpc_notifs_tx.clone()));
         protocol_handlers.extend(rpc_handlers);
         let (rpc_net_notifs_tx, rpc_net_notifs_rx) =
             channel::new(self.channel_size, &counters::PENDING_RPC_NOTIFICATIONS);
-        let (rpc_reqs_tx, rpc_reqs_rx) =
-            channel::new(self.channel_size, &counters::PENDING_RPC_REQUESTS);
+        let (rpc_reqs_tx, rpc_reqs_rx) = priority_queue::new(
+            &protocol_priorities,
+            self.channel_size,
+            &counters::PENDING_RPC_REQUESTS,
+        );
         let rpc = Rpc::new(
             self.executor.clone(),
             rpc_reqs_rx,
@@ -404,6 +600,9 @@ impl NetworkBuilder {
             Duration::from_millis(self.inbound_rpc_timeout_ms),
             self.max_concurrent_outbound_rpcs,
             self.max_concurrent_inbound_rpcs,
+            compression.clone(),
+            peer_metrics.clone(),
+            self.streaming_rpc_protocols.iter().cloned().collect(),
         );
         self.executor.spawn(rpc.start());
         debug!("Started RPC actor");
@@ -424,102 +623,119 @@ impl NetworkBuilder {
             pm_ping_notifs_rx,
             Duration::from_millis(self.ping_timeout_ms),
             self.ping_failures_tolerated,
+            peer_metrics.clone(),
         );
         self.executor.spawn(health_checker.start());
         debug!("Started health checker");
 
-        let mut net_conn_mgr_reqs_tx = None;
-
-        // We start the discovery and connectivity_manager module only if the network is
-        // permissioned.
-        if self.is_permissioned {
-            // Initialize and start connectivity manager.
-            let (conn_mgr_reqs_tx, conn_mgr_reqs_rx) = channel::new(
-                self.channel_size,
-                &counters::PENDING_CONNECTIVITY_MANAGER_REQUESTS,
-            );
-            net_conn_mgr_reqs_tx = Some(conn_mgr_reqs_tx.clone());
-            let (pm_conn_mgr_notifs_tx, pm_conn_mgr_notifs_rx) = channel::new(
-                self.channel_size,
-                &counters::PENDING_PEER_MANAGER_CONNECTIVITY_MANAGER_NOTIFICATIONS,
-            );
-            peer_event_handlers.push(pm_conn_mgr_notifs_tx);
-            let conn_mgr = ConnectivityManager::new(
-                self.trusted_peers.clone(),
-                Interval::new_interval(Duration::from_millis(self.connectivity_check_interval_ms))
-                    .fuse(),
-                PeerManagerRequestSender::new(pm_reqs_tx.clone()),
-                pm_conn_mgr_notifs_rx,
-                conn_mgr_reqs_rx,
-                ExponentialBackoff::from_millis(2).factor(1000 /* seconds */),
-                self.max_connection_delay_ms,
-            );
-            self.executor.spawn(conn_mgr.start());
-            debug!("Started connection manager");
-
-            // Initialize and start Discovery actor.
-            let (pm_discovery_notifs_tx, pm_discovery_notifs_rx) = channel::new(
-                self.channel_size,
-                &counters::PENDING_PEER_MANAGER_DISCOVERY_NOTIFICATIONS,
-            );
-            protocol_handlers.insert(
-                ProtocolId::from_static(DISCOVERY_PROTOCOL_NAME),
-                pm_discovery_notifs_tx.clone(),
-            );
-            peer_event_handlers.push(pm_discovery_notifs_tx);
-            let (signing_private_key, _signing_public_key) =
-                self.signing_keys.take().expect("Signing keys not set");
-            // Setup signer from keys.
-            let signer = ValidatorSigner::new(self.peer_id, signing_private_key);
-            let discovery = Discovery::new(
-                self.peer_id,
-                vec![self
-                    .advertised_address
-                    .clone()
-                    .unwrap_or_else(|| self.addr.clone())],
-                signer,
-                self.seed_peers.clone(),
-                self.trusted_peers.clone(),
-                Interval::new_interval(Duration::from_millis(self.discovery_interval_ms)).fuse(),
-                PeerManagerRequestSender::new(pm_reqs_tx.clone()),
-                pm_discovery_notifs_rx,
-                conn_mgr_reqs_tx.clone(),
-                Duration::from_millis(self.discovery_msg_timeout_ms),
-            );
-            self.executor.spawn(discovery.start());
-            debug!("Started discovery protocol actor");
-        }
+        // Initialize and start connectivity manager. In a permissionless network, any
+        // discovered peer is eligible to be dialed.
+        let (conn_mgr_reqs_tx, conn_mgr_reqs_rx) = channel::new(
+            self.channel_size,
+            &counters::PENDING_CONNECTIVITY_MANAGER_REQUESTS,
+        );
+        let (pm_conn_mgr_notifs_tx, pm_conn_mgr_notifs_rx) = channel::new(
+            self.channel_size,
+            &counters::PENDING_PEER_MANAGER_CONNECTIVITY_MANAGER_NOTIFICATIONS,
+        );
+        peer_event_handlers.push(pm_conn_mgr_notifs_tx);
+        let connectivity_policy = if self.is_permissioned {
+            ConnectivityPolicy::Permissioned
+        } else {
+            ConnectivityPolicy::Permissionless {
+                target_connections: self.target_connections,
+            }
+        };
+        let conn_mgr = ConnectivityManager::new(
+            self.trusted_peers.clone(),
+            self.rotated_keys.clone(),
+            connectivity_policy,
+            Interval::new_interval(Duration::from_millis(self.connectivity_check_interval_ms))
+                .fuse(),
+            PeerManagerRequestSender::new(pm_reqs_tx.clone()),
+            pm_conn_mgr_notifs_rx,
+            conn_mgr_reqs_rx,
+            ExponentialBackoff::from_millis(2).factor(1000 /* seconds */),
+            self.max_connection_delay_ms,
+        );
+        self.executor.spawn(conn_mgr.start());
+        debug!("Started connection manager");
+
+        // Initialize and start Discovery actor. In a permissionless network, the discovery notes
+        // are self-certified.
+        let (pm_discovery_notifs_tx, pm_discovery_notifs_rx) = channel::new(
+            self.channel_size,
+            &counters::PENDING_PEER_MANAGER_DISCOVERY_NOTIFICATIONS,
+        );
+        protocol_handlers.insert(
+            ProtocolId::from_static(DISCOVERY_PROTOCOL_NAME),
+            pm_discovery_notifs_tx.clone(),
+        );
+        peer_event_handlers.push(pm_discovery_notifs_tx);
+        let (signing_private_key, _signing_public_key) =
+            self.signing_keys.take().expect("Signing keys not set");
+        // Setup signer from keys.
+        let signer = ValidatorSigner::new(self.peer_id, signing_private_key);
+        let advertised_addresses = if self.advertised_addresses.is_empty() {
+            self.listen_addresses.clone()
+        } else {
+            self.advertised_addresses.clone()
+        };
+        let discovery = Discovery::new(
+            self.peer_id,
+            advertised_addresses,
+            self.dns_seed_addr.clone().into_bytes(),
+            signer,
+            self.seed_peers.clone(),
+            self.trusted_peers.clone(),
+            self.is_permissioned,
+            self.seed_resolver.take(),
+            Interval::new_interval(Duration::from_millis(self.discovery_interval_ms)).fuse(),
+            PeerManagerRequestSender::new(pm_reqs_tx.clone()),
+            pm_discovery_notifs_rx,
+            conn_mgr_reqs_tx.clone(),
+            Duration::from_millis(self.discovery_msg_timeout_ms),
+        );
+        self.executor.spawn(discovery.start());
+        debug!("Started discovery protocol actor");
 
         let (pm_net_notifs_tx, pm_net_notifs_rx) = channel::new(
             self.channel_size,
             &counters::PENDING_PEER_MANAGER_NET_NOTIFICATIONS,
         );
         peer_event_handlers.push(pm_net_notifs_tx);
+        self.peer_reputation
+            .write()
+            .unwrap()
+            .set_trusted_peers(self.trusted_peers.clone());
         let peer_mgr = PeerManager::new(
             transport,
             self.executor.clone(),
             self.peer_id,
-            self.addr.clone(),
+            self.listen_addresses.clone(),
             pm_reqs_rx,
             protocol_handlers,
             peer_event_handlers,
+            self.inbound_connection_limits,
+            self.peer_reputation.clone(),
+            compression,
+            peer_metrics.clone(),
         );
-        let listen_addr = peer_mgr.listen_addr().clone();
+        let listen_addr = peer_mgr.listen_addrs()[0].clone();
         self.executor.spawn(peer_mgr.start());
         debug!("Started peer manager");
 
-        // Setup communication channels.
-        let (network_reqs_tx, network_reqs_rx) =
-            channel::new(self.channel_size, &counters::PENDING_NETWORK_REQUESTS);
         let validator_network = NetworkProvider::new(
             pm_net_notifs_rx,
             rpc_reqs_tx,
             rpc_net_notifs_rx,
             ds_reqs_tx,
             ds_net_notifs_rx,
-            net_conn_mgr_reqs_tx,
-            network_reqs_rx,
-            network_reqs_tx,
+            Some(conn_mgr_reqs_tx),
+            PeerManagerRequestSender::new(pm_reqs_tx),
+            self.peer_reputation.clone(),
+            peer_metrics,
+            protocol_priorities,
             self.max_concurrent_network_reqs,
             self.max_concurrent_network_notifs,
             self.channel_size,
@@ -527,3 +743,24 @@ impl NetworkBuilder {
         (listen_addr, Box::new(validator_network))
     }
 }
+
+/// Consensus messages get ahead of everything else, and mempool broadcasts get behind.
+fn default_protocol_priorities() -> HashMap<ProtocolId, ProtocolPriority> {
+    [
+        (CONSENSUS_DIRECT_SEND_PROTOCOL, ProtocolPriority::High),
+        (CONSENSUS_RPC_PROTOCOL, ProtocolPriority::High),
+        (CONSENSUS_STREAMING_RPC_PROTOCOL, ProtocolPriority::High),
+        (MEMPOOL_DIRECT_SEND_PROTOCOL, ProtocolPriority::Low),
+    ]
+    .iter()
+    .map(|(protocol, priority)| (ProtocolId::from_static(*protocol), *priority))
+    .collect()
+}
+
+/// Mempool batches and state synchronizer chunks are large and highly compressible.
+fn default_compressed_protocols() -> Vec<ProtocolId> {
+    vec![
+        ProtocolId::from_static(MEMPOOL_DIRECT_SEND_PROTOCOL),
+        ProtocolId::from_static(STATE_SYNCHRONIZER_MSG_PROTOCOL),
+    ]
+}
@@ -6,10 +6,11 @@
 use crate::{
     error::NetworkError,
     interface::{NetworkNotification, NetworkRequest},
+    peer_reputation::Misbehavior,
     proto::StateSynchronizerMsg,
     protocols::direct_send::Message,
     utils::MessageExt,
-    validator_network::Event,
+    validator_network::{drop_streaming_rpc, Event},
     ProtocolId,
 };
 use channel;
@@ -41,6 +42,9 @@ impl StateSynchronizerEvents {
             NetworkNotification::RecvRpc(_, _) => {
                 unimplemented!("StateSynchronizer does not currently use RPC");
             }
+            NetworkNotification::RecvStreamingRpc(peer_id, rpc_req) => {
+                Err(drop_streaming_rpc("StateSynchronizer", peer_id, rpc_req))
+            }
             NetworkNotification::RecvMessage(peer_id, msg) => {
                 let msg = StateSynchronizerMsg::decode(msg.mdata.as_ref())?;
                 Ok(Event::Message((peer_id, msg)))
@@ -69,6 +73,19 @@ impl StateSynchronizerSender {
         Self { inner }
     }
 
+    /// Report a misbehavior of the remote peer `peer_id`. The peer is disconnected and banned
+    /// once its accumulated penalty reaches the ban threshold.
+    pub async fn report_peer(
+        &mut self,
+        peer_id: PeerId,
+        misbehavior: Misbehavior,
+    ) -> Result<(), NetworkError> {
+        self.inner
+            .send(NetworkRequest::ReportPeer(peer_id, misbehavior))
+            .await?;
+        Ok(())
+    }
+
     pub async fn send_to(
         &mut self,
         recipient: PeerId,
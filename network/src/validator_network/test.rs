@@ -4,17 +4,18 @@
 //! Integration tests for validator_network.
 use crate::{
     common::NetworkPublicKeys,
-    proto::{ConsensusMsg, ConsensusMsg_oneof, MempoolSyncMsg, RequestBlock, RespondBlock},
+    proto::{ConsensusMsg, ConsensusMsg_oneof, MempoolSyncMsg, RequestBlock, RespondBlock, Vote},
     utils::MessageExt,
     validator_network::{
         network_builder::{NetworkBuilder, TransportType},
-        Event, CONSENSUS_RPC_PROTOCOL, MEMPOOL_DIRECT_SEND_PROTOCOL,
+        Event, CONSENSUS_DIRECT_SEND_PROTOCOL, CONSENSUS_RPC_PROTOCOL,
+        CONSENSUS_STREAMING_RPC_PROTOCOL, MEMPOOL_DIRECT_SEND_PROTOCOL,
     },
     ProtocolId,
 };
 use config::config::RoleType;
 use crypto::{ed25519::compat, test_utils::TEST_SEED, traits::ValidKey, x25519};
-use futures::{executor::block_on, future::join, StreamExt};
+use futures::{channel::oneshot, executor::block_on, future::join, SinkExt, StreamExt};
 use libra_types::{
     account_address::{AccountAddress, ADDRESS_LENGTH},
     proto::types::SignedTransaction,
@@ -28,7 +29,7 @@ use std::{
     convert::{TryFrom, TryInto},
     time::Duration,
 };
-use tokio::runtime::Runtime;
+use tokio::{future::FutureExt as _, runtime::Runtime};
 
 #[test]
 fn test_network_builder() {
@@ -291,6 +292,8 @@ fn test_permissionless_mempool_sync() {
     )
     .channel_size(8)
     .direct_send_protocols(vec![mempool_sync_protocol.clone()])
+    // The dialer does not compress its messages, so the listener exchanges raw messages with it.
+    .compressed_protocols(vec![])
     .build();
     let (mut dialer_mp_net_sender, mut dialer_mp_net_events) =
         network_provider.add_mempool(vec![mempool_sync_protocol.clone()]);
@@ -348,11 +351,202 @@ fn test_permissionless_mempool_sync() {
     block_on(join(f_dialer, f_listener));
 }
 
+// Test that only the identity key of a permissioned end-point can be rotated: the peer id of a
+// permissionless end-point is derived from its identity key.
 #[test]
-fn test_consensus_rpc() {
+fn test_permissionless_identity_key_not_rotatable() {
+    let runtime = Runtime::new().unwrap();
+    let mut rng = StdRng::from_seed(TEST_SEED);
+    let (signing_private_key, signing_public_key) = compat::generate_keypair(&mut rng);
+    let (identity_private_key, identity_public_key) = x25519::compat::generate_keypair(&mut rng);
+    let peer_id = PeerId::try_from(identity_public_key.to_bytes()).unwrap();
+
+    let addr: Multiaddr = "/memory/0".parse().unwrap();
+    let mut network_builder =
+        NetworkBuilder::new(runtime.executor(), peer_id, addr, RoleType::Validator);
+    network_builder
+        .signing_keys((signing_private_key.clone(), signing_public_key.clone()))
+        .permissioned(false)
+        .transport(TransportType::PermissionlessMemoryNoise(Some((
+            identity_private_key.clone(),
+            identity_public_key.clone(),
+        ))))
+        .channel_size(8);
+    let (_listen_addr, network_provider) = network_builder.build();
+    runtime.executor().spawn(network_provider.start());
+    assert!(network_builder.noise_config().is_none());
+
+    let addr: Multiaddr = "/memory/0".parse().unwrap();
+    let mut network_builder =
+        NetworkBuilder::new(runtime.executor(), peer_id, addr, RoleType::Validator);
+    network_builder
+        .signing_keys((signing_private_key, signing_public_key.clone()))
+        .transport(TransportType::MemoryNoise(Some((
+            identity_private_key,
+            identity_public_key.clone(),
+        ))))
+        .trusted_peers(
+            vec![(
+                peer_id,
+                NetworkPublicKeys {
+                    signing_public_key,
+                    identity_public_key: identity_public_key.clone(),
+                },
+            )]
+            .into_iter()
+            .collect(),
+        )
+        .channel_size(8);
+    let (_listen_addr, network_provider) = network_builder.build();
+    runtime.executor().spawn(network_provider.start());
+    let noise_config = network_builder.noise_config().unwrap();
+    assert_eq!(noise_config.public_key(), identity_public_key.to_bytes());
+}
+
+// Test that two end-points can exchange messages over the UDP transport.
+#[test]
+fn test_udp_mempool_sync() {
     ::libra_logger::try_init_for_testing();
     let runtime = Runtime::new().unwrap();
-    let rpc_protocol = ProtocolId::from_static(CONSENSUS_RPC_PROTOCOL);
+    let mempool_sync_protocol = ProtocolId::from_static(MEMPOOL_DIRECT_SEND_PROTOCOL);
+
+    // Setup peer ids.
+    let listener_peer_id = PeerId::random();
+    let dialer_peer_id = PeerId::random();
+    // Setup signing public keys.
+    let mut rng = StdRng::from_seed(TEST_SEED);
+    let (listener_signing_private_key, listener_signing_public_key) =
+        compat::generate_keypair(&mut rng);
+    let (dialer_signing_private_key, dialer_signing_public_key) =
+        compat::generate_keypair(&mut rng);
+    // Setup identity keys.
+    let (listener_identity_private_key, listener_identity_public_key) =
+        x25519::compat::generate_keypair(&mut rng);
+    let (dialer_identity_private_key, dialer_identity_public_key) =
+        x25519::compat::generate_keypair(&mut rng);
+
+    let trusted_peers: HashMap<_, _> = vec![
+        (
+            listener_peer_id,
+            NetworkPublicKeys {
+                signing_public_key: listener_signing_public_key.clone(),
+                identity_public_key: listener_identity_public_key.clone(),
+            },
+        ),
+        (
+            dialer_peer_id,
+            NetworkPublicKeys {
+                signing_public_key: dialer_signing_public_key.clone(),
+                identity_public_key: dialer_identity_public_key.clone(),
+            },
+        ),
+    ]
+    .into_iter()
+    .collect();
+
+    // Set up the listener network
+    let listener_addr: Multiaddr = "/ip4/127.0.0.1/udp/0".parse().unwrap();
+    let (listener_addr, mut network_provider) = NetworkBuilder::new(
+        runtime.executor(),
+        listener_peer_id,
+        listener_addr,
+        RoleType::Validator,
+    )
+    .signing_keys((listener_signing_private_key, listener_signing_public_key))
+    .trusted_peers(trusted_peers.clone())
+    .transport(TransportType::UdpNoise(Some((
+        listener_identity_private_key,
+        listener_identity_public_key,
+    ))))
+    .channel_size(8)
+    .direct_send_protocols(vec![mempool_sync_protocol.clone()])
+    .build();
+    let (_, mut listener_mp_net_events) =
+        network_provider.add_mempool(vec![mempool_sync_protocol.clone()]);
+    runtime.executor().spawn(network_provider.start());
+
+    // Set up the dialer network
+    let dialer_addr: Multiaddr = "/ip4/127.0.0.1/udp/0".parse().unwrap();
+    let (_dialer_addr, mut network_provider) = NetworkBuilder::new(
+        runtime.executor(),
+        dialer_peer_id,
+        dialer_addr,
+        RoleType::Validator,
+    )
+    .transport(TransportType::UdpNoise(Some((
+        dialer_identity_private_key,
+        dialer_identity_public_key,
+    ))))
+    .signing_keys((dialer_signing_private_key, dialer_signing_public_key))
+    .trusted_peers(trusted_peers.clone())
+    .seed_peers(
+        [(listener_peer_id, vec![listener_addr])]
+            .iter()
+            .cloned()
+            .collect(),
+    )
+    .channel_size(8)
+    .direct_send_protocols(vec![mempool_sync_protocol.clone()])
+    .build();
+    let (mut dialer_mp_net_sender, mut dialer_mp_net_events) =
+        network_provider.add_mempool(vec![mempool_sync_protocol.clone()]);
+    runtime.executor().spawn(network_provider.start());
+
+    // The dialer dials the listener and sends a mempool sync message
+    let mut mempool_msg = MempoolSyncMsg::default();
+    mempool_msg.peer_id = dialer_peer_id.into();
+    let sender = AccountAddress::new([0; ADDRESS_LENGTH]);
+    let keypair = compat::generate_keypair(&mut rng);
+    let txn: SignedTransaction = get_test_signed_txn(sender, 0, keypair.0, keypair.1, None)
+        .try_into()
+        .unwrap();
+    mempool_msg.transactions.push(txn.clone());
+
+    let f_dialer = async move {
+        // Wait until dialing finished and NewPeer event received
+        match dialer_mp_net_events.next().await.unwrap().unwrap() {
+            Event::NewPeer(peer_id) => {
+                assert_eq!(peer_id, listener_peer_id);
+            }
+            event => panic!("Unexpected event {:?}", event),
+        }
+
+        // Dialer sends a mempool sync message
+        dialer_mp_net_sender
+            .send_to(listener_peer_id, mempool_msg)
+            .await
+            .unwrap();
+    };
+
+    // The listener receives a mempool sync message
+    let f_listener = async move {
+        // The listener receives a NewPeer event first
+        match listener_mp_net_events.next().await.unwrap().unwrap() {
+            Event::NewPeer(peer_id) => {
+                assert_eq!(peer_id, dialer_peer_id);
+            }
+            event => panic!("Unexpected event {:?}", event),
+        }
+
+        // The listener then receives the mempool sync message
+        match listener_mp_net_events.next().await.unwrap().unwrap() {
+            Event::Message((peer_id, msg)) => {
+                assert_eq!(peer_id, dialer_peer_id);
+                let transactions: Vec<SignedTransaction> = msg.transactions;
+                assert_eq!(transactions, vec![txn]);
+            }
+            event => panic!("Unexpected event {:?}", event),
+        }
+    };
+
+    block_on(join(f_dialer, f_listener));
+}
+
+#[test]
+fn test_consensus_streaming_rpc() {
+    ::libra_logger::try_init_for_testing();
+    let runtime = Runtime::new().unwrap();
+    let rpc_protocol = ProtocolId::from_static(CONSENSUS_STREAMING_RPC_PROTOCOL);
 
     // Setup peer ids.
     let listener_peer_id = PeerId::random();
@@ -400,7 +594,7 @@ fn test_consensus_rpc() {
     .trusted_peers(trusted_peers.clone())
     .transport(TransportType::Memory)
     .channel_size(8)
-    .rpc_protocols(vec![rpc_protocol.clone()])
+    .streaming_rpc_protocols(vec![rpc_protocol.clone()])
     .build();
     let (_, mut listener_con_net_events) =
         network_provider.add_consensus(vec![rpc_protocol.clone()]);
@@ -424,7 +618,7 @@ fn test_consensus_rpc() {
             .collect(),
     )
     .channel_size(8)
-    .rpc_protocols(vec![rpc_protocol.clone()])
+    .streaming_rpc_protocols(vec![rpc_protocol.clone()])
     .build();
     let (mut dialer_con_net_sender, mut dialer_con_net_events) =
         network_provider.add_consensus(vec![rpc_protocol.clone()]);
@@ -436,7 +630,7 @@ fn test_consensus_rpc() {
 
     let res_block_msg = RespondBlock::default();
 
-    // The dialer dials the listener and sends a RequestBlock rpc request
+    // The dialer dials the listener and sends a RequestBlock streaming rpc request
     let req_block_msg_clone = req_block_msg.clone();
     let res_block_msg_clone = res_block_msg.clone();
     let f_dialer = async move {
@@ -448,7 +642,7 @@ fn test_consensus_rpc() {
             event => panic!("Unexpected event {:?}", event),
         }
 
-        // Dialer sends a RequestBlock rpc request.
+        // Dialer sends a RequestBlock streaming rpc request.
         let res_block_msg = dialer_con_net_sender
             .request_block(
                 listener_peer_id,
@@ -460,8 +654,8 @@ fn test_consensus_rpc() {
         assert_eq!(res_block_msg, res_block_msg_clone);
     };
 
-    // The listener receives a RequestBlock rpc request and sends a RespondBlock
-    // rpc response.
+    // The listener receives a RequestBlock streaming rpc request and sends a RespondBlock
+    // chunk.
     let req_block_msg_clone = req_block_msg.clone();
     let res_block_msg_clone = res_block_msg.clone();
     let f_listener = async move {
@@ -475,7 +669,7 @@ fn test_consensus_rpc() {
 
         // The listener then handles the RequestBlock rpc request.
         match listener_con_net_events.next().await.unwrap().unwrap() {
-            Event::RpcRequest((peer_id, req_msg, res_tx)) => {
+            Event::StreamingRpcRequest((peer_id, req_msg, mut res_tx)) => {
                 assert_eq!(peer_id, dialer_peer_id);
 
                 // Check the request
@@ -489,7 +683,7 @@ fn test_consensus_rpc() {
                     message: Some(ConsensusMsg_oneof::RespondBlock(res_block_msg_clone)),
                 };
                 let res_data = res_msg.to_bytes().unwrap();
-                res_tx.send(Ok(res_data)).unwrap();
+                res_tx.send(Ok(res_data)).await.unwrap();
             }
             event => panic!("Unexpected event {:?}", event),
         }
@@ -497,3 +691,164 @@ fn test_consensus_rpc() {
 
     block_on(join(f_dialer, f_listener));
 }
+
+// Test that consensus messages are still delivered promptly while mempool floods the peer.
+#[test]
+fn test_consensus_during_mempool_flood() {
+    ::libra_logger::try_init_for_testing();
+    let runtime = Runtime::new().unwrap();
+    let mempool_sync_protocol = ProtocolId::from_static(MEMPOOL_DIRECT_SEND_PROTOCOL);
+    let consensus_protocol = ProtocolId::from_static(CONSENSUS_DIRECT_SEND_PROTOCOL);
+    let protocols = vec![mempool_sync_protocol.clone(), consensus_protocol.clone()];
+
+    // Setup peer ids.
+    let listener_peer_id = PeerId::random();
+    let dialer_peer_id = PeerId::random();
+    // Setup signing public keys.
+    let mut rng = StdRng::from_seed(TEST_SEED);
+    let (listener_signing_private_key, listener_signing_public_key) =
+        compat::generate_keypair(&mut rng);
+    let (dialer_signing_private_key, dialer_signing_public_key) =
+        compat::generate_keypair(&mut rng);
+    // Setup identity public keys.
+    let (_listener_identity_private_key, listener_identity_public_key) =
+        x25519::compat::generate_keypair(&mut rng);
+    let (_dialer_identity_private_key, dialer_identity_public_key) =
+        x25519::compat::generate_keypair(&mut rng);
+
+    let trusted_peers: HashMap<_, _> = vec![
+        (
+            listener_peer_id,
+            NetworkPublicKeys {
+                signing_public_key: listener_signing_public_key.clone(),
+                identity_public_key: listener_identity_public_key.clone(),
+            },
+        ),
+        (
+            dialer_peer_id,
+            NetworkPublicKeys {
+                signing_public_key: dialer_signing_public_key.clone(),
+                identity_public_key: dialer_identity_public_key.clone(),
+            },
+        ),
+    ]
+    .into_iter()
+    .collect();
+
+    // Set up the listener network
+    let listener_addr: Multiaddr = "/memory/0".parse().unwrap();
+    let (listener_addr, mut network_provider) = NetworkBuilder::new(
+        runtime.executor(),
+        listener_peer_id,
+        listener_addr,
+        RoleType::Validator,
+    )
+    .signing_keys((listener_signing_private_key, listener_signing_public_key))
+    .trusted_peers(trusted_peers.clone())
+    .transport(TransportType::Memory)
+    .channel_size(8)
+    .direct_send_protocols(protocols.clone())
+    .build();
+    let (_, mut listener_mp_net_events) =
+        network_provider.add_mempool(vec![mempool_sync_protocol.clone()]);
+    let (_, mut listener_con_net_events) =
+        network_provider.add_consensus(vec![consensus_protocol.clone()]);
+    runtime.executor().spawn(network_provider.start());
+
+    // Set up the dialer network
+    let dialer_addr: Multiaddr = "/memory/0".parse().unwrap();
+    let (_dialer_addr, mut network_provider) = NetworkBuilder::new(
+        runtime.executor(),
+        dialer_peer_id,
+        dialer_addr,
+        RoleType::Validator,
+    )
+    .transport(TransportType::Memory)
+    .signing_keys((dialer_signing_private_key, dialer_signing_public_key))
+    .trusted_peers(trusted_peers.clone())
+    .seed_peers(
+        [(listener_peer_id, vec![listener_addr])]
+            .iter()
+            .cloned()
+            .collect(),
+    )
+    .channel_size(8)
+    .direct_send_protocols(protocols.clone())
+    .build();
+    let (mut dialer_mp_net_sender, _dialer_mp_net_events) =
+        network_provider.add_mempool(vec![mempool_sync_protocol.clone()]);
+    let (mut dialer_con_net_sender, mut dialer_con_net_events) =
+        network_provider.add_consensus(vec![consensus_protocol.clone()]);
+    runtime.executor().spawn(network_provider.start());
+
+    let mut mempool_msg = MempoolSyncMsg::default();
+    mempool_msg.peer_id = dialer_peer_id.into();
+    let sender = AccountAddress::new([0; ADDRESS_LENGTH]);
+    let keypair = compat::generate_keypair(&mut rng);
+    let txn: SignedTransaction = get_test_signed_txn(sender, 0, keypair.0, keypair.1, None)
+        .try_into()
+        .unwrap();
+    mempool_msg.transactions.push(txn);
+    let mut vote = Vote::default();
+    vote.author = dialer_peer_id.into();
+    let vote_msg = ConsensusMsg {
+        message: Some(ConsensusMsg_oneof::Vote(vote.clone())),
+    };
+
+    // The dialer floods the listener with mempool messages, and sends a vote in the middle of
+    // the flood.
+    let executor = runtime.executor();
+    let f_dialer = async move {
+        // Wait until dialing finished and NewPeer event received
+        match dialer_con_net_events.next().await.unwrap().unwrap() {
+            Event::NewPeer(peer_id) => {
+                assert_eq!(peer_id, listener_peer_id);
+            }
+            event => panic!("Unexpected event {:?}", event),
+        }
+
+        executor.spawn(async move {
+            for _ in 0..10_000 {
+                dialer_mp_net_sender
+                    .send_to(listener_peer_id, mempool_msg.clone())
+                    .await
+                    .unwrap();
+            }
+        });
+        dialer_con_net_sender
+            .send_to(listener_peer_id, vote_msg)
+            .await
+            .unwrap();
+    };
+
+    // The listener keeps draining the mempool messages, and receives the vote promptly.
+    runtime
+        .executor()
+        .spawn(async move { while let Some(_event) = listener_mp_net_events.next().await {} });
+    let (vote_tx, vote_rx) = oneshot::channel();
+    runtime.executor().spawn(async move {
+        let f_vote = async move {
+            loop {
+                match listener_con_net_events.next().await.unwrap().unwrap() {
+                    Event::NewPeer(peer_id) => {
+                        assert_eq!(peer_id, dialer_peer_id);
+                    }
+                    Event::Message((peer_id, msg)) => {
+                        assert_eq!(peer_id, dialer_peer_id);
+                        break msg;
+                    }
+                    event => panic!("Unexpected event {:?}", event),
+                }
+            }
+        };
+        vote_tx
+            .send(f_vote.timeout(Duration::from_secs(5)).await)
+            .unwrap();
+    });
+
+    block_on(f_dialer);
+    let msg = block_on(vote_rx)
+        .unwrap()
+        .expect("Vote delayed by the mempool flood");
+    assert_eq!(msg.message, Some(ConsensusMsg_oneof::Vote(vote)));
+}
@@ -21,6 +21,7 @@ use libra_types::{
 use network::{
     proto::{GetChunkRequest, GetChunkResponse, StateSynchronizerMsg, StateSynchronizerMsg_oneof},
     validator_network::{Event, StateSynchronizerEvents, StateSynchronizerSender},
+    Misbehavior,
 };
 use std::{
     collections::HashMap,
@@ -358,8 +359,7 @@ impl<T: ExecutorProxyTrait> SyncCoordinator<T> {
                 // version was not requested, or version was requested from a different peer,
                 // so need to penalize peer for maliciously sending chunk
                 if has_requested {
-                    self.peer_manager
-                        .update_score(&peer_id, PeerScoreUpdateType::InvalidChunk)
+                    self.penalize_invalid_chunk(peer_id).await;
                 }
                 return Err(format_err!(
                     "[state sync] non sequential chunk. Known version: {}, received: {}",
@@ -381,8 +381,7 @@ impl<T: ExecutorProxyTrait> SyncCoordinator<T> {
             .await;
         let latest_version = self.executor_proxy.get_latest_version().await?;
         if latest_version <= previous_version {
-            self.peer_manager
-                .update_score(peer_id, PeerScoreUpdateType::InvalidChunk);
+            self.penalize_invalid_chunk(peer_id).await;
         } else {
             self.commit(latest_version).await;
         }
@@ -395,6 +394,21 @@ impl<T: ExecutorProxyTrait> SyncCoordinator<T> {
         result
     }
 
+    /// penalizes the peer for an invalid chunk, both in the local peer score and in the
+    /// reputation of the peer in the network layer
+    async fn penalize_invalid_chunk(&mut self, peer_id: &PeerId) {
+        self.peer_manager
+            .update_score(peer_id, PeerScoreUpdateType::InvalidChunk);
+        if let Some(mut sender) = self.peer_manager.get_network_sender(peer_id) {
+            if let Err(err) = sender
+                .report_peer(*peer_id, Misbehavior::MalformedMessage)
+                .await
+            {
+                error!("[state sync] failed to report peer {}: {:?}", peer_id, err);
+            }
+        }
+    }
+
     async fn validate_and_store_chunk(
         &mut self,
         txn_list_with_proof: TransactionListWithProof,
@@ -52,6 +52,7 @@ use libra_types::{
     account_state_blob::{AccountStateBlob, AccountStateWithProof},
     contract_event::EventWithProof,
     crypto_proxies::{LedgerInfoWithSignatures, ValidatorChangeEventWithProof},
+    event::EventKey,
     get_with_proof::{RequestItem, ResponseItem},
     proof::{
         AccountStateProof, AccumulatorConsistencyProof, EventProof, SignedTransactionProof,
@@ -202,9 +203,6 @@ impl LibraDB {
         limit: u64,
         ledger_version: Version,
     ) -> Result<(Vec<EventWithProof>, AccountStateWithProof)> {
-        error_if_too_many_requested(limit, MAX_LIMIT)?;
-
-        let get_latest = !ascending && start_seq_num == u64::max_value();
         let account_state =
             self.get_account_state_with_proof(query_path.address, ledger_version, ledger_version)?;
         let account_resource = if let Some(account_blob) = &account_state.blob {
@@ -215,6 +213,32 @@ impl LibraDB {
         let event_key = account_resource
             .get_event_handle_by_query_path(&query_path.path)?
             .key();
+        let events_with_proof = self.get_events_by_event_key(
+            event_key,
+            start_seq_num,
+            ascending,
+            limit,
+            ledger_version,
+        )?;
+
+        // We always need to return the account blob to prove that this is indeed the event that was
+        // being queried.
+        Ok((events_with_proof, account_state))
+    }
+
+    /// Returns events emitted under `event_key` with sequence number in range designated by
+    /// `start_seq_num`, `ascending` and `limit`, the same way as `get_events_by_query_path` does.
+    fn get_events_by_event_key(
+        &self,
+        event_key: &EventKey,
+        start_seq_num: u64,
+        ascending: bool,
+        limit: u64,
+        ledger_version: Version,
+    ) -> Result<Vec<EventWithProof>> {
+        error_if_too_many_requested(limit, MAX_LIMIT)?;
+
+        let get_latest = !ascending && start_seq_num == u64::max_value();
         let cursor = if get_latest {
             // Caller wants the latest, figure out the latest seq_num.
             // In the case of no events on that path, use 0 and expect empty result below.
@@ -230,7 +254,7 @@ impl LibraDB {
 
         // Query the index.
         let mut event_keys = self.event_store.lookup_events_by_key(
-            &event_key,
+            event_key,
             first_seq,
             real_limit,
             ledger_version,
@@ -273,9 +297,7 @@ impl LibraDB {
             events_with_proof.reverse();
         }
 
-        // We always need to return the account blob to prove that this is indeed the event that was
-        // being queried.
-        Ok((events_with_proof, account_state))
+        Ok(events_with_proof)
     }
 
     /// Returns a signed transaction that is the `seq_num`-th one associated with the given account.
@@ -533,6 +555,22 @@ impl LibraDB {
                         txn_list_with_proof,
                     })
                 }
+                RequestItem::GetEventsByEventKey {
+                    event_key,
+                    start_event_seq_num,
+                    ascending,
+                    limit,
+                } => {
+                    let events_with_proof = self.get_events_by_event_key(
+                        &event_key,
+                        start_event_seq_num,
+                        ascending,
+                        limit,
+                        ledger_version,
+                    )?;
+
+                    Ok(ResponseItem::GetEventsByEventKey { events_with_proof })
+                }
             })
             .collect::<Result<Vec<_>>>()?;
 
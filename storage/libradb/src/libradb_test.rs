@@ -244,6 +244,31 @@ fn verify_events_by_query_path(
                 is_latest,
             )?;
             assert_eq!(events, rev_traversed);
+
+            let event_key = events.first().expect("Shouldn't be empty").key();
+            let by_event_key: Vec<_> = db
+                .get_events_by_event_key(
+                    event_key,
+                    first_seq,
+                    /* ascending = */ true,
+                    3, /* limit */
+                    ledger_info.version(),
+                )?
+                .into_iter()
+                .enumerate()
+                .map(|(i, e)| {
+                    e.verify(
+                        ledger_info,
+                        event_key,
+                        first_seq + i as u64,
+                        e.transaction_version,
+                        e.event_index,
+                    )
+                    .unwrap();
+                    e.event
+                })
+                .collect();
+            assert_eq!(&events[..by_event_key.len()], by_event_key.as_slice());
             Ok(())
         })
         .collect::<Result<Vec<_>>>()?;
@@ -463,4 +488,7 @@ fn test_too_many_requested() {
             0
         )
         .is_err());
+    assert!(db
+        .get_events_by_event_key(&EventKey::random(), 0, true, 1001 /* limit */, 0)
+        .is_err());
 }
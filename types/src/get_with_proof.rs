@@ -7,13 +7,15 @@ use crate::{
     account_config::get_account_resource_or_default,
     account_state_blob::AccountStateWithProof,
     contract_event::EventWithProof,
+    event::EventKey,
     ledger_info::{LedgerInfo, LedgerInfoWithSignatures},
     proof::AccumulatorConsistencyProof,
     proto::types::{
         GetAccountStateRequest, GetAccountStateResponse,
         GetAccountTransactionBySequenceNumberRequest,
         GetAccountTransactionBySequenceNumberResponse, GetEventsByEventAccessPathRequest,
-        GetEventsByEventAccessPathResponse, GetTransactionsRequest, GetTransactionsResponse,
+        GetEventsByEventAccessPathResponse, GetEventsByEventKeyRequest,
+        GetEventsByEventKeyResponse, GetTransactionsRequest, GetTransactionsResponse,
     },
     transaction::{SignedTransactionWithProof, TransactionListWithProof, Version},
     validator_change::ValidatorChangeEventWithProof,
@@ -289,6 +291,23 @@ fn verify_response_item(
             *fetch_events,
             txn_list_with_proof,
         ),
+        // GetEventsByEventKey
+        (
+            RequestItem::GetEventsByEventKey {
+                event_key,
+                start_event_seq_num,
+                ascending,
+                limit,
+            },
+            ResponseItem::GetEventsByEventKey { events_with_proof },
+        ) => verify_get_events_by_event_key_resp(
+            ledger_info,
+            event_key,
+            *start_event_seq_num,
+            *ascending,
+            *limit,
+            events_with_proof,
+        ),
         // Request-response item types mismatch.
         _ => bail!(
             "RequestItem/ResponseItem types mismatch. request: {:?}, response: {:?}",
@@ -404,6 +423,59 @@ fn verify_get_events_by_access_path_resp(
     Ok(())
 }
 
+/// Verifies every returned event against `ledger_info` and checks that the sequence numbers are
+/// the consecutive ones the request asked for. Without the event handle there is no proof of the
+/// latest sequence number, so a server may return fewer events than actually exist.
+fn verify_get_events_by_event_key_resp(
+    ledger_info: &LedgerInfo,
+    req_event_key: &EventKey,
+    req_start_seq_num: u64,
+    req_ascending: bool,
+    req_limit: u64,
+    events_with_proof: &[EventWithProof],
+) -> Result<()> {
+    ensure!(
+        events_with_proof.len() as u64 <= req_limit,
+        "Expecting at most {} events, got {}.",
+        req_limit,
+        events_with_proof.len(),
+    );
+    let first_seq_num = match events_with_proof.first() {
+        Some(e) => e.event.sequence_number(),
+        None => return Ok(()),
+    };
+    if req_ascending || req_start_seq_num != u64::max_value() {
+        ensure!(
+            first_seq_num == req_start_seq_num,
+            "First event has sequence number {}, expecting {}.",
+            first_seq_num,
+            req_start_seq_num,
+        );
+    }
+
+    events_with_proof
+        .iter()
+        .enumerate()
+        .map(|(i, e)| {
+            let seq_num = if req_ascending {
+                first_seq_num.checked_add(i as u64)
+            } else {
+                first_seq_num.checked_sub(i as u64)
+            }
+            .ok_or_else(|| format_err!("Event sequence number overflow."))?;
+            e.verify(
+                ledger_info,
+                req_event_key,
+                seq_num,
+                e.transaction_version,
+                e.event_index,
+            )
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    Ok(())
+}
+
 fn verify_get_txns_resp(
     ledger_info: &LedgerInfo,
     req_start_version: Version,
@@ -456,6 +528,12 @@ pub enum RequestItem {
         limit: u64,
         fetch_events: bool,
     },
+    GetEventsByEventKey {
+        event_key: EventKey,
+        start_event_seq_num: u64,
+        ascending: bool,
+        limit: u64,
+    },
 }
 
 impl TryFrom<crate::proto::types::RequestItem> for RequestItem {
@@ -511,6 +589,19 @@ impl TryFrom<crate::proto::types::RequestItem> for RequestItem {
                     fetch_events,
                 }
             }
+            GetEventsByEventKeyRequest(request) => {
+                let event_key = EventKey::try_from(request.event_key.as_slice())?;
+                let start_event_seq_num = request.start_event_seq_num;
+                let ascending = request.ascending;
+                let limit = request.limit;
+
+                RequestItem::GetEventsByEventKey {
+                    event_key,
+                    start_event_seq_num,
+                    ascending,
+                    limit,
+                }
+            }
         };
 
         Ok(request)
@@ -560,6 +651,17 @@ impl From<RequestItem> for crate::proto::types::RequestItem {
                 limit,
                 fetch_events,
             }),
+            RequestItem::GetEventsByEventKey {
+                event_key,
+                start_event_seq_num,
+                ascending,
+                limit,
+            } => RequestedItems::GetEventsByEventKeyRequest(GetEventsByEventKeyRequest {
+                event_key: event_key.to_vec(),
+                start_event_seq_num,
+                ascending,
+                limit,
+            }),
         };
 
         Self {
@@ -588,6 +690,9 @@ pub enum ResponseItem {
     GetTransactions {
         txn_list_with_proof: TransactionListWithProof,
     },
+    GetEventsByEventKey {
+        events_with_proof: Vec<EventWithProof>,
+    },
 }
 
 impl ResponseItem {
@@ -638,6 +743,13 @@ impl ResponseItem {
             _ => bail!("Not ResponseItem::GetTransactions."),
         }
     }
+
+    pub fn into_get_events_by_event_key_response(self) -> Result<Vec<EventWithProof>> {
+        match self {
+            ResponseItem::GetEventsByEventKey { events_with_proof } => Ok(events_with_proof),
+            _ => bail!("Not ResponseItem::GetEventsByEventKey."),
+        }
+    }
 }
 
 impl TryFrom<crate::proto::types::ResponseItem> for ResponseItem {
@@ -701,6 +813,15 @@ impl TryFrom<crate::proto::types::ResponseItem> for ResponseItem {
                     txn_list_with_proof,
                 }
             }
+            GetEventsByEventKeyResponse(response) => {
+                let events_with_proof = response
+                    .events_with_proof
+                    .into_iter()
+                    .map(TryFrom::try_from)
+                    .collect::<Result<Vec<_>>>()?;
+
+                ResponseItem::GetEventsByEventKey { events_with_proof }
+            }
         };
 
         Ok(response)
@@ -741,6 +862,11 @@ impl From<ResponseItem> for crate::proto::types::ResponseItem {
             } => ResponseItems::GetTransactionsResponse(GetTransactionsResponse {
                 txn_list_with_proof: Some(txn_list_with_proof.into()),
             }),
+            ResponseItem::GetEventsByEventKey { events_with_proof } => {
+                ResponseItems::GetEventsByEventKeyResponse(GetEventsByEventKeyResponse {
+                    events_with_proof: events_with_proof.into_iter().map(Into::into).collect(),
+                })
+            }
         };
 
         Self {
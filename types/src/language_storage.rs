@@ -11,14 +11,37 @@ use failure::Result;
 #[cfg(any(test, feature = "testing"))]
 use proptest_derive::Arbitrary;
 use serde::{Deserialize, Serialize};
-use std::convert::{TryFrom, TryInto};
+use std::{
+    convert::{TryFrom, TryInto},
+    fmt,
+};
+
+/// The type of a Move value, with the type parameters of structs instantiated.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Hash, Eq, Clone, PartialOrd, Ord)]
+pub enum TypeTag {
+    Bool,
+    U8,
+    U64,
+    U128,
+    ByteArray,
+    Address,
+    String,
+    Struct(StructTag),
+}
 
 #[derive(Serialize, Deserialize, Debug, PartialEq, Hash, Eq, Clone, PartialOrd, Ord)]
 pub struct StructTag {
     pub address: AccountAddress,
     pub module: Identifier,
     pub name: Identifier,
-    pub type_params: Vec<StructTag>,
+    pub type_params: Vec<TypeTag>,
+}
+
+impl StructTag {
+    /// Returns the id of the module declaring the struct.
+    pub fn module_id(&self) -> ModuleId {
+        ModuleId::new(self.address, self.module.clone())
+    }
 }
 
 /// Represents the intitial key into global storage where we first index by the address, and then
@@ -114,3 +137,32 @@ impl CryptoHash for StructTag {
         state.finish()
     }
 }
+
+impl fmt::Display for TypeTag {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            TypeTag::Bool => write!(f, "bool"),
+            TypeTag::U8 => write!(f, "u8"),
+            TypeTag::U64 => write!(f, "u64"),
+            TypeTag::U128 => write!(f, "u128"),
+            TypeTag::ByteArray => write!(f, "bytearray"),
+            TypeTag::Address => write!(f, "address"),
+            TypeTag::String => write!(f, "string"),
+            TypeTag::Struct(struct_tag) => write!(f, "{}", struct_tag),
+        }
+    }
+}
+
+impl fmt::Display for StructTag {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}::{}::{}", self.address, self.module, self.name)?;
+        if let Some((first, rest)) = self.type_params.split_first() {
+            write!(f, "<{}", first)?;
+            for type_param in rest {
+                write!(f, ", {}", type_param)?;
+            }
+            write!(f, ">")?;
+        }
+        Ok(())
+    }
+}
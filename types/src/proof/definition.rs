@@ -253,6 +253,21 @@ impl SparseMerkleProof {
         expected_root_hash: HashValue,
         element_key: HashValue,
         element_blob: Option<&AccountStateBlob>,
+    ) -> Result<()> {
+        self.verify_by_hash(
+            expected_root_hash,
+            element_key,
+            element_blob.map(CryptoHash::hash),
+        )
+    }
+
+    /// Same as `verify`, but takes the hash of the element's value instead of the value itself,
+    /// so that proofs of trees storing values other than account blobs can be verified too.
+    pub fn verify_by_hash(
+        &self,
+        expected_root_hash: HashValue,
+        element_key: HashValue,
+        element_hash: Option<HashValue>,
     ) -> Result<()> {
         ensure!(
             self.siblings.len() <= HashValue::LENGTH_IN_BITS,
@@ -261,8 +276,8 @@ impl SparseMerkleProof {
             self.siblings.len(),
         );
 
-        match (element_blob, self.leaf) {
-            (Some(blob), Some((proof_key, proof_value_hash))) => {
+        match (element_hash, self.leaf) {
+            (Some(hash), Some((proof_key, proof_value_hash))) => {
                 // This is an inclusion proof, so the key and value hash provided in the proof
                 // should match element_key and element_value_hash. `siblings` should prove the
                 // route from the leaf node to the root.
@@ -272,7 +287,6 @@ impl SparseMerkleProof {
                     proof_key,
                     element_key
                 );
-                let hash = blob.hash();
                 ensure!(
                     hash == proof_value_hash,
                     "Value hashes do not match. Value hash in proof: {:x}. \
@@ -281,7 +295,7 @@ impl SparseMerkleProof {
                     hash,
                 );
             }
-            (Some(_blob), None) => bail!("Expected inclusion proof. Found non-inclusion proof."),
+            (Some(_hash), None) => bail!("Expected inclusion proof. Found non-inclusion proof."),
             (None, Some((proof_key, _))) => {
                 // This is a non-inclusion proof. The proof intends to show that if a leaf node
                 // representing `element_key` is inserted, it will break a currently existing leaf
@@ -12,6 +12,8 @@ use crate::{
     contract_event::ContractEvent,
     event::{EventHandle, EventKey},
     get_with_proof::{ResponseItem, UpdateToLatestLedgerResponse},
+    identifier::Identifier,
+    language_storage::{StructTag, TypeTag},
     ledger_info::{LedgerInfo, LedgerInfoWithSignatures},
     proof::{AccumulatorConsistencyProof, TransactionAccumulatorProof},
     transaction::{
@@ -513,6 +515,18 @@ impl Arbitrary for TransactionArgument {
             any::<AccountAddress>().prop_map(TransactionArgument::Address),
             any::<ByteArray>().prop_map(TransactionArgument::ByteArray),
             ".*".prop_map(TransactionArgument::String),
+            any::<bool>().prop_map(TransactionArgument::Bool),
+            vec(any::<u64>(), 0..10).prop_map(|values| TransactionArgument::Vector(
+                values.into_iter().map(TransactionArgument::U64).collect()
+            )),
+            vec(any::<AccountAddress>(), 0..10).prop_map(|addresses| {
+                TransactionArgument::Vector(
+                    addresses
+                        .into_iter()
+                        .map(TransactionArgument::Address)
+                        .collect(),
+                )
+            }),
         ]
         .boxed()
     }
@@ -580,6 +594,7 @@ impl Arbitrary for UpdateToLatestLedgerResponse<Ed25519Signature> {
 
 #[derive(Arbitrary, Debug)]
 pub struct ContractEventGen {
+    type_tag: TypeTag,
     payload: Vec<u8>,
     use_sent_key: bool,
 }
@@ -600,7 +615,7 @@ impl ContractEventGen {
         *event_handle.count_mut() += 1;
         let event_key = event_handle.key();
 
-        ContractEvent::new(*event_key, sequence_number, self.payload)
+        ContractEvent::new(*event_key, sequence_number, self.type_tag, self.payload)
     }
 }
 
@@ -653,10 +668,55 @@ impl ContractEvent {
     pub fn strategy_impl(
         event_key_strategy: impl Strategy<Value = EventKey>,
     ) -> impl Strategy<Value = Self> {
-        (event_key_strategy, any::<u64>(), vec(any::<u8>(), 1..10)).prop_map(
-            |(event_key, seq_num, event_data)| ContractEvent::new(event_key, seq_num, event_data),
+        (
+            event_key_strategy,
+            any::<u64>(),
+            any::<TypeTag>(),
+            vec(any::<u8>(), 1..10),
+        )
+            .prop_map(|(event_key, seq_num, type_tag, event_data)| {
+                ContractEvent::new(event_key, seq_num, type_tag, event_data)
+            })
+    }
+}
+
+impl Arbitrary for TypeTag {
+    type Parameters = ();
+    fn arbitrary_with(_args: Self::Parameters) -> Self::Strategy {
+        let leaf = prop_oneof![
+            Just(TypeTag::Bool),
+            Just(TypeTag::U8),
+            Just(TypeTag::U64),
+            Just(TypeTag::U128),
+            Just(TypeTag::ByteArray),
+            Just(TypeTag::Address),
+            Just(TypeTag::String),
+        ];
+        leaf.prop_recursive(
+            2, // levels deep
+            8, // max size
+            2, // type parameters per struct
+            |inner| {
+                (
+                    any::<AccountAddress>(),
+                    any::<Identifier>(),
+                    any::<Identifier>(),
+                    vec(inner, 0..=2),
+                )
+                    .prop_map(|(address, module, name, type_params)| {
+                        TypeTag::Struct(StructTag {
+                            address,
+                            module,
+                            name,
+                            type_params,
+                        })
+                    })
+            },
         )
+        .boxed()
     }
+
+    type Strategy = BoxedStrategy<Self>;
 }
 
 impl EventHandle {
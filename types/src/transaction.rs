@@ -47,7 +47,9 @@ pub use program::Program;
 pub use script::{Script, SCRIPT_HASH_LENGTH};
 
 use std::ops::Deref;
-pub use transaction_argument::{parse_as_transaction_argument, TransactionArgument};
+pub use transaction_argument::{
+    parse_as_transaction_argument, parse_as_vector_with, TransactionArgument,
+};
 
 pub type Version = u64; // Height - also used for MVCC in StateDB
 
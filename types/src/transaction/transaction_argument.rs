@@ -4,8 +4,8 @@
 //use crate::errors::*;
 use crate::{account_address::AccountAddress, byte_array::ByteArray};
 use failure::prelude::*;
-use serde::{Deserialize, Serialize};
-use std::{convert::TryFrom, fmt};
+use serde::{de, Deserialize, Serialize};
+use std::{convert::TryFrom, fmt, mem};
 
 #[derive(Clone, Hash, Eq, PartialEq, Serialize, Deserialize)]
 pub enum TransactionArgument {
@@ -13,6 +13,60 @@ pub enum TransactionArgument {
     Address(AccountAddress),
     String(String),
     ByteArray(ByteArray),
+    Bool(bool),
+    /// A vector of arguments of the same type, which can't be a vector.
+    Vector(#[serde(deserialize_with = "deserialize_vector_elements")] Vec<TransactionArgument>),
+}
+
+/// An element of a `TransactionArgument::Vector`. The variants mirror those of
+/// `TransactionArgument` except `Vector`, so that deserializing a vector doesn't recurse: a nested
+/// vector is an unknown variant here.
+#[derive(Deserialize)]
+enum VectorElement {
+    U64(u64),
+    Address(AccountAddress),
+    String(String),
+    ByteArray(ByteArray),
+    Bool(bool),
+}
+
+impl From<VectorElement> for TransactionArgument {
+    fn from(element: VectorElement) -> Self {
+        match element {
+            VectorElement::U64(value) => TransactionArgument::U64(value),
+            VectorElement::Address(address) => TransactionArgument::Address(address),
+            VectorElement::String(string) => TransactionArgument::String(string),
+            VectorElement::ByteArray(byte_array) => TransactionArgument::ByteArray(byte_array),
+            VectorElement::Bool(boolean) => TransactionArgument::Bool(boolean),
+        }
+    }
+}
+
+fn deserialize_vector_elements<'de, D>(
+    deserializer: D,
+) -> std::result::Result<Vec<TransactionArgument>, D::Error>
+where
+    D: de::Deserializer<'de>,
+{
+    use serde::de::Error;
+
+    let elements: Vec<TransactionArgument> = Vec::<VectorElement>::deserialize(deserializer)?
+        .into_iter()
+        .map(TransactionArgument::from)
+        .collect();
+    if !have_same_type(&elements) {
+        return Err(D::Error::custom("vector elements must have the same type"));
+    }
+    Ok(elements)
+}
+
+fn have_same_type(elements: &[TransactionArgument]) -> bool {
+    match elements.first() {
+        Some(first) => elements
+            .iter()
+            .all(|element| mem::discriminant(element) == mem::discriminant(first)),
+        None => true,
+    }
 }
 
 impl fmt::Debug for TransactionArgument {
@@ -24,6 +78,8 @@ impl fmt::Debug for TransactionArgument {
             TransactionArgument::ByteArray(byte_array) => {
                 write!(f, "{{ByteArray: 0x{}}}", byte_array)
             }
+            TransactionArgument::Bool(boolean) => write!(f, "{{BOOL: {}}}", boolean),
+            TransactionArgument::Vector(elements) => write!(f, "{{VECTOR: {:?}}}", elements),
         }
     }
 }
@@ -86,6 +142,47 @@ pub fn parse_as_u64(s: &str) -> Result<TransactionArgument> {
     Ok(TransactionArgument::U64(s.parse::<u64>()?))
 }
 
+/// Parses the given string as bool.
+pub fn parse_as_bool(s: &str) -> Result<TransactionArgument> {
+    Ok(TransactionArgument::Bool(s.parse::<bool>()?))
+}
+
+/// Parses the given string as a vector, such as `[1,2,3]`, parsing each element with
+/// `parse_element`. The elements must all have the same type and can't be vectors.
+pub fn parse_as_vector_with(
+    s: &str,
+    parse_element: impl Fn(&str) -> Result<TransactionArgument>,
+) -> Result<TransactionArgument> {
+    if !(s.starts_with('[') && s.ends_with(']') && s.len() >= 2) {
+        return Err(ErrorKind::ParseError(format!("\"{}\" is not a vector", s)).into());
+    }
+    let s = &s[1..s.len() - 1];
+    if s.is_empty() {
+        return Ok(TransactionArgument::Vector(vec![]));
+    }
+    let elements = s
+        .split(',')
+        .map(|element| parse_element(element.trim()))
+        .collect::<Result<Vec<_>>>()?;
+    if let Some(first) = elements.first() {
+        if let TransactionArgument::Vector(_) = first {
+            return Err(ErrorKind::ParseError("vectors cannot be nested".to_string()).into());
+        }
+        if !have_same_type(&elements) {
+            return Err(ErrorKind::ParseError(
+                "vector elements must have the same type".to_string(),
+            )
+            .into());
+        }
+    }
+    Ok(TransactionArgument::Vector(elements))
+}
+
+/// Parses the given string as a vector of transaction arguments.
+pub fn parse_as_vector(s: &str) -> Result<TransactionArgument> {
+    parse_as_vector_with(s, parse_as_transaction_argument)
+}
+
 macro_rules! return_if_ok {
     ($e: expr) => {{
         if let Ok(res) = $e {
@@ -99,6 +196,8 @@ pub fn parse_as_transaction_argument(s: &str) -> Result<TransactionArgument> {
     return_if_ok!(parse_as_address(s));
     return_if_ok!(parse_as_u64(s));
     return_if_ok!(parse_as_byte_array(s));
+    return_if_ok!(parse_as_bool(s));
+    return_if_ok!(parse_as_vector(s));
     Err(ErrorKind::ParseError(format!("cannot parse \"{}\" as transaction argument", s)).into())
 }
 
@@ -151,9 +250,67 @@ mod test_transaction_argument {
         }
     }
 
+    #[test]
+    fn parse_bool() {
+        assert_eq!(
+            parse_as_bool("true").unwrap(),
+            TransactionArgument::Bool(true)
+        );
+        assert_eq!(
+            parse_as_bool("false").unwrap(),
+            TransactionArgument::Bool(false)
+        );
+        for s in &["", "1", "yes"] {
+            parse_as_bool(s).unwrap_err();
+        }
+    }
+
+    #[test]
+    fn parse_vector() {
+        assert_eq!(
+            parse_as_vector("[1,2, 3]").unwrap(),
+            TransactionArgument::Vector(vec![
+                TransactionArgument::U64(1),
+                TransactionArgument::U64(2),
+                TransactionArgument::U64(3),
+            ])
+        );
+        assert_eq!(
+            parse_as_vector("[]").unwrap(),
+            TransactionArgument::Vector(vec![])
+        );
+        for s in &["[0x1,0x2]", "[true,false]", "[b\"aa\"]"] {
+            parse_as_vector(s).unwrap();
+        }
+
+        for s in &["", "[", "1,2", "[1,]", "[1,true]", "[[1],[2]]"] {
+            parse_as_vector(s).unwrap_err();
+        }
+    }
+
+    #[test]
+    fn deserialize_vector() {
+        let vector = TransactionArgument::Vector(vec![
+            TransactionArgument::Bool(true),
+            TransactionArgument::Bool(false),
+        ]);
+        assert_eq!(
+            lcs::from_bytes::<TransactionArgument>(&lcs::to_bytes(&vector).unwrap()).unwrap(),
+            vector
+        );
+
+        let nested = TransactionArgument::Vector(vec![TransactionArgument::Vector(vec![])]);
+        lcs::from_bytes::<TransactionArgument>(&lcs::to_bytes(&nested).unwrap()).unwrap_err();
+        let mixed = TransactionArgument::Vector(vec![
+            TransactionArgument::U64(1),
+            TransactionArgument::Bool(true),
+        ]);
+        lcs::from_bytes::<TransactionArgument>(&lcs::to_bytes(&mixed).unwrap()).unwrap_err();
+    }
+
     #[test]
     fn parse_args() {
-        for s in &["123", "0xf", "b\"aaa\""] {
+        for s in &["123", "0xf", "b\"aaa\"", "true", "[1,2]"] {
             parse_as_transaction_argument(s).unwrap();
         }
 
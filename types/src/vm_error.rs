@@ -3,6 +3,7 @@
 
 #![allow(clippy::unit_arg)]
 
+use crate::{identifier::Identifier, language_storage::ModuleId};
 use failure::prelude::*;
 use num_enum::{IntoPrimitive, TryFromPrimitive};
 #[cfg(any(test, feature = "testing"))]
@@ -10,7 +11,10 @@ use proptest::prelude::*;
 #[cfg(any(test, feature = "testing"))]
 use proptest_derive::Arbitrary;
 use serde::{de, ser};
-use std::{convert::TryFrom, fmt};
+use std::{
+    convert::{TryFrom, TryInto},
+    fmt,
+};
 
 /// The minimum status code for validation statuses
 pub static VALIDATION_STATUS_MIN_CODE: u64 = 0;
@@ -58,6 +62,33 @@ pub struct VMStatus {
     /// The optional message. Useful for verification errors, and for returning information in
     /// validation.
     pub message: Option<String>,
+
+    /// The module and function that aborted, for an ABORTED major status. Together with the abort
+    /// code in the sub status, this can be resolved to the error the module declared for the code.
+    pub abort_location: Option<AbortLocation>,
+}
+
+/// The function that executed an `Abort` instruction.
+#[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Debug, Hash)]
+#[cfg_attr(any(test, feature = "testing"), derive(Arbitrary))]
+#[cfg_attr(any(test, feature = "testing"), proptest(no_params))]
+pub struct AbortLocation {
+    /// The module defining the function.
+    pub module: ModuleId,
+    /// The name of the function.
+    pub function: Identifier,
+}
+
+impl fmt::Display for AbortLocation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}.{}::{}",
+            self.module.address(),
+            self.module.name(),
+            self.function
+        )
+    }
 }
 
 /// A status type is one of 5 different variants, along with a fallback variant in the case that we
@@ -95,6 +126,10 @@ impl fmt::Display for VMStatus {
             status = format!("{} with sub status {}", status, sub_status);
         }
 
+        if let Some(ref abort_location) = self.abort_location {
+            status = format!("{} in {}", status, abort_location);
+        }
+
         if let Some(ref msg) = self.message {
             status = format!("{} and message {}", status, msg);
         }
@@ -112,6 +147,7 @@ impl VMStatus {
             major_status,
             sub_status: None,
             message: None,
+            abort_location: None,
         }
     }
 
@@ -127,6 +163,12 @@ impl VMStatus {
         self
     }
 
+    /// Adds the location of the abort to the VM status.
+    pub fn with_abort_location(mut self, module: ModuleId, function: Identifier) -> Self {
+        self.abort_location = Some(AbortLocation { module, function });
+        self
+    }
+
     /// Mutates the VMStatus sub status field to be the new `sub_status` passed in.
     pub fn set_sub_status(&mut self, sub_status: u64) {
         self.sub_status = Some(sub_status);
@@ -218,6 +260,10 @@ impl TryFrom<crate::proto::types::VmStatus> for VMStatus {
             status.set_message(proto.message);
         }
 
+        if let Some(abort_location) = proto.abort_location {
+            status.abort_location = Some(AbortLocation::try_from(abort_location)?);
+        }
+
         Ok(status)
     }
 }
@@ -244,10 +290,35 @@ impl From<VMStatus> for crate::proto::types::VmStatus {
             proto_status.message = string;
         }
 
+        proto_status.abort_location = status.abort_location.map(Into::into);
+
         proto_status
     }
 }
 
+impl TryFrom<crate::proto::types::AbortLocation> for AbortLocation {
+    type Error = Error;
+
+    fn try_from(proto: crate::proto::types::AbortLocation) -> Result<Self> {
+        Ok(Self {
+            module: proto
+                .module
+                .ok_or_else(|| format_err!("Missing module"))?
+                .try_into()?,
+            function: Identifier::new(proto.function)?,
+        })
+    }
+}
+
+impl From<AbortLocation> for crate::proto::types::AbortLocation {
+    fn from(location: AbortLocation) -> Self {
+        Self {
+            module: Some(location.module.into()),
+            function: location.function.into_string(),
+        }
+    }
+}
+
 #[allow(non_camel_case_types)]
 #[derive(
     Clone, Copy, Debug, Eq, Hash, PartialEq, PartialOrd, Ord, IntoPrimitive, TryFromPrimitive,
@@ -447,6 +518,12 @@ pub enum StatusCode {
     EXECUTION_STACK_OVERFLOW = 4020,
     CALL_STACK_OVERFLOW = 4021,
     NATIVE_FUNCTION_ERROR = 4022,
+    // The sender is trying to upgrade a published module to a version that removes or changes
+    // a public function signature, a struct layout or a struct kind.
+    INCOMPATIBLE_MODULE_UPGRADE = 4023,
+    // The sender is trying to publish a module that depends, directly or not, on a module that
+    // depends on it.
+    CYCLIC_MODULE_DEPENDENCY = 4024,
 
     // A reserved status to represent an unknown vm status.
     UNKNOWN_STATUS = std::u64::MAX,
@@ -503,4 +580,5 @@ pub mod sub_status {
 
     // Native Function Error sub-codes
     pub const NFE_VECTOR_ERROR_BASE: u64 = 0;
+    pub const NFE_MAP_ERROR_BASE: u64 = 100;
 }